@@ -2,17 +2,25 @@ use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use percent_encoding::{NON_ALPHANUMERIC, utf8_percent_encode};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
 
+use super::websocket::WebSocket;
 use crate::models::{
-    Episode, HomeSection, HomeSectionType, Library, LibraryType, MediaItem, Movie, Resolution,
-    Season, Show, StreamInfo, User,
+    Episode, ExternalSubtitle, HomeSection, HomeSectionType, Library, LibraryType, MediaItem,
+    Movie, MusicAlbum, MusicTrack, Resolution, Season, Show, StreamInfo, TranscodeStatus,
+    Trickplay, TrickplayFrame, User,
 };
+use crate::player::DeviceCapabilities;
 
 const JELLYFIN_CLIENT_NAME: &str = "Reel";
 const JELLYFIN_VERSION: &str = "0.1.0";
+/// Audio-only containers the player demuxes, for music direct play
+const AUDIO_CONTAINERS: &str = "mp3,flac,ogg,oga,opus,m4a,m4b,aac,wav,webm,mka";
+/// Audio profiles of DTS-HD streams, which only decode as far as the DTS core
+const DTS_HD_PROFILES: &[&str] = &["DTS-HD MA", "DTS-HD HRA"];
 
 #[derive(Clone)]
 pub struct JellyfinApi {
@@ -48,8 +56,10 @@ impl JellyfinApi {
         }
     }
 
+    /// Get the persisted device ID, creating one on first use. Jellyfin ties
+    /// active encodings to the device, so it must be stable across API instances.
     fn get_or_create_device_id() -> String {
-        Uuid::new_v4().to_string()
+        crate::backends::persisted_id("jellyfin_device_id")
     }
 
     fn get_auth_header(&self) -> String {
@@ -524,6 +534,117 @@ impl JellyfinApi {
         Ok(movies)
     }
 
+    /// Get all albums of a music library, wherever they sit in its folders
+    pub async fn get_music_albums(&self, library_id: &str) -> Result<Vec<MusicAlbum>> {
+        let url = format!(
+            "{}/Users/{}/Items?ParentId={}&IncludeItemTypes=MusicAlbum&Recursive=true&Fields=Genres,ChildCount&SortBy=SortName",
+            self.base_url, self.user_id, library_id
+        );
+        let albums: Vec<MusicAlbum> = self
+            .get_music_items(&url, "albums")
+            .await?
+            .into_iter()
+            .map(|item| self.music_album(item))
+            .collect();
+
+        info!("Found {} albums in library {}", albums.len(), library_id);
+        Ok(albums)
+    }
+
+    /// Get every track of a music library in one request
+    pub async fn get_library_tracks(&self, library_id: &str) -> Result<Vec<MusicTrack>> {
+        let url = format!(
+            "{}/Users/{}/Items?ParentId={}&IncludeItemTypes=Audio&Recursive=true&SortBy=Album,ParentIndexNumber,IndexNumber",
+            self.base_url, self.user_id, library_id
+        );
+        let tracks: Vec<MusicTrack> = self
+            .get_music_items(&url, "tracks")
+            .await?
+            .into_iter()
+            .map(|item| self.music_track(item))
+            .collect();
+
+        info!("Found {} tracks in library {}", tracks.len(), library_id);
+        Ok(tracks)
+    }
+
+    /// Get the tracks of an album
+    pub async fn get_album_tracks(&self, album_id: &str) -> Result<Vec<MusicTrack>> {
+        let url = format!(
+            "{}/Users/{}/Items?ParentId={}&IncludeItemTypes=Audio&Recursive=true&SortBy=ParentIndexNumber,IndexNumber",
+            self.base_url, self.user_id, album_id
+        );
+        Ok(self
+            .get_music_items(&url, "album tracks")
+            .await?
+            .into_iter()
+            .map(|item| self.music_track(item))
+            .collect())
+    }
+
+    async fn get_music_items(&self, url: &str, what: &str) -> Result<Vec<JellyfinItem>> {
+        let response = self
+            .client
+            .get(url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to get {}: {}", what, response.status()));
+        }
+
+        let items_response: ItemsResponse = response.json().await?;
+        Ok(items_response.items)
+    }
+
+    fn music_album(&self, item: JellyfinItem) -> MusicAlbum {
+        let cover_url =
+            self.build_image_url(&item.id, "Primary", item.image_tags.primary.as_deref());
+        MusicAlbum {
+            id: item.id,
+            title: item.name,
+            artist: item
+                .album_artist
+                .or_else(|| item.artists.first().cloned())
+                .unwrap_or_else(|| "Unknown Artist".to_string()),
+            year: item.production_year,
+            track_count: item.child_count.unwrap_or(0).max(0) as u32,
+            duration: Duration::from_secs(item.run_time_ticks.unwrap_or(0) / 10_000_000),
+            cover_url,
+            genres: item.genres.unwrap_or_default(),
+        }
+    }
+
+    fn music_track(&self, item: JellyfinItem) -> MusicTrack {
+        // Tracks rarely have art of their own, so fall back to the album's
+        let cover_url = self
+            .build_image_url(&item.id, "Primary", item.image_tags.primary.as_deref())
+            .or_else(|| {
+                item.album_id.as_deref().and_then(|album_id| {
+                    self.build_image_url(
+                        album_id,
+                        "Primary",
+                        item.album_primary_image_tag.as_deref(),
+                    )
+                })
+            });
+        MusicTrack {
+            id: item.id,
+            title: item.name,
+            artist: item
+                .artists
+                .into_iter()
+                .next()
+                .or(item.album_artist)
+                .unwrap_or_else(|| "Unknown Artist".to_string()),
+            album: item.album.unwrap_or_else(|| "Unknown Album".to_string()),
+            track_number: item.index_number.and_then(|n| u32::try_from(n).ok()),
+            duration: Duration::from_secs(item.run_time_ticks.unwrap_or(0) / 10_000_000),
+            cover_url,
+        }
+    }
+
     pub async fn get_shows(&self, library_id: &str) -> Result<Vec<Show>> {
         let url = format!(
             "{}/Users/{}/Items?ParentId={}&IncludeItemTypes=Series&Fields=Overview,Genres,DateCreated,ChildCount,People&SortBy=SortName",
@@ -691,26 +812,10 @@ impl JellyfinApi {
                     let mut intro = None;
                     let mut credits = None;
 
-                    for segment in segments {
-                        match segment.segment_type {
-                            MediaSegmentType::Intro => {
-                                intro = Some(crate::models::ChapterMarker {
-                                    start_time: Duration::from_secs(
-                                        segment.start_ticks / 10_000_000,
-                                    ),
-                                    end_time: Duration::from_secs(segment.end_ticks / 10_000_000),
-                                    marker_type: crate::models::ChapterType::Intro,
-                                });
-                            }
-                            MediaSegmentType::Credits | MediaSegmentType::Outro => {
-                                credits = Some(crate::models::ChapterMarker {
-                                    start_time: Duration::from_secs(
-                                        segment.start_ticks / 10_000_000,
-                                    ),
-                                    end_time: Duration::from_secs(segment.end_ticks / 10_000_000),
-                                    marker_type: crate::models::ChapterType::Credits,
-                                });
-                            }
+                    for marker in segments.iter().filter_map(|s| s.to_marker()) {
+                        match marker.marker_type {
+                            crate::models::ChapterType::Intro => intro = Some(marker),
+                            crate::models::ChapterType::Credits => credits = Some(marker),
                             _ => {}
                         }
                     }
@@ -767,7 +872,11 @@ impl JellyfinApi {
         Ok(episodes)
     }
 
-    pub async fn get_stream_url(&self, media_id: &str) -> Result<StreamInfo> {
+    pub async fn get_stream_url(
+        &self,
+        media_id: &str,
+        capabilities: &DeviceCapabilities,
+    ) -> Result<StreamInfo> {
         let playback_info_url = format!(
             "{}/Items/{}/PlaybackInfo?UserId={}&StartTimeTicks=0&IsPlayback=true&AutoOpenLiveStream=true&MediaSourceId={}",
             self.base_url, media_id, self.user_id, media_id
@@ -779,28 +888,8 @@ impl JellyfinApi {
             .header("X-Emby-Authorization", self.get_auth_header())
             .header("Content-Type", "application/json")
             .json(&serde_json::json!({
-                "DeviceProfile": {
-                    "MaxStreamingBitrate": 120000000,
-                    "DirectPlayProfiles": [
-                        {
-                            "Container": "mp4,m4v,mkv,webm",
-                            "Type": "Video",
-                            "VideoCodec": "h264,hevc,vp8,vp9,av1",
-                            "AudioCodec": "aac,mp3,opus,flac,vorbis"
-                        }
-                    ],
-                    "TranscodingProfiles": [
-                        {
-                            "Container": "mp4",
-                            "Type": "Video",
-                            "AudioCodec": "aac",
-                            "VideoCodec": "h264",
-                            "Context": "Streaming",
-                            "Protocol": "hls",
-                            "MaxAudioChannels": "6"
-                        }
-                    ]
-                }
+                "DeviceProfile": build_device_profile(capabilities),
+                "MaxStreamingBitrate": capabilities.max_streaming_bitrate,
             }))
             .send()
             .await?;
@@ -819,6 +908,14 @@ impl JellyfinApi {
         }
 
         let media_source = &playback_info.media_sources[0];
+        let transcoding =
+            !media_source.supports_direct_play && !media_source.supports_direct_stream;
+        // Only transcoded streams hold an encode on the server that needs cleaning up
+        let transcode_session_id = if transcoding {
+            playback_info.play_session_id.clone()
+        } else {
+            None
+        };
 
         let stream_url = if media_source.supports_direct_play {
             format!(
@@ -830,6 +927,14 @@ impl JellyfinApi {
                 "{}/Videos/{}/stream?mediaSourceId={}&api_key={}",
                 self.base_url, media_id, media_source.id, self.api_key
             )
+        } else if let Some(transcoding_url) = &media_source.transcoding_url {
+            // The server decided our device profile can't handle this source,
+            // so use the transcode it prepared for us
+            info!(
+                "Jellyfin requires transcoding for {} ({:?})",
+                media_id, media_source.transcoding_sub_protocol
+            );
+            format!("{}{}", self.base_url, transcoding_url)
         } else {
             format!(
                 "{}/Videos/{}/main.m3u8?mediaSourceId={}&api_key={}",
@@ -862,9 +967,228 @@ impl JellyfinApi {
                 height: video_stream.height.unwrap_or(0) as u32,
             },
             quality_options: vec![],
+            transcode_session_id,
+            external_subtitles: self.external_subtitles(media_id, media_source),
         })
     }
 
+    /// Sidecar subtitle streams of a media source with URLs the player can load directly
+    fn external_subtitles(
+        &self,
+        media_id: &str,
+        media_source: &MediaSource,
+    ) -> Vec<ExternalSubtitle> {
+        media_source
+            .media_streams
+            .iter()
+            .filter(|s| s.stream_type == "Subtitle" && s.is_external)
+            .filter_map(|stream| {
+                let codec = stream.codec.clone().unwrap_or_else(|| "srt".to_string());
+                let url = match &stream.delivery_url {
+                    Some(delivery_url) if delivery_url.contains("api_key=") => {
+                        format!("{}{}", self.base_url, delivery_url)
+                    }
+                    Some(delivery_url) => {
+                        let separator = if delivery_url.contains('?') { '&' } else { '?' };
+                        format!(
+                            "{}{}{}api_key={}",
+                            self.base_url, delivery_url, separator, self.api_key
+                        )
+                    }
+                    None => format!(
+                        "{}/Videos/{}/{}/Subtitles/{}/0/Stream.{}?api_key={}",
+                        self.base_url,
+                        media_id,
+                        media_source.id,
+                        stream.index?,
+                        codec,
+                        self.api_key
+                    ),
+                };
+
+                Some(ExternalSubtitle {
+                    url,
+                    title: stream
+                        .display_title
+                        .clone()
+                        .unwrap_or_else(|| "External".to_string()),
+                    language: stream.language.clone(),
+                    codec,
+                    forced: stream.is_forced,
+                    default: stream.is_default,
+                })
+            })
+            .collect()
+    }
+
+    /// Fetch trickplay tile sheets for a media item and split them into preview frames
+    pub async fn get_trickplay(&self, media_id: &str) -> Result<Option<Trickplay>> {
+        let url = format!(
+            "{}/Users/{}/Items/{}?Fields=Trickplay",
+            self.base_url, self.user_id, media_id
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to get item: {}", response.status()));
+        }
+
+        let item: TrickplayItem = response.json().await?;
+
+        // Use the smallest resolution the server generated; previews are shown small anyway
+        let Some((media_source_id, info)) = item
+            .trickplay
+            .and_then(|sources| sources.into_iter().next())
+            .and_then(|(source_id, resolutions)| {
+                resolutions
+                    .into_values()
+                    .min_by_key(|info| info.width)
+                    .map(|info| (source_id, info))
+            })
+        else {
+            debug!("No trickplay data available for item {}", media_id);
+            return Ok(None);
+        };
+
+        let tiles_per_sheet = (info.tile_width * info.tile_height) as usize;
+        if tiles_per_sheet == 0 {
+            return Ok(None);
+        }
+        let sheet_count = (info.thumbnail_count as usize).div_ceil(tiles_per_sheet);
+
+        let mut frames = Vec::with_capacity(info.thumbnail_count as usize);
+        for sheet_index in 0..sheet_count {
+            let sheet_url = format!(
+                "{}/Videos/{}/Trickplay/{}/{}.jpg",
+                self.base_url, media_id, info.width, sheet_index
+            );
+
+            let response = self
+                .client
+                .get(&sheet_url)
+                .header("X-Emby-Authorization", self.get_auth_header())
+                .query(&[("MediaSourceId", media_source_id.as_str())])
+                .send()
+                .await?;
+
+            if !response.status().is_success() {
+                return Err(anyhow!(
+                    "Failed to fetch trickplay tile sheet: {}",
+                    response.status()
+                ));
+            }
+
+            let sheet = response.bytes().await?;
+            let first_index = sheet_index * tiles_per_sheet;
+            let info = info.clone();
+            // Decoding and re-encoding tiles is CPU bound, keep it off the async runtime
+            let sheet_frames = tokio::task::spawn_blocking(move || {
+                split_trickplay_sheet(&sheet, &info, first_index)
+            })
+            .await??;
+            frames.extend(sheet_frames);
+        }
+
+        Ok(Some(Trickplay { frames }))
+    }
+
+    /// Stop the server-side encode for a play session
+    pub async fn stop_transcode_session(&self, play_session_id: &str) -> Result<()> {
+        let url = format!("{}/Videos/ActiveEncodings", self.base_url);
+
+        debug!("Stopping Jellyfin transcode session: {}", play_session_id);
+
+        let response = self
+            .client
+            .delete(&url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .query(&[
+                ("DeviceId", self.device_id.as_str()),
+                ("PlaySessionId", play_session_id),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to stop transcode session: {}",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Keep a transcoding play session alive while playback is paused
+    pub async fn ping_transcode_session(&self, play_session_id: &str) -> Result<()> {
+        let url = format!("{}/Sessions/Playing/Ping", self.base_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .query(&[("playSessionId", play_session_id)])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to ping transcode session: {}",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// What the server's encoder is doing for this device's playing session
+    pub async fn get_transcode_status(
+        &self,
+        play_session_id: &str,
+    ) -> Result<Option<TranscodeStatus>> {
+        let url = format!("{}/Sessions", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .query(&[("deviceId", self.device_id.as_str())])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to get sessions: {}", response.status()));
+        }
+
+        let sessions: Vec<JellyfinSession> = response.json().await?;
+        // Older servers don't report the play session, this device only plays one stream anyway
+        let status = sessions
+            .into_iter()
+            .filter(|session| {
+                session
+                    .play_state
+                    .as_ref()
+                    .and_then(|state| state.play_session_id.as_deref())
+                    .is_none_or(|id| id == play_session_id)
+            })
+            .find_map(|session| session.transcoding_info)
+            .map(|info| TranscodeStatus {
+                video_transcoded: !info.is_video_direct,
+                audio_transcoded: !info.is_audio_direct,
+                // Only the encoding frame rate is reported, not how it compares to playback
+                speed: None,
+                hardware: info.hardware_acceleration_type.filter(|hw| hw != "none"),
+                reasons: info.transcode_reasons,
+            });
+        Ok(status)
+    }
+
     pub async fn report_playback_start(&self, media_id: &str) -> Result<()> {
         let url = format!("{}/Sessions/Playing", self.base_url);
 
@@ -942,6 +1266,58 @@ impl JellyfinApi {
         Ok(())
     }
 
+    /// Announce this session as one the server's apps can send playback commands to
+    pub async fn register_remote_control(&self) -> Result<()> {
+        let url = format!("{}/Sessions/Capabilities/Full", self.base_url);
+
+        let response = self
+            .client
+            .post(&url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .json(&serde_json::json!({
+                "PlayableMediaTypes": ["Video", "Audio"],
+                "SupportedCommands": [
+                    "VolumeUp",
+                    "VolumeDown",
+                    "Mute",
+                    "Unmute",
+                    "ToggleMute",
+                    "SetVolume",
+                    "ToggleFullscreen",
+                    "DisplayMessage",
+                    "Play",
+                    "Playstate",
+                ],
+                "SupportsMediaControl": true,
+                "SupportsPersistentIdentifier": true,
+            }))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to register remote control: {}",
+                response.status()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Connect to the socket the server pushes remote control commands through
+    pub(super) async fn open_session_socket(&self) -> Result<WebSocket<reqwest::Upgraded>> {
+        let url = format!(
+            "{}/socket?api_key={}&deviceId={}",
+            self.base_url,
+            utf8_percent_encode(&self.api_key, NON_ALPHANUMERIC),
+            utf8_percent_encode(&self.device_id, NON_ALPHANUMERIC)
+        );
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-Emby-Authorization", self.get_auth_header().parse()?);
+        WebSocket::connect(&url, headers).await
+    }
+
     pub async fn mark_as_watched(&self, media_id: &str) -> Result<()> {
         let url = format!(
             "{}/Users/{}/PlayedItems/{}",
@@ -1054,6 +1430,35 @@ impl JellyfinApi {
         Ok(segments.items)
     }
 
+    /// Fetch the chapters of an item, pointing at chapter images when the server extracted them
+    pub async fn get_chapters(&self, item_id: &str) -> Result<Vec<crate::models::Chapter>> {
+        let url = format!(
+            "{}/Users/{}/Items/{}?Fields=Chapters",
+            self.base_url, self.user_id, item_id
+        );
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Emby-Authorization", self.get_auth_header())
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to get item: {}", response.status()));
+        }
+
+        let item: ChaptersItem = response.json().await?;
+        let mut chapters: Vec<_> = item
+            .chapters
+            .iter()
+            .enumerate()
+            .map(|(index, chapter)| chapter.to_chapter(&self.base_url, item_id, index))
+            .collect();
+        chapters.sort_by_key(|chapter| chapter.start_time);
+        Ok(chapters)
+    }
+
     pub async fn find_next_episode(&self, current_episode: &Episode) -> Result<Option<Episode>> {
         // First, get the current episode's full info to get series ID
         let current_item = self.get_item(&current_episode.id).await?;
@@ -1510,6 +1915,139 @@ impl JellyfinApi {
     }
 }
 
+/// Cut a trickplay tile sheet into individual JPEG preview frames
+fn split_trickplay_sheet(
+    sheet: &[u8],
+    info: &TrickplayInfo,
+    first_index: usize,
+) -> Result<Vec<TrickplayFrame>> {
+    let sheet = image::load_from_memory(sheet)?;
+    let mut frames = Vec::new();
+
+    for tile in 0..(info.tile_width * info.tile_height) {
+        let index = first_index + tile as usize;
+        if index >= info.thumbnail_count as usize {
+            break;
+        }
+
+        let x = (tile % info.tile_width) * info.width;
+        let y = (tile / info.tile_width) * info.height;
+        if x + info.width > sheet.width() || y + info.height > sheet.height() {
+            break;
+        }
+
+        let thumbnail =
+            image::DynamicImage::ImageRgb8(sheet.crop_imm(x, y, info.width, info.height).to_rgb8());
+        let mut image = Vec::new();
+        thumbnail.write_to(
+            &mut std::io::Cursor::new(&mut image),
+            image::ImageFormat::Jpeg,
+        )?;
+
+        frames.push(TrickplayFrame {
+            timestamp: Duration::from_millis(index as u64 * info.interval),
+            image,
+        });
+    }
+
+    Ok(frames)
+}
+
+/// Build a Jellyfin DeviceProfile describing what the local player can decode
+fn build_device_profile(capabilities: &DeviceCapabilities) -> serde_json::Value {
+    let transcode_audio = capabilities.transcode_audio_codecs();
+
+    let mut codec_profiles: Vec<serde_json::Value> = capabilities
+        .height_limited_codecs()
+        .into_iter()
+        .map(|(codec, max_height)| {
+            serde_json::json!({
+                "Type": "Video",
+                "Codec": codec,
+                "Conditions": [
+                    {
+                        "Condition": "LessThanEqual",
+                        "Property": "Height",
+                        "Value": max_height.to_string(),
+                        "IsRequired": false
+                    }
+                ]
+            })
+        })
+        .collect();
+    // Only the DTS core can be decoded, so the lossless and high resolution
+    // extensions are transcoded
+    if capabilities.supports_audio_codec("dts") {
+        codec_profiles.push(serde_json::json!({
+            "Type": "VideoAudio",
+            "Codec": "dts",
+            "Conditions": DTS_HD_PROFILES
+                .iter()
+                .map(|profile| serde_json::json!({
+                    "Condition": "NotEquals",
+                    "Property": "AudioProfile",
+                    "Value": profile,
+                    "IsRequired": true
+                }))
+                .collect::<Vec<_>>()
+        }));
+    }
+
+    serde_json::json!({
+        "Name": JELLYFIN_CLIENT_NAME,
+        "MaxStreamingBitrate": capabilities.max_streaming_bitrate,
+        "MaxStaticBitrate": capabilities.max_streaming_bitrate,
+        "DirectPlayProfiles": [
+            {
+                "Container": capabilities.containers.join(","),
+                "Type": "Video",
+                "VideoCodec": capabilities.video_codecs.join(","),
+                "AudioCodec": capabilities.audio_codecs.join(",")
+            },
+            {
+                "Container": AUDIO_CONTAINERS,
+                "Type": "Audio",
+                "AudioCodec": capabilities.audio_codecs.join(",")
+            }
+        ],
+        "TranscodingProfiles": [
+            {
+                "Container": "ts",
+                "Type": "Video",
+                "AudioCodec": transcode_audio.join(","),
+                "VideoCodec": "h264",
+                "Context": "Streaming",
+                "Protocol": "hls",
+                "MaxAudioChannels": capabilities.max_audio_channels.to_string(),
+                "BreakOnNonKeyFrames": true
+            },
+            {
+                "Container": "mp3",
+                "Type": "Audio",
+                "AudioCodec": "mp3",
+                "Context": "Streaming",
+                "Protocol": "http"
+            }
+        ],
+        "CodecProfiles": codec_profiles,
+        "SubtitleProfiles": [
+            { "Format": "srt", "Method": "Embed" },
+            { "Format": "ass", "Method": "Embed" },
+            { "Format": "ssa", "Method": "Embed" },
+            { "Format": "subrip", "Method": "Embed" },
+            { "Format": "pgssub", "Method": "Embed" },
+            { "Format": "dvdsub", "Method": "Embed" },
+            { "Format": "vtt", "Method": "Embed" },
+            // Sidecar files are fetched separately and handed to the player
+            { "Format": "srt", "Method": "External" },
+            { "Format": "subrip", "Method": "External" },
+            { "Format": "ass", "Method": "External" },
+            { "Format": "ssa", "Method": "External" },
+            { "Format": "vtt", "Method": "External" }
+        ]
+    })
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct ServerInfo {
@@ -1568,6 +2106,31 @@ struct JellyfinView {
     primary_image_tag: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinSession {
+    play_state: Option<JellyfinPlayState>,
+    transcoding_info: Option<JellyfinTranscodingInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinPlayState {
+    play_session_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinTranscodingInfo {
+    #[serde(default)]
+    is_video_direct: bool,
+    #[serde(default)]
+    is_audio_direct: bool,
+    hardware_acceleration_type: Option<String>,
+    #[serde(default)]
+    transcode_reasons: Vec<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct ItemsResponse {
@@ -1599,6 +2162,12 @@ pub struct JellyfinItem {
     series_id: Option<String>,
     child_count: Option<i32>,
     people: Option<Vec<BaseItemPerson>>,
+    album: Option<String>,
+    album_id: Option<String>,
+    album_primary_image_tag: Option<String>,
+    album_artist: Option<String>,
+    #[serde(default)]
+    artists: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -1640,6 +2209,27 @@ pub struct MediaSegment {
     pub end_ticks: u64,
 }
 
+impl MediaSegment {
+    /// The skippable marker this segment describes, if it is one we handle
+    pub fn to_marker(&self) -> Option<crate::models::ChapterMarker> {
+        use crate::models::ChapterType;
+
+        let marker_type = match self.segment_type {
+            MediaSegmentType::Intro => ChapterType::Intro,
+            MediaSegmentType::Credits | MediaSegmentType::Outro => ChapterType::Credits,
+            MediaSegmentType::Recap => ChapterType::Recap,
+            MediaSegmentType::Preview => ChapterType::Preview,
+            MediaSegmentType::Commercial | MediaSegmentType::Other => return None,
+        };
+        // Ticks are 100ns units
+        Some(crate::models::ChapterMarker {
+            start_time: Duration::from_micros(self.start_ticks / 10),
+            end_time: Duration::from_micros(self.end_ticks / 10),
+            marker_type,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Copy)]
 pub enum MediaSegmentType {
     Intro,
@@ -1651,6 +2241,66 @@ pub enum MediaSegmentType {
     Other,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ChaptersItem {
+    #[serde(default)]
+    chapters: Vec<ChapterInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ChapterInfo {
+    pub start_position_ticks: u64,
+    pub name: Option<String>,
+    pub image_tag: Option<String>,
+}
+
+impl ChapterInfo {
+    /// Chapter images are addressed by the chapter's position in the item's list
+    pub fn to_chapter(
+        &self,
+        base_url: &str,
+        item_id: &str,
+        index: usize,
+    ) -> crate::models::Chapter {
+        crate::models::Chapter {
+            title: self
+                .name
+                .clone()
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("Chapter {}", index + 1)),
+            // Ticks are 100ns units
+            start_time: Duration::from_micros(self.start_position_ticks / 10),
+            thumbnail_url: self.image_tag.as_ref().map(|tag| {
+                format!(
+                    "{}/Items/{}/Images/Chapter/{}?tag={}&maxWidth=320",
+                    base_url, item_id, index, tag
+                )
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TrickplayItem {
+    /// Media source ID -> thumbnail width -> trickplay info
+    trickplay: Option<HashMap<String, HashMap<String, TrickplayInfo>>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+struct TrickplayInfo {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    thumbnail_count: u32,
+    /// Milliseconds between thumbnails
+    interval: u64,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct MediaSegmentsResponse {
@@ -1661,6 +2311,7 @@ struct MediaSegmentsResponse {
 #[serde(rename_all = "PascalCase")]
 struct PlaybackInfoResponse {
     media_sources: Vec<MediaSource>,
+    play_session_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1671,6 +2322,8 @@ struct MediaSource {
     bitrate: Option<u32>,
     supports_direct_play: bool,
     supports_direct_stream: bool,
+    transcoding_url: Option<String>,
+    transcoding_sub_protocol: Option<String>,
     media_streams: Vec<MediaStream>,
 }
 
@@ -1682,4 +2335,68 @@ struct MediaStream {
     codec: Option<String>,
     width: Option<i32>,
     height: Option<i32>,
+    index: Option<i32>,
+    language: Option<String>,
+    display_title: Option<String>,
+    #[serde(default)]
+    is_external: bool,
+    #[serde(default)]
+    is_forced: bool,
+    #[serde(default)]
+    is_default: bool,
+    /// Set when the device profile asked for the stream to be delivered externally
+    delivery_url: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api() -> JellyfinApi {
+        JellyfinApi {
+            client: reqwest::Client::new(),
+            base_url: "http://jellyfin.local".to_string(),
+            api_key: "key".to_string(),
+            user_id: "user".to_string(),
+            device_id: "device".to_string(),
+            backend_id: "backend".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_music_items() {
+        let response: ItemsResponse = serde_json::from_str(
+            r#"{"Items": [
+                {"Id": "t1", "Name": "Intro", "Type": "Audio", "Album": "Debut", "AlbumId": "a1", "AlbumPrimaryImageTag": "cover", "AlbumArtist": "Band", "Artists": ["Band", "Guest"], "IndexNumber": 1, "RunTimeTicks": 610000000},
+                {"Id": "t2", "Name": "Loose", "Type": "Audio"},
+                {"Id": "a1", "Name": "Debut", "Type": "MusicAlbum", "AlbumArtist": "Band", "ProductionYear": 2001, "ChildCount": 2, "Genres": ["Rock"], "ImageTags": {"Primary": "cover"}}
+            ]}"#,
+        )
+        .unwrap();
+        let api = api();
+        let mut items = response.items.into_iter();
+
+        let intro = api.music_track(items.next().unwrap());
+        assert_eq!(intro.artist, "Band");
+        assert_eq!(intro.album, "Debut");
+        assert_eq!(intro.track_number, Some(1));
+        assert_eq!(intro.duration, Duration::from_secs(61));
+        assert_eq!(
+            intro.cover_url.as_deref(),
+            Some("http://jellyfin.local/Items/a1/Images/Primary?tag=cover")
+        );
+
+        let loose = api.music_track(items.next().unwrap());
+        assert_eq!(loose.artist, "Unknown Artist");
+        assert_eq!(loose.album, "Unknown Album");
+        assert_eq!(loose.track_number, None);
+        assert_eq!(loose.cover_url, None);
+
+        let album = api.music_album(items.next().unwrap());
+        assert_eq!(album.artist, "Band");
+        assert_eq!(album.year, Some(2001));
+        assert_eq!(album.track_count, 2);
+        assert_eq!(album.genres, vec!["Rock".to_string()]);
+        assert!(album.cover_url.is_some());
+    }
 }
@@ -1,4 +1,6 @@
 pub mod api;
+mod session;
+mod websocket;
 
 pub use api::JellyfinApi;
 
@@ -10,7 +12,7 @@ use reqwest::Client;
 use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, mpsc};
 use tracing::{error, info};
 
 use super::traits::{
@@ -18,8 +20,10 @@ use super::traits::{
 };
 use crate::models::{
     AuthProvider, BackendId, Credentials, Episode, HomeSection, Library, LibraryId, MediaItem,
-    MediaItemId, Movie, Season, Show, ShowId, Source, SourceId, SourceType, StreamInfo, User,
+    MediaItemId, Movie, MusicAlbum, MusicTrack, Season, Show, ShowId, Source, SourceId, SourceType,
+    StreamInfo, TranscodeStatus, Trickplay, User,
 };
+use crate::player::{DeviceCapabilities, RemoteCommand, RemoteControlHandle};
 use crate::services::core::auth::AuthService;
 
 pub struct JellyfinBackend {
@@ -494,6 +498,21 @@ impl MediaBackend for JellyfinBackend {
         api.get_movies(&library_id.to_string()).await
     }
 
+    async fn get_music_albums(&self, library_id: &LibraryId) -> Result<Vec<MusicAlbum>> {
+        let api = self.ensure_api_initialized().await?;
+        api.get_music_albums(&library_id.to_string()).await
+    }
+
+    async fn get_music_tracks(&self, album_id: &MediaItemId) -> Result<Vec<MusicTrack>> {
+        let api = self.ensure_api_initialized().await?;
+        api.get_album_tracks(album_id.as_str()).await
+    }
+
+    async fn get_library_tracks(&self, library_id: &LibraryId) -> Result<Vec<MusicTrack>> {
+        let api = self.ensure_api_initialized().await?;
+        api.get_library_tracks(&library_id.to_string()).await
+    }
+
     async fn get_shows(&self, library_id: &LibraryId) -> Result<Vec<Show>> {
         let api = self.ensure_api_initialized().await?;
         api.get_shows(&library_id.to_string()).await
@@ -534,7 +553,8 @@ impl MediaBackend for JellyfinBackend {
             media_id
         );
 
-        let stream_info = api.get_stream_url(&jellyfin_item_id).await?;
+        let capabilities = DeviceCapabilities::current().await;
+        let stream_info = api.get_stream_url(&jellyfin_item_id, &capabilities).await?;
 
         // Report playback start after successfully getting stream info
         api.report_playback_start(&jellyfin_item_id).await.ok();
@@ -542,6 +562,48 @@ impl MediaBackend for JellyfinBackend {
         Ok(stream_info)
     }
 
+    async fn get_stream_url_for(
+        &self,
+        media_id: &MediaItemId,
+        capabilities: &DeviceCapabilities,
+    ) -> Result<StreamInfo> {
+        let api = self.ensure_api_initialized().await?;
+        let jellyfin_item_id = self.extract_jellyfin_item_id(media_id);
+        api.get_stream_url(&jellyfin_item_id, capabilities).await
+    }
+
+    async fn stop_transcode_session(&self, session_id: &str) -> Result<()> {
+        let api = self.ensure_api_initialized().await?;
+        api.stop_transcode_session(session_id).await
+    }
+
+    async fn ping_transcode_session(&self, session_id: &str) -> Result<()> {
+        let api = self.ensure_api_initialized().await?;
+        api.ping_transcode_session(session_id).await
+    }
+
+    async fn get_transcode_status(&self, session_id: &str) -> Result<Option<TranscodeStatus>> {
+        let api = self.ensure_api_initialized().await?;
+        api.get_transcode_status(session_id).await
+    }
+
+    async fn get_trickplay(&self, media_id: &MediaItemId) -> Result<Option<Trickplay>> {
+        let api = self.ensure_api_initialized().await?;
+        let jellyfin_item_id = self.extract_jellyfin_item_id(media_id);
+        api.get_trickplay(&jellyfin_item_id).await
+    }
+
+    async fn start_remote_control(
+        &self,
+        commands: mpsc::UnboundedSender<RemoteCommand>,
+        _sources: &[Source],
+    ) -> Result<Option<RemoteControlHandle>> {
+        let api = self.ensure_api_initialized().await?;
+        Ok(Some(RemoteControlHandle::new(tokio::spawn(session::run(
+            api, commands,
+        )))))
+    }
+
     async fn update_progress(
         &self,
         media_id: &MediaItemId,
@@ -632,44 +694,22 @@ impl MediaBackend for JellyfinBackend {
     async fn fetch_media_markers(
         &self,
         media_id: &MediaItemId,
-    ) -> Result<(
-        Option<crate::models::ChapterMarker>,
-        Option<crate::models::ChapterMarker>,
-    )> {
+    ) -> Result<Vec<crate::models::ChapterMarker>> {
         let api = self.ensure_api_initialized().await?;
 
         let jellyfin_item_id = self.extract_jellyfin_item_id(media_id);
-        if let Ok(segments) = api.get_media_segments(&jellyfin_item_id).await {
-            let mut intro = None;
-            let mut credits = None;
-
-            for segment in segments {
-                match segment.segment_type {
-                    crate::backends::jellyfin::api::MediaSegmentType::Intro => {
-                        intro = Some(crate::models::ChapterMarker {
-                            start_time: Duration::from_secs(segment.start_ticks / 10_000_000),
-                            end_time: Duration::from_secs(segment.end_ticks / 10_000_000),
-                            marker_type: crate::models::ChapterType::Intro,
-                        });
-                    }
-                    crate::backends::jellyfin::api::MediaSegmentType::Credits
-                    | crate::backends::jellyfin::api::MediaSegmentType::Outro => {
-                        credits = Some(crate::models::ChapterMarker {
-                            start_time: Duration::from_secs(segment.start_ticks / 10_000_000),
-                            end_time: Duration::from_secs(segment.end_ticks / 10_000_000),
-                            marker_type: crate::models::ChapterType::Credits,
-                        });
-                    }
-                    _ => {}
-                }
-            }
-
-            Ok((intro, credits))
-        } else {
-            Ok((None, None))
+        match api.get_media_segments(&jellyfin_item_id).await {
+            Ok(segments) => Ok(segments.iter().filter_map(|s| s.to_marker()).collect()),
+            Err(_) => Ok(Vec::new()),
         }
     }
 
+    async fn fetch_chapters(&self, media_id: &MediaItemId) -> Result<Vec<crate::models::Chapter>> {
+        let api = self.ensure_api_initialized().await?;
+        let jellyfin_item_id = self.extract_jellyfin_item_id(media_id);
+        api.get_chapters(&jellyfin_item_id).await
+    }
+
     async fn get_backend_info(&self) -> BackendInfo {
         let server_name = self.server_name.read().await.clone();
         BackendInfo {
@@ -981,4 +1021,40 @@ mod tests {
         assert!(debug_str.contains("backend_id"));
         assert!(debug_str.contains("server_name"));
     }
+
+    #[test]
+    fn test_media_segment_to_marker() {
+        let segment: api::MediaSegment =
+            serde_json::from_str(r#"{"Type": "Recap", "StartTicks": 0, "EndTicks": 905000000}"#)
+                .unwrap();
+        let marker = segment.to_marker().unwrap();
+        assert_eq!(marker.marker_type, crate::models::ChapterType::Recap);
+        assert_eq!(marker.end_time, Duration::from_millis(90_500));
+
+        let segment: api::MediaSegment =
+            serde_json::from_str(r#"{"Type": "Commercial", "StartTicks": 0, "EndTicks": 10}"#)
+                .unwrap();
+        assert!(segment.to_marker().is_none());
+    }
+
+    #[test]
+    fn test_chapter_info_to_chapter() {
+        let info: api::ChapterInfo = serde_json::from_str(
+            r#"{"StartPositionTicks": 6000000000, "Name": "The Heist", "ImageTag": "abc"}"#,
+        )
+        .unwrap();
+        let chapter = info.to_chapter("https://jf.example", "item1", 2);
+        assert_eq!(chapter.title, "The Heist");
+        assert_eq!(chapter.start_time, Duration::from_secs(600));
+        assert_eq!(
+            chapter.thumbnail_url.as_deref(),
+            Some("https://jf.example/Items/item1/Images/Chapter/2?tag=abc&maxWidth=320")
+        );
+
+        let info: api::ChapterInfo =
+            serde_json::from_str(r#"{"StartPositionTicks": 0, "Name": ""}"#).unwrap();
+        let chapter = info.to_chapter("https://jf.example", "item1", 0);
+        assert_eq!(chapter.title, "Chapter 1");
+        assert!(chapter.thumbnail_url.is_none());
+    }
 }
@@ -0,0 +1,262 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use super::JellyfinApi;
+use crate::models::MediaItemId;
+use crate::player::{PlayMode, RemoteCommand};
+
+const TICKS_PER_SECOND: u64 = 10_000_000;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct SessionMessage {
+    message_type: String,
+    #[serde(default)]
+    data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlayRequest {
+    #[serde(default)]
+    item_ids: Vec<String>,
+    start_position_ticks: Option<u64>,
+    #[serde(default)]
+    play_command: String,
+    start_index: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlaystateRequest {
+    command: String,
+    seek_position_ticks: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GeneralCommand {
+    name: String,
+    #[serde(default)]
+    arguments: std::collections::HashMap<String, String>,
+}
+
+/// What a message on the session socket asks of us
+#[derive(Debug, PartialEq)]
+enum Incoming {
+    /// The server drops sessions that stay quiet for longer than this
+    KeepAlive(Duration),
+    Command(RemoteCommand),
+    Ignored,
+}
+
+fn parse_message(text: &str) -> Result<Incoming> {
+    let message: SessionMessage = serde_json::from_str(text)?;
+    let command = match message.message_type.as_str() {
+        "ForceKeepAlive" => {
+            let seconds = message.data.as_u64().unwrap_or(60).max(2);
+            return Ok(Incoming::KeepAlive(Duration::from_secs(seconds)));
+        }
+        "Play" => {
+            let request: PlayRequest = serde_json::from_value(message.data)?;
+            let mode = match request.play_command.as_str() {
+                "PlayNext" => PlayMode::Next,
+                "PlayLast" => PlayMode::Last,
+                _ => PlayMode::Now,
+            };
+            Some(RemoteCommand::Play {
+                items: request.item_ids.into_iter().map(MediaItemId::new).collect(),
+                start_index: request.start_index.unwrap_or(0),
+                start_position: request
+                    .start_position_ticks
+                    .filter(|ticks| *ticks > 0)
+                    .map(ticks_to_duration),
+                mode,
+            })
+        }
+        "Playstate" => {
+            let request: PlaystateRequest = serde_json::from_value(message.data)?;
+            match request.command.as_str() {
+                "Stop" => Some(RemoteCommand::Stop),
+                "Pause" => Some(RemoteCommand::Pause),
+                "Unpause" => Some(RemoteCommand::Resume),
+                "PlayPause" => Some(RemoteCommand::PlayPause),
+                "NextTrack" => Some(RemoteCommand::Next),
+                "PreviousTrack" => Some(RemoteCommand::Previous),
+                "Seek" => request
+                    .seek_position_ticks
+                    .map(|ticks| RemoteCommand::Seek(ticks_to_duration(ticks))),
+                "Rewind" => Some(RemoteCommand::SeekRelative(-10)),
+                "FastForward" => Some(RemoteCommand::SeekRelative(30)),
+                _ => None,
+            }
+        }
+        "GeneralCommand" => {
+            let command: GeneralCommand = serde_json::from_value(message.data)?;
+            match command.name.as_str() {
+                "SetVolume" => command
+                    .arguments
+                    .get("Volume")
+                    .and_then(|volume| volume.parse::<f64>().ok())
+                    .map(|volume| RemoteCommand::SetVolume((volume / 100.0).clamp(0.0, 1.0))),
+                "VolumeUp" => Some(RemoteCommand::VolumeUp),
+                "VolumeDown" => Some(RemoteCommand::VolumeDown),
+                "Mute" => Some(RemoteCommand::SetMuted(Some(true))),
+                "Unmute" => Some(RemoteCommand::SetMuted(Some(false))),
+                "ToggleMute" => Some(RemoteCommand::SetMuted(None)),
+                "ToggleFullscreen" => Some(RemoteCommand::ToggleFullscreen),
+                "DisplayMessage" => {
+                    let header = command.arguments.get("Header").cloned().unwrap_or_default();
+                    let text = command.arguments.get("Text").cloned().unwrap_or_default();
+                    let message = match (header.is_empty(), text.is_empty()) {
+                        (false, false) => format!("{}: {}", header, text),
+                        (false, true) => header,
+                        _ => text,
+                    };
+                    (!message.is_empty()).then_some(RemoteCommand::ShowMessage(message))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    Ok(command.map_or(Incoming::Ignored, Incoming::Command))
+}
+
+fn ticks_to_duration(ticks: u64) -> Duration {
+    Duration::from_nanos(ticks.saturating_mul(1_000_000_000 / TICKS_PER_SECOND))
+}
+
+/// Keep the session registered and its socket open until `commands` is closed,
+/// reconnecting with a growing delay whenever the server goes away
+pub(super) async fn run(api: JellyfinApi, commands: mpsc::UnboundedSender<RemoteCommand>) {
+    let mut delay = RECONNECT_DELAY;
+    while !commands.is_closed() {
+        match connect_and_listen(&api, &commands).await {
+            Ok(()) => {
+                debug!("Jellyfin session socket closed");
+                delay = RECONNECT_DELAY;
+            }
+            Err(e) => {
+                warn!("Jellyfin remote control unavailable: {}", e);
+                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+            }
+        }
+        tokio::time::sleep(delay).await;
+    }
+}
+
+async fn connect_and_listen(
+    api: &JellyfinApi,
+    commands: &mpsc::UnboundedSender<RemoteCommand>,
+) -> Result<()> {
+    api.register_remote_control().await?;
+    let mut socket = api.open_session_socket().await?;
+    info!("Jellyfin remote control session connected");
+
+    let mut keep_alive: Option<tokio::task::JoinHandle<()>> = None;
+    let result: Result<()> = async {
+        while let Some(text) = socket.next_text().await? {
+            match parse_message(&text) {
+                Ok(Incoming::KeepAlive(timeout)) => {
+                    let sender = socket.sender();
+                    if let Some(task) = keep_alive.replace(tokio::spawn(async move {
+                        let mut interval = tokio::time::interval(timeout / 2);
+                        loop {
+                            interval.tick().await;
+                            if sender
+                                .send_text(r#"{"MessageType":"KeepAlive"}"#)
+                                .await
+                                .is_err()
+                            {
+                                break;
+                            }
+                        }
+                    })) {
+                        task.abort();
+                    }
+                }
+                Ok(Incoming::Command(command)) => {
+                    if commands.send(command).is_err() {
+                        break;
+                    }
+                }
+                Ok(Incoming::Ignored) => {}
+                Err(e) => debug!("Ignoring unreadable Jellyfin session message: {}", e),
+            }
+        }
+        Ok(())
+    }
+    .await;
+
+    if let Some(task) = keep_alive {
+        task.abort();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_play() {
+        let message = r#"{"MessageType":"Play","Data":{"ItemIds":["a","b"],"StartPositionTicks":600000000,"PlayCommand":"PlayNow","StartIndex":1}}"#;
+        assert_eq!(
+            parse_message(message).unwrap(),
+            Incoming::Command(RemoteCommand::Play {
+                items: vec![MediaItemId::new("a"), MediaItemId::new("b")],
+                start_index: 1,
+                start_position: Some(Duration::from_secs(60)),
+                mode: PlayMode::Now,
+            })
+        );
+
+        let message = r#"{"MessageType":"Play","Data":{"ItemIds":["c"],"PlayCommand":"PlayLast"}}"#;
+        assert!(matches!(
+            parse_message(message).unwrap(),
+            Incoming::Command(RemoteCommand::Play {
+                mode: PlayMode::Last,
+                start_position: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_playstate_and_commands() {
+        let seek =
+            r#"{"MessageType":"Playstate","Data":{"Command":"Seek","SeekPositionTicks":15000000}}"#;
+        assert_eq!(
+            parse_message(seek).unwrap(),
+            Incoming::Command(RemoteCommand::Seek(Duration::from_millis(1500)))
+        );
+
+        let volume = r#"{"MessageType":"GeneralCommand","Data":{"Name":"SetVolume","Arguments":{"Volume":"40"}}}"#;
+        assert_eq!(
+            parse_message(volume).unwrap(),
+            Incoming::Command(RemoteCommand::SetVolume(0.4))
+        );
+
+        let message = r#"{"MessageType":"GeneralCommand","Data":{"Name":"DisplayMessage","Arguments":{"Header":"Hi","Text":"there","TimeoutMs":"5000"}}}"#;
+        assert_eq!(
+            parse_message(message).unwrap(),
+            Incoming::Command(RemoteCommand::ShowMessage("Hi: there".to_string()))
+        );
+
+        assert_eq!(
+            parse_message(r#"{"MessageType":"ForceKeepAlive","Data":60}"#).unwrap(),
+            Incoming::KeepAlive(Duration::from_secs(60))
+        );
+        assert_eq!(
+            parse_message(r#"{"MessageType":"KeepAlive"}"#).unwrap(),
+            Incoming::Ignored
+        );
+    }
+}
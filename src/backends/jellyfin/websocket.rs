@@ -0,0 +1,258 @@
+use anyhow::{Context, Result, anyhow};
+use base64::Engine;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+use tokio::sync::Mutex;
+
+/// Session socket messages are a few KiB at most, so anything much larger is treated
+/// as a broken connection before its payload is read
+const MAX_MESSAGE_SIZE: u64 = 1024 * 1024;
+/// RFC 6455 keeps pings, pongs and closes to a single short frame
+const MAX_CONTROL_PAYLOAD: u64 = 125;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Just enough of a WebSocket client (RFC 6455) for the text messages of Jellyfin's
+/// session socket. The HTTP handshake is left to reqwest, so TLS and proxies work as
+/// for every other request.
+pub struct WebSocket<S> {
+    reader: ReadHalf<S>,
+    sender: WebSocketSender<S>,
+}
+
+/// Sends on a [`WebSocket`] while another task waits for its messages
+pub struct WebSocketSender<S> {
+    writer: Arc<Mutex<WriteHalf<S>>>,
+}
+
+impl<S> Clone for WebSocketSender<S> {
+    fn clone(&self) -> Self {
+        Self {
+            writer: self.writer.clone(),
+        }
+    }
+}
+
+impl WebSocket<reqwest::Upgraded> {
+    /// Open a socket at an `http` or `https` URL
+    pub async fn connect(url: &str, headers: reqwest::header::HeaderMap) -> Result<Self> {
+        // Upgrades only work on HTTP/1.1 connections
+        let client = reqwest::Client::builder()
+            .http1_only()
+            .build()
+            .context("Failed to create HTTP client")?;
+        let key = base64::engine::general_purpose::STANDARD.encode(uuid::Uuid::new_v4().as_bytes());
+
+        let response = client
+            .get(url)
+            .headers(headers)
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", key)
+            .send()
+            .await?;
+        if response.status() != reqwest::StatusCode::SWITCHING_PROTOCOLS {
+            return Err(anyhow!(
+                "Server refused the WebSocket connection: {}",
+                response.status()
+            ));
+        }
+
+        Ok(Self::from_stream(response.upgrade().await?))
+    }
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> WebSocket<S> {
+    fn from_stream(stream: S) -> Self {
+        let (reader, writer) = tokio::io::split(stream);
+        Self {
+            reader,
+            sender: WebSocketSender {
+                writer: Arc::new(Mutex::new(writer)),
+            },
+        }
+    }
+
+    pub fn sender(&self) -> WebSocketSender<S> {
+        self.sender.clone()
+    }
+
+    /// Wait for the next text message, answering pings on the way. `None` once the
+    /// server closed the socket. Not cancel safe, so keep it out of `select!` and send
+    /// from other tasks through [`Self::sender`] instead.
+    pub async fn next_text(&mut self) -> Result<Option<String>> {
+        let mut message = Vec::new();
+        let mut message_opcode = None;
+        loop {
+            let (fin, opcode, payload) = self.read_frame().await?;
+            match opcode {
+                OPCODE_PING => self.sender.send_frame(OPCODE_PONG, &payload).await?,
+                OPCODE_PONG => {}
+                OPCODE_CLOSE => {
+                    let _ = self.sender.send_frame(OPCODE_CLOSE, &payload).await;
+                    return Ok(None);
+                }
+                OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                    if opcode != OPCODE_CONTINUATION {
+                        message_opcode = Some(opcode);
+                        message.clear();
+                    }
+                    message.extend_from_slice(&payload);
+                    if message.len() as u64 > MAX_MESSAGE_SIZE {
+                        return Err(anyhow!("WebSocket message too large"));
+                    }
+                    if fin {
+                        if message_opcode == Some(OPCODE_TEXT) {
+                            return Ok(Some(String::from_utf8(std::mem::take(&mut message))?));
+                        }
+                        // Nothing sends binary messages on this socket
+                        message.clear();
+                    }
+                }
+                other => return Err(anyhow!("Unknown WebSocket opcode {}", other)),
+            }
+        }
+    }
+
+    async fn read_frame(&mut self) -> Result<(bool, u8, Vec<u8>)> {
+        let mut head = [0u8; 2];
+        self.reader.read_exact(&mut head).await?;
+        let fin = head[0] & 0x80 != 0;
+        let opcode = head[0] & 0x0F;
+        let masked = head[1] & 0x80 != 0;
+        let length = match head[1] & 0x7F {
+            126 => u64::from(self.reader.read_u16().await?),
+            127 => self.reader.read_u64().await?,
+            length => u64::from(length),
+        };
+        if length > MAX_MESSAGE_SIZE {
+            return Err(anyhow!("WebSocket frame too large"));
+        }
+        if opcode & 0x8 != 0 && (length > MAX_CONTROL_PAYLOAD || !fin) {
+            return Err(anyhow!("Invalid WebSocket control frame"));
+        }
+
+        let mut mask = [0u8; 4];
+        if masked {
+            self.reader.read_exact(&mut mask).await?;
+        }
+        let mut payload = vec![0u8; length as usize];
+        self.reader.read_exact(&mut payload).await?;
+        if masked {
+            apply_mask(&mut payload, mask);
+        }
+        Ok((fin, opcode, payload))
+    }
+}
+
+impl<S: AsyncWrite> WebSocketSender<S> {
+    pub async fn send_text(&self, text: &str) -> Result<()> {
+        self.send_frame(OPCODE_TEXT, text.as_bytes()).await
+    }
+
+    async fn send_frame(&self, opcode: u8, payload: &[u8]) -> Result<()> {
+        // Clients have to mask everything they send
+        let mask: [u8; 4] = uuid::Uuid::new_v4().as_bytes()[..4]
+            .try_into()
+            .expect("four bytes");
+        let mut writer = self.writer.lock().await;
+        writer
+            .write_all(&encode_frame(opcode, payload, mask))
+            .await?;
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+fn apply_mask(payload: &mut [u8], mask: [u8; 4]) {
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+}
+
+/// A single final frame, masked with `mask`
+fn encode_frame(opcode: u8, payload: &[u8], mask: [u8; 4]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        length @ 0..=125 => frame.push(0x80 | length as u8),
+        length @ 126..=0xFFFF => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&mask);
+    let start = frame.len();
+    frame.extend_from_slice(payload);
+    apply_mask(&mut frame[start..], mask);
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame() {
+        let frame = encode_frame(OPCODE_TEXT, b"Hi", [1, 2, 3, 4]);
+        assert_eq!(frame, vec![0x81, 0x82, 1, 2, 3, 4, b'H' ^ 1, b'i' ^ 2]);
+
+        let frame = encode_frame(OPCODE_TEXT, &[0; 300], [0; 4]);
+        assert_eq!(&frame[..4], &[0x81, 0xFE, 0x01, 0x2C]);
+        assert_eq!(frame.len(), 4 + 4 + 300);
+    }
+
+    #[tokio::test]
+    async fn test_fragmented_message_and_ping() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut socket = WebSocket::from_stream(client);
+
+        // A ping between the two halves of a text message, then a close
+        server
+            .write_all(&[0x01, 3, b'a', b'b', b'c'])
+            .await
+            .unwrap();
+        server.write_all(&[0x89, 1, b'p']).await.unwrap();
+        server.write_all(&[0x80, 2, b'd', b'e']).await.unwrap();
+        server.write_all(&[0x88, 0]).await.unwrap();
+
+        assert_eq!(socket.next_text().await.unwrap().as_deref(), Some("abcde"));
+        assert_eq!(socket.next_text().await.unwrap(), None);
+
+        // The ping was answered with a masked pong carrying the same payload
+        let mut pong = [0u8; 7];
+        server.read_exact(&mut pong).await.unwrap();
+        assert_eq!(pong[0], 0x8A);
+        assert_eq!(pong[1], 0x81);
+        assert_eq!(pong[6] ^ pong[2], b'p');
+    }
+
+    #[tokio::test]
+    async fn test_oversized_frames_are_refused() {
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut socket = WebSocket::from_stream(client);
+
+        // Only the header of a frame claiming to be larger than any message
+        let mut head = vec![0x81, 127];
+        head.extend_from_slice(&(MAX_MESSAGE_SIZE + 1).to_be_bytes());
+        server.write_all(&head).await.unwrap();
+        assert!(socket.next_text().await.is_err());
+
+        let (client, mut server) = tokio::io::duplex(1024);
+        let mut socket = WebSocket::from_stream(client);
+
+        // A ping longer than control frames may be
+        server.write_all(&[0x89, 126, 0, 200]).await.unwrap();
+        assert!(socket.next_text().await.is_err());
+    }
+}
@@ -117,6 +117,8 @@ impl MediaBackend for LocalBackend {
                 height: 0,
             },
             quality_options: vec![], // Local files don't need quality options
+            transcode_session_id: None,
+            external_subtitles: vec![],
         })
     }
 
@@ -5,3 +5,29 @@ pub mod traits;
 
 // Re-export commonly used types
 pub use traits::MediaBackend;
+
+use tracing::warn;
+
+/// Get the identifier persisted in `file_name` under the config directory, creating it
+/// on first use. Servers and their apps tell installs apart by it, so it must survive
+/// restarts and be shared by every API instance.
+pub(crate) fn persisted_id(file_name: &str) -> String {
+    let Some(id_file) = dirs::config_dir().map(|d| d.join("reel").join(file_name)) else {
+        return uuid::Uuid::new_v4().to_string();
+    };
+
+    if let Ok(id) = std::fs::read_to_string(&id_file) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return id.to_string();
+        }
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    if let Some(parent) = id_file.parent()
+        && let Err(e) = std::fs::create_dir_all(parent).and_then(|_| std::fs::write(&id_file, &id))
+    {
+        warn!("Failed to persist {}: {}", file_name, e);
+    }
+    id
+}
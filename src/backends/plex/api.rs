@@ -7,9 +7,21 @@ use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
 use crate::models::{
-    ChapterMarker, ChapterType, Episode, HomeSection, HomeSectionType, Library, LibraryType,
-    MediaItem, Movie, QualityOption, Resolution, Season, Show, StreamInfo,
+    Chapter, ChapterMarker, ChapterType, Episode, ExternalSubtitle, HomeSection, HomeSectionType,
+    Library, LibraryType, MediaItem, Movie, MusicAlbum, MusicTrack, QualityOption, Resolution,
+    Season, Show, StreamInfo, TranscodeStatus, Trickplay,
 };
+use crate::player::DeviceCapabilities;
+
+/// Plex decision code meaning the media can be direct played as-is
+const DIRECT_PLAY_OK: u32 = 1000;
+/// `streamType` of subtitle streams in Plex metadata
+const PLEX_SUBTITLE_STREAM_TYPE: u32 = 3;
+/// `audio.profile` values of DTS-HD Master Audio and High Resolution Audio streams
+const DTS_HD_PROFILES: &[&str] = &["ma", "hra"];
+/// Plex metadata types of albums and tracks, for listing them across a music library
+const PLEX_ALBUM_TYPE: u32 = 9;
+const PLEX_TRACK_TYPE: u32 = 10;
 
 // Plex Identity response for getting server machine ID
 #[derive(Debug, Deserialize)]
@@ -36,8 +48,14 @@ pub struct PlexApi {
 
 impl PlexApi {
     pub fn with_backend_id(base_url: String, auth_token: String, backend_id: String) -> Self {
+        // Servers tie transcode sessions to the client, so every request names this install
+        let mut headers = reqwest::header::HeaderMap::new();
+        if let Ok(client_id) = reqwest::header::HeaderValue::from_str(super::client_identifier()) {
+            headers.insert("X-Plex-Client-Identifier", client_id);
+        }
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
+            .default_headers(headers)
             .build()
             .expect("Failed to create HTTP client");
 
@@ -347,10 +365,116 @@ impl PlexApi {
         Ok(episodes)
     }
 
-    /// Get stream URL for a media item
-    pub async fn get_stream_url(&self, media_id: &str) -> Result<StreamInfo> {
-        // For Plex, we can usually direct play
-        // This is a simplified version - real implementation would check transcoding needs
+    /// Get all albums of a music library
+    pub async fn get_music_albums(&self, library_id: &str) -> Result<Vec<MusicAlbum>> {
+        let url = format!(
+            "{}/library/sections/{}/all?type={}",
+            self.base_url, library_id, PLEX_ALBUM_TYPE
+        );
+        let albums: Vec<MusicAlbum> = self
+            .get_music_metadata(&url, "albums")
+            .await?
+            .into_iter()
+            .map(|meta| self.music_album(meta))
+            .collect();
+
+        info!("Found {} albums in library {}", albums.len(), library_id);
+        Ok(albums)
+    }
+
+    /// Get every track of a music library in one request
+    pub async fn get_library_tracks(&self, library_id: &str) -> Result<Vec<MusicTrack>> {
+        let url = format!(
+            "{}/library/sections/{}/all?type={}",
+            self.base_url, library_id, PLEX_TRACK_TYPE
+        );
+        let tracks: Vec<MusicTrack> = self
+            .get_music_metadata(&url, "tracks")
+            .await?
+            .into_iter()
+            .map(|meta| self.music_track(meta))
+            .collect();
+
+        info!("Found {} tracks in library {}", tracks.len(), library_id);
+        Ok(tracks)
+    }
+
+    /// Get the tracks of an album
+    pub async fn get_album_tracks(&self, album_id: &str) -> Result<Vec<MusicTrack>> {
+        let url = format!("{}/library/metadata/{}/children", self.base_url, album_id);
+        Ok(self
+            .get_music_metadata(&url, "album tracks")
+            .await?
+            .into_iter()
+            .map(|meta| self.music_track(meta))
+            .collect())
+    }
+
+    async fn get_music_metadata(&self, url: &str, what: &str) -> Result<Vec<PlexMusicMetadata>> {
+        let response = self
+            .client
+            .get(url)
+            .header("X-Plex-Token", &self.auth_token)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to get {}: {}", what, response.status()));
+        }
+
+        let plex_response: PlexMusicResponse = response.json().await?;
+        Ok(plex_response.media_container.metadata)
+    }
+
+    fn music_album(&self, meta: PlexMusicMetadata) -> MusicAlbum {
+        MusicAlbum {
+            id: meta.rating_key,
+            title: meta.title,
+            // An album's parent is its artist
+            artist: meta
+                .parent_title
+                .unwrap_or_else(|| "Unknown Artist".to_string()),
+            year: meta.year,
+            track_count: meta.leaf_count.unwrap_or(0),
+            duration: Duration::from_millis(meta.duration.unwrap_or(0)),
+            cover_url: meta.thumb.map(|t| self.build_image_url(&t)),
+            genres: meta
+                .genre
+                .unwrap_or_default()
+                .into_iter()
+                .map(|g| g.tag)
+                .collect(),
+        }
+    }
+
+    fn music_track(&self, meta: PlexMusicMetadata) -> MusicTrack {
+        MusicTrack {
+            id: meta.rating_key,
+            title: meta.title,
+            // Tracks by someone other than the album artist name them separately
+            artist: meta
+                .original_title
+                .or(meta.grandparent_title)
+                .unwrap_or_else(|| "Unknown Artist".to_string()),
+            album: meta
+                .parent_title
+                .unwrap_or_else(|| "Unknown Album".to_string()),
+            track_number: meta.index,
+            duration: Duration::from_millis(meta.duration.unwrap_or(0)),
+            cover_url: meta
+                .parent_thumb
+                .or(meta.thumb)
+                .map(|t| self.build_image_url(&t)),
+        }
+    }
+
+    /// Get stream URL for a media item, negotiated for a player with `capabilities`
+    pub async fn get_stream_url(
+        &self,
+        media_id: &str,
+        capabilities: &DeviceCapabilities,
+    ) -> Result<StreamInfo> {
         let url = format!("{}/library/metadata/{}", self.base_url, media_id);
 
         let response = self
@@ -371,78 +495,387 @@ impl PlexApi {
             && let Some(media) = metadata.media.as_ref().and_then(|m| m.first())
             && let Some(part) = media.part.as_ref().and_then(|p| p.first())
         {
-            let stream_url = format!(
+            let profile_extra = build_client_profile_extra(capabilities);
+            let session_id = uuid::Uuid::new_v4().to_string();
+
+            // Ask the server whether our client profile can direct play this media
+            let direct_play = match self
+                .get_playback_decision(media_id, &session_id, &profile_extra)
+                .await
+            {
+                Ok(decision) => {
+                    info!(
+                        "Plex playback decision for {}: direct play {:?} ({}), transcode {:?} ({})",
+                        media_id,
+                        decision.direct_play_decision_code,
+                        decision.direct_play_decision_text.as_deref().unwrap_or(""),
+                        decision.transcode_decision_code,
+                        decision.transcode_decision_text.as_deref().unwrap_or("")
+                    );
+                    decision.direct_play_decision_code == Some(DIRECT_PLAY_OK)
+                }
+                Err(e) => {
+                    // Without a decision the server still transcodes to something playable
+                    warn!(
+                        "Failed to get Plex playback decision, transcoding instead: {}",
+                        e
+                    );
+                    false
+                }
+            };
+
+            return Ok(self.build_stream_info(
+                media_id,
+                media,
+                part,
+                direct_play,
+                &session_id,
+                &profile_extra,
+            ));
+        }
+
+        Err(anyhow!("Failed to get stream info for media"))
+    }
+
+    /// Stream info for a part once the server decided whether it can be direct played.
+    /// Every transcoded URL shares `session_id`, which is only reported for the URLs
+    /// that actually transcode.
+    fn build_stream_info(
+        &self,
+        media_id: &str,
+        media: &PlexMedia,
+        part: &PlexPart,
+        direct_play: bool,
+        session_id: &str,
+        profile_extra: &str,
+    ) -> StreamInfo {
+        let session = |transcodes: bool| transcodes.then(|| session_id.to_string());
+
+        let stream_url = if direct_play {
+            format!(
                 "{}{}?X-Plex-Token={}",
                 self.base_url, part.key, self.auth_token
+            )
+        } else {
+            // Let the server copy whatever streams it can and transcode the rest
+            self.build_transcode_url(
+                media_id,
+                session_id,
+                profile_extra,
+                &[("directStream", "1".to_string())],
+            )
+        };
+
+        // Generate quality options for transcoding
+        let mut quality_options = Vec::new();
+
+        // Add original quality (direct play)
+        let original_bitrate = media.bitrate.unwrap_or(0);
+        let original_width = media.width.unwrap_or(1920);
+        let original_height = media.height.unwrap_or(1080);
+
+        quality_options.push(QualityOption {
+            name: format!("Original ({}p)", original_height),
+            resolution: Resolution {
+                width: original_width,
+                height: original_height,
+            },
+            bitrate: original_bitrate,
+            url: stream_url.clone(),
+            requires_transcode: !direct_play,
+            transcode_session_id: session(!direct_play),
+        });
+
+        // Add transcoding options
+        let transcode_qualities = vec![
+            ("1080p", 1920, 1080, 8000000),
+            ("720p", 1280, 720, 4000000),
+            ("480p", 854, 480, 2000000),
+            ("360p", 640, 360, 1000000),
+        ];
+
+        for (name, width, height, bitrate) in transcode_qualities {
+            // Only add qualities lower than original
+            if height < original_height {
+                let transcode_url = self.build_transcode_url(
+                    media_id,
+                    session_id,
+                    profile_extra,
+                    &[
+                        ("directStream", "0".to_string()),
+                        ("maxVideoBitrate", (bitrate / 1000).to_string()), // Convert to kbps
+                        ("videoResolution", format!("{}x{}", width, height)),
+                    ],
+                );
+
+                quality_options.push(QualityOption {
+                    name: name.to_string(),
+                    resolution: Resolution { width, height },
+                    bitrate: bitrate as u64,
+                    url: transcode_url,
+                    requires_transcode: true,
+                    transcode_session_id: session(true),
+                });
+            }
+        }
+
+        StreamInfo {
+            url: stream_url,
+            direct_play,
+            video_codec: media.video_codec.clone().unwrap_or_default(),
+            audio_codec: media.audio_codec.clone().unwrap_or_default(),
+            container: part.container.clone().unwrap_or_default(),
+            bitrate: original_bitrate,
+            resolution: Resolution {
+                width: original_width,
+                height: original_height,
+            },
+            quality_options,
+            transcode_session_id: session(!direct_play),
+            external_subtitles: self.external_subtitles(part),
+        }
+    }
+
+    /// Sidecar subtitle files attached to a part, downloadable from their stream key
+    fn external_subtitles(&self, part: &PlexPart) -> Vec<ExternalSubtitle> {
+        part.stream
+            .iter()
+            .flatten()
+            .filter(|stream| stream.stream_type == Some(PLEX_SUBTITLE_STREAM_TYPE))
+            .filter_map(|stream| {
+                let key = stream.key.as_ref()?;
+                Some(ExternalSubtitle {
+                    url: format!("{}{}?X-Plex-Token={}", self.base_url, key, self.auth_token),
+                    title: stream
+                        .extended_display_title
+                        .clone()
+                        .or_else(|| stream.display_title.clone())
+                        .unwrap_or_else(|| "External".to_string()),
+                    language: stream.language_code.clone(),
+                    codec: stream.codec.clone().unwrap_or_default(),
+                    forced: stream.forced,
+                    default: stream.default,
+                })
+            })
+            .collect()
+    }
+
+    /// Ask the universal transcoder whether the media can be direct played with our profile
+    async fn get_playback_decision(
+        &self,
+        media_id: &str,
+        session_id: &str,
+        profile_extra: &str,
+    ) -> Result<PlexDecisionContainer> {
+        let url = format!("{}/video/:/transcode/universal/decision", self.base_url);
+        let path = format!("/library/metadata/{}", media_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Plex-Token", &self.auth_token)
+            .header("X-Plex-Product", "Reel")
+            .header("X-Plex-Platform", "Linux")
+            .header("X-Plex-Client-Profile-Extra", profile_extra)
+            .header("Accept", "application/json")
+            .query(&[
+                ("path", path.as_str()),
+                ("mediaIndex", "0"),
+                ("partIndex", "0"),
+                ("protocol", "hls"),
+                ("directPlay", "1"),
+                ("directStream", "1"),
+                ("hasMDE", "1"),
+                ("session", session_id),
+            ])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to get playback decision: {}",
+                response.status()
+            ));
+        }
+
+        let decision: PlexDecisionResponse = response.json().await?;
+        Ok(decision.media_container)
+    }
+
+    /// Build a universal transcoder HLS URL. Client headers are passed as query
+    /// parameters since the player fetches this URL without our HTTP client.
+    fn build_transcode_url(
+        &self,
+        media_id: &str,
+        session_id: &str,
+        profile_extra: &str,
+        params: &[(&str, String)],
+    ) -> String {
+        let path = format!("/library/metadata/{}", media_id);
+        let mut url = format!(
+            "{}/video/:/transcode/universal/start.m3u8?path={}&mediaIndex=0&partIndex=0&protocol=hls&directPlay=0&fastSeek=1&session={}",
+            self.base_url,
+            utf8_percent_encode(&path, NON_ALPHANUMERIC),
+            session_id
+        );
+
+        for (key, value) in params {
+            url.push_str(&format!(
+                "&{}={}",
+                key,
+                utf8_percent_encode(value, NON_ALPHANUMERIC)
+            ));
+        }
+
+        url.push_str(&format!(
+            "&X-Plex-Client-Identifier={}&X-Plex-Product=Reel&X-Plex-Platform=Linux&X-Plex-Client-Profile-Extra={}&X-Plex-Token={}",
+            utf8_percent_encode(super::client_identifier(), NON_ALPHANUMERIC),
+            utf8_percent_encode(profile_extra, NON_ALPHANUMERIC),
+            self.auth_token
+        ));
+
+        url
+    }
+
+    /// Fetch the BIF seek preview index for a media item, if the server generated one
+    pub async fn get_trickplay(&self, media_id: &str) -> Result<Option<Trickplay>> {
+        let url = format!("{}/library/metadata/{}", self.base_url, media_id);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Plex-Token", &self.auth_token)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!("Failed to get media info: {}", response.status()));
+        }
+
+        let plex_response: PlexMediaResponse = response.json().await?;
+
+        let Some(part) = plex_response
+            .media_container
+            .metadata
+            .first()
+            .and_then(|m| m.media.as_ref())
+            .and_then(|m| m.first())
+            .and_then(|m| m.part.as_ref())
+            .and_then(|p| p.first())
+        else {
+            return Ok(None);
+        };
+
+        let (Some(part_id), Some("sd")) = (part.id, part.indexes.as_deref()) else {
+            debug!("No preview index available for media ID: {}", media_id);
+            return Ok(None);
+        };
+
+        let index_url = format!("{}/library/parts/{}/indexes/sd", self.base_url, part_id);
+        let response = self
+            .client
+            .get(&index_url)
+            .header("X-Plex-Token", &self.auth_token)
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            warn!(
+                "Failed to fetch preview index for part {}: {}",
+                part_id,
+                response.status()
             );
+            return Ok(None);
+        }
 
-            // Generate quality options for transcoding
-            let mut quality_options = Vec::new();
+        let data = response.bytes().await?;
+        Ok(Some(Trickplay::from_bif(&data)?))
+    }
 
-            // Add original quality (direct play)
-            let original_bitrate = media.bitrate.unwrap_or(0);
-            let original_width = media.width.unwrap_or(1920);
-            let original_height = media.height.unwrap_or(1080);
+    /// Stop a universal transcoder session so the server stops encoding
+    pub async fn stop_transcode_session(&self, session_id: &str) -> Result<()> {
+        let url = format!("{}/video/:/transcode/universal/stop", self.base_url);
 
-            quality_options.push(QualityOption {
-                name: format!("Original ({}p)", original_height),
-                resolution: Resolution {
-                    width: original_width,
-                    height: original_height,
-                },
-                bitrate: original_bitrate,
-                url: stream_url.clone(),
-                requires_transcode: false,
-            });
+        debug!("Stopping Plex transcode session: {}", session_id);
 
-            // Add transcoding options
-            let transcode_qualities = vec![
-                ("1080p", 1920, 1080, 8000000),
-                ("720p", 1280, 720, 4000000),
-                ("480p", 854, 480, 2000000),
-                ("360p", 640, 360, 1000000),
-            ];
-
-            for (name, width, height, bitrate) in transcode_qualities {
-                // Only add qualities lower than original
-                if height < original_height {
-                    let path = format!("/library/metadata/{}", media_id);
-                    let transcode_url = format!(
-                        "{}/video/:/transcode/universal/start.m3u8?path={}&mediaIndex=0&partIndex=0&protocol=hls&directPlay=0&directStream=0&fastSeek=1&maxVideoBitrate={}&videoResolution={}x{}&X-Plex-Token={}",
-                        self.base_url,
-                        path.replace("/", "%2F"),
-                        bitrate / 1000, // Convert to kbps
-                        width,
-                        height,
-                        self.auth_token
-                    );
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Plex-Token", &self.auth_token)
+            .query(&[("session", session_id)])
+            .send()
+            .await?;
 
-                    quality_options.push(QualityOption {
-                        name: name.to_string(),
-                        resolution: Resolution { width, height },
-                        bitrate: bitrate as u64,
-                        url: transcode_url,
-                        requires_transcode: true,
-                    });
-                }
-            }
+        // 404 means the session already ended on the server
+        if !response.status().is_success() && response.status() != 404 {
+            return Err(anyhow!(
+                "Failed to stop transcode session: {}",
+                response.status()
+            ));
+        }
 
-            return Ok(StreamInfo {
-                url: stream_url,
-                direct_play: true,
-                video_codec: media.video_codec.clone().unwrap_or_default(),
-                audio_codec: media.audio_codec.clone().unwrap_or_default(),
-                container: part.container.clone().unwrap_or_default(),
-                bitrate: original_bitrate,
-                resolution: Resolution {
-                    width: original_width,
-                    height: original_height,
-                },
-                quality_options,
-            });
+        Ok(())
+    }
+
+    /// Keep a universal transcoder session alive while playback is paused
+    pub async fn ping_transcode_session(&self, session_id: &str) -> Result<()> {
+        let url = format!("{}/video/:/transcode/universal/ping", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Plex-Token", &self.auth_token)
+            .query(&[("session", session_id)])
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to ping transcode session: {}",
+                response.status()
+            ));
         }
 
-        Err(anyhow!("Failed to get stream info for media"))
+        Ok(())
+    }
+
+    /// Look up a universal transcoder session among the server's running ones
+    pub async fn get_transcode_status(&self, session_id: &str) -> Result<Option<TranscodeStatus>> {
+        let url = format!("{}/transcode/sessions", self.base_url);
+
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Plex-Token", &self.auth_token)
+            .header("Accept", "application/json")
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "Failed to get transcode sessions: {}",
+                response.status()
+            ));
+        }
+
+        let sessions: PlexTranscodeSessionsResponse = response.json().await?;
+        // Session keys look like /transcode/sessions/<session id>
+        let status = sessions
+            .media_container
+            .transcode_session
+            .into_iter()
+            .find(|session| session.key.rsplit('/').next() == Some(session_id))
+            .map(|session| TranscodeStatus {
+                video_transcoded: session.video_decision.as_deref() == Some("transcode"),
+                audio_transcoded: session.audio_decision.as_deref() == Some("transcode"),
+                speed: session.speed,
+                hardware: session
+                    .transcode_hw_encoding
+                    .filter(|encoder| !encoder.is_empty()),
+                reasons: Vec::new(),
+            });
+        Ok(status)
     }
 
     /// Update playback progress
@@ -489,7 +922,6 @@ impl PlexApi {
             .client
             .get(&timeline_url)
             .header("X-Plex-Token", &self.auth_token)
-            .header("X-Plex-Client-Identifier", "reel")
             .header("X-Plex-Product", "Reel")
             .header("X-Plex-Version", "0.1.0")
             .header("X-Plex-Platform", "Linux")
@@ -1089,9 +1521,22 @@ impl PlexApi {
         &self,
         rating_key: &str,
     ) -> Result<(Option<ChapterMarker>, Option<ChapterMarker>)> {
-        // Include additional parameters to ensure markers are returned
+        let markers = self.fetch_markers(rating_key).await?;
+        let find = |marker_type: ChapterType| {
+            markers
+                .iter()
+                .find(|m| m.marker_type == marker_type)
+                .cloned()
+        };
+        Ok((find(ChapterType::Intro), find(ChapterType::Credits)))
+    }
+
+    /// Fetch the full metadata of one item, including markers and chapters
+    async fn fetch_playback_metadata(
+        &self,
+        rating_key: &str,
+    ) -> Result<Option<PlexMetadataWithMarkers>> {
         // includeChapters=1 ensures chapter/marker data is included
-        info!("Fetching markers for media ID: {}", rating_key);
         let url = format!(
             "{}/library/metadata/{}?includeChapters=1&includeMarkers=1&includeOnDeck=1&includeRelated=1&includeExtras=1&includeGeolocation=1&X-Plex-Token={}",
             self.base_url, rating_key, self.auth_token
@@ -1107,11 +1552,11 @@ impl PlexApi {
 
         if !response.status().is_success() {
             warn!(
-                "Failed to fetch markers for episode {}: {}",
+                "Failed to fetch metadata for media {}: {}",
                 rating_key,
                 response.status()
             );
-            return Ok((None, None));
+            return Ok(None);
         }
 
         let response_text = response.text().await?;
@@ -1121,14 +1566,23 @@ impl PlexApi {
             Ok(d) => d,
             Err(e) => {
                 error!("Failed to parse Plex metadata response: {}", e);
-                return Ok((None, None));
+                return Ok(None);
             }
         };
 
-        let mut intro_marker = None;
-        let mut credits_marker = None;
+        let metadata = data.media_container.metadata.into_iter().next();
+        if metadata.is_none() {
+            warn!("No metadata found in response for media ID: {}", rating_key);
+        }
+        Ok(metadata)
+    }
+
+    /// Fetch every skippable marker (intro, credits, recap, preview) for any media
+    pub async fn fetch_markers(&self, rating_key: &str) -> Result<Vec<ChapterMarker>> {
+        info!("Fetching markers for media ID: {}", rating_key);
+        let mut result = Vec::new();
 
-        if let Some(metadata) = data.media_container.metadata.first() {
+        if let Some(metadata) = self.fetch_playback_metadata(rating_key).await? {
             if let Some(markers) = &metadata.marker {
                 info!(
                     "Found {} markers for media ID: {}",
@@ -1140,33 +1594,128 @@ impl PlexApi {
                         "Marker type: '{}', start: {}ms, end: {}ms",
                         marker.type_, marker.start_time_offset, marker.end_time_offset
                     );
-                    match marker.type_.as_str() {
-                        "intro" => {
-                            intro_marker = Some(ChapterMarker {
-                                start_time: Duration::from_millis(marker.start_time_offset),
-                                end_time: Duration::from_millis(marker.end_time_offset),
-                                marker_type: ChapterType::Intro,
-                            });
-                        }
-                        "credits" => {
-                            credits_marker = Some(ChapterMarker {
-                                start_time: Duration::from_millis(marker.start_time_offset),
-                                end_time: Duration::from_millis(marker.end_time_offset),
-                                marker_type: ChapterType::Credits,
-                            });
-                        }
-                        _ => {}
-                    }
+                    let marker_type = match marker.type_.as_str() {
+                        "intro" => ChapterType::Intro,
+                        "credits" => ChapterType::Credits,
+                        "recap" => ChapterType::Recap,
+                        "preview" => ChapterType::Preview,
+                        _ => continue,
+                    };
+                    result.push(ChapterMarker {
+                        start_time: Duration::from_millis(marker.start_time_offset),
+                        end_time: Duration::from_millis(marker.end_time_offset),
+                        marker_type,
+                    });
                 }
             } else {
                 info!("No markers found for media ID: {}", rating_key);
             }
-        } else {
-            warn!("No metadata found in response for media ID: {}", rating_key);
         }
 
-        Ok((intro_marker, credits_marker))
+        Ok(result)
+    }
+
+    /// Fetch the chapters of any media, with thumbnails when the server generated them
+    pub async fn fetch_chapters(&self, rating_key: &str) -> Result<Vec<Chapter>> {
+        let Some(metadata) = self.fetch_playback_metadata(rating_key).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut chapters: Vec<Chapter> = metadata
+            .chapter
+            .iter()
+            .enumerate()
+            .map(|(i, chapter)| Chapter {
+                title: chapter
+                    .tag
+                    .clone()
+                    .filter(|tag| !tag.is_empty())
+                    .unwrap_or_else(|| format!("Chapter {}", i + 1)),
+                start_time: Duration::from_millis(chapter.start_time_offset),
+                thumbnail_url: chapter
+                    .thumb
+                    .as_deref()
+                    .map(|thumb| self.build_chapter_thumb_url(thumb)),
+            })
+            .collect();
+        chapters.sort_by_key(|chapter| chapter.start_time);
+
+        debug!(
+            "Found {} chapters for media ID: {}",
+            chapters.len(),
+            rating_key
+        );
+        Ok(chapters)
+    }
+
+    /// Chapter thumbnails are video frames, so resize them to a 16:9 box
+    fn build_chapter_thumb_url(&self, path: &str) -> String {
+        let encoded_url = utf8_percent_encode(path, NON_ALPHANUMERIC).to_string();
+        format!(
+            "{}/photo/:/transcode?width=320&height=180&minSize=1&url={}&X-Plex-Token={}",
+            self.base_url, encoded_url, self.auth_token
+        )
+    }
+}
+
+/// Translate our codec names to the ones Plex uses in client profiles
+fn plex_codec_name(codec: &str) -> &str {
+    match codec {
+        "dts" => "dca",
+        other => other,
+    }
+}
+
+/// Build the X-Plex-Client-Profile-Extra describing what the local player can decode
+fn build_client_profile_extra(capabilities: &DeviceCapabilities) -> String {
+    let video_codecs: Vec<&str> = capabilities
+        .video_codecs
+        .iter()
+        .map(|c| plex_codec_name(c))
+        .collect();
+    let audio_codecs: Vec<&str> = capabilities
+        .audio_codecs
+        .iter()
+        .map(|c| plex_codec_name(c))
+        .collect();
+    let transcode_audio = capabilities.transcode_audio_codecs();
+
+    let mut directives = vec![
+        format!(
+            "add-transcode-target(type=videoProfile&context=streaming&protocol=hls&container=mpegts&videoCodec=h264&audioCodec={})",
+            transcode_audio.join(",")
+        ),
+        format!(
+            "add-direct-play-profile(type=videoProfile&container={}&videoCodec={}&audioCodec={})",
+            capabilities.containers.join(","),
+            video_codecs.join(","),
+            audio_codecs.join(",")
+        ),
+        format!(
+            "add-limitation(scope=videoAudioCodec&scopeName=*&type=upperBound&name=audio.channels&value={})",
+            capabilities.max_audio_channels
+        ),
+    ];
+
+    // Only the DTS core can be decoded, so DTS-HD streams are transcoded
+    if capabilities.supports_audio_codec("dts") {
+        for profile in DTS_HD_PROFILES {
+            directives.push(format!(
+                "add-limitation(scope=videoAudioCodec&scopeName=dca&type=notMatch&name=audio.profile&value={})",
+                profile
+            ));
+        }
+    }
+
+    for (codec, max_height) in capabilities.height_limited_codecs() {
+        directives.push(format!(
+            "add-limitation(scope=videoCodec&scopeName={}&type=upperBound&name=video.height&value={}&isRequired=false)",
+            plex_codec_name(codec),
+            max_height
+        ));
     }
+
+    directives.join("+")
 }
 
 // Plex API Response Types
@@ -1187,6 +1736,8 @@ struct PlexMetadataContainer {
 struct PlexMetadataWithMarkers {
     #[serde(rename = "Marker", default)]
     marker: Option<Vec<PlexMarker>>,
+    #[serde(rename = "Chapter", default)]
+    chapter: Vec<PlexChapter>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1199,6 +1750,52 @@ struct PlexMarker {
     end_time_offset: u64,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlexChapter {
+    tag: Option<String>,
+    #[serde(default)]
+    start_time_offset: u64,
+    thumb: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlexDecisionResponse {
+    media_container: PlexDecisionContainer,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlexDecisionContainer {
+    direct_play_decision_code: Option<u32>,
+    direct_play_decision_text: Option<String>,
+    transcode_decision_code: Option<u32>,
+    transcode_decision_text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlexTranscodeSessionsResponse {
+    media_container: PlexTranscodeSessionsContainer,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlexTranscodeSessionsContainer {
+    #[serde(rename = "TranscodeSession", default)]
+    transcode_session: Vec<PlexTranscodeSession>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlexTranscodeSession {
+    key: String,
+    video_decision: Option<String>,
+    audio_decision: Option<String>,
+    speed: Option<f64>,
+    transcode_hw_encoding: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct PlexLibrariesResponse {
@@ -1257,6 +1854,38 @@ struct PlexTag {
     tag: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlexMusicResponse {
+    media_container: PlexMusicContainer,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlexMusicContainer {
+    #[serde(rename = "Metadata", default)]
+    metadata: Vec<PlexMusicMetadata>,
+}
+
+/// An album or a track. Parents and grandparents are the album and artist of a
+/// track, and the artist of an album.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlexMusicMetadata {
+    rating_key: String,
+    title: String,
+    parent_title: Option<String>,
+    grandparent_title: Option<String>,
+    original_title: Option<String>,
+    index: Option<u32>,
+    year: Option<u32>,
+    duration: Option<u64>,
+    leaf_count: Option<u32>,
+    thumb: Option<String>,
+    parent_thumb: Option<String>,
+    #[serde(rename = "Genre", default)]
+    genre: Option<Vec<PlexTag>>,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct PlexShowsResponse {
@@ -1372,8 +2001,29 @@ struct PlexMedia {
 
 #[derive(Debug, Deserialize)]
 struct PlexPart {
+    id: Option<u64>,
     key: String,
     container: Option<String>,
+    /// Set to "sd" when the server has generated a BIF preview index
+    indexes: Option<String>,
+    #[serde(rename = "Stream", default)]
+    stream: Option<Vec<PlexStream>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PlexStream {
+    stream_type: Option<u32>,
+    /// Only set for sidecar streams that are served separately from the part
+    key: Option<String>,
+    codec: Option<String>,
+    language_code: Option<String>,
+    display_title: Option<String>,
+    extended_display_title: Option<String>,
+    #[serde(default)]
+    forced: bool,
+    #[serde(default)]
+    default: bool,
 }
 
 // Generic metadata structure that can handle movies, shows, and episodes
@@ -1451,3 +2101,121 @@ struct PlexHub {
     #[serde(rename = "Metadata", default)]
     metadata: Vec<PlexGenericMetadata>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api() -> PlexApi {
+        PlexApi::with_backend_id(
+            "https://plex.local:32400".to_string(),
+            "token".to_string(),
+            "backend".to_string(),
+        )
+    }
+
+    fn media() -> PlexMedia {
+        serde_json::from_value(serde_json::json!({
+            "bitrate": 20000,
+            "width": 1920,
+            "height": 1080,
+            "videoCodec": "hevc",
+            "audioCodec": "eac3",
+            "Part": [{ "key": "/library/parts/1/file.mkv", "container": "mkv" }]
+        }))
+        .unwrap()
+    }
+
+    fn stream_info(direct_play: bool) -> StreamInfo {
+        let media = media();
+        let part = &media.part.as_ref().unwrap()[0];
+        api().build_stream_info("42", &media, part, direct_play, "session-1", "")
+    }
+
+    #[test]
+    fn test_direct_play_has_no_transcode_session() {
+        let info = stream_info(true);
+        assert_eq!(
+            info.url,
+            "https://plex.local:32400/library/parts/1/file.mkv?X-Plex-Token=token"
+        );
+        assert_eq!(info.transcode_session_id, None);
+
+        let original = &info.quality_options[0];
+        assert!(!original.requires_transcode);
+        assert_eq!(original.transcode_session_id, None);
+
+        // Lower qualities still transcode under the shared session
+        let lower = &info.quality_options[1..];
+        assert_eq!(lower.len(), 3);
+        for option in lower {
+            assert!(option.requires_transcode);
+            assert_eq!(option.transcode_session_id.as_deref(), Some("session-1"));
+            assert!(option.url.contains("session=session-1"));
+        }
+    }
+
+    #[test]
+    fn test_transcode_reports_its_session() {
+        let info = stream_info(false);
+        assert!(info.url.contains("/video/:/transcode/universal/start.m3u8"));
+        assert!(info.url.contains("session=session-1"));
+        assert_eq!(info.transcode_session_id.as_deref(), Some("session-1"));
+
+        let original = &info.quality_options[0];
+        assert!(original.requires_transcode);
+        assert_eq!(original.url, info.url);
+        assert_eq!(original.transcode_session_id.as_deref(), Some("session-1"));
+    }
+
+    #[test]
+    fn test_profile_transcodes_dts_hd() {
+        let mut capabilities = DeviceCapabilities::baseline();
+        let profile = build_client_profile_extra(&capabilities);
+        assert!(!profile.contains("audio.profile"));
+
+        capabilities.audio_codecs.push("dts".to_string());
+        let profile = build_client_profile_extra(&capabilities);
+        assert!(!profile.contains("dca-ma"));
+        assert!(profile.contains(",dca)"));
+        for profile_name in DTS_HD_PROFILES {
+            assert!(profile.contains(&format!(
+                "scopeName=dca&type=notMatch&name=audio.profile&value={})",
+                profile_name
+            )));
+        }
+    }
+
+    #[test]
+    fn test_music_metadata() {
+        let response: PlexMusicResponse = serde_json::from_str(
+            r#"{"MediaContainer": {"Metadata": [
+                {"ratingKey": "11", "title": "Intro", "parentTitle": "Debut", "grandparentTitle": "Band", "index": 1, "duration": 61000, "parentThumb": "/library/metadata/10/thumb"},
+                {"ratingKey": "12", "title": "Guest Spot", "parentTitle": "Debut", "grandparentTitle": "Band", "originalTitle": "Guest", "index": 2},
+                {"ratingKey": "10", "title": "Debut", "parentTitle": "Band", "year": 2001, "leafCount": 2, "Genre": [{"tag": "Rock"}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let api = api();
+        let mut metadata = response.media_container.metadata.into_iter();
+
+        let intro = api.music_track(metadata.next().unwrap());
+        assert_eq!(intro.id, "11");
+        assert_eq!(intro.artist, "Band");
+        assert_eq!(intro.album, "Debut");
+        assert_eq!(intro.track_number, Some(1));
+        assert_eq!(intro.duration, Duration::from_secs(61));
+        assert!(intro.cover_url.unwrap().contains("metadata%2F10%2Fthumb"));
+
+        let guest = api.music_track(metadata.next().unwrap());
+        assert_eq!(guest.artist, "Guest");
+        assert_eq!(guest.track_number, Some(2));
+
+        let album = api.music_album(metadata.next().unwrap());
+        assert_eq!(album.title, "Debut");
+        assert_eq!(album.artist, "Band");
+        assert_eq!(album.year, Some(2001));
+        assert_eq!(album.track_count, 2);
+        assert_eq!(album.genres, vec!["Rock".to_string()]);
+    }
+}
@@ -6,7 +6,6 @@ use tokio::sync::oneshot;
 use tracing::{debug, info};
 
 const PLEX_TV_URL: &str = "https://plex.tv";
-const CLIENT_ID: &str = "reel-media-player";
 const PRODUCT_NAME: &str = "Reel";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,7 +32,7 @@ impl PlexAuth {
         let response = client
             .post(format!("{}/api/v2/pins", PLEX_TV_URL))
             .header("X-Plex-Product", PRODUCT_NAME)
-            .header("X-Plex-Client-Identifier", CLIENT_ID)
+            .header("X-Plex-Client-Identifier", super::client_identifier())
             .header("Accept", "application/json")
             .send()
             .await?;
@@ -79,7 +78,7 @@ impl PlexAuth {
 
             let response = client
                 .get(format!("{}/api/v2/pins/{}", PLEX_TV_URL, pin_id))
-                .header("X-Plex-Client-Identifier", CLIENT_ID)
+                .header("X-Plex-Client-Identifier", super::client_identifier())
                 .header("X-Plex-Product", PRODUCT_NAME)
                 .header("Accept", "application/json")
                 .send()
@@ -195,7 +194,7 @@ impl PlexAuth {
             .get(format!("{}/api/v2/resources", PLEX_TV_URL))
             .header("X-Plex-Token", auth_token)
             .header("X-Plex-Product", PRODUCT_NAME)
-            .header("X-Plex-Client-Identifier", CLIENT_ID)
+            .header("X-Plex-Client-Identifier", super::client_identifier())
             .header("Accept", "application/json")
             .query(&[("includeHttps", "1"), ("includeRelay", "1")])
             .send()
@@ -0,0 +1,829 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+use crate::models::MediaItemId;
+use crate::player::remote_control::watch_playback;
+use crate::player::{PlayMode, PlayerState, RemoteCommand, RemotePlayback};
+
+/// Port Plex apps expect players to listen on, announced through GDM either way
+const COMPANION_PORT: u16 = 32500;
+/// GDM (Plex's "good day mate" discovery) multicast group and ports for players
+const GDM_MULTICAST: Ipv4Addr = Ipv4Addr::new(239, 0, 0, 250);
+const GDM_SEARCH_PORT: u16 = 32412;
+const GDM_HELLO_PORT: u16 = 32413;
+const CAPABILITIES: &str = "timeline,playback,navigation";
+const TIMELINE_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a `poll?wait=1` request is held open waiting for a change
+const POLL_TIMEOUT: Duration = Duration::from_secs(20);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_REQUEST_SIZE: usize = 16 * 1024;
+const STEP_FORWARD_SECS: i64 = 30;
+const STEP_BACK_SECS: i64 = -15;
+
+/// The Plex server a controller told us to play from
+#[derive(Debug, Clone, PartialEq)]
+struct ServerTarget {
+    protocol: String,
+    address: String,
+    port: String,
+    token: Option<String>,
+    machine_identifier: Option<String>,
+}
+
+impl ServerTarget {
+    fn base_url(&self) -> String {
+        format!("{}://{}:{}", self.protocol, self.address, self.port)
+    }
+}
+
+/// The server a controller named, if it is one of `servers` (configured base URLs by
+/// machine identifier). It is reached at its configured URL, never at the address
+/// in the request, so nothing on the network can point us at a server of its own.
+fn configured_server(
+    servers: &HashMap<String, String>,
+    requested: &ServerTarget,
+) -> Option<ServerTarget> {
+    let machine_identifier = requested.machine_identifier.as_ref()?;
+    let url = url::Url::parse(servers.get(machine_identifier)?).ok()?;
+    Some(ServerTarget {
+        protocol: url.scheme().to_string(),
+        address: url.host_str()?.to_string(),
+        port: url.port_or_known_default()?.to_string(),
+        token: requested.token.clone(),
+        machine_identifier: Some(machine_identifier.clone()),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PlayMedia {
+    server: ServerTarget,
+    rating_key: String,
+    offset: Option<Duration>,
+    container_key: Option<String>,
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    path: String,
+    params: HashMap<String, String>,
+    client_id: Option<String>,
+    /// Plex token the controller sent along, not the one in a playMedia's parameters
+    token: Option<String>,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Command(RemoteCommand),
+    PlayMedia(PlayMedia),
+    Subscribe {
+        protocol: String,
+        port: String,
+    },
+    Unsubscribe,
+    Poll {
+        wait: bool,
+    },
+    Resources,
+    /// Understood but nothing for a desktop player to do
+    Ignore,
+    NotFound,
+}
+
+/// A controller receiving our timeline
+#[derive(Debug)]
+struct Subscriber {
+    url: String,
+    command_id: String,
+}
+
+/// Parse the request line and headers of an HTTP request
+fn parse_request(head: &str) -> Option<Request> {
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+
+    let url = url::Url::parse(&format!("http://localhost{}", target)).ok()?;
+    let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+    let headers: Vec<(&str, &str)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    // Plex apps send these as headers or, from browsers, as parameters
+    let header_or_param = |name: &str| {
+        headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.to_string())
+            .or_else(|| params.get(name).cloned())
+    };
+    let client_id = header_or_param("X-Plex-Client-Identifier");
+    let token = header_or_param("X-Plex-Token");
+
+    Some(Request {
+        method,
+        path: url.path().trim_end_matches('/').to_string(),
+        params,
+        client_id,
+        token,
+    })
+}
+
+fn action_for(request: &Request) -> Action {
+    let path = request.path.as_str();
+    let command = match path {
+        "/resources" => return Action::Resources,
+        "/player/timeline/subscribe" => {
+            return Action::Subscribe {
+                protocol: request.param("protocol").unwrap_or("http").to_string(),
+                port: request.param("port").unwrap_or("32400").to_string(),
+            };
+        }
+        "/player/timeline/unsubscribe" => return Action::Unsubscribe,
+        "/player/timeline/poll" => {
+            return Action::Poll {
+                wait: request.param("wait") == Some("1"),
+            };
+        }
+        "/player/playback/playMedia" => {
+            return match play_media(request) {
+                Some(play) => Action::PlayMedia(play),
+                None => Action::Ignore,
+            };
+        }
+        "/player/playback/play" => RemoteCommand::Resume,
+        "/player/playback/pause" => RemoteCommand::Pause,
+        "/player/playback/stop" => RemoteCommand::Stop,
+        "/player/playback/skipNext" => RemoteCommand::Next,
+        "/player/playback/skipPrevious" => RemoteCommand::Previous,
+        "/player/playback/stepForward" => RemoteCommand::SeekRelative(STEP_FORWARD_SECS),
+        "/player/playback/stepBack" => RemoteCommand::SeekRelative(STEP_BACK_SECS),
+        "/player/playback/seekTo" => {
+            match request
+                .param("offset")
+                .and_then(|ms| ms.parse::<u64>().ok())
+            {
+                Some(ms) => RemoteCommand::Seek(Duration::from_millis(ms)),
+                None => return Action::Ignore,
+            }
+        }
+        "/player/playback/setParameters" => {
+            match request.param("volume").and_then(|v| v.parse::<f64>().ok()) {
+                Some(volume) => RemoteCommand::SetVolume((volume / 100.0).clamp(0.0, 1.0)),
+                None => return Action::Ignore,
+            }
+        }
+        _ if path.starts_with("/player/") => return Action::Ignore,
+        _ => return Action::NotFound,
+    };
+    Action::Command(command)
+}
+
+fn play_media(request: &Request) -> Option<PlayMedia> {
+    let rating_key = request.param("key")?.rsplit('/').next()?.to_string();
+    Some(PlayMedia {
+        server: ServerTarget {
+            protocol: request.param("protocol").unwrap_or("http").to_string(),
+            address: request.param("address")?.to_string(),
+            port: request.param("port").unwrap_or("32400").to_string(),
+            token: request.param("token").map(str::to_string),
+            machine_identifier: request.param("machineIdentifier").map(str::to_string),
+        },
+        rating_key,
+        offset: request
+            .param("offset")
+            .and_then(|ms| ms.parse::<u64>().ok())
+            .filter(|ms| *ms > 0)
+            .map(Duration::from_millis),
+        container_key: request
+            .param("containerKey")
+            .filter(|key| key.starts_with("/playQueues/"))
+            .map(str::to_string),
+    })
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn timeline_xml(
+    playback: &RemotePlayback,
+    server: Option<&ServerTarget>,
+    command_id: &str,
+    client_id: &str,
+) -> String {
+    let state = match playback.state {
+        PlayerState::Playing => "playing",
+        PlayerState::Paused => "paused",
+        PlayerState::Loading => "buffering",
+        _ => "stopped",
+    };
+
+    let video = match (&playback.media_id, server, state) {
+        (Some(media_id), Some(server), state) if state != "stopped" => {
+            let mut video = format!(
+                r#"<Timeline type="video" state="{}" time="{}" ratingKey="{}" key="/library/metadata/{}" protocol="{}" address="{}" port="{}" volume="{}" controllable="playPause,stop,volume,stepBack,stepForward,seekTo,skipNext,skipPrevious""#,
+                state,
+                playback.position.as_millis(),
+                escape_xml(media_id.as_str()),
+                escape_xml(media_id.as_str()),
+                escape_xml(&server.protocol),
+                escape_xml(&server.address),
+                escape_xml(&server.port),
+                (playback.volume * 100.0).round() as u32,
+            );
+            if let Some(duration) = playback.duration {
+                video.push_str(&format!(r#" duration="{}""#, duration.as_millis()));
+            }
+            if let Some(machine_identifier) = &server.machine_identifier {
+                video.push_str(&format!(
+                    r#" machineIdentifier="{}""#,
+                    escape_xml(machine_identifier)
+                ));
+            }
+            video.push_str("/>");
+            video
+        }
+        _ => r#"<Timeline type="video" state="stopped"/>"#.to_string(),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?><MediaContainer location="fullScreenVideo" commandID="{}" machineIdentifier="{}"><Timeline type="music" state="stopped"/><Timeline type="photo" state="stopped"/>{}</MediaContainer>"#,
+        escape_xml(command_id),
+        escape_xml(client_id),
+        video
+    )
+}
+
+/// Headers describing this player, shared by GDM replies and announcements
+fn gdm_headers(client_id: &str, name: &str, port: u16) -> String {
+    format!(
+        "Content-Type: plex/media-player\r\nResource-Identifier: {}\r\nName: {}\r\nPort: {}\r\nProduct: Reel\r\nVersion: {}\r\nProtocol: plex\r\nProtocol-Version: 1\r\nProtocol-Capabilities: {}\r\nDevice-Class: pc\r\n\r\n",
+        client_id,
+        name,
+        port,
+        env!("CARGO_PKG_VERSION"),
+        CAPABILITIES
+    )
+}
+
+fn device_name() -> String {
+    std::fs::read_to_string("/etc/hostname")
+        .ok()
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty())
+        .map(|host| format!("Reel ({})", host))
+        .unwrap_or_else(|| "Reel".to_string())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PlayQueueResponse {
+    media_container: PlayQueueContainer,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayQueueContainer {
+    #[serde(rename = "playQueueSelectedItemOffset", default)]
+    selected_offset: usize,
+    #[serde(rename = "Metadata", default)]
+    metadata: Vec<PlayQueueItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlayQueueItem {
+    #[serde(rename = "ratingKey")]
+    rating_key: String,
+}
+
+struct Companion {
+    client: reqwest::Client,
+    client_id: String,
+    name: String,
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+    /// Base URLs of the Plex servers configured as sources, by machine identifier
+    servers: HashMap<String, String>,
+    /// Controllers that proved they may use one of those servers, with the address
+    /// they did so from
+    controllers: Mutex<HashMap<String, IpAddr>>,
+    server: Mutex<Option<ServerTarget>>,
+    subscribers: Mutex<HashMap<String, Subscriber>>,
+}
+
+impl Companion {
+    fn timeline(&self, playback: &RemotePlayback, command_id: &str) -> String {
+        let server = self.server.lock().unwrap().clone();
+        timeline_xml(playback, server.as_ref(), command_id, &self.client_id)
+    }
+
+    /// Whether the server at `base_url` accepts `token`, which proves the controller
+    /// holding it may play from that server
+    async fn token_accepted(&self, base_url: &str, token: &str) -> bool {
+        let response = self
+            .client
+            .get(format!("{}/library/sections", base_url))
+            .header("Accept", "application/json")
+            .header("X-Plex-Client-Identifier", &self.client_id)
+            .header("X-Plex-Token", token)
+            .send()
+            .await;
+        matches!(response, Ok(response) if response.status().is_success())
+    }
+
+    /// Whether the controller sending `request` may control playback. A controller
+    /// is trusted from the address it proved itself from, by a playMedia or by any
+    /// request carrying a token a configured server accepts.
+    async fn authorize(&self, request: &Request, peer: SocketAddr) -> bool {
+        let Some(client_id) = &request.client_id else {
+            return false;
+        };
+        if self.controllers.lock().unwrap().get(client_id) == Some(&peer.ip()) {
+            return true;
+        }
+        let Some(token) = &request.token else {
+            return false;
+        };
+        for base_url in self.servers.values() {
+            if self.token_accepted(base_url, token).await {
+                self.trust(client_id, peer);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn trust(&self, client_id: &str, peer: SocketAddr) {
+        info!(
+            "Plex controller {} at {} may control playback",
+            client_id,
+            peer.ip()
+        );
+        self.controllers
+            .lock()
+            .unwrap()
+            .insert(client_id.to_string(), peer.ip());
+    }
+
+    /// Items of the play queue the controller made for us on `server`, falling back
+    /// to the single requested item when the queue can't be read
+    async fn play_queue(
+        &self,
+        play: &PlayMedia,
+        server: &ServerTarget,
+    ) -> (Vec<MediaItemId>, usize) {
+        let single = (vec![MediaItemId::new(&play.rating_key)], 0);
+        let Some(container_key) = &play.container_key else {
+            return single;
+        };
+
+        let mut request = self
+            .client
+            .get(format!("{}{}", server.base_url(), container_key))
+            .header("Accept", "application/json")
+            .header("X-Plex-Client-Identifier", &self.client_id);
+        if let Some(token) = &server.token {
+            request = request.header("X-Plex-Token", token);
+        }
+
+        let queue = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                response.json::<PlayQueueResponse>().await.ok()
+            }
+            _ => None,
+        };
+        match queue {
+            Some(queue) if !queue.media_container.metadata.is_empty() => {
+                let items: Vec<MediaItemId> = queue
+                    .media_container
+                    .metadata
+                    .into_iter()
+                    .map(|item| MediaItemId::new(item.rating_key))
+                    .collect();
+                // Trust the requested key over the offset when they disagree
+                let start = items
+                    .iter()
+                    .position(|id| id.as_str() == play.rating_key)
+                    .unwrap_or(queue.media_container.selected_offset.min(items.len() - 1));
+                (items, start)
+            }
+            _ => {
+                debug!("Playing {} without its play queue", play.rating_key);
+                single
+            }
+        }
+    }
+
+    async fn handle_connection(self: Arc<Self>, mut stream: TcpStream, peer: SocketAddr) {
+        let mut buffer = Vec::new();
+        let head = tokio::time::timeout(REQUEST_TIMEOUT, async {
+            let mut chunk = [0u8; 2048];
+            loop {
+                if let Some(end) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                    return Some(String::from_utf8_lossy(&buffer[..end]).into_owned());
+                }
+                if buffer.len() > MAX_REQUEST_SIZE {
+                    return None;
+                }
+                match stream.read(&mut chunk).await {
+                    Ok(0) | Err(_) => return None,
+                    Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+                }
+            }
+        })
+        .await
+        .ok()
+        .flatten();
+
+        let Some(request) = head.as_deref().and_then(parse_request) else {
+            return;
+        };
+        let (status, body) = self.respond(&request, peer).await;
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/xml; charset=utf-8\r\nContent-Length: {}\r\nX-Plex-Client-Identifier: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            self.client_id,
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
+    async fn respond(&self, request: &Request, peer: SocketAddr) -> (&'static str, String) {
+        const OK: &str =
+            r#"<?xml version="1.0" encoding="UTF-8"?><Response code="200" status="OK"/>"#;
+        if request.method == "OPTIONS" {
+            return ("200 OK", String::new());
+        }
+
+        let command_id = request.param("commandID").unwrap_or("0").to_string();
+        if let Some(client_id) = &request.client_id
+            && let Some(subscriber) = self.subscribers.lock().unwrap().get_mut(client_id)
+        {
+            subscriber.command_id = command_id.clone();
+        }
+
+        match action_for(request) {
+            Action::Resources => (
+                "200 OK",
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><MediaContainer size="1"><Player title="{}" protocol="plex" protocolVersion="1" protocolCapabilities="{}" machineIdentifier="{}" product="Reel" platform="Linux" platformVersion="" deviceClass="pc"/></MediaContainer>"#,
+                    escape_xml(&self.name),
+                    CAPABILITIES,
+                    escape_xml(&self.client_id)
+                ),
+            ),
+            Action::Subscribe { protocol, port } => {
+                // The timeline shows what's playing, but only trusted controllers
+                // may change it
+                self.authorize(request, peer).await;
+                if let Some(client_id) = &request.client_id {
+                    let url = format!("{}://{}:{}/:/timeline", protocol, peer.ip(), port);
+                    info!("Plex controller {} subscribed to the timeline", client_id);
+                    self.subscribers
+                        .lock()
+                        .unwrap()
+                        .insert(client_id.clone(), Subscriber { url, command_id });
+                }
+                ("200 OK", OK.to_string())
+            }
+            Action::Unsubscribe => {
+                if let Some(client_id) = &request.client_id {
+                    self.subscribers.lock().unwrap().remove(client_id);
+                }
+                ("200 OK", OK.to_string())
+            }
+            Action::Poll { wait } => {
+                let mut playback = watch_playback();
+                if wait {
+                    let _ = tokio::time::timeout(POLL_TIMEOUT, playback.changed()).await;
+                }
+                let current = playback.borrow().clone();
+                ("200 OK", self.timeline(&current, &command_id))
+            }
+            Action::PlayMedia(play) => {
+                let Some(server) = configured_server(&self.servers, &play.server) else {
+                    warn!(
+                        "Ignoring Plex playMedia from {} for unknown server {:?}",
+                        peer.ip(),
+                        play.server.machine_identifier
+                    );
+                    return ("403 Forbidden", String::new());
+                };
+                let accepted = match &server.token {
+                    Some(token) => self.token_accepted(&server.base_url(), token).await,
+                    None => false,
+                };
+                if !accepted {
+                    warn!(
+                        "Ignoring Plex playMedia from {} without a token the server accepts",
+                        peer.ip()
+                    );
+                    return ("401 Unauthorized", String::new());
+                }
+                if let Some(client_id) = &request.client_id {
+                    self.trust(client_id, peer);
+                }
+
+                let (items, start_index) = self.play_queue(&play, &server).await;
+                *self.server.lock().unwrap() = Some(server);
+                let _ = self.commands.send(RemoteCommand::Play {
+                    items,
+                    start_index,
+                    start_position: play.offset,
+                    mode: PlayMode::Now,
+                });
+                ("200 OK", OK.to_string())
+            }
+            Action::Command(command) => {
+                if !self.authorize(request, peer).await {
+                    warn!(
+                        "Ignoring Plex command from untrusted controller at {}",
+                        peer.ip()
+                    );
+                    return ("401 Unauthorized", String::new());
+                }
+                let _ = self.commands.send(command);
+                ("200 OK", OK.to_string())
+            }
+            Action::Ignore => ("200 OK", OK.to_string()),
+            Action::NotFound => ("404 Not Found", String::new()),
+        }
+    }
+
+    /// Push the timeline to every subscriber each second, dropping the ones that
+    /// stopped listening
+    async fn send_timelines(self: Arc<Self>) {
+        let mut playback = watch_playback();
+        let mut interval = tokio::time::interval(TIMELINE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                changed = playback.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                }
+            }
+
+            let current = playback.borrow_and_update().clone();
+            let targets: Vec<(String, String, String)> = self
+                .subscribers
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(id, subscriber)| {
+                    (
+                        id.clone(),
+                        subscriber.url.clone(),
+                        self.timeline(&current, &subscriber.command_id),
+                    )
+                })
+                .collect();
+
+            for (id, url, body) in targets {
+                let result = self
+                    .client
+                    .post(&url)
+                    .header("Content-Type", "text/xml")
+                    .header("X-Plex-Client-Identifier", &self.client_id)
+                    .header("X-Plex-Device-Name", &self.name)
+                    .body(body)
+                    .send()
+                    .await;
+                if let Err(e) = result {
+                    debug!("Dropping Plex timeline subscriber {}: {}", id, e);
+                    self.subscribers.lock().unwrap().remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Answer GDM searches so Plex apps on the network list this player
+    async fn answer_discovery(self: Arc<Self>, port: u16) -> Result<()> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, GDM_SEARCH_PORT)).await?;
+        socket.join_multicast_v4(GDM_MULTICAST, Ipv4Addr::UNSPECIFIED)?;
+
+        let headers = gdm_headers(&self.client_id, &self.name, port);
+        let hello = format!("HELLO * HTTP/1.0\r\n{}", headers);
+        socket
+            .send_to(hello.as_bytes(), (GDM_MULTICAST, GDM_HELLO_PORT))
+            .await?;
+
+        let reply = format!("HTTP/1.0 200 OK\r\n{}", headers);
+        let mut buffer = [0u8; 1024];
+        loop {
+            let (len, from) = socket.recv_from(&mut buffer).await?;
+            if buffer[..len].starts_with(b"M-SEARCH * HTTP/1.") {
+                let _ = socket.send_to(reply.as_bytes(), from).await;
+            }
+        }
+    }
+}
+
+/// Start listening for Plex apps on the local network. One companion serves every
+/// Plex server in `servers` (base URLs by machine identifier), since apps tell us
+/// which server to play from with each command.
+pub(super) async fn start(
+    commands: mpsc::UnboundedSender<RemoteCommand>,
+    servers: HashMap<String, String>,
+) -> Result<JoinHandle<()>> {
+    let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, COMPANION_PORT)).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!(
+                "Plex companion port {} unavailable ({}), using any free port",
+                COMPANION_PORT, e
+            );
+            TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0)).await?
+        }
+    };
+    let port = listener.local_addr()?.port();
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+    let companion = Arc::new(Companion {
+        client,
+        client_id: super::client_identifier().to_string(),
+        name: device_name(),
+        commands,
+        servers,
+        controllers: Mutex::new(HashMap::new()),
+        server: Mutex::new(None),
+        subscribers: Mutex::new(HashMap::new()),
+    });
+    info!("Plex companion listening on port {}", port);
+
+    Ok(tokio::spawn(async move {
+        // Owning the helpers here stops them along with this task
+        let mut tasks = JoinSet::new();
+        tasks.spawn(companion.clone().send_timelines());
+        let discovery = companion.clone();
+        tasks.spawn(async move {
+            if let Err(e) = discovery.answer_discovery(port).await {
+                warn!("Plex player discovery unavailable: {}", e);
+            }
+        });
+
+        while !companion.commands.is_closed() {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        tasks.spawn(companion.clone().handle_connection(stream, peer));
+                    }
+                    Err(e) => warn!("Plex companion stopped accepting connections: {}", e),
+                },
+                // Reap finished connections so the set doesn't grow without bound
+                Some(_) = tasks.join_next(), if !tasks.is_empty() => {}
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(head: &str) -> Request {
+        parse_request(head).unwrap()
+    }
+
+    #[test]
+    fn test_parse_play_media() {
+        let request = request(
+            "GET /player/playback/playMedia?key=%2Flibrary%2Fmetadata%2F42&offset=90000&machineIdentifier=abc&address=192.168.1.2&port=32400&protocol=http&token=secret&containerKey=%2FplayQueues%2F7%3Fown%3D1&commandID=3 HTTP/1.1\r\nHost: reel:32500\r\nx-plex-client-identifier: phone",
+        );
+        assert_eq!(request.client_id.as_deref(), Some("phone"));
+        assert_eq!(
+            action_for(&request),
+            Action::PlayMedia(PlayMedia {
+                server: ServerTarget {
+                    protocol: "http".to_string(),
+                    address: "192.168.1.2".to_string(),
+                    port: "32400".to_string(),
+                    token: Some("secret".to_string()),
+                    machine_identifier: Some("abc".to_string()),
+                },
+                rating_key: "42".to_string(),
+                offset: Some(Duration::from_secs(90)),
+                container_key: Some("/playQueues/7?own=1".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_only_configured_servers_are_played_from() {
+        let servers = HashMap::from([(
+            "abc".to_string(),
+            "https://10-0-0-5.hash.plex.direct:32400".to_string(),
+        )]);
+        let mut requested = ServerTarget {
+            protocol: "http".to_string(),
+            address: "192.168.1.66".to_string(),
+            port: "8080".to_string(),
+            token: Some("secret".to_string()),
+            machine_identifier: Some("abc".to_string()),
+        };
+
+        // The configured address wins over the one in the request
+        let server = configured_server(&servers, &requested).unwrap();
+        assert_eq!(server.base_url(), "https://10-0-0-5.hash.plex.direct:32400");
+        assert_eq!(server.token.as_deref(), Some("secret"));
+
+        requested.machine_identifier = Some("elsewhere".to_string());
+        assert_eq!(configured_server(&servers, &requested), None);
+        requested.machine_identifier = None;
+        assert_eq!(configured_server(&servers, &requested), None);
+    }
+
+    #[test]
+    fn test_controller_token() {
+        let header = request(
+            "GET /player/playback/pause HTTP/1.1\r\nX-Plex-Token: abc\r\nX-Plex-Client-Identifier: phone",
+        );
+        assert_eq!(header.token.as_deref(), Some("abc"));
+
+        let param = request("GET /player/playback/pause?X-Plex-Token=def HTTP/1.1");
+        assert_eq!(param.token.as_deref(), Some("def"));
+
+        // A playMedia's token is for the server, not proof of the controller
+        let play = request("GET /player/playback/playMedia?key=%2F1&token=ghi HTTP/1.1");
+        assert_eq!(play.token, None);
+    }
+
+    #[test]
+    fn test_playback_commands() {
+        let seek = request("GET /player/playback/seekTo?offset=1500&commandID=4 HTTP/1.1");
+        assert_eq!(
+            action_for(&seek),
+            Action::Command(RemoteCommand::Seek(Duration::from_millis(1500)))
+        );
+
+        let volume = request("GET /player/playback/setParameters?volume=25 HTTP/1.1");
+        assert_eq!(
+            action_for(&volume),
+            Action::Command(RemoteCommand::SetVolume(0.25))
+        );
+
+        let poll = request("GET /player/timeline/poll?wait=1&commandID=5 HTTP/1.1");
+        assert_eq!(action_for(&poll), Action::Poll { wait: true });
+        assert_eq!(
+            action_for(&request("GET /player/navigation/moveUp HTTP/1.1")),
+            Action::Ignore
+        );
+        assert_eq!(
+            action_for(&request("GET /favicon.ico HTTP/1.1")),
+            Action::NotFound
+        );
+    }
+
+    #[test]
+    fn test_timeline_xml() {
+        let server = ServerTarget {
+            protocol: "http".to_string(),
+            address: "10.0.0.5".to_string(),
+            port: "32400".to_string(),
+            token: None,
+            machine_identifier: Some("abc".to_string()),
+        };
+        let playback = RemotePlayback {
+            media_id: Some(MediaItemId::new("42")),
+            state: PlayerState::Paused,
+            position: Duration::from_secs(10),
+            duration: Some(Duration::from_secs(60)),
+            volume: 0.5,
+        };
+
+        let xml = timeline_xml(&playback, Some(&server), "9", "reel");
+        assert!(xml.contains(r#"commandID="9""#));
+        assert!(xml.contains(r#"state="paused" time="10000" ratingKey="42""#));
+        assert!(xml.contains(r#"duration="60000""#));
+        assert!(xml.contains(r#"volume="50""#));
+
+        let stopped = timeline_xml(&RemotePlayback::default(), Some(&server), "9", "reel");
+        assert!(stopped.contains(r#"<Timeline type="video" state="stopped"/>"#));
+    }
+}
@@ -1,5 +1,6 @@
 mod api;
 mod auth;
+mod companion;
 
 pub use api::PlexApi;
 pub use auth::{PlexAuth, PlexConnection, PlexPin, PlexServer};
@@ -9,18 +10,28 @@ use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use dirs;
 use std::fmt;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, mpsc};
 use tracing::info;
 
 use super::traits::{MediaBackend, SearchResults};
 use crate::models::{
-    AuthProvider, BackendId, ChapterMarker, Credentials, Episode, Library, LibraryId, MediaItemId,
-    Movie, Season, Show, ShowId, Source, SourceId, SourceType, StreamInfo, User,
+    AuthProvider, BackendId, Chapter, ChapterMarker, Credentials, Episode, Library, LibraryId,
+    MediaItemId, Movie, MusicAlbum, MusicTrack, Season, Show, ShowId, Source, SourceId, SourceType,
+    StreamInfo, TranscodeStatus, Trickplay, User,
 };
+use crate::player::{DeviceCapabilities, RemoteCommand, RemoteControlHandle};
 use crate::services::core::auth::AuthService;
 
+/// Identifier of this install, sent with every Plex request. Servers tie transcode
+/// sessions to it and Plex apps remember players by it, so it is persisted across
+/// restarts.
+pub(crate) fn client_identifier() -> &'static str {
+    static CLIENT_IDENTIFIER: OnceLock<String> = OnceLock::new();
+    CLIENT_IDENTIFIER.get_or_init(|| crate::backends::persisted_id("plex_player_id"))
+}
+
 pub struct PlexBackend {
     base_url: Arc<RwLock<Option<String>>>,
     auth_token: Arc<RwLock<Option<String>>>,
@@ -993,6 +1004,21 @@ impl MediaBackend for PlexBackend {
         api.get_seasons(&show_id.to_string()).await
     }
 
+    async fn get_music_albums(&self, library_id: &LibraryId) -> Result<Vec<MusicAlbum>> {
+        let api = self.get_api().await?;
+        api.get_music_albums(&library_id.to_string()).await
+    }
+
+    async fn get_music_tracks(&self, album_id: &MediaItemId) -> Result<Vec<MusicTrack>> {
+        let api = self.get_api().await?;
+        api.get_album_tracks(album_id.as_str()).await
+    }
+
+    async fn get_library_tracks(&self, library_id: &LibraryId) -> Result<Vec<MusicTrack>> {
+        let api = self.get_api().await?;
+        api.get_library_tracks(&library_id.to_string()).await
+    }
+
     async fn get_episodes(&self, show_id: &ShowId, season_number: u32) -> Result<Vec<Episode>> {
         let api = self.get_api().await?;
 
@@ -1019,6 +1045,15 @@ impl MediaBackend for PlexBackend {
     }
 
     async fn get_stream_url(&self, media_id: &MediaItemId) -> Result<StreamInfo> {
+        let capabilities = DeviceCapabilities::current().await;
+        self.get_stream_url_for(media_id, &capabilities).await
+    }
+
+    async fn get_stream_url_for(
+        &self,
+        media_id: &MediaItemId,
+        capabilities: &DeviceCapabilities,
+    ) -> Result<StreamInfo> {
         tracing::info!(
             "get_stream_url() called for media_id: {} on backend: {}",
             media_id,
@@ -1056,7 +1091,7 @@ impl MediaBackend for PlexBackend {
                 // Fall back to existing API if available
                 let api = self.get_api().await?;
                 tracing::info!("Got API client, fetching stream URL from Plex API");
-                let result = api.get_stream_url(rating_key).await;
+                let result = api.get_stream_url(rating_key, capabilities).await;
                 match &result {
                     Ok(info) => tracing::info!("Successfully got stream URL: {}", info.url),
                     Err(e) => tracing::error!("Failed to get stream URL: {}", e),
@@ -1084,7 +1119,7 @@ impl MediaBackend for PlexBackend {
         };
 
         tracing::info!("Fetching stream URL from Plex API");
-        let result = api.get_stream_url(rating_key).await;
+        let result = api.get_stream_url(rating_key, capabilities).await;
         match &result {
             Ok(info) => tracing::info!("Successfully got stream URL: {}", info.url),
             Err(e) => tracing::error!("Failed to get stream URL: {}", e),
@@ -1092,6 +1127,54 @@ impl MediaBackend for PlexBackend {
         result
     }
 
+    async fn stop_transcode_session(&self, session_id: &str) -> Result<()> {
+        let api = self.get_api().await?;
+        api.stop_transcode_session(session_id).await
+    }
+
+    async fn ping_transcode_session(&self, session_id: &str) -> Result<()> {
+        let api = self.get_api().await?;
+        api.ping_transcode_session(session_id).await
+    }
+
+    async fn get_transcode_status(&self, session_id: &str) -> Result<Option<TranscodeStatus>> {
+        let api = self.get_api().await?;
+        api.get_transcode_status(session_id).await
+    }
+
+    async fn get_trickplay(&self, media_id: &MediaItemId) -> Result<Option<Trickplay>> {
+        // Composite IDs end with the Plex rating key
+        let rating_key = media_id
+            .as_str()
+            .split(':')
+            .next_back()
+            .unwrap_or(media_id.as_str());
+        let api = self.get_api().await?;
+        api.get_trickplay(rating_key).await
+    }
+
+    async fn start_remote_control(
+        &self,
+        commands: mpsc::UnboundedSender<RemoteCommand>,
+        sources: &[Source],
+    ) -> Result<Option<RemoteControlHandle>> {
+        // Only the servers set up here may be played from, at the address they were
+        // set up with
+        let servers = sources
+            .iter()
+            .filter_map(|source| match &source.source_type {
+                SourceType::PlexServer { machine_id, .. } if !machine_id.is_empty() => {
+                    let url = source.connection_info.primary_url.as_ref()?;
+                    Some((machine_id.clone(), url.trim_end_matches('/').to_string()))
+                }
+                _ => None,
+            })
+            .collect();
+        Ok(Some(RemoteControlHandle::new(
+            companion::start(commands, servers).await?,
+        )))
+    }
+
     async fn update_progress(
         &self,
         media_id: &MediaItemId,
@@ -1223,13 +1306,15 @@ impl MediaBackend for PlexBackend {
         api.fetch_episode_markers(episode_id.as_str()).await
     }
 
-    async fn fetch_media_markers(
-        &self,
-        media_id: &MediaItemId,
-    ) -> Result<(Option<ChapterMarker>, Option<ChapterMarker>)> {
+    async fn fetch_media_markers(&self, media_id: &MediaItemId) -> Result<Vec<ChapterMarker>> {
         // Plex uses the same API endpoint for both movies and episodes
         let api = self.get_api().await?;
-        api.fetch_episode_markers(media_id.as_str()).await
+        api.fetch_markers(media_id.as_str()).await
+    }
+
+    async fn fetch_chapters(&self, media_id: &MediaItemId) -> Result<Vec<Chapter>> {
+        let api = self.get_api().await?;
+        api.fetch_chapters(media_id.as_str()).await
     }
 
     async fn find_next_episode(&self, current_episode: &Episode) -> Result<Option<Episode>> {
@@ -2,11 +2,14 @@ use anyhow::Result;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use std::time::Duration;
+use tokio::sync::mpsc;
 
 use crate::models::{
-    BackendId, ChapterMarker, Credentials, Episode, HomeSection, Library, LibraryId, MediaItem,
-    MediaItemId, Movie, MusicAlbum, MusicTrack, Photo, Season, Show, ShowId, StreamInfo, User,
+    BackendId, Chapter, ChapterMarker, Credentials, Episode, HomeSection, Library, LibraryId,
+    MediaItem, MediaItemId, Movie, MusicAlbum, MusicTrack, Photo, Season, Show, ShowId, Source,
+    StreamInfo, TranscodeStatus, Trickplay, User,
 };
+use crate::player::{DeviceCapabilities, RemoteCommand, RemoteControlHandle};
 
 #[async_trait]
 pub trait MediaBackend: Send + Sync + std::fmt::Debug {
@@ -42,6 +45,50 @@ pub trait MediaBackend: Send + Sync + std::fmt::Debug {
 
     async fn get_stream_url(&self, media_id: &MediaItemId) -> Result<StreamInfo>;
 
+    /// Get a stream negotiated for another player's `capabilities`, such as a cast
+    /// receiver's, in a transcode session of its own
+    async fn get_stream_url_for(
+        &self,
+        media_id: &MediaItemId,
+        _capabilities: &DeviceCapabilities,
+    ) -> Result<StreamInfo> {
+        // Backends without server-side negotiation serve the same stream to everyone
+        self.get_stream_url(media_id).await
+    }
+
+    /// Stop a server-side transcode session started by `get_stream_url`
+    async fn stop_transcode_session(&self, _session_id: &str) -> Result<()> {
+        // Default implementation does nothing
+        // Only backends that transcode need to override this
+        Ok(())
+    }
+
+    /// Keep a server-side transcode session alive, e.g. while playback is paused
+    async fn ping_transcode_session(&self, _session_id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// What the server is doing in a transcode session, `None` once the session is gone
+    async fn get_transcode_status(&self, _session_id: &str) -> Result<Option<TranscodeStatus>> {
+        Ok(None)
+    }
+
+    /// Fetch seek-bar preview thumbnails generated by the server
+    async fn get_trickplay(&self, _media_id: &MediaItemId) -> Result<Option<Trickplay>> {
+        Ok(None)
+    }
+
+    /// Let the server's own apps control playback here, passing what they ask for to
+    /// `commands` until the returned handle is dropped. `sources` are all configured
+    /// sources, for backends whose one session serves several of them.
+    async fn start_remote_control(
+        &self,
+        _commands: mpsc::UnboundedSender<RemoteCommand>,
+        _sources: &[Source],
+    ) -> Result<Option<RemoteControlHandle>> {
+        Ok(None)
+    }
+
     async fn update_progress(
         &self,
         media_id: &MediaItemId,
@@ -74,14 +121,16 @@ pub trait MediaBackend: Send + Sync + std::fmt::Debug {
         Ok((None, None))
     }
 
-    /// Fetch intro and credits markers for any media (movie or episode)
-    async fn fetch_media_markers(
-        &self,
-        _media_id: &MediaItemId,
-    ) -> Result<(Option<ChapterMarker>, Option<ChapterMarker>)> {
+    /// Fetch skippable intro, credits, recap and preview markers for any media (movie or episode)
+    async fn fetch_media_markers(&self, _media_id: &MediaItemId) -> Result<Vec<ChapterMarker>> {
         // Default implementation returns no markers
         // Backends should override this to provide marker functionality
-        Ok((None, None))
+        Ok(Vec::new())
+    }
+
+    /// Fetch server-side chapters, with thumbnail URLs when the server has chapter images
+    async fn fetch_chapters(&self, _media_id: &MediaItemId) -> Result<Vec<Chapter>> {
+        Ok(Vec::new())
     }
 
     /// Find the next episode after the given episode
@@ -137,6 +186,11 @@ pub trait MediaBackend: Send + Sync + std::fmt::Debug {
         Ok(Vec::new())
     }
 
+    // Optional: Get every track of a music library in one request, for syncing
+    async fn get_library_tracks(&self, _library_id: &LibraryId) -> Result<Vec<MusicTrack>> {
+        Ok(Vec::new())
+    }
+
     // Optional: Get photos for photo libraries
     async fn get_photos(&self, _library_id: &LibraryId) -> Result<Vec<Photo>> {
         Ok(Vec::new())
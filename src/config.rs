@@ -4,6 +4,9 @@ use std::fs;
 use std::path::PathBuf;
 use tracing::{debug, info};
 
+use crate::core::player_traits::ReplayGainMode;
+use crate::models::{AudioEffects, AudioPreset, ChapterType, MarkerSkipMode};
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     #[serde(default, skip_serializing_if = "GeneralConfig::is_default")]
@@ -63,6 +66,30 @@ pub struct PlaybackConfig {
     #[serde(default = "default_true", skip_serializing_if = "is_true")]
     pub skip_credits: bool,
 
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub skip_recap: bool,
+
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub skip_preview: bool,
+
+    #[serde(default = "default_false", skip_serializing_if = "is_false")]
+    pub auto_skip_intro: bool,
+
+    #[serde(default = "default_false", skip_serializing_if = "is_false")]
+    pub auto_skip_credits: bool,
+
+    #[serde(default = "default_false", skip_serializing_if = "is_false")]
+    pub auto_skip_recap: bool,
+
+    #[serde(default = "default_false", skip_serializing_if = "is_false")]
+    pub auto_skip_preview: bool,
+
+    #[serde(
+        default = "default_auto_skip_delay",
+        skip_serializing_if = "is_default_auto_skip_delay"
+    )]
+    pub auto_skip_delay: u64,
+
     #[serde(default = "default_true", skip_serializing_if = "is_true")]
     pub auto_play_next: bool,
 
@@ -107,6 +134,56 @@ pub struct PlaybackConfig {
         skip_serializing_if = "is_default_progress_update_interval"
     )]
     pub progress_update_interval_seconds: u32,
+
+    #[serde(default = "default_false", skip_serializing_if = "is_false")]
+    pub inhibit_while_paused: bool,
+
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub sleep_timer_fade_out: bool,
+
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub gapless_audio: bool,
+
+    #[serde(
+        default = "default_replaygain",
+        skip_serializing_if = "is_default_replaygain"
+    )]
+    pub replaygain: String,
+
+    /// Folder for screenshots and clips; empty uses the Pictures folder
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub capture_directory: String,
+
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub screenshot_include_subtitles: bool,
+
+    /// Audio output to play on; empty uses the system default
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub audio_device: String,
+
+    /// Codecs sent undecoded to a receiver: ac3, eac3, dts, dts-hd, truehd
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_passthrough: Vec<String>,
+
+    #[serde(
+        default = "default_audio_preset",
+        skip_serializing_if = "is_default_audio_preset"
+    )]
+    pub audio_preset: String,
+
+    /// Gain of each equalizer band in dB, from the lowest band up; empty is flat
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub equalizer: Vec<f64>,
+
+    /// Let Jellyfin and Plex apps play to and control this player. Off unless turned
+    /// on, since Plex apps reach it through a port open to the local network.
+    #[serde(default = "default_false", skip_serializing_if = "is_false")]
+    pub remote_control: bool,
+
+    /// Step down a quality when reconnecting a dropped stream keeps failing. Only Plex
+    /// offers the lower qualities; Jellyfin streams reconnect at the quality they had.
+    #[serde(default = "default_true", skip_serializing_if = "is_true")]
+    pub reconnect_lower_quality: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -373,6 +450,13 @@ impl Default for PlaybackConfig {
             default_audio: default_audio(),
             skip_intro: default_true(),
             skip_credits: default_true(),
+            skip_recap: default_true(),
+            skip_preview: default_true(),
+            auto_skip_intro: default_false(),
+            auto_skip_credits: default_false(),
+            auto_skip_recap: default_false(),
+            auto_skip_preview: default_false(),
+            auto_skip_delay: default_auto_skip_delay(),
             auto_play_next: default_true(),
             auto_play_delay: default_auto_play_delay(),
             mpv_verbose_logging: default_false(),
@@ -382,6 +466,18 @@ impl Default for PlaybackConfig {
             auto_resume: default_true(),
             resume_threshold_seconds: default_resume_threshold(),
             progress_update_interval_seconds: default_progress_update_interval(),
+            inhibit_while_paused: default_false(),
+            sleep_timer_fade_out: default_true(),
+            gapless_audio: default_true(),
+            replaygain: default_replaygain(),
+            capture_directory: String::new(),
+            screenshot_include_subtitles: default_true(),
+            audio_device: String::new(),
+            audio_passthrough: Vec::new(),
+            audio_preset: default_audio_preset(),
+            equalizer: Vec::new(),
+            remote_control: default_false(),
+            reconnect_lower_quality: default_true(),
         }
     }
 }
@@ -430,9 +526,18 @@ fn default_plex_url() -> String {
 fn default_auto_play_delay() -> u64 {
     10 // 10 seconds countdown
 }
+fn default_auto_skip_delay() -> u64 {
+    5 // 5 seconds countdown
+}
 fn default_false() -> bool {
     false
 }
+fn default_replaygain() -> String {
+    "track".to_string()
+}
+fn default_audio_preset() -> String {
+    "off".to_string()
+}
 
 fn default_cache_size_mb() -> u32 {
     1500 // 1.5GB for ~15-30 min of 1080p/4K content
@@ -475,6 +580,14 @@ fn is_false(value: &bool) -> bool {
     !(*value)
 }
 
+fn is_default_replaygain(value: &str) -> bool {
+    value == default_replaygain()
+}
+
+fn is_default_audio_preset(value: &str) -> bool {
+    value == default_audio_preset()
+}
+
 fn is_default_subtitle(value: &str) -> bool {
     value == default_subtitle()
 }
@@ -503,6 +616,10 @@ fn is_default_auto_play_delay(value: &u64) -> bool {
     *value == default_auto_play_delay()
 }
 
+fn is_default_auto_skip_delay(value: &u64) -> bool {
+    *value == default_auto_skip_delay()
+}
+
 fn is_default_cache_size_mb(value: &u32) -> bool {
     *value == default_cache_size_mb()
 }
@@ -534,6 +651,67 @@ impl PlaybackConfig {
     fn is_default(value: &Self) -> bool {
         value == &Self::default()
     }
+
+    /// How markers of a type are handled unless a show remembers its own choice
+    pub fn skip_mode(&self, marker_type: ChapterType) -> MarkerSkipMode {
+        let (offered, automatic) = match marker_type {
+            ChapterType::Intro => (self.skip_intro, self.auto_skip_intro),
+            ChapterType::Credits => (self.skip_credits, self.auto_skip_credits),
+            ChapterType::Recap => (self.skip_recap, self.auto_skip_recap),
+            ChapterType::Preview => (self.skip_preview, self.auto_skip_preview),
+        };
+        match (offered, automatic) {
+            (false, _) => MarkerSkipMode::Off,
+            (true, false) => MarkerSkipMode::Button,
+            (true, true) => MarkerSkipMode::Auto,
+        }
+    }
+
+    pub fn set_skip_mode(&mut self, marker_type: ChapterType, mode: MarkerSkipMode) {
+        let (offered, automatic) = match marker_type {
+            ChapterType::Intro => (&mut self.skip_intro, &mut self.auto_skip_intro),
+            ChapterType::Credits => (&mut self.skip_credits, &mut self.auto_skip_credits),
+            ChapterType::Recap => (&mut self.skip_recap, &mut self.auto_skip_recap),
+            ChapterType::Preview => (&mut self.skip_preview, &mut self.auto_skip_preview),
+        };
+        *offered = mode != MarkerSkipMode::Off;
+        *automatic = mode == MarkerSkipMode::Auto;
+    }
+
+    pub fn replaygain_mode(&self) -> ReplayGainMode {
+        ReplayGainMode::parse(&self.replaygain).unwrap_or_default()
+    }
+
+    pub fn audio_effects(&self) -> AudioEffects {
+        let mut effects = AudioEffects {
+            preset: AudioPreset::parse(&self.audio_preset).unwrap_or_default(),
+            ..AudioEffects::default()
+        };
+        for (band, gain) in effects.equalizer.iter_mut().zip(&self.equalizer) {
+            *band = *gain;
+        }
+        effects
+    }
+
+    pub fn set_audio_effects(&mut self, effects: &AudioEffects) {
+        self.audio_preset = effects.preset.as_str().to_string();
+        self.equalizer = if effects.equalizer.iter().all(|gain| *gain == 0.0) {
+            Vec::new()
+        } else {
+            effects.equalizer.to_vec()
+        };
+    }
+
+    /// Where screenshots and clips are saved
+    pub fn capture_directory(&self) -> PathBuf {
+        if !self.capture_directory.is_empty() {
+            return PathBuf::from(&self.capture_directory);
+        }
+        dirs::picture_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(std::env::temp_dir)
+            .join("Reel")
+    }
 }
 
 impl NetworkConfig {
@@ -787,4 +965,26 @@ mod tests {
         assert_eq!(deserialized.playback.hardware_acceleration, true);
         assert_eq!(deserialized.network.max_retries, 3);
     }
+
+    #[test]
+    fn test_skip_mode_from_flags() {
+        let mut playback = PlaybackConfig::default();
+        assert_eq!(
+            playback.skip_mode(ChapterType::Intro),
+            MarkerSkipMode::Button
+        );
+
+        playback.auto_skip_recap = true;
+        assert_eq!(playback.skip_mode(ChapterType::Recap), MarkerSkipMode::Auto);
+
+        // Turning skipping off wins over auto-skip
+        playback.skip_recap = false;
+        assert_eq!(playback.skip_mode(ChapterType::Recap), MarkerSkipMode::Off);
+
+        playback.set_skip_mode(ChapterType::Credits, MarkerSkipMode::Auto);
+        assert_eq!(
+            playback.skip_mode(ChapterType::Credits),
+            MarkerSkipMode::Auto
+        );
+    }
 }
@@ -1,4 +1,391 @@
 // Platform-agnostic player traits
-// For now, we'll keep this minimal and add platform abstractions when needed
+// Every playback backend implements `MediaPlayer`, so the controller and UI never
+// need to know whether mpv or GStreamer is doing the work
 
-// Note: Currently unused but kept as placeholder for future platform abstractions
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+use crate::models::{AudioEffects, Chapter, CropRect, VideoAdjustments};
+use crate::player::UpscalingMode;
+
+/// How many unread events a slow subscriber may fall behind before it starts lagging
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerState {
+    Idle,
+    Loading,
+    Playing,
+    Paused,
+    Stopped,
+    Error,
+}
+
+/// Changes a backend pushes to its subscribers as they happen
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerEvent {
+    StateChanged(PlayerState),
+    PositionChanged {
+        position: Duration,
+        duration: Option<Duration>,
+    },
+    /// The end of the current media was reached
+    EndOfStream,
+    /// The media queued with `queue_next` took over without a gap, instead of an end of stream
+    NextMediaStarted,
+    /// Audio or subtitle tracks were added, removed or discovered
+    TracksChanged,
+    /// Embedded chapters were discovered or replaced
+    ChaptersChanged,
+    /// Network buffering progress; 100 means playback can continue
+    Buffering(u8),
+    Error(String),
+}
+
+/// Optional features a backend may or may not support
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PlayerCapabilities {
+    pub frame_step_backward: bool,
+    pub upscaling: bool,
+    pub playback_speed: bool,
+    pub subtitle_delay: bool,
+    pub audio_delay: bool,
+    pub external_subtitles: bool,
+    /// Can preload the next media and switch to it without a gap
+    pub gapless: bool,
+    pub replaygain: bool,
+    /// Aspect override, zoom, pan, crop and colour equalizer
+    pub video_adjustments: bool,
+    pub black_bar_detection: bool,
+    /// Can save the current frame to an image file
+    pub screenshots: bool,
+    /// Can list audio outputs and play on one other than the default
+    pub audio_output_selection: bool,
+    /// Can send compressed surround audio to the receiver untouched
+    pub audio_passthrough: bool,
+    /// Dynamic range compression presets and the equalizer
+    pub audio_effects: bool,
+    /// Can repeat a section of the media seamlessly
+    pub ab_loop: bool,
+}
+
+/// Which ReplayGain tags are used to even out loudness between tracks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReplayGainMode {
+    Off,
+    /// Every track at the same loudness
+    #[default]
+    Track,
+    /// Albums at the same loudness, keeping the differences between their tracks
+    Album,
+}
+
+impl ReplayGainMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReplayGainMode::Off => "off",
+            ReplayGainMode::Track => "track",
+            ReplayGainMode::Album => "album",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(ReplayGainMode::Off),
+            "track" => Some(ReplayGainMode::Track),
+            "album" => Some(ReplayGainMode::Album),
+            _ => None,
+        }
+    }
+}
+
+/// A sound card or sound server sink audio can be played on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutputDevice {
+    /// Identifier passed back to `set_audio_device`
+    pub name: String,
+    /// Human readable name for menus
+    pub description: String,
+}
+
+/// Diagnostics of the playing stream for the statistics overlay.
+/// Backends leave out what they can't tell.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlaybackStats {
+    pub container: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    /// Decoded video size in pixels
+    pub resolution: Option<(u32, u32)>,
+    pub frame_rate: Option<f64>,
+    /// Video decoder, and the hardware decoding API it uses if any
+    pub decoder: Option<String>,
+    pub hwdec: Option<String>,
+    pub dropped_frames: Option<u64>,
+    /// Frames shown later than they should have been; only mpv counts these
+    pub delayed_frames: Option<u64>,
+    /// Media buffered ahead of the playback position
+    pub cache_duration: Option<Duration>,
+    pub buffer_percent: Option<u8>,
+    /// Rate the stream arrives at, in bytes per second
+    pub network_bytes_per_second: Option<u64>,
+}
+
+/// Current state plus the channel its transitions and other events are broadcast on.
+/// Cheap to clone, so backend callbacks can hold their own copy.
+#[derive(Clone)]
+pub struct PlayerEventSource {
+    state: Arc<Mutex<PlayerState>>,
+    sender: broadcast::Sender<PlayerEvent>,
+}
+
+impl Default for PlayerEventSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlayerEventSource {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            state: Arc::new(Mutex::new(PlayerState::Idle)),
+            sender,
+        }
+    }
+
+    pub fn state(&self) -> PlayerState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// Update the state, notifying subscribers only if it actually changed
+    pub fn set_state(&self, state: PlayerState) {
+        let changed = {
+            let mut current = self.state.lock().unwrap();
+            let changed = *current != state;
+            *current = state.clone();
+            changed
+        };
+        if changed {
+            self.emit(PlayerEvent::StateChanged(state));
+        }
+    }
+
+    pub fn emit(&self, event: PlayerEvent) {
+        // Sending only fails when nobody is subscribed, which is fine
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// A playback backend. Implementations are driven from the GTK main thread,
+/// so futures are not required to be `Send`.
+#[async_trait(?Send)]
+pub trait MediaPlayer {
+    /// Human readable backend name for logs
+    fn backend_name(&self) -> &'static str;
+
+    /// Which optional features this backend supports
+    fn capabilities(&self) -> PlayerCapabilities;
+
+    /// Receive state changes, position updates, end of stream, track changes and buffering
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent>;
+
+    fn create_video_widget(&self) -> gtk4::Widget;
+
+    async fn load_media(&self, url: &str) -> Result<()>;
+    async fn play(&self) -> Result<()>;
+    async fn pause(&self) -> Result<()>;
+    async fn stop(&self) -> Result<()>;
+    async fn seek(&self, position: Duration) -> Result<()>;
+
+    async fn get_position(&self) -> Option<Duration>;
+    async fn get_duration(&self) -> Option<Duration>;
+    async fn get_state(&self) -> PlayerState;
+    async fn get_video_dimensions(&self) -> Option<(i32, i32)>;
+    /// Skip video decoding and output entirely, for music
+    async fn set_audio_only(&self, audio_only: bool) -> Result<()>;
+
+    /// Set volume (0.0 to 1.0)
+    async fn set_volume(&self, volume: f64) -> Result<()>;
+    async fn toggle_mute(&self) -> Result<()>;
+    async fn is_muted(&self) -> bool;
+
+    async fn get_audio_tracks(&self) -> Vec<(i32, String)>;
+    async fn get_subtitle_tracks(&self) -> Vec<(i32, String)>;
+    async fn set_audio_track(&self, track_index: i32) -> Result<()>;
+    async fn set_subtitle_track(&self, track_index: i32) -> Result<()>;
+    async fn get_current_audio_track(&self) -> i32;
+    async fn get_current_subtitle_track(&self) -> i32;
+    async fn cycle_audio_track(&self) -> Result<()>;
+    async fn cycle_subtitle_track(&self) -> Result<()>;
+
+    /// Load an external subtitle file as an extra track, optionally switching to it.
+    /// GStreamer's playbin holds a single external subtitle at a time, so there a
+    /// selected file replaces the previous one and an unselected one is skipped.
+    async fn add_subtitle(
+        &self,
+        url: &str,
+        title: &str,
+        language: Option<&str>,
+        select: bool,
+    ) -> Result<()>;
+
+    /// Shift subtitle timing; positive values show subtitles later
+    async fn set_subtitle_delay(&self, delay_ms: i64) -> Result<()>;
+    /// Shift audio timing; positive values play audio later
+    async fn set_audio_delay(&self, delay_ms: i64) -> Result<()>;
+
+    async fn set_playback_speed(&self, speed: f64) -> Result<()>;
+    async fn get_playback_speed(&self) -> f64;
+
+    async fn frame_step_forward(&self) -> Result<()>;
+
+    /// Chapters embedded in the current media, sorted by start time
+    async fn get_chapters(&self) -> Vec<Chapter>;
+
+    async fn seek_to_chapter(&self, index: usize) -> Result<()> {
+        let chapters = self.get_chapters().await;
+        let chapter = chapters
+            .get(index)
+            .ok_or_else(|| anyhow::anyhow!("No chapter at index {}", index))?;
+        self.seek(chapter.start_time).await
+    }
+
+    async fn frame_step_backward(&self) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Backward frame stepping is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    async fn set_upscaling_mode(&self, _mode: UpscalingMode) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Upscaling mode is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Preload media to start the moment the current one ends, or drop it with `None`.
+    /// The switch is reported as `NextMediaStarted` instead of `EndOfStream`.
+    async fn queue_next(&self, _url: Option<&str>) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Gapless playback is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    async fn set_replaygain(&self, _mode: ReplayGainMode) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "ReplayGain is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Apply aspect override, zoom, pan, crop and colour settings, replacing the previous ones
+    async fn set_video_adjustments(&self, _adjustments: VideoAdjustments) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Video adjustments are not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Repeat the part between the two positions until cleared with `None`
+    async fn set_ab_loop(&self, _range: Option<(Duration, Duration)>) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "A-B loops are not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Look for black bars in the current frame; `None` when there are none
+    async fn detect_black_bars(&self) -> Result<Option<CropRect>> {
+        Err(anyhow::anyhow!(
+            "Black bar detection is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Save the current frame at source resolution as a PNG at `path`,
+    /// with the visible subtitles drawn on it if `include_subtitles`
+    async fn screenshot(&self, _path: &Path, _include_subtitles: bool) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Screenshots are not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Snapshot of decoding, dropped frames and buffering for diagnosing stutter
+    async fn get_stats(&self) -> Result<PlaybackStats> {
+        Err(anyhow::anyhow!(
+            "Playback statistics are not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Audio outputs available to play on, not including the system default
+    async fn get_audio_devices(&self) -> Result<Vec<AudioOutputDevice>> {
+        Err(anyhow::anyhow!(
+            "Audio output selection is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Play on the output with this `AudioOutputDevice::name`, or the system default if empty
+    async fn set_audio_device(&self, _name: &str) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Audio output selection is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Apply a dynamic range preset and equalizer, replacing the previous ones
+    async fn set_audio_effects(&self, _effects: AudioEffects) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Audio effects are not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+
+    /// Pass audio in these codecs (ac3, eac3, dts, dts-hd, truehd) to the output
+    /// undecoded, for a receiver to decode; empty decodes everything
+    async fn set_audio_passthrough(&self, _codecs: &[String]) -> Result<()> {
+        Err(anyhow::anyhow!(
+            "Audio passthrough is not supported by the {} backend",
+            self.backend_name()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_state_only_notifies_on_change() {
+        let source = PlayerEventSource::new();
+        let mut events = source.subscribe();
+
+        source.set_state(PlayerState::Playing);
+        source.set_state(PlayerState::Playing);
+        source.set_state(PlayerState::Paused);
+
+        assert_eq!(
+            events.try_recv().unwrap(),
+            PlayerEvent::StateChanged(PlayerState::Playing)
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            PlayerEvent::StateChanged(PlayerState::Paused)
+        );
+        assert!(events.try_recv().is_err());
+        assert_eq!(source.state(), PlayerState::Paused);
+    }
+}
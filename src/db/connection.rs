@@ -74,6 +74,34 @@ impl Database {
         })
     }
 
+    /// A fresh in-memory database with every migration applied, for tests
+    #[cfg(test)]
+    pub async fn in_memory() -> Result<Self> {
+        use sea_orm::{ConnectionTrait, Statement};
+
+        // Each connection to :memory: opens its own empty database, so only keep one
+        let mut opt = ConnectOptions::new("sqlite::memory:");
+        opt.max_connections(1)
+            .min_connections(1)
+            .sqlx_logging(false);
+        let connection = SeaOrmDatabase::connect(opt)
+            .await
+            .context("Failed to open in-memory database")?;
+        connection
+            .execute(Statement::from_string(
+                sea_orm::DatabaseBackend::Sqlite,
+                "PRAGMA foreign_keys = ON",
+            ))
+            .await
+            .context("Failed to enable foreign key constraints")?;
+
+        let database = Self {
+            connection: Arc::new(connection),
+        };
+        database.migrate().await?;
+        Ok(database)
+    }
+
     /// Get a clone of the database connection
     pub fn get_connection(&self) -> DatabaseConnection {
         self.connection.clone()
@@ -1,7 +1,10 @@
 pub mod libraries;
 pub mod media_items;
 pub mod offline_content;
+pub mod play_queue;
+pub mod play_queue_items;
 pub mod playback_progress;
+pub mod show_preferences;
 pub mod sources;
 pub mod sync_status;
 
@@ -10,10 +13,20 @@ pub use libraries::{ActiveModel as LibraryActiveModel, Entity as Library, Model
 pub use media_items::{
     ActiveModel as MediaItemActiveModel, Entity as MediaItem, Model as MediaItemModel,
 };
+pub use play_queue::{
+    ActiveModel as PlayQueueActiveModel, Entity as PlayQueue, Model as PlayQueueModel,
+};
+pub use play_queue_items::{
+    ActiveModel as PlayQueueItemActiveModel, Entity as PlayQueueItem, Model as PlayQueueItemModel,
+};
 pub use playback_progress::{
     ActiveModel as PlaybackProgressActiveModel, Entity as PlaybackProgress,
     Model as PlaybackProgressModel,
 };
+pub use show_preferences::{
+    ActiveModel as ShowPreferencesActiveModel, Entity as ShowPreferences,
+    Model as ShowPreferencesModel,
+};
 pub use sources::{ActiveModel as SourceActiveModel, Entity as Source, Model as SourceModel};
 pub use sync_status::{
     ActiveModel as SyncStatusActiveModel, Entity as SyncStatus, Model as SyncStatusModel,
@@ -0,0 +1,21 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// An entry in the play queue, keyed by its position in play order
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "play_queue_items")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub position: i32,
+    pub media_item_id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub media_type: String,
+    pub duration_ms: Option<i64>,
+    pub original_position: Option<i32>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
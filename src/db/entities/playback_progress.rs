@@ -14,6 +14,13 @@ pub struct Model {
     pub view_count: i32,
     pub last_watched_at: Option<DateTime>,
     pub updated_at: DateTime,
+    /// Subtitle timing offset chosen by the user for this item
+    pub subtitle_delay_ms: i64,
+    /// Audio timing offset chosen by the user for this item
+    pub audio_delay_ms: i64,
+    /// Aspect, zoom, crop and colour settings chosen by the user for this item
+    #[sea_orm(column_type = "Json")]
+    pub video_adjustments: Option<Json>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
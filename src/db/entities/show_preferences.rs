@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{ChapterType, MarkerSkipMode};
+
+/// Playback choices remembered for a whole show rather than a single episode
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "show_preferences")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub show_id: String,
+    pub intro_skip_mode: Option<String>,
+    pub credits_skip_mode: Option<String>,
+    pub recap_skip_mode: Option<String>,
+    pub preview_skip_mode: Option<String>,
+    pub updated_at: DateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// The skip mode chosen for a marker type, if the show overrides the global setting
+    pub fn skip_mode(&self, marker_type: ChapterType) -> Option<MarkerSkipMode> {
+        let value = match marker_type {
+            ChapterType::Intro => &self.intro_skip_mode,
+            ChapterType::Credits => &self.credits_skip_mode,
+            ChapterType::Recap => &self.recap_skip_mode,
+            ChapterType::Preview => &self.preview_skip_mode,
+        };
+        value.as_deref().and_then(MarkerSkipMode::parse)
+    }
+}
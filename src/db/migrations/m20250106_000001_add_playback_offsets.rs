@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add per-item subtitle delay to playback_progress table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlaybackProgress::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(PlaybackProgress::SubtitleDelayMs)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Add per-item audio delay to playback_progress table
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlaybackProgress::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(PlaybackProgress::AudioDelayMs)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Remove the added columns
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlaybackProgress::Table)
+                    .drop_column(PlaybackProgress::SubtitleDelayMs)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlaybackProgress::Table)
+                    .drop_column(PlaybackProgress::AudioDelayMs)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum PlaybackProgress {
+    Table,
+    SubtitleDelayMs,
+    AudioDelayMs,
+}
@@ -0,0 +1,56 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create show_preferences table; a NULL skip mode follows the global setting
+        manager
+            .create_table(
+                Table::create()
+                    .table(ShowPreferences::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ShowPreferences::ShowId)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(ShowPreferences::IntroSkipMode).string())
+                    .col(ColumnDef::new(ShowPreferences::CreditsSkipMode).string())
+                    .col(ColumnDef::new(ShowPreferences::RecapSkipMode).string())
+                    .col(ColumnDef::new(ShowPreferences::PreviewSkipMode).string())
+                    .col(
+                        ColumnDef::new(ShowPreferences::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ShowPreferences::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ShowPreferences {
+    Table,
+    ShowId,
+    IntroSkipMode,
+    CreditsSkipMode,
+    RecapSkipMode,
+    PreviewSkipMode,
+    UpdatedAt,
+}
@@ -0,0 +1,110 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Create play_queue table; a single row holds the queue's modes and position
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlayQueue::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PlayQueue::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(PlayQueue::CurrentIndex).integer())
+                    .col(
+                        ColumnDef::new(PlayQueue::Shuffle)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(PlayQueue::RepeatMode)
+                            .string()
+                            .not_null()
+                            .default("off"),
+                    )
+                    .col(
+                        ColumnDef::new(PlayQueue::UpdatedAt)
+                            .timestamp()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Create play_queue_items table. Items are copied rather than referenced so
+        // the queue survives a library resync.
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlayQueueItems::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(PlayQueueItems::Position)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(PlayQueueItems::MediaItemId)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PlayQueueItems::Title).string().not_null())
+                    .col(ColumnDef::new(PlayQueueItems::Subtitle).string())
+                    .col(
+                        ColumnDef::new(PlayQueueItems::MediaType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(PlayQueueItems::DurationMs).big_integer())
+                    .col(ColumnDef::new(PlayQueueItems::OriginalPosition).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PlayQueueItems::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(PlayQueue::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum PlayQueue {
+    Table,
+    Id,
+    CurrentIndex,
+    Shuffle,
+    RepeatMode,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum PlayQueueItems {
+    Table,
+    Position,
+    MediaItemId,
+    Title,
+    Subtitle,
+    MediaType,
+    DurationMs,
+    OriginalPosition,
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Add per-item picture settings to playback_progress table; NULL leaves the video as is
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlaybackProgress::Table)
+                    .add_column_if_not_exists(
+                        ColumnDef::new(PlaybackProgress::VideoAdjustments).json(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(PlaybackProgress::Table)
+                    .drop_column(PlaybackProgress::VideoAdjustments)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum PlaybackProgress {
+    Table,
+    VideoAdjustments,
+}
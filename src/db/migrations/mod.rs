@@ -5,6 +5,10 @@ mod m20250102_000001_add_episode_fields;
 mod m20250103_000001_add_source_connections;
 mod m20250104_000001_add_sync_total_items;
 mod m20250105_000001_add_connection_tracking;
+mod m20250106_000001_add_playback_offsets;
+mod m20250107_000001_add_show_preferences;
+mod m20250108_000001_add_play_queue;
+mod m20250109_000001_add_video_adjustments;
 
 pub struct Migrator;
 
@@ -17,6 +21,10 @@ impl MigratorTrait for Migrator {
             Box::new(m20250103_000001_add_source_connections::Migration),
             Box::new(m20250104_000001_add_sync_total_items::Migration),
             Box::new(m20250105_000001_add_connection_tracking::Migration),
+            Box::new(m20250106_000001_add_playback_offsets::Migration),
+            Box::new(m20250107_000001_add_show_preferences::Migration),
+            Box::new(m20250108_000001_add_play_queue::Migration),
+            Box::new(m20250109_000001_add_video_adjustments::Migration),
         ]
     }
 }
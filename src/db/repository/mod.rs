@@ -1,6 +1,8 @@
 pub mod library_repository;
 pub mod media_repository;
+pub mod play_queue_repository;
 pub mod playback_repository;
+pub mod show_preferences_repository;
 pub mod source_repository;
 pub mod sync_repository;
 
@@ -49,5 +51,7 @@ impl BaseRepository {
 // Re-export specific repositories
 pub use library_repository::{LibraryRepository, LibraryRepositoryImpl};
 pub use media_repository::{MediaRepository, MediaRepositoryImpl};
+pub use play_queue_repository::{PlayQueueRepository, PlayQueueRepositoryImpl};
 pub use playback_repository::{PlaybackRepository, PlaybackRepositoryImpl};
+pub use show_preferences_repository::{ShowPreferencesRepository, ShowPreferencesRepositoryImpl};
 pub use source_repository::SourceRepositoryImpl;
@@ -0,0 +1,128 @@
+use super::{BaseRepository, Repository};
+use crate::db::entities::{
+    PlayQueue, PlayQueueActiveModel, PlayQueueItem, PlayQueueItemActiveModel, PlayQueueItemModel,
+    PlayQueueModel, play_queue_items,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use sea_orm::{
+    ActiveModelTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryOrder, Set,
+    TransactionTrait,
+};
+use std::sync::Arc;
+
+/// ID of the single play queue state row
+const QUEUE_STATE_ID: i32 = 1;
+
+/// Repository trait for the persisted play queue
+#[async_trait]
+pub trait PlayQueueRepository: Repository<PlayQueueItemModel> {
+    /// Load the queue state and its items in play order
+    async fn load_queue(&self) -> Result<(Option<PlayQueueModel>, Vec<PlayQueueItemModel>)>;
+
+    /// Replace the stored queue with new state and items
+    async fn replace_queue(
+        &self,
+        state: PlayQueueModel,
+        items: Vec<PlayQueueItemModel>,
+    ) -> Result<()>;
+}
+
+#[derive(Debug)]
+pub struct PlayQueueRepositoryImpl {
+    base: BaseRepository,
+}
+
+impl PlayQueueRepositoryImpl {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self {
+            base: BaseRepository::new(db),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository<PlayQueueItemModel> for PlayQueueRepositoryImpl {
+    type Entity = PlayQueueItem;
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<PlayQueueItemModel>> {
+        let position = id.parse::<i32>().unwrap_or(0);
+        Ok(PlayQueueItem::find_by_id(position)
+            .one(self.base.db.as_ref())
+            .await?)
+    }
+
+    async fn find_all(&self) -> Result<Vec<PlayQueueItemModel>> {
+        Ok(PlayQueueItem::find()
+            .order_by_asc(play_queue_items::Column::Position)
+            .all(self.base.db.as_ref())
+            .await?)
+    }
+
+    async fn insert(&self, entity: PlayQueueItemModel) -> Result<PlayQueueItemModel> {
+        let active_model: PlayQueueItemActiveModel = entity.into();
+        Ok(active_model.insert(self.base.db.as_ref()).await?)
+    }
+
+    async fn update(&self, entity: PlayQueueItemModel) -> Result<PlayQueueItemModel> {
+        let active_model: PlayQueueItemActiveModel = entity.into();
+        Ok(active_model.update(self.base.db.as_ref()).await?)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        let position = id.parse::<i32>().unwrap_or(0);
+        PlayQueueItem::delete_by_id(position)
+            .exec(self.base.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<u64> {
+        Ok(PlayQueueItem::find().count(self.base.db.as_ref()).await?)
+    }
+}
+
+#[async_trait]
+impl PlayQueueRepository for PlayQueueRepositoryImpl {
+    async fn load_queue(&self) -> Result<(Option<PlayQueueModel>, Vec<PlayQueueItemModel>)> {
+        let state = PlayQueue::find_by_id(QUEUE_STATE_ID)
+            .one(self.base.db.as_ref())
+            .await?;
+        let items = self.find_all().await?;
+        Ok((state, items))
+    }
+
+    async fn replace_queue(
+        &self,
+        state: PlayQueueModel,
+        items: Vec<PlayQueueItemModel>,
+    ) -> Result<()> {
+        // Positions shift on every edit, so rewrite the whole queue at once
+        let txn = self.base.db.begin().await?;
+
+        PlayQueueItem::delete_many().exec(&txn).await?;
+        if !items.is_empty() {
+            PlayQueueItem::insert_many(
+                items
+                    .into_iter()
+                    .map(PlayQueueItemActiveModel::from)
+                    .collect::<Vec<_>>(),
+            )
+            .exec(&txn)
+            .await?;
+        }
+
+        PlayQueue::delete_by_id(QUEUE_STATE_ID).exec(&txn).await?;
+        let active_state = PlayQueueActiveModel {
+            id: Set(QUEUE_STATE_ID),
+            current_index: Set(state.current_index),
+            shuffle: Set(state.shuffle),
+            repeat_mode: Set(state.repeat_mode),
+            updated_at: Set(chrono::Utc::now().naive_utc()),
+        };
+        active_state.insert(&txn).await?;
+
+        txn.commit().await?;
+        Ok(())
+    }
+}
@@ -39,6 +39,23 @@ pub trait PlaybackRepository: Repository<PlaybackProgressModel> {
         duration_ms: i64,
     ) -> Result<PlaybackProgressModel>;
 
+    /// Update or create the subtitle and audio delays for a media item
+    async fn upsert_sync_offsets(
+        &self,
+        media_id: &str,
+        user_id: Option<&str>,
+        subtitle_delay_ms: i64,
+        audio_delay_ms: i64,
+    ) -> Result<PlaybackProgressModel>;
+
+    /// Update or create the picture settings for a media item
+    async fn upsert_video_adjustments(
+        &self,
+        media_id: &str,
+        user_id: Option<&str>,
+        video_adjustments: Option<serde_json::Value>,
+    ) -> Result<PlaybackProgressModel>;
+
     /// Mark an item as watched
     async fn mark_watched(&self, media_id: &str, user_id: Option<&str>) -> Result<()>;
 
@@ -95,6 +112,9 @@ impl Repository<PlaybackProgressModel> for PlaybackRepositoryImpl {
             view_count: Set(entity.view_count),
             last_watched_at: Set(entity.last_watched_at),
             updated_at: Set(chrono::Utc::now().naive_utc()),
+            subtitle_delay_ms: Set(entity.subtitle_delay_ms),
+            audio_delay_ms: Set(entity.audio_delay_ms),
+            video_adjustments: Set(entity.video_adjustments.clone()),
         };
 
         Ok(active_model.insert(self.base.db.as_ref()).await?)
@@ -209,6 +229,93 @@ impl PlaybackRepository for PlaybackRepositoryImpl {
                 view_count: Set(0),
                 last_watched_at: Set(Some(now)),
                 updated_at: Set(now),
+                subtitle_delay_ms: Set(0),
+                audio_delay_ms: Set(0),
+                video_adjustments: Set(None),
+            };
+
+            Ok(active_model.insert(self.base.db.as_ref()).await?)
+        }
+    }
+
+    async fn upsert_sync_offsets(
+        &self,
+        media_id: &str,
+        user_id: Option<&str>,
+        subtitle_delay_ms: i64,
+        audio_delay_ms: i64,
+    ) -> Result<PlaybackProgressModel> {
+        let existing = if let Some(uid) = user_id {
+            self.find_by_media_and_user(media_id, uid).await?
+        } else {
+            self.find_by_media_id(media_id).await?
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+
+        if let Some(progress) = existing {
+            let mut active_model: PlaybackProgressActiveModel = progress.into();
+            active_model.subtitle_delay_ms = Set(subtitle_delay_ms);
+            active_model.audio_delay_ms = Set(audio_delay_ms);
+            active_model.updated_at = Set(now);
+
+            Ok(active_model.update(self.base.db.as_ref()).await?)
+        } else {
+            // Not played before, so there is no progress to keep yet
+            let active_model = PlaybackProgressActiveModel {
+                id: sea_orm::NotSet,
+                media_id: Set(media_id.to_string()),
+                user_id: Set(user_id.map(|s| s.to_string())),
+                position_ms: Set(0),
+                duration_ms: Set(0),
+                watched: Set(false),
+                view_count: Set(0),
+                last_watched_at: Set(None),
+                updated_at: Set(now),
+                subtitle_delay_ms: Set(subtitle_delay_ms),
+                audio_delay_ms: Set(audio_delay_ms),
+                video_adjustments: Set(None),
+            };
+
+            Ok(active_model.insert(self.base.db.as_ref()).await?)
+        }
+    }
+
+    async fn upsert_video_adjustments(
+        &self,
+        media_id: &str,
+        user_id: Option<&str>,
+        video_adjustments: Option<serde_json::Value>,
+    ) -> Result<PlaybackProgressModel> {
+        let existing = if let Some(uid) = user_id {
+            self.find_by_media_and_user(media_id, uid).await?
+        } else {
+            self.find_by_media_id(media_id).await?
+        };
+
+        let now = chrono::Utc::now().naive_utc();
+
+        if let Some(progress) = existing {
+            let mut active_model: PlaybackProgressActiveModel = progress.into();
+            active_model.video_adjustments = Set(video_adjustments);
+            active_model.updated_at = Set(now);
+
+            Ok(active_model.update(self.base.db.as_ref()).await?)
+        } else {
+            // Not played before, so there is no progress to keep yet
+            let active_model = PlaybackProgressActiveModel {
+                id: sea_orm::NotSet,
+                media_id: Set(media_id.to_string()),
+                user_id: Set(user_id.map(|s| s.to_string())),
+                position_ms: Set(0),
+                duration_ms: Set(0),
+                watched: Set(false),
+                view_count: Set(0),
+                last_watched_at: Set(None),
+                updated_at: Set(now),
+                subtitle_delay_ms: Set(0),
+                audio_delay_ms: Set(0),
+                video_adjustments: Set(video_adjustments),
             };
 
             Ok(active_model.insert(self.base.db.as_ref()).await?)
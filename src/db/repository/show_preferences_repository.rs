@@ -0,0 +1,118 @@
+use super::{BaseRepository, Repository};
+use crate::db::entities::{ShowPreferences, ShowPreferencesActiveModel, ShowPreferencesModel};
+use crate::models::{ChapterType, MarkerSkipMode};
+use anyhow::Result;
+use async_trait::async_trait;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, PaginatorTrait, Set};
+use std::sync::Arc;
+
+/// Repository trait for per-show preferences
+#[async_trait]
+pub trait ShowPreferencesRepository: Repository<ShowPreferencesModel> {
+    /// Remember how a marker type should be skipped for a show
+    async fn set_skip_mode(
+        &self,
+        show_id: &str,
+        marker_type: ChapterType,
+        mode: MarkerSkipMode,
+    ) -> Result<ShowPreferencesModel>;
+}
+
+#[derive(Debug)]
+pub struct ShowPreferencesRepositoryImpl {
+    base: BaseRepository,
+}
+
+impl ShowPreferencesRepositoryImpl {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self {
+            base: BaseRepository::new(db),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository<ShowPreferencesModel> for ShowPreferencesRepositoryImpl {
+    type Entity = ShowPreferences;
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<ShowPreferencesModel>> {
+        Ok(ShowPreferences::find_by_id(id.to_string())
+            .one(self.base.db.as_ref())
+            .await?)
+    }
+
+    async fn find_all(&self) -> Result<Vec<ShowPreferencesModel>> {
+        Ok(ShowPreferences::find().all(self.base.db.as_ref()).await?)
+    }
+
+    async fn insert(&self, entity: ShowPreferencesModel) -> Result<ShowPreferencesModel> {
+        let active_model = ShowPreferencesActiveModel {
+            show_id: Set(entity.show_id),
+            intro_skip_mode: Set(entity.intro_skip_mode),
+            credits_skip_mode: Set(entity.credits_skip_mode),
+            recap_skip_mode: Set(entity.recap_skip_mode),
+            preview_skip_mode: Set(entity.preview_skip_mode),
+            updated_at: Set(chrono::Utc::now().naive_utc()),
+        };
+
+        Ok(active_model.insert(self.base.db.as_ref()).await?)
+    }
+
+    async fn update(&self, entity: ShowPreferencesModel) -> Result<ShowPreferencesModel> {
+        let mut active_model: ShowPreferencesActiveModel = entity.into();
+        active_model.updated_at = Set(chrono::Utc::now().naive_utc());
+
+        Ok(active_model.update(self.base.db.as_ref()).await?)
+    }
+
+    async fn delete(&self, id: &str) -> Result<()> {
+        ShowPreferences::delete_by_id(id.to_string())
+            .exec(self.base.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn count(&self) -> Result<u64> {
+        Ok(ShowPreferences::find().count(self.base.db.as_ref()).await?)
+    }
+}
+
+#[async_trait]
+impl ShowPreferencesRepository for ShowPreferencesRepositoryImpl {
+    async fn set_skip_mode(
+        &self,
+        show_id: &str,
+        marker_type: ChapterType,
+        mode: MarkerSkipMode,
+    ) -> Result<ShowPreferencesModel> {
+        let existing = self.find_by_id(show_id).await?;
+        let is_new = existing.is_none();
+
+        let mut active_model: ShowPreferencesActiveModel = match existing {
+            Some(preferences) => preferences.into(),
+            None => ShowPreferencesActiveModel {
+                show_id: Set(show_id.to_string()),
+                intro_skip_mode: Set(None),
+                credits_skip_mode: Set(None),
+                recap_skip_mode: Set(None),
+                preview_skip_mode: Set(None),
+                updated_at: Set(chrono::Utc::now().naive_utc()),
+            },
+        };
+
+        let value = Set(Some(mode.as_str().to_string()));
+        match marker_type {
+            ChapterType::Intro => active_model.intro_skip_mode = value,
+            ChapterType::Credits => active_model.credits_skip_mode = value,
+            ChapterType::Recap => active_model.recap_skip_mode = value,
+            ChapterType::Preview => active_model.preview_skip_mode = value,
+        }
+        active_model.updated_at = Set(chrono::Utc::now().naive_utc());
+
+        if is_new {
+            Ok(active_model.insert(self.base.db.as_ref()).await?)
+        } else {
+            Ok(active_model.update(self.base.db.as_ref()).await?)
+        }
+    }
+}
@@ -0,0 +1,170 @@
+pub const EQUALIZER_BANDS: usize = 10;
+
+/// Centre frequencies of the equalizer bands in Hz, an octave apart
+pub const EQUALIZER_FREQUENCIES: [u32; EQUALIZER_BANDS] =
+    [31, 62, 125, 250, 500, 1000, 2000, 4000, 8000, 16000];
+
+/// Largest boost or cut of an equalizer band in dB
+pub const EQUALIZER_MAX_GAIN: f64 = 12.0;
+
+/// Presence boost added to the 1k, 2k and 4k bands for dialogue, in dB
+const DIALOGUE_BOOST: [(usize, f64); 3] = [(5, 3.0), (6, 6.0), (7, 3.0)];
+
+/// Dynamic range processing for listening at low volume
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AudioPreset {
+    #[default]
+    Off,
+    /// Quiet explosions and raise quiet passages
+    Night,
+    /// Bring speech forward, with lighter compression
+    DialogueBoost,
+}
+
+/// Compressor settings of a preset, in the linear units both ffmpeg and GStreamer take
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compression {
+    /// Level above which the signal is compressed, 0..1
+    pub threshold: f64,
+    /// How many dB of input above the threshold make one dB of output
+    pub ratio: f64,
+    /// Gain applied afterwards to make up for the lost loudness
+    pub makeup: f64,
+}
+
+impl AudioPreset {
+    pub const ALL: [AudioPreset; 3] = [
+        AudioPreset::Off,
+        AudioPreset::Night,
+        AudioPreset::DialogueBoost,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioPreset::Off => "off",
+            AudioPreset::Night => "night",
+            AudioPreset::DialogueBoost => "dialogue",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(AudioPreset::Off),
+            "night" => Some(AudioPreset::Night),
+            "dialogue" => Some(AudioPreset::DialogueBoost),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioPreset::Off => "Off",
+            AudioPreset::Night => "Night",
+            AudioPreset::DialogueBoost => "Dialogue Boost",
+        }
+    }
+
+    pub fn compression(&self) -> Option<Compression> {
+        match self {
+            AudioPreset::Off => None,
+            // -24 dB at 6:1, made up by 12 dB
+            AudioPreset::Night => Some(Compression {
+                threshold: 0.063,
+                ratio: 6.0,
+                makeup: 4.0,
+            }),
+            // -18 dB at 3:1, made up by 6 dB
+            AudioPreset::DialogueBoost => Some(Compression {
+                threshold: 0.125,
+                ratio: 3.0,
+                makeup: 2.0,
+            }),
+        }
+    }
+}
+
+/// Sound processing remembered for all playback
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AudioEffects {
+    pub preset: AudioPreset,
+    /// Gain of each equalizer band in dB, 0 leaving it unchanged
+    pub equalizer: [f64; EQUALIZER_BANDS],
+}
+
+impl AudioEffects {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Equalizer gains with the preset's own boost added
+    pub fn band_gains(&self) -> [f64; EQUALIZER_BANDS] {
+        let mut gains = self.equalizer;
+        if self.preset == AudioPreset::DialogueBoost {
+            for (band, boost) in DIALOGUE_BOOST {
+                gains[band] += boost;
+            }
+        }
+        gains.map(|gain| gain.clamp(-EQUALIZER_MAX_GAIN, EQUALIZER_MAX_GAIN))
+    }
+
+    /// In the form mpv's `af` takes, empty when nothing is to be done
+    pub fn to_mpv_filter(&self) -> String {
+        let mut filters: Vec<String> = self
+            .band_gains()
+            .iter()
+            .zip(EQUALIZER_FREQUENCIES)
+            .filter(|(gain, _)| gain.abs() >= 0.05)
+            .map(|(gain, frequency)| format!("equalizer=f={}:t=o:w=1:g={:.1}", frequency, gain))
+            .collect();
+        if let Some(compression) = self.preset.compression() {
+            filters.push(format!(
+                "acompressor=threshold={}:ratio={}:attack=10:release=250:makeup={}",
+                compression.threshold, compression.ratio, compression.makeup
+            ));
+            // The make up gain would otherwise clip the loudest peaks
+            filters.push("alimiter=limit=0.95:level=false".to_string());
+        }
+
+        if filters.is_empty() {
+            String::new()
+        } else {
+            format!("lavfi=[{}]", filters.join(","))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mpv_filter() {
+        assert_eq!(AudioEffects::default().to_mpv_filter(), "");
+
+        let mut effects = AudioEffects::default();
+        effects.equalizer[0] = 4.0;
+        assert_eq!(
+            effects.to_mpv_filter(),
+            "lavfi=[equalizer=f=31:t=o:w=1:g=4.0]"
+        );
+
+        effects.preset = AudioPreset::Night;
+        let filter = effects.to_mpv_filter();
+        assert!(filter.starts_with("lavfi=[equalizer=f=31"));
+        assert!(filter.contains("acompressor=threshold=0.063:ratio=6"));
+        assert!(filter.ends_with("alimiter=limit=0.95:level=false]"));
+    }
+
+    #[test]
+    fn test_dialogue_boost_adds_to_equalizer() {
+        let mut effects = AudioEffects {
+            preset: AudioPreset::DialogueBoost,
+            ..AudioEffects::default()
+        };
+        effects.equalizer[6] = 10.0;
+        let gains = effects.band_gains();
+        assert_eq!(gains[5], 3.0);
+        assert_eq!(gains[6], EQUALIZER_MAX_GAIN);
+        assert_eq!(gains[0], 0.0);
+    }
+}
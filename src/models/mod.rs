@@ -1,14 +1,22 @@
+pub mod audio_effects;
 pub mod auth_provider;
 pub mod connection;
 mod identifiers;
+pub mod play_queue;
 pub mod playlist_context;
+pub mod trickplay;
+pub mod video_adjustments;
 
+pub use audio_effects::{AudioEffects, AudioPreset};
 pub use auth_provider::{
     AuthProvider, ConnectionInfo, NetworkAuthType, NetworkCredentialData, Source, SourceType,
 };
 pub use connection::{ServerConnection, ServerConnections};
 pub use identifiers::{BackendId, LibraryId, MediaItemId, ProviderId, ShowId, SourceId, UserId};
+pub use play_queue::{PlayQueue, QueueItem, RepeatMode};
 pub use playlist_context::{EpisodeInfo, PlaylistContext};
+pub use trickplay::{Trickplay, TrickplayFrame};
+pub use video_adjustments::{CropRect, VideoAdjustments};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -122,14 +130,21 @@ pub struct Episode {
     pub credits_marker: Option<ChapterMarker>, // End credits marker
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChapterMarker {
     pub start_time: Duration,
     pub end_time: Duration,
     pub marker_type: ChapterType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl ChapterMarker {
+    /// Whether a playback position falls inside this marker
+    pub fn contains(&self, position: Duration) -> bool {
+        position >= self.start_time && position < self.end_time
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ChapterType {
     Intro,
     Credits,
@@ -137,6 +152,75 @@ pub enum ChapterType {
     Preview,
 }
 
+impl ChapterType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChapterType::Intro => "intro",
+            ChapterType::Credits => "credits",
+            ChapterType::Recap => "recap",
+            ChapterType::Preview => "preview",
+        }
+    }
+
+    /// Name shown on skip buttons
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChapterType::Intro => "Intro",
+            ChapterType::Credits => "Credits",
+            ChapterType::Recap => "Recap",
+            ChapterType::Preview => "Preview",
+        }
+    }
+}
+
+/// What the player does when playback enters a marker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkerSkipMode {
+    /// Ignore the marker
+    Off,
+    /// Offer a skip button
+    Button,
+    /// Skip after a short countdown that can be cancelled
+    Auto,
+}
+
+impl MarkerSkipMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarkerSkipMode::Off => "off",
+            MarkerSkipMode::Button => "button",
+            MarkerSkipMode::Auto => "auto",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(MarkerSkipMode::Off),
+            "button" => Some(MarkerSkipMode::Button),
+            "auto" => Some(MarkerSkipMode::Auto),
+            _ => None,
+        }
+    }
+}
+
+/// A named chapter, either embedded in the file or reported by the server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub title: String,
+    pub start_time: Duration,
+    pub thumbnail_url: Option<String>,
+}
+
+impl Chapter {
+    /// Index of the chapter playing at `position` in a list sorted by start time
+    pub fn index_at(chapters: &[Chapter], position: Duration) -> Option<usize> {
+        chapters
+            .iter()
+            .rposition(|chapter| chapter.start_time <= position)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Person {
     pub id: String,
@@ -155,6 +239,53 @@ pub struct StreamInfo {
     pub bitrate: u64,
     pub resolution: Resolution,
     pub quality_options: Vec<QualityOption>,
+    /// Server-side transcode session backing this stream, if any
+    pub transcode_session_id: Option<String>,
+    /// Sidecar subtitle files the player has to load separately
+    #[serde(default)]
+    pub external_subtitles: Vec<ExternalSubtitle>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalSubtitle {
+    pub url: String,
+    pub title: String,
+    /// ISO 639 language code, if known
+    pub language: Option<String>,
+    pub codec: String,
+    pub forced: bool,
+    pub default: bool,
+}
+
+impl ExternalSubtitle {
+    /// Loose language match so "en" from the preferences also matches "eng" from the server
+    fn matches_language(&self, preference: &str) -> bool {
+        self.language.as_deref().is_some_and(|language| {
+            if language.is_empty() || preference.is_empty() {
+                return false;
+            }
+            let language = language.to_lowercase();
+            let preference = preference.to_lowercase();
+            language.starts_with(&preference) || preference.starts_with(&language)
+        })
+    }
+}
+
+impl StreamInfo {
+    /// Index of the external subtitle to enable for a `default_subtitle` preference:
+    /// "none" disables them, "auto" follows the server's default flag, anything else
+    /// is treated as a language code. Full subtitles win over forced ones.
+    pub fn preferred_external_subtitle(&self, preference: &str) -> Option<usize> {
+        let subtitles = &self.external_subtitles;
+        match preference {
+            "" | "none" => None,
+            "auto" => subtitles.iter().position(|s| s.default),
+            language => subtitles
+                .iter()
+                .position(|s| !s.forced && s.matches_language(language))
+                .or_else(|| subtitles.iter().position(|s| s.matches_language(language))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,6 +295,23 @@ pub struct QualityOption {
     pub bitrate: u64,
     pub url: String,
     pub requires_transcode: bool,
+    /// Server-side transcode session behind `url`, if it transcodes
+    #[serde(default)]
+    pub transcode_session_id: Option<String>,
+}
+
+/// What the server is doing to a transcoded stream, as its session reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub struct TranscodeStatus {
+    /// Whether video and audio are re-encoded instead of copied
+    pub video_transcoded: bool,
+    pub audio_transcoded: bool,
+    /// Encoding speed relative to playback; below 1.0 the server can't keep up
+    pub speed: Option<f64>,
+    /// Hardware encoder in use, if any
+    pub hardware: Option<String>,
+    /// Why the server transcodes, e.g. an unsupported codec or a bitrate limit
+    pub reasons: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -461,3 +609,68 @@ pub mod source_utils {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subtitle(language: Option<&str>, forced: bool, default: bool) -> ExternalSubtitle {
+        ExternalSubtitle {
+            url: "https://server/subtitle.srt".to_string(),
+            title: language.unwrap_or("Unknown").to_string(),
+            language: language.map(str::to_string),
+            codec: "srt".to_string(),
+            forced,
+            default,
+        }
+    }
+
+    fn stream(external_subtitles: Vec<ExternalSubtitle>) -> StreamInfo {
+        StreamInfo {
+            url: "https://server/video.mkv".to_string(),
+            direct_play: true,
+            video_codec: "h264".to_string(),
+            audio_codec: "aac".to_string(),
+            container: "mkv".to_string(),
+            bitrate: 0,
+            resolution: Resolution {
+                width: 1920,
+                height: 1080,
+            },
+            quality_options: Vec::new(),
+            transcode_session_id: None,
+            external_subtitles,
+        }
+    }
+
+    #[test]
+    fn test_matches_language() {
+        let english = subtitle(Some("eng"), false, false);
+        assert!(english.matches_language("en"));
+        assert!(english.matches_language("ENG"));
+        assert!(english.matches_language("eng"));
+        assert!(!english.matches_language("de"));
+        assert!(!english.matches_language(""));
+        assert!(!subtitle(Some(""), false, false).matches_language("en"));
+        assert!(!subtitle(None, false, false).matches_language("en"));
+    }
+
+    #[test]
+    fn test_preferred_external_subtitle() {
+        let info = stream(vec![
+            subtitle(Some("eng"), true, false),
+            subtitle(Some("ger"), false, true),
+            subtitle(Some("eng"), false, false),
+        ]);
+        assert_eq!(info.preferred_external_subtitle(""), None);
+        assert_eq!(info.preferred_external_subtitle("none"), None);
+        assert_eq!(info.preferred_external_subtitle("auto"), Some(1));
+        // Full subtitles win over forced ones in the same language
+        assert_eq!(info.preferred_external_subtitle("en"), Some(2));
+        assert_eq!(info.preferred_external_subtitle("fr"), None);
+
+        let forced_only = stream(vec![subtitle(Some("eng"), true, false)]);
+        assert_eq!(forced_only.preferred_external_subtitle("en"), Some(0));
+        assert_eq!(forced_only.preferred_external_subtitle("auto"), None);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::models::MediaItemId;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// An entry in the play queue. Movies, episodes and tracks from any source can be mixed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueItem {
+    /// Media item ID
+    pub id: MediaItemId,
+    /// Display title
+    pub title: String,
+    /// Secondary line, e.g. the show and episode number or the artist
+    pub subtitle: Option<String>,
+    /// Media type as stored in the database ('movie', 'episode', 'track', ...)
+    pub media_type: String,
+    /// Duration in milliseconds
+    pub duration_ms: Option<i64>,
+    /// Position before the queue was shuffled, used to restore the order
+    pub original_position: Option<usize>,
+}
+
+/// What happens when the queue or the current item runs out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    /// Replay the current item instead of moving on
+    One,
+    /// Start over from the first item after the last one
+    All,
+}
+
+impl RepeatMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RepeatMode::Off => "off",
+            RepeatMode::One => "one",
+            RepeatMode::All => "all",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(RepeatMode::Off),
+            "one" => Some(RepeatMode::One),
+            "all" => Some(RepeatMode::All),
+            _ => None,
+        }
+    }
+
+    /// The mode the repeat button switches to next
+    pub fn cycle(&self) -> Self {
+        match self {
+            RepeatMode::Off => RepeatMode::All,
+            RepeatMode::All => RepeatMode::One,
+            RepeatMode::One => RepeatMode::Off,
+        }
+    }
+}
+
+/// A user-editable list of items to play, in play order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayQueue {
+    pub items: Vec<QueueItem>,
+    /// Index of the item playing now, if playback started from the queue
+    pub current_index: Option<usize>,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+impl PlayQueue {
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn current(&self) -> Option<&QueueItem> {
+        self.current_index.and_then(|index| self.items.get(index))
+    }
+
+    /// Where items that should play next are inserted
+    fn next_slot(&self) -> usize {
+        self.current_index.map_or(0, |index| index + 1)
+    }
+
+    /// Insert an item right after the current one
+    pub fn play_next(&mut self, item: QueueItem) {
+        let slot = self.next_slot().min(self.items.len());
+        self.items.insert(slot, item);
+    }
+
+    /// Append an item to the end of the queue
+    pub fn enqueue(&mut self, item: QueueItem) {
+        self.items.push(item);
+    }
+
+    /// Remove an item. Removing the current item leaves the queue positioned so
+    /// the item that followed it plays next.
+    pub fn remove(&mut self, index: usize) -> Option<QueueItem> {
+        if index >= self.items.len() {
+            return None;
+        }
+        let item = self.items.remove(index);
+        self.current_index = match self.current_index {
+            Some(current) if index < current => Some(current - 1),
+            Some(current) if index == current => current.checked_sub(1),
+            current => current,
+        };
+        Some(item)
+    }
+
+    /// Move an item to another position, keeping track of the current one
+    pub fn move_item(&mut self, from: usize, to: usize) {
+        if from >= self.items.len() || to >= self.items.len() || from == to {
+            return;
+        }
+        let item = self.items.remove(from);
+        self.items.insert(to, item);
+
+        self.current_index = self.current_index.map(|current| {
+            if current == from {
+                to
+            } else if from < current && to >= current {
+                current - 1
+            } else if from > current && to <= current {
+                current + 1
+            } else {
+                current
+            }
+        });
+    }
+
+    /// Drop everything except the item playing now
+    pub fn clear(&mut self) {
+        match self
+            .current_index
+            .and_then(|index| self.items.get(index).cloned())
+        {
+            Some(current) => {
+                self.items = vec![current];
+                self.current_index = Some(0);
+            }
+            None => {
+                self.items.clear();
+                self.current_index = None;
+            }
+        }
+    }
+
+    /// Index of the item Next skips to
+    pub fn next_index(&self) -> Option<usize> {
+        let next = self.next_slot();
+        if next < self.items.len() {
+            Some(next)
+        } else if self.repeat == RepeatMode::All && !self.items.is_empty() {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Index of the item that plays when the current one ends on its own
+    pub fn auto_advance_index(&self) -> Option<usize> {
+        match (self.repeat, self.current_index) {
+            (RepeatMode::One, Some(current)) if current < self.items.len() => Some(current),
+            _ => self.next_index(),
+        }
+    }
+
+    /// Index of the item Previous goes back to
+    pub fn previous_index(&self) -> Option<usize> {
+        match self.current_index {
+            Some(current) if current > 0 => Some(current - 1),
+            Some(_) if self.repeat == RepeatMode::All && self.items.len() > 1 => {
+                Some(self.items.len() - 1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Items after the current one, in play order
+    pub fn upcoming(&self) -> &[QueueItem] {
+        &self.items[self.next_slot().min(self.items.len())..]
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        let state = RandomState::new();
+        let mut seed = state.build_hasher().finish() | 1;
+        // xorshift is plenty for putting a queue in a different order
+        self.set_shuffle_with(shuffle, |bound| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % bound as u64) as usize
+        });
+    }
+
+    /// Shuffle or restore the order, drawing positions from `random(bound)` in `0..bound`.
+    /// The current item moves to the front so everything else is still ahead of it.
+    pub fn set_shuffle_with(&mut self, shuffle: bool, mut random: impl FnMut(usize) -> usize) {
+        if shuffle == self.shuffle {
+            return;
+        }
+        self.shuffle = shuffle;
+
+        if shuffle {
+            for (position, item) in self.items.iter_mut().enumerate() {
+                item.original_position = Some(position);
+            }
+            if let Some(current) = self.current_index.filter(|&i| i < self.items.len()) {
+                let item = self.items.remove(current);
+                self.items.insert(0, item);
+                self.current_index = Some(0);
+            }
+
+            // Fisher-Yates over the items after the current one
+            let start = self.next_slot();
+            for i in (start + 1..self.items.len()).rev() {
+                let j = start + random(i - start + 1);
+                self.items.swap(i, j);
+            }
+        } else {
+            // Items added while shuffled have no original position and keep their order at the end
+            let mut order: Vec<usize> = (0..self.items.len()).collect();
+            order.sort_by_key(|&i| self.items[i].original_position.unwrap_or(usize::MAX));
+
+            let mut items: Vec<Option<QueueItem>> = std::mem::take(&mut self.items)
+                .into_iter()
+                .map(Some)
+                .collect();
+            self.current_index = self
+                .current_index
+                .and_then(|current| order.iter().position(|&i| i == current));
+            self.items = order
+                .into_iter()
+                .filter_map(|i| items[i].take())
+                .map(|item| QueueItem {
+                    original_position: None,
+                    ..item
+                })
+                .collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str) -> QueueItem {
+        QueueItem {
+            id: MediaItemId::new(id),
+            title: id.to_string(),
+            subtitle: None,
+            media_type: "movie".to_string(),
+            duration_ms: None,
+            original_position: None,
+        }
+    }
+
+    fn ids(queue: &PlayQueue) -> Vec<&str> {
+        queue.items.iter().map(|i| i.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_play_next_and_enqueue() {
+        let mut queue = PlayQueue::default();
+        queue.enqueue(item("a"));
+        queue.enqueue(item("b"));
+        queue.current_index = Some(0);
+        queue.play_next(item("c"));
+        queue.enqueue(item("d"));
+
+        assert_eq!(ids(&queue), ["a", "c", "b", "d"]);
+        assert_eq!(queue.next_index(), Some(1));
+        assert_eq!(queue.upcoming().len(), 3);
+    }
+
+    #[test]
+    fn test_remove_and_move_track_current() {
+        let mut queue = PlayQueue::default();
+        for id in ["a", "b", "c", "d"] {
+            queue.enqueue(item(id));
+        }
+        queue.current_index = Some(2);
+
+        queue.move_item(3, 0);
+        assert_eq!(ids(&queue), ["d", "a", "b", "c"]);
+        assert_eq!(queue.current().unwrap().id.as_str(), "c");
+
+        queue.remove(0);
+        assert_eq!(queue.current().unwrap().id.as_str(), "c");
+
+        // Removing the current item lines up the one after it
+        queue.enqueue(item("e"));
+        queue.remove(2);
+        assert_eq!(queue.items[queue.next_index().unwrap()].id.as_str(), "e");
+    }
+
+    #[test]
+    fn test_repeat_modes() {
+        let mut queue = PlayQueue::default();
+        queue.enqueue(item("a"));
+        queue.enqueue(item("b"));
+        queue.current_index = Some(1);
+
+        assert_eq!(queue.next_index(), None);
+        assert_eq!(queue.previous_index(), Some(0));
+
+        queue.repeat = RepeatMode::All;
+        assert_eq!(queue.next_index(), Some(0));
+        assert_eq!(queue.auto_advance_index(), Some(0));
+
+        queue.repeat = RepeatMode::One;
+        assert_eq!(queue.auto_advance_index(), Some(1));
+        // Skipping by hand still moves on
+        assert_eq!(queue.next_index(), None);
+        assert_eq!(
+            RepeatMode::parse(queue.repeat.cycle().as_str()),
+            Some(RepeatMode::Off)
+        );
+    }
+
+    #[test]
+    fn test_shuffle_keeps_current_and_restores_order() {
+        let mut queue = PlayQueue::default();
+        for id in ["a", "b", "c", "d", "e"] {
+            queue.enqueue(item(id));
+        }
+        queue.current_index = Some(2);
+
+        // A fixed draw keeps the order deterministic
+        queue.set_shuffle_with(true, |_| 0);
+        assert_eq!(queue.current_index, Some(0));
+        assert_eq!(queue.current().unwrap().id.as_str(), "c");
+        let mut shuffled = ids(&queue);
+        shuffled.sort();
+        assert_eq!(shuffled, ["a", "b", "c", "d", "e"]);
+
+        queue.enqueue(item("f"));
+        queue.set_shuffle_with(false, |_| 0);
+        assert_eq!(ids(&queue), ["a", "b", "c", "d", "e", "f"]);
+        assert_eq!(queue.current_index, Some(2));
+        assert!(queue.items.iter().all(|i| i.original_position.is_none()));
+    }
+
+    #[test]
+    fn test_clear_keeps_current() {
+        let mut queue = PlayQueue::default();
+        queue.enqueue(item("a"));
+        queue.enqueue(item("b"));
+        queue.current_index = Some(1);
+        queue.clear();
+        assert_eq!(ids(&queue), ["b"]);
+        assert_eq!(queue.current_index, Some(0));
+
+        queue.current_index = None;
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::models::{MediaItemId, ShowId};
+use crate::models::{MediaItemId, PlayQueue, ShowId};
 use serde::{Deserialize, Serialize};
 
 /// Represents the context in which media is playing, enabling navigation between items
@@ -20,10 +20,12 @@ pub enum PlaylistContext {
         /// Whether to automatically play the next episode
         auto_play_next: bool,
     },
+
+    /// User-edited play queue mixing items from any source
+    Queue(PlayQueue),
     // Future variants:
     // Album { ... }
     // Playlist { ... }
-    // Queue { ... }
 }
 
 /// Minimal episode information for playlist context
@@ -61,6 +63,7 @@ impl PlaylistContext {
                     None
                 }
             }
+            PlaylistContext::Queue(queue) => queue.next_index().map(|i| queue.items[i].id.clone()),
         }
     }
 
@@ -79,6 +82,28 @@ impl PlaylistContext {
                     None
                 }
             }
+            PlaylistContext::Queue(queue) => {
+                queue.previous_index().map(|i| queue.items[i].id.clone())
+            }
+        }
+    }
+
+    /// Get the item to play when the current one finishes on its own
+    pub fn get_auto_advance_item(&self) -> Option<MediaItemId> {
+        match self {
+            PlaylistContext::Queue(queue) => queue
+                .auto_advance_index()
+                .map(|i| queue.items[i].id.clone()),
+            _ => self.get_next_item(),
+        }
+    }
+
+    /// Whether playback should continue on its own when an item ends
+    pub fn auto_play_next(&self) -> bool {
+        match self {
+            PlaylistContext::SingleItem => false,
+            PlaylistContext::TvShow { auto_play_next, .. } => *auto_play_next,
+            PlaylistContext::Queue(_) => true,
         }
     }
 
@@ -98,6 +123,24 @@ impl PlaylistContext {
                     false
                 }
             }
+            PlaylistContext::Queue(queue) => {
+                // The same item can be queued more than once, so prefer the
+                // neighbouring entries Next and Previous would pick
+                let matches = |index: &usize| &queue.items[*index].id == item_id;
+                let new_index = queue
+                    .auto_advance_index()
+                    .filter(matches)
+                    .or_else(|| queue.next_index().filter(matches))
+                    .or_else(|| queue.previous_index().filter(matches))
+                    .or_else(|| queue.items.iter().position(|i| &i.id == item_id));
+                match new_index {
+                    Some(index) => {
+                        queue.current_index = Some(index);
+                        true
+                    }
+                    None => false,
+                }
+            }
         }
     }
 
@@ -110,6 +153,7 @@ impl PlaylistContext {
                 current_index,
                 ..
             } => *current_index + 1 < episodes.len(),
+            PlaylistContext::Queue(queue) => queue.next_index().is_some(),
         }
     }
 
@@ -118,13 +162,14 @@ impl PlaylistContext {
         match self {
             PlaylistContext::SingleItem => false,
             PlaylistContext::TvShow { current_index, .. } => *current_index > 0,
+            PlaylistContext::Queue(queue) => queue.previous_index().is_some(),
         }
     }
 
     /// Get information about the next episode (for auto-play UI)
     pub fn get_next_episode_info(&self) -> Option<&EpisodeInfo> {
         match self {
-            PlaylistContext::SingleItem => None,
+            PlaylistContext::SingleItem | PlaylistContext::Queue(_) => None,
             PlaylistContext::TvShow {
                 episodes,
                 current_index,
@@ -0,0 +1,185 @@
+use anyhow::{Result, anyhow};
+use std::time::Duration;
+
+/// Magic bytes at the start of every BIF file
+const BIF_MAGIC: [u8; 8] = [0x89, 0x42, 0x49, 0x46, 0x0d, 0x0a, 0x1a, 0x0a];
+/// The index table starts right after the fixed-size header
+const BIF_HEADER_SIZE: usize = 64;
+/// Timestamp of the terminating index entry
+const BIF_END_MARKER: u32 = 0xffff_ffff;
+
+/// Seek-bar preview thumbnails for a media item
+#[derive(Debug, Clone, Default)]
+pub struct Trickplay {
+    /// Frames sorted by timestamp
+    pub frames: Vec<TrickplayFrame>,
+}
+
+/// A single preview thumbnail
+#[derive(Debug, Clone)]
+pub struct TrickplayFrame {
+    /// Position in the media this frame was taken from
+    pub timestamp: Duration,
+    /// JPEG-encoded image data
+    pub image: Vec<u8>,
+}
+
+impl Trickplay {
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Index of the frame to show for a position: the last frame at or before it
+    pub fn frame_index_at(&self, position: Duration) -> Option<usize> {
+        if self.frames.is_empty() {
+            return None;
+        }
+        let index = self
+            .frames
+            .partition_point(|frame| frame.timestamp <= position);
+        Some(index.saturating_sub(1))
+    }
+
+    /// The frame to show for a position
+    pub fn frame_at(&self, position: Duration) -> Option<&TrickplayFrame> {
+        self.frame_index_at(position).map(|i| &self.frames[i])
+    }
+
+    /// Parse a Roku BIF (Base Index Frames) file, the format Plex serves its indexes in
+    pub fn from_bif(data: &[u8]) -> Result<Self> {
+        if data.len() < BIF_HEADER_SIZE || data[..8] != BIF_MAGIC {
+            return Err(anyhow!("Not a BIF file"));
+        }
+
+        let read_u32 = |offset: usize| -> Result<u32> {
+            data.get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .ok_or_else(|| anyhow!("Truncated BIF file"))
+        };
+
+        let image_count = read_u32(12)? as usize;
+        // The count comes from the file, so check the index fits before allocating for it
+        if image_count >= (data.len() - BIF_HEADER_SIZE) / 8 {
+            return Err(anyhow!("Truncated BIF file"));
+        }
+        // Timestamps are multiplied by this to get milliseconds; 0 means 1000
+        let multiplier = match read_u32(16)? {
+            0 => 1000,
+            m => m as u64,
+        };
+
+        // Each image has an index entry, plus one terminating entry for the end offset
+        let mut entries = Vec::with_capacity(image_count + 1);
+        for i in 0..=image_count {
+            let entry_offset = BIF_HEADER_SIZE + i * 8;
+            entries.push((
+                read_u32(entry_offset)?,
+                read_u32(entry_offset + 4)? as usize,
+            ));
+        }
+
+        let mut frames = Vec::with_capacity(image_count);
+        for pair in entries.windows(2) {
+            let (timestamp, start) = pair[0];
+            let (_, end) = pair[1];
+            if timestamp == BIF_END_MARKER {
+                break;
+            }
+
+            let image = data
+                .get(start..end)
+                .ok_or_else(|| anyhow!("BIF frame out of bounds"))?;
+            frames.push(TrickplayFrame {
+                timestamp: Duration::from_millis(timestamp as u64 * multiplier),
+                image: image.to_vec(),
+            });
+        }
+
+        Ok(Self { frames })
+    }
+
+    /// Serialize to BIF so every source can share the same on-disk cache format
+    pub fn to_bif(&self) -> Vec<u8> {
+        let index_size = (self.frames.len() + 1) * 8;
+        let images_size: usize = self.frames.iter().map(|f| f.image.len()).sum();
+        let mut data = Vec::with_capacity(BIF_HEADER_SIZE + index_size + images_size);
+
+        data.extend_from_slice(&BIF_MAGIC);
+        data.extend_from_slice(&0u32.to_le_bytes()); // version
+        data.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes()); // timestamps in milliseconds
+        data.resize(BIF_HEADER_SIZE, 0);
+
+        let mut offset = (BIF_HEADER_SIZE + index_size) as u32;
+        for frame in &self.frames {
+            data.extend_from_slice(&(frame.timestamp.as_millis() as u32).to_le_bytes());
+            data.extend_from_slice(&offset.to_le_bytes());
+            offset += frame.image.len() as u32;
+        }
+        data.extend_from_slice(&BIF_END_MARKER.to_le_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+
+        for frame in &self.frames {
+            data.extend_from_slice(&frame.image);
+        }
+
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Trickplay {
+        Trickplay {
+            frames: (0..3)
+                .map(|i| TrickplayFrame {
+                    timestamp: Duration::from_secs(i * 10),
+                    image: vec![i as u8; 4 + i as usize],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_bif_roundtrip() {
+        let parsed = Trickplay::from_bif(&sample().to_bif()).unwrap();
+        assert_eq!(parsed.frames.len(), 3);
+        for (original, parsed) in sample().frames.iter().zip(&parsed.frames) {
+            assert_eq!(original.timestamp, parsed.timestamp);
+            assert_eq!(original.image, parsed.image);
+        }
+    }
+
+    #[test]
+    fn test_from_bif_rejects_invalid_data() {
+        assert!(Trickplay::from_bif(b"not a bif").is_err());
+        let mut truncated = sample().to_bif();
+        truncated.truncate(BIF_HEADER_SIZE + 4);
+        assert!(Trickplay::from_bif(&truncated).is_err());
+    }
+
+    #[test]
+    fn test_from_bif_rejects_oversized_image_count() {
+        let mut bif = sample().to_bif();
+        bif[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = Trickplay::from_bif(&bif).unwrap_err();
+        assert_eq!(error.to_string(), "Truncated BIF file");
+
+        // One more image than the index has room for
+        let mut bif = sample().to_bif();
+        let index_entries = (bif.len() - BIF_HEADER_SIZE) / 8;
+        bif[12..16].copy_from_slice(&(index_entries as u32).to_le_bytes());
+        assert!(Trickplay::from_bif(&bif).is_err());
+    }
+
+    #[test]
+    fn test_frame_at_picks_preceding_frame() {
+        let trickplay = sample();
+        assert_eq!(trickplay.frame_index_at(Duration::from_secs(0)), Some(0));
+        assert_eq!(trickplay.frame_index_at(Duration::from_secs(15)), Some(1));
+        assert_eq!(trickplay.frame_index_at(Duration::from_secs(100)), Some(2));
+        assert_eq!(Trickplay::default().frame_index_at(Duration::ZERO), None);
+    }
+}
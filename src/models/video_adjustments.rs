@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+/// Display aspect ratios offered as overrides, with their labels
+pub const ASPECT_RATIO_PRESETS: &[(&str, f64)] = &[
+    ("4:3", 4.0 / 3.0),
+    ("16:9", 16.0 / 9.0),
+    ("1.85:1", 1.85),
+    ("2.35:1", 2.35),
+    ("2.39:1", 2.39),
+];
+
+/// Luma at or below this counts as black, a little above limited-range black (16)
+const BLACK_LUMA_THRESHOLD: u8 = 24;
+
+/// Part of the source frame to keep, in video pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    /// In the `WxH+X+Y` form mpv's `video-crop` takes
+    pub fn to_mpv_string(&self) -> String {
+        format!("{}x{}+{}+{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Picture settings remembered per media item
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoAdjustments {
+    /// Display aspect ratio replacing the one the stream is flagged with
+    pub aspect_ratio: Option<f64>,
+    /// Scale factor; 1.0 fits the video to the window
+    pub zoom: f64,
+    /// Offset of the video as a fraction of its scaled size, positive moving it right or down
+    pub pan_x: f64,
+    pub pan_y: f64,
+    /// Black bars cut off the source frame
+    pub crop: Option<CropRect>,
+    /// Colour equalizer in -100..=100, 0 leaving the picture unchanged
+    pub brightness: i32,
+    pub contrast: i32,
+    pub saturation: i32,
+}
+
+impl Default for VideoAdjustments {
+    fn default() -> Self {
+        Self {
+            aspect_ratio: None,
+            zoom: 1.0,
+            pan_x: 0.0,
+            pan_y: 0.0,
+            crop: None,
+            brightness: 0,
+            contrast: 0,
+            saturation: 0,
+        }
+    }
+}
+
+impl VideoAdjustments {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Pixels to cut from the left, right, top and bottom of a `width` x `height` frame,
+    /// for backends that zoom and pan by cropping instead of scaling the output
+    pub fn crop_edges(&self, width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let crop = self.crop.unwrap_or(CropRect {
+            x: 0,
+            y: 0,
+            width,
+            height,
+        });
+        let (left, right) = Self::zoom_edges(crop.width, self.zoom, self.pan_x);
+        let (top, bottom) = Self::zoom_edges(crop.height, self.zoom, self.pan_y);
+
+        (
+            crop.x + left,
+            width.saturating_sub(crop.x + crop.width) + right,
+            crop.y + top,
+            height.saturating_sub(crop.y + crop.height) + bottom,
+        )
+    }
+
+    /// Split what zooming hides of one axis between its two edges, following the pan
+    fn zoom_edges(size: u32, zoom: f64, pan: f64) -> (u32, u32) {
+        // Zooming out can't be done by cropping
+        let hidden = size as f64 * (1.0 - 1.0 / zoom.max(1.0));
+        let start = (hidden / 2.0 - pan * size as f64).clamp(0.0, hidden);
+        let start = start.round() as u32;
+        (start, (hidden.round() as u32).saturating_sub(start))
+    }
+}
+
+/// Find the black bars around a frame from its luma plane, `stride` bytes per row.
+/// Returns `None` when there are no bars or the whole frame is black.
+pub fn detect_black_bars(
+    luma: &[u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+) -> Option<CropRect> {
+    if width == 0 || height == 0 || luma.len() < stride * (height - 1) + width {
+        return None;
+    }
+
+    let row_is_black = |y: usize| {
+        luma[y * stride..y * stride + width]
+            .iter()
+            .all(|&l| l <= BLACK_LUMA_THRESHOLD)
+    };
+    let column_is_black = |x: usize, top: usize, bottom: usize| {
+        (top..bottom).all(|y| luma[y * stride + x] <= BLACK_LUMA_THRESHOLD)
+    };
+
+    let top = (0..height).find(|&y| !row_is_black(y))?;
+    let bottom = (top..height).rev().find(|&y| !row_is_black(y))? + 1;
+    let left = (0..width).find(|&x| !column_is_black(x, top, bottom))?;
+    let right = (left..width)
+        .rev()
+        .find(|&x| !column_is_black(x, top, bottom))?
+        + 1;
+
+    // Keep even offsets and sizes, chroma planes are usually subsampled by two
+    let x = left.next_multiple_of(2);
+    let y = top.next_multiple_of(2);
+    let crop = CropRect {
+        x: x as u32,
+        y: y as u32,
+        width: ((right - x) & !1) as u32,
+        height: ((bottom - y) & !1) as u32,
+    };
+
+    let full = crop.x == 0
+        && crop.y == 0
+        && crop.width as usize >= width & !1
+        && crop.height as usize >= height & !1;
+    if full || crop.width == 0 || crop.height == 0 {
+        None
+    } else {
+        Some(crop)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A frame with black bars of the given size around a grey picture
+    fn letterboxed(width: usize, height: usize, bar_x: usize, bar_y: usize) -> Vec<u8> {
+        let mut luma = vec![16u8; width * height];
+        for y in bar_y..height - bar_y {
+            for x in bar_x..width - bar_x {
+                luma[y * width + x] = 128;
+            }
+        }
+        luma
+    }
+
+    #[test]
+    fn test_detect_black_bars() {
+        let luma = letterboxed(64, 48, 0, 6);
+        assert_eq!(
+            detect_black_bars(&luma, 64, 48, 64),
+            Some(CropRect {
+                x: 0,
+                y: 6,
+                width: 64,
+                height: 36,
+            })
+        );
+
+        let luma = letterboxed(64, 48, 8, 0);
+        assert_eq!(
+            detect_black_bars(&luma, 64, 48, 64).map(|c| c.width),
+            Some(48)
+        );
+
+        // Nothing to crop, and a black frame in a fade is left alone
+        assert_eq!(
+            detect_black_bars(&letterboxed(64, 48, 0, 0), 64, 48, 64),
+            None
+        );
+        assert_eq!(detect_black_bars(&[16u8; 64 * 48], 64, 48, 64), None);
+    }
+
+    #[test]
+    fn test_crop_edges_follow_zoom_and_pan() {
+        let mut adjustments = VideoAdjustments::default();
+        assert_eq!(adjustments.crop_edges(1920, 1080), (0, 0, 0, 0));
+
+        adjustments.zoom = 2.0;
+        assert_eq!(adjustments.crop_edges(1920, 1080), (480, 480, 270, 270));
+
+        // Panning by a quarter of the frame shows one edge of it
+        adjustments.pan_x = 0.25;
+        assert_eq!(adjustments.crop_edges(1920, 1080).0, 0);
+        adjustments.pan_x = -0.25;
+        assert_eq!(adjustments.crop_edges(1920, 1080).1, 0);
+
+        adjustments = VideoAdjustments {
+            crop: Some(CropRect {
+                x: 0,
+                y: 140,
+                width: 1920,
+                height: 800,
+            }),
+            ..VideoAdjustments::default()
+        };
+        assert_eq!(adjustments.crop_edges(1920, 1080), (0, 0, 140, 140));
+    }
+}
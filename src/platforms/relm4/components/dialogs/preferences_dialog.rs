@@ -4,7 +4,32 @@ use libadwaita as adw;
 use relm4::gtk;
 use relm4::prelude::*;
 
+use crate::core::player_traits::ReplayGainMode;
 use crate::db::connection::DatabaseConnection;
+use crate::models::{ChapterType, MarkerSkipMode};
+
+/// Skip modes in the order they are listed in the skip rows
+const SKIP_MODES: [MarkerSkipMode; 3] = [
+    MarkerSkipMode::Off,
+    MarkerSkipMode::Button,
+    MarkerSkipMode::Auto,
+];
+const SKIP_MODE_LABELS: [&str; 3] = ["Off", "Show Skip Button", "Skip Automatically"];
+
+/// ReplayGain modes in the order they are listed in the ReplayGain row
+const REPLAYGAIN_MODES: [ReplayGainMode; 3] = [
+    ReplayGainMode::Off,
+    ReplayGainMode::Track,
+    ReplayGainMode::Album,
+];
+const REPLAYGAIN_LABELS: [&str; 3] = ["Off", "Track", "Album"];
+
+/// Codecs a receiver can be sent undecoded, as stored in the config
+const PASSTHROUGH_CODECS: [&str; 5] = ["ac3", "eac3", "dts", "dts-hd", "truehd"];
+
+fn skip_mode_index(mode: MarkerSkipMode) -> u32 {
+    SKIP_MODES.iter().position(|m| *m == mode).unwrap_or(1) as u32
+}
 
 #[derive(Debug)]
 pub struct PreferencesDialog {
@@ -12,6 +37,20 @@ pub struct PreferencesDialog {
     // Player preferences
     default_player: String,
     hardware_acceleration: bool,
+    inhibit_while_paused: bool,
+    sleep_timer_fade_out: bool,
+    remote_control: bool,
+    intro_skip_mode: MarkerSkipMode,
+    credits_skip_mode: MarkerSkipMode,
+    recap_skip_mode: MarkerSkipMode,
+    preview_skip_mode: MarkerSkipMode,
+    audio_passthrough: Vec<String>,
+    // Capture preferences
+    capture_directory: String,
+    screenshot_include_subtitles: bool,
+    // Music preferences
+    gapless_audio: bool,
+    replaygain: ReplayGainMode,
     // Display preferences
     items_per_page: i32,
     // Cache preferences
@@ -22,6 +61,16 @@ pub struct PreferencesDialog {
 #[derive(Debug)]
 pub enum PreferencesDialogInput {
     SetDefaultPlayer(String),
+    SetInhibitWhilePaused(bool),
+    SetSleepTimerFadeOut(bool),
+    SetRemoteControl(bool),
+    SetSkipMode(ChapterType, MarkerSkipMode),
+    SetAudioPassthrough(&'static str, bool),
+    ChooseCaptureDirectory,
+    SetCaptureDirectory(std::path::PathBuf),
+    SetScreenshotIncludeSubtitles(bool),
+    SetGaplessAudio(bool),
+    SetReplayGain(ReplayGainMode),
     Close,
 }
 
@@ -83,6 +132,189 @@ impl AsyncComponent for PreferencesDialog {
                             }
                         }
                     },
+
+                    add = &adw::SwitchRow {
+                        set_title: "Keep Screen On While Paused",
+                        set_subtitle: "Prevent screen blanking and suspend when playback is paused",
+                        set_active: model.inhibit_while_paused,
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetInhibitWhilePaused(row.is_active()));
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "Fade Out Before Sleep",
+                        set_subtitle: "Lower the volume over the last 30 seconds of a sleep timer",
+                        set_active: model.sleep_timer_fade_out,
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetSleepTimerFadeOut(row.is_active()));
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "Remote Control",
+                        set_subtitle: "Let Jellyfin and Plex apps play to this device. Plex apps connect over a port open to the local network. Applies after restarting Reel",
+                        set_active: model.remote_control,
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetRemoteControl(row.is_active()));
+                        }
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Audio Passthrough",
+                    set_description: Some("Send surround formats undecoded to an AV receiver over HDMI or S/PDIF. Only turn on formats the receiver can decode. While any is on, the GStreamer player leaves out sound effects, volume normalization and pitch correction."),
+                    set_margin_start: 24,
+                    set_margin_end: 24,
+                    set_margin_bottom: 24,
+
+                    add = &adw::SwitchRow {
+                        set_title: "Dolby Digital (AC-3)",
+                        set_active: model.audio_passthrough.iter().any(|c| c == PASSTHROUGH_CODECS[0]),
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetAudioPassthrough(PASSTHROUGH_CODECS[0], row.is_active()));
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "Dolby Digital Plus (E-AC-3)",
+                        set_active: model.audio_passthrough.iter().any(|c| c == PASSTHROUGH_CODECS[1]),
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetAudioPassthrough(PASSTHROUGH_CODECS[1], row.is_active()));
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "DTS",
+                        set_active: model.audio_passthrough.iter().any(|c| c == PASSTHROUGH_CODECS[2]),
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetAudioPassthrough(PASSTHROUGH_CODECS[2], row.is_active()));
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "DTS-HD",
+                        set_active: model.audio_passthrough.iter().any(|c| c == PASSTHROUGH_CODECS[3]),
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetAudioPassthrough(PASSTHROUGH_CODECS[3], row.is_active()));
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "Dolby TrueHD",
+                        set_active: model.audio_passthrough.iter().any(|c| c == PASSTHROUGH_CODECS[4]),
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetAudioPassthrough(PASSTHROUGH_CODECS[4], row.is_active()));
+                        }
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Skipping",
+                    set_description: Some("How intros, credits, recaps and previews are skipped. Shows can remember their own choice from the player."),
+                    set_margin_start: 24,
+                    set_margin_end: 24,
+                    set_margin_bottom: 24,
+
+                    add = &adw::ComboRow {
+                        set_title: "Intro",
+                        set_model: Some(&gtk::StringList::new(&SKIP_MODE_LABELS)),
+                        set_selected: skip_mode_index(model.intro_skip_mode),
+                        connect_selected_notify[sender] => move |row| {
+                            let mode = SKIP_MODES[row.selected() as usize];
+                            sender.input(PreferencesDialogInput::SetSkipMode(ChapterType::Intro, mode));
+                        }
+                    },
+
+                    add = &adw::ComboRow {
+                        set_title: "Credits",
+                        set_subtitle: "The next episode countdown starts when credits roll",
+                        set_model: Some(&gtk::StringList::new(&SKIP_MODE_LABELS)),
+                        set_selected: skip_mode_index(model.credits_skip_mode),
+                        connect_selected_notify[sender] => move |row| {
+                            let mode = SKIP_MODES[row.selected() as usize];
+                            sender.input(PreferencesDialogInput::SetSkipMode(ChapterType::Credits, mode));
+                        }
+                    },
+
+                    add = &adw::ComboRow {
+                        set_title: "Recap",
+                        set_model: Some(&gtk::StringList::new(&SKIP_MODE_LABELS)),
+                        set_selected: skip_mode_index(model.recap_skip_mode),
+                        connect_selected_notify[sender] => move |row| {
+                            let mode = SKIP_MODES[row.selected() as usize];
+                            sender.input(PreferencesDialogInput::SetSkipMode(ChapterType::Recap, mode));
+                        }
+                    },
+
+                    add = &adw::ComboRow {
+                        set_title: "Preview",
+                        set_model: Some(&gtk::StringList::new(&SKIP_MODE_LABELS)),
+                        set_selected: skip_mode_index(model.preview_skip_mode),
+                        connect_selected_notify[sender] => move |row| {
+                            let mode = SKIP_MODES[row.selected() as usize];
+                            sender.input(PreferencesDialogInput::SetSkipMode(ChapterType::Preview, mode));
+                        }
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Screenshots and Clips",
+                    set_margin_start: 24,
+                    set_margin_end: 24,
+                    set_margin_bottom: 24,
+
+                    add = &adw::ActionRow {
+                        set_title: "Save To",
+                        #[watch]
+                        set_subtitle: &model.capture_directory,
+
+                        add_suffix = &gtk::Button {
+                            set_icon_name: "folder-open-symbolic",
+                            set_tooltip_text: Some("Choose Folder"),
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "flat",
+                            connect_clicked => PreferencesDialogInput::ChooseCaptureDirectory,
+                        }
+                    },
+
+                    add = &adw::SwitchRow {
+                        set_title: "Include Subtitles in Screenshots",
+                        set_active: model.screenshot_include_subtitles,
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetScreenshotIncludeSubtitles(row.is_active()));
+                        }
+                    },
+                },
+
+                add = &adw::PreferencesGroup {
+                    set_title: "Music",
+                    set_margin_start: 24,
+                    set_margin_end: 24,
+                    set_margin_bottom: 24,
+
+                    add = &adw::SwitchRow {
+                        set_title: "Gapless Playback",
+                        set_subtitle: "Start the next track without a pause in between",
+                        set_active: model.gapless_audio,
+                        connect_active_notify[sender] => move |row| {
+                            sender.input(PreferencesDialogInput::SetGaplessAudio(row.is_active()));
+                        }
+                    },
+
+                    add = &adw::ComboRow {
+                        set_title: "ReplayGain",
+                        set_subtitle: "Even out loudness using the volume tags of tracks or whole albums",
+                        set_model: Some(&gtk::StringList::new(&REPLAYGAIN_LABELS)),
+                        set_selected: REPLAYGAIN_MODES
+                            .iter()
+                            .position(|m| *m == model.replaygain)
+                            .unwrap_or(1) as u32,
+                        connect_selected_notify[sender] => move |row| {
+                            let mode = REPLAYGAIN_MODES[row.selected() as usize];
+                            sender.input(PreferencesDialogInput::SetReplayGain(mode));
+                        }
+                    },
                 },
             },
         }
@@ -106,6 +338,18 @@ impl AsyncComponent for PreferencesDialog {
             db,
             default_player: config.playback.player_backend,
             hardware_acceleration: config.playback.hardware_acceleration,
+            inhibit_while_paused: config.playback.inhibit_while_paused,
+            sleep_timer_fade_out: config.playback.sleep_timer_fade_out,
+            remote_control: config.playback.remote_control,
+            intro_skip_mode: config.playback.skip_mode(ChapterType::Intro),
+            credits_skip_mode: config.playback.skip_mode(ChapterType::Credits),
+            recap_skip_mode: config.playback.skip_mode(ChapterType::Recap),
+            preview_skip_mode: config.playback.skip_mode(ChapterType::Preview),
+            audio_passthrough: config.playback.audio_passthrough,
+            capture_directory: config.playback.capture_directory().display().to_string(),
+            screenshot_include_subtitles: config.playback.screenshot_include_subtitles,
+            gapless_audio: config.playback.gapless_audio,
+            replaygain: config.playback.replaygain_mode(),
             items_per_page: 48,
             cache_size_mb: config.playback.mpv_cache_size_mb as i32,
             auto_clean_cache: true,
@@ -149,6 +393,192 @@ impl AsyncComponent for PreferencesDialog {
                     }
                 }
             }
+            PreferencesDialogInput::SetInhibitWhilePaused(enabled) => {
+                self.inhibit_while_paused = enabled;
+                tracing::info!("Inhibit while paused: {}", enabled);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.inhibit_while_paused = enabled;
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetSleepTimerFadeOut(enabled) => {
+                self.sleep_timer_fade_out = enabled;
+                tracing::info!("Sleep timer fade out: {}", enabled);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.sleep_timer_fade_out = enabled;
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetRemoteControl(enabled) => {
+                self.remote_control = enabled;
+                tracing::info!("Remote control: {}", enabled);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.remote_control = enabled;
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetSkipMode(marker_type, mode) => {
+                match marker_type {
+                    ChapterType::Intro => self.intro_skip_mode = mode,
+                    ChapterType::Credits => self.credits_skip_mode = mode,
+                    ChapterType::Recap => self.recap_skip_mode = mode,
+                    ChapterType::Preview => self.preview_skip_mode = mode,
+                }
+                tracing::info!("{} skip mode: {}", marker_type.label(), mode.as_str());
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.set_skip_mode(marker_type, mode);
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetAudioPassthrough(codec, enabled) => {
+                self.audio_passthrough.retain(|c| c != codec);
+                if enabled {
+                    self.audio_passthrough.push(codec.to_string());
+                }
+                // Keep the order stable in the config file
+                self.audio_passthrough.sort_by_key(|c| {
+                    PASSTHROUGH_CODECS
+                        .iter()
+                        .position(|p| p == c)
+                        .unwrap_or(PASSTHROUGH_CODECS.len())
+                });
+                tracing::info!("Audio passthrough: {:?}", self.audio_passthrough);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.audio_passthrough = self.audio_passthrough.clone();
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::ChooseCaptureDirectory => {
+                let dialog = gtk::FileDialog::builder()
+                    .title("Save Screenshots and Clips To")
+                    .modal(true)
+                    .initial_folder(&gtk::gio::File::for_path(&self.capture_directory))
+                    .build();
+
+                let window = root.root().and_downcast::<gtk::Window>();
+                let sender = sender.clone();
+                relm4::spawn_local(async move {
+                    // Dismissing the dialog also ends up here as an error
+                    if let Ok(folder) = dialog.select_folder_future(window.as_ref()).await
+                        && let Some(path) = folder.path()
+                    {
+                        sender.input(PreferencesDialogInput::SetCaptureDirectory(path));
+                    }
+                });
+            }
+            PreferencesDialogInput::SetCaptureDirectory(path) => {
+                self.capture_directory = path.display().to_string();
+                tracing::info!("Capture directory: {}", self.capture_directory);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.capture_directory = self.capture_directory.clone();
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetScreenshotIncludeSubtitles(enabled) => {
+                self.screenshot_include_subtitles = enabled;
+                tracing::info!("Screenshot subtitles: {}", enabled);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.screenshot_include_subtitles = enabled;
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetGaplessAudio(enabled) => {
+                self.gapless_audio = enabled;
+                tracing::info!("Gapless audio: {}", enabled);
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.gapless_audio = enabled;
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
+            PreferencesDialogInput::SetReplayGain(mode) => {
+                self.replaygain = mode;
+                tracing::info!("ReplayGain: {}", mode.as_str());
+
+                let mut config = match crate::config::Config::load() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        tracing::error!("Failed to load config: {}", e);
+                        crate::config::Config::default()
+                    }
+                };
+                config.playback.replaygain = mode.as_str().to_string();
+
+                if let Err(e) = config.save() {
+                    tracing::error!("Failed to save preference: {}", e);
+                }
+            }
             PreferencesDialogInput::Close => {
                 root.close();
                 sender.output(PreferencesDialogOutput::Closed).unwrap();
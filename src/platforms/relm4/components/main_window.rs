@@ -9,12 +9,14 @@ use super::dialogs::{
     AuthDialog, AuthDialogInput, AuthDialogOutput, PreferencesDialog, PreferencesDialogInput,
     PreferencesDialogOutput,
 };
+use super::music_player::{MusicPlayer, MusicPlayerInput, MusicPlayerOutput};
 use super::pages::{
     HomePage, LibraryPage, MovieDetailsPage, PlayerPage, ShowDetailsPage, SourcesPage,
 };
 use super::sidebar::{Sidebar, SidebarInput, SidebarOutput};
 use crate::db::connection::DatabaseConnection;
-use crate::models::{LibraryId, MediaItemId, PlaylistContext, SourceId};
+use crate::models::{LibraryId, MediaItemId, PlayQueue, PlaylistContext, SourceId};
+use crate::player::{PartyEvent, PlayMode, RemoteCommand, RemoteControlHandle, WatchParty};
 
 #[derive(Debug)]
 pub struct MainWindow {
@@ -25,6 +27,7 @@ pub struct MainWindow {
     movie_details_page: Option<AsyncController<MovieDetailsPage>>,
     show_details_page: Option<AsyncController<ShowDetailsPage>>,
     player_page: Option<AsyncController<PlayerPage>>,
+    music_player: AsyncController<MusicPlayer>,
     sources_page: Option<AsyncController<SourcesPage>>,
     sources_nav_page: Option<adw::NavigationPage>,
     preferences_dialog: Option<AsyncController<PreferencesDialog>>,
@@ -47,10 +50,16 @@ pub struct MainWindow {
     saved_window_size: Option<(i32, i32)>,
     was_maximized: bool,
     was_fullscreen: bool,
+    // The player page is closed while its video plays in the picture-in-picture window
+    player_in_pip: bool,
     // Current navigation state
     current_library_id: Option<LibraryId>,
     // Toast overlay for notifications
     toast_overlay: adw::ToastOverlay,
+    // Joined watch party, handed to the player page once it shows the host's item
+    pending_watch_party: Option<(WatchParty, tokio::sync::mpsc::UnboundedReceiver<PartyEvent>)>,
+    // Sessions letting Jellyfin and Plex apps control playback, stopped when dropped
+    remote_control: Vec<RemoteControlHandle>,
 }
 
 #[derive(Debug)]
@@ -66,15 +75,40 @@ pub enum MainWindowInput {
         media_id: MediaItemId,
         context: PlaylistContext,
     },
+    QueueMedia {
+        media_id: MediaItemId,
+        play_next: bool,
+    },
+    /// Play an album or track in the mini-player
+    PlayMusic(MediaItemId),
+    /// The video player or a queue command saved a new queue
+    QueueChanged(PlayQueue),
+    /// The music player saved a new queue
+    MusicQueueChanged(PlayQueue),
     NavigateToPreferences,
     ToggleSidebar,
     SyncSource(SourceId),
     RestoreWindowChrome,
+    PictureInPictureStarted,
+    PictureInPictureEnded {
+        return_to_player: bool,
+    },
     ResizeWindow(i32, i32),
     SetHeaderStartContent(Option<gtk::Widget>),
     SetHeaderEndContent(Option<gtk::Widget>),
     ClearHeaderContent,
     ShowToast(String),
+    /// Toast for a saved screenshot or clip, with a button opening it
+    ShowCaptureToast(std::path::PathBuf),
+    JoinWatchParty(String),
+    WatchPartyJoined {
+        party: WatchParty,
+        events: tokio::sync::mpsc::UnboundedReceiver<PartyEvent>,
+        media_id: MediaItemId,
+    },
+    RemoteControlStarted(Vec<RemoteControlHandle>),
+    /// Something a Jellyfin or Plex app asked this player to do
+    RemoteCommand(RemoteCommand),
 }
 
 #[derive(Debug)]
@@ -257,6 +291,36 @@ impl AsyncComponent for MainWindow {
         });
         root.add_action(&about_action);
 
+        // Join watch party action
+        let join_watch_party_action = gio::SimpleAction::new("join-watch-party", None);
+        let window_clone = root.clone();
+        let sender_clone = sender.clone();
+        join_watch_party_action.connect_activate(move |_, _| {
+            let entry = gtk::Entry::builder()
+                .placeholder_text("C0A8-0105-BA0E-1234-ABCD")
+                .activates_default(true)
+                .build();
+            let dialog = adw::AlertDialog::builder()
+                .heading("Join Watch Party")
+                .body("Enter the code shown by the host. Watch parties work between computers on the same local network, and everyone plays from their own access to the same server.")
+                .extra_child(&entry)
+                .default_response("join")
+                .close_response("cancel")
+                .build();
+            dialog.add_responses(&[("cancel", "_Cancel"), ("join", "_Join")]);
+            dialog.set_response_appearance("join", adw::ResponseAppearance::Suggested);
+
+            let sender = sender_clone.clone();
+            dialog.connect_response(Some("join"), move |_, _| {
+                let code = entry.text().trim().to_string();
+                if !code.is_empty() {
+                    sender.input(MainWindowInput::JoinWatchParty(code));
+                }
+            });
+            dialog.present(Some(&window_clone));
+        });
+        root.add_action(&join_watch_party_action);
+
         // Quit action
         let quit_action = gio::SimpleAction::new("quit", None);
         quit_action.set_enabled(true);
@@ -298,6 +362,23 @@ impl AsyncComponent for MainWindow {
                 }
             });
 
+        // Music keeps playing in a bar below whatever page is open
+        let music_player =
+            MusicPlayer::builder()
+                .launch(db.clone())
+                .forward(sender.input_sender(), |output| match output {
+                    MusicPlayerOutput::Error(message) => MainWindowInput::ShowToast(message),
+                    MusicPlayerOutput::QueueChanged(queue) => {
+                        MainWindowInput::MusicQueueChanged(queue)
+                    }
+                    MusicPlayerOutput::PlayVideo { media_id, queue } => {
+                        MainWindowInput::NavigateToPlayerWithContext {
+                            media_id,
+                            context: PlaylistContext::Queue(queue),
+                        }
+                    }
+                });
+
         // Initialize the auth dialog with parent window
         let auth_dialog = AuthDialog::builder()
             .launch((db.clone(), Some(root.clone().upcast())))
@@ -322,6 +403,7 @@ impl AsyncComponent for MainWindow {
             movie_details_page: None,
             show_details_page: None,
             player_page: None,
+            music_player,
             sources_page: None,
             sources_nav_page: None,
             preferences_dialog: None,
@@ -339,6 +421,9 @@ impl AsyncComponent for MainWindow {
             saved_window_size: None,
             was_maximized: false,
             was_fullscreen: false,
+            player_in_pip: false,
+            pending_watch_party: None,
+            remote_control: Vec::new(),
             current_library_id: None,
             toast_overlay: adw::ToastOverlay::new(),
         };
@@ -354,6 +439,7 @@ impl AsyncComponent for MainWindow {
         // First section with preferences
         let section1 = gio::Menu::new();
         section1.append(Some("_Preferences"), Some("win.preferences"));
+        section1.append(Some("_Join Watch Party…"), Some("win.join-watch-party"));
         primary_menu.append_section(None, &section1);
 
         // Second section with about
@@ -397,6 +483,9 @@ impl AsyncComponent for MainWindow {
         model.content_header.clone_from(&widgets.content_header);
         model.sidebar_header.clone_from(&widgets.sidebar_header);
         model.content_toolbar.clone_from(&widgets.content_toolbar);
+        model
+            .content_toolbar
+            .add_bottom_bar(model.music_player.widget());
         model.sidebar_toolbar.clone_from(&widgets.sidebar_toolbar);
         model.split_view.clone_from(&widgets.split_view);
         model.content_stack.clone_from(&widgets.content_stack);
@@ -431,6 +520,29 @@ impl AsyncComponent for MainWindow {
         // Trigger initial sync of all sources after a short delay to let UI initialize
         sender.input(MainWindowInput::Navigate("init_sync".to_string()));
 
+        // Let the servers' own apps play to and control this player
+        if crate::config::Config::load()
+            .map(|config| config.playback.remote_control)
+            .unwrap_or(false)
+        {
+            let db = model.db.clone();
+            let sender = sender.clone();
+            relm4::spawn(async move {
+                let (commands, mut incoming) = tokio::sync::mpsc::unbounded_channel();
+                match crate::services::core::backend::BackendService::start_remote_control(
+                    &db, commands,
+                )
+                .await
+                {
+                    Ok(handles) => sender.input(MainWindowInput::RemoteControlStarted(handles)),
+                    Err(e) => tracing::warn!("Failed to start remote control: {}", e),
+                }
+                while let Some(command) = incoming.recv().await {
+                    sender.input(MainWindowInput::RemoteCommand(command));
+                }
+            });
+        }
+
         AsyncComponentParts { model, widgets }
     }
 
@@ -799,6 +911,11 @@ impl AsyncComponent for MainWindow {
                     _ => {}
                 }
             }
+            MainWindowInput::PlayMusic(media_id) => {
+                tracing::info!("Playing music: {}", media_id);
+                self.music_player
+                    .emit(MusicPlayerInput::PlayMedia(media_id));
+            }
             MainWindowInput::NavigateToPreferences => {
                 tracing::info!("Opening preferences dialog");
                 // Create and show preferences dialog
@@ -935,6 +1052,9 @@ impl AsyncComponent for MainWindow {
                                 sender_clone
                                     .input(MainWindowInput::NavigateToPlayer(item_id_clone));
                             }
+                            "album" | "track" => {
+                                sender_clone.input(MainWindowInput::PlayMusic(item_id_clone));
+                            }
                             _ => {
                                 tracing::warn!("Unknown media type: {}", media.media_type);
                             }
@@ -957,6 +1077,9 @@ impl AsyncComponent for MainWindow {
                             tracing::info!("Playing media: {}", id);
                             MainWindowInput::NavigateToPlayer(id)
                         }
+                        crate::platforms::relm4::components::pages::movie_details::MovieDetailsOutput::QueueMedia { media_id, play_next } => {
+                            MainWindowInput::QueueMedia { media_id, play_next }
+                        }
                         crate::platforms::relm4::components::pages::movie_details::MovieDetailsOutput::NavigateBack => {
                             MainWindowInput::Navigate("back".to_string())
                         }
@@ -991,6 +1114,9 @@ impl AsyncComponent for MainWindow {
                             tracing::info!("Playing episode with context: {}", media_id);
                             MainWindowInput::NavigateToPlayerWithContext { media_id, context }
                         }
+                        crate::platforms::relm4::components::pages::show_details::ShowDetailsOutput::QueueMedia { media_id, play_next } => {
+                            MainWindowInput::QueueMedia { media_id, play_next }
+                        }
                         crate::platforms::relm4::components::pages::show_details::ShowDetailsOutput::NavigateBack => {
                             MainWindowInput::Navigate("back".to_string())
                         }
@@ -1011,6 +1137,13 @@ impl AsyncComponent for MainWindow {
             MainWindowInput::NavigateToPlayer(media_id) => {
                 tracing::info!("Navigating to player for media: {}", media_id);
 
+                // Bring the video back from the picture-in-picture window
+                if std::mem::take(&mut self.player_in_pip)
+                    && let Some(ref player_page) = self.player_page
+                {
+                    player_page.sender().send(crate::platforms::relm4::components::pages::player::PlayerInput::LeavePictureInPicture).unwrap();
+                }
+
                 // Save current window state before entering player
                 let (width, height) = root.default_size();
                 self.saved_window_size = Some((width, height));
@@ -1020,6 +1153,8 @@ impl AsyncComponent for MainWindow {
                 // Hide window chrome for immersive viewing
                 self.content_header.set_visible(false);
                 self.sidebar_header.set_visible(false);
+                self.content_toolbar.set_reveal_bottom_bars(false);
+                self.music_player.emit(MusicPlayerInput::Pause);
                 self.split_view.set_collapsed(true);
                 self.content_toolbar
                     .set_top_bar_style(adw::ToolbarStyle::Flat);
@@ -1053,6 +1188,21 @@ impl AsyncComponent for MainWindow {
                                     // Player is requesting window size change for aspect ratio
                                     MainWindowInput::ResizeWindow(width, height)
                                 }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::PictureInPictureStarted => {
+                                    MainWindowInput::PictureInPictureStarted
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::PictureInPictureEnded { return_to_player } => {
+                                    MainWindowInput::PictureInPictureEnded { return_to_player }
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::ShowToast(message) => {
+                                    MainWindowInput::ShowToast(message)
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::CaptureSaved(path) => {
+                                    MainWindowInput::ShowCaptureToast(path)
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::QueueChanged(queue) => {
+                                    MainWindowInput::QueueChanged(queue)
+                                }
                             }),
                     );
                 } else if let Some(ref player_page) = self.player_page {
@@ -1067,11 +1217,23 @@ impl AsyncComponent for MainWindow {
                         .child(player_page.widget())
                         .build();
                     self.navigation_view.push(&page);
+
+                    // Now playing what the watch party is on
+                    if let Some((party, events)) = self.pending_watch_party.take() {
+                        player_page.sender().send(crate::platforms::relm4::components::pages::player::PlayerInput::JoinWatchParty { party, events }).unwrap();
+                    }
                 }
             }
             MainWindowInput::NavigateToPlayerWithContext { media_id, context } => {
                 tracing::info!("Navigating to player with context for media: {}", media_id);
 
+                // Bring the video back from the picture-in-picture window
+                if std::mem::take(&mut self.player_in_pip)
+                    && let Some(ref player_page) = self.player_page
+                {
+                    player_page.sender().send(crate::platforms::relm4::components::pages::player::PlayerInput::LeavePictureInPicture).unwrap();
+                }
+
                 // Save current window state before entering player
                 let (width, height) = root.default_size();
                 self.saved_window_size = Some((width, height));
@@ -1081,6 +1243,8 @@ impl AsyncComponent for MainWindow {
                 // Hide window chrome for immersive viewing
                 self.content_header.set_visible(false);
                 self.sidebar_header.set_visible(false);
+                self.content_toolbar.set_reveal_bottom_bars(false);
+                self.music_player.emit(MusicPlayerInput::Pause);
                 self.split_view.set_collapsed(true);
                 self.content_toolbar
                     .set_top_bar_style(adw::ToolbarStyle::Flat);
@@ -1114,6 +1278,21 @@ impl AsyncComponent for MainWindow {
                                     // Player is requesting window size change for aspect ratio
                                     MainWindowInput::ResizeWindow(width, height)
                                 }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::PictureInPictureStarted => {
+                                    MainWindowInput::PictureInPictureStarted
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::PictureInPictureEnded { return_to_player } => {
+                                    MainWindowInput::PictureInPictureEnded { return_to_player }
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::ShowToast(message) => {
+                                    MainWindowInput::ShowToast(message)
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::CaptureSaved(path) => {
+                                    MainWindowInput::ShowCaptureToast(path)
+                                }
+                                crate::platforms::relm4::components::pages::player::PlayerOutput::QueueChanged(queue) => {
+                                    MainWindowInput::QueueChanged(queue)
+                                }
                             }),
                     );
                     // Send the context to the player
@@ -1199,8 +1378,11 @@ impl AsyncComponent for MainWindow {
             MainWindowInput::RestoreWindowChrome => {
                 tracing::info!("Restoring window chrome after player");
 
-                // Stop the player before leaving the page
-                if let Some(ref player_page) = self.player_page {
+                // Stop the player before leaving the page, unless it keeps playing
+                // in the picture-in-picture window
+                if !self.player_in_pip
+                    && let Some(ref player_page) = self.player_page
+                {
                     tracing::info!("Stopping player before navigation");
                     player_page
                         .sender()
@@ -1213,6 +1395,7 @@ impl AsyncComponent for MainWindow {
                 // Show window chrome again
                 self.content_header.set_visible(true);
                 self.sidebar_header.set_visible(true);
+                self.content_toolbar.set_reveal_bottom_bars(true);
                 self.split_view.set_collapsed(false);
                 self.content_toolbar
                     .set_top_bar_style(adw::ToolbarStyle::Raised);
@@ -1243,6 +1426,43 @@ impl AsyncComponent for MainWindow {
                 // Pop the player page from navigation
                 self.navigation_view.pop();
             }
+            MainWindowInput::PictureInPictureStarted => {
+                tracing::info!("Player moved to picture-in-picture, returning to library");
+                self.player_in_pip = true;
+                sender.input(MainWindowInput::RestoreWindowChrome);
+            }
+            MainWindowInput::PictureInPictureEnded { return_to_player } => {
+                // Already handled when navigating to the player took the video back
+                if !std::mem::take(&mut self.player_in_pip) || !return_to_player {
+                    return;
+                }
+                tracing::info!("Returning to player from picture-in-picture");
+
+                // Save current window state before entering player
+                let (width, height) = root.default_size();
+                self.saved_window_size = Some((width, height));
+                self.was_maximized = root.is_maximized();
+                self.was_fullscreen = root.is_fullscreen();
+
+                // Hide window chrome for immersive viewing
+                self.content_header.set_visible(false);
+                self.sidebar_header.set_visible(false);
+                self.content_toolbar.set_reveal_bottom_bars(false);
+                self.music_player.emit(MusicPlayerInput::Pause);
+                self.split_view.set_collapsed(true);
+                self.content_toolbar
+                    .set_top_bar_style(adw::ToolbarStyle::Flat);
+                self.sidebar_toolbar
+                    .set_top_bar_style(adw::ToolbarStyle::Flat);
+
+                if let Some(ref player_page) = self.player_page {
+                    let page = adw::NavigationPage::builder()
+                        .title("Player")
+                        .child(player_page.widget())
+                        .build();
+                    self.navigation_view.push(&page);
+                }
+            }
             MainWindowInput::ResizeWindow(width, height) => {
                 tracing::info!(
                     "Resizing window to {}x{} for video aspect ratio",
@@ -1289,11 +1509,185 @@ impl AsyncComponent for MainWindow {
                     self.header_end_box.remove(&child);
                 }
             }
+            MainWindowInput::QueueMedia {
+                media_id,
+                play_next,
+            } => {
+                if let Some(ref player_page) = self.player_page {
+                    // The player owns the live queue, saves it and reports how adding went
+                    player_page
+                        .sender()
+                        .send(
+                            crate::platforms::relm4::components::pages::player::PlayerInput::QueueMedia {
+                                media_id,
+                                play_next,
+                            },
+                        )
+                        .unwrap_or_else(|_| {
+                            tracing::error!("Failed to queue media in player");
+                        });
+                } else {
+                    let db = self.db.clone();
+                    let sender_clone = sender.clone();
+                    relm4::spawn(async move {
+                        use crate::services::commands::{
+                            Command, media_commands::QueueMediaCommand,
+                        };
+
+                        let cmd = QueueMediaCommand {
+                            db,
+                            media_id,
+                            play_next,
+                        };
+                        match cmd.execute().await {
+                            Ok(queue) => {
+                                sender_clone.input(MainWindowInput::ShowToast(
+                                    if play_next {
+                                        "Playing next"
+                                    } else {
+                                        "Added to queue"
+                                    }
+                                    .to_string(),
+                                ));
+                                sender_clone.input(MainWindowInput::QueueChanged(queue));
+                            }
+                            Err(e) => {
+                                tracing::error!("Failed to add to queue: {}", e);
+                                sender_clone.input(MainWindowInput::ShowToast(
+                                    "Failed to add to queue".to_string(),
+                                ));
+                            }
+                        }
+                    });
+                }
+            }
+            MainWindowInput::QueueChanged(queue) => {
+                self.music_player.emit(MusicPlayerInput::SyncQueue(queue));
+            }
+            MainWindowInput::MusicQueueChanged(queue) => {
+                if let Some(ref player_page) = self.player_page {
+                    player_page.emit(
+                        crate::platforms::relm4::components::pages::player::PlayerInput::QueueReplaced(
+                            queue,
+                        ),
+                    );
+                }
+            }
             MainWindowInput::ShowToast(message) => {
                 let toast = adw::Toast::new(&message);
                 toast.set_timeout(3);
                 self.toast_overlay.add_toast(toast);
             }
+            MainWindowInput::JoinWatchParty(code) => {
+                let name = gtk::glib::real_name().to_string_lossy().into_owned();
+                let sender = sender.clone();
+                relm4::spawn(async move {
+                    match WatchParty::join(&code, &name).await {
+                        Ok((party, events, media_id)) => {
+                            sender.input(MainWindowInput::WatchPartyJoined {
+                                party,
+                                events,
+                                media_id: MediaItemId::new(media_id),
+                            });
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to join watch party: {}", e);
+                            sender.input(MainWindowInput::ShowToast(format!(
+                                "Failed to join the watch party: {}",
+                                e
+                            )));
+                        }
+                    }
+                });
+            }
+            MainWindowInput::WatchPartyJoined {
+                party,
+                events,
+                media_id,
+            } => {
+                self.pending_watch_party = Some((party, events));
+                sender.input(MainWindowInput::NavigateToPlayer(media_id));
+            }
+            MainWindowInput::RemoteControlStarted(handles) => {
+                tracing::info!("Accepting remote control from {} session(s)", handles.len());
+                self.remote_control = handles;
+            }
+            MainWindowInput::RemoteCommand(RemoteCommand::Play {
+                items,
+                start_index,
+                start_position,
+                mode,
+            }) => {
+                let start_index = start_index.min(items.len().saturating_sub(1));
+                // With nothing to queue behind, queued items just play
+                if mode == PlayMode::Now || self.player_page.is_none() {
+                    if let Some(media_id) = items.get(start_index) {
+                        sender.input(MainWindowInput::NavigateToPlayer(media_id.clone()));
+                        if let Some(position) = start_position {
+                            sender.input(MainWindowInput::RemoteCommand(RemoteCommand::Seek(
+                                position,
+                            )));
+                        }
+                        let rest = items[start_index + 1..].to_vec();
+                        if !rest.is_empty() {
+                            sender.input(MainWindowInput::RemoteCommand(RemoteCommand::Play {
+                                items: rest,
+                                start_index: 0,
+                                start_position: None,
+                                mode: PlayMode::Last,
+                            }));
+                        }
+                    }
+                } else if let Some(ref player_page) = self.player_page {
+                    let play_next = mode == PlayMode::Next;
+                    let mut items = items;
+                    // Each item queued next goes in front of the one before
+                    if play_next {
+                        items.reverse();
+                    }
+                    for media_id in items {
+                        player_page.emit(
+                            crate::platforms::relm4::components::pages::player::PlayerInput::QueueMedia {
+                                media_id,
+                                play_next,
+                            },
+                        );
+                    }
+                }
+            }
+            MainWindowInput::RemoteCommand(RemoteCommand::ShowMessage(message)) => {
+                sender.input(MainWindowInput::ShowToast(message));
+            }
+            MainWindowInput::RemoteCommand(command) => {
+                if let Some(ref player_page) = self.player_page {
+                    player_page.emit(
+                        crate::platforms::relm4::components::pages::player::PlayerInput::RemoteCommand(
+                            command,
+                        ),
+                    );
+                }
+            }
+            MainWindowInput::ShowCaptureToast(path) => {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                let toast = adw::Toast::builder()
+                    .title(format!("Saved {}", name))
+                    .button_label("Open")
+                    .timeout(5)
+                    .build();
+                let window = root.clone();
+                toast.connect_button_clicked(move |_| {
+                    let launcher = gtk::FileLauncher::new(Some(&gtk::gio::File::for_path(&path)));
+                    launcher.launch(Some(&window), None::<&gtk::gio::Cancellable>, |result| {
+                        if let Err(e) = result {
+                            tracing::warn!("Failed to open capture: {}", e);
+                        }
+                    });
+                });
+                self.toast_overlay.add_toast(toast);
+            }
         }
     }
 
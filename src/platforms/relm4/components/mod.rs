@@ -1,6 +1,7 @@
 pub mod dialogs;
 pub mod factories;
 pub mod main_window;
+pub mod music_player;
 pub mod pages;
 pub mod shared;
 pub mod sidebar;
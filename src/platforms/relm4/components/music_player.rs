@@ -0,0 +1,968 @@
+use crate::config::Config;
+use crate::db::connection::DatabaseConnection;
+use crate::models::{MediaItemId, PlayQueue, QueueItem, RepeatMode};
+use crate::platforms::relm4::components::pages::player::format_duration;
+use crate::platforms::relm4::components::workers::image_loader::{
+    ImageLoader, ImageLoaderInput, ImageLoaderOutput, ImageRequest, ImageSize,
+};
+use crate::player::{
+    DeviceCapabilities, PlayerController, PlayerEvent, PlayerHandle, PlayerState, ReplayGainMode,
+};
+use adw::prelude::*;
+use gtk::glib;
+use gtk::prelude::*;
+use libadwaita as adw;
+use relm4::WorkerController;
+use relm4::gtk;
+use relm4::prelude::*;
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+/// How far into a track Previous restarts it instead of going back one
+const TRACK_RESTART_THRESHOLD: Duration = Duration::from_secs(3);
+
+/// Audio playback that keeps going while browsing: a mini-player bar for the bottom of
+/// the main window and a now-playing dialog with the track list. It owns its own
+/// audio-only player, separate from the video player page, but plays from the same
+/// saved queue. Movies and episodes in that queue are handed to the video player.
+pub struct MusicPlayer {
+    db: DatabaseConnection,
+    player: Option<PlayerHandle>,
+    queue: PlayQueue,
+    cover_url: Option<String>,
+    cover: Option<gtk::gdk::Texture>,
+    state: PlayerState,
+    position: Duration,
+    duration: Option<Duration>,
+    gapless: bool,
+    replaygain: ReplayGainMode,
+    /// Queue index handed to the backend to follow the current track without a gap
+    preloaded_index: Option<usize>,
+    image_loader: WorkerController<ImageLoader>,
+    now_playing: adw::Dialog,
+    track_list: gtk::ListBox,
+}
+
+impl std::fmt::Debug for MusicPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MusicPlayer")
+            .field("queue", &self.queue)
+            .field("state", &self.state)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum MusicPlayerInput {
+    /// Play an album, or a track along with the rest of its album, in place of the queue
+    PlayMedia(MediaItemId),
+    PlayIndex(usize),
+    TogglePlayPause,
+    /// Pause without touching the queue, e.g. when a video starts
+    Pause,
+    Next,
+    Previous,
+    Seek(f64),
+    SetShuffle(bool),
+    CycleRepeat,
+    ShowNowPlaying,
+    /// Stop and hide the mini-player
+    Close,
+    /// Hand the track after the current one to the backend ahead of time
+    PreloadNext,
+    CoverLoaded(Option<gtk::gdk::Texture>),
+    /// The queue was changed and saved elsewhere, e.g. in the video player
+    SyncQueue(PlayQueue),
+}
+
+#[derive(Debug)]
+pub enum MusicPlayerOutput {
+    Error(String),
+    /// The queue was changed and saved here
+    QueueChanged(PlayQueue),
+    /// The queue moved on to something that isn't music
+    PlayVideo {
+        media_id: MediaItemId,
+        queue: PlayQueue,
+    },
+}
+
+pub enum MusicPlayerCommandOutput {
+    QueueBuilt {
+        queue: PlayQueue,
+        cover_url: Option<String>,
+    },
+    TrackStarted(usize),
+    /// The backend has the track at this index queued up, for the given current track
+    Preloaded {
+        index: Option<usize>,
+        for_index: usize,
+    },
+    StateChanged(PlayerState),
+    PositionUpdate {
+        position: Duration,
+        duration: Option<Duration>,
+    },
+    EndOfStream,
+    NextMediaStarted,
+    Error(String),
+}
+
+impl std::fmt::Debug for MusicPlayerCommandOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::QueueBuilt { queue, .. } => {
+                write!(f, "QueueBuilt({} tracks)", queue.items.len())
+            }
+            Self::TrackStarted(index) => write!(f, "TrackStarted({})", index),
+            Self::Preloaded { index, for_index } => {
+                write!(f, "Preloaded({:?} after {})", index, for_index)
+            }
+            Self::StateChanged(state) => write!(f, "StateChanged({:?})", state),
+            Self::PositionUpdate { position, .. } => write!(f, "PositionUpdate({:?})", position),
+            Self::EndOfStream => write!(f, "EndOfStream"),
+            Self::NextMediaStarted => write!(f, "NextMediaStarted"),
+            Self::Error(message) => write!(f, "Error({})", message),
+        }
+    }
+}
+
+#[relm4::component(pub async)]
+impl AsyncComponent for MusicPlayer {
+    type Init = DatabaseConnection;
+    type Input = MusicPlayerInput;
+    type Output = MusicPlayerOutput;
+    type CommandOutput = MusicPlayerCommandOutput;
+
+    view! {
+        #[root]
+        gtk::Revealer {
+            set_transition_type: gtk::RevealerTransitionType::SlideUp,
+            #[watch]
+            set_reveal_child: model.player.is_some() && model.queue.current().is_some(),
+
+            gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_spacing: 12,
+                set_margin_top: 6,
+                set_margin_bottom: 6,
+                set_margin_start: 12,
+                set_margin_end: 12,
+                add_css_class: "toolbar",
+
+                gtk::Button {
+                    add_css_class: "flat",
+                    set_hexpand: true,
+                    set_tooltip_text: Some("Now Playing"),
+                    connect_clicked => MusicPlayerInput::ShowNowPlaying,
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 12,
+
+                        gtk::Picture {
+                            set_width_request: 40,
+                            set_height_request: 40,
+                            set_content_fit: gtk::ContentFit::Cover,
+                            #[watch]
+                            set_paintable: model.cover.as_ref(),
+                        },
+
+                        gtk::Box {
+                            set_orientation: gtk::Orientation::Vertical,
+                            set_valign: gtk::Align::Center,
+
+                            gtk::Label {
+                                set_xalign: 0.0,
+                                set_ellipsize: gtk::pango::EllipsizeMode::End,
+                                add_css_class: "heading",
+                                #[watch]
+                                set_label: &model.title(),
+                            },
+
+                            gtk::Label {
+                                set_xalign: 0.0,
+                                set_ellipsize: gtk::pango::EllipsizeMode::End,
+                                add_css_class: "dim-label",
+                                add_css_class: "caption",
+                                #[watch]
+                                set_label: &model.artist(),
+                            },
+                        },
+                    },
+                },
+
+                gtk::Button {
+                    set_icon_name: "media-skip-backward-symbolic",
+                    set_tooltip_text: Some("Previous"),
+                    set_valign: gtk::Align::Center,
+                    add_css_class: "flat",
+                    add_css_class: "circular",
+                    connect_clicked => MusicPlayerInput::Previous,
+                },
+
+                gtk::Button {
+                    set_valign: gtk::Align::Center,
+                    add_css_class: "circular",
+                    #[watch]
+                    set_icon_name: model.play_pause_icon(),
+                    connect_clicked => MusicPlayerInput::TogglePlayPause,
+                },
+
+                gtk::Button {
+                    set_icon_name: "media-skip-forward-symbolic",
+                    set_tooltip_text: Some("Next"),
+                    set_valign: gtk::Align::Center,
+                    add_css_class: "flat",
+                    add_css_class: "circular",
+                    #[watch]
+                    set_sensitive: model.queue.next_index().is_some(),
+                    connect_clicked => MusicPlayerInput::Next,
+                },
+
+                gtk::Scale {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_width_request: 200,
+                    set_valign: gtk::Align::Center,
+                    #[watch]
+                    set_range: (0.0, model.duration_secs()),
+                    #[watch]
+                    set_value: model.position.as_secs_f64(),
+                    connect_change_value[sender] => move |_, _, value| {
+                        sender.input(MusicPlayerInput::Seek(value));
+                        glib::Propagation::Proceed
+                    },
+                },
+
+                gtk::Label {
+                    add_css_class: "dim-label",
+                    add_css_class: "numeric",
+                    #[watch]
+                    set_label: &format_duration(model.position),
+                },
+
+                gtk::Button {
+                    set_icon_name: "window-close-symbolic",
+                    set_tooltip_text: Some("Stop"),
+                    set_valign: gtk::Align::Center,
+                    add_css_class: "flat",
+                    add_css_class: "circular",
+                    connect_clicked => MusicPlayerInput::Close,
+                },
+            },
+        },
+
+        #[name(now_playing)]
+        adw::Dialog {
+            set_title: "Now Playing",
+            set_content_width: 420,
+            set_content_height: 720,
+
+            #[wrap(Some)]
+            set_child = &adw::ToolbarView {
+                add_top_bar = &adw::HeaderBar {},
+
+                #[wrap(Some)]
+                set_content = &gtk::Box {
+                    set_orientation: gtk::Orientation::Vertical,
+                    set_spacing: 12,
+                    set_margin_top: 12,
+                    set_margin_bottom: 24,
+                    set_margin_start: 24,
+                    set_margin_end: 24,
+
+                    gtk::Picture {
+                        set_width_request: 280,
+                        set_height_request: 280,
+                        set_halign: gtk::Align::Center,
+                        set_content_fit: gtk::ContentFit::Cover,
+                        add_css_class: "card",
+                        #[watch]
+                        set_paintable: model.cover.as_ref(),
+                    },
+
+                    gtk::Label {
+                        set_wrap: true,
+                        set_justify: gtk::Justification::Center,
+                        add_css_class: "title-2",
+                        #[watch]
+                        set_label: &model.title(),
+                    },
+
+                    gtk::Label {
+                        add_css_class: "dim-label",
+                        #[watch]
+                        set_label: &model.artist(),
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 6,
+
+                        gtk::Label {
+                            add_css_class: "dim-label",
+                            add_css_class: "numeric",
+                            #[watch]
+                            set_label: &format_duration(model.position),
+                        },
+
+                        gtk::Scale {
+                            set_orientation: gtk::Orientation::Horizontal,
+                            set_hexpand: true,
+                            #[watch]
+                            set_range: (0.0, model.duration_secs()),
+                            #[watch]
+                            set_value: model.position.as_secs_f64(),
+                            connect_change_value[sender] => move |_, _, value| {
+                                sender.input(MusicPlayerInput::Seek(value));
+                                glib::Propagation::Proceed
+                            },
+                        },
+
+                        gtk::Label {
+                            add_css_class: "dim-label",
+                            add_css_class: "numeric",
+                            #[watch]
+                            set_label: &format_duration(model.duration.unwrap_or_default()),
+                        },
+                    },
+
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 12,
+                        set_halign: gtk::Align::Center,
+
+                        gtk::ToggleButton {
+                            set_icon_name: "media-playlist-shuffle-symbolic",
+                            set_tooltip_text: Some("Shuffle"),
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "flat",
+                            #[watch]
+                            #[block_signal(shuffle_handler)]
+                            set_active: model.queue.shuffle,
+                            connect_toggled[sender] => move |button| {
+                                sender.input(MusicPlayerInput::SetShuffle(button.is_active()));
+                            } @shuffle_handler,
+                        },
+
+                        gtk::Button {
+                            set_icon_name: "media-skip-backward-symbolic",
+                            set_tooltip_text: Some("Previous"),
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "flat",
+                            add_css_class: "circular",
+                            connect_clicked => MusicPlayerInput::Previous,
+                        },
+
+                        gtk::Button {
+                            set_width_request: 56,
+                            set_height_request: 56,
+                            add_css_class: "circular",
+                            add_css_class: "suggested-action",
+                            #[watch]
+                            set_icon_name: model.play_pause_icon(),
+                            connect_clicked => MusicPlayerInput::TogglePlayPause,
+                        },
+
+                        gtk::Button {
+                            set_icon_name: "media-skip-forward-symbolic",
+                            set_tooltip_text: Some("Next"),
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "flat",
+                            add_css_class: "circular",
+                            #[watch]
+                            set_sensitive: model.queue.next_index().is_some(),
+                            connect_clicked => MusicPlayerInput::Next,
+                        },
+
+                        gtk::Button {
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "flat",
+                            #[watch]
+                            set_icon_name: model.repeat_icon().0,
+                            #[watch]
+                            set_tooltip_text: Some(model.repeat_icon().1),
+                            connect_clicked => MusicPlayerInput::CycleRepeat,
+                        },
+                    },
+
+                    gtk::ScrolledWindow {
+                        set_vexpand: true,
+                        set_hscrollbar_policy: gtk::PolicyType::Never,
+
+                        #[name(track_list)]
+                        gtk::ListBox {
+                            set_selection_mode: gtk::SelectionMode::None,
+                            add_css_class: "boxed-list",
+                            connect_row_activated[sender] => move |_, row| {
+                                sender.input(MusicPlayerInput::PlayIndex(row.index() as usize));
+                            },
+                        },
+                    },
+                },
+            },
+        },
+    }
+
+    async fn init(
+        db: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let config = Config::load().unwrap_or_default();
+
+        let image_loader =
+            ImageLoader::builder()
+                .detach_worker(())
+                .forward(sender.input_sender(), |output| match output {
+                    ImageLoaderOutput::ImageLoaded { texture, .. } => {
+                        MusicPlayerInput::CoverLoaded(Some(texture))
+                    }
+                    ImageLoaderOutput::LoadFailed { .. } | ImageLoaderOutput::CacheCleared => {
+                        MusicPlayerInput::CoverLoaded(None)
+                    }
+                });
+
+        let mut model = Self {
+            db,
+            player: None,
+            queue: PlayQueue::default(),
+            cover_url: None,
+            cover: None,
+            state: PlayerState::Idle,
+            position: Duration::ZERO,
+            duration: None,
+            gapless: config.playback.gapless_audio,
+            replaygain: config.playback.replaygain_mode(),
+            preloaded_index: None,
+            image_loader,
+            now_playing: adw::Dialog::new(),
+            track_list: gtk::ListBox::new(),
+        };
+
+        let widgets = view_output!();
+        model.now_playing.clone_from(&widgets.now_playing);
+        model.track_list.clone_from(&widgets.track_list);
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        match msg {
+            MusicPlayerInput::PlayMedia(media_id) => {
+                info!("Playing music from {}", media_id);
+                let db = self.db.clone();
+                sender.oneshot_command(async move {
+                    use crate::services::commands::Command;
+                    use crate::services::commands::media_commands::BuildMusicQueueCommand;
+
+                    match (BuildMusicQueueCommand { db, media_id }).execute().await {
+                        Ok((queue, cover_url)) => {
+                            MusicPlayerCommandOutput::QueueBuilt { queue, cover_url }
+                        }
+                        Err(e) => {
+                            MusicPlayerCommandOutput::Error(format!("Failed to play music: {}", e))
+                        }
+                    }
+                });
+            }
+            MusicPlayerInput::PlayIndex(index) => {
+                let Some(item) = self.queue.items.get(index).cloned() else {
+                    return;
+                };
+                if !plays_as_music(&item) {
+                    info!("Handing {} to the video player", item.id);
+                    self.stop_player();
+                    self.queue.current_index = Some(index);
+                    sender
+                        .output(MusicPlayerOutput::PlayVideo {
+                            media_id: item.id,
+                            queue: self.queue.clone(),
+                        })
+                        .ok();
+                    return;
+                }
+                let Some(player) = self.ensure_player(&sender).await else {
+                    return;
+                };
+
+                self.queue.current_index = Some(index);
+                self.queue_changed(&sender);
+                self.preloaded_index = None;
+                self.position = Duration::ZERO;
+                self.duration = item
+                    .duration_ms
+                    .map(|ms| Duration::from_millis(ms.max(0) as u64));
+                self.update_track_list();
+
+                let db = self.db.clone();
+                sender.oneshot_command(async move {
+                    use crate::services::commands::Command;
+                    use crate::services::commands::media_commands::GetStreamUrlCommand;
+
+                    let stream_info = match (GetStreamUrlCommand {
+                        db,
+                        media_item_id: item.id.clone(),
+                    })
+                    .execute()
+                    .await
+                    {
+                        Ok(info) => info,
+                        Err(e) => {
+                            return MusicPlayerCommandOutput::Error(format!(
+                                "Failed to play {}: {}",
+                                item.title, e
+                            ));
+                        }
+                    };
+
+                    if let Err(e) = player.load_media(&stream_info.url).await {
+                        return MusicPlayerCommandOutput::Error(format!(
+                            "Failed to play {}: {}",
+                            item.title, e
+                        ));
+                    }
+                    if let Err(e) = player.play().await {
+                        return MusicPlayerCommandOutput::Error(format!(
+                            "Failed to play {}: {}",
+                            item.title, e
+                        ));
+                    }
+                    MusicPlayerCommandOutput::TrackStarted(index)
+                });
+            }
+            MusicPlayerInput::TogglePlayPause => {
+                let Some(player) = self.player.clone() else {
+                    return;
+                };
+                let playing = self.state == PlayerState::Playing;
+                relm4::spawn_local(async move {
+                    let result = if playing {
+                        player.pause().await
+                    } else {
+                        player.play().await
+                    };
+                    if let Err(e) = result {
+                        error!("Failed to toggle music playback: {}", e);
+                    }
+                });
+            }
+            MusicPlayerInput::Pause => {
+                if self.state == PlayerState::Playing
+                    && let Some(player) = self.player.clone()
+                {
+                    relm4::spawn_local(async move {
+                        if let Err(e) = player.pause().await {
+                            error!("Failed to pause music: {}", e);
+                        }
+                    });
+                }
+            }
+            MusicPlayerInput::Next => {
+                if let Some(index) = self.queue.next_index() {
+                    sender.input(MusicPlayerInput::PlayIndex(index));
+                }
+            }
+            MusicPlayerInput::Previous => {
+                if self.position > TRACK_RESTART_THRESHOLD || self.queue.previous_index().is_none()
+                {
+                    sender.input(MusicPlayerInput::Seek(0.0));
+                } else if let Some(index) = self.queue.previous_index() {
+                    sender.input(MusicPlayerInput::PlayIndex(index));
+                }
+            }
+            MusicPlayerInput::Seek(secs) => {
+                let Some(player) = self.player.clone() else {
+                    return;
+                };
+                let position = Duration::from_secs_f64(secs.max(0.0));
+                self.position = position;
+                relm4::spawn_local(async move {
+                    if let Err(e) = player.seek(position).await {
+                        error!("Failed to seek music: {}", e);
+                    }
+                });
+            }
+            MusicPlayerInput::SetShuffle(shuffle) => {
+                self.queue.set_shuffle(shuffle);
+                self.queue_changed(&sender);
+                self.update_track_list();
+                sender.input(MusicPlayerInput::PreloadNext);
+            }
+            MusicPlayerInput::CycleRepeat => {
+                self.queue.repeat = self.queue.repeat.cycle();
+                self.queue_changed(&sender);
+                sender.input(MusicPlayerInput::PreloadNext);
+            }
+            MusicPlayerInput::ShowNowPlaying => {
+                self.now_playing.present(Some(root));
+            }
+            MusicPlayerInput::Close => {
+                // The queue stays saved for the video player and the next album
+                self.stop_player();
+                self.now_playing.close();
+            }
+            MusicPlayerInput::PreloadNext => {
+                let (Some(player), Some(current)) = (self.player.clone(), self.queue.current_index)
+                else {
+                    return;
+                };
+                if !self.gapless {
+                    return;
+                }
+                self.preloaded_index = None;
+
+                // Anything but music ends the gapless run and goes to the video player
+                let next = self.queue.auto_advance_index().and_then(|index| {
+                    let item = self
+                        .queue
+                        .items
+                        .get(index)
+                        .filter(|item| plays_as_music(item))?;
+                    Some((index, item.id.clone()))
+                });
+                let db = self.db.clone();
+                sender.oneshot_command(async move {
+                    use crate::services::commands::Command;
+                    use crate::services::commands::media_commands::GetStreamUrlCommand;
+
+                    let (index, url) = match next {
+                        Some((index, media_item_id)) => {
+                            match (GetStreamUrlCommand { db, media_item_id }).execute().await {
+                                Ok(info) => (Some(index), Some(info.url)),
+                                Err(e) => {
+                                    // The end of the track falls back to a normal load
+                                    warn!("Failed to preload next track: {}", e);
+                                    (None, None)
+                                }
+                            }
+                        }
+                        None => (None, None),
+                    };
+                    match player.queue_next(url.as_deref()).await {
+                        Ok(()) => MusicPlayerCommandOutput::Preloaded {
+                            index,
+                            for_index: current,
+                        },
+                        Err(e) => {
+                            warn!("Gapless playback unavailable: {}", e);
+                            MusicPlayerCommandOutput::Preloaded {
+                                index: None,
+                                for_index: current,
+                            }
+                        }
+                    }
+                });
+            }
+            MusicPlayerInput::CoverLoaded(texture) => {
+                self.cover = texture;
+            }
+            MusicPlayerInput::SyncQueue(queue) => {
+                let current = self.queue.current().map(|item| item.id.clone());
+                self.queue = queue;
+                if self.queue.current().map(|item| &item.id) != current.as_ref() {
+                    // The video player moved the queue on, so the track here is done with
+                    self.stop_player();
+                } else if self.player.is_some() {
+                    sender.input(MusicPlayerInput::PreloadNext);
+                }
+                self.update_track_list();
+            }
+        }
+    }
+
+    async fn update_cmd(
+        &mut self,
+        msg: Self::CommandOutput,
+        sender: AsyncComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        match msg {
+            MusicPlayerCommandOutput::QueueBuilt { queue, cover_url } => {
+                let start = queue.current_index.unwrap_or(0);
+                self.queue = queue;
+                if self.cover_url != cover_url {
+                    self.cover = None;
+                    if let Some(url) = &cover_url {
+                        self.image_loader
+                            .emit(ImageLoaderInput::LoadImage(ImageRequest {
+                                id: format!("music-cover:{}", url),
+                                url: url.clone(),
+                                size: ImageSize::Custom(512, 512),
+                                priority: 0,
+                            }));
+                    }
+                    self.cover_url = cover_url;
+                }
+                sender.input(MusicPlayerInput::PlayIndex(start));
+            }
+            MusicPlayerCommandOutput::TrackStarted(index) => {
+                debug!("Music track {} started", index);
+                sender.input(MusicPlayerInput::PreloadNext);
+            }
+            MusicPlayerCommandOutput::Preloaded { index, for_index } => {
+                // A skip while the URL was fetched makes this preload stale
+                if self.queue.current_index == Some(for_index) {
+                    self.preloaded_index = index;
+                } else {
+                    sender.input(MusicPlayerInput::PreloadNext);
+                }
+            }
+            MusicPlayerCommandOutput::StateChanged(state) => {
+                self.state = state;
+            }
+            MusicPlayerCommandOutput::PositionUpdate { position, duration } => {
+                self.position = position;
+                if duration.is_some() {
+                    self.duration = duration;
+                }
+            }
+            MusicPlayerCommandOutput::NextMediaStarted => {
+                if let Some(index) = self.preloaded_index.take() {
+                    self.queue.current_index = Some(index);
+                    self.position = Duration::ZERO;
+                    self.duration = self.queue.items[index]
+                        .duration_ms
+                        .map(|ms| Duration::from_millis(ms.max(0) as u64));
+                    self.queue_changed(&sender);
+                    self.update_track_list();
+                    sender.input(MusicPlayerInput::PreloadNext);
+                }
+            }
+            MusicPlayerCommandOutput::EndOfStream => {
+                // Without a preload the next track loads the usual way, with a short gap
+                if let Some(index) = self.queue.auto_advance_index() {
+                    sender.input(MusicPlayerInput::PlayIndex(index));
+                }
+            }
+            MusicPlayerCommandOutput::Error(message) => {
+                error!("{}", message);
+                sender.output(MusicPlayerOutput::Error(message)).ok();
+            }
+        }
+    }
+}
+
+impl MusicPlayer {
+    fn title(&self) -> String {
+        self.queue
+            .current()
+            .map(|item| item.title.clone())
+            .unwrap_or_default()
+    }
+
+    fn artist(&self) -> String {
+        self.queue
+            .current()
+            .and_then(|item| item.subtitle.clone())
+            .unwrap_or_default()
+    }
+
+    fn duration_secs(&self) -> f64 {
+        self.duration
+            .map(|duration| duration.as_secs_f64())
+            .unwrap_or_default()
+            .max(1.0)
+    }
+
+    fn play_pause_icon(&self) -> &'static str {
+        if self.state == PlayerState::Playing {
+            "media-playback-pause-symbolic"
+        } else {
+            "media-playback-start-symbolic"
+        }
+    }
+
+    fn repeat_icon(&self) -> (&'static str, &'static str) {
+        match self.queue.repeat {
+            RepeatMode::Off => ("media-playlist-consecutive-symbolic", "Repeat Off"),
+            RepeatMode::All => ("media-playlist-repeat-symbolic", "Repeat All"),
+            RepeatMode::One => ("media-playlist-repeat-song-symbolic", "Repeat One"),
+        }
+    }
+
+    /// Save the queue and tell the video player about it
+    fn queue_changed(&self, sender: &AsyncComponentSender<Self>) {
+        let db = self.db.clone();
+        let queue = self.queue.clone();
+        relm4::spawn(async move {
+            use crate::services::commands::Command;
+            use crate::services::commands::media_commands::SavePlayQueueCommand;
+
+            if let Err(e) = (SavePlayQueueCommand { db, queue }).execute().await {
+                error!("Failed to save play queue: {}", e);
+            }
+        });
+        sender
+            .output(MusicPlayerOutput::QueueChanged(self.queue.clone()))
+            .ok();
+    }
+
+    /// Stop and drop the player, which hides the mini-player. A new one is made for
+    /// the next track.
+    fn stop_player(&mut self) {
+        if let Some(player) = self.player.take() {
+            relm4::spawn_local(async move {
+                if let Err(e) = player.stop().await {
+                    warn!("Failed to stop music: {}", e);
+                }
+                player.shutdown().ok();
+            });
+        }
+        self.preloaded_index = None;
+        self.state = PlayerState::Idle;
+        self.position = Duration::ZERO;
+        self.duration = None;
+    }
+
+    /// The audio-only player, started the first time music plays. It is set up
+    /// before it is handed out, so the first track already loads audio only.
+    async fn ensure_player(&mut self, sender: &AsyncComponentSender<Self>) -> Option<PlayerHandle> {
+        if let Some(player) = &self.player {
+            return Some(player.clone());
+        }
+
+        // Pick up preference changes made since the last player was closed
+        let config = Config::load().unwrap_or_default();
+        DeviceCapabilities::set_config(&config);
+        self.gapless = config.playback.gapless_audio;
+        self.replaygain = config.playback.replaygain_mode();
+        let (handle, controller) = match PlayerController::new(&config) {
+            Ok(parts) => parts,
+            Err(e) => {
+                sender
+                    .output(MusicPlayerOutput::Error(format!(
+                        "Failed to start the music player: {}",
+                        e
+                    )))
+                    .ok();
+                return None;
+            }
+        };
+        info!("Music player controller initialized");
+
+        // The player holds raw pointers that are not Send, so it runs on the main thread
+        glib::spawn_future_local(async move {
+            controller.run().await;
+        });
+
+        if let Err(e) = handle.set_audio_only(true).await {
+            warn!("Failed to switch the music player to audio only: {}", e);
+        }
+        if let Err(e) = handle.set_replaygain(self.replaygain).await {
+            debug!("ReplayGain unavailable: {}", e);
+        }
+        // Music plays on the same output picked in the video player
+        let audio_device = &config.playback.audio_device;
+        if !audio_device.is_empty()
+            && let Err(e) = handle.set_audio_device(audio_device).await
+        {
+            debug!("Audio output selection unavailable: {}", e);
+        }
+        let audio_effects = config.playback.audio_effects();
+        if !audio_effects.is_default()
+            && let Err(e) = handle.set_audio_effects(audio_effects).await
+        {
+            debug!("Audio effects unavailable: {}", e);
+        }
+
+        let events_handle = handle.clone();
+        sender.command(move |out, shutdown| {
+            shutdown
+                .register(async move {
+                    let mut events = match events_handle.subscribe().await {
+                        Ok(events) => events,
+                        Err(e) => {
+                            error!("Failed to subscribe to music player events: {}", e);
+                            return;
+                        }
+                    };
+                    loop {
+                        let output = match events.recv().await {
+                            Ok(PlayerEvent::StateChanged(state)) => {
+                                MusicPlayerCommandOutput::StateChanged(state)
+                            }
+                            Ok(PlayerEvent::PositionChanged { position, duration }) => {
+                                MusicPlayerCommandOutput::PositionUpdate { position, duration }
+                            }
+                            Ok(PlayerEvent::EndOfStream) => MusicPlayerCommandOutput::EndOfStream,
+                            Ok(PlayerEvent::NextMediaStarted) => {
+                                MusicPlayerCommandOutput::NextMediaStarted
+                            }
+                            Ok(PlayerEvent::Error(message)) => {
+                                MusicPlayerCommandOutput::Error(message)
+                            }
+                            Ok(_) => continue,
+                            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                                debug!("Skipped {} music player events", skipped);
+                                continue;
+                            }
+                            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                        };
+                        if out.send(output).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .drop_on_shutdown()
+        });
+
+        self.player = Some(handle.clone());
+        Some(handle)
+    }
+
+    fn update_track_list(&self) {
+        self.track_list.remove_all();
+
+        for (index, item) in self.queue.items.iter().enumerate() {
+            let is_current = self.queue.current_index == Some(index);
+            let row_box = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(12)
+                .margin_top(8)
+                .margin_bottom(8)
+                .margin_start(12)
+                .margin_end(12)
+                .build();
+
+            let playing_icon = gtk::Image::from_icon_name("media-playback-start-symbolic");
+            playing_icon.set_opacity(if is_current { 1.0 } else { 0.0 });
+            row_box.append(&playing_icon);
+
+            let title = gtk::Label::builder()
+                .label(&item.title)
+                .xalign(0.0)
+                .hexpand(true)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .build();
+            if is_current {
+                title.add_css_class("heading");
+            }
+            row_box.append(&title);
+
+            if let Some(ms) = item.duration_ms {
+                let duration = gtk::Label::builder()
+                    .label(format_duration(Duration::from_millis(ms.max(0) as u64)))
+                    .css_classes(["dim-label", "numeric"])
+                    .build();
+                row_box.append(&duration);
+            }
+
+            let row = gtk::ListBoxRow::builder()
+                .child(&row_box)
+                .activatable(true)
+                .build();
+            self.track_list.append(&row);
+        }
+    }
+}
+
+/// Whether the music player plays this queue item, rather than the video player
+fn plays_as_music(item: &QueueItem) -> bool {
+    item.media_type == "track"
+}
@@ -27,11 +27,19 @@ pub enum MovieDetailsInput {
     LoadMovie(MediaItemId),
     PlayMovie,
     ToggleWatched,
+    /// Add the movie to the play queue, right after the current item or at the end
+    QueueMovie {
+        play_next: bool,
+    },
 }
 
 #[derive(Debug)]
 pub enum MovieDetailsOutput {
     PlayMedia(MediaItemId),
+    QueueMedia {
+        media_id: MediaItemId,
+        play_next: bool,
+    },
     NavigateBack,
 }
 
@@ -276,6 +284,22 @@ impl AsyncComponent for MovieDetailsPage {
 
                                         connect_clicked => MovieDetailsInput::ToggleWatched,
                                     },
+
+                                    gtk::Button {
+                                        add_css_class: "action-button-secondary",
+                                        add_css_class: "interactive-element",
+                                        set_icon_name: "media-skip-forward-symbolic",
+                                        set_tooltip_text: Some("Play next"),
+                                        connect_clicked => MovieDetailsInput::QueueMovie { play_next: true },
+                                    },
+
+                                    gtk::Button {
+                                        add_css_class: "action-button-secondary",
+                                        add_css_class: "interactive-element",
+                                        set_icon_name: "list-add-symbolic",
+                                        set_tooltip_text: Some("Add to queue"),
+                                        connect_clicked => MovieDetailsInput::QueueMovie { play_next: false },
+                                    },
                                 },
                             },
                         },
@@ -383,6 +407,14 @@ impl AsyncComponent for MovieDetailsPage {
                     .output(MovieDetailsOutput::PlayMedia(self.item_id.clone()))
                     .unwrap();
             }
+            MovieDetailsInput::QueueMovie { play_next } => {
+                sender
+                    .output(MovieDetailsOutput::QueueMedia {
+                        media_id: self.item_id.clone(),
+                        play_next,
+                    })
+                    .unwrap();
+            }
             MovieDetailsInput::ToggleWatched => {
                 if let Some(movie) = &mut self.movie {
                     movie.watched = !movie.watched;
@@ -1,17 +1,402 @@
 use crate::config::Config;
-use crate::models::{MediaItemId, PlaylistContext};
-use crate::player::{PlayerController, PlayerHandle, PlayerState};
+use crate::models::audio_effects::{EQUALIZER_FREQUENCIES, EQUALIZER_MAX_GAIN};
+use crate::models::video_adjustments::ASPECT_RATIO_PRESETS;
+use crate::models::{
+    AudioEffects, AudioPreset, Chapter, ChapterMarker, ChapterType, CropRect, ExternalSubtitle,
+    MarkerSkipMode, MediaItemId, PlayQueue, PlaylistContext, QualityOption, QueueItem, RepeatMode,
+    ShowId, StreamInfo, TranscodeStatus, Trickplay, VideoAdjustments,
+};
+use crate::platforms::relm4::components::workers::image_loader::{
+    ImageLoader, ImageLoaderInput, ImageLoaderOutput, ImageRequest, ImageSize,
+};
+use crate::player::cast::{self, CastDevice, CastMedia, CastReceiver, CastStatus};
+use crate::player::mpris::{local_artwork, position_update_due};
+use crate::player::remote_control::report_playback;
+use crate::player::watch_party::drift_correction;
+use crate::player::{
+    DeviceCapabilities, DriftCorrection, MprisAction, MprisMetadata, MprisServer, PartyEvent,
+    PartyMessage, PlaybackStats, PlayerController, PlayerEvent, PlayerHandle, PlayerState,
+    RemoteCommand, RemotePlayback, WatchParty,
+};
+use crate::services::core::CaptureService;
 use adw::prelude::*;
 use gtk::glib::{self, SourceId};
 use gtk::prelude::*;
 use libadwaita as adw;
 use relm4::gtk;
 use relm4::prelude::*;
+use relm4::{Worker, WorkerController};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
-fn format_duration(duration: Duration) -> String {
+/// How often to ping a paused transcode session so the server doesn't reap it
+const TRANSCODE_KEEPALIVE_INTERVAL_SECS: u32 = 30;
+/// Step size for the subtitle and audio delay shortcuts
+const SYNC_OFFSET_STEP_MS: i64 = 100;
+/// How long a changed delay stays on screen
+const SYNC_OFFSET_OSD_DURATION: Duration = Duration::from_millis(1500);
+/// File extensions accepted when loading or dropping subtitle files
+const SUBTITLE_FILE_EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt", "sub"];
+/// Seek distance standing in for one frame when the backend can't step backward
+const FRAME_STEP_FALLBACK: Duration = Duration::from_millis(40);
+/// How far into a chapter "previous chapter" restarts it instead of going back one
+const CHAPTER_RESTART_THRESHOLD: Duration = Duration::from_secs(3);
+/// Sleep timer lengths offered in the menu, in minutes
+const SLEEP_TIMER_MINUTES: &[u64] = &[15, 30, 45, 60, 90];
+/// How long before the sleep timer stops playback the volume starts fading
+const SLEEP_FADE_DURATION: Duration = Duration::from_secs(30);
+/// Picture settings are saved once the sliders have been left alone this long
+const VIDEO_ADJUSTMENTS_SAVE_DELAY: Duration = Duration::from_secs(1);
+/// Sound settings are saved once the equalizer has been left alone this long
+const AUDIO_EFFECTS_SAVE_DELAY: Duration = Duration::from_secs(1);
+/// How often the statistics overlay is refreshed
+const STATS_INTERVAL: Duration = Duration::from_secs(1);
+/// Refreshes between asking the server about its transcode session
+const TRANSCODE_STATUS_TICKS: u32 = 5;
+/// How often a watch party host tells participants where it is
+const WATCH_PARTY_HEARTBEAT: Duration = Duration::from_secs(1);
+/// How long a participant's own change takes precedence over the host's older state
+const WATCH_PARTY_SETTLE_TIME: Duration = Duration::from_secs(2);
+/// How often a receiver is asked where it is while casting
+const CAST_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+/// How long the network is searched for receivers when the cast menu opens
+const CAST_DISCOVERY_TIME: Duration = Duration::from_secs(3);
+/// Status requests in a row a receiver may fail before casting is given up
+const CAST_MAX_FAILURES: u32 = 5;
+/// How often playback is checked for a stream that stopped delivering
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How long playback may go without moving before the stream counts as dropped
+const STALL_TIMEOUT: Duration = Duration::from_secs(20);
+/// Attempts to bring back a dropped stream before the error is shown
+const RECONNECT_MAX_ATTEMPTS: u32 = 5;
+/// Longest wait between reconnect attempts
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// A stream ending this far before its duration was cut off rather than finished
+const EARLY_END_MARGIN: Duration = Duration::from_secs(30);
+
+/// When the sleep timer stops playback
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SleepTimer {
+    /// Stop once this moment is reached
+    At(std::time::Instant),
+    /// Stop when the current item ends instead of moving on to the next one
+    EndOfItem,
+}
+
+/// A receiver playing the current item, with the page acting as its remote
+struct CastSession {
+    device: CastDevice,
+    receiver: Arc<dyn CastReceiver>,
+    muted: bool,
+    /// Set once the receiver reported playback, so it stopping afterwards means the item ended
+    started: bool,
+    failures: u32,
+    status_pending: bool,
+    /// The receiver's own transcode session on the server, stopped when casting ends
+    transcode_session: Option<(MediaItemId, String)>,
+}
+
+/// One setting changed in the video adjustments panel
+#[derive(Debug, Clone, Copy)]
+pub enum VideoSetting {
+    Aspect(Option<f64>),
+    Zoom(f64),
+    PanX(f64),
+    PanY(f64),
+    Brightness(i32),
+    Contrast(i32),
+    Saturation(i32),
+}
+
+impl VideoSetting {
+    fn apply(self, adjustments: &mut VideoAdjustments) {
+        match self {
+            VideoSetting::Aspect(ratio) => adjustments.aspect_ratio = ratio,
+            VideoSetting::Zoom(zoom) => adjustments.zoom = zoom,
+            VideoSetting::PanX(pan) => adjustments.pan_x = pan,
+            VideoSetting::PanY(pan) => adjustments.pan_y = pan,
+            VideoSetting::Brightness(value) => adjustments.brightness = value,
+            VideoSetting::Contrast(value) => adjustments.contrast = value,
+            VideoSetting::Saturation(value) => adjustments.saturation = value,
+        }
+    }
+}
+
+/// Aspect, zoom and pan, crop and colour controls of the video adjustments popover
+struct VideoAdjustmentControls {
+    aspect: gtk::DropDown,
+    zoom: gtk::Scale,
+    pan_x: gtk::Scale,
+    pan_y: gtk::Scale,
+    brightness: gtk::Scale,
+    contrast: gtk::Scale,
+    saturation: gtk::Scale,
+    crop_status: gtk::Label,
+}
+
+impl VideoAdjustmentControls {
+    fn new(sender: &AsyncComponentSender<PlayerPage>) -> (Self, gtk::Popover) {
+        let grid = gtk::Grid::builder()
+            .row_spacing(6)
+            .column_spacing(12)
+            .width_request(320)
+            .margin_top(6)
+            .margin_bottom(6)
+            .margin_start(6)
+            .margin_end(6)
+            .build();
+        let mut row = 0;
+        let mut attach = |label: &str, widget: &gtk::Widget| {
+            grid.attach(
+                &gtk::Label::builder().label(label).xalign(0.0).build(),
+                0,
+                row,
+                1,
+                1,
+            );
+            widget.set_hexpand(true);
+            grid.attach(widget, 1, row, 1, 1);
+            row += 1;
+        };
+
+        let mut aspect_labels = vec!["Original"];
+        aspect_labels.extend(ASPECT_RATIO_PRESETS.iter().map(|(label, _)| *label));
+        let aspect = gtk::DropDown::from_strings(&aspect_labels);
+        {
+            let sender = sender.clone();
+            aspect.connect_selected_notify(move |dropdown| {
+                let ratio = (dropdown.selected() as usize)
+                    .checked_sub(1)
+                    .and_then(|index| ASPECT_RATIO_PRESETS.get(index))
+                    .map(|(_, ratio)| *ratio);
+                sender.input(PlayerInput::AdjustVideo(VideoSetting::Aspect(ratio)));
+            });
+        }
+        attach("Aspect Ratio", aspect.upcast_ref());
+
+        let scale =
+            |min: f64, max: f64, step: f64, digits: i32, setting: fn(f64) -> VideoSetting| {
+                let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, min, max, step);
+                scale.set_digits(digits);
+                scale.set_round_digits(digits);
+                scale.set_draw_value(true);
+                scale.set_value_pos(gtk::PositionType::Right);
+                let sender = sender.clone();
+                scale.connect_value_changed(move |scale| {
+                    sender.input(PlayerInput::AdjustVideo(setting(scale.value())));
+                });
+                scale
+            };
+        let zoom = scale(1.0, 3.0, 0.05, 2, VideoSetting::Zoom);
+        let pan_x = scale(-0.5, 0.5, 0.01, 2, VideoSetting::PanX);
+        let pan_y = scale(-0.5, 0.5, 0.01, 2, VideoSetting::PanY);
+        let brightness = scale(-100.0, 100.0, 1.0, 0, |v| {
+            VideoSetting::Brightness(v.round() as i32)
+        });
+        let contrast = scale(-100.0, 100.0, 1.0, 0, |v| {
+            VideoSetting::Contrast(v.round() as i32)
+        });
+        let saturation = scale(-100.0, 100.0, 1.0, 0, |v| {
+            VideoSetting::Saturation(v.round() as i32)
+        });
+        attach("Zoom", zoom.upcast_ref());
+        attach("Pan Horizontal", pan_x.upcast_ref());
+        attach("Pan Vertical", pan_y.upcast_ref());
+        attach("Brightness", brightness.upcast_ref());
+        attach("Contrast", contrast.upcast_ref());
+        attach("Saturation", saturation.upcast_ref());
+
+        let crop_buttons = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        let detect_button = gtk::Button::with_label("Detect");
+        detect_button.set_tooltip_text(Some("Crop the black bars of the current frame"));
+        let clear_button = gtk::Button::with_label("Clear");
+        crop_buttons.append(&detect_button);
+        crop_buttons.append(&clear_button);
+        attach("Black Bars", crop_buttons.upcast_ref());
+        let crop_status = gtk::Label::builder()
+            .xalign(0.0)
+            .css_classes(["dim-label", "caption"])
+            .build();
+        attach("", crop_status.upcast_ref());
+
+        let reset_button = gtk::Button::with_label("Reset All");
+        reset_button.set_halign(gtk::Align::End);
+        attach("", reset_button.upcast_ref());
+
+        let buttons: [(&gtk::Button, fn() -> PlayerInput); 3] = [
+            (&detect_button, || PlayerInput::DetectBlackBars),
+            (&clear_button, || PlayerInput::ClearCrop),
+            (&reset_button, || PlayerInput::ResetVideoAdjustments),
+        ];
+        for (button, input) in buttons {
+            let sender = sender.clone();
+            button.connect_clicked(move |_| sender.input(input()));
+        }
+
+        let popover = gtk::Popover::builder().child(&grid).build();
+        (
+            Self {
+                aspect,
+                zoom,
+                pan_x,
+                pan_y,
+                brightness,
+                contrast,
+                saturation,
+                crop_status,
+            },
+            popover,
+        )
+    }
+
+    /// Show the settings of a newly loaded item. The change signals this emits carry
+    /// the values the page already has, so they are ignored.
+    fn sync(&self, adjustments: &VideoAdjustments) {
+        let aspect_index = adjustments
+            .aspect_ratio
+            .and_then(|ratio| {
+                ASPECT_RATIO_PRESETS
+                    .iter()
+                    .position(|(_, preset)| (preset - ratio).abs() < 0.001)
+            })
+            .map_or(0, |index| index + 1);
+        self.aspect.set_selected(aspect_index as u32);
+        self.zoom.set_value(adjustments.zoom);
+        self.pan_x.set_value(adjustments.pan_x);
+        self.pan_y.set_value(adjustments.pan_y);
+        self.brightness.set_value(adjustments.brightness as f64);
+        self.contrast.set_value(adjustments.contrast as f64);
+        self.saturation.set_value(adjustments.saturation as f64);
+        self.set_crop(adjustments.crop);
+    }
+
+    fn set_crop(&self, crop: Option<CropRect>) {
+        self.crop_status.set_label(&match crop {
+            Some(crop) => format!("Cropped to {}×{}", crop.width, crop.height),
+            None => "Not cropped".to_string(),
+        });
+    }
+}
+
+/// Small window the video moves into while the main window goes back to browsing
+struct PipWindow {
+    window: gtk::Window,
+    overlay: gtk::Overlay,
+    play_button: gtk::Button,
+}
+
+impl PipWindow {
+    fn new(
+        parent: &adw::ApplicationWindow,
+        video: &gtk::Widget,
+        sender: &AsyncComponentSender<PlayerPage>,
+    ) -> Self {
+        // GTK 4 can't ask the compositor to keep a window above all others, but
+        // transient windows stay above their parent on most window managers
+        let window = gtk::Window::builder()
+            .title("Reel")
+            .default_width(480)
+            .default_height(270)
+            .transient_for(parent)
+            .destroy_with_parent(true)
+            .build();
+        // An empty titlebar keeps the resize borders without showing a header bar
+        window.set_titlebar(Some(&gtk::Box::new(gtk::Orientation::Horizontal, 0)));
+
+        let overlay = gtk::Overlay::new();
+        overlay.add_css_class("video-area");
+        overlay.set_child(Some(video));
+
+        let controls = gtk::Box::new(gtk::Orientation::Horizontal, 6);
+        controls.set_halign(gtk::Align::Center);
+        controls.set_valign(gtk::Align::End);
+        controls.set_margin_bottom(8);
+        controls.add_css_class("osd-overlay");
+        controls.set_visible(false);
+
+        let play_button = gtk::Button::from_icon_name("media-playback-start-symbolic");
+        play_button.set_tooltip_text(Some("Play/Pause"));
+        let back_button = gtk::Button::from_icon_name("view-fullscreen-symbolic");
+        back_button.set_tooltip_text(Some("Back to Player"));
+        let close_button = gtk::Button::from_icon_name("window-close-symbolic");
+        close_button.set_tooltip_text(Some("Stop"));
+        let buttons: [(&gtk::Button, fn() -> PlayerInput); 3] = [
+            (&play_button, || PlayerInput::PlayPause),
+            (&back_button, || PlayerInput::LeavePictureInPicture),
+            (&close_button, || PlayerInput::ClosePictureInPicture),
+        ];
+        for (button, input) in buttons {
+            button.add_css_class("osd");
+            button.add_css_class("circular");
+            let sender = sender.clone();
+            button.connect_clicked(move |_| sender.input(input()));
+            controls.append(button);
+        }
+        overlay.add_overlay(&controls);
+
+        // Controls only show while the pointer is over the video
+        let motion = gtk::EventControllerMotion::new();
+        let controls_enter = controls.clone();
+        motion.connect_enter(move |_, _, _| controls_enter.set_visible(true));
+        motion.connect_leave(move |_| controls.set_visible(false));
+        overlay.add_controller(motion);
+
+        let key_controller = gtk::EventControllerKey::new();
+        let key_sender = sender.clone();
+        key_controller.connect_key_pressed(move |_, key, _, _| match key {
+            gtk::gdk::Key::space => {
+                key_sender.input(PlayerInput::PlayPause);
+                glib::Propagation::Stop
+            }
+            gtk::gdk::Key::Escape | gtk::gdk::Key::p => {
+                key_sender.input(PlayerInput::LeavePictureInPicture);
+                glib::Propagation::Stop
+            }
+            _ => glib::Propagation::Proceed,
+        });
+        window.add_controller(key_controller);
+
+        // Without a header bar, dragging the video moves the window
+        let handle = gtk::WindowHandle::new();
+        handle.set_child(Some(&overlay));
+        window.set_child(Some(&handle));
+
+        let close_sender = sender.clone();
+        window.connect_close_request(move |_| {
+            close_sender.input(PlayerInput::ClosePictureInPicture);
+            glib::Propagation::Stop
+        });
+
+        window.present();
+
+        Self {
+            window,
+            overlay,
+            play_button,
+        }
+    }
+
+    fn set_playing(&self, playing: bool) {
+        self.play_button.set_icon_name(if playing {
+            "media-playback-pause-symbolic"
+        } else {
+            "media-playback-start-symbolic"
+        });
+    }
+
+    /// Close the window and hand back the video widget
+    fn close(self) -> Option<gtk::Widget> {
+        // Detach the video first so it unrealizes while its GL context still exists
+        let video = self.overlay.child();
+        self.overlay.set_child(None::<&gtk::Widget>);
+        self.window.destroy();
+        video
+    }
+}
+
+pub(crate) fn format_duration(duration: Duration) -> String {
     let total_secs = duration.as_secs();
     let hours = total_secs / 3600;
     let minutes = (total_secs % 3600) / 60;
@@ -63,7 +448,9 @@ pub struct PlayerPage {
     last_progress_save: std::time::Instant,
     // Cached config values to avoid reloading config file every second
     config_auto_resume: bool,
+    config_inhibit_while_paused: bool,
     config_resume_threshold_seconds: u64,
+    config_default_subtitle: String,
     config_progress_update_interval_seconds: u64,
     // Playback state
     playback_speed: f64,
@@ -72,6 +459,128 @@ pub struct PlayerPage {
     subtitle_menu_button: gtk::MenuButton,
     current_audio_track: i32,
     current_subtitle_track: i32,
+    // Per-item timing offsets, persisted with the playback progress
+    subtitle_delay_ms: i64,
+    audio_delay_ms: i64,
+    sync_offset_osd: Option<String>,
+    sync_offset_osd_timer: Option<SourceId>,
+    // Quality selection
+    quality_menu_button: gtk::MenuButton,
+    quality_options: Vec<QualityOption>,
+    // Sidecar subtitles of the current stream, reloaded when the quality changes
+    external_subtitles: Vec<ExternalSubtitle>,
+    preferred_external_subtitle: Option<usize>,
+    // Server-side transcode session for the current stream
+    transcode_session: Option<(MediaItemId, String)>,
+    is_transcoding: bool,
+    transcode_keepalive_timer: Option<SourceId>,
+    // Seek preview thumbnails
+    trickplay: Option<Trickplay>,
+    seek_preview_popover: gtk::Popover,
+    seek_preview_picture: gtk::Picture,
+    seek_preview_index: Option<usize>,
+    // Desktop media controls over D-Bus, with the last position sent to them
+    mpris: Option<MprisServer>,
+    mpris_position: Option<(Duration, std::time::Instant)>,
+    // Cookie for the idle/suspend inhibitor held while playing
+    inhibit_cookie: Option<u32>,
+    // Network buffering progress while playback is stalled
+    buffering_percent: Option<u8>,
+    // Recovery of a dropped stream; attempts is 0 unless reconnecting
+    reconnect_attempts: u32,
+    reconnect_timer: Option<SourceId>,
+    reconnect_in_flight: bool,
+    reconnect_position: Duration,
+    // When playback last moved forward, to notice a stream that stopped delivering
+    last_progress_at: std::time::Instant,
+    stall_timer: Option<SourceId>,
+    // Index into quality_options of the stream being played
+    quality_index: usize,
+    config_reconnect_lower_quality: bool,
+    // Skippable intro, credits, recap and preview segments of the current item
+    markers: Vec<ChapterMarker>,
+    config_skip_modes: HashMap<ChapterType, MarkerSkipMode>,
+    config_auto_skip_delay: u64,
+    config_auto_play_delay: u64,
+    // Skip modes remembered for the current show, overriding the configured ones
+    show_skip_modes: HashMap<ChapterType, MarkerSkipMode>,
+    // Marker the position is inside, and the seconds left before it is skipped
+    active_marker: Option<ChapterMarker>,
+    skip_countdown: Option<u64>,
+    // Seconds left before the next episode starts once the credits roll
+    next_episode_countdown: Option<u64>,
+    next_episode_cancelled: bool,
+    countdown_timer: Option<SourceId>,
+    // Chapters of the current item; the server's replace the ones embedded in the file
+    chapters: Vec<Chapter>,
+    chapters_from_server: bool,
+    current_chapter: Option<usize>,
+    chapter_menu_button: gtk::MenuButton,
+    chapter_list: gtk::ListBox,
+    chapter_thumbnails: HashMap<String, gtk::Picture>,
+    image_loader: WorkerController<ImageLoader>,
+    // Sleep timer and the volume fade leading up to it
+    sleep_timer: Option<SleepTimer>,
+    sleep_timer_source: Option<SourceId>,
+    sleep_fade: Option<f64>,
+    config_sleep_timer_fade_out: bool,
+    sleep_timer_menu_button: gtk::MenuButton,
+    // User-edited play queue, mirrored into the playlist context while playing from it
+    play_queue: PlayQueue,
+    /// Set once the saved queue loaded or the queue changed here, whichever came first
+    queue_loaded: bool,
+    /// Items queued before that, added on top of the saved queue
+    pending_queue_items: Vec<(QueueItem, bool)>,
+    queue_menu_button: gtk::MenuButton,
+    queue_list: gtk::ListBox,
+    queue_shuffle_button: gtk::ToggleButton,
+    queue_repeat_button: gtk::Button,
+    // Floating window the video is moved into while browsing
+    pip: Option<PipWindow>,
+    // Aspect, zoom, crop and colour settings of the current item
+    video_adjustments: VideoAdjustments,
+    video_adjustment_controls: VideoAdjustmentControls,
+    video_adjustments_menu_button: gtk::MenuButton,
+    video_adjustments_save_timer: Option<SourceId>,
+    // Current stream, as the server described it
+    stream_info: Option<StreamInfo>,
+    // Screenshots, and the marked part of the stream to export as a clip
+    clip_start: Option<Duration>,
+    clip_end: Option<Duration>,
+    capture_menu_button: gtk::MenuButton,
+    export_clip_action: gtk::gio::SimpleAction,
+    // A-B loop, repeating once both ends are marked
+    loop_start: Option<Duration>,
+    loop_end: Option<Duration>,
+    // Statistics overlay, refreshed every second while shown
+    stats: Option<PlaybackStats>,
+    stats_timer: Option<SourceId>,
+    stats_ticks: u32,
+    transcode_status: Option<TranscodeStatus>,
+    /// Audio output in use, empty for the system default
+    audio_device: String,
+    audio_effects: AudioEffects,
+    audio_effects_save_timer: Option<SourceId>,
+    // Watch party this instance hosts or has joined
+    watch_party: Option<WatchParty>,
+    watch_party_menu_button: gtk::MenuButton,
+    watch_party_heartbeat: Option<SourceId>,
+    // Item the party is on, so loads it asked for aren't announced back to it
+    party_media_id: Option<MediaItemId>,
+    // When this participant last changed playback itself
+    party_action_at: Option<std::time::Instant>,
+    // Speed multiplier while catching up with the host, 1.0 when in sync
+    party_rate: f64,
+    // Where the loading item starts instead of resuming, applied once it is ready;
+    // set by remote apps and by giving up on reconnecting, for a manual retry
+    start_position: Option<Duration>,
+    // Receiver the current item is cast to, and those found the last time the menu opened
+    cast: Option<CastSession>,
+    cast_devices: Vec<CastDevice>,
+    finding_cast_devices: bool,
+    cast_menu: gtk::gio::Menu,
+    cast_menu_button: gtk::MenuButton,
+    cast_status_timer: Option<SourceId>,
 }
 
 impl PlayerPage {
@@ -80,10 +589,18 @@ impl PlayerPage {
             let player_clone = player.clone();
             let audio_menu_button = self.audio_menu_button.clone();
             let current_track = self.current_audio_track;
+            let current_device = self.audio_device.clone();
+            let audio_effects = self.audio_effects;
             let sender = sender.clone();
 
             glib::spawn_future_local(async move {
                 let tracks = player_clone.get_audio_tracks().await.unwrap_or_default();
+                let capabilities = player_clone.capabilities().await.unwrap_or_default();
+                let devices = if capabilities.audio_output_selection {
+                    player_clone.get_audio_devices().await.unwrap_or_default()
+                } else {
+                    Vec::new()
+                };
 
                 if tracks.is_empty() {
                     // No audio tracks available, disable the button
@@ -102,11 +619,88 @@ impl PlayerPage {
                         menu.append_item(&item);
                     }
 
+                    // Only worth offering with somewhere other than the default to go
+                    if !devices.is_empty() {
+                        let device_section = gtk::gio::Menu::new();
+                        let item = gtk::gio::MenuItem::new(Some("System Default"), None);
+                        item.set_action_and_target_value(
+                            Some("player.audio-device"),
+                            Some(&"".to_variant()),
+                        );
+                        device_section.append_item(&item);
+                        for device in &devices {
+                            let item = gtk::gio::MenuItem::new(Some(&device.description), None);
+                            item.set_action_and_target_value(
+                                Some("player.audio-device"),
+                                Some(&device.name.to_variant()),
+                            );
+                            device_section.append_item(&item);
+                        }
+                        menu.append_section(Some("Output"), &device_section);
+                    }
+
+                    if capabilities.audio_effects {
+                        let sound_section = gtk::gio::Menu::new();
+                        for preset in AudioPreset::ALL {
+                            let item = gtk::gio::MenuItem::new(Some(preset.label()), None);
+                            item.set_action_and_target_value(
+                                Some("player.audio-preset"),
+                                Some(&preset.as_str().to_variant()),
+                            );
+                            sound_section.append_item(&item);
+                        }
+                        let equalizer_menu = gtk::gio::Menu::new();
+                        let item = gtk::gio::MenuItem::new(None, None);
+                        item.set_attribute_value("custom", Some(&"equalizer".to_variant()));
+                        equalizer_menu.append_item(&item);
+                        sound_section.append_submenu(Some("Equalizer"), &equalizer_menu);
+                        menu.append_section(Some("Sound"), &sound_section);
+                    }
+
                     // Create popover from menu model
                     let popover = gtk::PopoverMenu::from_model(Some(&menu));
+                    if capabilities.audio_effects {
+                        popover.add_child(
+                            &Self::equalizer_controls(&audio_effects, &sender),
+                            "equalizer",
+                        );
+                    }
 
                     // Add actions for each track
                     let action_group = gtk::gio::SimpleActionGroup::new();
+
+                    // Radio items showing the output in use
+                    let device_action = gtk::gio::SimpleAction::new_stateful(
+                        "audio-device",
+                        Some(glib::VariantTy::STRING),
+                        &current_device.to_variant(),
+                    );
+                    let sender_clone = sender.clone();
+                    device_action.connect_activate(move |action, parameter| {
+                        if let Some(name) = parameter.and_then(|p| p.get::<String>()) {
+                            action.set_state(&name.to_variant());
+                            sender_clone.input(PlayerInput::SetAudioDevice(name));
+                        }
+                    });
+                    action_group.add_action(&device_action);
+
+                    let preset_action = gtk::gio::SimpleAction::new_stateful(
+                        "audio-preset",
+                        Some(glib::VariantTy::STRING),
+                        &audio_effects.preset.as_str().to_variant(),
+                    );
+                    let sender_clone = sender.clone();
+                    preset_action.connect_activate(move |action, parameter| {
+                        if let Some(preset) = parameter
+                            .and_then(|p| p.get::<String>())
+                            .and_then(|p| AudioPreset::parse(&p))
+                        {
+                            action.set_state(&preset.as_str().to_variant());
+                            sender_clone.input(PlayerInput::SetAudioPreset(preset));
+                        }
+                    });
+                    action_group.add_action(&preset_action);
+
                     for (track_id, _) in &tracks {
                         let action_name = format!("audio-track-{}", track_id);
                         let action = gtk::gio::SimpleAction::new(&action_name, None);
@@ -136,1762 +730,7022 @@ impl PlayerPage {
             glib::spawn_future_local(async move {
                 let tracks = player_clone.get_subtitle_tracks().await.unwrap_or_default();
 
-                if tracks.is_empty() || tracks.len() == 1 {
-                    // No subtitle tracks available (only "None" option), disable the button
-                    subtitle_menu_button.set_sensitive(false);
-                    subtitle_menu_button.set_popover(None::<&gtk::Popover>);
-                } else {
-                    subtitle_menu_button.set_sensitive(true);
+                // Always enabled so a subtitle file can be loaded even without tracks
+                subtitle_menu_button.set_sensitive(true);
 
-                    // Create menu
-                    let menu = gtk::gio::Menu::new();
+                // Create menu
+                let menu = gtk::gio::Menu::new();
 
+                // Only list tracks when there is more than the "None" option
+                if tracks.len() > 1 {
                     for (track_id, track_name) in &tracks {
                         let item = gtk::gio::MenuItem::new(Some(&track_name), None);
                         let action_name = format!("player.subtitle-track-{}", track_id);
                         item.set_action_and_target_value(Some(&action_name), None);
                         menu.append_item(&item);
                     }
+                }
 
-                    // Create popover from menu model
-                    let popover = gtk::PopoverMenu::from_model(Some(&menu));
+                let file_section = gtk::gio::Menu::new();
+                file_section.append(
+                    Some("Load Subtitle File…"),
+                    Some("player.load-subtitle-file"),
+                );
+                menu.append_section(None, &file_section);
+
+                // Create popover from menu model
+                let popover = gtk::PopoverMenu::from_model(Some(&menu));
+
+                // Add actions for each track
+                let action_group = gtk::gio::SimpleActionGroup::new();
+                for (track_id, _) in &tracks {
+                    let action_name = format!("subtitle-track-{}", track_id);
+                    let action = gtk::gio::SimpleAction::new(&action_name, None);
+                    let sender_clone = sender.clone();
+                    let track_id_copy = *track_id;
+                    action.connect_activate(move |_, _| {
+                        sender_clone.input(PlayerInput::SetSubtitleTrack(track_id_copy));
+                    });
+                    action_group.add_action(&action);
+                }
 
-                    // Add actions for each track
-                    let action_group = gtk::gio::SimpleActionGroup::new();
-                    for (track_id, _) in &tracks {
-                        let action_name = format!("subtitle-track-{}", track_id);
-                        let action = gtk::gio::SimpleAction::new(&action_name, None);
-                        let sender_clone = sender.clone();
-                        let track_id_copy = *track_id;
-                        action.connect_activate(move |_, _| {
-                            sender_clone.input(PlayerInput::SetSubtitleTrack(track_id_copy));
-                        });
-                        action_group.add_action(&action);
-                    }
+                let load_action = gtk::gio::SimpleAction::new("load-subtitle-file", None);
+                let sender_clone = sender.clone();
+                load_action.connect_activate(move |_, _| {
+                    sender_clone.input(PlayerInput::OpenSubtitleFile);
+                });
+                action_group.add_action(&load_action);
 
-                    // Insert the action group
-                    subtitle_menu_button.insert_action_group("player", Some(&action_group));
-                    subtitle_menu_button.set_popover(Some(&popover));
-                }
+                // Insert the action group
+                subtitle_menu_button.insert_action_group("player", Some(&action_group));
+                subtitle_menu_button.set_popover(Some(&popover));
             });
         }
     }
 
-    fn update_playlist_position_label(&self, context: &PlaylistContext) {
-        match context {
-            PlaylistContext::SingleItem => {
-                self.playlist_position_label.set_text("");
-            }
-            PlaylistContext::TvShow {
-                show_title,
-                current_index,
-                episodes,
-                ..
-            } => {
-                if let Some(current_episode) = episodes.get(*current_index) {
-                    let text = format!(
-                        "{} - S{}E{} - Episode {} of {}",
-                        show_title,
-                        current_episode.season_number,
-                        current_episode.episode_number,
-                        current_index + 1,
-                        episodes.len()
-                    );
-                    self.playlist_position_label.set_text(&text);
-                }
+    /// Bring a freshly loaded stream back to how it was left: its sidecar subtitles,
+    /// saved sync offsets and picture adjustments, then refresh the track menus
+    async fn restore_stream_state(
+        db: &crate::db::connection::DatabaseConnection,
+        media_id: &MediaItemId,
+        stream_info: &StreamInfo,
+        default_subtitle: &str,
+        player_handle: &PlayerHandle,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        Self::load_external_subtitles(
+            player_handle,
+            &stream_info.external_subtitles,
+            stream_info.preferred_external_subtitle(default_subtitle),
+        )
+        .await;
+        Self::restore_sync_offsets(db, media_id, player_handle, sender).await;
+        Self::restore_video_adjustments(db, media_id, player_handle, sender).await;
+        sender.input(PlayerInput::UpdateTrackMenus);
+    }
+
+    /// Hand sidecar subtitles to the player, switching to the preferred one
+    async fn load_external_subtitles(
+        player_handle: &PlayerHandle,
+        subtitles: &[ExternalSubtitle],
+        preferred: Option<usize>,
+    ) {
+        // Load the preferred one first, the GStreamer backend only keeps a single subtitle file
+        let order = preferred
+            .into_iter()
+            .chain((0..subtitles.len()).filter(|&i| Some(i) != preferred));
+
+        for index in order {
+            let subtitle = &subtitles[index];
+            if let Err(e) = player_handle
+                .add_subtitle(
+                    &subtitle.url,
+                    &subtitle.title,
+                    subtitle.language.as_deref(),
+                    Some(index) == preferred,
+                )
+                .await
+            {
+                warn!(
+                    "Failed to load external subtitle '{}': {}",
+                    subtitle.title, e
+                );
             }
         }
     }
-}
 
-impl std::fmt::Debug for PlayerPage {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("PlayerPage")
-            .field("media_item_id", &self.media_item_id)
-            .field("player_state", &self.player_state)
-            .field("position", &self.position)
-            .field("duration", &self.duration)
-            .field("volume", &self.volume)
-            .finish()
+    /// Push a change to desktop media controls, if the MPRIS service is running
+    fn update_mpris<F, Fut>(&self, update: F)
+    where
+        F: FnOnce(MprisServer) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = anyhow::Result<()>> + Send,
+    {
+        if let Some(mpris) = self.mpris.clone() {
+            relm4::spawn(async move {
+                if let Err(e) = update(mpris).await {
+                    debug!("Failed to update MPRIS state: {}", e);
+                }
+            });
+        }
     }
-}
-
-#[derive(Debug)]
-pub enum PlayerInput {
-    LoadMedia(MediaItemId),
-    LoadMediaWithContext {
-        media_id: MediaItemId,
-        context: PlaylistContext,
-    },
-    UpdateTrackMenus,
-    SetAudioTrack(i32),
-    SetSubtitleTrack(i32),
-    PlayPause,
-    Stop,
-    Seek(Duration),
-    SetVolume(f64),
-    UpdatePosition,
-    ToggleFullscreen,
-    ShowControls,
-    HideControls,
-    ResetControlsTimer,
-    Previous,
-    Next,
-    ShowCursor,
-    HideCursor,
-    ResetCursorTimer,
-    StartSeeking,
-    StopSeeking,
-    UpdateSeekPreview(Duration),
-    Rewind,
-    Forward,
-    RetryLoad,
-    ClearError,
-    ShowError(String),
-    EscapePressed,
-    NavigateBack,
-    // Speed controls
-    SpeedUp,
-    SpeedDown,
-    SpeedReset,
-    // Frame stepping
-    FrameStepForward,
-    FrameStepBackward,
-    // Audio controls
-    ToggleMute,
-    VolumeUp,
-    VolumeDown,
-    // Track cycling
-    CycleSubtitleTrack,
-    CycleAudioTrack,
-    // Control visibility
-    ToggleControlsVisibility,
-    // Relative seeking
-    SeekRelative(i64), // Positive for forward, negative for backward
-}
 
-#[derive(Debug, Clone)]
-pub enum PlayerOutput {
-    NavigateBack,
-    MediaLoaded,
-    Error(String),
-    WindowStateChanged { width: i32, height: i32 },
-}
-
-pub enum PlayerCommandOutput {
-    StateChanged(PlayerState),
-    PositionUpdate {
-        position: Option<Duration>,
-        duration: Option<Duration>,
-        state: PlayerState,
-    },
-    LoadError(String),
-}
+    /// Describe the current item and playlist position to desktop media controls
+    fn update_mpris_metadata(&self) {
+        let Some(media_id) = self.media_item_id.clone() else {
+            return;
+        };
+        let db = (*self.db).clone();
+        let (can_go_next, can_go_previous, playlist_show_title) = match &self.playlist_context {
+            Some(context) => (
+                self.next_target(false).is_some(),
+                context.has_previous(),
+                match context {
+                    PlaylistContext::TvShow { show_title, .. } => Some(show_title.clone()),
+                    PlaylistContext::SingleItem | PlaylistContext::Queue(_) => None,
+                },
+            ),
+            None => (self.play_queue.next_index().is_some(), false, None),
+        };
 
-impl std::fmt::Debug for PlayerCommandOutput {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::StateChanged(state) => write!(f, "StateChanged({:?})", state),
-            Self::PositionUpdate {
-                position,
-                duration,
-                state,
-            } => {
-                write!(
-                    f,
-                    "PositionUpdate {{ position: {:?}, duration: {:?}, state: {:?} }}",
-                    position, duration, state
-                )
+        self.update_mpris(move |mpris| async move {
+            use crate::services::commands::Command;
+            use crate::services::commands::media_commands::GetMediaItemCommand;
+
+            if let Some(item) = (GetMediaItemCommand {
+                db,
+                item_id: media_id,
+            })
+            .execute()
+            .await?
+            {
+                let mut metadata = MprisMetadata::from_media_item(&item);
+                if metadata.show_title.is_none() {
+                    metadata.show_title = playlist_show_title;
+                }
+                metadata.artwork_url = match metadata.artwork_url {
+                    Some(url) => local_artwork(&url).await,
+                    None => None,
+                };
+                mpris.set_metadata(metadata).await?;
             }
-            Self::LoadError(msg) => write!(f, "LoadError({})", msg),
+            mpris.set_navigation(can_go_next, can_go_previous).await
+        });
+    }
+
+    /// Seek a freshly loaded item to its saved position, if auto-resume is on and it
+    /// got further than the threshold. Returns where playback resumed.
+    async fn resume_saved_position(
+        db: &crate::db::connection::DatabaseConnection,
+        media_id: &MediaItemId,
+        player_handle: &PlayerHandle,
+        auto_resume: bool,
+        resume_threshold_seconds: u64,
+    ) -> Option<Duration> {
+        use crate::services::commands::Command;
+        use crate::services::commands::GetPlaybackProgressCommand;
+
+        if !auto_resume {
+            return None;
         }
+        let (position_ms, _duration_ms) = (GetPlaybackProgressCommand {
+            db: db.clone(),
+            media_id: media_id.clone(),
+            user_id: "default".to_string(), // TODO: Get actual user ID
+        })
+        .execute()
+        .await
+        .ok()??;
+
+        // Only resume if we've watched more than the threshold
+        if position_ms <= (resume_threshold_seconds as i64) * 1000 {
+            return None;
+        }
+        let resume_position = Duration::from_millis(position_ms as u64);
+        info!("Resuming playback from {:?}", resume_position);
+        if let Err(e) = player_handle.seek(resume_position).await {
+            warn!("Failed to seek to saved position: {}", e);
+            return None;
+        }
+        Some(resume_position)
     }
-}
 
-#[relm4::component(pub async)]
-impl AsyncComponent for PlayerPage {
-    type Init = (
-        Option<MediaItemId>,
-        Arc<crate::db::connection::DatabaseConnection>,
-        adw::ApplicationWindow,
-    );
-    type Input = PlayerInput;
-    type Output = PlayerOutput;
-    type CommandOutput = PlayerCommandOutput;
+    /// Re-apply the subtitle and audio delays saved for a media item
+    async fn restore_sync_offsets(
+        db: &crate::db::connection::DatabaseConnection,
+        media_id: &MediaItemId,
+        player_handle: &PlayerHandle,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        use crate::services::commands::Command;
+        use crate::services::commands::media_commands::GetSyncOffsetsCommand;
+
+        let (subtitle_delay_ms, audio_delay_ms) = (GetSyncOffsetsCommand {
+            db: db.clone(),
+            media_id: media_id.clone(),
+        })
+        .execute()
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load sync offsets: {}", e);
+            (0, 0)
+        });
 
-    view! {
-        gtk::Overlay {
-            set_vexpand: true,
-            set_hexpand: true,
+        // Always apply, mpv keeps the previous item's offsets otherwise
+        if let Err(e) = player_handle.set_subtitle_delay(subtitle_delay_ms).await {
+            debug!("Failed to apply subtitle delay: {}", e);
+        }
+        if let Err(e) = player_handle.set_audio_delay(audio_delay_ms).await {
+            debug!("Failed to apply audio delay: {}", e);
+        }
 
-            // Video container as the main child
-            model.video_container.clone() {
-                set_vexpand: true,
-                set_hexpand: true,
-                set_valign: gtk::Align::Fill,
-                set_halign: gtk::Align::Fill,
-                add_css_class: "video-area",
-            },
+        sender.input(PlayerInput::SyncOffsetsLoaded {
+            subtitle_delay_ms,
+            audio_delay_ms,
+        });
+    }
 
-            // Top left OSD controls (back button)
-            add_overlay = &gtk::Box {
-                set_halign: gtk::Align::Start,
-                set_valign: gtk::Align::Start,
-                set_margin_top: 12,
-                set_margin_start: 12,
-                add_css_class: "osd-overlay",
-                #[watch]
-                set_visible: model.show_controls,
-                #[watch]
-                set_opacity: if model.show_controls { 1.0 } else { 0.0 },
+    /// Re-apply the picture settings saved for a media item
+    async fn restore_video_adjustments(
+        db: &crate::db::connection::DatabaseConnection,
+        media_id: &MediaItemId,
+        player_handle: &PlayerHandle,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        use crate::services::commands::Command;
+        use crate::services::commands::media_commands::GetVideoAdjustmentsCommand;
+
+        let adjustments = (GetVideoAdjustmentsCommand {
+            db: db.clone(),
+            media_id: media_id.clone(),
+        })
+        .execute()
+        .await
+        .unwrap_or_else(|e| {
+            warn!("Failed to load video adjustments: {}", e);
+            VideoAdjustments::default()
+        });
 
-                gtk::Button {
-                    set_icon_name: "go-previous-symbolic",
-                    set_tooltip_text: Some("Back"),
-                    add_css_class: "osd",
-                    add_css_class: "circular",
-                    connect_clicked[sender] => move |_| {
-                        sender.input(PlayerInput::NavigateBack);
-                    },
-                },
-            },
+        // Always apply, the backends keep the previous item's settings otherwise
+        if let Err(e) = player_handle.set_video_adjustments(adjustments).await {
+            debug!("Failed to apply video adjustments: {}", e);
+        }
 
-            // Top right OSD controls (fullscreen button)
-            add_overlay = &gtk::Box {
-                set_halign: gtk::Align::End,
-                set_valign: gtk::Align::Start,
-                set_margin_top: 12,
-                set_margin_end: 12,
-                add_css_class: "osd-overlay",
-                #[watch]
-                set_visible: model.show_controls,
-                #[watch]
-                set_opacity: if model.show_controls { 1.0 } else { 0.0 },
+        sender.input(PlayerInput::VideoAdjustmentsLoaded(adjustments));
+    }
 
-                gtk::Button {
-                    #[watch]
-                    set_icon_name: if model.is_fullscreen {
-                        "view-restore-symbolic"
-                    } else {
-                        "view-fullscreen-symbolic"
-                    },
-                    set_tooltip_text: Some("Toggle Fullscreen"),
-                    add_css_class: "osd",
-                    add_css_class: "circular",
-                    connect_clicked => PlayerInput::ToggleFullscreen,
-                },
+    /// Apply a change to the picture settings and save them once the user settles
+    fn update_video_adjustments(
+        &mut self,
+        adjustments: VideoAdjustments,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        if adjustments == self.video_adjustments {
+            return;
+        }
+        self.video_adjustments = adjustments;
+
+        if let Some(player) = &self.player {
+            let player_handle = player.clone();
+            glib::spawn_future_local(async move {
+                if let Err(e) = player_handle.set_video_adjustments(adjustments).await {
+                    warn!("Failed to set video adjustments: {}", e);
+                }
+            });
+        }
+
+        let Some(media_id) = self.media_item_id.clone() else {
+            return;
+        };
+        if let Some(timer) = self.video_adjustments_save_timer.take() {
+            timer.remove();
+        }
+        // The item is captured now, so a save still pending after switching items lands on the right one
+        let sender = sender.clone();
+        self.video_adjustments_save_timer = Some(glib::timeout_add_local_once(
+            VIDEO_ADJUSTMENTS_SAVE_DELAY,
+            move || {
+                sender.input(PlayerInput::SaveVideoAdjustments {
+                    media_id,
+                    adjustments,
+                });
             },
+        ));
+    }
 
-            // Error message overlay
-            add_overlay = &gtk::Box {
-                set_orientation: gtk::Orientation::Vertical,
-                set_halign: gtk::Align::Center,
-                set_valign: gtk::Align::Center,
-                set_spacing: 20,
-                #[watch]
-                set_visible: model.error_message.is_some(),
-                add_css_class: "osd",
-                add_css_class: "error-overlay",
+    /// Persist the current delays for the playing item
+    fn save_sync_offsets(&self) {
+        let Some(media_id) = self.media_item_id.clone() else {
+            return;
+        };
+        let db = (*self.db).clone();
+        let subtitle_delay_ms = self.subtitle_delay_ms;
+        let audio_delay_ms = self.audio_delay_ms;
+
+        relm4::spawn(async move {
+            use crate::services::commands::Command;
+            use crate::services::commands::media_commands::SaveSyncOffsetsCommand;
+
+            let command = SaveSyncOffsetsCommand {
+                db,
+                media_id,
+                subtitle_delay_ms,
+                audio_delay_ms,
+            };
+
+            if let Err(e) = command.execute().await {
+                warn!("Failed to save sync offsets: {}", e);
+            }
+        });
+    }
 
-                gtk::Image {
-                    set_icon_name: Some("dialog-error-symbolic"),
-                    set_pixel_size: 64,
-                    add_css_class: "error-icon",
-                },
+    fn populate_quality_menu(&self, sender: AsyncComponentSender<Self>) {
+        if self.quality_options.len() <= 1 {
+            // Nothing to choose from, disable the button
+            self.quality_menu_button.set_sensitive(false);
+            self.quality_menu_button.set_popover(None::<&gtk::Popover>);
+            return;
+        }
 
-                gtk::Label {
-                    #[watch]
-                    set_label: model.error_message.as_deref().unwrap_or("An error occurred"),
-                    set_wrap: true,
-                    set_max_width_chars: 50,
-                    add_css_class: "title-2",
-                },
+        self.quality_menu_button.set_sensitive(true);
 
-                gtk::Box {
-                    set_orientation: gtk::Orientation::Horizontal,
-                    set_spacing: 12,
-                    set_halign: gtk::Align::Center,
+        // Create menu
+        let menu = gtk::gio::Menu::new();
+        let action_group = gtk::gio::SimpleActionGroup::new();
 
-                    gtk::Button {
-                        set_label: "Retry",
-                        add_css_class: "suggested-action",
-                        add_css_class: "pill",
-                        connect_clicked => PlayerInput::RetryLoad,
-                    },
+        for (index, option) in self.quality_options.iter().enumerate() {
+            let item = gtk::gio::MenuItem::new(Some(&option.name), None);
+            let action_name = format!("player.quality-{}", index);
+            item.set_action_and_target_value(Some(&action_name), None);
+            menu.append_item(&item);
 
-                    gtk::Button {
-                        set_label: "Go Back",
-                        add_css_class: "pill",
-                        connect_clicked[sender] => move |_| {
-                            sender.input(PlayerInput::NavigateBack);
-                        },
-                    },
-                },
-            },
+            let action = gtk::gio::SimpleAction::new(&format!("quality-{}", index), None);
+            let sender_clone = sender.clone();
+            action.connect_activate(move |_, _| {
+                sender_clone.input(PlayerInput::SetQuality(index));
+            });
+            action_group.add_action(&action);
+        }
 
-            // Bottom controls overlay with full control layout
-            add_overlay = &gtk::Box {
-                set_orientation: gtk::Orientation::Vertical,
-                set_halign: gtk::Align::Center,
-                set_valign: gtk::Align::End,
-                set_margin_all: 20,
-                set_width_request: 700,
-                #[watch]
-                set_visible: model.show_controls && model.error_message.is_none(),
-                #[watch]
-                set_opacity: if model.show_controls && model.error_message.is_none() { 1.0 } else { 0.0 },
-                add_css_class: "osd",
-                add_css_class: "player-controls",
-                add_css_class: "minimal",
+        let popover = gtk::PopoverMenu::from_model(Some(&menu));
+        self.quality_menu_button
+            .insert_action_group("player", Some(&action_group));
+        self.quality_menu_button.set_popover(Some(&popover));
+    }
 
-                // Playlist position indicator
-                model.playlist_position_label.clone() {
-                    add_css_class: "dim-label",
-                    set_margin_bottom: 4,
-                },
+    /// Stop the server-side transcode for the current stream, if there is one
+    fn stop_transcode_session(&mut self) {
+        if let Some(timer) = self.transcode_keepalive_timer.take() {
+            timer.remove();
+        }
 
-                // Progress bar with time labels
-                gtk::Box {
-                    set_orientation: gtk::Orientation::Horizontal,
-                    set_spacing: 8,
-                    set_margin_bottom: 8,
+        let was_transcoding = std::mem::take(&mut self.is_transcoding);
+        if let Some((media_id, session_id)) = self.transcode_session.take()
+            && was_transcoding
+        {
+            let db = (*self.db).clone();
+            relm4::spawn(async move {
+                use crate::services::commands::Command;
+                use crate::services::commands::media_commands::StopTranscodeSessionCommand;
+
+                let command = StopTranscodeSessionCommand {
+                    db,
+                    media_item_id: media_id,
+                    session_id,
+                };
 
-                    model.position_label.clone() {
-                        add_css_class: "dim-label",
-                        set_width_chars: 7,
-                    },
+                if let Err(e) = command.execute().await {
+                    debug!("Failed to stop transcode session: {}", e);
+                }
+            });
+        }
+    }
 
-                    model.seek_bar.clone() {
-                        set_hexpand: true,
-                        set_draw_value: false,
-                        add_css_class: "progress-bar",
-                    },
+    /// Ping the transcode session while paused so long pauses don't kill the stream
+    fn update_transcode_keepalive(&mut self, sender: &AsyncComponentSender<Self>) {
+        let needs_keepalive = self.is_transcoding
+            && self.transcode_session.is_some()
+            && self.player_state == PlayerState::Paused;
 
-                    model.duration_label.clone() {
-                        add_css_class: "dim-label",
-                        set_width_chars: 7,
-                    },
+        if needs_keepalive && self.transcode_keepalive_timer.is_none() {
+            let sender = sender.clone();
+            self.transcode_keepalive_timer = Some(glib::timeout_add_seconds_local(
+                TRANSCODE_KEEPALIVE_INTERVAL_SECS,
+                move || {
+                    sender.input(PlayerInput::PingTranscodeSession);
+                    glib::ControlFlow::Continue
                 },
+            ));
+        } else if !needs_keepalive && let Some(timer) = self.transcode_keepalive_timer.take() {
+            timer.remove();
+        }
+    }
 
-                // Main controls row with three sections
-                gtk::Box {
-                    set_orientation: gtk::Orientation::Horizontal,
-                    set_spacing: 0,
+    /// Keep the screen from blanking and the system from suspending while playing
+    fn update_inhibit(&mut self) {
+        let change = inhibit_change(
+            &self.player_state,
+            self.config_inhibit_while_paused,
+            self.inhibit_cookie.is_some(),
+        );
 
-                    // Left section: Volume control
-                    gtk::Box {
-                        set_orientation: gtk::Orientation::Horizontal,
-                        set_width_request: 150,
-                        set_halign: gtk::Align::Start,
-                        set_spacing: 4,
+        if change == Some(true) {
+            let cookie = relm4::main_application().inhibit(
+                Some(&self.window),
+                gtk::ApplicationInhibitFlags::IDLE | gtk::ApplicationInhibitFlags::SUSPEND,
+                Some("Playing media"),
+            );
+            // A zero cookie means the session refused the request
+            if cookie == 0 {
+                warn!("Failed to inhibit screensaver during playback");
+            } else {
+                debug!("Inhibited screensaver (cookie {})", cookie);
+                self.inhibit_cookie = Some(cookie);
+            }
+        } else if change == Some(false) {
+            self.release_inhibit();
+        }
+    }
 
-                        gtk::Image {
-                            set_icon_name: Some("audio-volume-high-symbolic"),
-                            set_pixel_size: 16,
-                            add_css_class: "dim-label",
-                        },
+    /// Move the video back from the picture-in-picture window, stopping playback
+    /// unless the player page is shown again
+    fn close_pip(&mut self, return_to_player: bool, sender: &AsyncComponentSender<Self>) {
+        let Some(pip) = self.pip.take() else {
+            return;
+        };
+        if let Some(video) = pip.close() {
+            self.video_container.append(&video);
+        }
+        if !return_to_player {
+            sender.input(PlayerInput::Stop);
+        }
+        sender
+            .output(PlayerOutput::PictureInPictureEnded { return_to_player })
+            .unwrap();
+    }
 
-                        model.volume_slider.clone() {
-                            set_size_request: (100, -1),
-                            set_draw_value: false,
-                            add_css_class: "volume-slider",
-                            set_value: 1.0,
-                        },
-                    },
+    fn release_inhibit(&mut self) {
+        if let Some(cookie) = self.inhibit_cookie.take() {
+            relm4::main_application().uninhibit(cookie);
+            debug!("Released screensaver inhibit (cookie {})", cookie);
+        }
+    }
 
-                    // Center section: Playback controls
-                    gtk::Box {
-                        set_orientation: gtk::Orientation::Horizontal,
-                        set_spacing: 4,
-                        set_halign: gtk::Align::Center,
-                        set_hexpand: true,
+    /// How markers of a type are handled, preferring what the current show remembers
+    fn skip_mode(&self, marker_type: ChapterType) -> MarkerSkipMode {
+        self.show_skip_modes
+            .get(&marker_type)
+            .or_else(|| self.config_skip_modes.get(&marker_type))
+            .copied()
+            .unwrap_or(MarkerSkipMode::Button)
+    }
 
-                        // Previous track button
-                        gtk::Button {
-                            set_icon_name: "media-skip-backward-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Previous"),
-                            #[watch]
-                            set_sensitive: model.can_go_previous,
-                            connect_clicked => PlayerInput::Previous,
-                        },
+    fn show_id(&self) -> Option<&ShowId> {
+        match &self.playlist_context {
+            Some(PlaylistContext::TvShow { show_id, .. }) => Some(show_id),
+            _ => None,
+        }
+    }
 
-                        // Rewind button (seek backward 10s)
-                        gtk::Button {
-                            set_icon_name: "media-seek-backward-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Rewind 10 seconds"),
-                            connect_clicked => PlayerInput::Rewind,
-                        },
+    /// Whether the playlist or the queue will move on to another item when this one ends
+    fn will_auto_play_next(&self) -> bool {
+        self.sleep_timer != Some(SleepTimer::EndOfItem) && self.next_target(true).is_some()
+    }
 
-                        // Play/pause button (center, compact)
-                        gtk::Box {
-                            set_size_request: (36, 36),
-                            set_halign: gtk::Align::Center,
-                            set_valign: gtk::Align::Center,
-                            add_css_class: "play-pause-container",
+    fn next_queue_index(&self, auto: bool) -> Option<usize> {
+        next_queue_index(self.playlist_context.as_ref(), &self.play_queue, auto)
+    }
 
-                            gtk::Button {
-                                #[watch]
-                                set_icon_name: if matches!(model.player_state, PlayerState::Playing) {
-                                    "media-playback-pause-symbolic"
-                                } else {
-                                    "media-playback-start-symbolic"
-                                },
-                                add_css_class: "circular",
-                                add_css_class: "play-pause-button",
-                                set_can_shrink: false,
-                                connect_clicked => PlayerInput::PlayPause,
-                            },
-                        },
+    fn next_target(&self, auto: bool) -> Option<(MediaItemId, PlaylistContext)> {
+        next_target(self.playlist_context.as_ref(), &self.play_queue, auto)
+    }
 
-                        // Forward button (seek forward 10s)
-                        gtk::Button {
-                            set_icon_name: "media-seek-forward-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Forward 10 seconds"),
-                            connect_clicked => PlayerInput::Forward,
-                        },
+    fn queue_target(&self, index: usize) -> (MediaItemId, PlaylistContext) {
+        queue_target(&self.play_queue, index)
+    }
 
-                        // Next track button
-                        gtk::Button {
-                            set_icon_name: "media-skip-forward-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Next"),
-                            #[watch]
-                            set_sensitive: model.can_go_next,
-                            connect_clicked => PlayerInput::Next,
-                        },
+    /// Refresh everything that depends on the queue after it was edited, and save it
+    fn queue_changed(&mut self, sender: &AsyncComponentSender<Self>) {
+        // From here on this copy is the newest, so a late load mustn't replace it
+        self.queue_loaded = true;
+        for (item, play_next) in std::mem::take(&mut self.pending_queue_items) {
+            self.add_to_queue(item, play_next);
+        }
+        if let Some(PlaylistContext::Queue(queue)) = &mut self.playlist_context {
+            *queue = self.play_queue.clone();
+        }
+        self.can_go_previous = self
+            .playlist_context
+            .as_ref()
+            .is_some_and(|context| context.has_previous());
+        self.can_go_next = self.next_target(false).is_some();
+        if let Some(context) = &self.playlist_context {
+            self.update_playlist_position_label(context);
+        }
+        self.update_queue_list(sender);
+        self.update_mpris_metadata();
+        sender
+            .output(PlayerOutput::QueueChanged(self.play_queue.clone()))
+            .unwrap();
+
+        let db = (*self.db).clone();
+        let queue = self.play_queue.clone();
+        relm4::spawn(async move {
+            use crate::services::commands::Command;
+            use crate::services::commands::media_commands::SavePlayQueueCommand;
+
+            if let Err(e) = (SavePlayQueueCommand { db, queue }).execute().await {
+                error!("Failed to save play queue: {}", e);
+            }
+        });
+    }
+
+    fn add_to_queue(&mut self, item: QueueItem, play_next: bool) {
+        if play_next {
+            self.play_queue.play_next(item);
+        } else {
+            self.play_queue.enqueue(item);
+        }
+    }
+
+    /// Rebuild the Up Next list and the shuffle and repeat buttons
+    fn update_queue_list(&self, sender: &AsyncComponentSender<Self>) {
+        self.queue_list.remove_all();
+        let playing_from_queue = matches!(self.playlist_context, Some(PlaylistContext::Queue(_)));
+        let last = self.play_queue.items.len().saturating_sub(1);
+
+        for (index, item) in self.play_queue.items.iter().enumerate() {
+            let is_current = playing_from_queue && self.play_queue.current_index == Some(index);
+            let row_box = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(6)
+                .margin_top(4)
+                .margin_bottom(4)
+                .margin_start(6)
+                .margin_end(6)
+                .build();
+
+            let playing_icon = gtk::Image::from_icon_name("media-playback-start-symbolic");
+            playing_icon.set_opacity(if is_current { 1.0 } else { 0.0 });
+            row_box.append(&playing_icon);
+
+            let labels = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .valign(gtk::Align::Center)
+                .hexpand(true)
+                .spacing(2)
+                .build();
+            let title = gtk::Label::builder()
+                .label(&item.title)
+                .xalign(0.0)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .max_width_chars(32)
+                .build();
+            if is_current {
+                title.add_css_class("heading");
+            }
+            labels.append(&title);
+            if let Some(subtitle) = &item.subtitle {
+                let subtitle = gtk::Label::builder()
+                    .label(subtitle)
+                    .xalign(0.0)
+                    .ellipsize(gtk::pango::EllipsizeMode::End)
+                    .max_width_chars(32)
+                    .build();
+                subtitle.add_css_class("dim-label");
+                subtitle.add_css_class("caption");
+                labels.append(&subtitle);
+            }
+            row_box.append(&labels);
+
+            for (icon, tooltip, enabled, input) in [
+                (
+                    "go-up-symbolic",
+                    "Move Up",
+                    index > 0,
+                    PlayerInput::MoveQueueItem {
+                        from: index,
+                        to: index.saturating_sub(1),
                     },
+                ),
+                (
+                    "go-down-symbolic",
+                    "Move Down",
+                    index < last,
+                    PlayerInput::MoveQueueItem {
+                        from: index,
+                        to: index + 1,
+                    },
+                ),
+                (
+                    "list-remove-symbolic",
+                    "Remove",
+                    true,
+                    PlayerInput::RemoveQueueItem(index),
+                ),
+            ] {
+                let button = gtk::Button::builder()
+                    .icon_name(icon)
+                    .tooltip_text(tooltip)
+                    .valign(gtk::Align::Center)
+                    .sensitive(enabled)
+                    .build();
+                button.add_css_class("flat");
+                button.add_css_class("circular");
+                let sender = sender.clone();
+                let input = std::cell::Cell::new(Some(input));
+                button.connect_clicked(move |_| {
+                    // The list is rebuilt after every edit, so each button fires once
+                    if let Some(input) = input.take() {
+                        sender.input(input);
+                    }
+                });
+                row_box.append(&button);
+            }
 
-                    // Right section: Track selection and fullscreen
-                    gtk::Box {
-                        set_orientation: gtk::Orientation::Horizontal,
-                        set_width_request: 150,
-                        set_halign: gtk::Align::End,
-                        set_spacing: 2,
+            self.queue_list.append(&row_box);
+        }
 
-                        // Audio tracks button
-                        model.audio_menu_button.clone() {
-                            set_icon_name: "audio-x-generic-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Audio Track"),
-                        },
+        self.queue_shuffle_button
+            .set_active(self.play_queue.shuffle);
+        let (icon, tooltip) = match self.play_queue.repeat {
+            RepeatMode::Off => ("media-playlist-consecutive-symbolic", "Repeat Off"),
+            RepeatMode::All => ("media-playlist-repeat-symbolic", "Repeat All"),
+            RepeatMode::One => ("media-playlist-repeat-song-symbolic", "Repeat One"),
+        };
+        self.queue_repeat_button.set_icon_name(icon);
+        self.queue_repeat_button.set_tooltip_text(Some(tooltip));
+    }
 
-                        // Subtitle tracks button
-                        model.subtitle_menu_button.clone() {
-                            set_icon_name: "media-view-subtitles-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Subtitles"),
-                        },
+    /// Time left before the sleep timer stops playback
+    fn sleep_timer_remaining(&self) -> Option<Duration> {
+        sleep_timer_remaining(
+            self.sleep_timer?,
+            std::time::Instant::now(),
+            self.position,
+            self.duration,
+        )
+    }
 
-                        // Quality/Resolution button
-                        gtk::MenuButton {
-                            set_icon_name: "preferences-system-symbolic",
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Video Quality"),
-                        },
+    fn sleep_timer_label(&self) -> String {
+        match self.sleep_timer {
+            Some(SleepTimer::At(_)) => {
+                format_duration(self.sleep_timer_remaining().unwrap_or_default())
+            }
+            Some(SleepTimer::EndOfItem) => "End of item".to_string(),
+            None => String::new(),
+        }
+    }
 
-                        // Fullscreen button
-                        gtk::Button {
-                            #[watch]
-                            set_icon_name: if model.is_fullscreen {
-                                "view-restore-symbolic"
-                            } else {
-                                "view-fullscreen-symbolic"
-                            },
-                            add_css_class: "flat",
-                            set_tooltip_text: Some("Fullscreen"),
-                            connect_clicked => PlayerInput::ToggleFullscreen,
-                        },
-                    },
-                },
-            },
+    fn set_sleep_timer(&mut self, timer: Option<SleepTimer>, sender: &AsyncComponentSender<Self>) {
+        self.sleep_timer = timer;
+
+        match timer {
+            Some(_) if self.sleep_timer_source.is_none() => {
+                let sender = sender.clone();
+                self.sleep_timer_source = Some(glib::timeout_add_seconds_local(1, move || {
+                    sender.input(PlayerInput::SleepTimerTick);
+                    glib::ControlFlow::Continue
+                }));
+            }
+            None => {
+                if let Some(source) = self.sleep_timer_source.take() {
+                    source.remove();
+                }
+            }
+            _ => {}
+        }
+
+        if timer.is_some() {
+            self.sleep_timer_menu_button.add_css_class("accent");
+        } else {
+            self.sleep_timer_menu_button.remove_css_class("accent");
         }
     }
 
-    async fn init(
-        init: Self::Init,
-        root: Self::Root,
-        sender: AsyncComponentSender<Self>,
-    ) -> AsyncComponentParts<Self> {
-        let (media_item_id, db, window) = init;
+    /// Scale the player volume down without touching the volume the viewer chose
+    fn set_sleep_fade(&mut self, fade: Option<f64>) {
+        if fade == self.sleep_fade {
+            return;
+        }
+        self.sleep_fade = fade;
+        if let Some(player) = &self.player {
+            let player_handle = player.clone();
+            let volume = self.volume * fade.unwrap_or(1.0);
+            glib::spawn_future_local(async move {
+                player_handle.set_volume(volume).await.ok();
+            });
+        }
+    }
 
-        // Load player CSS styles
-        let css_provider = gtk::CssProvider::new();
-        css_provider.load_from_string(include_str!("../../styles/player.css"));
-        gtk::style_context_add_provider_for_display(
-            &gtk::gdk::Display::default().expect("Could not get default display"),
-            &css_provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
+    /// Stop playback the same way the stop button does, so progress reaches the server.
+    /// The fade stays applied until the player has stopped.
+    fn fire_sleep_timer(&mut self, sender: &AsyncComponentSender<Self>) {
+        info!("Sleep timer expired, stopping playback");
+        self.set_sleep_timer(None, sender);
+        sender.input(PlayerInput::Stop);
+    }
 
-        // Create a container for the video widget that will be populated when player initializes
-        let video_container = gtk::Box::builder()
-            .orientation(gtk::Orientation::Vertical)
-            .build();
+    /// Forget the markers and chapters of the previous item and any countdown in progress
+    fn reset_markers(&mut self) {
+        self.set_chapters(Vec::new(), false);
+        self.markers.clear();
+        self.show_skip_modes.clear();
+        self.active_marker = None;
+        self.skip_countdown = None;
+        self.next_episode_countdown = None;
+        self.next_episode_cancelled = false;
+        if let Some(timer) = self.countdown_timer.take() {
+            timer.remove();
+        }
+    }
 
-        // Add drag gesture to make the window draggable by clicking on the video area
-        let drag_gesture = gtk::GestureDrag::new();
-        let window_clone = window.clone();
-        drag_gesture.connect_drag_begin(move |gesture, start_x, start_y| {
-            // Only start drag if it's a primary button (left click) and not in fullscreen
-            if let Some(event) = gesture.current_event() {
-                if event.triggers_context_menu() {
-                    return; // Don't drag on right-click
-                }
+    /// A slider per equalizer band with its frequency below, and a button to flatten them
+    fn equalizer_controls(
+        effects: &AudioEffects,
+        sender: &AsyncComponentSender<Self>,
+    ) -> gtk::Widget {
+        let bands = gtk::Box::new(gtk::Orientation::Horizontal, 2);
+        let mut scales = Vec::new();
+        for (band, frequency) in EQUALIZER_FREQUENCIES.iter().enumerate() {
+            let scale = gtk::Scale::with_range(
+                gtk::Orientation::Vertical,
+                -EQUALIZER_MAX_GAIN,
+                EQUALIZER_MAX_GAIN,
+                0.5,
+            );
+            // Boosts go up
+            scale.set_inverted(true);
+            scale.set_height_request(160);
+            scale.add_mark(0.0, gtk::PositionType::Right, None);
+            scale.set_value(effects.equalizer[band]);
+            scale.set_tooltip_text(Some(&format!("{:+.1} dB", effects.equalizer[band])));
+            let sender = sender.clone();
+            scale.connect_value_changed(move |scale| {
+                scale.set_tooltip_text(Some(&format!("{:+.1} dB", scale.value())));
+                sender.input(PlayerInput::SetEqualizerBand(band, scale.value()));
+            });
 
-                // Get the surface and start the drag
-                if !window_clone.is_fullscreen() {
-                    if let Some(surface) = window_clone.surface() {
-                        // Check if surface implements Toplevel interface
-                        use gtk::gdk::prelude::ToplevelExt;
-                        if let Some(toplevel) = surface.downcast_ref::<gtk::gdk::Toplevel>() {
-                            toplevel.begin_move(
-                                &event.device().unwrap(),
-                                gesture.current_button() as i32,
-                                start_x,
-                                start_y,
-                                event.time(),
-                            );
-                        }
-                    }
-                }
+            let label = if *frequency >= 1000 {
+                format!("{}k", frequency / 1000)
+            } else {
+                frequency.to_string()
+            };
+            let column = gtk::Box::new(gtk::Orientation::Vertical, 4);
+            column.append(&scale);
+            column.append(
+                &gtk::Label::builder()
+                    .label(label)
+                    .css_classes(["caption", "dim-label"])
+                    .build(),
+            );
+            bands.append(&column);
+            scales.push(scale);
+        }
+
+        let reset_button = gtk::Button::with_label("Flat");
+        reset_button.set_halign(gtk::Align::End);
+        reset_button.connect_clicked(move |_| {
+            for scale in &scales {
+                scale.set_value(0.0);
             }
         });
-        video_container.add_controller(drag_gesture);
-
-        // Add a placeholder initially
-        let placeholder = gtk::Label::new(Some("Initializing player..."));
-        placeholder.add_css_class("title-1");
-        placeholder.set_valign(gtk::Align::Center);
-        placeholder.set_halign(gtk::Align::Center);
-        video_container.append(&placeholder);
 
-        // Create seek bar
-        let seek_bar = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0);
-        seek_bar.set_draw_value(false);
-        seek_bar.set_has_tooltip(true);
+        let content = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .margin_top(6)
+            .margin_bottom(6)
+            .margin_start(6)
+            .margin_end(6)
+            .build();
+        content.append(&bands);
+        content.append(&reset_button);
+        content.upcast()
+    }
 
-        // Add tooltip to show time at cursor position
-        seek_bar.connect_query_tooltip(|scale, x, _y, _keyboard_mode, tooltip| {
-            let adjustment = scale.adjustment();
-            // x is already relative to the widget
-            let width = scale.allocated_width() as f64;
-            let ratio = x as f64 / width;
-            let max = adjustment.upper();
-            let value = ratio * max;
-            // Clamp value to ensure it's non-negative
-            let duration = Duration::from_secs_f64(value.max(0.0));
-            tooltip.set_text(Some(&format_duration(duration)));
-            true
-        });
+    /// Apply a change to the sound settings and remember them once the user settles
+    fn update_audio_effects(&mut self, effects: AudioEffects, sender: &AsyncComponentSender<Self>) {
+        if effects == self.audio_effects {
+            return;
+        }
+        self.audio_effects = effects;
 
-        // Create time labels
-        let position_label = gtk::Label::new(Some("0:00"));
-        let duration_label = gtk::Label::new(Some("0:00"));
-        let playlist_position_label = gtk::Label::new(None);
+        if let Some(player) = &self.player {
+            let player_handle = player.clone();
+            glib::spawn_future_local(async move {
+                if let Err(e) = player_handle.set_audio_effects(effects).await {
+                    warn!("Failed to set audio effects: {}", e);
+                }
+            });
+        }
 
-        // Create volume slider
-        let volume_slider = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 1.0, 0.01);
-        volume_slider.set_value(1.0);
-        volume_slider.set_draw_value(false);
+        if let Some(timer) = self.audio_effects_save_timer.take() {
+            timer.remove();
+        }
+        let sender = sender.clone();
+        self.audio_effects_save_timer = Some(glib::timeout_add_local_once(
+            AUDIO_EFFECTS_SAVE_DELAY,
+            move || sender.input(PlayerInput::SaveAudioEffects),
+        ));
+    }
 
-        // Create menu buttons for track selection
-        let audio_menu_button = gtk::MenuButton::new();
-        let subtitle_menu_button = gtk::MenuButton::new();
+    /// Show a changed delay over the video for a moment
+    fn show_sync_offset(&mut self, text: String, sender: &AsyncComponentSender<Self>) {
+        self.sync_offset_osd = Some(text);
+        if let Some(timer) = self.sync_offset_osd_timer.take() {
+            timer.remove();
+        }
+        let sender = sender.clone();
+        self.sync_offset_osd_timer = Some(glib::timeout_add_local_once(
+            SYNC_OFFSET_OSD_DURATION,
+            move || sender.input(PlayerInput::HideSyncOffset),
+        ));
+    }
 
-        // Load config once at initialization
+    /// Read the config again on every load, so changes in Preferences apply to the
+    /// next item rather than the next time the page is built
+    fn reload_config(&mut self) {
         let config = Config::load().unwrap_or_default();
+        DeviceCapabilities::set_config(&config);
+        self.config_inhibit_while_paused = config.playback.inhibit_while_paused;
+        self.config_skip_modes = marker_skip_modes(&config);
+        self.config_auto_skip_delay = config.playback.auto_skip_delay;
+        self.config_auto_play_delay = config.playback.auto_play_delay;
+        self.apply_audio_output(config);
+    }
 
-        let mut model = Self {
-            media_item_id,
-            player: None,
-            player_state: PlayerState::Idle,
-            position: Duration::from_secs(0),
-            duration: Duration::from_secs(0),
-            volume: 1.0,
-            db,
-            video_container: video_container.clone(),
-            video_placeholder: Some(placeholder.clone()),
-            playlist_context: None,
-            can_go_previous: false,
-            can_go_next: false,
-            show_controls: true,
-            is_fullscreen: false,
-            controls_timer: None,
-            cursor_timer: None,
-            seek_bar: seek_bar.clone(),
-            position_label: position_label.clone(),
-            duration_label: duration_label.clone(),
-            volume_slider: volume_slider.clone(),
-            playlist_position_label: playlist_position_label.clone(),
-            window: window.clone(),
-            is_seeking: false,
-            error_message: None,
-            retry_count: 0,
-            max_retries: 3,
-            retry_timer: None,
-            last_progress_save: std::time::Instant::now(),
-            // Cache config values to avoid reloading every second
-            config_auto_resume: config.playback.auto_resume,
-            config_resume_threshold_seconds: config.playback.resume_threshold_seconds as u64,
-            config_progress_update_interval_seconds: config
-                .playback
-                .progress_update_interval_seconds
-                as u64,
-            playback_speed: 1.0,
-            audio_menu_button: audio_menu_button.clone(),
-            subtitle_menu_button: subtitle_menu_button.clone(),
-            current_audio_track: -1,
-            current_subtitle_track: -1,
+    /// Hand the configured audio output, passthrough codecs and sound settings to the
+    /// player
+    fn apply_audio_output(&mut self, config: Config) {
+        let Some(player) = &self.player else {
+            return;
         };
+        self.audio_device = config.playback.audio_device.clone();
+
+        let player = player.clone();
+        let audio_effects = self.audio_effects;
+        glib::spawn_future_local(async move {
+            let Ok(capabilities) = player.capabilities().await else {
+                return;
+            };
+            if capabilities.audio_effects
+                && let Err(e) = player.set_audio_effects(audio_effects).await
+            {
+                warn!("Failed to set audio effects: {}", e);
+            }
+            if capabilities.audio_output_selection
+                && let Err(e) = player.set_audio_device(&config.playback.audio_device).await
+            {
+                warn!("Failed to set audio device: {}", e);
+            }
+            if capabilities.audio_passthrough
+                && let Err(e) = player
+                    .set_audio_passthrough(config.playback.audio_passthrough)
+                    .await
+            {
+                warn!("Failed to set audio passthrough: {}", e);
+            }
+        });
+    }
 
-        // Initialize the player controller
-        match PlayerController::new(&config) {
-            Ok((handle, controller)) => {
-                info!("Player controller initialized successfully");
+    fn set_clip_marks(&mut self, start: Option<Duration>, end: Option<Duration>) {
+        self.clip_start = start;
+        self.clip_end = end;
+        self.export_clip_action
+            .set_enabled(matches!((start, end), (Some(start), Some(end)) if end > start));
+        if start.is_some() || end.is_some() {
+            self.capture_menu_button.add_css_class("accent");
+        } else {
+            self.capture_menu_button.remove_css_class("accent");
+        }
+    }
 
-                // Spawn the controller task on the main thread's executor
-                // This is needed because Player contains raw pointers that are not Send
-                glib::spawn_future_local(async move {
-                    controller.run().await;
-                });
+    /// Text under the spinner while playback waits on the network
+    fn buffering_text(&self) -> String {
+        buffering_text(self.reconnect_attempts, self.buffering_percent)
+    }
 
-                // Create video widget synchronously on main thread
-                // Note: We need to spawn this as a local future too
-                let handle_clone = handle.clone();
-                let video_container_clone = model.video_container.clone();
-                let placeholder_clone = model.video_placeholder.take();
-                glib::spawn_future_local(async move {
-                    if let Ok(video_widget) = handle_clone.create_video_widget().await {
-                        // Remove placeholder if it exists
-                        if let Some(placeholder) = placeholder_clone {
-                            video_container_clone.remove(&placeholder);
-                        }
+    /// Text of the statistics overlay, one `label value` pair per line
+    fn stats_text(&self) -> String {
+        match &self.stats {
+            Some(stats) => stats_text(
+                stats,
+                self.stream_info.as_ref(),
+                self.is_transcoding,
+                self.transcode_status.as_ref(),
+            ),
+            None => String::new(),
+        }
+    }
 
-                        // Set proper expansion for the video widget
-                        video_widget.set_vexpand(true);
-                        video_widget.set_hexpand(true);
-                        video_widget.set_valign(gtk::Align::Fill);
-                        video_widget.set_halign(gtk::Align::Fill);
-
-                        // Add the video widget
-                        video_container_clone.append(&video_widget);
-
-                        info!("Video widget successfully attached to container");
-                    }
-                });
-
-                model.player = Some(handle);
-            }
+    /// Title captures of an item are named after, with the show and episode for episodes
+    async fn capture_title(
+        db: &crate::db::connection::DatabaseConnection,
+        media_id: &MediaItemId,
+    ) -> String {
+        use crate::models::MediaItem;
+        use crate::services::commands::Command;
+        use crate::services::commands::media_commands::GetMediaItemCommand;
+
+        match (GetMediaItemCommand {
+            db: db.clone(),
+            item_id: media_id.clone(),
+        })
+        .execute()
+        .await
+        {
+            Ok(Some(MediaItem::Episode(episode))) => format!(
+                "{} S{:02}E{:02} {}",
+                episode.show_title.as_deref().unwrap_or_default(),
+                episode.season_number,
+                episode.episode_number,
+                episode.title
+            ),
+            Ok(Some(item)) => item.title().to_string(),
+            Ok(None) => media_id.to_string(),
             Err(e) => {
-                error!("Failed to initialize player controller: {}", e);
-                model.error_message = Some(format!("Failed to initialize player: {}", e));
-                model.player_state = PlayerState::Error;
+                warn!("Failed to look up title for capture: {}", e);
+                media_id.to_string()
             }
         }
+    }
+
+    /// Offer, count down to or skip a marker when playback moves into it
+    fn update_active_marker(&mut self, sender: &AsyncComponentSender<Self>) {
+        let current = self
+            .markers
+            .iter()
+            .find(|m| m.contains(self.position))
+            .cloned();
+        if current == self.active_marker {
+            return;
+        }
 
-        // Setup seek bar handlers
+        // Seeking back out of the credits means the viewer isn't done yet
+        if let Some(previous) = &self.active_marker
+            && previous.marker_type == ChapterType::Credits
+            && self.position < previous.start_time
         {
-            // Track when user starts dragging
-            let sender_press = sender.clone();
-            let button_controller = gtk::GestureClick::new();
-            button_controller.set_button(gtk::gdk::BUTTON_PRIMARY);
-            button_controller.connect_pressed(move |_, _, _, _| {
-                sender_press.input(PlayerInput::StartSeeking);
-            });
-            model.seek_bar.add_controller(button_controller);
+            self.next_episode_countdown = None;
+        }
 
-            // Track when user releases drag and perform seek
-            let sender_release = sender.clone();
-            let seek_bar_clone = model.seek_bar.clone();
-            let button_release_controller = gtk::GestureClick::new();
-            button_release_controller.set_button(gtk::gdk::BUTTON_PRIMARY);
-            button_release_controller.connect_released(move |_, _, _, _| {
-                let position = seek_bar_clone.value();
-                sender_release.input(PlayerInput::StopSeeking);
-                // Ensure position is non-negative before creating Duration
-                sender_release.input(PlayerInput::Seek(Duration::from_secs_f64(
-                    position.max(0.0),
-                )));
-            });
-            model.seek_bar.add_controller(button_release_controller);
+        self.active_marker = current.clone();
+        self.skip_countdown = None;
+        let Some(marker) = current else {
+            return;
+        };
 
-            // Also handle value changes during drag for smooth preview
-            let sender_changed = sender.clone();
-            let seek_bar = model.seek_bar.clone();
-            seek_bar.connect_value_changed(move |scale| {
-                // Update time labels during drag for preview
-                // Clamp scale value to prevent negative Duration
-                let position = Duration::from_secs_f64(scale.value().max(0.0));
-                sender_changed.input(PlayerInput::UpdateSeekPreview(position));
-            });
+        let mode = self.skip_mode(marker.marker_type);
+        debug!(
+            "Entered {} marker ({:?} - {:?}), skip mode {}",
+            marker.marker_type.as_str(),
+            marker.start_time,
+            marker.end_time,
+            mode.as_str()
+        );
+        match mode {
+            // Ending credits lead straight into the next episode, even when they
+            // aren't offered for skipping
+            _ if marker.marker_type == ChapterType::Credits && self.will_auto_play_next() => {
+                if !self.next_episode_cancelled {
+                    self.next_episode_countdown = Some(self.config_auto_play_delay);
+                    self.start_countdown_timer(sender);
+                }
+            }
+            MarkerSkipMode::Off => self.active_marker = None,
+            MarkerSkipMode::Auto => {
+                self.skip_countdown = Some(self.config_auto_skip_delay);
+                self.start_countdown_timer(sender);
+            }
+            MarkerSkipMode::Button => {}
         }
+    }
 
-        // Setup volume slider handler
-        {
+    fn start_countdown_timer(&mut self, sender: &AsyncComponentSender<Self>) {
+        if self.countdown_timer.is_none() {
             let sender = sender.clone();
-            let volume_slider = model.volume_slider.clone();
-            volume_slider.connect_value_changed(move |scale| {
-                sender.input(PlayerInput::SetVolume(scale.value()));
-            });
+            self.countdown_timer = Some(glib::timeout_add_seconds_local(1, move || {
+                sender.input(PlayerInput::CountdownTick);
+                glib::ControlFlow::Continue
+            }));
         }
+    }
 
-        // Setup mouse motion handler for showing controls and cursor
-        {
-            let sender = sender.clone();
-            let motion_controller = gtk::EventControllerMotion::new();
-            motion_controller.connect_motion(move |_, _, _| {
-                sender.input(PlayerInput::ShowControls);
-                sender.input(PlayerInput::ShowCursor);
-            });
-            root.add_controller(motion_controller);
+    /// Whether the marker playback is in has a skip button, which markers that are
+    /// only followed for the credits countdown don't
+    fn offers_skip(&self) -> bool {
+        self.active_marker
+            .as_ref()
+            .is_some_and(|m| self.skip_mode(m.marker_type) != MarkerSkipMode::Off)
+    }
+
+    fn skip_prompt(&self) -> String {
+        match (&self.active_marker, self.skip_countdown) {
+            (Some(marker), Some(seconds)) => {
+                format!("Skip {} ({}s)", marker.marker_type.label(), seconds)
+            }
+            (Some(marker), None) => format!("Skip {}", marker.marker_type.label()),
+            _ => String::new(),
         }
+    }
 
-        // Setup keyboard shortcuts
+    fn next_episode_title(&self) -> String {
+        if let Some(index) = self.next_queue_index(true) {
+            return self.play_queue.items[index].title.clone();
+        }
+        match self
+            .playlist_context
+            .as_ref()
+            .and_then(|c| c.get_next_episode_info())
         {
-            let sender = sender.clone();
-            let sender_for_escape = sender.clone();
-            let sender_for_fullscreen_check = sender.clone();
-            let key_controller = gtk::EventControllerKey::new();
-            key_controller.connect_key_pressed(move |controller, key, _keycode, modifiers| {
-                use gtk::gdk::ModifierType;
+            Some(episode) => format!(
+                "S{}E{} · {}",
+                episode.season_number, episode.episode_number, episode.title
+            ),
+            None => String::new(),
+        }
+    }
 
-                // Check modifier keys
-                let shift_pressed = modifiers.contains(ModifierType::SHIFT_MASK);
-                let ctrl_pressed = modifiers.contains(ModifierType::CONTROL_MASK);
+    /// Show the preview thumbnail for a seek position above the seek bar
+    fn update_seek_preview_thumbnail(&mut self, position: Duration) {
+        let Some(index) = self
+            .trickplay
+            .as_ref()
+            .and_then(|t| t.frame_index_at(position))
+        else {
+            return;
+        };
 
-                match key {
-                    // Fullscreen controls
-                    gtk::gdk::Key::F11 | gtk::gdk::Key::f => {
-                        sender.input(PlayerInput::ToggleFullscreen);
-                        glib::Propagation::Stop
-                    }
-                    // Playback controls
-                    gtk::gdk::Key::space => {
-                        sender.input(PlayerInput::PlayPause);
-                        glib::Propagation::Stop
-                    }
-                    // Quit/Stop
-                    gtk::gdk::Key::Escape | gtk::gdk::Key::q => {
-                        sender_for_fullscreen_check.input(PlayerInput::EscapePressed);
-                        glib::Propagation::Stop
-                    }
-                    // Seeking controls
-                    gtk::gdk::Key::Left => {
-                        if ctrl_pressed {
-                            // Ctrl+Left: seek back 10s
-                            sender.input(PlayerInput::SeekRelative(-10));
-                        } else if shift_pressed {
-                            // Shift+Left: seek back 1s
-                            sender.input(PlayerInput::SeekRelative(-1));
-                        } else {
-                            // Left: seek back 5s (default)
-                            sender.input(PlayerInput::SeekRelative(-5));
-                        }
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::Right => {
-                        if ctrl_pressed {
-                            // Ctrl+Right: seek forward 10s
-                            sender.input(PlayerInput::SeekRelative(10));
-                        } else if shift_pressed {
-                            // Shift+Right: seek forward 1s
-                            sender.input(PlayerInput::SeekRelative(1));
-                        } else {
-                            // Right: seek forward 5s (default)
-                            sender.input(PlayerInput::SeekRelative(5));
-                        }
-                        glib::Propagation::Stop
-                    }
-                    // Speed controls
-                    gtk::gdk::Key::bracketleft => {
-                        // [ key: speed down
-                        sender.input(PlayerInput::SpeedDown);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::bracketright => {
-                        // ] key: speed up
-                        sender.input(PlayerInput::SpeedUp);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::BackSpace => {
-                        // Backspace: reset speed
-                        sender.input(PlayerInput::SpeedReset);
-                        glib::Propagation::Stop
-                    }
-                    // Volume controls
-                    gtk::gdk::Key::_9 => {
-                        // 9: volume down by 10%
-                        sender.input(PlayerInput::VolumeDown);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::_0 => {
-                        // 0: volume up by 10%
-                        sender.input(PlayerInput::VolumeUp);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::m => {
-                        // m: toggle mute
-                        sender.input(PlayerInput::ToggleMute);
-                        glib::Propagation::Stop
-                    }
-                    // Frame stepping
-                    gtk::gdk::Key::period => {
-                        // . key: frame step forward
-                        sender.input(PlayerInput::FrameStepForward);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::comma => {
-                        // , key: frame step backward
-                        sender.input(PlayerInput::FrameStepBackward);
-                        glib::Propagation::Stop
-                    }
-                    // Subtitle controls
-                    gtk::gdk::Key::v => {
-                        // v: cycle subtitles
-                        sender.input(PlayerInput::CycleSubtitleTrack);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::j => {
-                        if shift_pressed {
-                            // Shift+J: cycle subtitle track backward (same as v for simplicity)
-                            sender.input(PlayerInput::CycleSubtitleTrack);
-                        } else {
-                            // j: cycle subtitle track forward
-                            sender.input(PlayerInput::CycleSubtitleTrack);
-                        }
-                        glib::Propagation::Stop
-                    }
-                    // Audio track cycling
-                    gtk::gdk::Key::numbersign => {
-                        // # key: cycle audio track
-                        sender.input(PlayerInput::CycleAudioTrack);
-                        glib::Propagation::Stop
-                    }
-                    gtk::gdk::Key::_3 if shift_pressed => {
-                        // Shift+3 (also #): cycle audio track
-                        sender.input(PlayerInput::CycleAudioTrack);
-                        glib::Propagation::Stop
-                    }
-                    // Controls visibility
-                    gtk::gdk::Key::Tab => {
-                        // Tab: toggle controls visibility
-                        sender.input(PlayerInput::ToggleControlsVisibility);
-                        glib::Propagation::Stop
-                    }
-                    _ => glib::Propagation::Proceed,
+        // Only decode a new texture when the frame actually changes
+        if self.seek_preview_index != Some(index)
+            && let Some(frame) = self.trickplay.as_ref().map(|t| &t.frames[index])
+        {
+            let bytes = glib::Bytes::from(frame.image.as_slice());
+            match gtk::gdk::Texture::from_bytes(&bytes) {
+                Ok(texture) => self.seek_preview_picture.set_paintable(Some(&texture)),
+                Err(e) => {
+                    debug!("Failed to decode preview thumbnail: {}", e);
+                    return;
                 }
-            });
-            root.add_controller(key_controller);
+            }
+            self.seek_preview_index = Some(index);
         }
 
-        // Start position update timer (1Hz)
-        {
-            let sender = sender.clone();
-            glib::timeout_add_seconds_local(1, move || {
-                sender.input(PlayerInput::UpdatePosition);
-                glib::ControlFlow::Continue
-            });
+        // Point the popover at the seek position
+        let duration = self.duration.as_secs_f64();
+        if duration > 0.0 {
+            let x = (position.as_secs_f64() / duration * self.seek_bar.width() as f64) as i32;
+            self.seek_preview_popover
+                .set_pointing_to(Some(&gtk::gdk::Rectangle::new(x, 0, 1, 1)));
         }
 
-        // Start controls and cursor timers
-        sender.input(PlayerInput::ResetControlsTimer);
-        sender.input(PlayerInput::ResetCursorTimer);
+        if !self.seek_preview_popover.is_visible() {
+            self.seek_preview_popover.popup();
+        }
+    }
 
-        // Load media if provided
-        if let Some(id) = &model.media_item_id {
-            sender.input(PlayerInput::LoadMedia(id.clone()));
+    /// Chapter ticks below the seek bar, and the ends of the A-B loop above it
+    fn refresh_seek_bar_marks(&self) {
+        // The first chapter usually starts at zero, where a tick adds nothing
+        self.seek_bar.clear_marks();
+        for chapter in self.chapters.iter().filter(|c| !c.start_time.is_zero()) {
+            self.seek_bar.add_mark(
+                chapter.start_time.as_secs_f64(),
+                gtk::PositionType::Bottom,
+                None,
+            );
+        }
+        for (point, label) in [(self.loop_start, "A"), (self.loop_end, "B")] {
+            if let Some(point) = point {
+                self.seek_bar
+                    .add_mark(point.as_secs_f64(), gtk::PositionType::Top, Some(label));
+            }
         }
+    }
 
-        let widgets = view_output!();
+    /// Change the loop, repeating once both ends are set
+    fn set_loop(&mut self, start: Option<Duration>, end: Option<Duration>) {
+        let was_looping = self.loop_start.is_some() && self.loop_end.is_some();
+        self.loop_start = start;
+        self.loop_end = end;
+        self.refresh_seek_bar_marks();
 
-        AsyncComponentParts { model, widgets }
+        let range = start.zip(end);
+        if let Some(player) = &self.player
+            && (range.is_some() || was_looping)
+        {
+            let player_handle = player.clone();
+            glib::spawn_future_local(async move {
+                if let Err(e) = player_handle.set_ab_loop(range).await {
+                    warn!("Failed to set A-B loop: {}", e);
+                }
+            });
+        }
     }
 
-    async fn update(
+    /// Keep Jellyfin and Plex apps controlling this player up to date
+    fn report_remote_playback(&self) {
+        report_playback(RemotePlayback {
+            media_id: self.media_item_id.clone(),
+            state: self.player_state.clone(),
+            position: self.position,
+            duration: (!self.duration.is_zero()).then_some(self.duration),
+            volume: self.volume,
+        });
+    }
+
+    fn handle_remote_command(
         &mut self,
-        msg: Self::Input,
-        sender: AsyncComponentSender<Self>,
-        root: &Self::Root,
+        command: RemoteCommand,
+        sender: &AsyncComponentSender<Self>,
     ) {
-        match msg {
-            PlayerInput::LoadMedia(id) => {
-                self.media_item_id = Some(id.clone());
-                self.player_state = PlayerState::Loading;
-                // Clear context when loading without context
-                self.playlist_context = None;
-                // No navigation available for single items
-                self.can_go_previous = false;
-                self.can_go_next = false;
-                // Clear playlist position label
-                self.playlist_position_label.set_text("");
-                // Clear any existing error message
-                self.error_message = None;
-
-                // Get actual media URL from backend using GetStreamUrlCommand
-                let db_clone = self.db.clone();
-                let media_id = id.clone();
-                let media_id_for_resume = media_id.clone();
-                let sender_clone = sender.clone();
-                // Capture cached config values to avoid reloading config in async closure
-                let auto_resume = self.config_auto_resume;
-                let resume_threshold_seconds = self.config_resume_threshold_seconds;
-
+        match command {
+            RemoteCommand::PlayPause => sender.input(PlayerInput::PlayPause),
+            RemoteCommand::Pause => {
+                if self.player_state == PlayerState::Playing {
+                    sender.input(PlayerInput::PlayPause);
+                }
+            }
+            RemoteCommand::Resume => {
+                if self.player_state == PlayerState::Paused {
+                    sender.input(PlayerInput::PlayPause);
+                }
+            }
+            // Leaving the page is only right while it is showing something
+            RemoteCommand::Stop
+                if matches!(
+                    self.player_state,
+                    PlayerState::Playing | PlayerState::Paused | PlayerState::Loading
+                ) =>
+            {
+                sender.input(PlayerInput::Stop);
+                sender.input(PlayerInput::NavigateBack);
+            }
+            RemoteCommand::Stop => {}
+            // The item is still loading when the app starts it somewhere in the middle
+            RemoteCommand::Seek(position) if self.player_state == PlayerState::Loading => {
+                self.start_position = Some(position);
+            }
+            RemoteCommand::Seek(position) => sender.input(PlayerInput::Seek(position)),
+            RemoteCommand::SeekRelative(seconds) => {
+                sender.input(PlayerInput::SeekRelative(seconds))
+            }
+            RemoteCommand::Next => sender.input(PlayerInput::Next),
+            RemoteCommand::Previous => sender.input(PlayerInput::Previous),
+            RemoteCommand::SetVolume(volume) => {
+                self.volume = volume;
+                self.volume_slider.set_value(volume);
+                sender.input(PlayerInput::SetVolume(volume));
+            }
+            RemoteCommand::VolumeUp => sender.input(PlayerInput::VolumeUp),
+            RemoteCommand::VolumeDown => sender.input(PlayerInput::VolumeDown),
+            RemoteCommand::SetMuted(None) => sender.input(PlayerInput::ToggleMute),
+            RemoteCommand::SetMuted(Some(muted)) => {
                 if let Some(player) = &self.player {
                     let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        use crate::services::commands::Command;
-                        use crate::services::commands::media_commands::GetStreamUrlCommand;
-
-                        // Get the stream info from the backend using stateless command
-                        let stream_info = match (GetStreamUrlCommand {
-                            db: db_clone.as_ref().clone(),
-                            media_item_id: media_id,
-                        })
-                        .execute()
-                        .await
-                        {
-                            Ok(info) => info,
-                            Err(e) => {
-                                error!("Failed to get stream URL: {}", e);
-                                let user_message = match e.to_string().as_str() {
-                                    s if s.contains("network") || s.contains("connection") =>
-                                        "Network connection error. Please check your internet connection.".to_string(),
-                                    s if s.contains("unauthorized") || s.contains("401") =>
-                                        "Authentication failed. Please check your server credentials.".to_string(),
-                                    s if s.contains("not found") || s.contains("404") =>
-                                        "Media not found on server. It may have been removed.".to_string(),
-                                    s if s.contains("timeout") =>
-                                        "Server connection timed out. Please try again.".to_string(),
-                                    _ => format!("Failed to load media: {}", e)
-                                };
-                                return PlayerCommandOutput::LoadError(user_message);
-                            }
-                        };
-
-                        info!("Got stream URL: {}", stream_info.url);
+                    glib::spawn_future_local(async move {
+                        if player_handle.is_muted().await.is_ok_and(|m| m != muted) {
+                            player_handle.toggle_mute().await.ok();
+                        }
+                    });
+                }
+            }
+            RemoteCommand::ToggleFullscreen => sender.input(PlayerInput::ToggleFullscreen),
+            // Starting playback and messages are up to the main window
+            RemoteCommand::Play { .. } | RemoteCommand::ShowMessage(_) => {}
+        }
+    }
 
-                        // Load the media into the player using channel-based API
-                        match player_handle.load_media(&stream_info.url).await {
-                            Ok(_) => {
-                                info!("Media loaded successfully");
+    /// Follow a playback state reported by the player, or by the receiver while casting
+    fn apply_state(&mut self, state: PlayerState, sender: &AsyncComponentSender<Self>) {
+        self.player_state = state.clone();
+        let mpris_state = state.clone();
+        self.update_mpris(|mpris| async move { mpris.set_playback_state(mpris_state).await });
+        // The next position goes out right away, whatever the extrapolation said
+        self.mpris_position = None;
+        // Clear error on successful state change
+        if !matches!(&state, PlayerState::Error) {
+            self.error_message = None;
+            self.retry_count = 0;
+        }
+        self.update_stall_timer(sender);
+        self.update_transcode_keepalive(sender);
+        self.update_inhibit();
+        if let Some(pip) = &self.pip {
+            pip.set_playing(matches!(state, PlayerState::Playing));
+        }
+        self.report_remote_playback();
+    }
 
-                                // Populate track menus after media loads
-                                sender_clone.input(PlayerInput::UpdateTrackMenus);
+    fn apply_position(
+        &mut self,
+        pos: Duration,
+        duration: Option<Duration>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        // Moving forward at playback pace means the stream is delivering again
+        if pos > self.position && pos - self.position < STALL_CHECK_INTERVAL {
+            self.last_progress_at = std::time::Instant::now();
+            if self.reconnect_attempts > 0
+                && self.reconnect_timer.is_none()
+                && !self.reconnect_in_flight
+            {
+                info!(
+                    "Stream recovered after {} reconnect attempt(s)",
+                    self.reconnect_attempts
+                );
+                self.reconnect_attempts = 0;
+            }
+        }
+        self.position = pos;
+        // Update position label
+        self.position_label.set_text(&format_duration(pos));
 
-                                // Check for saved playback progress and resume if configured
-                                use crate::services::commands::GetPlaybackProgressCommand;
+        // Update seek bar position (only if not being dragged)
+        if !self.is_seeking {
+            self.seek_bar.set_value(pos.as_secs_f64());
+        }
+        let now = std::time::Instant::now();
+        if self.mpris.is_some()
+            && position_update_due(self.mpris_position, pos, self.playback_speed, now)
+        {
+            self.mpris_position = Some((pos, now));
+            self.update_mpris(move |mpris| async move { mpris.update_position(pos).await });
+        }
+        self.update_active_marker(sender);
+        self.update_current_chapter();
+        self.report_remote_playback();
+
+        // Save playback progress to database at configured interval, but not the
+        // start of a reloaded stream before it is back where it dropped
+        if self.reconnect_attempts == 0
+            && let (Some(media_id), Some(dur)) = (&self.media_item_id, duration)
+        {
+            // Use cached config value instead of reloading config file
+            let save_interval_secs = self.config_progress_update_interval_seconds;
+
+            // Check if enough time has passed since last save
+            let elapsed = self.last_progress_save.elapsed().as_secs();
+
+            // Always save if watched (>90%) or if interval has passed
+            let watched = pos.as_secs_f64() / dur.as_secs_f64() > 0.9;
+
+            if watched || elapsed >= save_interval_secs {
+                self.last_progress_save = std::time::Instant::now();
+
+                let db = (*self.db).clone();
+                let media_id = media_id.clone();
+                let position_ms = pos.as_millis() as i64;
+                let duration_ms = dur.as_millis() as i64;
+                // Nothing else tells the server how far a receiver got
+                let report = self.cast.is_some().then(|| (db.clone(), media_id.clone()));
+
+                relm4::spawn(async move {
+                    use crate::services::commands::media_commands::ReportPlaybackProgressCommand;
+                    use crate::services::commands::{Command, UpdatePlaybackProgressCommand};
+
+                    let command = UpdatePlaybackProgressCommand {
+                        db,
+                        media_id,
+                        position_ms,
+                        duration_ms,
+                        watched,
+                    };
+
+                    if let Err(e) = command.execute().await {
+                        debug!("Failed to save playback progress: {}", e);
+                    }
 
-                                // Use cached config values
-                                if auto_resume {
-                                    // Get saved progress
-                                    if let Ok(Some((position_ms, _duration_ms))) =
-                                        (GetPlaybackProgressCommand {
-                                            db: db_clone.as_ref().clone(),
-                                            media_id: media_id_for_resume.clone(),
-                                            user_id: "default".to_string(), // TODO: Get actual user ID
-                                        }).execute().await
-                                    {
-                                        // Only resume if we've watched more than the threshold
-                                        let threshold_ms = (resume_threshold_seconds as i64) * 1000;
-                                        if position_ms > threshold_ms {
-                                            let resume_position = std::time::Duration::from_millis(position_ms as u64);
-                                            info!("Resuming playback from {:?}", resume_position);
-
-                                            // Seek to saved position
-                                            if let Err(e) = player_handle.seek(resume_position).await {
-                                                warn!("Failed to seek to saved position: {}", e);
-                                            }
-                                        }
-                                    }
-                                }
+                    if let Some((db, media_item_id)) = report {
+                        let command = ReportPlaybackProgressCommand {
+                            db,
+                            media_item_id,
+                            position: pos,
+                            duration: dur,
+                        };
+                        if let Err(e) = command.execute().await {
+                            debug!("Failed to report cast progress to the server: {}", e);
+                        }
+                    }
+                });
+            }
+        }
+        if let Some(dur) = duration {
+            self.duration = dur;
+            // Update duration label
+            self.duration_label.set_text(&format_duration(dur));
+            // Update seek bar range
+            self.seek_bar.set_range(0.0, dur.as_secs_f64());
+        }
+    }
 
-                                // Try to get video dimensions and calculate appropriate window size
-                                if let Ok(Some((width, height))) =
-                                    player_handle.get_video_dimensions().await
-                                {
-                                    if width > 0 && height > 0 {
-                                        // Calculate window size based on video aspect ratio
-                                        // Keep width reasonable (max 1920) and scale height accordingly
-                                        let max_width = 1920.0_f32.min(width as f32);
-                                        let scale = max_width / width as f32;
-                                        let window_width = max_width as i32;
-                                        let window_height = (height as f32 * scale) as i32;
+    /// The current item played to its end
+    fn item_ended(&mut self, sender: &AsyncComponentSender<Self>) {
+        // A dropped connection can look like the stream running out early
+        if self.can_reconnect() && ended_early(self.position, self.duration) {
+            self.reconnect("The stream ended early".to_string(), sender);
+            return;
+        }
+        // Continue with the next episode when the playlist asks for it, unless
+        // the countdown shown over the credits was cancelled
+        self.next_episode_countdown = None;
+        if self.sleep_timer == Some(SleepTimer::EndOfItem) {
+            self.fire_sleep_timer(sender);
+        } else if self.will_auto_play_next() && !self.next_episode_cancelled {
+            sender.input(PlayerInput::AutoAdvance);
+        }
+    }
 
-                                        // Add some padding for controls
-                                        let final_height = window_height + 100; // Extra space for controls
+    /// Whether the current stream can be brought back by reloading it
+    fn can_reconnect(&self) -> bool {
+        self.cast.is_none()
+            && self.player.is_some()
+            && self.media_item_id.is_some()
+            && can_reconnect(
+                self.stream_info.as_ref(),
+                &self.player_state,
+                self.reconnect_attempts,
+            )
+    }
 
-                                        info!(
-                                            "Video dimensions: {}x{}, window size: {}x{}",
-                                            width, height, window_width, final_height
-                                        );
+    /// Schedule the next attempt at reloading a dropped stream, or give up and
+    /// show `reason` once enough of them failed
+    fn reconnect(&mut self, reason: String, sender: &AsyncComponentSender<Self>) {
+        if !self.can_reconnect() {
+            self.cancel_reconnect();
+            return;
+        }
+        // The next attempt is already on its way
+        if self.reconnect_timer.is_some() || self.reconnect_in_flight {
+            return;
+        }
+        if self.reconnect_attempts >= RECONNECT_MAX_ATTEMPTS {
+            self.cancel_reconnect();
+            // Retrying by hand continues from where playback dropped
+            self.start_position =
+                (!self.reconnect_position.is_zero()).then_some(self.reconnect_position);
+            sender.input(PlayerInput::ShowError(format!(
+                "Lost the connection to the server: {}",
+                reason
+            )));
+            return;
+        }
+        if self.reconnect_attempts == 0 {
+            warn!("Stream dropped ({}), reconnecting", reason);
+            self.reconnect_position = self.position;
+        }
+        self.reconnect_attempts += 1;
+        self.buffering_percent = None;
+        self.update_stall_timer(sender);
+
+        let delay = reconnect_delay(self.reconnect_attempts);
+        info!(
+            "Scheduling reconnect attempt #{} after {:?}",
+            self.reconnect_attempts, delay
+        );
+        let sender_clone = sender.clone();
+        self.reconnect_timer = Some(glib::timeout_add_local(delay, move || {
+            sender_clone.input(PlayerInput::Reconnect);
+            glib::ControlFlow::Break
+        }));
+    }
 
-                                        // Request window resize through output
-                                        sender_clone
-                                            .output(PlayerOutput::WindowStateChanged {
-                                                width: window_width,
-                                                height: final_height,
-                                            })
-                                            .ok();
-                                    }
-                                }
+    /// Stop bringing back the stream, like when something else is played
+    fn cancel_reconnect(&mut self) {
+        if let Some(timer) = self.reconnect_timer.take() {
+            timer.remove();
+        }
+        self.reconnect_attempts = 0;
+        self.reconnect_in_flight = false;
+    }
 
-                                // Get the actual state from the player after loading
-                                let actual_state = player_handle
-                                    .get_state()
-                                    .await
-                                    .unwrap_or(PlayerState::Idle);
-                                PlayerCommandOutput::StateChanged(actual_state)
-                            }
-                            Err(e) => {
-                                error!("Failed to load media: {}", e);
-                                let user_message = match e.to_string().as_str() {
-                                    s if s.contains("codec") || s.contains("decoder") =>
-                                        "Media format not supported. The file may use an incompatible codec.".to_string(),
-                                    s if s.contains("permission") || s.contains("access") =>
-                                        "Permission denied. Check file or server access rights.".to_string(),
-                                    s if s.contains("memory") =>
-                                        "Not enough memory to play this media.".to_string(),
-                                    _ => format!("Playback error: {}", e)
-                                };
-                                PlayerCommandOutput::LoadError(user_message)
-                            }
-                        }
-                    });
-                }
+    /// Check for a stream that stopped delivering while playback should be moving
+    fn update_stall_timer(&mut self, sender: &AsyncComponentSender<Self>) {
+        let watching = self.cast.is_none()
+            && (self.player_state == PlayerState::Playing || self.reconnect_attempts > 0);
+        if !watching {
+            if let Some(timer) = self.stall_timer.take() {
+                timer.remove();
             }
-            PlayerInput::LoadMediaWithContext { media_id, context } => {
-                self.media_item_id = Some(media_id.clone());
-                self.player_state = PlayerState::Loading;
+            return;
+        }
+        if self.stall_timer.is_none() {
+            self.last_progress_at = std::time::Instant::now();
+            let sender_clone = sender.clone();
+            self.stall_timer = Some(glib::timeout_add_local(STALL_CHECK_INTERVAL, move || {
+                sender_clone.input(PlayerInput::CheckStall);
+                glib::ControlFlow::Continue
+            }));
+        }
+    }
 
-                // Update navigation state based on context
-                self.can_go_previous = context.has_previous();
-                self.can_go_next = context.has_next();
+    /// Receivers to cast to, or stopping once casting
+    fn update_cast_menu(&self) {
+        self.cast_menu.remove_all();
+        if let Some(cast) = &self.cast {
+            let section = gtk::gio::Menu::new();
+            section.append(Some("Stop Casting"), Some("player.stop-casting"));
+            self.cast_menu
+                .append_section(Some(&format!("Casting to {}", cast.device.name)), &section);
+            self.cast_menu_button.add_css_class("accent");
+            return;
+        }
+        self.cast_menu_button.remove_css_class("accent");
+
+        let devices = gtk::gio::Menu::new();
+        for (index, device) in self.cast_devices.iter().enumerate() {
+            let label = match device.target {
+                cast::CastTarget::Chromecast(_) => device.name.clone(),
+                cast::CastTarget::Dlna { .. } => format!("{} (DLNA)", device.name),
+            };
+            let item = gtk::gio::MenuItem::new(Some(&label), None);
+            item.set_action_and_target_value(
+                Some("player.cast-to"),
+                Some(&(index as u32).to_variant()),
+            );
+            devices.append_item(&item);
+        }
+        if self.finding_cast_devices {
+            devices.append(Some("Searching…"), None);
+        } else if self.cast_devices.is_empty() {
+            devices.append(Some("No Devices Found"), None);
+        }
+        self.cast_menu.append_section(Some("Cast To"), &devices);
 
-                // Update playlist position label
-                self.update_playlist_position_label(&context);
+        let search = gtk::gio::Menu::new();
+        search.append(Some("Search Again"), Some("player.find-cast-devices"));
+        self.cast_menu.append_section(None, &search);
+    }
 
-                self.playlist_context = Some(context);
-                // Clear any existing error message
-                self.error_message = None;
+    /// The quality picked here for a receiver to match, unless it's the original
+    fn cast_quality(&self) -> Option<QualityOption> {
+        if self.quality_index == 0 {
+            return None;
+        }
+        self.quality_options.get(self.quality_index).cloned()
+    }
 
-                // Get actual media URL from backend using GetStreamUrlCommand
-                let db_clone = self.db.clone();
-                let media_id_clone = media_id.clone();
-                let media_id_for_resume = media_id_clone.clone();
-                let sender_clone = sender.clone();
-                // Capture cached config values to avoid reloading config in async closure
-                let auto_resume = self.config_auto_resume;
-                let resume_threshold_seconds = self.config_resume_threshold_seconds;
+    /// An item as a receiver fetches it, starting at `start`. The server negotiates a
+    /// stream of its own for receivers, at `quality` if one was picked, and the
+    /// transcode session behind it is returned to be stopped once casting ends.
+    async fn cast_media(
+        db: crate::db::connection::DatabaseConnection,
+        media_id: MediaItemId,
+        quality: Option<QualityOption>,
+        start: Duration,
+    ) -> anyhow::Result<(CastMedia, Option<(MediaItemId, String)>)> {
+        use crate::services::commands::Command;
+        use crate::services::commands::media_commands::GetCastStreamCommand;
 
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        use crate::services::commands::Command;
-                        use crate::services::commands::media_commands::GetStreamUrlCommand;
+        let stream = (GetCastStreamCommand {
+            db,
+            media_item_id: media_id.clone(),
+            max_bitrate: quality.as_ref().map_or(0, |quality| quality.bitrate),
+        })
+        .execute()
+        .await?;
+        let (url, session_id) = quality
+            .and_then(|quality| {
+                stream
+                    .quality_options
+                    .iter()
+                    .find(|option| option.name == quality.name)
+            })
+            .map_or((&stream.url, &stream.transcode_session_id), |option| {
+                (&option.url, &option.transcode_session_id)
+            });
 
-                        // Get the stream info from the backend using stateless command
-                        let stream_info = match (GetStreamUrlCommand {
-                            db: db_clone.as_ref().clone(),
-                            media_item_id: media_id_clone,
-                        })
-                        .execute()
-                        .await
-                        {
-                            Ok(info) => info,
-                            Err(e) => {
-                                error!("Failed to get stream URL: {}", e);
-                                let user_message = match e.to_string().as_str() {
-                                    s if s.contains("network") || s.contains("connection") =>
-                                        "Network connection error. Please check your internet connection.".to_string(),
-                                    s if s.contains("unauthorized") || s.contains("401") =>
-                                        "Authentication failed. Please check your server credentials.".to_string(),
-                                    s if s.contains("not found") || s.contains("404") =>
-                                        "Media not found on server. It may have been removed.".to_string(),
-                                    s if s.contains("timeout") =>
-                                        "Server connection timed out. Please try again.".to_string(),
-                                    _ => format!("Failed to load media: {}", e)
-                                };
-                                return PlayerCommandOutput::LoadError(user_message);
-                            }
-                        };
+        // Receivers fetch the stream themselves, so it has to come from the network
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            anyhow::bail!("This item can't be cast");
+        }
+        let media = CastMedia {
+            url: url.clone(),
+            title: String::new(),
+            content_type: cast::content_type(url, &stream.container).to_string(),
+            start,
+        };
+        Ok((
+            media,
+            session_id.clone().map(|session_id| (media_id, session_id)),
+        ))
+    }
 
-                        info!("Got stream URL: {}", stream_info.url);
+    /// Stop the server transcoding for a receiver
+    async fn stop_cast_transcode(
+        db: crate::db::connection::DatabaseConnection,
+        transcode_session: Option<(MediaItemId, String)>,
+    ) {
+        use crate::services::commands::Command;
+        use crate::services::commands::media_commands::StopTranscodeSessionCommand;
 
-                        // Load the media into the player using channel-based API
-                        match player_handle.load_media(&stream_info.url).await {
-                            Ok(_) => {
-                                info!("Media loaded successfully with playlist context");
+        let Some((media_item_id, session_id)) = transcode_session else {
+            return;
+        };
+        let command = StopTranscodeSessionCommand {
+            db,
+            media_item_id,
+            session_id,
+        };
+        if let Err(e) = command.execute().await {
+            debug!("Failed to stop the receiver's transcode session: {}", e);
+        }
+    }
 
-                                // Populate track menus after media loads
-                                sender_clone.input(PlayerInput::UpdateTrackMenus);
+    /// Start `media` on a receiver, titled after the item it comes from
+    async fn load_on_receiver(
+        db: crate::db::connection::DatabaseConnection,
+        media_id: Option<MediaItemId>,
+        mut media: CastMedia,
+        receiver: &dyn CastReceiver,
+    ) -> anyhow::Result<()> {
+        use crate::services::commands::Command;
+        use crate::services::commands::media_commands::GetMediaItemCommand;
+
+        if let Some(item_id) = media_id
+            && let Ok(Some(item)) = (GetMediaItemCommand { db, item_id }).execute().await
+        {
+            media.title = item.title().to_string();
+        }
+        receiver.load(&media).await
+    }
 
-                                // Check for saved playback progress and resume if configured
-                                use crate::services::commands::GetPlaybackProgressCommand;
+    fn start_cast(
+        &mut self,
+        device: CastDevice,
+        receiver: Arc<dyn CastReceiver>,
+        transcode_session: Option<(MediaItemId, String)>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        self.end_cast(true, sender);
+        self.cancel_reconnect();
 
-                                // Use cached config values
-                                if auto_resume {
-                                    // Get saved progress
-                                    if let Ok(Some((position_ms, _duration_ms))) =
-                                        (GetPlaybackProgressCommand {
-                                            db: db_clone.as_ref().clone(),
-                                            media_id: media_id_for_resume.clone(),
-                                            user_id: "default".to_string(), // TODO: Get actual user ID
-                                        }).execute().await
-                                    {
-                                        // Only resume if we've watched more than the threshold
-                                        let threshold_ms = (resume_threshold_seconds as i64) * 1000;
-                                        if position_ms > threshold_ms {
-                                            let resume_position = std::time::Duration::from_millis(position_ms as u64);
-                                            info!("Resuming playback from {:?}", resume_position);
-
-                                            // Seek to saved position
-                                            if let Err(e) = player_handle.seek(resume_position).await {
-                                                warn!("Failed to seek to saved position: {}", e);
-                                            }
-                                        }
-                                    }
-                                }
+        // The local player waits, paused, for casting to end
+        if let Some(player) = &self.player {
+            let player = player.clone();
+            glib::spawn_future_local(async move {
+                player.pause().await.ok();
+            });
+        }
 
-                                // Try to get video dimensions and calculate appropriate window size
-                                if let Ok(Some((width, height))) =
-                                    player_handle.get_video_dimensions().await
-                                {
-                                    if width > 0 && height > 0 {
-                                        // Calculate window size based on video aspect ratio
-                                        // Keep width reasonable (max 1920) and scale height accordingly
-                                        let max_width = 1920.0_f32.min(width as f32);
-                                        let scale = max_width / width as f32;
-                                        let window_width = max_width as i32;
-                                        let window_height = (height as f32 * scale) as i32;
+        let sender_clone = sender.clone();
+        self.cast_status_timer = Some(glib::timeout_add_local(CAST_STATUS_INTERVAL, move || {
+            sender_clone.input(PlayerInput::CastStatusTick);
+            glib::ControlFlow::Continue
+        }));
+        sender
+            .output(PlayerOutput::ShowToast(format!(
+                "Casting to {}",
+                device.name
+            )))
+            .unwrap();
+        self.cast = Some(CastSession {
+            device,
+            receiver,
+            muted: false,
+            started: false,
+            failures: 0,
+            status_pending: false,
+            transcode_session,
+        });
+        self.update_cast_menu();
+    }
 
-                                        // Add some padding for controls
-                                        let final_height = window_height + 100; // Extra space for controls
+    /// Hand playback back to the local player, paused where the receiver got to
+    fn end_cast(&mut self, stop_receiver: bool, sender: &AsyncComponentSender<Self>) {
+        if let Some(timer) = self.cast_status_timer.take() {
+            timer.remove();
+        }
+        let Some(cast) = self.cast.take() else {
+            return;
+        };
 
-                                        info!(
-                                            "Video dimensions: {}x{}, window size: {}x{}",
-                                            width, height, window_width, final_height
+        let db = (*self.db).clone();
+        let report = self
+            .media_item_id
+            .clone()
+            .map(|id| (id, self.position, self.duration));
+        relm4::spawn(async move {
+            use crate::services::commands::Command;
+            use crate::services::commands::media_commands::ReportPlaybackProgressCommand;
+
+            if stop_receiver && let Err(e) = cast.receiver.stop().await {
+                debug!("Failed to stop {}: {}", cast.device.name, e);
+            }
+            Self::stop_cast_transcode(db.clone(), cast.transcode_session).await;
+            if let Some((media_item_id, position, duration)) = report {
+                let command = ReportPlaybackProgressCommand {
+                    db,
+                    media_item_id,
+                    position,
+                    duration,
+                };
+                if let Err(e) = command.execute().await {
+                    debug!("Failed to report cast progress to the server: {}", e);
+                }
+            }
+        });
+
+        if let Some(player) = &self.player {
+            let player = player.clone();
+            let position = self.position;
+            sender.oneshot_command(async move {
+                player.seek(position).await.ok();
+                PlayerCommandOutput::StateChanged(
+                    player.get_state().await.unwrap_or(PlayerState::Paused),
+                )
+            });
+        }
+        self.update_cast_menu();
+    }
+
+    /// Send transport controls to the receiver while casting, handing anything else back
+    fn control_cast(
+        &mut self,
+        msg: PlayerInput,
+        sender: &AsyncComponentSender<Self>,
+    ) -> Option<PlayerInput> {
+        let Some(receiver) = self.cast.as_ref().map(|cast| cast.receiver.clone()) else {
+            return Some(msg);
+        };
+        let status_receiver = receiver.clone();
+        let command: std::pin::Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>> = match msg
+        {
+            PlayerInput::PlayPause => {
+                if self.player_state == PlayerState::Playing {
+                    Box::pin(async move { receiver.pause().await })
+                } else {
+                    Box::pin(async move { receiver.play().await })
+                }
+            }
+            PlayerInput::Seek(position) => Box::pin(async move { receiver.seek(position).await }),
+            PlayerInput::SetVolume(volume) => {
+                self.volume = volume;
+                Box::pin(async move { receiver.set_volume(volume).await })
+            }
+            PlayerInput::ToggleMute => {
+                if let Some(cast) = &mut self.cast {
+                    cast.muted = !cast.muted;
+                }
+                let muted = self.cast.as_ref().is_some_and(|cast| cast.muted);
+                Box::pin(async move { receiver.set_muted(muted).await })
+            }
+            PlayerInput::Stop => {
+                self.end_cast(true, sender);
+                return Some(PlayerInput::Stop);
+            }
+            other => return Some(other),
+        };
+
+        sender.oneshot_command(async move {
+            let status = match command.await {
+                Ok(()) => status_receiver.status().await,
+                Err(e) => Err(e),
+            };
+            PlayerCommandOutput::CastStatus(status.map_err(|e| e.to_string()))
+        });
+        None
+    }
+
+    /// Offer hosting, or the join code and leaving once a party is going on
+    fn update_watch_party_menu(&self) {
+        let menu = gtk::gio::Menu::new();
+        match &self.watch_party {
+            None => menu.append(Some("Host Watch Party"), Some("player.host-watch-party")),
+            Some(party) => {
+                let section = gtk::gio::Menu::new();
+                if let Some(code) = party.code() {
+                    section.append(Some("Copy Join Code"), Some("player.copy-watch-party-code"));
+                    section.append(Some("End Watch Party"), Some("player.leave-watch-party"));
+                    menu.append_section(Some(&format!("Join Code {}", code)), &section);
+                } else {
+                    section.append(Some("Leave Watch Party"), Some("player.leave-watch-party"));
+                    menu.append_section(Some("In a Watch Party"), &section);
+                }
+            }
+        }
+        self.watch_party_menu_button
+            .set_popover(Some(&gtk::PopoverMenu::from_model(Some(&menu))));
+
+        if self.watch_party.is_some() {
+            self.watch_party_menu_button.add_css_class("accent");
+        } else {
+            self.watch_party_menu_button.remove_css_class("accent");
+        }
+    }
+
+    fn start_watch_party(
+        &mut self,
+        party: WatchParty,
+        mut events: tokio::sync::mpsc::UnboundedReceiver<PartyEvent>,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        self.leave_watch_party();
+
+        // Ends by itself once the party is dropped
+        let sender_clone = sender.clone();
+        glib::spawn_future_local(async move {
+            while let Some(event) = events.recv().await {
+                sender_clone.input(PlayerInput::WatchPartyEvent(event));
+            }
+        });
+
+        if party.is_host() {
+            sender.input(PlayerInput::WatchPartyHeartbeat);
+            let sender = sender.clone();
+            self.watch_party_heartbeat =
+                Some(glib::timeout_add_local(WATCH_PARTY_HEARTBEAT, move || {
+                    sender.input(PlayerInput::WatchPartyHeartbeat);
+                    glib::ControlFlow::Continue
+                }));
+        }
+        self.party_media_id = self.media_item_id.clone();
+        self.party_action_at = None;
+        self.watch_party = Some(party);
+        self.update_watch_party_menu();
+    }
+
+    fn leave_watch_party(&mut self) {
+        if let Some(timer) = self.watch_party_heartbeat.take() {
+            timer.remove();
+        }
+        if self.watch_party.take().is_some() {
+            self.party_media_id = None;
+            self.set_party_rate(1.0);
+            self.update_watch_party_menu();
+        }
+    }
+
+    /// Share a playback change made here with the rest of the watch party
+    fn send_to_party(&mut self, message: PartyMessage) {
+        if let Some(party) = &self.watch_party {
+            party.send(message);
+            self.party_action_at = Some(std::time::Instant::now());
+        }
+    }
+
+    /// Announce a newly loaded item, unless the party asked for it
+    fn share_party_load(&mut self, media_id: &MediaItemId) {
+        if self.party_media_id.as_ref() != Some(media_id) {
+            self.party_media_id = Some(media_id.clone());
+            self.send_to_party(PartyMessage::Load {
+                media_id: media_id.to_string(),
+            });
+        }
+    }
+
+    /// Follow a change another participant made, or the host's regular state
+    fn apply_party_message(&mut self, message: PartyMessage, sender: &AsyncComponentSender<Self>) {
+        let is_host = self
+            .watch_party
+            .as_ref()
+            .is_some_and(|party| party.is_host());
+        match message {
+            PartyMessage::Load { media_id } => {
+                self.load_party_item(MediaItemId::new(media_id), sender)
+            }
+            PartyMessage::Play { position_ms } => self.sync_to_party(
+                Duration::from_millis(position_ms),
+                Some(true),
+                false,
+                sender,
+            ),
+            PartyMessage::Pause { position_ms } => self.sync_to_party(
+                Duration::from_millis(position_ms),
+                Some(false),
+                false,
+                sender,
+            ),
+            PartyMessage::Seek { position_ms } => {
+                self.sync_to_party(Duration::from_millis(position_ms), None, false, sender)
+            }
+            PartyMessage::State {
+                media_id,
+                position_ms,
+                playing,
+            } if !is_host => {
+                // The host's state predates a change made here that it hasn't seen yet
+                if self
+                    .party_action_at
+                    .is_some_and(|at| at.elapsed() < WATCH_PARTY_SETTLE_TIME)
+                {
+                    return;
+                }
+                let media_id = MediaItemId::new(media_id);
+                if self.party_media_id.as_ref() != Some(&media_id) {
+                    self.load_party_item(media_id, sender);
+                } else if self.media_item_id.as_ref() == Some(&media_id) {
+                    self.sync_to_party(
+                        Duration::from_millis(position_ms),
+                        Some(playing),
+                        true,
+                        sender,
+                    );
+                }
+            }
+            PartyMessage::State { .. } | PartyMessage::Hello { .. } => {}
+        }
+    }
+
+    fn load_party_item(&mut self, media_id: MediaItemId, sender: &AsyncComponentSender<Self>) {
+        if self.media_item_id.as_ref() != Some(&media_id) {
+            // Every participant streams it with their own access to the server
+            self.party_media_id = Some(media_id.clone());
+            sender.input(PlayerInput::LoadMedia(media_id));
+        }
+    }
+
+    /// Move to `target`, playing or pausing as the party does. `gradual` allows catching
+    /// up by playing slightly faster or slower instead of seeking.
+    fn sync_to_party(
+        &self,
+        target: Duration,
+        playing: Option<bool>,
+        gradual: bool,
+        sender: &AsyncComponentSender<Self>,
+    ) {
+        let is_playing = match self.player_state {
+            PlayerState::Playing => true,
+            PlayerState::Paused => false,
+            // Loading applies the state once playback starts
+            _ => return,
+        };
+        let Some(player) = &self.player else {
+            return;
+        };
+
+        let player_handle = player.clone();
+        let sender = sender.clone();
+        glib::spawn_future_local(async move {
+            let playing = playing.unwrap_or(is_playing);
+            if playing != is_playing {
+                let result = if playing {
+                    player_handle.play().await
+                } else {
+                    player_handle.pause().await
+                };
+                if let Err(e) = result {
+                    warn!("Failed to follow the watch party: {}", e);
+                }
+            }
+            if let Ok(Some(position)) = player_handle.get_position().await {
+                sender.input(PlayerInput::WatchPartyDrift {
+                    position,
+                    target,
+                    gradual: gradual && playing,
+                });
+            }
+        });
+    }
+
+    /// Play faster or slower than the chosen speed to catch up with the party
+    fn set_party_rate(&mut self, rate: f64) {
+        if (rate - self.party_rate).abs() < f64::EPSILON {
+            return;
+        }
+        self.party_rate = rate;
+        if let Some(player) = &self.player {
+            let player_handle = player.clone();
+            let speed = self.playback_speed * rate;
+            glib::spawn_future_local(async move {
+                if let Err(e) = player_handle.set_playback_speed(speed).await {
+                    warn!("Failed to adjust speed for the watch party: {}", e);
+                }
+            });
+        }
+    }
+
+    /// Replace the chapter list, redrawing the seek bar ticks and the chapter menu
+    fn set_chapters(&mut self, chapters: Vec<Chapter>, from_server: bool) {
+        self.chapters = chapters;
+        self.chapters_from_server = from_server;
+        self.current_chapter = None;
+
+        self.refresh_seek_bar_marks();
+
+        self.chapter_list.remove_all();
+        self.chapter_thumbnails.clear();
+        let show_thumbnails = self.chapters.iter().any(|c| c.thumbnail_url.is_some());
+        let media_id = self
+            .media_item_id
+            .as_ref()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            let row_box = gtk::Box::builder()
+                .orientation(gtk::Orientation::Horizontal)
+                .spacing(12)
+                .margin_top(6)
+                .margin_bottom(6)
+                .margin_start(6)
+                .margin_end(6)
+                .build();
+
+            if show_thumbnails {
+                let picture = gtk::Picture::builder()
+                    .width_request(128)
+                    .height_request(72)
+                    .content_fit(gtk::ContentFit::Cover)
+                    .build();
+                picture.add_css_class("chapter-thumbnail");
+                row_box.append(&picture);
+
+                if let Some(url) = &chapter.thumbnail_url {
+                    let id = format!("{}:chapter-{}", media_id, index);
+                    self.chapter_thumbnails.insert(id.clone(), picture);
+                    self.image_loader
+                        .emit(ImageLoaderInput::LoadImage(ImageRequest {
+                            id,
+                            url: url.clone(),
+                            size: ImageSize::Custom(256, 144),
+                            priority: index.min(u8::MAX as usize) as u8,
+                        }));
+                }
+            }
+
+            let labels = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .valign(gtk::Align::Center)
+                .spacing(2)
+                .build();
+            let title = gtk::Label::builder()
+                .label(&chapter.title)
+                .xalign(0.0)
+                .ellipsize(gtk::pango::EllipsizeMode::End)
+                .max_width_chars(32)
+                .build();
+            let time = gtk::Label::builder()
+                .label(format_duration(chapter.start_time))
+                .xalign(0.0)
+                .build();
+            time.add_css_class("dim-label");
+            time.add_css_class("caption");
+            labels.append(&title);
+            labels.append(&time);
+            row_box.append(&labels);
+
+            self.chapter_list.append(&row_box);
+        }
+
+        self.update_current_chapter();
+    }
+
+    /// Highlight the chapter the position is in
+    fn update_current_chapter(&mut self) {
+        let current = Chapter::index_at(&self.chapters, self.position);
+        if current == self.current_chapter {
+            return;
+        }
+        self.current_chapter = current;
+        match current.and_then(|index| self.chapter_list.row_at_index(index as i32)) {
+            Some(row) => self.chapter_list.select_row(Some(&row)),
+            None => self.chapter_list.unselect_all(),
+        }
+    }
+
+    fn update_playlist_position_label(&self, context: &PlaylistContext) {
+        match context {
+            PlaylistContext::SingleItem => {
+                self.playlist_position_label.set_text("");
+            }
+            PlaylistContext::TvShow {
+                show_title,
+                current_index,
+                episodes,
+                ..
+            } => {
+                if let Some(current_episode) = episodes.get(*current_index) {
+                    let text = format!(
+                        "{} - S{}E{} - Episode {} of {}",
+                        show_title,
+                        current_episode.season_number,
+                        current_episode.episode_number,
+                        current_index + 1,
+                        episodes.len()
+                    );
+                    self.playlist_position_label.set_text(&text);
+                }
+            }
+            PlaylistContext::Queue(queue) => match queue.current_index {
+                Some(index) => {
+                    let text = format!("Queue - Item {} of {}", index + 1, queue.items.len());
+                    self.playlist_position_label.set_text(&text);
+                }
+                None => self.playlist_position_label.set_text(""),
+            },
+        }
+    }
+}
+
+impl std::fmt::Debug for PlayerPage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlayerPage")
+            .field("media_item_id", &self.media_item_id)
+            .field("player_state", &self.player_state)
+            .field("position", &self.position)
+            .field("duration", &self.duration)
+            .field("volume", &self.volume)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub enum PlayerInput {
+    LoadMedia(MediaItemId),
+    LoadMediaWithContext {
+        media_id: MediaItemId,
+        context: PlaylistContext,
+    },
+    StreamLoaded {
+        media_id: MediaItemId,
+        stream_info: StreamInfo,
+    },
+    UpdateTrackMenus,
+    SetAudioTrack(i32),
+    /// Play on another audio output, empty for the system default
+    SetAudioDevice(String),
+    SetAudioPreset(AudioPreset),
+    SetEqualizerBand(usize, f64),
+    SaveAudioEffects,
+    SetSubtitleTrack(i32),
+    PlayPause,
+    Stop,
+    Seek(Duration),
+    SetVolume(f64),
+    ToggleFullscreen,
+    ShowControls,
+    HideControls,
+    ResetControlsTimer,
+    Previous,
+    Next,
+    ShowCursor,
+    HideCursor,
+    ResetCursorTimer,
+    StartSeeking,
+    StopSeeking,
+    UpdateSeekPreview(Duration),
+    Rewind,
+    Forward,
+    RetryLoad,
+    /// Reload the dropped stream from a fresh connection
+    Reconnect,
+    /// See whether playback stopped moving
+    CheckStall,
+    ClearError,
+    ShowError(String),
+    EscapePressed,
+    NavigateBack,
+    // Speed controls
+    SpeedUp,
+    SpeedDown,
+    SpeedReset,
+    // Frame stepping
+    FrameStepForward,
+    FrameStepBackward,
+    // Audio controls
+    ToggleMute,
+    VolumeUp,
+    VolumeDown,
+    // Track cycling
+    CycleSubtitleTrack,
+    CycleAudioTrack,
+    // Control visibility
+    ToggleControlsVisibility,
+    // Relative seeking
+    SeekRelative(i64), // Positive for forward, negative for backward
+    // Quality and transcoding
+    SetQuality(usize),
+    PingTranscodeSession,
+    // Subtitle files and timing
+    OpenSubtitleFile,
+    LoadSubtitleFile(std::path::PathBuf),
+    AdjustSubtitleDelay(i64),
+    AdjustAudioDelay(i64),
+    HideSyncOffset,
+    SyncOffsetsLoaded {
+        subtitle_delay_ms: i64,
+        audio_delay_ms: i64,
+    },
+    // Intro, credits, recap and preview skipping
+    SkipMarker,
+    CancelSkip,
+    ToggleShowAutoSkip,
+    CancelNextEpisode,
+    CountdownTick,
+    // Chapter navigation
+    SeekToChapter(usize),
+    NextChapter,
+    PreviousChapter,
+    ChapterThumbnailLoaded {
+        id: String,
+        texture: gtk::gdk::Texture,
+    },
+    ChapterThumbnailFailed,
+    // Sleep timer
+    SetSleepTimer(u64),
+    SleepAfterCurrent,
+    CancelSleepTimer,
+    SleepTimerTick,
+    // Play queue
+    QueueMedia {
+        media_id: MediaItemId,
+        play_next: bool,
+    },
+    PlayQueueItem(usize),
+    /// The queue was changed and saved by the music player
+    QueueReplaced(PlayQueue),
+    MoveQueueItem {
+        from: usize,
+        to: usize,
+    },
+    RemoveQueueItem(usize),
+    SetQueueShuffle(bool),
+    CycleQueueRepeat,
+    ClearQueue,
+    /// Move on after the current item ended on its own
+    AutoAdvance,
+    // Video adjustments
+    AdjustVideo(VideoSetting),
+    DetectBlackBars,
+    BlackBarsDetected(Result<Option<CropRect>, String>),
+    ClearCrop,
+    ResetVideoAdjustments,
+    VideoAdjustmentsLoaded(VideoAdjustments),
+    SaveVideoAdjustments {
+        media_id: MediaItemId,
+        adjustments: VideoAdjustments,
+    },
+    // Picture-in-picture
+    EnterPictureInPicture,
+    /// Move the video back into the player page
+    LeavePictureInPicture,
+    /// Stop playback from the picture-in-picture window
+    ClosePictureInPicture,
+    // Screenshots and clips
+    TakeScreenshot,
+    /// Mark the current position as where the clip starts, or ends if `end`
+    MarkClip {
+        end: bool,
+    },
+    ClipMarked {
+        end: bool,
+        position: Duration,
+    },
+    ExportClip,
+    // A-B loop
+    /// Mark where the loop starts, then where it ends, then clear it
+    CycleLoop,
+    LoopPointMarked(Duration),
+    ClearLoop,
+    // Watch party
+    HostWatchParty,
+    /// Take part in a party joined from the main window
+    JoinWatchParty {
+        party: WatchParty,
+        events: tokio::sync::mpsc::UnboundedReceiver<PartyEvent>,
+    },
+    CopyWatchPartyCode,
+    LeaveWatchParty,
+    WatchPartyEvent(PartyEvent),
+    WatchPartyHeartbeat,
+    /// Where playback is here compared to where the party is
+    WatchPartyDrift {
+        position: Duration,
+        target: Duration,
+        gradual: bool,
+    },
+    /// Playback control from a Jellyfin or Plex app
+    RemoteCommand(RemoteCommand),
+    // Casting
+    FindCastDevices,
+    /// Play the current item on one of the receivers found
+    CastTo(usize),
+    StopCasting,
+    CastStatusTick,
+    // Statistics overlay
+    ToggleStats,
+    StatsTick,
+    StatsUpdated(PlaybackStats),
+}
+
+#[derive(Debug, Clone)]
+pub enum PlayerOutput {
+    NavigateBack,
+    MediaLoaded,
+    Error(String),
+    WindowStateChanged {
+        width: i32,
+        height: i32,
+    },
+    /// The video moved to the picture-in-picture window and the page can be left
+    PictureInPictureStarted,
+    /// The picture-in-picture window closed, either back to the player page or stopping playback
+    PictureInPictureEnded {
+        return_to_player: bool,
+    },
+    ShowToast(String),
+    /// A screenshot or clip was saved to this file
+    CaptureSaved(std::path::PathBuf),
+    /// The queue was changed and saved here
+    QueueChanged(PlayQueue),
+}
+
+pub enum PlayerCommandOutput {
+    StateChanged(PlayerState),
+    /// A newly loaded item is ready, including any resume seek
+    MediaReady(PlayerState),
+    PositionUpdate {
+        position: Duration,
+        duration: Option<Duration>,
+    },
+    EndOfStream,
+    TracksChanged,
+    ChaptersChanged,
+    Buffering(u8),
+    LoadError(String),
+    /// The player failed while playing, which a reconnect may recover from
+    PlaybackError(String),
+    /// A reconnect attempt finished, with the new stream and its quality index
+    Reconnected {
+        media_id: MediaItemId,
+        result: Result<(StreamInfo, usize), String>,
+    },
+    TrickplayLoaded {
+        media_id: MediaItemId,
+        trickplay: Option<Trickplay>,
+    },
+    MarkersLoaded {
+        media_id: MediaItemId,
+        markers: Vec<ChapterMarker>,
+        show_skip_modes: HashMap<ChapterType, MarkerSkipMode>,
+    },
+    ChaptersLoaded {
+        media_id: MediaItemId,
+        chapters: Vec<Chapter>,
+        from_server: bool,
+    },
+    MprisStarted(
+        Option<(
+            MprisServer,
+            tokio::sync::mpsc::UnboundedReceiver<MprisAction>,
+        )>,
+    ),
+    QueueLoaded(PlayQueue),
+    QueueItemReady {
+        item: QueueItem,
+        play_next: bool,
+    },
+    QueueItemFailed,
+    WatchPartyStarted(
+        Result<(WatchParty, tokio::sync::mpsc::UnboundedReceiver<PartyEvent>), String>,
+    ),
+    CastDevicesFound(Vec<CastDevice>),
+    CastStarted(
+        Result<
+            (
+                CastDevice,
+                Arc<dyn CastReceiver>,
+                Option<(MediaItemId, String)>,
+            ),
+            String,
+        >,
+    ),
+    CastStatus(Result<CastStatus, String>),
+    /// The receiver moved on to the next item in a transcode session of its own
+    CastReloaded(Result<(CastStatus, Option<(MediaItemId, String)>), String>),
+    /// What the server is doing in the stream's transcode session
+    TranscodeStatusLoaded(Result<Option<TranscodeStatus>, String>),
+    /// A screenshot or clip was saved, or why it wasn't
+    CaptureFinished(Result<std::path::PathBuf, String>),
+}
+
+impl std::fmt::Debug for PlayerCommandOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StateChanged(state) => write!(f, "StateChanged({:?})", state),
+            Self::MediaReady(state) => write!(f, "MediaReady({:?})", state),
+            Self::PositionUpdate { position, duration } => {
+                write!(
+                    f,
+                    "PositionUpdate {{ position: {:?}, duration: {:?} }}",
+                    position, duration
+                )
+            }
+            Self::EndOfStream => write!(f, "EndOfStream"),
+            Self::TracksChanged => write!(f, "TracksChanged"),
+            Self::ChaptersChanged => write!(f, "ChaptersChanged"),
+            Self::Buffering(percent) => write!(f, "Buffering({}%)", percent),
+            Self::LoadError(msg) => write!(f, "LoadError({})", msg),
+            Self::PlaybackError(msg) => write!(f, "PlaybackError({})", msg),
+            Self::Reconnected { media_id, result } => write!(
+                f,
+                "Reconnected {{ media_id: {}, result: {:?} }}",
+                media_id,
+                result.as_ref().map(|(_, quality)| quality)
+            ),
+            Self::TrickplayLoaded {
+                media_id,
+                trickplay,
+            } => write!(
+                f,
+                "TrickplayLoaded {{ media_id: {}, frames: {:?} }}",
+                media_id,
+                trickplay.as_ref().map(|t| t.frames.len())
+            ),
+            Self::MarkersLoaded {
+                media_id, markers, ..
+            } => write!(
+                f,
+                "MarkersLoaded {{ media_id: {}, markers: {} }}",
+                media_id,
+                markers.len()
+            ),
+            Self::ChaptersLoaded {
+                media_id,
+                chapters,
+                from_server,
+            } => write!(
+                f,
+                "ChaptersLoaded {{ media_id: {}, chapters: {}, from_server: {} }}",
+                media_id,
+                chapters.len(),
+                from_server
+            ),
+            Self::MprisStarted(started) => write!(f, "MprisStarted({})", started.is_some()),
+            Self::QueueLoaded(queue) => write!(f, "QueueLoaded({} items)", queue.items.len()),
+            Self::QueueItemReady { item, play_next } => write!(
+                f,
+                "QueueItemReady {{ id: {}, play_next: {} }}",
+                item.id, play_next
+            ),
+            Self::QueueItemFailed => write!(f, "QueueItemFailed"),
+            Self::WatchPartyStarted(started) => {
+                write!(f, "WatchPartyStarted({:?})", started.as_ref().map(|_| ()))
+            }
+            Self::CastDevicesFound(devices) => {
+                write!(f, "CastDevicesFound({} devices)", devices.len())
+            }
+            Self::CastStarted(started) => write!(
+                f,
+                "CastStarted({:?})",
+                started.as_ref().map(|(device, _, _)| &device.name)
+            ),
+            Self::CastStatus(status) => write!(f, "CastStatus({:?})", status),
+            Self::CastReloaded(reloaded) => write!(
+                f,
+                "CastReloaded({:?})",
+                reloaded.as_ref().map(|(status, _)| status)
+            ),
+            Self::TranscodeStatusLoaded(status) => write!(f, "TranscodeStatusLoaded({:?})", status),
+            Self::CaptureFinished(result) => write!(f, "CaptureFinished({:?})", result),
+        }
+    }
+}
+
+#[relm4::component(pub async)]
+impl AsyncComponent for PlayerPage {
+    type Init = (
+        Option<MediaItemId>,
+        Arc<crate::db::connection::DatabaseConnection>,
+        adw::ApplicationWindow,
+    );
+    type Input = PlayerInput;
+    type Output = PlayerOutput;
+    type CommandOutput = PlayerCommandOutput;
+
+    view! {
+        gtk::Overlay {
+            set_vexpand: true,
+            set_hexpand: true,
+
+            // Video container as the main child
+            model.video_container.clone() {
+                set_vexpand: true,
+                set_hexpand: true,
+                set_valign: gtk::Align::Fill,
+                set_halign: gtk::Align::Fill,
+                add_css_class: "video-area",
+            },
+
+            // Top left OSD controls (back button)
+            add_overlay = &gtk::Box {
+                set_halign: gtk::Align::Start,
+                set_valign: gtk::Align::Start,
+                set_margin_top: 12,
+                set_margin_start: 12,
+                add_css_class: "osd-overlay",
+                #[watch]
+                set_visible: model.show_controls,
+                #[watch]
+                set_opacity: if model.show_controls { 1.0 } else { 0.0 },
+
+                gtk::Button {
+                    set_icon_name: "go-previous-symbolic",
+                    set_tooltip_text: Some("Back"),
+                    add_css_class: "osd",
+                    add_css_class: "circular",
+                    connect_clicked[sender] => move |_| {
+                        sender.input(PlayerInput::NavigateBack);
+                    },
+                },
+            },
+
+            // Top right OSD controls (picture-in-picture and fullscreen buttons)
+            add_overlay = &gtk::Box {
+                set_halign: gtk::Align::End,
+                set_valign: gtk::Align::Start,
+                set_margin_top: 12,
+                set_margin_end: 12,
+                set_spacing: 6,
+                add_css_class: "osd-overlay",
+                #[watch]
+                set_visible: model.show_controls,
+                #[watch]
+                set_opacity: if model.show_controls { 1.0 } else { 0.0 },
+
+                gtk::Button {
+                    set_icon_name: "utilities-system-monitor-symbolic",
+                    set_tooltip_text: Some("Playback Statistics"),
+                    add_css_class: "osd",
+                    add_css_class: "circular",
+                    connect_clicked => PlayerInput::ToggleStats,
+                },
+
+                gtk::Button {
+                    set_icon_name: "view-paged-symbolic",
+                    set_tooltip_text: Some("Picture in Picture"),
+                    add_css_class: "osd",
+                    add_css_class: "circular",
+                    connect_clicked => PlayerInput::EnterPictureInPicture,
+                },
+
+                gtk::Button {
+                    #[watch]
+                    set_icon_name: if model.is_fullscreen {
+                        "view-restore-symbolic"
+                    } else {
+                        "view-fullscreen-symbolic"
+                    },
+                    set_tooltip_text: Some("Toggle Fullscreen"),
+                    add_css_class: "osd",
+                    add_css_class: "circular",
+                    connect_clicked => PlayerInput::ToggleFullscreen,
+                },
+            },
+
+            // Statistics for diagnosing stutter
+            add_overlay = &gtk::Label {
+                set_halign: gtk::Align::Start,
+                set_valign: gtk::Align::Start,
+                set_margin_top: 64,
+                set_margin_start: 12,
+                set_xalign: 0.0,
+                set_selectable: false,
+                set_can_target: false,
+                add_css_class: "osd",
+                add_css_class: "monospace",
+                #[watch]
+                set_visible: model.stats.is_some(),
+                #[watch]
+                set_label: &model.stats_text(),
+            },
+
+            // Subtitle and audio delay while it's being changed
+            add_overlay = &gtk::Label {
+                set_halign: gtk::Align::Center,
+                set_valign: gtk::Align::Start,
+                set_margin_top: 64,
+                set_can_target: false,
+                add_css_class: "osd",
+                add_css_class: "title-4",
+                #[watch]
+                set_visible: model.sync_offset_osd.is_some(),
+                #[watch]
+                set_label: model.sync_offset_osd.as_deref().unwrap_or_default(),
+            },
+
+            // Buffering indicator
+            add_overlay = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_halign: gtk::Align::Center,
+                set_valign: gtk::Align::Center,
+                set_spacing: 12,
+                #[watch]
+                set_visible: (model.buffering_percent.is_some() || model.reconnect_attempts > 0)
+                    && model.error_message.is_none(),
+                add_css_class: "osd",
+
+                gtk::Spinner {
+                    set_spinning: true,
+                    set_width_request: 48,
+                    set_height_request: 48,
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model.buffering_text(),
+                },
+            },
+
+            // Skip intro/credits/recap/preview prompt
+            add_overlay = &gtk::Box {
+                set_orientation: gtk::Orientation::Horizontal,
+                set_halign: gtk::Align::End,
+                set_valign: gtk::Align::End,
+                set_margin_end: 32,
+                set_margin_bottom: 160,
+                set_spacing: 8,
+                #[watch]
+                set_visible: model.offers_skip()
+                    && model.next_episode_countdown.is_none()
+                    && model.error_message.is_none(),
+                add_css_class: "osd",
+
+                gtk::Button {
+                    #[watch]
+                    set_label: &model.skip_prompt(),
+                    add_css_class: "suggested-action",
+                    add_css_class: "pill",
+                    connect_clicked => PlayerInput::SkipMarker,
+                },
+
+                gtk::Button {
+                    set_label: "Cancel",
+                    add_css_class: "pill",
+                    #[watch]
+                    set_visible: model.skip_countdown.is_some(),
+                    connect_clicked => PlayerInput::CancelSkip,
+                },
+
+                gtk::Button {
+                    #[watch]
+                    set_label: if model
+                        .active_marker
+                        .as_ref()
+                        .is_some_and(|m| model.skip_mode(m.marker_type) == MarkerSkipMode::Auto)
+                    {
+                        "Stop Auto-Skipping for This Show"
+                    } else {
+                        "Always Skip for This Show"
+                    },
+                    add_css_class: "flat",
+                    add_css_class: "pill",
+                    #[watch]
+                    set_visible: model.show_id().is_some(),
+                    connect_clicked => PlayerInput::ToggleShowAutoSkip,
+                },
+            },
+
+            // Up next countdown shown over the ending credits
+            add_overlay = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_halign: gtk::Align::End,
+                set_valign: gtk::Align::End,
+                set_margin_end: 32,
+                set_margin_bottom: 160,
+                set_spacing: 8,
+                #[watch]
+                set_visible: model.next_episode_countdown.is_some() && model.error_message.is_none(),
+                add_css_class: "osd",
+
+                gtk::Label {
+                    set_label: "Up Next",
+                    set_halign: gtk::Align::Start,
+                    add_css_class: "dim-label",
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: &model.next_episode_title(),
+                    set_halign: gtk::Align::Start,
+                    set_ellipsize: gtk::pango::EllipsizeMode::End,
+                    set_max_width_chars: 40,
+                    add_css_class: "title-4",
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 8,
+
+                    gtk::Button {
+                        #[watch]
+                        set_label: &format!(
+                            "Play Now ({}s)",
+                            model.next_episode_countdown.unwrap_or(0)
+                        ),
+                        add_css_class: "suggested-action",
+                        add_css_class: "pill",
+                        connect_clicked => PlayerInput::AutoAdvance,
+                    },
+
+                    gtk::Button {
+                        set_label: "Cancel",
+                        add_css_class: "pill",
+                        connect_clicked => PlayerInput::CancelNextEpisode,
+                    },
+                },
+            },
+
+            // Error message overlay
+            add_overlay = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_halign: gtk::Align::Center,
+                set_valign: gtk::Align::Center,
+                set_spacing: 20,
+                #[watch]
+                set_visible: model.error_message.is_some(),
+                add_css_class: "osd",
+                add_css_class: "error-overlay",
+
+                gtk::Image {
+                    set_icon_name: Some("dialog-error-symbolic"),
+                    set_pixel_size: 64,
+                    add_css_class: "error-icon",
+                },
+
+                gtk::Label {
+                    #[watch]
+                    set_label: model.error_message.as_deref().unwrap_or("An error occurred"),
+                    set_wrap: true,
+                    set_max_width_chars: 50,
+                    add_css_class: "title-2",
+                },
+
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 12,
+                    set_halign: gtk::Align::Center,
+
+                    gtk::Button {
+                        set_label: "Retry",
+                        add_css_class: "suggested-action",
+                        add_css_class: "pill",
+                        connect_clicked => PlayerInput::RetryLoad,
+                    },
+
+                    gtk::Button {
+                        set_label: "Go Back",
+                        add_css_class: "pill",
+                        connect_clicked[sender] => move |_| {
+                            sender.input(PlayerInput::NavigateBack);
+                        },
+                    },
+                },
+            },
+
+            // Bottom controls overlay with full control layout
+            add_overlay = &gtk::Box {
+                set_orientation: gtk::Orientation::Vertical,
+                set_halign: gtk::Align::Center,
+                set_valign: gtk::Align::End,
+                set_margin_all: 20,
+                set_width_request: 700,
+                #[watch]
+                set_visible: model.show_controls && model.error_message.is_none(),
+                #[watch]
+                set_opacity: if model.show_controls && model.error_message.is_none() { 1.0 } else { 0.0 },
+                add_css_class: "osd",
+                add_css_class: "player-controls",
+                add_css_class: "minimal",
+
+                // Playlist position indicator
+                model.playlist_position_label.clone() {
+                    add_css_class: "dim-label",
+                    set_margin_bottom: 4,
+                },
+
+                // Progress bar with time labels
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 8,
+                    set_margin_bottom: 8,
+
+                    model.position_label.clone() {
+                        add_css_class: "dim-label",
+                        set_width_chars: 7,
+                    },
+
+                    model.seek_bar.clone() {
+                        set_hexpand: true,
+                        set_draw_value: false,
+                        add_css_class: "progress-bar",
+                    },
+
+                    model.duration_label.clone() {
+                        add_css_class: "dim-label",
+                        set_width_chars: 7,
+                    },
+                },
+
+                // Main controls row with three sections
+                gtk::Box {
+                    set_orientation: gtk::Orientation::Horizontal,
+                    set_spacing: 0,
+
+                    // Left section: Volume control
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_width_request: 150,
+                        set_halign: gtk::Align::Start,
+                        set_spacing: 4,
+
+                        gtk::Image {
+                            set_icon_name: Some("audio-volume-high-symbolic"),
+                            set_pixel_size: 16,
+                            add_css_class: "dim-label",
+                        },
+
+                        model.volume_slider.clone() {
+                            set_size_request: (100, -1),
+                            set_draw_value: false,
+                            add_css_class: "volume-slider",
+                            set_value: 1.0,
+                        },
+                    },
+
+                    // Center section: Playback controls
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_spacing: 4,
+                        set_halign: gtk::Align::Center,
+                        set_hexpand: true,
+
+                        // Previous track button
+                        gtk::Button {
+                            set_icon_name: "media-skip-backward-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Previous"),
+                            #[watch]
+                            set_sensitive: model.can_go_previous,
+                            connect_clicked => PlayerInput::Previous,
+                        },
+
+                        // Rewind button (seek backward 10s)
+                        gtk::Button {
+                            set_icon_name: "media-seek-backward-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Rewind 10 seconds"),
+                            connect_clicked => PlayerInput::Rewind,
+                        },
+
+                        // Play/pause button (center, compact)
+                        gtk::Box {
+                            set_size_request: (36, 36),
+                            set_halign: gtk::Align::Center,
+                            set_valign: gtk::Align::Center,
+                            add_css_class: "play-pause-container",
+
+                            gtk::Button {
+                                #[watch]
+                                set_icon_name: if matches!(model.player_state, PlayerState::Playing) {
+                                    "media-playback-pause-symbolic"
+                                } else {
+                                    "media-playback-start-symbolic"
+                                },
+                                add_css_class: "circular",
+                                add_css_class: "play-pause-button",
+                                set_can_shrink: false,
+                                connect_clicked => PlayerInput::PlayPause,
+                            },
+                        },
+
+                        // Forward button (seek forward 10s)
+                        gtk::Button {
+                            set_icon_name: "media-seek-forward-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Forward 10 seconds"),
+                            connect_clicked => PlayerInput::Forward,
+                        },
+
+                        // Next track button
+                        gtk::Button {
+                            set_icon_name: "media-skip-forward-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Next"),
+                            #[watch]
+                            set_sensitive: model.can_go_next,
+                            connect_clicked => PlayerInput::Next,
+                        },
+                    },
+
+                    // Right section: Track selection and fullscreen
+                    gtk::Box {
+                        set_orientation: gtk::Orientation::Horizontal,
+                        set_width_request: 150,
+                        set_halign: gtk::Align::End,
+                        set_spacing: 2,
+
+                        // Play queue button
+                        model.queue_menu_button.clone() {
+                            set_icon_name: "view-list-ordered-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Play Queue"),
+                        },
+
+                        // Sleep timer button and time left
+                        gtk::Label {
+                            add_css_class: "dim-label",
+                            add_css_class: "numeric",
+                            #[watch]
+                            set_visible: model.sleep_timer.is_some(),
+                            #[watch]
+                            set_label: &model.sleep_timer_label(),
+                        },
+
+                        model.sleep_timer_menu_button.clone() {
+                            set_icon_name: "alarm-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Sleep Timer"),
+                        },
+
+                        // Chapters button
+                        model.chapter_menu_button.clone() {
+                            set_icon_name: "view-list-bullet-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Chapters"),
+                            #[watch]
+                            set_visible: !model.chapters.is_empty(),
+                        },
+
+                        // Audio tracks button
+                        model.audio_menu_button.clone() {
+                            set_icon_name: "audio-x-generic-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Audio Track"),
+                        },
+
+                        // Subtitle tracks button
+                        model.subtitle_menu_button.clone() {
+                            set_icon_name: "media-view-subtitles-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Subtitles"),
+                        },
+
+                        // Watch party button
+                        model.watch_party_menu_button.clone() {
+                            set_icon_name: "system-users-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Watch Party"),
+                        },
+
+                        // Cast button
+                        model.cast_menu_button.clone() {
+                            set_icon_name: "video-display-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Cast"),
+                        },
+
+                        // Screenshot and clip button
+                        model.capture_menu_button.clone() {
+                            set_icon_name: "camera-photo-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Screenshot and Clip"),
+                        },
+
+                        // Aspect, zoom, crop and colour button
+                        model.video_adjustments_menu_button.clone() {
+                            set_icon_name: "display-brightness-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Video Adjustments"),
+                        },
+
+                        // Quality/Resolution button
+                        model.quality_menu_button.clone() {
+                            set_icon_name: "preferences-system-symbolic",
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Video Quality"),
+                        },
+
+                        // Fullscreen button
+                        gtk::Button {
+                            #[watch]
+                            set_icon_name: if model.is_fullscreen {
+                                "view-restore-symbolic"
+                            } else {
+                                "view-fullscreen-symbolic"
+                            },
+                            add_css_class: "flat",
+                            set_tooltip_text: Some("Fullscreen"),
+                            connect_clicked => PlayerInput::ToggleFullscreen,
+                        },
+                    },
+                },
+            },
+        }
+    }
+
+    async fn init(
+        init: Self::Init,
+        root: Self::Root,
+        sender: AsyncComponentSender<Self>,
+    ) -> AsyncComponentParts<Self> {
+        let (media_item_id, db, window) = init;
+
+        // Load player CSS styles
+        let css_provider = gtk::CssProvider::new();
+        css_provider.load_from_string(include_str!("../../styles/player.css"));
+        gtk::style_context_add_provider_for_display(
+            &gtk::gdk::Display::default().expect("Could not get default display"),
+            &css_provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+
+        // Create a container for the video widget that will be populated when player initializes
+        let video_container = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .build();
+
+        // Add drag gesture to make the window draggable by clicking on the video area
+        let drag_gesture = gtk::GestureDrag::new();
+        let window_clone = window.clone();
+        drag_gesture.connect_drag_begin(move |gesture, start_x, start_y| {
+            // Only start drag if it's a primary button (left click) and not in fullscreen
+            if let Some(event) = gesture.current_event() {
+                if event.triggers_context_menu() {
+                    return; // Don't drag on right-click
+                }
+
+                // Get the surface and start the drag
+                if !window_clone.is_fullscreen() {
+                    if let Some(surface) = window_clone.surface() {
+                        // Check if surface implements Toplevel interface
+                        use gtk::gdk::prelude::ToplevelExt;
+                        if let Some(toplevel) = surface.downcast_ref::<gtk::gdk::Toplevel>() {
+                            toplevel.begin_move(
+                                &event.device().unwrap(),
+                                gesture.current_button() as i32,
+                                start_x,
+                                start_y,
+                                event.time(),
+                            );
+                        }
+                    }
+                }
+            }
+        });
+        video_container.add_controller(drag_gesture);
+
+        // Accept subtitle files dropped onto the video
+        let drop_target = gtk::DropTarget::new(
+            gtk::gdk::FileList::static_type(),
+            gtk::gdk::DragAction::COPY,
+        );
+        let sender_drop = sender.clone();
+        drop_target.connect_drop(move |_, value, _, _| {
+            let Ok(files) = value.get::<gtk::gdk::FileList>() else {
+                return false;
+            };
+
+            let subtitle_path = files
+                .files()
+                .into_iter()
+                .filter_map(|f| f.path())
+                .find(|path| {
+                    path.extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| {
+                            SUBTITLE_FILE_EXTENSIONS.contains(&ext.to_lowercase().as_str())
+                        })
+                });
+
+            match subtitle_path {
+                Some(path) => {
+                    sender_drop.input(PlayerInput::LoadSubtitleFile(path));
+                    true
+                }
+                None => false,
+            }
+        });
+        video_container.add_controller(drop_target);
+
+        // Add a placeholder initially
+        let placeholder = gtk::Label::new(Some("Initializing player..."));
+        placeholder.add_css_class("title-1");
+        placeholder.set_valign(gtk::Align::Center);
+        placeholder.set_halign(gtk::Align::Center);
+        video_container.append(&placeholder);
+
+        // Create seek bar
+        let seek_bar = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 100.0, 1.0);
+        seek_bar.set_draw_value(false);
+        seek_bar.set_has_tooltip(true);
+
+        // Add tooltip to show time at cursor position
+        seek_bar.connect_query_tooltip(|scale, x, _y, _keyboard_mode, tooltip| {
+            let adjustment = scale.adjustment();
+            // x is already relative to the widget
+            let width = scale.allocated_width() as f64;
+            let ratio = x as f64 / width;
+            let max = adjustment.upper();
+            let value = ratio * max;
+            // Clamp value to ensure it's non-negative
+            let duration = Duration::from_secs_f64(value.max(0.0));
+            tooltip.set_text(Some(&format_duration(duration)));
+            true
+        });
+
+        // Create seek preview popover shown above the seek bar while dragging
+        let seek_preview_picture = gtk::Picture::builder()
+            .width_request(240)
+            .height_request(135)
+            .content_fit(gtk::ContentFit::Contain)
+            .build();
+        let seek_preview_popover = gtk::Popover::builder()
+            .child(&seek_preview_picture)
+            .autohide(false)
+            .has_arrow(false)
+            .position(gtk::PositionType::Top)
+            .can_target(false)
+            .build();
+        seek_preview_popover.add_css_class("seek-preview");
+        seek_preview_popover.set_parent(&seek_bar);
+
+        // Create time labels
+        let position_label = gtk::Label::new(Some("0:00"));
+        let duration_label = gtk::Label::new(Some("0:00"));
+        let playlist_position_label = gtk::Label::new(None);
+
+        // Create volume slider
+        let volume_slider = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, 1.0, 0.01);
+        volume_slider.set_value(1.0);
+        volume_slider.set_draw_value(false);
+
+        // Create menu buttons for track selection
+        let audio_menu_button = gtk::MenuButton::new();
+        let subtitle_menu_button = gtk::MenuButton::new();
+        let quality_menu_button = gtk::MenuButton::new();
+        quality_menu_button.set_sensitive(false);
+
+        // Chapter menu, filled once the chapters of an item are known
+        let chapter_list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::Single)
+            .activate_on_single_click(true)
+            .build();
+        chapter_list.add_css_class("navigation-sidebar");
+        let chapter_popover = gtk::Popover::builder()
+            .child(
+                &gtk::ScrolledWindow::builder()
+                    .hscrollbar_policy(gtk::PolicyType::Never)
+                    .max_content_height(420)
+                    .propagate_natural_height(true)
+                    .child(&chapter_list)
+                    .build(),
+            )
+            .build();
+        let chapter_menu_button = gtk::MenuButton::builder()
+            .popover(&chapter_popover)
+            .visible(false)
+            .build();
+        {
+            let sender = sender.clone();
+            chapter_list.connect_row_activated(move |_, row| {
+                chapter_popover.popdown();
+                sender.input(PlayerInput::SeekToChapter(row.index() as usize));
+            });
+        }
+
+        // Sleep timer menu
+        let sleep_timer_menu_button = gtk::MenuButton::new();
+        {
+            let menu = gtk::gio::Menu::new();
+            let action_group = gtk::gio::SimpleActionGroup::new();
+
+            let lengths = gtk::gio::Menu::new();
+            for &minutes in SLEEP_TIMER_MINUTES {
+                let label = if minutes % 60 == 0 {
+                    match minutes / 60 {
+                        1 => "1 hour".to_string(),
+                        hours => format!("{} hours", hours),
+                    }
+                } else {
+                    format!("{} minutes", minutes)
+                };
+                let action_name = format!("sleep-{}", minutes);
+                lengths.append(Some(&label), Some(&format!("player.{}", action_name)));
+
+                let action = gtk::gio::SimpleAction::new(&action_name, None);
+                let sender = sender.clone();
+                action.connect_activate(move |_, _| {
+                    sender.input(PlayerInput::SetSleepTimer(minutes));
+                });
+                action_group.add_action(&action);
+            }
+            menu.append_section(None, &lengths);
+
+            let other = gtk::gio::Menu::new();
+            other.append(
+                Some("End of Current Item"),
+                Some("player.sleep-end-of-item"),
+            );
+            other.append(Some("Off"), Some("player.sleep-off"));
+            menu.append_section(None, &other);
+
+            let end_of_item = gtk::gio::SimpleAction::new("sleep-end-of-item", None);
+            let sender_clone = sender.clone();
+            end_of_item.connect_activate(move |_, _| {
+                sender_clone.input(PlayerInput::SleepAfterCurrent);
+            });
+            action_group.add_action(&end_of_item);
+
+            let off = gtk::gio::SimpleAction::new("sleep-off", None);
+            let sender_clone = sender.clone();
+            off.connect_activate(move |_, _| {
+                sender_clone.input(PlayerInput::CancelSleepTimer);
+            });
+            action_group.add_action(&off);
+
+            sleep_timer_menu_button.insert_action_group("player", Some(&action_group));
+            sleep_timer_menu_button.set_popover(Some(&gtk::PopoverMenu::from_model(Some(&menu))));
+        }
+
+        // Play queue popover with its Up Next list
+        let queue_list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .activate_on_single_click(true)
+            .build();
+        queue_list.add_css_class("navigation-sidebar");
+        queue_list.set_placeholder(Some(
+            &gtk::Label::builder()
+                .label("The queue is empty")
+                .margin_top(12)
+                .margin_bottom(12)
+                .css_classes(["dim-label"])
+                .build(),
+        ));
+        let queue_shuffle_button = gtk::ToggleButton::builder()
+            .icon_name("media-playlist-shuffle-symbolic")
+            .tooltip_text("Shuffle")
+            .css_classes(["flat"])
+            .build();
+        let queue_repeat_button = gtk::Button::builder()
+            .icon_name("media-playlist-consecutive-symbolic")
+            .tooltip_text("Repeat Off")
+            .css_classes(["flat"])
+            .build();
+        let queue_clear_button = gtk::Button::builder()
+            .icon_name("edit-clear-all-symbolic")
+            .tooltip_text("Clear Queue")
+            .css_classes(["flat"])
+            .build();
+        let queue_header = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(2)
+            .margin_start(6)
+            .margin_end(6)
+            .build();
+        queue_header.append(
+            &gtk::Label::builder()
+                .label("Up Next")
+                .xalign(0.0)
+                .hexpand(true)
+                .css_classes(["heading"])
+                .build(),
+        );
+        queue_header.append(&queue_shuffle_button);
+        queue_header.append(&queue_repeat_button);
+        queue_header.append(&queue_clear_button);
+        let queue_box = gtk::Box::builder()
+            .orientation(gtk::Orientation::Vertical)
+            .spacing(6)
+            .width_request(360)
+            .build();
+        queue_box.append(&queue_header);
+        queue_box.append(
+            &gtk::ScrolledWindow::builder()
+                .hscrollbar_policy(gtk::PolicyType::Never)
+                .max_content_height(420)
+                .propagate_natural_height(true)
+                .child(&queue_list)
+                .build(),
+        );
+        let queue_popover = gtk::Popover::builder().child(&queue_box).build();
+        let queue_menu_button = gtk::MenuButton::builder().popover(&queue_popover).build();
+        {
+            let sender = sender.clone();
+            queue_list.connect_row_activated(move |_, row| {
+                queue_popover.popdown();
+                sender.input(PlayerInput::PlayQueueItem(row.index() as usize));
+            });
+        }
+        {
+            let sender = sender.clone();
+            queue_shuffle_button.connect_toggled(move |button| {
+                sender.input(PlayerInput::SetQueueShuffle(button.is_active()));
+            });
+        }
+        {
+            let sender = sender.clone();
+            queue_repeat_button.connect_clicked(move |_| {
+                sender.input(PlayerInput::CycleQueueRepeat);
+            });
+        }
+        {
+            let sender = sender.clone();
+            queue_clear_button.connect_clicked(move |_| {
+                sender.input(PlayerInput::ClearQueue);
+            });
+        }
+
+        // Video adjustments popover
+        let (video_adjustment_controls, video_adjustments_popover) =
+            VideoAdjustmentControls::new(&sender);
+        video_adjustment_controls.sync(&VideoAdjustments::default());
+        let video_adjustments_menu_button = gtk::MenuButton::builder()
+            .popover(&video_adjustments_popover)
+            .build();
+
+        // Screenshot and clip menu
+        let capture_menu_button = gtk::MenuButton::new();
+        let export_clip_action = gtk::gio::SimpleAction::new("export-clip", None);
+        {
+            let menu = gtk::gio::Menu::new();
+            menu.append(Some("Take Screenshot"), Some("player.take-screenshot"));
+
+            let clip = gtk::gio::Menu::new();
+            clip.append(Some("Mark Clip Start"), Some("player.mark-clip-start"));
+            clip.append(Some("Mark Clip End"), Some("player.mark-clip-end"));
+            clip.append(Some("Export Clip"), Some("player.export-clip"));
+            menu.append_section(None, &clip);
+
+            let action_group = gtk::gio::SimpleActionGroup::new();
+            let actions: [(&str, fn() -> PlayerInput); 3] = [
+                ("take-screenshot", || PlayerInput::TakeScreenshot),
+                ("mark-clip-start", || PlayerInput::MarkClip { end: false }),
+                ("mark-clip-end", || PlayerInput::MarkClip { end: true }),
+            ];
+            for (name, input) in actions {
+                let action = gtk::gio::SimpleAction::new(name, None);
+                let sender = sender.clone();
+                action.connect_activate(move |_, _| sender.input(input()));
+                action_group.add_action(&action);
+            }
+
+            // Enabled once both ends of the clip are marked
+            export_clip_action.set_enabled(false);
+            let sender_clone = sender.clone();
+            export_clip_action.connect_activate(move |_, _| {
+                sender_clone.input(PlayerInput::ExportClip);
+            });
+            action_group.add_action(&export_clip_action);
+
+            capture_menu_button.insert_action_group("player", Some(&action_group));
+            capture_menu_button.set_popover(Some(&gtk::PopoverMenu::from_model(Some(&menu))));
+        }
+
+        // Watch party menu, its items depending on whether one is going on
+        let watch_party_menu_button = gtk::MenuButton::new();
+        {
+            let action_group = gtk::gio::SimpleActionGroup::new();
+            let actions: [(&str, fn() -> PlayerInput); 3] = [
+                ("host-watch-party", || PlayerInput::HostWatchParty),
+                ("copy-watch-party-code", || PlayerInput::CopyWatchPartyCode),
+                ("leave-watch-party", || PlayerInput::LeaveWatchParty),
+            ];
+            for (name, input) in actions {
+                let action = gtk::gio::SimpleAction::new(name, None);
+                let sender = sender.clone();
+                action.connect_activate(move |_, _| sender.input(input()));
+                action_group.add_action(&action);
+            }
+            watch_party_menu_button.insert_action_group("player", Some(&action_group));
+        }
+
+        // Cast menu, looking for receivers each time it opens
+        let cast_menu = gtk::gio::Menu::new();
+        let cast_menu_button = gtk::MenuButton::new();
+        {
+            let action_group = gtk::gio::SimpleActionGroup::new();
+            let actions: [(&str, fn() -> PlayerInput); 2] = [
+                ("find-cast-devices", || PlayerInput::FindCastDevices),
+                ("stop-casting", || PlayerInput::StopCasting),
+            ];
+            for (name, input) in actions {
+                let action = gtk::gio::SimpleAction::new(name, None);
+                let sender = sender.clone();
+                action.connect_activate(move |_, _| sender.input(input()));
+                action_group.add_action(&action);
+            }
+
+            let cast_to = gtk::gio::SimpleAction::new("cast-to", Some(glib::VariantTy::UINT32));
+            let sender_clone = sender.clone();
+            cast_to.connect_activate(move |_, parameter| {
+                if let Some(index) = parameter.and_then(|p| p.get::<u32>()) {
+                    sender_clone.input(PlayerInput::CastTo(index as usize));
+                }
+            });
+            action_group.add_action(&cast_to);
+            cast_menu_button.insert_action_group("player", Some(&action_group));
+
+            cast_menu_button.set_popover(Some(&gtk::PopoverMenu::from_model(Some(&cast_menu))));
+            let sender_clone = sender.clone();
+            cast_menu_button.connect_active_notify(move |button| {
+                if button.is_active() {
+                    sender_clone.input(PlayerInput::FindCastDevices);
+                }
+            });
+        }
+
+        let image_loader =
+            ImageLoader::builder()
+                .detach_worker(())
+                .forward(sender.input_sender(), |output| match output {
+                    ImageLoaderOutput::ImageLoaded { id, texture, .. } => {
+                        PlayerInput::ChapterThumbnailLoaded { id, texture }
+                    }
+                    ImageLoaderOutput::LoadFailed { .. } | ImageLoaderOutput::CacheCleared => {
+                        PlayerInput::ChapterThumbnailFailed
+                    }
+                });
+
+        // Load config once at initialization
+        let config = Config::load().unwrap_or_default();
+
+        let mut model = Self {
+            media_item_id,
+            player: None,
+            player_state: PlayerState::Idle,
+            position: Duration::from_secs(0),
+            duration: Duration::from_secs(0),
+            volume: 1.0,
+            db,
+            video_container: video_container.clone(),
+            video_placeholder: Some(placeholder.clone()),
+            playlist_context: None,
+            can_go_previous: false,
+            can_go_next: false,
+            show_controls: true,
+            is_fullscreen: false,
+            controls_timer: None,
+            cursor_timer: None,
+            seek_bar: seek_bar.clone(),
+            position_label: position_label.clone(),
+            duration_label: duration_label.clone(),
+            volume_slider: volume_slider.clone(),
+            playlist_position_label: playlist_position_label.clone(),
+            window: window.clone(),
+            is_seeking: false,
+            error_message: None,
+            retry_count: 0,
+            max_retries: 3,
+            retry_timer: None,
+            last_progress_save: std::time::Instant::now(),
+            // Cache config values to avoid reloading every second
+            config_auto_resume: config.playback.auto_resume,
+            config_inhibit_while_paused: config.playback.inhibit_while_paused,
+            config_resume_threshold_seconds: config.playback.resume_threshold_seconds as u64,
+            config_default_subtitle: config.playback.default_subtitle.clone(),
+            config_progress_update_interval_seconds: config
+                .playback
+                .progress_update_interval_seconds
+                as u64,
+            playback_speed: 1.0,
+            audio_menu_button: audio_menu_button.clone(),
+            subtitle_menu_button: subtitle_menu_button.clone(),
+            current_audio_track: -1,
+            current_subtitle_track: -1,
+            subtitle_delay_ms: 0,
+            audio_delay_ms: 0,
+            sync_offset_osd: None,
+            sync_offset_osd_timer: None,
+            quality_menu_button: quality_menu_button.clone(),
+            quality_options: Vec::new(),
+            external_subtitles: Vec::new(),
+            preferred_external_subtitle: None,
+            transcode_session: None,
+            is_transcoding: false,
+            transcode_keepalive_timer: None,
+            trickplay: None,
+            seek_preview_popover,
+            seek_preview_picture,
+            seek_preview_index: None,
+            mpris: None,
+            mpris_position: None,
+            inhibit_cookie: None,
+            buffering_percent: None,
+            reconnect_attempts: 0,
+            reconnect_timer: None,
+            reconnect_in_flight: false,
+            reconnect_position: Duration::ZERO,
+            last_progress_at: std::time::Instant::now(),
+            stall_timer: None,
+            quality_index: 0,
+            config_reconnect_lower_quality: config.playback.reconnect_lower_quality,
+            markers: Vec::new(),
+            config_skip_modes: marker_skip_modes(&config),
+            config_auto_skip_delay: config.playback.auto_skip_delay,
+            config_auto_play_delay: config.playback.auto_play_delay,
+            show_skip_modes: HashMap::new(),
+            active_marker: None,
+            skip_countdown: None,
+            next_episode_countdown: None,
+            next_episode_cancelled: false,
+            countdown_timer: None,
+            chapters: Vec::new(),
+            chapters_from_server: false,
+            current_chapter: None,
+            chapter_menu_button: chapter_menu_button.clone(),
+            chapter_list,
+            chapter_thumbnails: HashMap::new(),
+            image_loader,
+            sleep_timer: None,
+            sleep_timer_source: None,
+            sleep_fade: None,
+            config_sleep_timer_fade_out: config.playback.sleep_timer_fade_out,
+            sleep_timer_menu_button: sleep_timer_menu_button.clone(),
+            play_queue: PlayQueue::default(),
+            queue_loaded: false,
+            pending_queue_items: Vec::new(),
+            queue_menu_button: queue_menu_button.clone(),
+            queue_list,
+            queue_shuffle_button,
+            queue_repeat_button,
+            pip: None,
+            video_adjustments: VideoAdjustments::default(),
+            video_adjustment_controls,
+            video_adjustments_menu_button: video_adjustments_menu_button.clone(),
+            video_adjustments_save_timer: None,
+            stream_info: None,
+            clip_start: None,
+            clip_end: None,
+            capture_menu_button: capture_menu_button.clone(),
+            export_clip_action,
+            loop_start: None,
+            loop_end: None,
+            stats: None,
+            stats_timer: None,
+            stats_ticks: 0,
+            transcode_status: None,
+            audio_device: String::new(),
+            audio_effects: config.playback.audio_effects(),
+            audio_effects_save_timer: None,
+            watch_party: None,
+            watch_party_menu_button: watch_party_menu_button.clone(),
+            watch_party_heartbeat: None,
+            party_media_id: None,
+            party_action_at: None,
+            party_rate: 1.0,
+            start_position: None,
+            cast: None,
+            cast_devices: Vec::new(),
+            finding_cast_devices: false,
+            cast_menu,
+            cast_menu_button: cast_menu_button.clone(),
+            cast_status_timer: None,
+        };
+        model.update_watch_party_menu();
+        model.update_cast_menu();
+
+        // Restore the queue saved by the last session
+        {
+            let db = (*model.db).clone();
+            sender.oneshot_command(async move {
+                use crate::services::commands::Command;
+                use crate::services::commands::media_commands::GetPlayQueueCommand;
+
+                match (GetPlayQueueCommand { db }).execute().await {
+                    Ok(queue) => PlayerCommandOutput::QueueLoaded(queue),
+                    Err(e) => {
+                        error!("Failed to load play queue: {}", e);
+                        PlayerCommandOutput::QueueLoaded(PlayQueue::default())
+                    }
+                }
+            });
+        }
+
+        // Initialize the player controller
+        match PlayerController::new(&config) {
+            Ok((handle, controller)) => {
+                info!("Player controller initialized successfully");
+
+                // Spawn the controller task on the main thread's executor
+                // This is needed because Player contains raw pointers that are not Send
+                glib::spawn_future_local(async move {
+                    controller.run().await;
+                });
+
+                // Create video widget synchronously on main thread
+                // Note: We need to spawn this as a local future too
+                let handle_clone = handle.clone();
+                let video_container_clone = model.video_container.clone();
+                let placeholder_clone = model.video_placeholder.take();
+                glib::spawn_future_local(async move {
+                    if let Ok(video_widget) = handle_clone.create_video_widget().await {
+                        // Remove placeholder if it exists
+                        if let Some(placeholder) = placeholder_clone {
+                            video_container_clone.remove(&placeholder);
+                        }
+
+                        // Set proper expansion for the video widget
+                        video_widget.set_vexpand(true);
+                        video_widget.set_hexpand(true);
+                        video_widget.set_valign(gtk::Align::Fill);
+                        video_widget.set_halign(gtk::Align::Fill);
+
+                        // Add the video widget
+                        video_container_clone.append(&video_widget);
+
+                        info!("Video widget successfully attached to container");
+                    }
+                });
+
+                // Follow what the backend reports instead of polling it
+                let events_handle = handle.clone();
+                sender.command(move |out, shutdown| {
+                    shutdown
+                        .register(async move {
+                            let mut events = match events_handle.subscribe().await {
+                                Ok(events) => events,
+                                Err(e) => {
+                                    error!("Failed to subscribe to player events: {}", e);
+                                    return;
+                                }
+                            };
+                            loop {
+                                let output = match events.recv().await {
+                                    Ok(PlayerEvent::StateChanged(state)) => {
+                                        PlayerCommandOutput::StateChanged(state)
+                                    }
+                                    Ok(PlayerEvent::PositionChanged { position, duration }) => {
+                                        PlayerCommandOutput::PositionUpdate { position, duration }
+                                    }
+                                    Ok(PlayerEvent::EndOfStream) => {
+                                        PlayerCommandOutput::EndOfStream
+                                    }
+                                    // Nothing is queued on the video player's backend
+                                    Ok(PlayerEvent::NextMediaStarted) => continue,
+                                    Ok(PlayerEvent::TracksChanged) => {
+                                        PlayerCommandOutput::TracksChanged
+                                    }
+                                    Ok(PlayerEvent::ChaptersChanged) => {
+                                        PlayerCommandOutput::ChaptersChanged
+                                    }
+                                    Ok(PlayerEvent::Buffering(percent)) => {
+                                        PlayerCommandOutput::Buffering(percent)
+                                    }
+                                    Ok(PlayerEvent::Error(message)) => {
+                                        PlayerCommandOutput::PlaybackError(message)
+                                    }
+                                    Err(tokio::sync::broadcast::error::RecvError::Lagged(
+                                        skipped,
+                                    )) => {
+                                        debug!("Skipped {} player events", skipped);
+                                        continue;
+                                    }
+                                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                                };
+                                if out.send(output).is_err() {
+                                    break;
+                                }
+                            }
+                        })
+                        .drop_on_shutdown()
+                });
+
+                // Expose playback to desktop media controls
+                let mpris_handle = handle.clone();
+                sender.oneshot_command(async move {
+                    match MprisServer::start(mpris_handle).await {
+                        Ok(started) => PlayerCommandOutput::MprisStarted(Some(started)),
+                        Err(e) => {
+                            warn!("Failed to start MPRIS service: {}", e);
+                            PlayerCommandOutput::MprisStarted(None)
+                        }
+                    }
+                });
+
+                model.player = Some(handle);
+            }
+            Err(e) => {
+                error!("Failed to initialize player controller: {}", e);
+                model.error_message = Some(format!("Failed to initialize player: {}", e));
+                model.player_state = PlayerState::Error;
+            }
+        }
+
+        // Setup seek bar handlers
+        {
+            // Track when user starts dragging
+            let sender_press = sender.clone();
+            let button_controller = gtk::GestureClick::new();
+            button_controller.set_button(gtk::gdk::BUTTON_PRIMARY);
+            button_controller.connect_pressed(move |_, _, _, _| {
+                sender_press.input(PlayerInput::StartSeeking);
+            });
+            model.seek_bar.add_controller(button_controller);
+
+            // Track when user releases drag and perform seek
+            let sender_release = sender.clone();
+            let seek_bar_clone = model.seek_bar.clone();
+            let button_release_controller = gtk::GestureClick::new();
+            button_release_controller.set_button(gtk::gdk::BUTTON_PRIMARY);
+            button_release_controller.connect_released(move |_, _, _, _| {
+                let position = seek_bar_clone.value();
+                sender_release.input(PlayerInput::StopSeeking);
+                // Ensure position is non-negative before creating Duration
+                sender_release.input(PlayerInput::Seek(Duration::from_secs_f64(
+                    position.max(0.0),
+                )));
+            });
+            model.seek_bar.add_controller(button_release_controller);
+
+            // Also handle value changes during drag for smooth preview
+            let sender_changed = sender.clone();
+            let seek_bar = model.seek_bar.clone();
+            seek_bar.connect_value_changed(move |scale| {
+                // Update time labels during drag for preview
+                // Clamp scale value to prevent negative Duration
+                let position = Duration::from_secs_f64(scale.value().max(0.0));
+                sender_changed.input(PlayerInput::UpdateSeekPreview(position));
+            });
+        }
+
+        // Setup volume slider handler
+        {
+            let sender = sender.clone();
+            let volume_slider = model.volume_slider.clone();
+            volume_slider.connect_value_changed(move |scale| {
+                sender.input(PlayerInput::SetVolume(scale.value()));
+            });
+        }
+
+        // Setup mouse motion handler for showing controls and cursor
+        {
+            let sender = sender.clone();
+            let motion_controller = gtk::EventControllerMotion::new();
+            motion_controller.connect_motion(move |_, _, _| {
+                sender.input(PlayerInput::ShowControls);
+                sender.input(PlayerInput::ShowCursor);
+            });
+            root.add_controller(motion_controller);
+        }
+
+        // Setup keyboard shortcuts
+        {
+            let sender = sender.clone();
+            let sender_for_escape = sender.clone();
+            let sender_for_fullscreen_check = sender.clone();
+            let key_controller = gtk::EventControllerKey::new();
+            key_controller.connect_key_pressed(move |controller, key, _keycode, modifiers| {
+                use gtk::gdk::ModifierType;
+
+                // Check modifier keys
+                let shift_pressed = modifiers.contains(ModifierType::SHIFT_MASK);
+                let ctrl_pressed = modifiers.contains(ModifierType::CONTROL_MASK);
+
+                match key {
+                    // Fullscreen controls
+                    gtk::gdk::Key::F11 | gtk::gdk::Key::f => {
+                        sender.input(PlayerInput::ToggleFullscreen);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::p => {
+                        sender.input(PlayerInput::EnterPictureInPicture);
+                        glib::Propagation::Stop
+                    }
+                    // Playback controls
+                    gtk::gdk::Key::space => {
+                        sender.input(PlayerInput::PlayPause);
+                        glib::Propagation::Stop
+                    }
+                    // Quit/Stop
+                    gtk::gdk::Key::Escape | gtk::gdk::Key::q => {
+                        sender_for_fullscreen_check.input(PlayerInput::EscapePressed);
+                        glib::Propagation::Stop
+                    }
+                    // Seeking controls
+                    gtk::gdk::Key::Left => {
+                        if ctrl_pressed {
+                            // Ctrl+Left: seek back 10s
+                            sender.input(PlayerInput::SeekRelative(-10));
+                        } else if shift_pressed {
+                            // Shift+Left: seek back 1s
+                            sender.input(PlayerInput::SeekRelative(-1));
+                        } else {
+                            // Left: seek back 5s (default)
+                            sender.input(PlayerInput::SeekRelative(-5));
+                        }
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::Right => {
+                        if ctrl_pressed {
+                            // Ctrl+Right: seek forward 10s
+                            sender.input(PlayerInput::SeekRelative(10));
+                        } else if shift_pressed {
+                            // Shift+Right: seek forward 1s
+                            sender.input(PlayerInput::SeekRelative(1));
+                        } else {
+                            // Right: seek forward 5s (default)
+                            sender.input(PlayerInput::SeekRelative(5));
+                        }
+                        glib::Propagation::Stop
+                    }
+                    // Chapter navigation
+                    gtk::gdk::Key::Page_Up => {
+                        sender.input(PlayerInput::PreviousChapter);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::Page_Down => {
+                        sender.input(PlayerInput::NextChapter);
+                        glib::Propagation::Stop
+                    }
+                    // Speed controls
+                    gtk::gdk::Key::bracketleft => {
+                        // [ key: speed down
+                        sender.input(PlayerInput::SpeedDown);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::bracketright => {
+                        // ] key: speed up
+                        sender.input(PlayerInput::SpeedUp);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::BackSpace => {
+                        // Backspace: reset speed
+                        sender.input(PlayerInput::SpeedReset);
+                        glib::Propagation::Stop
+                    }
+                    // Volume controls
+                    gtk::gdk::Key::_9 => {
+                        // 9: volume down by 10%
+                        sender.input(PlayerInput::VolumeDown);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::_0 => {
+                        // 0: volume up by 10%
+                        sender.input(PlayerInput::VolumeUp);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::m => {
+                        // m: toggle mute
+                        sender.input(PlayerInput::ToggleMute);
+                        glib::Propagation::Stop
+                    }
+                    // Frame stepping
+                    gtk::gdk::Key::period => {
+                        // . key: frame step forward
+                        sender.input(PlayerInput::FrameStepForward);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::comma => {
+                        // , key: frame step backward
+                        sender.input(PlayerInput::FrameStepBackward);
+                        glib::Propagation::Stop
+                    }
+                    // Subtitle controls
+                    gtk::gdk::Key::v => {
+                        // v: cycle subtitles
+                        sender.input(PlayerInput::CycleSubtitleTrack);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::j => {
+                        if shift_pressed {
+                            // Shift+J: cycle subtitle track backward (same as v for simplicity)
+                            sender.input(PlayerInput::CycleSubtitleTrack);
+                        } else {
+                            // j: cycle subtitle track forward
+                            sender.input(PlayerInput::CycleSubtitleTrack);
+                        }
+                        glib::Propagation::Stop
+                    }
+                    // Subtitle timing
+                    gtk::gdk::Key::z => {
+                        // z: show subtitles earlier
+                        sender.input(PlayerInput::AdjustSubtitleDelay(-SYNC_OFFSET_STEP_MS));
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::x => {
+                        // x: show subtitles later
+                        sender.input(PlayerInput::AdjustSubtitleDelay(SYNC_OFFSET_STEP_MS));
+                        glib::Propagation::Stop
+                    }
+                    // Audio timing
+                    gtk::gdk::Key::minus if ctrl_pressed => {
+                        // Ctrl+-: play audio earlier
+                        sender.input(PlayerInput::AdjustAudioDelay(-SYNC_OFFSET_STEP_MS));
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::plus | gtk::gdk::Key::equal if ctrl_pressed => {
+                        // Ctrl++: play audio later
+                        sender.input(PlayerInput::AdjustAudioDelay(SYNC_OFFSET_STEP_MS));
+                        glib::Propagation::Stop
+                    }
+                    // Audio track cycling
+                    gtk::gdk::Key::numbersign => {
+                        // # key: cycle audio track
+                        sender.input(PlayerInput::CycleAudioTrack);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::_3 if shift_pressed => {
+                        // Shift+3 (also #): cycle audio track
+                        sender.input(PlayerInput::CycleAudioTrack);
+                        glib::Propagation::Stop
+                    }
+                    // Controls visibility
+                    gtk::gdk::Key::Tab => {
+                        // Tab: toggle controls visibility
+                        sender.input(PlayerInput::ToggleControlsVisibility);
+                        glib::Propagation::Stop
+                    }
+                    // Screenshots and clips
+                    gtk::gdk::Key::s => {
+                        // s: save the current frame
+                        sender.input(PlayerInput::TakeScreenshot);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::I => {
+                        // Shift+I: toggle playback statistics
+                        sender.input(PlayerInput::ToggleStats);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::i => {
+                        // i: clip starts here
+                        sender.input(PlayerInput::MarkClip { end: false });
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::o => {
+                        // o: clip ends here
+                        sender.input(PlayerInput::MarkClip { end: true });
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::l => {
+                        // l: loop from here, loop until here, stop looping
+                        sender.input(PlayerInput::CycleLoop);
+                        glib::Propagation::Stop
+                    }
+                    gtk::gdk::Key::e => {
+                        // e: export the marked clip
+                        sender.input(PlayerInput::ExportClip);
+                        glib::Propagation::Stop
+                    }
+                    _ => glib::Propagation::Proceed,
+                }
+            });
+            root.add_controller(key_controller);
+        }
+
+        // Start controls and cursor timers
+        sender.input(PlayerInput::ResetControlsTimer);
+        sender.input(PlayerInput::ResetCursorTimer);
+
+        // Load media if provided
+        if let Some(id) = &model.media_item_id {
+            sender.input(PlayerInput::LoadMedia(id.clone()));
+        }
+
+        let widgets = view_output!();
+
+        AsyncComponentParts { model, widgets }
+    }
+
+    async fn update(
+        &mut self,
+        msg: Self::Input,
+        sender: AsyncComponentSender<Self>,
+        root: &Self::Root,
+    ) {
+        // Transport controls drive the receiver while casting
+        let Some(msg) = self.control_cast(msg, &sender) else {
+            return;
+        };
+        match msg {
+            PlayerInput::LoadMedia(id) => {
+                // The previous stream's transcode is no longer needed
+                self.stop_transcode_session();
+                self.cancel_reconnect();
+                self.reset_markers();
+                self.set_clip_marks(None, None);
+                self.set_loop(None, None);
+                self.reload_config();
+                self.share_party_load(&id);
+                self.media_item_id = Some(id.clone());
+                self.player_state = PlayerState::Loading;
+                // Clear context when loading without context
+                self.playlist_context = None;
+                // Nothing plays from the queue, so Next starts it from the top
+                self.play_queue.current_index = None;
+                // Only the queue can follow a single item
+                self.can_go_previous = false;
+                self.can_go_next = self.play_queue.next_index().is_some();
+                // Clear playlist position label
+                self.playlist_position_label.set_text("");
+                // Clear any existing error message
+                self.error_message = None;
+
+                // Get actual media URL from backend using GetStreamUrlCommand
+                let db_clone = self.db.clone();
+                let media_id = id.clone();
+                let media_id_for_resume = media_id.clone();
+                let sender_clone = sender.clone();
+                // Capture cached config values to avoid reloading config in async closure
+                let auto_resume = self.config_auto_resume;
+                let resume_threshold_seconds = self.config_resume_threshold_seconds;
+                let default_subtitle = self.config_default_subtitle.clone();
+
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::GetStreamUrlCommand;
+
+                        // Get the stream info from the backend using stateless command
+                        let stream_info = match (GetStreamUrlCommand {
+                            db: db_clone.as_ref().clone(),
+                            media_item_id: media_id,
+                        })
+                        .execute()
+                        .await
+                        {
+                            Ok(info) => info,
+                            Err(e) => {
+                                error!("Failed to get stream URL: {}", e);
+                                let user_message = match e.to_string().as_str() {
+                                    s if s.contains("network") || s.contains("connection") =>
+                                        "Network connection error. Please check your internet connection.".to_string(),
+                                    s if s.contains("unauthorized") || s.contains("401") =>
+                                        "Authentication failed. Please check your server credentials.".to_string(),
+                                    s if s.contains("not found") || s.contains("404") =>
+                                        "Media not found on server. It may have been removed.".to_string(),
+                                    s if s.contains("timeout") =>
+                                        "Server connection timed out. Please try again.".to_string(),
+                                    _ => format!("Failed to load media: {}", e)
+                                };
+                                return PlayerCommandOutput::LoadError(user_message);
+                            }
+                        };
+
+                        info!("Got stream URL: {}", stream_info.url);
+                        sender_clone.input(PlayerInput::StreamLoaded {
+                            media_id: media_id_for_resume.clone(),
+                            stream_info: stream_info.clone(),
+                        });
+
+                        // Load the media into the player using channel-based API
+                        match player_handle.load_media(&stream_info.url).await {
+                            Ok(_) => {
+                                info!("Media loaded successfully");
+
+                                Self::restore_stream_state(
+                                    db_clone.as_ref(),
+                                    &media_id_for_resume,
+                                    &stream_info,
+                                    &default_subtitle,
+                                    &player_handle,
+                                    &sender_clone,
+                                )
+                                .await;
+
+                                // Use cached config values
+                                Self::resume_saved_position(
+                                    db_clone.as_ref(),
+                                    &media_id_for_resume,
+                                    &player_handle,
+                                    auto_resume,
+                                    resume_threshold_seconds,
+                                )
+                                .await;
+
+                                // Try to get video dimensions and calculate appropriate window size
+                                if let Ok(Some((width, height))) =
+                                    player_handle.get_video_dimensions().await
+                                {
+                                    if width > 0 && height > 0 {
+                                        // Calculate window size based on video aspect ratio
+                                        // Keep width reasonable (max 1920) and scale height accordingly
+                                        let max_width = 1920.0_f32.min(width as f32);
+                                        let scale = max_width / width as f32;
+                                        let window_width = max_width as i32;
+                                        let window_height = (height as f32 * scale) as i32;
+
+                                        // Add some padding for controls
+                                        let final_height = window_height + 100; // Extra space for controls
+
+                                        info!(
+                                            "Video dimensions: {}x{}, window size: {}x{}",
+                                            width, height, window_width, final_height
+                                        );
+
+                                        // Request window resize through output
+                                        sender_clone
+                                            .output(PlayerOutput::WindowStateChanged {
+                                                width: window_width,
+                                                height: final_height,
+                                            })
+                                            .ok();
+                                    }
+                                }
+
+                                // Get the actual state from the player after loading
+                                let actual_state = player_handle
+                                    .get_state()
+                                    .await
+                                    .unwrap_or(PlayerState::Idle);
+                                PlayerCommandOutput::MediaReady(actual_state)
+                            }
+                            Err(e) => {
+                                error!("Failed to load media: {}", e);
+                                let user_message = match e.to_string().as_str() {
+                                    s if s.contains("codec") || s.contains("decoder") =>
+                                        "Media format not supported. The file may use an incompatible codec.".to_string(),
+                                    s if s.contains("permission") || s.contains("access") =>
+                                        "Permission denied. Check file or server access rights.".to_string(),
+                                    s if s.contains("memory") =>
+                                        "Not enough memory to play this media.".to_string(),
+                                    _ => format!("Playback error: {}", e)
+                                };
+                                PlayerCommandOutput::LoadError(user_message)
+                            }
+                        }
+                    });
+                }
+            }
+            PlayerInput::LoadMediaWithContext { media_id, context } => {
+                // The previous stream's transcode is no longer needed
+                self.stop_transcode_session();
+                self.cancel_reconnect();
+                self.reset_markers();
+                self.set_clip_marks(None, None);
+                self.set_loop(None, None);
+                self.reload_config();
+                self.share_party_load(&media_id);
+                self.media_item_id = Some(media_id.clone());
+                self.player_state = PlayerState::Loading;
+
+                // Playing from the queue moves its position along, and anything else
+                // leaves it to start from the top
+                if let PlaylistContext::Queue(queue) = &context {
+                    self.play_queue = queue.clone();
+                } else {
+                    self.play_queue.current_index = None;
+                }
+
+                // Update playlist position label
+                self.update_playlist_position_label(&context);
+
+                self.playlist_context = Some(context);
+
+                // Update navigation state based on context
+                if matches!(self.playlist_context, Some(PlaylistContext::Queue(_))) {
+                    self.queue_changed(&sender);
+                } else {
+                    self.can_go_previous = self
+                        .playlist_context
+                        .as_ref()
+                        .is_some_and(|context| context.has_previous());
+                    self.can_go_next = self.next_target(false).is_some();
+                }
+                // Clear any existing error message
+                self.error_message = None;
+
+                // Get actual media URL from backend using GetStreamUrlCommand
+                let db_clone = self.db.clone();
+                let media_id_clone = media_id.clone();
+                let media_id_for_resume = media_id_clone.clone();
+                let sender_clone = sender.clone();
+                // Capture cached config values to avoid reloading config in async closure
+                let auto_resume = self.config_auto_resume;
+                let resume_threshold_seconds = self.config_resume_threshold_seconds;
+                let default_subtitle = self.config_default_subtitle.clone();
+
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::GetStreamUrlCommand;
+
+                        // Get the stream info from the backend using stateless command
+                        let stream_info = match (GetStreamUrlCommand {
+                            db: db_clone.as_ref().clone(),
+                            media_item_id: media_id_clone,
+                        })
+                        .execute()
+                        .await
+                        {
+                            Ok(info) => info,
+                            Err(e) => {
+                                error!("Failed to get stream URL: {}", e);
+                                let user_message = match e.to_string().as_str() {
+                                    s if s.contains("network") || s.contains("connection") =>
+                                        "Network connection error. Please check your internet connection.".to_string(),
+                                    s if s.contains("unauthorized") || s.contains("401") =>
+                                        "Authentication failed. Please check your server credentials.".to_string(),
+                                    s if s.contains("not found") || s.contains("404") =>
+                                        "Media not found on server. It may have been removed.".to_string(),
+                                    s if s.contains("timeout") =>
+                                        "Server connection timed out. Please try again.".to_string(),
+                                    _ => format!("Failed to load media: {}", e)
+                                };
+                                return PlayerCommandOutput::LoadError(user_message);
+                            }
+                        };
+
+                        info!("Got stream URL: {}", stream_info.url);
+                        sender_clone.input(PlayerInput::StreamLoaded {
+                            media_id: media_id_for_resume.clone(),
+                            stream_info: stream_info.clone(),
+                        });
+
+                        // Load the media into the player using channel-based API
+                        match player_handle.load_media(&stream_info.url).await {
+                            Ok(_) => {
+                                info!("Media loaded successfully with playlist context");
+
+                                Self::restore_stream_state(
+                                    db_clone.as_ref(),
+                                    &media_id_for_resume,
+                                    &stream_info,
+                                    &default_subtitle,
+                                    &player_handle,
+                                    &sender_clone,
+                                )
+                                .await;
+
+                                // Use cached config values
+                                Self::resume_saved_position(
+                                    db_clone.as_ref(),
+                                    &media_id_for_resume,
+                                    &player_handle,
+                                    auto_resume,
+                                    resume_threshold_seconds,
+                                )
+                                .await;
+
+                                // Try to get video dimensions and calculate appropriate window size
+                                if let Ok(Some((width, height))) =
+                                    player_handle.get_video_dimensions().await
+                                {
+                                    if width > 0 && height > 0 {
+                                        // Calculate window size based on video aspect ratio
+                                        // Keep width reasonable (max 1920) and scale height accordingly
+                                        let max_width = 1920.0_f32.min(width as f32);
+                                        let scale = max_width / width as f32;
+                                        let window_width = max_width as i32;
+                                        let window_height = (height as f32 * scale) as i32;
+
+                                        // Add some padding for controls
+                                        let final_height = window_height + 100; // Extra space for controls
+
+                                        info!(
+                                            "Video dimensions: {}x{}, window size: {}x{}",
+                                            width, height, window_width, final_height
                                         );
 
-                                        // Request window resize through output
-                                        sender_clone
-                                            .output(PlayerOutput::WindowStateChanged {
-                                                width: window_width,
-                                                height: final_height,
-                                            })
-                                            .ok();
-                                    }
-                                }
+                                        // Request window resize through output
+                                        sender_clone
+                                            .output(PlayerOutput::WindowStateChanged {
+                                                width: window_width,
+                                                height: final_height,
+                                            })
+                                            .ok();
+                                    }
+                                }
+
+                                // Get the actual state from the player after loading
+                                let actual_state = player_handle
+                                    .get_state()
+                                    .await
+                                    .unwrap_or(PlayerState::Idle);
+                                PlayerCommandOutput::MediaReady(actual_state)
+                            }
+                            Err(e) => {
+                                error!("Failed to load media: {}", e);
+                                let user_message = match e.to_string().as_str() {
+                                    s if s.contains("codec") || s.contains("decoder") =>
+                                        "Media format not supported. The file may use an incompatible codec.".to_string(),
+                                    s if s.contains("permission") || s.contains("access") =>
+                                        "Permission denied. Check file or server access rights.".to_string(),
+                                    s if s.contains("memory") =>
+                                        "Not enough memory to play this media.".to_string(),
+                                    _ => format!("Playback error: {}", e)
+                                };
+                                PlayerCommandOutput::LoadError(user_message)
+                            }
+                        }
+                    });
+                }
+            }
+            PlayerInput::PlayPause => {
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    let current_state = self.player_state.clone();
+                    let party = self.watch_party.as_ref().map(|party| party.outgoing());
+                    if party.is_some() {
+                        self.party_action_at = Some(std::time::Instant::now());
+                    }
+
+                    sender.oneshot_command(async move {
+                        // Execute the play/pause command based on current state
+                        match current_state {
+                            PlayerState::Playing => {
+                                player_handle.pause().await.ok();
+                            }
+                            _ => {
+                                player_handle.play().await.ok();
+                            }
+                        };
+
+                        // Others in the watch party do the same at the same point
+                        if let Some(party) = party {
+                            let position_ms = player_handle
+                                .get_position()
+                                .await
+                                .ok()
+                                .flatten()
+                                .unwrap_or_default()
+                                .as_millis() as u64;
+                            let _ = party.send(match current_state {
+                                PlayerState::Playing => PartyMessage::Pause { position_ms },
+                                _ => PartyMessage::Play { position_ms },
+                            });
+                        }
+
+                        // Get the actual state from the player after the command
+                        let actual_state = player_handle
+                            .get_state()
+                            .await
+                            .unwrap_or(PlayerState::Error);
+
+                        PlayerCommandOutput::StateChanged(actual_state)
+                    });
+                }
+            }
+            PlayerInput::Stop => {
+                self.stop_transcode_session();
+                self.cancel_reconnect();
+                self.release_inhibit();
+                self.buffering_percent = None;
+                self.reset_markers();
+                self.update_mpris(|mpris| async move {
+                    mpris.set_playback_state(PlayerState::Stopped).await?;
+                    mpris.set_metadata(MprisMetadata::default()).await
+                });
+
+                // Save current progress before stopping
+                if let Some(media_id) = &self.media_item_id {
+                    let db = (*self.db).clone();
+                    let media_id = media_id.clone();
+                    let position_ms = self.position.as_millis() as i64;
+                    let duration_ms = self.duration.as_millis() as i64;
+                    let watched = position_ms as f64 / duration_ms as f64 > 0.9;
+
+                    relm4::spawn(async move {
+                        use crate::services::commands::{Command, UpdatePlaybackProgressCommand};
+
+                        let command = UpdatePlaybackProgressCommand {
+                            db,
+                            media_id,
+                            position_ms,
+                            duration_ms,
+                            watched,
+                        };
+
+                        if let Err(e) = command.execute().await {
+                            debug!("Failed to save final playback progress: {}", e);
+                        }
+                    });
+                }
+
+                // Undo a sleep timer fade once nothing is playing anymore
+                let restore_volume = self.sleep_fade.take().map(|_| self.volume);
+
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        player_handle.stop().await.ok();
+                        if let Some(volume) = restore_volume {
+                            player_handle.set_volume(volume).await.ok();
+                        }
+                        // Get the actual state from the player after stopping
+                        let actual_state = player_handle
+                            .get_state()
+                            .await
+                            .unwrap_or(PlayerState::Stopped);
+                        PlayerCommandOutput::StateChanged(actual_state)
+                    });
+                }
+            }
+            PlayerInput::Seek(position) => {
+                self.send_to_party(PartyMessage::Seek {
+                    position_ms: position.as_millis() as u64,
+                });
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        player_handle.seek(position).await.ok();
+                        // Get the actual state from the player after seeking
+                        let actual_state = player_handle
+                            .get_state()
+                            .await
+                            .unwrap_or(PlayerState::Error);
+                        PlayerCommandOutput::StateChanged(actual_state)
+                    });
+                }
+            }
+            PlayerInput::SetVolume(volume) => {
+                self.volume = volume;
+                self.update_mpris(move |mpris| async move { mpris.set_volume(volume).await });
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    // A sleep timer fading out keeps scaling whatever volume is chosen
+                    let player_volume = volume * self.sleep_fade.unwrap_or(1.0);
+                    sender.oneshot_command(async move {
+                        player_handle.set_volume(player_volume).await.ok();
+                        // Get the actual state from the player after volume change
+                        let actual_state = player_handle
+                            .get_state()
+                            .await
+                            .unwrap_or(PlayerState::Error);
+                        PlayerCommandOutput::StateChanged(actual_state)
+                    });
+                }
+            }
+            PlayerInput::ToggleFullscreen => {
+                self.is_fullscreen = !self.is_fullscreen;
+                if self.is_fullscreen {
+                    self.window.fullscreen();
+                    // Hide cursor immediately in fullscreen
+                    sender.input(PlayerInput::HideCursor);
+                } else {
+                    self.window.unfullscreen();
+                    // Show cursor when exiting fullscreen
+                    sender.input(PlayerInput::ShowCursor);
+                }
+            }
+            PlayerInput::ShowControls => {
+                self.show_controls = true;
+                sender.input(PlayerInput::ResetControlsTimer);
+                // Also reset cursor timer when controls are shown
+                sender.input(PlayerInput::ResetCursorTimer);
+            }
+            PlayerInput::HideControls => {
+                self.show_controls = false;
+                self.controls_timer = None;
+            }
+            PlayerInput::ResetControlsTimer => {
+                // Cancel existing timer
+                if let Some(timer) = self.controls_timer.take() {
+                    timer.remove();
+                }
+
+                // Start new 3-second timer
+                let sender = sender.clone();
+                self.controls_timer = Some(glib::timeout_add_seconds_local(3, move || {
+                    sender.input(PlayerInput::HideControls);
+                    glib::ControlFlow::Break
+                }));
+            }
+            PlayerInput::Previous => {
+                debug!("Previous track requested");
+
+                if let Some(PlaylistContext::Queue(_)) = self.playlist_context {
+                    if let Some(index) = self.play_queue.previous_index() {
+                        let (media_id, context) = self.queue_target(index);
+                        sender.input(PlayerInput::LoadMediaWithContext { media_id, context });
+                    } else {
+                        debug!("Already at the start of the queue");
+                    }
+                } else if let Some(ref context) = self.playlist_context {
+                    if let Some(prev_id) = context.get_previous_item() {
+                        // Keep the context and just load the previous media
+                        let mut new_context = context.clone();
+                        new_context.update_current_index(&prev_id);
+
+                        sender.input(PlayerInput::LoadMediaWithContext {
+                            media_id: prev_id,
+                            context: new_context,
+                        });
+                    } else {
+                        debug!("No previous episode available");
+                    }
+                } else {
+                    debug!("No playlist context available for previous navigation");
+                }
+            }
+            PlayerInput::Next => {
+                debug!("Next track requested");
+
+                // The show's next episode, or else the queue
+                if let Some((media_id, context)) = self.next_target(false) {
+                    sender.input(PlayerInput::LoadMediaWithContext { media_id, context });
+                } else {
+                    debug!("Nothing to play next");
+                }
+            }
+            PlayerInput::AutoAdvance => {
+                if let Some((media_id, context)) = self.next_target(true) {
+                    sender.input(PlayerInput::LoadMediaWithContext { media_id, context });
+                }
+            }
+            PlayerInput::ShowCursor => {
+                // Set cursor to default (visible)
+                if let Some(surface) = self.window.surface() {
+                    if let Some(cursor) = gtk::gdk::Cursor::from_name("default", None) {
+                        surface.set_cursor(Some(&cursor));
+                    }
+                }
+                sender.input(PlayerInput::ResetCursorTimer);
+            }
+            PlayerInput::HideCursor => {
+                // Set cursor to blank/none (invisible)
+                if let Some(surface) = self.window.surface() {
+                    if let Some(cursor) = gtk::gdk::Cursor::from_name("none", None) {
+                        surface.set_cursor(Some(&cursor));
+                    } else {
+                        // If 'none' cursor doesn't exist, try 'blank' or just hide it
+                        surface.set_cursor(None);
+                    }
+                }
+                self.cursor_timer = None;
+            }
+            PlayerInput::ResetCursorTimer => {
+                // Cancel existing timer
+                if let Some(timer) = self.cursor_timer.take() {
+                    timer.remove();
+                }
+
+                // Start new 3-second timer for cursor hiding
+                let sender = sender.clone();
+                self.cursor_timer = Some(glib::timeout_add_seconds_local(3, move || {
+                    sender.input(PlayerInput::HideCursor);
+                    glib::ControlFlow::Break
+                }));
+            }
+            PlayerInput::StartSeeking => {
+                self.is_seeking = true;
+            }
+            PlayerInput::StopSeeking => {
+                self.is_seeking = false;
+                self.seek_preview_popover.popdown();
+            }
+            PlayerInput::UpdateSeekPreview(position) => {
+                // Update position label to show preview time during seek
+                self.position_label.set_text(&format_duration(position));
+                if self.is_seeking {
+                    self.update_seek_preview_thumbnail(position);
+                }
+            }
+            PlayerInput::Rewind => {
+                // Seek backward 10 seconds
+                let new_position = self.position.saturating_sub(Duration::from_secs(10));
+                sender.input(PlayerInput::Seek(new_position));
+            }
+            PlayerInput::Forward => {
+                // Seek forward 10 seconds
+                let new_position = self.position + Duration::from_secs(10);
+                // Clamp to duration if we have it
+                let final_position = if new_position > self.duration {
+                    self.duration
+                } else {
+                    new_position
+                };
+                sender.input(PlayerInput::Seek(final_position));
+            }
+            PlayerInput::SeekRelative(seconds) => {
+                // Relative seeking: positive for forward, negative for backward
+                let duration = if seconds >= 0 {
+                    Duration::from_secs(seconds as u64)
+                } else {
+                    Duration::from_secs((-seconds) as u64)
+                };
+
+                let new_position = if seconds >= 0 {
+                    // Forward seek
+                    let pos = self.position + duration;
+                    // Clamp to duration
+                    if pos > self.duration {
+                        self.duration
+                    } else {
+                        pos
+                    }
+                } else {
+                    // Backward seek
+                    self.position.saturating_sub(duration)
+                };
+
+                sender.input(PlayerInput::Seek(new_position));
+            }
+            PlayerInput::SpeedUp => {
+                // Increase playback speed by 10%
+                self.playback_speed = (self.playback_speed * 1.1).min(4.0); // Cap at 4x speed
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    let speed = self.playback_speed;
+                    sender.oneshot_command(async move {
+                        player_handle.set_playback_speed(speed).await.ok();
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::SpeedDown => {
+                // Decrease playback speed by 10%
+                self.playback_speed = (self.playback_speed * 0.9).max(0.25); // Min 0.25x speed
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    let speed = self.playback_speed;
+                    sender.oneshot_command(async move {
+                        player_handle.set_playback_speed(speed).await.ok();
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::SpeedReset => {
+                // Reset playback speed to normal
+                self.playback_speed = 1.0;
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        player_handle.set_playback_speed(1.0).await.ok();
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::FrameStepForward => {
+                // Step one frame forward (while paused)
+                if self.player_state == PlayerState::Paused {
+                    if let Some(player) = &self.player {
+                        let player_handle = player.clone();
+                        sender.oneshot_command(async move {
+                            player_handle.frame_step_forward().await.ok();
+                            PlayerCommandOutput::StateChanged(PlayerState::Paused)
+                        });
+                    }
+                }
+            }
+            PlayerInput::FrameStepBackward => {
+                // Step one frame backward (while paused)
+                if self.player_state == PlayerState::Paused {
+                    if let Some(player) = &self.player {
+                        let player_handle = player.clone();
+                        sender.oneshot_command(async move {
+                            let supported = player_handle
+                                .capabilities()
+                                .await
+                                .is_ok_and(|c| c.frame_step_backward);
+                            if supported {
+                                player_handle.frame_step_backward().await.ok();
+                            } else if let Ok(Some(position)) = player_handle.get_position().await {
+                                // Approximate a frame with a short seek back
+                                let target = position.saturating_sub(FRAME_STEP_FALLBACK);
+                                player_handle.seek(target).await.ok();
+                            }
+                            PlayerCommandOutput::StateChanged(PlayerState::Paused)
+                        });
+                    }
+                }
+            }
+            PlayerInput::ToggleMute => {
+                // Toggle mute state
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        player_handle.toggle_mute().await.ok();
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::VolumeUp => {
+                // Increase volume by 10%
+                let new_volume = (self.volume + 0.1).min(1.0);
+                self.volume = new_volume;
+                self.volume_slider.set_value(new_volume);
+                sender.input(PlayerInput::SetVolume(new_volume));
+            }
+            PlayerInput::VolumeDown => {
+                // Decrease volume by 10%
+                let new_volume = (self.volume - 0.1).max(0.0);
+                self.volume = new_volume;
+                self.volume_slider.set_value(new_volume);
+                sender.input(PlayerInput::SetVolume(new_volume));
+            }
+            PlayerInput::CycleSubtitleTrack => {
+                // Cycle through available subtitle tracks
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        player_handle.cycle_subtitle_track().await.ok();
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::CycleAudioTrack => {
+                // Cycle through available audio tracks
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        player_handle.cycle_audio_track().await.ok();
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::UpdateTrackMenus => {
+                // Populate the track selection menus
+                self.populate_audio_menu(sender.clone());
+                self.populate_subtitle_menu(sender.clone());
+
+                // Also get current track selections
+                if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    let sender_clone = sender.clone();
+                    glib::spawn_future_local(async move {
+                        let _audio_track =
+                            player_handle.get_current_audio_track().await.unwrap_or(-1);
+                        let _subtitle_track = player_handle
+                            .get_current_subtitle_track()
+                            .await
+                            .unwrap_or(-1);
+                        // Store the current tracks (we'll update the model in a moment)
+                    });
+                }
+            }
+            PlayerInput::SetAudioTrack(track_id) => {
+                if let Some(player) = &self.player {
+                    self.current_audio_track = track_id;
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        let _ = player_handle.set_audio_track(track_id).await;
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::SetAudioDevice(name) => {
+                if name != self.audio_device {
+                    self.audio_device = name.clone();
+
+                    let mut config = Config::load().unwrap_or_default();
+                    config.playback.audio_device = name.clone();
+                    if let Err(e) = config.save() {
+                        error!("Failed to save audio device: {}", e);
+                    }
 
-                                // Get the actual state from the player after loading
-                                let actual_state = player_handle
-                                    .get_state()
-                                    .await
-                                    .unwrap_or(PlayerState::Idle);
-                                PlayerCommandOutput::StateChanged(actual_state)
+                    if let Some(player) = &self.player {
+                        let player = player.clone();
+                        glib::spawn_future_local(async move {
+                            if let Err(e) = player.set_audio_device(&name).await {
+                                warn!("Failed to set audio device: {}", e);
                             }
+                        });
+                    }
+                }
+            }
+            PlayerInput::SetAudioPreset(preset) => {
+                self.update_audio_effects(
+                    AudioEffects {
+                        preset,
+                        ..self.audio_effects
+                    },
+                    &sender,
+                );
+            }
+            PlayerInput::SetEqualizerBand(band, gain) => {
+                let mut effects = self.audio_effects;
+                effects.equalizer[band] = gain;
+                self.update_audio_effects(effects, &sender);
+            }
+            PlayerInput::SaveAudioEffects => {
+                self.audio_effects_save_timer = None;
+                let mut config = Config::load().unwrap_or_default();
+                config.playback.set_audio_effects(&self.audio_effects);
+                if let Err(e) = config.save() {
+                    error!("Failed to save audio effects: {}", e);
+                }
+            }
+            PlayerInput::SetSubtitleTrack(track_id) => {
+                if let Some(player) = &self.player {
+                    self.current_subtitle_track = track_id;
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        let _ = player_handle.set_subtitle_track(track_id).await;
+                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                    });
+                }
+            }
+            PlayerInput::ToggleControlsVisibility => {
+                // Toggle controls visibility
+                self.show_controls = !self.show_controls;
+                if self.show_controls {
+                    // Reset timer when showing controls
+                    sender.input(PlayerInput::ResetControlsTimer);
+                    sender.input(PlayerInput::ResetCursorTimer);
+                }
+            }
+            PlayerInput::RetryLoad => {
+                // Clear the error and retry loading the media
+                self.error_message = None;
+                self.cancel_reconnect();
+
+                // Check if we've exceeded max retries
+                if self.retry_count >= self.max_retries {
+                    self.error_message = Some(
+                        "Failed to load media after multiple attempts. Please check your connection and try again.".to_string()
+                    );
+                    self.retry_count = 0;
+                    return;
+                }
+
+                // Increment retry count
+                self.retry_count += 1;
+
+                // Calculate exponential backoff delay (1s, 2s, 4s)
+                let delay = Duration::from_secs(2_u64.pow(self.retry_count - 1));
+
+                // Schedule retry after delay
+                if let Some(timer) = self.retry_timer.take() {
+                    timer.remove();
+                }
+
+                let sender_clone = sender.clone();
+                let media_id = self.media_item_id.clone();
+                let context = self.playlist_context.clone();
+
+                info!("Scheduling retry #{} after {:?}", self.retry_count, delay);
+
+                self.retry_timer = Some(glib::timeout_add_local(delay, move || {
+                    if let Some(id) = &media_id {
+                        if let Some(ctx) = &context {
+                            sender_clone.input(PlayerInput::LoadMediaWithContext {
+                                media_id: id.clone(),
+                                context: ctx.clone(),
+                            });
+                        } else {
+                            sender_clone.input(PlayerInput::LoadMedia(id.clone()));
+                        }
+                    }
+                    glib::ControlFlow::Break
+                }));
+            }
+            PlayerInput::ClearError => {
+                self.error_message = None;
+                self.retry_count = 0;
+                if let Some(timer) = self.retry_timer.take() {
+                    timer.remove();
+                }
+                self.cancel_reconnect();
+            }
+            PlayerInput::Reconnect => {
+                // The timer fired, so it's gone already
+                self.reconnect_timer = None;
+                let (Some(player_handle), Some(media_id)) =
+                    (self.player.clone(), self.media_item_id.clone())
+                else {
+                    self.cancel_reconnect();
+                    return;
+                };
+                self.reconnect_in_flight = true;
+                // The server forgets a transcode nobody fetches, so the reload starts another
+                self.stop_transcode_session();
+
+                // A connection that keeps dropping may cope with a lower quality. Only
+                // Plex offers other qualities; Jellyfin streams reload as they were.
+                let quality_index =
+                    if self.config_reconnect_lower_quality && self.reconnect_attempts > 1 {
+                        self.quality_index + 1
+                    } else {
+                        self.quality_index
+                    };
+                let db = (*self.db).clone();
+                let position = self.reconnect_position;
+                let preferred_subtitle = self.preferred_external_subtitle;
+                let subtitle_delay_ms = self.subtitle_delay_ms;
+                let audio_delay_ms = self.audio_delay_ms;
+
+                sender.oneshot_command(async move {
+                    use crate::services::commands::Command;
+                    use crate::services::commands::media_commands::ReconnectStreamCommand;
+
+                    let result = async {
+                        let stream_info = (ReconnectStreamCommand {
+                            db,
+                            media_item_id: media_id.clone(),
+                        })
+                        .execute()
+                        .await?;
+
+                        let quality_index =
+                            quality_index.min(stream_info.quality_options.len().saturating_sub(1));
+                        let url = stream_info
+                            .quality_options
+                            .get(quality_index)
+                            .map_or(&stream_info.url, |option| &option.url);
+                        info!("Reconnecting to {}", url);
+                        player_handle.load_media(url).await?;
+
+                        Self::load_external_subtitles(
+                            &player_handle,
+                            &stream_info.external_subtitles,
+                            preferred_subtitle,
+                        )
+                        .await;
+                        player_handle
+                            .set_subtitle_delay(subtitle_delay_ms)
+                            .await
+                            .ok();
+                        player_handle.set_audio_delay(audio_delay_ms).await.ok();
+
+                        // Continue from where the stream dropped
+                        if !position.is_zero() {
+                            player_handle.seek(position).await?;
+                        }
+                        anyhow::Ok((stream_info, quality_index))
+                    }
+                    .await;
+
+                    PlayerCommandOutput::Reconnected {
+                        media_id,
+                        result: result.map_err(|e| e.to_string()),
+                    }
+                });
+            }
+            PlayerInput::CheckStall => {
+                // Attempts in progress are watched once they've reloaded the stream
+                if self.reconnect_timer.is_some() || self.reconnect_in_flight {
+                    return;
+                }
+                if self.last_progress_at.elapsed() >= STALL_TIMEOUT && self.can_reconnect() {
+                    // Each reload gets as long to start moving
+                    self.last_progress_at = std::time::Instant::now();
+                    self.reconnect("Playback stalled".to_string(), &sender);
+                }
+            }
+            PlayerInput::ShowError(msg) => {
+                error!("Player error: {}", msg);
+                self.error_message = Some(msg);
+                self.player_state = PlayerState::Error;
+                self.release_inhibit();
+            }
+            PlayerInput::EscapePressed => {
+                // ESC key behavior: exit fullscreen if in fullscreen, otherwise navigate back
+                if self.is_fullscreen {
+                    sender.input(PlayerInput::ToggleFullscreen);
+                } else {
+                    sender.input(PlayerInput::NavigateBack);
+                }
+            }
+            PlayerInput::AdjustVideo(setting) => {
+                let mut adjustments = self.video_adjustments;
+                setting.apply(&mut adjustments);
+                self.update_video_adjustments(adjustments, &sender);
+            }
+            PlayerInput::DetectBlackBars => {
+                if let Some(player) = &self.player {
+                    self.video_adjustment_controls
+                        .crop_status
+                        .set_label("Looking for black bars…");
+                    let player_handle = player.clone();
+                    let sender = sender.clone();
+                    glib::spawn_future_local(async move {
+                        let result = player_handle
+                            .detect_black_bars()
+                            .await
+                            .map_err(|e| e.to_string());
+                        sender.input(PlayerInput::BlackBarsDetected(result));
+                    });
+                }
+            }
+            PlayerInput::BlackBarsDetected(result) => match result {
+                Ok(Some(crop)) => {
+                    info!("Cropping black bars to {:?}", crop);
+                    self.update_video_adjustments(
+                        VideoAdjustments {
+                            crop: Some(crop),
+                            ..self.video_adjustments
+                        },
+                        &sender,
+                    );
+                    self.video_adjustment_controls.set_crop(Some(crop));
+                }
+                Ok(None) => {
+                    self.video_adjustment_controls
+                        .crop_status
+                        .set_label("No black bars found");
+                }
+                Err(e) => {
+                    warn!("Black bar detection failed: {}", e);
+                    self.video_adjustment_controls.crop_status.set_label(&e);
+                }
+            },
+            PlayerInput::ClearCrop => {
+                self.update_video_adjustments(
+                    VideoAdjustments {
+                        crop: None,
+                        ..self.video_adjustments
+                    },
+                    &sender,
+                );
+                self.video_adjustment_controls.set_crop(None);
+            }
+            PlayerInput::ResetVideoAdjustments => {
+                self.update_video_adjustments(VideoAdjustments::default(), &sender);
+                self.video_adjustment_controls
+                    .sync(&VideoAdjustments::default());
+            }
+            PlayerInput::VideoAdjustmentsLoaded(adjustments) => {
+                self.video_adjustments = adjustments;
+                self.video_adjustment_controls.sync(&adjustments);
+            }
+            PlayerInput::SaveVideoAdjustments {
+                media_id,
+                adjustments,
+            } => {
+                self.video_adjustments_save_timer = None;
+                let db = (*self.db).clone();
+                relm4::spawn(async move {
+                    use crate::services::commands::Command;
+                    use crate::services::commands::media_commands::SaveVideoAdjustmentsCommand;
+
+                    let command = SaveVideoAdjustmentsCommand {
+                        db,
+                        media_id,
+                        adjustments,
+                    };
+                    if let Err(e) = command.execute().await {
+                        warn!("Failed to save video adjustments: {}", e);
+                    }
+                });
+            }
+            PlayerInput::TakeScreenshot => {
+                if let (Some(player), Some(media_id)) = (&self.player, &self.media_item_id) {
+                    let player_handle = player.clone();
+                    let db = (*self.db).clone();
+                    let media_id = media_id.clone();
+                    let position = self.position;
+                    sender.oneshot_command(async move {
+                        let result = async {
+                            let config = Config::load().unwrap_or_default();
+                            let title = Self::capture_title(&db, &media_id).await;
+                            let path = CaptureService::capture_path(
+                                &config.playback.capture_directory(),
+                                &title,
+                                position,
+                                "png",
+                            )
+                            .await?;
+                            player_handle
+                                .screenshot(
+                                    path.clone(),
+                                    config.playback.screenshot_include_subtitles,
+                                )
+                                .await?;
+                            anyhow::Ok(path)
+                        }
+                        .await;
+                        PlayerCommandOutput::CaptureFinished(result.map_err(|e| e.to_string()))
+                    });
+                }
+            }
+            PlayerInput::MarkClip { end } => {
+                if let Some(player) = &self.player {
+                    // The position pushed every second is too coarse to cut at
+                    let player_handle = player.clone();
+                    let fallback = self.position;
+                    let sender = sender.clone();
+                    glib::spawn_future_local(async move {
+                        let position = player_handle
+                            .get_position()
+                            .await
+                            .ok()
+                            .flatten()
+                            .unwrap_or(fallback);
+                        sender.input(PlayerInput::ClipMarked { end, position });
+                    });
+                }
+            }
+            PlayerInput::ClipMarked { end, position } => {
+                let (start, end_mark) = if end {
+                    (
+                        self.clip_start.filter(|&start| start < position),
+                        Some(position),
+                    )
+                } else {
+                    (Some(position), self.clip_end.filter(|&end| end > position))
+                };
+                self.set_clip_marks(start, end_mark);
+
+                let message = match (start, end_mark) {
+                    (Some(start), Some(end)) => format!(
+                        "Clip {} to {} marked, press E to export",
+                        format_duration(start),
+                        format_duration(end)
+                    ),
+                    (Some(start), None) => format!("Clip starts at {}", format_duration(start)),
+                    (None, _) => format!(
+                        "Clip ends at {}, mark where it starts",
+                        format_duration(position)
+                    ),
+                };
+                sender.output(PlayerOutput::ShowToast(message)).unwrap();
+            }
+            PlayerInput::CycleLoop => {
+                if self.loop_end.is_some() {
+                    sender.input(PlayerInput::ClearLoop);
+                } else if let Some(player) = &self.player {
+                    // The position pushed every second is too coarse to loop a step or a phrase
+                    let player_handle = player.clone();
+                    let fallback = self.position;
+                    let sender = sender.clone();
+                    glib::spawn_future_local(async move {
+                        let position = player_handle
+                            .get_position()
+                            .await
+                            .ok()
+                            .flatten()
+                            .unwrap_or(fallback);
+                        sender.input(PlayerInput::LoopPointMarked(position));
+                    });
+                }
+            }
+            PlayerInput::LoopPointMarked(position) => {
+                let message = match self.loop_start {
+                    None => {
+                        self.set_loop(Some(position), None);
+                        format!(
+                            "Loop starts at {}, press L again where it ends",
+                            format_duration(position)
+                        )
+                    }
+                    // Marking the end before the start loops the same part
+                    Some(start) if start != position => {
+                        let (start, end) = (start.min(position), start.max(position));
+                        self.set_loop(Some(start), Some(end));
+                        format!(
+                            "Looping {} to {}",
+                            format_duration(start),
+                            format_duration(end)
+                        )
+                    }
+                    Some(_) => "The loop needs to end somewhere else".to_string(),
+                };
+                sender.output(PlayerOutput::ShowToast(message)).unwrap();
+            }
+            PlayerInput::ClearLoop => {
+                if self.loop_start.is_some() {
+                    self.set_loop(None, None);
+                    sender
+                        .output(PlayerOutput::ShowToast("Loop cleared".to_string()))
+                        .unwrap();
+                }
+            }
+            PlayerInput::HostWatchParty => {
+                sender.oneshot_command(async move {
+                    PlayerCommandOutput::WatchPartyStarted(
+                        WatchParty::host().await.map_err(|e| e.to_string()),
+                    )
+                });
+            }
+            PlayerInput::JoinWatchParty { party, events } => {
+                self.start_watch_party(party, events, &sender);
+                sender
+                    .output(PlayerOutput::ShowToast(
+                        "Joined the watch party".to_string(),
+                    ))
+                    .unwrap();
+            }
+            PlayerInput::CopyWatchPartyCode => {
+                if let Some(code) = self.watch_party.as_ref().and_then(|party| party.code()) {
+                    self.window.clipboard().set_text(code);
+                    sender
+                        .output(PlayerOutput::ShowToast(format!(
+                            "Copied join code {}",
+                            code
+                        )))
+                        .unwrap();
+                }
+            }
+            PlayerInput::LeaveWatchParty => {
+                let message = match &self.watch_party {
+                    Some(party) if party.is_host() => "Watch party ended",
+                    _ => "Left the watch party",
+                };
+                self.leave_watch_party();
+                sender
+                    .output(PlayerOutput::ShowToast(message.to_string()))
+                    .unwrap();
+            }
+            PlayerInput::WatchPartyEvent(event) => {
+                let message = match event {
+                    PartyEvent::Message(message) => {
+                        self.apply_party_message(message, &sender);
+                        None
+                    }
+                    PartyEvent::ParticipantJoined(name) => {
+                        Some(format!("{} joined the watch party", name))
+                    }
+                    PartyEvent::ParticipantLeft(name) => {
+                        Some(format!("{} left the watch party", name))
+                    }
+                    PartyEvent::Ended(reason) => {
+                        self.leave_watch_party();
+                        Some(reason)
+                    }
+                };
+                if let Some(message) = message {
+                    sender.output(PlayerOutput::ShowToast(message)).unwrap();
+                }
+            }
+            PlayerInput::WatchPartyHeartbeat => {
+                if let (Some(party), Some(player), Some(media_id)) =
+                    (&self.watch_party, &self.player, &self.media_item_id)
+                    && matches!(
+                        self.player_state,
+                        PlayerState::Playing | PlayerState::Paused
+                    )
+                {
+                    // The position pushed every second is too stale to keep others in step
+                    let outgoing = party.outgoing();
+                    let player_handle = player.clone();
+                    let media_id = media_id.to_string();
+                    let playing = self.player_state == PlayerState::Playing;
+                    glib::spawn_future_local(async move {
+                        if let Ok(Some(position)) = player_handle.get_position().await {
+                            let _ = outgoing.send(PartyMessage::State {
+                                media_id,
+                                position_ms: position.as_millis() as u64,
+                                playing,
+                            });
+                        }
+                    });
+                }
+            }
+            PlayerInput::WatchPartyDrift {
+                position,
+                target,
+                gradual,
+            } => {
+                let drift = target.as_secs_f64() - position.as_secs_f64();
+                let nudging = (self.party_rate - 1.0).abs() > f64::EPSILON;
+                match drift_correction(drift, gradual, nudging) {
+                    DriftCorrection::Seek => {
+                        debug!("Watch party: {:.2}s off, seeking to {:?}", drift, target);
+                        self.set_party_rate(1.0);
+                        if let Some(player) = &self.player {
+                            let player_handle = player.clone();
+                            glib::spawn_future_local(async move {
+                                if let Err(e) = player_handle.seek(target).await {
+                                    warn!("Failed to follow the watch party: {}", e);
+                                }
+                            });
+                        }
+                    }
+                    DriftCorrection::Rate(rate) => {
+                        if (rate - self.party_rate).abs() > f64::EPSILON {
+                            debug!("Watch party: {:.2}s off, playing at {}x", drift, rate);
+                        }
+                        self.set_party_rate(rate);
+                    }
+                }
+            }
+            PlayerInput::ExportClip => {
+                let (Some(start), Some(end)) = (self.clip_start, self.clip_end) else {
+                    sender
+                        .output(PlayerOutput::ShowToast(
+                            "Mark where the clip starts and ends first".to_string(),
+                        ))
+                        .unwrap();
+                    return;
+                };
+                if let (Some(stream_info), Some(media_id)) =
+                    (&self.stream_info, &self.media_item_id)
+                {
+                    let stream_url = stream_info.url.clone();
+                    let db = (*self.db).clone();
+                    let media_id = media_id.clone();
+                    sender.oneshot_command(async move {
+                        let result = async {
+                            let config = Config::load().unwrap_or_default();
+                            let title = Self::capture_title(&db, &media_id).await;
+                            let path = CaptureService::capture_path(
+                                &config.playback.capture_directory(),
+                                &title,
+                                start,
+                                "mkv",
+                            )
+                            .await?;
+                            CaptureService::export_clip(&stream_url, start, end, &path).await?;
+                            anyhow::Ok(path)
+                        }
+                        .await;
+                        PlayerCommandOutput::CaptureFinished(result.map_err(|e| e.to_string()))
+                    });
+                    self.set_clip_marks(None, None);
+                    sender
+                        .output(PlayerOutput::ShowToast("Exporting clip…".to_string()))
+                        .unwrap();
+                }
+            }
+            PlayerInput::RemoteCommand(command) => {
+                self.handle_remote_command(command, &sender);
+            }
+            PlayerInput::FindCastDevices => {
+                if !self.finding_cast_devices && self.cast.is_none() {
+                    self.finding_cast_devices = true;
+                    self.update_cast_menu();
+                    sender.oneshot_command(async move {
+                        PlayerCommandOutput::CastDevicesFound(
+                            cast::discover(CAST_DISCOVERY_TIME).await,
+                        )
+                    });
+                }
+            }
+            PlayerInput::CastTo(index) => {
+                let Some(device) = self.cast_devices.get(index).cloned() else {
+                    return;
+                };
+                let Some(media_id) = self.media_item_id.clone() else {
+                    sender
+                        .output(PlayerOutput::ShowToast(
+                            "This item can't be cast".to_string(),
+                        ))
+                        .unwrap();
+                    return;
+                };
+                let db = (*self.db).clone();
+                let quality = self.cast_quality();
+                let start = self.position;
+                sender.oneshot_command(async move {
+                    let started = async {
+                        let (media, transcode_session) =
+                            Self::cast_media(db.clone(), media_id.clone(), quality, start).await?;
+                        let loaded = async {
+                            let receiver: Arc<dyn CastReceiver> =
+                                cast::connect(&device).await?.into();
+                            Self::load_on_receiver(
+                                db.clone(),
+                                Some(media_id),
+                                media,
+                                receiver.as_ref(),
+                            )
+                            .await?;
+                            anyhow::Ok(receiver)
+                        }
+                        .await;
+                        match loaded {
+                            Ok(receiver) => Ok((device, receiver, transcode_session)),
                             Err(e) => {
-                                error!("Failed to load media: {}", e);
-                                let user_message = match e.to_string().as_str() {
-                                    s if s.contains("codec") || s.contains("decoder") =>
-                                        "Media format not supported. The file may use an incompatible codec.".to_string(),
-                                    s if s.contains("permission") || s.contains("access") =>
-                                        "Permission denied. Check file or server access rights.".to_string(),
-                                    s if s.contains("memory") =>
-                                        "Not enough memory to play this media.".to_string(),
-                                    _ => format!("Playback error: {}", e)
-                                };
-                                PlayerCommandOutput::LoadError(user_message)
+                                Self::stop_cast_transcode(db, transcode_session).await;
+                                Err(e)
                             }
                         }
+                    }
+                    .await;
+                    PlayerCommandOutput::CastStarted(started.map_err(|e| e.to_string()))
+                });
+            }
+            PlayerInput::StopCasting => self.end_cast(true, &sender),
+            PlayerInput::CastStatusTick => {
+                // Slow receivers aren't asked again before they answered
+                if let Some(cast) = &mut self.cast
+                    && !std::mem::replace(&mut cast.status_pending, true)
+                {
+                    let receiver = cast.receiver.clone();
+                    sender.oneshot_command(async move {
+                        PlayerCommandOutput::CastStatus(
+                            receiver.status().await.map_err(|e| e.to_string()),
+                        )
                     });
                 }
             }
-            PlayerInput::PlayPause => {
+            PlayerInput::ToggleStats => {
+                if let Some(timer) = self.stats_timer.take() {
+                    timer.remove();
+                    self.stats = None;
+                } else {
+                    self.stats = Some(PlaybackStats::default());
+                    self.stats_ticks = 0;
+                    sender.input(PlayerInput::StatsTick);
+                    let sender = sender.clone();
+                    self.stats_timer = Some(glib::timeout_add_local(STATS_INTERVAL, move || {
+                        sender.input(PlayerInput::StatsTick);
+                        glib::ControlFlow::Continue
+                    }));
+                }
+            }
+            PlayerInput::StatsTick => {
                 if let Some(player) = &self.player {
                     let player_handle = player.clone();
-                    let current_state = self.player_state.clone();
+                    let sender_clone = sender.clone();
+                    glib::spawn_future_local(async move {
+                        match player_handle.get_stats().await {
+                            Ok(stats) => sender_clone.input(PlayerInput::StatsUpdated(stats)),
+                            Err(e) => debug!("No playback statistics: {}", e),
+                        }
+                    });
+                }
 
+                // The server is asked less often than the player
+                if self.stats_ticks % TRANSCODE_STATUS_TICKS == 0
+                    && self.is_transcoding
+                    && let Some((media_id, session_id)) = self.transcode_session.clone()
+                {
+                    let db = (*self.db).clone();
                     sender.oneshot_command(async move {
-                        // Execute the play/pause command based on current state
-                        match current_state {
-                            PlayerState::Playing => {
-                                player_handle.pause().await.ok();
-                            }
-                            _ => {
-                                player_handle.play().await.ok();
-                            }
-                        };
-
-                        // Get the actual state from the player after the command
-                        let actual_state = player_handle
-                            .get_state()
-                            .await
-                            .unwrap_or(PlayerState::Error);
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::GetTranscodeStatusCommand;
 
-                        PlayerCommandOutput::StateChanged(actual_state)
+                        let status = (GetTranscodeStatusCommand {
+                            db,
+                            media_item_id: media_id,
+                            session_id,
+                        })
+                        .execute()
+                        .await;
+                        PlayerCommandOutput::TranscodeStatusLoaded(
+                            status.map_err(|e| e.to_string()),
+                        )
                     });
                 }
+                self.stats_ticks = self.stats_ticks.wrapping_add(1);
             }
-            PlayerInput::Stop => {
-                // Save current progress before stopping
-                if let Some(media_id) = &self.media_item_id {
-                    let db = (*self.db).clone();
-                    let media_id = media_id.clone();
-                    let position_ms = self.position.as_millis() as i64;
-                    let duration_ms = self.duration.as_millis() as i64;
-                    let watched = position_ms as f64 / duration_ms as f64 > 0.9;
+            PlayerInput::StatsUpdated(stats) => {
+                // Ignore a reply arriving after the overlay was closed
+                if self.stats.is_some() {
+                    self.stats = Some(stats);
+                }
+            }
+            PlayerInput::EnterPictureInPicture => {
+                if self.pip.is_some() || self.player.is_none() {
+                    return;
+                }
+                // The placeholder stays in the container when no video widget was created
+                let Some(video) = self
+                    .video_container
+                    .first_child()
+                    .filter(|child| !child.is::<gtk::Label>())
+                else {
+                    return;
+                };
 
-                    relm4::spawn(async move {
-                        use crate::services::commands::{Command, UpdatePlaybackProgressCommand};
+                if self.is_fullscreen {
+                    self.is_fullscreen = false;
+                    self.window.unfullscreen();
+                }
+                if let Some(timer) = self.cursor_timer.take() {
+                    timer.remove();
+                }
+                if let Some(timer) = self.controls_timer.take() {
+                    timer.remove();
+                }
+                sender.input(PlayerInput::ShowCursor);
 
-                        let command = UpdatePlaybackProgressCommand {
-                            db,
-                            media_id,
-                            position_ms,
-                            duration_ms,
-                            watched,
+                // Playback carries on; only the widget showing it moves
+                self.video_container.remove(&video);
+                let pip = PipWindow::new(&self.window, &video, &sender);
+                pip.set_playing(matches!(self.player_state, PlayerState::Playing));
+                self.pip = Some(pip);
+                sender
+                    .output(PlayerOutput::PictureInPictureStarted)
+                    .unwrap();
+            }
+            PlayerInput::LeavePictureInPicture => self.close_pip(true, &sender),
+            PlayerInput::ClosePictureInPicture => self.close_pip(false, &sender),
+            PlayerInput::NavigateBack => {
+                // Clear cursor timer and show cursor before navigating back
+                if let Some(timer) = self.cursor_timer.take() {
+                    timer.remove();
+                }
+                if let Some(timer) = self.controls_timer.take() {
+                    timer.remove();
+                }
+                sender.input(PlayerInput::ShowCursor);
+                // The receiver may be streaming from the transcode session about to stop
+                self.end_cast(true, &sender);
+                self.stop_transcode_session();
+                self.cancel_reconnect();
+                if let Some(timer) = self.stall_timer.take() {
+                    timer.remove();
+                }
+                self.start_position = None;
+                self.release_inhibit();
+                // Whatever is played next shouldn't be pushed onto the party
+                if self.watch_party.is_some() {
+                    sender.input(PlayerInput::LeaveWatchParty);
+                }
+                // Navigate back
+                sender.output(PlayerOutput::NavigateBack).unwrap();
+            }
+            PlayerInput::StreamLoaded {
+                media_id,
+                stream_info,
+            } => {
+                self.stream_info = Some(stream_info.clone());
+                self.transcode_status = None;
+                self.preferred_external_subtitle =
+                    stream_info.preferred_external_subtitle(&self.config_default_subtitle);
+                self.is_transcoding =
+                    !stream_info.direct_play && stream_info.transcode_session_id.is_some();
+                self.transcode_session = stream_info
+                    .transcode_session_id
+                    .map(|session_id| (media_id.clone(), session_id));
+                self.quality_options = stream_info.quality_options;
+                self.quality_index = 0;
+                self.populate_quality_menu(sender.clone());
+                self.external_subtitles = stream_info.external_subtitles;
+                self.update_mpris_metadata();
+
+                // Fetch seek preview thumbnails in the background
+                self.trickplay = None;
+                self.seek_preview_index = None;
+                let db = (*self.db).clone();
+                let stream_url = stream_info.url;
+                sender.oneshot_command(async move {
+                    use crate::services::commands::Command;
+                    use crate::services::commands::media_commands::GetTrickplayCommand;
+
+                    let trickplay = match (GetTrickplayCommand {
+                        db,
+                        media_item_id: media_id.clone(),
+                        stream_url,
+                    })
+                    .execute()
+                    .await
+                    {
+                        Ok(trickplay) => trickplay,
+                        Err(e) => {
+                            debug!("No seek preview thumbnails available: {}", e);
+                            None
+                        }
+                    };
+                    PlayerCommandOutput::TrickplayLoaded {
+                        media_id,
+                        trickplay,
+                    }
+                });
+
+                // Fetch skippable segments and what this show remembers about them
+                let db = (*self.db).clone();
+                let media_id = self.media_item_id.clone();
+                let show_id = self.show_id().cloned();
+                if let Some(media_id) = media_id {
+                    sender.oneshot_command(async move {
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::{
+                            GetMediaMarkersCommand, GetShowSkipModesCommand,
                         };
 
-                        if let Err(e) = command.execute().await {
-                            debug!("Failed to save final playback progress: {}", e);
+                        let markers = (GetMediaMarkersCommand {
+                            db: db.clone(),
+                            media_item_id: media_id.clone(),
+                        })
+                        .execute()
+                        .await
+                        .unwrap_or_else(|e| {
+                            debug!("No skip markers available: {}", e);
+                            Vec::new()
+                        });
+                        let show_skip_modes = match show_id {
+                            Some(show_id) => (GetShowSkipModesCommand { db, show_id })
+                                .execute()
+                                .await
+                                .unwrap_or_default(),
+                            None => HashMap::new(),
+                        };
+                        PlayerCommandOutput::MarkersLoaded {
+                            media_id,
+                            markers,
+                            show_skip_modes,
                         }
                     });
                 }
 
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
+                // Server chapters come with thumbnails, so prefer them over embedded ones
+                let db = (*self.db).clone();
+                if let Some(media_id) = self.media_item_id.clone() {
                     sender.oneshot_command(async move {
-                        player_handle.stop().await.ok();
-                        // Get the actual state from the player after stopping
-                        let actual_state = player_handle
-                            .get_state()
-                            .await
-                            .unwrap_or(PlayerState::Stopped);
-                        PlayerCommandOutput::StateChanged(actual_state)
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::GetChaptersCommand;
+
+                        let chapters = (GetChaptersCommand {
+                            db,
+                            media_item_id: media_id.clone(),
+                        })
+                        .execute()
+                        .await
+                        .unwrap_or_else(|e| {
+                            debug!("No server chapters available: {}", e);
+                            Vec::new()
+                        });
+                        PlayerCommandOutput::ChaptersLoaded {
+                            media_id,
+                            chapters,
+                            from_server: true,
+                        }
                     });
                 }
             }
-            PlayerInput::Seek(position) => {
-                if let Some(player) = &self.player {
+            PlayerInput::SetQuality(index) => {
+                if let Some(option) = self.quality_options.get(index).cloned()
+                    && let Some(player) = &self.player
+                {
+                    info!("Switching quality to {}", option.name);
+                    self.quality_index = index;
+
                     let player_handle = player.clone();
+                    let position = self.position;
+                    let db = (*self.db).clone();
+                    // Quality options share one session, so the old encode has to stop
+                    // before the new URL starts another under the same ID
+                    let previous_session = if self.is_transcoding {
+                        self.transcode_session.clone()
+                    } else {
+                        None
+                    };
+                    if let Some(timer) = self.transcode_keepalive_timer.take() {
+                        timer.remove();
+                    }
+                    self.is_transcoding = option.requires_transcode;
+                    self.transcode_session = self
+                        .media_item_id
+                        .clone()
+                        .zip(option.transcode_session_id.clone());
+                    let external_subtitles = self.external_subtitles.clone();
+                    let preferred_subtitle = self.preferred_external_subtitle;
+                    let subtitle_delay_ms = self.subtitle_delay_ms;
+                    let audio_delay_ms = self.audio_delay_ms;
+
                     sender.oneshot_command(async move {
-                        player_handle.seek(position).await.ok();
-                        // Get the actual state from the player after seeking
-                        let actual_state = player_handle
-                            .get_state()
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::StopTranscodeSessionCommand;
+
+                        if let Some((media_id, session_id)) = previous_session {
+                            let command = StopTranscodeSessionCommand {
+                                db,
+                                media_item_id: media_id,
+                                session_id,
+                            };
+                            if let Err(e) = command.execute().await {
+                                debug!("Failed to stop transcode session: {}", e);
+                            }
+                        }
+
+                        if let Err(e) = player_handle.load_media(&option.url).await {
+                            error!("Failed to switch quality: {}", e);
+                            return PlayerCommandOutput::LoadError(format!(
+                                "Failed to switch quality: {}",
+                                e
+                            ));
+                        }
+                        Self::load_external_subtitles(
+                            &player_handle,
+                            &external_subtitles,
+                            preferred_subtitle,
+                        )
+                        .await;
+                        player_handle
+                            .set_subtitle_delay(subtitle_delay_ms)
                             .await
-                            .unwrap_or(PlayerState::Error);
+                            .ok();
+                        player_handle.set_audio_delay(audio_delay_ms).await.ok();
+
+                        // Continue from where we were
+                        if !position.is_zero() {
+                            player_handle.seek(position).await.ok();
+                        }
+
+                        let actual_state =
+                            player_handle.get_state().await.unwrap_or(PlayerState::Idle);
                         PlayerCommandOutput::StateChanged(actual_state)
                     });
                 }
             }
-            PlayerInput::SetVolume(volume) => {
-                self.volume = volume;
+            PlayerInput::OpenSubtitleFile => {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some("Subtitles"));
+                for extension in SUBTITLE_FILE_EXTENSIONS {
+                    filter.add_suffix(extension);
+                }
+                let filters = gtk::gio::ListStore::new::<gtk::FileFilter>();
+                filters.append(&filter);
+
+                let dialog = gtk::FileDialog::builder()
+                    .title("Load Subtitle File")
+                    .modal(true)
+                    .filters(&filters)
+                    .default_filter(&filter)
+                    .build();
+
+                let window = self.window.clone();
+                let sender_clone = sender.clone();
+                glib::spawn_future_local(async move {
+                    // Dismissing the dialog also ends up here as an error
+                    if let Ok(file) = dialog.open_future(Some(&window)).await
+                        && let Some(path) = file.path()
+                    {
+                        sender_clone.input(PlayerInput::LoadSubtitleFile(path));
+                    }
+                });
+            }
+            PlayerInput::LoadSubtitleFile(path) => {
                 if let Some(player) = &self.player {
                     let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        player_handle.set_volume(volume).await.ok();
-                        // Get the actual state from the player after volume change
-                        let actual_state = player_handle
-                            .get_state()
-                            .await
-                            .unwrap_or(PlayerState::Error);
-                        PlayerCommandOutput::StateChanged(actual_state)
+                    let sender_clone = sender.clone();
+                    let uri = gtk::gio::File::for_path(&path).uri().to_string();
+                    let title = path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "External".to_string());
+
+                    glib::spawn_future_local(async move {
+                        info!("Loading subtitle file {}", uri);
+                        match player_handle.add_subtitle(&uri, &title, None, true).await {
+                            Ok(()) => sender_clone.input(PlayerInput::UpdateTrackMenus),
+                            // Playback carries on, so this isn't worth the error overlay
+                            Err(e) => {
+                                warn!("Failed to load subtitle file {}: {}", uri, e);
+                                sender_clone
+                                    .output(PlayerOutput::ShowToast(format!(
+                                        "Couldn't load {}",
+                                        title
+                                    )))
+                                    .unwrap();
+                            }
+                        }
                     });
                 }
             }
-            PlayerInput::UpdatePosition => {
+            PlayerInput::AdjustSubtitleDelay(delta_ms) => {
                 if let Some(player) = &self.player {
+                    self.subtitle_delay_ms += delta_ms;
+                    info!("Subtitle delay: {}ms", self.subtitle_delay_ms);
                     let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        let position = player_handle.get_position().await.unwrap_or(None);
-                        let duration = player_handle.get_duration().await.unwrap_or(None);
-                        let state = player_handle
-                            .get_state()
-                            .await
-                            .unwrap_or(PlayerState::Error);
-                        PlayerCommandOutput::PositionUpdate {
-                            position,
-                            duration,
-                            state,
+                    let delay_ms = self.subtitle_delay_ms;
+                    glib::spawn_future_local(async move {
+                        if let Err(e) = player_handle.set_subtitle_delay(delay_ms).await {
+                            warn!("Failed to set subtitle delay: {}", e);
                         }
                     });
+                    self.save_sync_offsets();
+                    self.show_sync_offset(sync_offset_text("Subtitle", delay_ms), &sender);
                 }
             }
-            PlayerInput::ToggleFullscreen => {
-                self.is_fullscreen = !self.is_fullscreen;
-                if self.is_fullscreen {
-                    self.window.fullscreen();
-                    // Hide cursor immediately in fullscreen
-                    sender.input(PlayerInput::HideCursor);
-                } else {
-                    self.window.unfullscreen();
-                    // Show cursor when exiting fullscreen
-                    sender.input(PlayerInput::ShowCursor);
-                }
-            }
-            PlayerInput::ShowControls => {
-                self.show_controls = true;
-                sender.input(PlayerInput::ResetControlsTimer);
-                // Also reset cursor timer when controls are shown
-                sender.input(PlayerInput::ResetCursorTimer);
-            }
-            PlayerInput::HideControls => {
-                self.show_controls = false;
-                self.controls_timer = None;
-            }
-            PlayerInput::ResetControlsTimer => {
-                // Cancel existing timer
-                if let Some(timer) = self.controls_timer.take() {
-                    timer.remove();
-                }
-
-                // Start new 3-second timer
-                let sender = sender.clone();
-                self.controls_timer = Some(glib::timeout_add_seconds_local(3, move || {
-                    sender.input(PlayerInput::HideControls);
-                    glib::ControlFlow::Break
-                }));
-            }
-            PlayerInput::Previous => {
-                debug!("Previous track requested");
-
-                if let Some(ref context) = self.playlist_context {
-                    if let Some(prev_id) = context.get_previous_item() {
-                        // Keep the context and just load the previous media
-                        let mut new_context = context.clone();
-                        new_context.update_current_index(&prev_id);
-
-                        sender.input(PlayerInput::LoadMediaWithContext {
-                            media_id: prev_id,
-                            context: new_context,
-                        });
-                    } else {
-                        debug!("No previous episode available");
-                    }
-                } else {
-                    debug!("No playlist context available for previous navigation");
+            PlayerInput::AdjustAudioDelay(delta_ms) => {
+                if let Some(player) = &self.player {
+                    self.audio_delay_ms += delta_ms;
+                    info!("Audio delay: {}ms", self.audio_delay_ms);
+                    let player_handle = player.clone();
+                    let delay_ms = self.audio_delay_ms;
+                    glib::spawn_future_local(async move {
+                        if let Err(e) = player_handle.set_audio_delay(delay_ms).await {
+                            warn!("Failed to set audio delay: {}", e);
+                        }
+                    });
+                    self.save_sync_offsets();
+                    self.show_sync_offset(sync_offset_text("Audio", delay_ms), &sender);
                 }
             }
-            PlayerInput::Next => {
-                debug!("Next track requested");
+            PlayerInput::HideSyncOffset => {
+                self.sync_offset_osd_timer = None;
+                self.sync_offset_osd = None;
+            }
+            PlayerInput::SyncOffsetsLoaded {
+                subtitle_delay_ms,
+                audio_delay_ms,
+            } => {
+                self.subtitle_delay_ms = subtitle_delay_ms;
+                self.audio_delay_ms = audio_delay_ms;
+            }
+            PlayerInput::SkipMarker => {
+                if let Some(marker) = self.active_marker.take() {
+                    info!("Skipping {}", marker.marker_type.as_str());
+                    self.skip_countdown = None;
+                    sender.input(PlayerInput::Seek(marker.end_time));
+                }
+            }
+            PlayerInput::CancelSkip => {
+                // Keep offering the button for the rest of this marker
+                self.skip_countdown = None;
+            }
+            PlayerInput::ToggleShowAutoSkip => {
+                if let (Some(marker), Some(show_id)) =
+                    (self.active_marker.clone(), self.show_id().cloned())
+                {
+                    let mode = match self.skip_mode(marker.marker_type) {
+                        MarkerSkipMode::Auto => MarkerSkipMode::Button,
+                        _ => MarkerSkipMode::Auto,
+                    };
+                    self.show_skip_modes.insert(marker.marker_type, mode);
+                    self.skip_countdown = None;
+                    if mode == MarkerSkipMode::Auto {
+                        // Choosing to always skip also skips this one
+                        sender.input(PlayerInput::SkipMarker);
+                    }
 
-                if let Some(ref context) = self.playlist_context {
-                    if let Some(next_id) = context.get_next_item() {
-                        // Keep the context and just load the next media
-                        let mut new_context = context.clone();
-                        new_context.update_current_index(&next_id);
+                    let db = (*self.db).clone();
+                    relm4::spawn(async move {
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::SaveShowSkipModeCommand;
 
-                        sender.input(PlayerInput::LoadMediaWithContext {
-                            media_id: next_id,
-                            context: new_context,
-                        });
-                    } else {
-                        debug!("No next episode available");
-                    }
-                } else {
-                    debug!("No playlist context available for next navigation");
+                        let command = SaveShowSkipModeCommand {
+                            db,
+                            show_id,
+                            marker_type: marker.marker_type,
+                            mode,
+                        };
+
+                        if let Err(e) = command.execute().await {
+                            warn!("Failed to save skip preference for show: {}", e);
+                        }
+                    });
                 }
             }
-            PlayerInput::ShowCursor => {
-                // Set cursor to default (visible)
-                if let Some(surface) = self.window.surface() {
-                    if let Some(cursor) = gtk::gdk::Cursor::from_name("default", None) {
-                        surface.set_cursor(Some(&cursor));
+            PlayerInput::CancelNextEpisode => {
+                self.next_episode_countdown = None;
+                self.next_episode_cancelled = true;
+            }
+            PlayerInput::CountdownTick => {
+                // Countdowns wait while playback is paused
+                if matches!(self.player_state, PlayerState::Playing) {
+                    match self.skip_countdown {
+                        Some(seconds) if seconds <= 1 => sender.input(PlayerInput::SkipMarker),
+                        Some(seconds) => self.skip_countdown = Some(seconds - 1),
+                        None => {}
+                    }
+                    match self.next_episode_countdown {
+                        Some(seconds) if seconds <= 1 => {
+                            self.next_episode_countdown = None;
+                            sender.input(PlayerInput::AutoAdvance);
+                        }
+                        Some(seconds) => self.next_episode_countdown = Some(seconds - 1),
+                        None => {}
                     }
                 }
-                sender.input(PlayerInput::ResetCursorTimer);
+
+                if self.skip_countdown.is_none()
+                    && self.next_episode_countdown.is_none()
+                    && let Some(timer) = self.countdown_timer.take()
+                {
+                    timer.remove();
+                }
             }
-            PlayerInput::HideCursor => {
-                // Set cursor to blank/none (invisible)
-                if let Some(surface) = self.window.surface() {
-                    if let Some(cursor) = gtk::gdk::Cursor::from_name("none", None) {
-                        surface.set_cursor(Some(&cursor));
-                    } else {
-                        // If 'none' cursor doesn't exist, try 'blank' or just hide it
-                        surface.set_cursor(None);
-                    }
+            PlayerInput::SeekToChapter(index) => {
+                let Some(chapter) = self.chapters.get(index) else {
+                    return;
+                };
+                if self.chapters_from_server {
+                    sender.input(PlayerInput::Seek(chapter.start_time));
+                } else if let Some(player) = &self.player {
+                    let player_handle = player.clone();
+                    sender.oneshot_command(async move {
+                        if let Err(e) = player_handle.seek_to_chapter(index).await {
+                            warn!("Failed to seek to chapter {}: {}", index, e);
+                        }
+                        let actual_state = player_handle
+                            .get_state()
+                            .await
+                            .unwrap_or(PlayerState::Error);
+                        PlayerCommandOutput::StateChanged(actual_state)
+                    });
                 }
-                self.cursor_timer = None;
             }
-            PlayerInput::ResetCursorTimer => {
-                // Cancel existing timer
-                if let Some(timer) = self.cursor_timer.take() {
-                    timer.remove();
+            PlayerInput::NextChapter => {
+                if let Some(index) = self
+                    .chapters
+                    .iter()
+                    .position(|chapter| chapter.start_time > self.position)
+                {
+                    sender.input(PlayerInput::SeekToChapter(index));
                 }
-
-                // Start new 3-second timer for cursor hiding
-                let sender = sender.clone();
-                self.cursor_timer = Some(glib::timeout_add_seconds_local(3, move || {
-                    sender.input(PlayerInput::HideCursor);
-                    glib::ControlFlow::Break
-                }));
             }
-            PlayerInput::StartSeeking => {
-                self.is_seeking = true;
+            PlayerInput::PreviousChapter => {
+                if let Some(index) = Chapter::index_at(&self.chapters, self.position) {
+                    // Restart the current chapter unless it only just started
+                    let into_chapter = self.position - self.chapters[index].start_time;
+                    let target = if into_chapter < CHAPTER_RESTART_THRESHOLD {
+                        index.saturating_sub(1)
+                    } else {
+                        index
+                    };
+                    sender.input(PlayerInput::SeekToChapter(target));
+                }
             }
-            PlayerInput::StopSeeking => {
-                self.is_seeking = false;
+            PlayerInput::ChapterThumbnailLoaded { id, texture } => {
+                if let Some(picture) = self.chapter_thumbnails.get(&id) {
+                    picture.set_paintable(Some(&texture));
+                }
             }
-            PlayerInput::UpdateSeekPreview(position) => {
-                // Update position label to show preview time during seek
-                self.position_label.set_text(&format_duration(position));
+            PlayerInput::ChapterThumbnailFailed => {
+                debug!("Failed to load a chapter thumbnail");
             }
-            PlayerInput::Rewind => {
-                // Seek backward 10 seconds
-                let new_position = self.position.saturating_sub(Duration::from_secs(10));
-                sender.input(PlayerInput::Seek(new_position));
+            PlayerInput::SetSleepTimer(minutes) => {
+                info!("Sleep timer set for {} minutes", minutes);
+                let deadline = std::time::Instant::now() + Duration::from_secs(minutes * 60);
+                self.set_sleep_timer(Some(SleepTimer::At(deadline)), &sender);
+                self.set_sleep_fade(None);
             }
-            PlayerInput::Forward => {
-                // Seek forward 10 seconds
-                let new_position = self.position + Duration::from_secs(10);
-                // Clamp to duration if we have it
-                let final_position = if new_position > self.duration {
-                    self.duration
-                } else {
-                    new_position
-                };
-                sender.input(PlayerInput::Seek(final_position));
+            PlayerInput::SleepAfterCurrent => {
+                info!("Playback will stop at the end of the current item");
+                self.set_sleep_timer(Some(SleepTimer::EndOfItem), &sender);
+                self.set_sleep_fade(None);
+                // The credits countdown may already be running for the next episode
+                self.next_episode_countdown = None;
             }
-            PlayerInput::SeekRelative(seconds) => {
-                // Relative seeking: positive for forward, negative for backward
-                let duration = if seconds >= 0 {
-                    Duration::from_secs(seconds as u64)
-                } else {
-                    Duration::from_secs((-seconds) as u64)
-                };
-
-                let new_position = if seconds >= 0 {
-                    // Forward seek
-                    let pos = self.position + duration;
-                    // Clamp to duration
-                    if pos > self.duration {
-                        self.duration
-                    } else {
-                        pos
-                    }
-                } else {
-                    // Backward seek
-                    self.position.saturating_sub(duration)
+            PlayerInput::CancelSleepTimer => {
+                self.set_sleep_timer(None, &sender);
+                self.set_sleep_fade(None);
+            }
+            PlayerInput::SleepTimerTick => {
+                let Some(remaining) = self.sleep_timer_remaining() else {
+                    return;
                 };
+                if matches!(self.sleep_timer, Some(SleepTimer::At(_))) && remaining.is_zero() {
+                    self.fire_sleep_timer(&sender);
+                    return;
+                }
 
-                sender.input(PlayerInput::Seek(new_position));
+                self.set_sleep_fade(sleep_fade(remaining, self.config_sleep_timer_fade_out));
             }
-            PlayerInput::SpeedUp => {
-                // Increase playback speed by 10%
-                self.playback_speed = (self.playback_speed * 1.1).min(4.0); // Cap at 4x speed
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    let speed = self.playback_speed;
-                    sender.oneshot_command(async move {
-                        player_handle.set_playback_speed(speed).await.ok();
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
-                    });
+            PlayerInput::QueueMedia {
+                media_id,
+                play_next,
+            } => {
+                let db = (*self.db).clone();
+                sender.command(move |out, shutdown| {
+                    shutdown
+                        .register(async move {
+                            use crate::services::core::PlaylistService;
+
+                            // A failure only affects the queue, not what is playing
+                            let output = match PlaylistService::build_queue_item(&db, &media_id)
+                                .await
+                            {
+                                Ok(item) => PlayerCommandOutput::QueueItemReady { item, play_next },
+                                Err(e) => {
+                                    error!("Failed to add {} to the queue: {}", media_id, e);
+                                    PlayerCommandOutput::QueueItemFailed
+                                }
+                            };
+                            let _ = out.send(output);
+                        })
+                        .drop_on_shutdown()
+                });
+            }
+            PlayerInput::PlayQueueItem(index) => {
+                if index < self.play_queue.items.len() {
+                    let (media_id, context) = self.queue_target(index);
+                    sender.input(PlayerInput::LoadMediaWithContext { media_id, context });
                 }
             }
-            PlayerInput::SpeedDown => {
-                // Decrease playback speed by 10%
-                self.playback_speed = (self.playback_speed * 0.9).max(0.25); // Min 0.25x speed
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    let speed = self.playback_speed;
-                    sender.oneshot_command(async move {
-                        player_handle.set_playback_speed(speed).await.ok();
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
-                    });
+            PlayerInput::QueueReplaced(queue) => {
+                // Music moved the queue on, so what plays here no longer comes from it
+                if matches!(self.playlist_context, Some(PlaylistContext::Queue(_)))
+                    && queue.current().map(|item| &item.id) != self.media_item_id.as_ref()
+                {
+                    self.playlist_context = Some(PlaylistContext::SingleItem);
+                }
+                self.play_queue = queue;
+                if !self.pending_queue_items.is_empty() {
+                    self.queue_changed(&sender);
+                    return;
+                }
+                self.queue_loaded = true;
+                if let Some(PlaylistContext::Queue(queue)) = &mut self.playlist_context {
+                    *queue = self.play_queue.clone();
                 }
+                self.can_go_previous = self
+                    .playlist_context
+                    .as_ref()
+                    .is_some_and(|context| context.has_previous());
+                self.can_go_next = self.next_target(false).is_some();
+                self.update_queue_list(&sender);
             }
-            PlayerInput::SpeedReset => {
-                // Reset playback speed to normal
-                self.playback_speed = 1.0;
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        player_handle.set_playback_speed(1.0).await.ok();
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+            PlayerInput::MoveQueueItem { from, to } => {
+                self.play_queue.move_item(from, to);
+                self.queue_changed(&sender);
+            }
+            PlayerInput::RemoveQueueItem(index) => {
+                self.play_queue.remove(index);
+                self.queue_changed(&sender);
+            }
+            PlayerInput::SetQueueShuffle(shuffle) => {
+                if shuffle != self.play_queue.shuffle {
+                    self.play_queue.set_shuffle(shuffle);
+                    self.queue_changed(&sender);
+                }
+            }
+            PlayerInput::CycleQueueRepeat => {
+                self.play_queue.repeat = self.play_queue.repeat.cycle();
+                self.queue_changed(&sender);
+            }
+            PlayerInput::ClearQueue => {
+                // Only keep the item playing now if it came from the queue
+                if !matches!(self.playlist_context, Some(PlaylistContext::Queue(_))) {
+                    self.play_queue.current_index = None;
+                }
+                self.play_queue.clear();
+                self.queue_changed(&sender);
+            }
+            PlayerInput::PingTranscodeSession => {
+                if let Some((media_id, session_id)) = self.transcode_session.clone() {
+                    let db = (*self.db).clone();
+                    relm4::spawn(async move {
+                        use crate::services::commands::Command;
+                        use crate::services::commands::media_commands::PingTranscodeSessionCommand;
+
+                        let command = PingTranscodeSessionCommand {
+                            db,
+                            media_item_id: media_id,
+                            session_id,
+                        };
+
+                        if let Err(e) = command.execute().await {
+                            debug!("Failed to ping transcode session: {}", e);
+                        }
                     });
                 }
             }
-            PlayerInput::FrameStepForward => {
-                // Step one frame forward (while paused)
-                if self.player_state == PlayerState::Paused {
-                    if let Some(player) = &self.player {
-                        let player_handle = player.clone();
-                        sender.oneshot_command(async move {
-                            player_handle.frame_step_forward().await.ok();
-                            PlayerCommandOutput::StateChanged(PlayerState::Paused)
-                        });
+        }
+    }
+
+    async fn update_cmd(
+        &mut self,
+        message: Self::CommandOutput,
+        sender: AsyncComponentSender<Self>,
+        _root: &Self::Root,
+    ) {
+        // The local player sits paused while casting; the receiver reports instead
+        if self.cast.is_some()
+            && matches!(
+                message,
+                PlayerCommandOutput::StateChanged(_)
+                    | PlayerCommandOutput::PositionUpdate { .. }
+                    | PlayerCommandOutput::EndOfStream
+                    | PlayerCommandOutput::Buffering(_)
+            )
+        {
+            return;
+        }
+
+        match message {
+            PlayerCommandOutput::StateChanged(state) => self.apply_state(state, &sender),
+            PlayerCommandOutput::MediaReady(_) if self.cast.is_some() => {
+                // Keep casting whatever is played next
+                let Some(media_id) = self.media_item_id.clone() else {
+                    self.end_cast(true, &sender);
+                    sender
+                        .output(PlayerOutput::ShowToast(
+                            "This item can't be cast".to_string(),
+                        ))
+                        .unwrap();
+                    return;
+                };
+                let Some(cast) = &mut self.cast else {
+                    return;
+                };
+                cast.started = false;
+                let receiver = cast.receiver.clone();
+                let previous_transcode = cast.transcode_session.take();
+                let player = self.player.clone();
+                let start = self.start_position.take();
+                let db = (*self.db).clone();
+                let quality = self.cast_quality();
+                sender.oneshot_command(async move {
+                    let mut resume = start.unwrap_or_default();
+                    if let Some(player) = player {
+                        player.pause().await.ok();
+                        // Start where the local player resumed
+                        if start.is_none() {
+                            resume = player
+                                .get_position()
+                                .await
+                                .ok()
+                                .flatten()
+                                .unwrap_or_default();
+                        }
+                    }
+                    Self::stop_cast_transcode(db.clone(), previous_transcode).await;
+                    let reloaded = async {
+                        let (media, transcode_session) =
+                            Self::cast_media(db.clone(), media_id.clone(), quality, resume).await?;
+                        let status = async {
+                            Self::load_on_receiver(
+                                db.clone(),
+                                Some(media_id),
+                                media,
+                                receiver.as_ref(),
+                            )
+                            .await?;
+                            receiver.status().await
+                        }
+                        .await;
+                        match status {
+                            Ok(status) => Ok((status, transcode_session)),
+                            Err(e) => {
+                                Self::stop_cast_transcode(db, transcode_session).await;
+                                Err(e)
+                            }
+                        }
                     }
+                    .await;
+                    PlayerCommandOutput::CastReloaded(reloaded.map_err(|e| e.to_string()))
+                });
+            }
+            PlayerCommandOutput::MediaReady(state) => {
+                // A start position from a remote app wins over resuming
+                if let Some(position) = self.start_position.take() {
+                    sender.input(PlayerInput::Seek(position));
                 }
+                sender.oneshot_command(async move { PlayerCommandOutput::StateChanged(state) });
             }
-            PlayerInput::FrameStepBackward => {
-                // Step one frame backward (while paused)
-                if self.player_state == PlayerState::Paused {
-                    if let Some(player) = &self.player {
-                        let player_handle = player.clone();
-                        sender.oneshot_command(async move {
-                            player_handle.frame_step_backward().await.ok();
-                            PlayerCommandOutput::StateChanged(PlayerState::Paused)
-                        });
+            PlayerCommandOutput::PlaybackError(message) if self.can_reconnect() => {
+                self.reconnect(message, &sender);
+            }
+            PlayerCommandOutput::LoadError(error_msg)
+            | PlayerCommandOutput::PlaybackError(error_msg) => {
+                self.start_position = None;
+                sender.input(PlayerInput::ShowError(error_msg));
+            }
+            PlayerCommandOutput::Reconnected { media_id, result } => {
+                // Something else is playing or recovery was called off meanwhile
+                if !self.reconnect_in_flight || self.media_item_id.as_ref() != Some(&media_id) {
+                    return;
+                }
+                self.reconnect_in_flight = false;
+                match result {
+                    Ok((stream_info, quality_index)) => {
+                        let (transcoding, session_id) =
+                            match stream_info.quality_options.get(quality_index) {
+                                Some(option) => (
+                                    option.requires_transcode,
+                                    option.transcode_session_id.clone(),
+                                ),
+                                None => (
+                                    !stream_info.direct_play
+                                        && stream_info.transcode_session_id.is_some(),
+                                    stream_info.transcode_session_id.clone(),
+                                ),
+                            };
+                        self.is_transcoding = transcoding;
+                        self.transcode_session =
+                            session_id.map(|session_id| (media_id, session_id));
+                        self.quality_options = stream_info.quality_options.clone();
+                        self.quality_index = quality_index;
+                        self.populate_quality_menu(sender.clone());
+                        self.external_subtitles = stream_info.external_subtitles.clone();
+                        self.stream_info = Some(stream_info);
+                        // The reloaded stream gets as long as any to start moving
+                        self.last_progress_at = std::time::Instant::now();
+                        self.update_transcode_keepalive(&sender);
+                        sender.input(PlayerInput::UpdateTrackMenus);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Reconnect attempt #{} failed: {}",
+                            self.reconnect_attempts, e
+                        );
+                        self.reconnect(e, &sender);
                     }
                 }
             }
-            PlayerInput::ToggleMute => {
-                // Toggle mute state
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        player_handle.toggle_mute().await.ok();
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
-                    });
+            PlayerCommandOutput::QueueLoaded(mut queue) => {
+                // The queue changed here first, so this copy is older
+                if self.queue_loaded {
+                    return;
+                }
+                // Left over from the last session, nothing plays from the queue yet
+                queue.current_index = None;
+                self.play_queue = queue;
+                if self.pending_queue_items.is_empty() {
+                    self.queue_loaded = true;
+                    self.can_go_next = self.next_target(false).is_some();
+                    self.update_queue_list(&sender);
+                } else {
+                    self.queue_changed(&sender);
                 }
             }
-            PlayerInput::VolumeUp => {
-                // Increase volume by 10%
-                let new_volume = (self.volume + 0.1).min(1.0);
-                self.volume = new_volume;
-                self.volume_slider.set_value(new_volume);
-                sender.input(PlayerInput::SetVolume(new_volume));
+            PlayerCommandOutput::QueueItemReady { item, play_next } => {
+                sender
+                    .output(PlayerOutput::ShowToast(
+                        if play_next {
+                            "Playing next"
+                        } else {
+                            "Added to queue"
+                        }
+                        .to_string(),
+                    ))
+                    .unwrap();
+                if self.queue_loaded {
+                    self.add_to_queue(item, play_next);
+                    self.queue_changed(&sender);
+                } else {
+                    // Saving now would replace the saved queue before it loaded
+                    self.pending_queue_items.push((item, play_next));
+                }
             }
-            PlayerInput::VolumeDown => {
-                // Decrease volume by 10%
-                let new_volume = (self.volume - 0.1).max(0.0);
-                self.volume = new_volume;
-                self.volume_slider.set_value(new_volume);
-                sender.input(PlayerInput::SetVolume(new_volume));
+            PlayerCommandOutput::QueueItemFailed => {
+                sender
+                    .output(PlayerOutput::ShowToast(
+                        "Failed to add to queue".to_string(),
+                    ))
+                    .unwrap();
             }
-            PlayerInput::CycleSubtitleTrack => {
-                // Cycle through available subtitle tracks
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        player_handle.cycle_subtitle_track().await.ok();
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+            PlayerCommandOutput::WatchPartyStarted(started) => match started {
+                Ok((party, events)) => {
+                    let message = match party.code() {
+                        Some(code) => {
+                            self.window.clipboard().set_text(code);
+                            format!(
+                                "Hosting a watch party for this network, join code {} copied",
+                                code
+                            )
+                        }
+                        None => "Hosting a watch party".to_string(),
+                    };
+                    self.start_watch_party(party, events, &sender);
+                    sender.output(PlayerOutput::ShowToast(message)).unwrap();
+                }
+                Err(e) => {
+                    sender
+                        .output(PlayerOutput::ShowToast(format!(
+                            "Failed to host a watch party: {}",
+                            e
+                        )))
+                        .unwrap();
+                }
+            },
+            PlayerCommandOutput::MprisStarted(started) => {
+                if let Some((mpris, mut actions)) = started {
+                    // Route requests that need the page, like playlist navigation
+                    let sender = sender.clone();
+                    let window = self.window.clone();
+                    glib::spawn_future_local(async move {
+                        while let Some(action) = actions.recv().await {
+                            match action {
+                                MprisAction::Raise => window.present(),
+                                MprisAction::Next => sender.input(PlayerInput::Next),
+                                MprisAction::Previous => sender.input(PlayerInput::Previous),
+                            }
+                        }
+                    });
+
+                    self.mpris = Some(mpris);
+                    // Catch up on anything that started before registration finished
+                    self.update_mpris_metadata();
+                    let state = self.player_state.clone();
+                    let volume = self.volume;
+                    self.update_mpris(move |mpris| async move {
+                        mpris.set_playback_state(state).await?;
+                        mpris.set_volume(volume).await
                     });
                 }
             }
-            PlayerInput::CycleAudioTrack => {
-                // Cycle through available audio tracks
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        player_handle.cycle_audio_track().await.ok();
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
-                    });
+            PlayerCommandOutput::TrickplayLoaded {
+                media_id,
+                trickplay,
+            } => {
+                // Ignore thumbnails for media that is no longer playing
+                if self.media_item_id.as_ref() == Some(&media_id) {
+                    self.trickplay = trickplay;
+                    self.seek_preview_index = None;
                 }
             }
-            PlayerInput::UpdateTrackMenus => {
-                // Populate the track selection menus
-                self.populate_audio_menu(sender.clone());
-                self.populate_subtitle_menu(sender.clone());
-
-                // Also get current track selections
-                if let Some(player) = &self.player {
-                    let player_handle = player.clone();
-                    let sender_clone = sender.clone();
-                    glib::spawn_future_local(async move {
-                        let _audio_track =
-                            player_handle.get_current_audio_track().await.unwrap_or(-1);
-                        let _subtitle_track = player_handle
-                            .get_current_subtitle_track()
-                            .await
-                            .unwrap_or(-1);
-                        // Store the current tracks (we'll update the model in a moment)
-                    });
+            PlayerCommandOutput::MarkersLoaded {
+                media_id,
+                markers,
+                show_skip_modes,
+            } => {
+                if self.media_item_id.as_ref() == Some(&media_id) {
+                    debug!("Loaded {} skip markers", markers.len());
+                    self.markers = markers;
+                    self.show_skip_modes = show_skip_modes;
+                    self.update_active_marker(&sender);
                 }
             }
-            PlayerInput::SetAudioTrack(track_id) => {
-                if let Some(player) = &self.player {
-                    self.current_audio_track = track_id;
+            PlayerCommandOutput::PositionUpdate { position, duration } => {
+                self.apply_position(position, duration, &sender)
+            }
+            PlayerCommandOutput::EndOfStream => self.item_ended(&sender),
+            PlayerCommandOutput::TracksChanged => {
+                sender.input(PlayerInput::UpdateTrackMenus);
+            }
+            PlayerCommandOutput::ChaptersChanged => {
+                // Embedded chapters only fill in for servers that don't report any
+                if !self.chapters_from_server
+                    && let (Some(player), Some(media_id)) = (&self.player, &self.media_item_id)
+                {
                     let player_handle = player.clone();
+                    let media_id = media_id.clone();
                     sender.oneshot_command(async move {
-                        let _ = player_handle.set_audio_track(track_id).await;
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
+                        let chapters = player_handle.get_chapters().await.unwrap_or_default();
+                        PlayerCommandOutput::ChaptersLoaded {
+                            media_id,
+                            chapters,
+                            from_server: false,
+                        }
                     });
                 }
             }
-            PlayerInput::SetSubtitleTrack(track_id) => {
-                if let Some(player) = &self.player {
-                    self.current_subtitle_track = track_id;
-                    let player_handle = player.clone();
-                    sender.oneshot_command(async move {
-                        let _ = player_handle.set_subtitle_track(track_id).await;
-                        PlayerCommandOutput::StateChanged(PlayerState::Playing)
-                    });
+            PlayerCommandOutput::ChaptersLoaded {
+                media_id,
+                chapters,
+                from_server,
+            } => {
+                if self.media_item_id.as_ref() == Some(&media_id)
+                    && (from_server || !self.chapters_from_server)
+                    && !(from_server && chapters.is_empty())
+                {
+                    debug!(
+                        "Loaded {} chapters from the {}",
+                        chapters.len(),
+                        if from_server { "server" } else { "player" }
+                    );
+                    self.set_chapters(chapters, from_server);
                 }
             }
-            PlayerInput::ToggleControlsVisibility => {
-                // Toggle controls visibility
-                self.show_controls = !self.show_controls;
-                if self.show_controls {
-                    // Reset timer when showing controls
-                    sender.input(PlayerInput::ResetControlsTimer);
-                    sender.input(PlayerInput::ResetCursorTimer);
+            PlayerCommandOutput::TranscodeStatusLoaded(status) => match status {
+                Ok(status) => self.transcode_status = status,
+                Err(e) => debug!("Failed to get transcode status: {}", e),
+            },
+            PlayerCommandOutput::CaptureFinished(result) => match result {
+                Ok(path) => {
+                    info!("Saved capture to {}", path.display());
+                    sender.output(PlayerOutput::CaptureSaved(path)).unwrap();
                 }
+                Err(e) => {
+                    warn!("Capture failed: {}", e);
+                    sender
+                        .output(PlayerOutput::ShowToast(format!("Capture failed: {}", e)))
+                        .unwrap();
+                }
+            },
+            PlayerCommandOutput::Buffering(percent) => {
+                self.buffering_percent = (percent < 100).then_some(percent);
             }
-            PlayerInput::RetryLoad => {
-                // Clear the error and retry loading the media
-                self.error_message = None;
-
-                // Check if we've exceeded max retries
-                if self.retry_count >= self.max_retries {
-                    self.error_message = Some(
-                        "Failed to load media after multiple attempts. Please check your connection and try again.".to_string()
+            PlayerCommandOutput::CastDevicesFound(devices) => {
+                self.finding_cast_devices = false;
+                self.cast_devices = devices;
+                self.update_cast_menu();
+            }
+            PlayerCommandOutput::CastStarted(started) => match started {
+                Ok((device, receiver, transcode_session)) => {
+                    self.start_cast(device, receiver, transcode_session, &sender)
+                }
+                Err(e) => {
+                    sender
+                        .output(PlayerOutput::ShowToast(format!("Failed to cast: {}", e)))
+                        .unwrap();
+                }
+            },
+            PlayerCommandOutput::CastReloaded(reloaded) => match reloaded {
+                Ok((status, transcode_session)) => {
+                    let Some(cast) = &mut self.cast else {
+                        // Casting ended while the receiver was loading
+                        let db = (*self.db).clone();
+                        relm4::spawn(Self::stop_cast_transcode(db, transcode_session));
+                        return;
+                    };
+                    cast.transcode_session = transcode_session;
+                    sender.oneshot_command(
+                        async move { PlayerCommandOutput::CastStatus(Ok(status)) },
                     );
-                    self.retry_count = 0;
-                    return;
                 }
-
-                // Increment retry count
-                self.retry_count += 1;
-
-                // Calculate exponential backoff delay (1s, 2s, 4s)
-                let delay = Duration::from_secs(2_u64.pow(self.retry_count - 1));
-
-                // Schedule retry after delay
-                if let Some(timer) = self.retry_timer.take() {
-                    timer.remove();
+                Err(e) => {
+                    self.end_cast(true, &sender);
+                    sender
+                        .output(PlayerOutput::ShowToast(format!("Failed to cast: {}", e)))
+                        .unwrap();
                 }
-
-                let sender_clone = sender.clone();
-                let media_id = self.media_item_id.clone();
-                let context = self.playlist_context.clone();
-
-                info!("Scheduling retry #{} after {:?}", self.retry_count, delay);
-
-                self.retry_timer = Some(glib::timeout_add_local(delay, move || {
-                    if let Some(id) = &media_id {
-                        if let Some(ctx) = &context {
-                            sender_clone.input(PlayerInput::LoadMediaWithContext {
-                                media_id: id.clone(),
-                                context: ctx.clone(),
-                            });
-                        } else {
-                            sender_clone.input(PlayerInput::LoadMedia(id.clone()));
+            },
+            PlayerCommandOutput::CastStatus(status) => {
+                let Some(cast) = &mut self.cast else {
+                    return;
+                };
+                cast.status_pending = false;
+                let status = match status {
+                    Ok(status) => {
+                        cast.failures = 0;
+                        status
+                    }
+                    Err(e) => {
+                        cast.failures += 1;
+                        warn!("{} did not answer: {}", cast.device.name, e);
+                        if cast.failures >= CAST_MAX_FAILURES {
+                            let message = format!("Lost the connection to {}", cast.device.name);
+                            self.end_cast(false, &sender);
+                            sender.output(PlayerOutput::ShowToast(message)).unwrap();
                         }
+                        return;
                     }
-                    glib::ControlFlow::Break
-                }));
-            }
-            PlayerInput::ClearError => {
-                self.error_message = None;
-                self.retry_count = 0;
-                if let Some(timer) = self.retry_timer.take() {
-                    timer.remove();
+                };
+
+                let playing = matches!(status.state, PlayerState::Playing | PlayerState::Paused);
+                let finished = match status.state {
+                    PlayerState::Playing | PlayerState::Paused => {
+                        cast.started = true;
+                        false
+                    }
+                    PlayerState::Stopped => std::mem::take(&mut cast.started),
+                    _ => false,
+                };
+                if status.state == PlayerState::Error {
+                    let message = format!("{} could not play this item", cast.device.name);
+                    self.end_cast(false, &sender);
+                    sender.output(PlayerOutput::ShowToast(message)).unwrap();
+                    return;
                 }
-            }
-            PlayerInput::ShowError(msg) => {
-                error!("Player error: {}", msg);
-                self.error_message = Some(msg);
-                self.player_state = PlayerState::Error;
-            }
-            PlayerInput::EscapePressed => {
-                // ESC key behavior: exit fullscreen if in fullscreen, otherwise navigate back
-                if self.is_fullscreen {
-                    sender.input(PlayerInput::ToggleFullscreen);
-                } else {
-                    sender.input(PlayerInput::NavigateBack);
+
+                if self.player_state != status.state {
+                    self.apply_state(status.state, &sender);
                 }
-            }
-            PlayerInput::NavigateBack => {
-                // Clear cursor timer and show cursor before navigating back
-                if let Some(timer) = self.cursor_timer.take() {
-                    timer.remove();
+                // Receivers report the start of the stream while loading or once stopped
+                if playing {
+                    self.apply_position(status.position, status.duration, &sender);
                 }
-                if let Some(timer) = self.controls_timer.take() {
-                    timer.remove();
+                if finished {
+                    self.item_ended(&sender);
                 }
-                sender.input(PlayerInput::ShowCursor);
-                // Navigate back
-                sender.output(PlayerOutput::NavigateBack).unwrap();
             }
         }
     }
+}
 
-    async fn update_cmd(
-        &mut self,
-        message: Self::CommandOutput,
-        sender: AsyncComponentSender<Self>,
-        _root: &Self::Root,
-    ) {
-        match message {
-            PlayerCommandOutput::StateChanged(state) => {
-                self.player_state = state.clone();
-                // Clear error on successful state change
-                if !matches!(&state, PlayerState::Error) {
-                    self.error_message = None;
-                    self.retry_count = 0;
-                }
-            }
-            PlayerCommandOutput::LoadError(error_msg) => {
-                sender.input(PlayerInput::ShowError(error_msg));
-            }
-            PlayerCommandOutput::PositionUpdate {
-                position,
-                duration,
-                state,
-            } => {
-                if let Some(pos) = position {
-                    self.position = pos;
-                    // Update position label
-                    self.position_label.set_text(&format_duration(pos));
+/// Queue position played next, if the queue rather than the playlist decides.
+/// `auto` is set when the current item ended on its own, where repeat one applies.
+fn next_queue_index(
+    context: Option<&PlaylistContext>,
+    queue: &PlayQueue,
+    auto: bool,
+) -> Option<usize> {
+    match context {
+        Some(PlaylistContext::Queue(_)) if auto => queue.auto_advance_index(),
+        // A show's remaining episodes come before the queue
+        Some(context @ PlaylistContext::TvShow { .. })
+            if context.has_next() && (!auto || context.auto_play_next()) =>
+        {
+            None
+        }
+        _ => queue.next_index(),
+    }
+}
 
-                    // Update seek bar position (only if not being dragged)
-                    if !self.is_seeking {
-                        self.seek_bar.set_value(pos.as_secs_f64());
-                    }
+/// The item and context to load for Next, or for auto-advance when `auto` is set
+fn next_target(
+    context: Option<&PlaylistContext>,
+    queue: &PlayQueue,
+    auto: bool,
+) -> Option<(MediaItemId, PlaylistContext)> {
+    if let Some(index) = next_queue_index(context, queue, auto) {
+        return Some(queue_target(queue, index));
+    }
+    match context {
+        Some(context @ PlaylistContext::TvShow { .. }) if !auto || context.auto_play_next() => {
+            let next_id = context.get_next_item()?;
+            let mut context = context.clone();
+            context.update_current_index(&next_id);
+            Some((next_id, context))
+        }
+        _ => None,
+    }
+}
 
-                    // Save playback progress to database at configured interval
-                    if let (Some(media_id), Some(dur)) = (&self.media_item_id, duration) {
-                        // Use cached config value instead of reloading config file
-                        let save_interval_secs = self.config_progress_update_interval_seconds;
+/// Context for playing the queue from a position
+fn queue_target(queue: &PlayQueue, index: usize) -> (MediaItemId, PlaylistContext) {
+    let mut queue = queue.clone();
+    queue.current_index = Some(index);
+    (queue.items[index].id.clone(), PlaylistContext::Queue(queue))
+}
+
+/// Time left before `timer` stops playback, unknown for the end of an item of
+/// unknown length
+fn sleep_timer_remaining(
+    timer: SleepTimer,
+    now: std::time::Instant,
+    position: Duration,
+    duration: Duration,
+) -> Option<Duration> {
+    match timer {
+        SleepTimer::At(deadline) => Some(deadline.saturating_duration_since(now)),
+        SleepTimer::EndOfItem if !duration.is_zero() => Some(duration.saturating_sub(position)),
+        SleepTimer::EndOfItem => None,
+    }
+}
+
+/// Share of the chosen volume to play at with `remaining` left on the sleep timer,
+/// ramping down over the last `SLEEP_FADE_DURATION` when fading out is on
+fn sleep_fade(remaining: Duration, enabled: bool) -> Option<f64> {
+    (enabled && remaining < SLEEP_FADE_DURATION)
+        .then(|| remaining.as_secs_f64() / SLEEP_FADE_DURATION.as_secs_f64())
+}
 
-                        // Check if enough time has passed since last save
-                        let elapsed = self.last_progress_save.elapsed().as_secs();
+/// Text under the spinner while playback waits on the network
+fn buffering_text(reconnect_attempts: u32, buffering_percent: Option<u8>) -> String {
+    match reconnect_attempts {
+        0 => format!("Buffering… {}%", buffering_percent.unwrap_or(0)),
+        1 => "Reconnecting…".to_string(),
+        attempt => format!(
+            "Reconnecting… (attempt {} of {})",
+            attempt, RECONNECT_MAX_ATTEMPTS
+        ),
+    }
+}
 
-                        // Always save if watched (>90%) or if interval has passed
-                        let watched = pos.as_secs_f64() / dur.as_secs_f64() > 0.9;
+/// Whether `stream` can be brought back by reloading it once it dropped
+fn can_reconnect(
+    stream: Option<&StreamInfo>,
+    state: &PlayerState,
+    reconnect_attempts: u32,
+) -> bool {
+    // Only streams from a server drop with the network
+    stream.is_some_and(|stream| {
+        stream.url.starts_with("http://") || stream.url.starts_with("https://")
+    })
+        // An item that never started playing shows its error right away
+        && (reconnect_attempts > 0 || matches!(state, PlayerState::Playing | PlayerState::Paused))
+}
 
-                        if watched || elapsed >= save_interval_secs {
-                            self.last_progress_save = std::time::Instant::now();
+/// Wait before reconnect attempt `attempt`: 1s, 2s, 4s and so on
+fn reconnect_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.saturating_sub(1).min(5)).min(RECONNECT_MAX_DELAY)
+}
 
-                            let db = (*self.db).clone();
-                            let media_id = media_id.clone();
-                            let position_ms = pos.as_millis() as i64;
-                            let duration_ms = dur.as_millis() as i64;
+/// Whether a stream that ended at `position` was cut off rather than finished
+fn ended_early(position: Duration, duration: Duration) -> bool {
+    duration > position + EARLY_END_MARGIN
+}
 
-                            relm4::spawn(async move {
-                                use crate::services::commands::{
-                                    Command, UpdatePlaybackProgressCommand,
-                                };
+/// How the screensaver inhibit has to change for a player state: `Some(true)` to take
+/// it, `Some(false)` to release it, `None` to leave it as it is
+fn inhibit_change(
+    state: &PlayerState,
+    inhibit_while_paused: bool,
+    inhibited: bool,
+) -> Option<bool> {
+    let should_inhibit = match state {
+        PlayerState::Playing => true,
+        PlayerState::Paused => inhibit_while_paused,
+        _ => false,
+    };
+    (should_inhibit != inhibited).then_some(should_inhibit)
+}
 
-                                let command = UpdatePlaybackProgressCommand {
-                                    db,
-                                    media_id,
-                                    position_ms,
-                                    duration_ms,
-                                    watched,
-                                };
+/// Skip mode configured for each kind of marker
+fn marker_skip_modes(config: &Config) -> HashMap<ChapterType, MarkerSkipMode> {
+    [
+        ChapterType::Intro,
+        ChapterType::Credits,
+        ChapterType::Recap,
+        ChapterType::Preview,
+    ]
+    .into_iter()
+    .map(|marker_type| (marker_type, config.playback.skip_mode(marker_type)))
+    .collect()
+}
 
-                                if let Err(e) = command.execute().await {
-                                    debug!("Failed to save playback progress: {}", e);
-                                }
-                            });
-                        }
-                    }
-                }
-                if let Some(dur) = duration {
-                    self.duration = dur;
-                    // Update duration label
-                    self.duration_label.set_text(&format_duration(dur));
-                    // Update seek bar range
-                    self.seek_bar.set_range(0.0, dur.as_secs_f64());
-                }
-                self.player_state = state;
+/// On-screen text for a subtitle or audio delay, signed so the direction is clear
+fn sync_offset_text(label: &str, delay_ms: i64) -> String {
+    if delay_ms == 0 {
+        format!("{} delay: 0 ms", label)
+    } else {
+        format!("{} delay: {:+} ms", label, delay_ms)
+    }
+}
+
+/// Text of the statistics overlay, one `label value` pair per line
+fn stats_text(
+    stats: &PlaybackStats,
+    stream_info: Option<&StreamInfo>,
+    is_transcoding: bool,
+    transcode_status: Option<&TranscodeStatus>,
+) -> String {
+    let join = |parts: Vec<String>| {
+        let line = parts.join(" · ");
+        if line.is_empty() {
+            "–".to_string()
+        } else {
+            line
+        }
+    };
+
+    let server = match stream_info {
+        None => "–".to_string(),
+        Some(info) if info.direct_play => "Direct play".to_string(),
+        Some(_) if !is_transcoding => "Direct stream".to_string(),
+        Some(_) => match transcode_status {
+            None => "Transcoding".to_string(),
+            Some(status) => {
+                let encoded: Vec<&str> = [
+                    (status.video_transcoded, "video"),
+                    (status.audio_transcoded, "audio"),
+                ]
+                .into_iter()
+                .filter_map(|(transcoded, stream)| transcoded.then_some(stream))
+                .collect();
+                let mut parts = vec![if encoded.is_empty() {
+                    "Remuxing".to_string()
+                } else {
+                    format!("Transcoding {}", encoded.join(" and "))
+                }];
+                parts.extend(status.speed.map(|speed| format!("{:.1}× speed", speed)));
+                parts.extend(status.hardware.clone());
+                join(parts)
             }
+        },
+    };
+    let source = stream_info.map(|info| {
+        join(
+            [&info.container, &info.video_codec, &info.audio_codec]
+                .into_iter()
+                .filter(|value| !value.is_empty())
+                .cloned()
+                .collect(),
+        )
+    });
+    let reasons = transcode_status
+        .filter(|status| is_transcoding && !status.reasons.is_empty())
+        .map(|status| status.reasons.join(", "));
+
+    let video = join(
+        stats
+            .video_codec
+            .clone()
+            .into_iter()
+            .chain(
+                stats
+                    .resolution
+                    .map(|(width, height)| format!("{}×{}", width, height)),
+            )
+            .chain(stats.frame_rate.map(|fps| format!("{:.3} fps", fps)))
+            .collect(),
+    );
+    let decoder = stats.decoder.as_ref().map(|decoder| match &stats.hwdec {
+        Some(hwdec) => format!("{} (hardware: {})", decoder, hwdec),
+        None => format!("{} (software)", decoder),
+    });
+    let frames = join(
+        stats
+            .dropped_frames
+            .map(|count| format!("{} dropped", count))
+            .into_iter()
+            .chain(
+                stats
+                    .delayed_frames
+                    .map(|count| format!("{} delayed", count)),
+            )
+            .collect(),
+    );
+    let cache = join(
+        stats
+            .cache_duration
+            .map(|cache| format!("{:.1} s", cache.as_secs_f64()))
+            .into_iter()
+            .chain(stats.buffer_percent.map(|percent| format!("{}%", percent)))
+            .collect(),
+    );
+    let network = stats
+        .network_bytes_per_second
+        .map(|rate| format!("{:.1} Mbit/s", rate as f64 * 8.0 / 1_000_000.0));
+
+    [
+        ("Server", Some(server)),
+        ("Reason", reasons),
+        ("Source", source),
+        ("Container", stats.container.clone()),
+        ("Video", Some(video)),
+        ("Audio", stats.audio_codec.clone()),
+        ("Decoder", decoder),
+        ("Frames", Some(frames)),
+        ("Cache", Some(cache)),
+        ("Network", network),
+    ]
+    .into_iter()
+    .filter_map(|(label, value)| value.map(|value| format!("{:<10}{}", label, value)))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::connection::{Database, DatabaseConnection};
+    use crate::models::{EpisodeInfo, Resolution};
+    use crate::player::NullPlayer;
+    use crate::player::null_player::NullClock;
+    use crate::services::commands::{Command, UpdatePlaybackProgressCommand};
+
+    fn stream(direct_play: bool) -> StreamInfo {
+        StreamInfo {
+            url: "http://server/stream".to_string(),
+            direct_play,
+            video_codec: "hevc".to_string(),
+            audio_codec: "eac3".to_string(),
+            container: "mkv".to_string(),
+            bitrate: 20_000_000,
+            resolution: Resolution {
+                width: 3840,
+                height: 2160,
+            },
+            quality_options: Vec::new(),
+            transcode_session_id: None,
+            external_subtitles: Vec::new(),
+        }
+    }
+
+    /// A database holding a show's episodes, for progress to be saved against
+    async fn episode_db(episodes: &[&str]) -> DatabaseConnection {
+        use crate::db::entities::{LibraryModel, MediaItemModel, SourceModel};
+        use crate::db::repository::{
+            LibraryRepositoryImpl, MediaRepositoryImpl, Repository, SourceRepositoryImpl,
+        };
+
+        let db = Database::in_memory().await.unwrap().get_connection();
+        let now = chrono::Utc::now().naive_utc();
+        SourceRepositoryImpl::new(db.clone())
+            .insert(SourceModel {
+                id: "source".to_string(),
+                name: "Server".to_string(),
+                source_type: "jellyfin".to_string(),
+                auth_provider_id: None,
+                connection_url: None,
+                connections: None,
+                machine_id: None,
+                is_owned: true,
+                is_online: true,
+                last_sync: None,
+                last_connection_test: None,
+                connection_failure_count: 0,
+                connection_quality: None,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+        LibraryRepositoryImpl::new(db.clone())
+            .insert(LibraryModel {
+                id: "library".to_string(),
+                source_id: "source".to_string(),
+                title: "Shows".to_string(),
+                library_type: "shows".to_string(),
+                icon: None,
+                item_count: episodes.len() as i32,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+        for (index, id) in episodes.iter().enumerate() {
+            MediaRepositoryImpl::new(db.clone())
+                .insert(MediaItemModel {
+                    id: id.to_string(),
+                    library_id: "library".to_string(),
+                    source_id: "source".to_string(),
+                    media_type: "episode".to_string(),
+                    title: id.to_string(),
+                    sort_title: None,
+                    year: None,
+                    duration_ms: Some(120_000),
+                    rating: None,
+                    poster_url: None,
+                    backdrop_url: None,
+                    overview: None,
+                    genres: None,
+                    added_at: None,
+                    updated_at: now,
+                    metadata: None,
+                    parent_id: None,
+                    season_number: Some(1),
+                    episode_number: Some(index as i32 + 1),
+                })
+                .await
+                .unwrap();
+        }
+        db
+    }
+
+    async fn wait_for_end(events: &mut tokio::sync::broadcast::Receiver<PlayerEvent>) {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            while events.recv().await.expect("player event channel closed")
+                != PlayerEvent::EndOfStream
+            {}
+        })
+        .await
+        .expect("timed out waiting for the end of the stream");
+    }
+
+    /// Resume from saved progress, play to the end and move on, through the same
+    /// steps the page takes, against the null player and an in-memory database
+    #[tokio::test]
+    async fn test_resume_and_auto_play_next_flow() {
+        let db = episode_db(&["episode-1", "episode-2"]).await;
+        let clock = NullClock::manual();
+        let (handle, controller) =
+            PlayerController::with_player(Box::new(NullPlayer::with_clock(clock.clone())));
+
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async move {
+                tokio::task::spawn_local(controller.run());
+                let mut events = handle.subscribe().await.unwrap();
+                let episode = |n: u32| EpisodeInfo {
+                    id: MediaItemId::new(format!("episode-{}", n)),
+                    title: format!("Episode {}", n),
+                    season_number: 1,
+                    episode_number: n,
+                    duration_ms: Some(120_000),
+                    watched: false,
+                    playback_position_ms: None,
+                };
+                let context = PlaylistContext::TvShow {
+                    show_id: ShowId::new("show"),
+                    show_title: "Show".to_string(),
+                    current_index: 0,
+                    episodes: vec![episode(1), episode(2)],
+                    auto_play_next: true,
+                };
+                let first = MediaItemId::new("episode-1");
+
+                // Most of the first episode was watched in an earlier session
+                (UpdatePlaybackProgressCommand {
+                    db: db.clone(),
+                    media_id: first.clone(),
+                    position_ms: 90_000,
+                    duration_ms: 120_000,
+                    watched: false,
+                })
+                .execute()
+                .await
+                .unwrap();
+
+                handle
+                    .load_media("null://episode-1?duration=120")
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    PlayerPage::resume_saved_position(&db, &first, &handle, false, 10).await,
+                    None
+                );
+                assert_eq!(
+                    PlayerPage::resume_saved_position(&db, &first, &handle, true, 100).await,
+                    None
+                );
+                assert_eq!(
+                    PlayerPage::resume_saved_position(&db, &first, &handle, true, 10).await,
+                    Some(Duration::from_secs(90))
+                );
+                handle.play().await.unwrap();
+                assert_eq!(
+                    handle.get_position().await.unwrap(),
+                    Some(Duration::from_secs(90))
+                );
+
+                // Reaching the end moves on to the next episode, which starts over
+                clock.advance(Duration::from_secs(30));
+                wait_for_end(&mut events).await;
+                let (next, context) = next_target(Some(&context), &PlayQueue::default(), true)
+                    .expect("second episode should follow");
+                assert_eq!(next, MediaItemId::new("episode-2"));
+
+                handle
+                    .load_media("null://episode-2?duration=120")
+                    .await
+                    .unwrap();
+                assert_eq!(
+                    PlayerPage::resume_saved_position(&db, &next, &handle, true, 10).await,
+                    None
+                );
+                handle.play().await.unwrap();
+                assert_eq!(handle.get_position().await.unwrap(), Some(Duration::ZERO));
+
+                // After the last episode the queue takes over, from its first item
+                assert!(next_target(Some(&context), &PlayQueue::default(), true).is_none());
+                let queue = PlayQueue {
+                    items: vec![QueueItem {
+                        id: MediaItemId::new("movie"),
+                        title: "Movie".to_string(),
+                        subtitle: None,
+                        media_type: "movie".to_string(),
+                        duration_ms: None,
+                        original_position: None,
+                    }],
+                    ..Default::default()
+                };
+                let (next, context) =
+                    next_target(Some(&context), &queue, true).expect("queue should follow");
+                assert_eq!(next, MediaItemId::new("movie"));
+                assert!(matches!(context, PlaylistContext::Queue(_)));
+
+                handle.shutdown().ok();
+            })
+            .await;
+    }
+
+    #[test]
+    fn test_sleep_timer_remaining() {
+        let now = std::time::Instant::now();
+        let deadline = now + Duration::from_secs(90);
+        assert_eq!(
+            sleep_timer_remaining(
+                SleepTimer::At(deadline),
+                now,
+                Duration::ZERO,
+                Duration::ZERO
+            ),
+            Some(Duration::from_secs(90))
+        );
+        // A deadline that passed counts as nothing left
+        assert_eq!(
+            sleep_timer_remaining(
+                SleepTimer::At(now),
+                now + Duration::from_secs(5),
+                Duration::ZERO,
+                Duration::ZERO
+            ),
+            Some(Duration::ZERO)
+        );
+
+        let end_of_item = |position, duration| {
+            sleep_timer_remaining(
+                SleepTimer::EndOfItem,
+                now,
+                Duration::from_secs(position),
+                Duration::from_secs(duration),
+            )
+        };
+        assert_eq!(end_of_item(100, 600), Some(Duration::from_secs(500)));
+        assert_eq!(end_of_item(700, 600), Some(Duration::ZERO));
+        assert_eq!(end_of_item(100, 0), None);
+    }
+
+    #[test]
+    fn test_sleep_fade() {
+        assert_eq!(sleep_fade(Duration::from_secs(60), true), None);
+        assert_eq!(sleep_fade(SLEEP_FADE_DURATION, true), None);
+        assert_eq!(sleep_fade(SLEEP_FADE_DURATION / 2, true), Some(0.5));
+        assert_eq!(sleep_fade(Duration::ZERO, true), Some(0.0));
+        assert_eq!(sleep_fade(Duration::from_secs(10), false), None);
+    }
+
+    #[test]
+    fn test_reconnect_delay() {
+        let delays: Vec<u64> = (1..=8).map(|n| reconnect_delay(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 30, 30, 30]);
+        assert_eq!(reconnect_delay(0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_buffering_text() {
+        assert_eq!(buffering_text(0, None), "Buffering… 0%");
+        assert_eq!(buffering_text(0, Some(42)), "Buffering… 42%");
+        assert_eq!(buffering_text(1, Some(42)), "Reconnecting…");
+        assert_eq!(
+            buffering_text(3, None),
+            format!("Reconnecting… (attempt 3 of {})", RECONNECT_MAX_ATTEMPTS)
+        );
+    }
+
+    #[test]
+    fn test_can_reconnect() {
+        let remote = stream(true);
+        assert!(can_reconnect(Some(&remote), &PlayerState::Playing, 0));
+        assert!(can_reconnect(Some(&remote), &PlayerState::Paused, 0));
+
+        // An item that never started shows its error instead, unless it dropped
+        // earlier and this is a later attempt
+        assert!(!can_reconnect(Some(&remote), &PlayerState::Loading, 0));
+        assert!(!can_reconnect(Some(&remote), &PlayerState::Error, 0));
+        assert!(can_reconnect(Some(&remote), &PlayerState::Error, 2));
+
+        // Local files don't drop
+        let local = StreamInfo {
+            url: "file:///home/user/video.mkv".to_string(),
+            ..stream(true)
+        };
+        assert!(!can_reconnect(Some(&local), &PlayerState::Playing, 0));
+        assert!(!can_reconnect(None, &PlayerState::Playing, 0));
+    }
+
+    #[test]
+    fn test_ended_early() {
+        let duration = Duration::from_secs(3600);
+        assert!(ended_early(Duration::from_secs(600), duration));
+        assert!(!ended_early(duration - EARLY_END_MARGIN, duration));
+        assert!(!ended_early(duration, duration));
+        // Players can report a position slightly past the end
+        assert!(!ended_early(duration + Duration::from_secs(1), duration));
+        // Nothing is known to be missing from an item of unknown length
+        assert!(!ended_early(Duration::from_secs(10), Duration::ZERO));
+    }
+
+    #[test]
+    fn test_inhibit_change() {
+        // Starting playback takes the inhibit once
+        assert_eq!(
+            inhibit_change(&PlayerState::Playing, false, false),
+            Some(true)
+        );
+        assert_eq!(inhibit_change(&PlayerState::Playing, false, true), None);
+
+        // Pausing keeps it only when configured to
+        assert_eq!(inhibit_change(&PlayerState::Paused, true, true), None);
+        assert_eq!(
+            inhibit_change(&PlayerState::Paused, false, true),
+            Some(false)
+        );
+        assert_eq!(
+            inhibit_change(&PlayerState::Paused, true, false),
+            Some(true)
+        );
+        assert_eq!(inhibit_change(&PlayerState::Paused, false, false), None);
+
+        // Anything else lets the screen sleep
+        for state in [
+            PlayerState::Idle,
+            PlayerState::Loading,
+            PlayerState::Stopped,
+            PlayerState::Error,
+        ] {
+            assert_eq!(inhibit_change(&state, true, true), Some(false));
+            assert_eq!(inhibit_change(&state, true, false), None);
         }
     }
+
+    #[test]
+    fn test_marker_skip_modes() {
+        let mut config = Config::default();
+        config
+            .playback
+            .set_skip_mode(ChapterType::Credits, MarkerSkipMode::Off);
+        config
+            .playback
+            .set_skip_mode(ChapterType::Intro, MarkerSkipMode::Auto);
+        let modes = marker_skip_modes(&config);
+        assert_eq!(modes.len(), 4);
+        assert_eq!(modes[&ChapterType::Credits], MarkerSkipMode::Off);
+        assert_eq!(modes[&ChapterType::Intro], MarkerSkipMode::Auto);
+        assert_eq!(
+            modes[&ChapterType::Recap],
+            config.playback.skip_mode(ChapterType::Recap)
+        );
+    }
+
+    #[test]
+    fn test_sync_offset_text() {
+        assert_eq!(sync_offset_text("Subtitle", 0), "Subtitle delay: 0 ms");
+        assert_eq!(sync_offset_text("Subtitle", 300), "Subtitle delay: +300 ms");
+        assert_eq!(sync_offset_text("Audio", -100), "Audio delay: -100 ms");
+    }
+
+    #[test]
+    fn test_stats_text_direct_play() {
+        let stats = PlaybackStats {
+            container: Some("matroska".to_string()),
+            video_codec: Some("hevc".to_string()),
+            audio_codec: Some("eac3".to_string()),
+            resolution: Some((3840, 2160)),
+            frame_rate: Some(23.976),
+            decoder: Some("hevc".to_string()),
+            hwdec: Some("vaapi".to_string()),
+            dropped_frames: Some(3),
+            delayed_frames: None,
+            cache_duration: Some(Duration::from_millis(12_340)),
+            buffer_percent: Some(100),
+            network_bytes_per_second: Some(2_500_000),
+        };
+
+        assert_eq!(
+            stats_text(&stats, Some(&stream(true)), false, None),
+            [
+                "Server    Direct play",
+                "Source    mkv · hevc · eac3",
+                "Container matroska",
+                "Video     hevc · 3840×2160 · 23.976 fps",
+                "Audio     eac3",
+                "Decoder   hevc (hardware: vaapi)",
+                "Frames    3 dropped",
+                "Cache     12.3 s · 100%",
+                "Network   20.0 Mbit/s",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn test_stats_text_transcoding() {
+        let status = TranscodeStatus {
+            video_transcoded: true,
+            audio_transcoded: false,
+            speed: Some(1.5),
+            hardware: Some("nvenc".to_string()),
+            reasons: vec!["Video codec not supported".to_string()],
+        };
+        let text = stats_text(
+            &PlaybackStats::default(),
+            Some(&stream(false)),
+            true,
+            Some(&status),
+        );
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Server    Transcoding video · 1.5× speed · nvenc");
+        assert_eq!(lines[1], "Reason    Video codec not supported");
+        // Stats the player couldn't tell are shown as unknown, not left out
+        assert!(lines.contains(&"Video     –"));
+        assert!(lines.contains(&"Frames    –"));
+        assert!(!text.contains("Decoder"));
+
+        // A direct stream isn't encoded, so transcode reasons don't apply
+        let text = stats_text(
+            &PlaybackStats::default(),
+            Some(&stream(false)),
+            false,
+            Some(&status),
+        );
+        assert!(text.starts_with("Server    Direct stream"));
+        assert!(!text.contains("Reason"));
+    }
 }
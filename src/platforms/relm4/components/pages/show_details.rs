@@ -49,6 +49,11 @@ pub enum ShowDetailsInput {
     LoadShow(MediaItemId),
     SelectSeason(u32),
     PlayEpisode(MediaItemId),
+    /// Add an episode to the play queue, right after the current item or at the end
+    QueueEpisode {
+        episode_id: MediaItemId,
+        play_next: bool,
+    },
     ToggleEpisodeWatched(usize),
     LoadEpisodes,
     ImageLoaded {
@@ -67,6 +72,10 @@ pub enum ShowDetailsOutput {
         media_id: MediaItemId,
         context: PlaylistContext,
     },
+    QueueMedia {
+        media_id: MediaItemId,
+        play_next: bool,
+    },
     NavigateBack,
 }
 
@@ -428,6 +437,17 @@ impl AsyncComponent for ShowDetailsPage {
                     });
                 }
             }
+            ShowDetailsInput::QueueEpisode {
+                episode_id,
+                play_next,
+            } => {
+                sender
+                    .output(ShowDetailsOutput::QueueMedia {
+                        media_id: episode_id,
+                        play_next,
+                    })
+                    .unwrap();
+            }
             ShowDetailsInput::PlayEpisode(episode_id) => {
                 // Build playlist context for the episode
                 let db_clone = self.db.clone();
@@ -806,11 +826,29 @@ fn create_episode_card(
     // Make the card clickable
     let click_controller = gtk::GestureClick::new();
     let episode_id = MediaItemId::new(&episode.id);
+    let sender_clone = sender.clone();
     click_controller.connect_released(move |_, _, _, _| {
-        sender.input(ShowDetailsInput::PlayEpisode(episode_id.clone()));
+        sender_clone.input(ShowDetailsInput::PlayEpisode(episode_id.clone()));
     });
     card.add_controller(click_controller);
 
+    // Right click offers the play queue actions
+    let queue_popover = create_queue_popover(MediaItemId::new(&episode.id), sender);
+    queue_popover.set_parent(&card);
+    {
+        // Popovers aren't dropped with their parent and must be unparented by hand
+        let queue_popover = queue_popover.clone();
+        card.connect_destroy(move |_| queue_popover.unparent());
+    }
+    let menu_controller = gtk::GestureClick::builder()
+        .button(gtk::gdk::BUTTON_SECONDARY)
+        .build();
+    menu_controller.connect_pressed(move |_, _, x, y| {
+        queue_popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        queue_popover.popup();
+    });
+    card.add_controller(menu_controller);
+
     // Add hover effects
     card.set_cursor_from_name(Some("pointer"));
 
@@ -920,3 +958,37 @@ fn create_episode_card(
 
     (card, picture)
 }
+
+fn create_queue_popover(
+    episode_id: MediaItemId,
+    sender: AsyncComponentSender<ShowDetailsPage>,
+) -> gtk::Popover {
+    let menu = gtk::Box::builder()
+        .orientation(gtk::Orientation::Vertical)
+        .css_classes(["menu"])
+        .build();
+    let popover = gtk::Popover::builder()
+        .child(&menu)
+        .has_arrow(false)
+        .build();
+
+    for (label, play_next) in [("Play Next", true), ("Add to Queue", false)] {
+        let button = gtk::Button::builder()
+            .label(label)
+            .css_classes(["flat"])
+            .build();
+        let popover = popover.clone();
+        let sender = sender.clone();
+        let episode_id = episode_id.clone();
+        button.connect_clicked(move |_| {
+            popover.popdown();
+            sender.input(ShowDetailsInput::QueueEpisode {
+                episode_id: episode_id.clone(),
+                play_next,
+            });
+        });
+        menu.append(&button);
+    }
+
+    popover
+}
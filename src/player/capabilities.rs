@@ -0,0 +1,503 @@
+use gstreamer as gst;
+use libmpv2::Mpv;
+use serde::Deserialize;
+use std::ffi::CString;
+use std::sync::{OnceLock, RwLock};
+use tracing::{debug, info, warn};
+
+use super::factory::PlayerBackend;
+use crate::config::Config;
+
+/// Codecs that are too expensive to software-decode above 1080p on older hardware
+const SOFTWARE_HEAVY_CODECS: &[&str] = &["hevc", "vp9", "av1"];
+
+/// Maximum height allowed for software-heavy codecs without a hardware decoder
+const SOFTWARE_DECODE_MAX_HEIGHT: u32 = 1080;
+
+/// Highest bitrate sent to a cast receiver, which streams over Wi-Fi
+const CAST_MAX_BITRATE: u64 = 20_000_000;
+
+/// GStreamer software decoders per codec, in server (ffmpeg) naming
+const GST_VIDEO_DECODERS: &[(&str, &[&str])] = &[
+    ("h264", &["avdec_h264", "openh264dec"]),
+    ("hevc", &["avdec_h265", "libde265dec"]),
+    ("vp8", &["vp8dec", "avdec_vp8"]),
+    ("vp9", &["vp9dec", "avdec_vp9"]),
+    ("av1", &["dav1ddec", "av1dec", "avdec_av1"]),
+    ("mpeg2video", &["avdec_mpeg2video", "mpeg2dec"]),
+    ("mpeg4", &["avdec_mpeg4"]),
+    ("vc1", &["avdec_vc1"]),
+];
+
+/// Hardware decoders per codec. The va and nvcodec plugins only register decoders the GPU
+/// actually supports, so this doubles as a system-wide probe for mpv's hwdec as well.
+const GST_HARDWARE_DECODERS: &[(&str, &[&str])] = &[
+    (
+        "h264",
+        &["vah264dec", "vaapih264dec", "nvh264dec", "v4l2slh264dec"],
+    ),
+    (
+        "hevc",
+        &["vah265dec", "vaapih265dec", "nvh265dec", "v4l2slh265dec"],
+    ),
+    (
+        "vp8",
+        &["vavp8dec", "vaapivp8dec", "nvvp8dec", "v4l2slvp8dec"],
+    ),
+    (
+        "vp9",
+        &["vavp9dec", "vaapivp9dec", "nvvp9dec", "v4l2slvp9dec"],
+    ),
+    ("av1", &["vaav1dec", "nvav1dec", "v4l2slav1dec"]),
+    (
+        "mpeg2video",
+        &["vampeg2dec", "vaapimpeg2dec", "nvmpeg2videodec"],
+    ),
+];
+
+const GST_AUDIO_DECODERS: &[(&str, &[&str])] = &[
+    ("aac", &["avdec_aac", "fdkaacdec", "faad"]),
+    ("mp3", &["mpg123audiodec", "avdec_mp3"]),
+    ("ac3", &["a52dec", "avdec_ac3"]),
+    ("eac3", &["avdec_eac3"]),
+    ("dts", &["dtsdec", "avdec_dca"]),
+    ("truehd", &["avdec_truehd"]),
+    ("flac", &["flacdec", "avdec_flac"]),
+    ("opus", &["opusdec", "avdec_opus"]),
+    ("vorbis", &["vorbisdec", "avdec_vorbis"]),
+    ("alac", &["avdec_alac"]),
+];
+
+const GST_DEMUXERS: &[(&str, &[&str])] = &[
+    ("matroskademux", &["mkv", "webm"]),
+    ("qtdemux", &["mp4", "m4v", "mov"]),
+    ("avidemux", &["avi"]),
+    ("tsdemux", &["ts", "mpegts"]),
+];
+
+/// Codecs mpv is checked for in its ffmpeg decoder list
+const MPV_VIDEO_CODECS: &[&str] = &[
+    "h264",
+    "hevc",
+    "vp8",
+    "vp9",
+    "av1",
+    "mpeg2video",
+    "mpeg4",
+    "vc1",
+];
+const MPV_AUDIO_CODECS: &[&str] = &[
+    "aac", "mp3", "ac3", "eac3", "dts", "truehd", "flac", "opus", "vorbis", "alac",
+];
+const MPV_CONTAINERS: &[&str] = &["mkv", "webm", "mp4", "m4v", "mov", "avi", "ts", "mpegts"];
+
+/// Audio codecs a server may transcode to, in order of preference
+const TRANSCODE_AUDIO_CODECS: &[&str] = &["aac", "ac3", "eac3", "mp3"];
+/// Surround codecs preferred over AAC when transcoding for a receiver, best first
+const PASSTHROUGH_TRANSCODE_CODECS: &[&str] = &["eac3", "ac3"];
+/// Channels a receiver decoding passed through audio can take, up to 7.1
+const PASSTHROUGH_MAX_AUDIO_CHANNELS: u32 = 8;
+
+static MPV_CAPABILITIES: OnceLock<DeviceCapabilities> = OnceLock::new();
+static GSTREAMER_CAPABILITIES: OnceLock<DeviceCapabilities> = OnceLock::new();
+static NULL_CAPABILITIES: OnceLock<DeviceCapabilities> = OnceLock::new();
+/// Settings the player last loaded, so stream requests don't read the config file again
+static PLAYER_CONFIG: RwLock<Option<Config>> = RwLock::new(None);
+
+/// What the local player can decode, used to negotiate direct play with media servers
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeviceCapabilities {
+    pub containers: Vec<String>,
+    pub video_codecs: Vec<String>,
+    pub audio_codecs: Vec<String>,
+    /// Video codecs with a hardware decoder available
+    pub hardware_video_codecs: Vec<String>,
+    pub max_audio_channels: u32,
+    pub max_streaming_bitrate: u64,
+    /// Audio codecs sent undecoded to a receiver, in server naming
+    pub passthrough_audio_codecs: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MpvDecoder {
+    codec: String,
+}
+
+impl DeviceCapabilities {
+    /// Hand over the config the player loaded, for `current` to work from
+    pub fn set_config(config: &Config) {
+        *PLAYER_CONFIG.write().unwrap() = Some(config.clone());
+    }
+
+    /// Capabilities for the config last handed to `set_config`, read from disk only if
+    /// the player hasn't loaded one yet. The first probe of a backend creates decoders,
+    /// so it runs on the blocking pool rather than the async caller's thread.
+    pub async fn current() -> DeviceCapabilities {
+        let config = PLAYER_CONFIG.read().unwrap().clone();
+        let config = match config {
+            Some(config) => config,
+            None => {
+                let config = Config::load().unwrap_or_default();
+                Self::set_config(&config);
+                config
+            }
+        };
+        tokio::task::spawn_blocking(move || Self::for_config(&config))
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Capability probe failed: {}", e);
+                Self::baseline()
+            })
+    }
+
+    /// Capabilities of the player backend selected in `config`, with its audio
+    /// passthrough applied. Probing happens once per backend and is cached for the
+    /// lifetime of the process.
+    pub fn for_config(config: &Config) -> DeviceCapabilities {
+        let probed = match PlayerBackend::from(config.playback.player_backend.as_str()) {
+            PlayerBackend::GStreamer => {
+                GSTREAMER_CAPABILITIES.get_or_init(|| Self::probe_gstreamer(config))
+            }
+            PlayerBackend::Mpv => MPV_CAPABILITIES.get_or_init(|| Self::probe_mpv(config)),
+            PlayerBackend::Null => NULL_CAPABILITIES.get_or_init(Self::baseline),
+        };
+        probed
+            .clone()
+            .with_passthrough(&config.playback.audio_passthrough)
+    }
+
+    /// Conservative capabilities used when probing fails
+    pub fn baseline() -> Self {
+        Self {
+            containers: to_strings(&["mp4", "m4v", "mkv", "webm"]),
+            video_codecs: to_strings(&["h264", "hevc", "vp8", "vp9", "av1"]),
+            audio_codecs: to_strings(&["aac", "mp3", "opus", "flac", "vorbis"]),
+            hardware_video_codecs: Vec::new(),
+            max_audio_channels: 6,
+            max_streaming_bitrate: 120_000_000,
+            passthrough_audio_codecs: Vec::new(),
+        }
+    }
+
+    /// What any cast receiver plays: H.264 or VP8 in MP4, WebM or MPEG-TS with stereo
+    /// audio, as the first Chromecast does. Receivers can't be probed, so servers
+    /// transcode HEVC and surround audio for all of them.
+    pub fn cast_receiver() -> Self {
+        Self {
+            containers: to_strings(&["mp4", "m4v", "webm", "mpegts"]),
+            video_codecs: to_strings(&["h264", "vp8"]),
+            audio_codecs: to_strings(&["aac", "mp3", "opus", "flac", "vorbis"]),
+            hardware_video_codecs: to_strings(&["h264", "vp8"]),
+            max_audio_channels: 2,
+            max_streaming_bitrate: CAST_MAX_BITRATE,
+            passthrough_audio_codecs: Vec::new(),
+        }
+    }
+
+    /// Cap the bitrate servers stream at, e.g. to a quality picked by the user
+    pub fn with_max_bitrate(mut self, bitrate: u64) -> Self {
+        if bitrate > 0 {
+            self.max_streaming_bitrate = self.max_streaming_bitrate.min(bitrate);
+        }
+        self
+    }
+
+    /// Count codecs passed through to a receiver as playable, whether or not they can
+    /// be decoded here, so servers stop transcoding surround audio
+    pub fn with_passthrough(mut self, codecs: &[String]) -> Self {
+        for codec in codecs {
+            // Servers call DTS-HD dts. Only its core is counted as playable; the
+            // server profiles still transcode the HD extensions.
+            let codec = match codec.as_str() {
+                "dts-hd" => "dts",
+                codec => codec,
+            };
+            if !self.passthrough_audio_codecs.iter().any(|c| c == codec) {
+                self.passthrough_audio_codecs.push(codec.to_string());
+            }
+            if !self.supports_audio_codec(codec) {
+                self.audio_codecs.push(codec.to_string());
+            }
+        }
+        if !self.passthrough_audio_codecs.is_empty() {
+            self.max_audio_channels = self.max_audio_channels.max(PASSTHROUGH_MAX_AUDIO_CHANNELS);
+        }
+        self
+    }
+
+    /// Audio codecs a server may transcode to, surround codecs the receiver takes first
+    pub fn transcode_audio_codecs(&self) -> Vec<&'static str> {
+        let passthrough = PASSTHROUGH_TRANSCODE_CODECS
+            .iter()
+            .filter(|c| self.passthrough_audio_codecs.iter().any(|p| p == *c));
+        let mut codecs: Vec<&'static str> = passthrough.copied().collect();
+        for codec in TRANSCODE_AUDIO_CODECS {
+            if self.supports_audio_codec(codec) && !codecs.contains(codec) {
+                codecs.push(codec);
+            }
+        }
+        codecs
+    }
+
+    pub fn supports_video_codec(&self, codec: &str) -> bool {
+        self.video_codecs
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(codec))
+    }
+
+    pub fn supports_audio_codec(&self, codec: &str) -> bool {
+        self.audio_codecs
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(codec))
+    }
+
+    /// Height limit for a video codec, if it can only be decoded in software
+    pub fn max_height_for(&self, codec: &str) -> Option<u32> {
+        let codec = codec.to_ascii_lowercase();
+        let heavy = SOFTWARE_HEAVY_CODECS.contains(&codec.as_str());
+        let hardware = self
+            .hardware_video_codecs
+            .iter()
+            .any(|c| c.eq_ignore_ascii_case(codec));
+        (heavy && !hardware).then_some(SOFTWARE_DECODE_MAX_HEIGHT)
+    }
+
+    /// Video codecs that are supported but limited to `SOFTWARE_DECODE_MAX_HEIGHT`
+    pub fn height_limited_codecs(&self) -> Vec<(&str, u32)> {
+        self.video_codecs
+            .iter()
+            .filter_map(|c| self.max_height_for(c).map(|h| (c.as_str(), h)))
+            .collect()
+    }
+
+    fn probe_gstreamer(config: &Config) -> Self {
+        info!("Probing GStreamer decoder capabilities");
+
+        if let Err(e) = gst::init() {
+            warn!("Failed to initialize GStreamer for probing: {}", e);
+            return Self::baseline();
+        }
+
+        let has_element = |name: &str| gst::ElementFactory::find(name).is_some();
+        let probe = |table: &[(&str, &[&str])]| -> Vec<String> {
+            table
+                .iter()
+                .filter(|(_, elements)| elements.iter().any(|e| has_element(e)))
+                .map(|(codec, _)| codec.to_string())
+                .collect()
+        };
+
+        let hardware_video_codecs = if config.playback.hardware_acceleration {
+            probe(GST_HARDWARE_DECODERS)
+        } else {
+            Vec::new()
+        };
+
+        let mut video_codecs = probe(GST_VIDEO_DECODERS);
+        for codec in &hardware_video_codecs {
+            if !video_codecs.contains(codec) {
+                video_codecs.push(codec.clone());
+            }
+        }
+
+        let containers: Vec<String> = GST_DEMUXERS
+            .iter()
+            .filter(|(demuxer, _)| has_element(demuxer))
+            .flat_map(|(_, containers)| containers.iter().map(|c| c.to_string()))
+            .collect();
+
+        let capabilities = Self {
+            containers,
+            video_codecs,
+            audio_codecs: probe(GST_AUDIO_DECODERS),
+            hardware_video_codecs,
+            max_audio_channels: 8,
+            max_streaming_bitrate: 120_000_000,
+            passthrough_audio_codecs: Vec::new(),
+        };
+
+        debug!("GStreamer capabilities: {:?}", capabilities);
+        capabilities.or_baseline()
+    }
+
+    fn probe_mpv(config: &Config) -> Self {
+        info!("Probing MPV decoder capabilities");
+
+        // MPV requires LC_NUMERIC to be set to "C"
+        unsafe {
+            let c_locale = CString::new("C").unwrap();
+            libc::setlocale(libc::LC_NUMERIC, c_locale.as_ptr());
+        }
+
+        let decoders: Vec<MpvDecoder> = match Mpv::new()
+            .map_err(|e| anyhow::anyhow!("Failed to create MPV instance: {:?}", e))
+            .and_then(|mpv| {
+                mpv.get_property::<String>("decoder-list")
+                    .map_err(|e| anyhow::anyhow!("Failed to get decoder-list: {:?}", e))
+            })
+            .and_then(|list| Ok(serde_json::from_str(&list)?))
+        {
+            Ok(decoders) => decoders,
+            Err(e) => {
+                warn!("Failed to probe MPV decoders: {}", e);
+                return Self::baseline();
+            }
+        };
+
+        let has_decoder = |codec: &str| decoders.iter().any(|d| d.codec == codec);
+
+        // mpv uses the same VA-API/NVDEC drivers, so the GStreamer hardware probe applies
+        let hardware_video_codecs = if config.playback.hardware_acceleration && gst::init().is_ok()
+        {
+            GST_HARDWARE_DECODERS
+                .iter()
+                .filter(|(_, elements)| {
+                    elements
+                        .iter()
+                        .any(|e| gst::ElementFactory::find(e).is_some())
+                })
+                .map(|(codec, _)| codec.to_string())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let capabilities = Self {
+            containers: to_strings(MPV_CONTAINERS),
+            video_codecs: MPV_VIDEO_CODECS
+                .iter()
+                .filter(|c| has_decoder(c))
+                .map(|c| c.to_string())
+                .collect(),
+            audio_codecs: MPV_AUDIO_CODECS
+                .iter()
+                .filter(|c| has_decoder(c))
+                .map(|c| c.to_string())
+                .collect(),
+            hardware_video_codecs,
+            max_audio_channels: 8,
+            max_streaming_bitrate: 120_000_000,
+            passthrough_audio_codecs: Vec::new(),
+        };
+
+        debug!("MPV capabilities: {:?}", capabilities);
+        capabilities.or_baseline()
+    }
+
+    /// Fall back to the baseline if probing found nothing usable
+    fn or_baseline(self) -> Self {
+        if self.containers.is_empty() || self.video_codecs.is_empty() {
+            warn!("Decoder probe found no usable codecs, using baseline capabilities");
+            Self::baseline()
+        } else {
+            self
+        }
+    }
+}
+
+fn to_strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_software_heavy_codecs_are_height_limited() {
+        let caps = DeviceCapabilities::baseline();
+        assert_eq!(caps.max_height_for("av1"), Some(SOFTWARE_DECODE_MAX_HEIGHT));
+        assert_eq!(
+            caps.max_height_for("hevc"),
+            Some(SOFTWARE_DECODE_MAX_HEIGHT)
+        );
+        assert_eq!(caps.max_height_for("h264"), None);
+        assert_eq!(
+            caps.max_height_for("HEVC"),
+            Some(SOFTWARE_DECODE_MAX_HEIGHT)
+        );
+    }
+
+    #[test]
+    fn test_hardware_codecs_are_not_height_limited() {
+        let mut caps = DeviceCapabilities::baseline();
+        caps.hardware_video_codecs = vec!["av1".to_string()];
+        assert_eq!(caps.max_height_for("av1"), None);
+        assert!(
+            caps.height_limited_codecs()
+                .iter()
+                .all(|(c, _)| *c != "av1")
+        );
+    }
+
+    #[test]
+    fn test_or_baseline_replaces_empty_probe() {
+        let caps = DeviceCapabilities {
+            containers: Vec::new(),
+            video_codecs: Vec::new(),
+            audio_codecs: Vec::new(),
+            hardware_video_codecs: Vec::new(),
+            max_audio_channels: 2,
+            max_streaming_bitrate: 0,
+            passthrough_audio_codecs: Vec::new(),
+        };
+        assert_eq!(caps.or_baseline(), DeviceCapabilities::baseline());
+    }
+
+    #[test]
+    fn test_for_config_applies_passthrough() {
+        let mut config = Config::default();
+        config.playback.player_backend = "null".to_string();
+        assert_eq!(
+            DeviceCapabilities::for_config(&config),
+            DeviceCapabilities::baseline()
+        );
+
+        config.playback.audio_passthrough = vec!["eac3".to_string()];
+        let caps = DeviceCapabilities::for_config(&config);
+        assert_eq!(caps.passthrough_audio_codecs, vec!["eac3"]);
+        assert!(caps.supports_audio_codec("eac3"));
+    }
+
+    #[test]
+    fn test_supports_codec_is_case_insensitive() {
+        let caps = DeviceCapabilities::baseline();
+        assert!(caps.supports_video_codec("H264"));
+        assert!(caps.supports_audio_codec("AAC"));
+        assert!(!caps.supports_audio_codec("dts"));
+    }
+
+    #[test]
+    fn test_passthrough_adds_codecs_and_prefers_surround_transcodes() {
+        let caps = DeviceCapabilities::baseline();
+        assert_eq!(caps.transcode_audio_codecs(), vec!["aac", "mp3"]);
+
+        let caps = caps.with_passthrough(&["ac3".to_string(), "dts-hd".to_string()]);
+        assert!(caps.supports_audio_codec("ac3"));
+        assert!(caps.supports_audio_codec("dts"));
+        assert_eq!(caps.passthrough_audio_codecs, vec!["ac3", "dts"]);
+        assert_eq!(caps.max_audio_channels, PASSTHROUGH_MAX_AUDIO_CHANNELS);
+        assert_eq!(caps.transcode_audio_codecs(), vec!["ac3", "aac", "mp3"]);
+    }
+
+    #[test]
+    fn test_cast_receiver_needs_hevc_transcoded() {
+        let caps = DeviceCapabilities::cast_receiver();
+        assert!(caps.supports_video_codec("h264"));
+        assert!(!caps.supports_video_codec("hevc"));
+        assert!(!caps.containers.iter().any(|c| c == "mkv"));
+        assert_eq!(caps.max_audio_channels, 2);
+
+        assert_eq!(
+            caps.clone()
+                .with_max_bitrate(4_000_000)
+                .max_streaming_bitrate,
+            4_000_000
+        );
+        assert_eq!(caps.clone().with_max_bitrate(0), caps);
+        assert_eq!(
+            caps.with_max_bitrate(u64::MAX).max_streaming_bitrate,
+            CAST_MAX_BITRATE
+        );
+    }
+}
@@ -0,0 +1,611 @@
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use super::{CastMedia, CastReceiver, CastStatus};
+use crate::player::PlayerState;
+
+/// Google's Default Media Receiver, which plays any URL it is given
+const DEFAULT_MEDIA_RECEIVER: &str = "CC1AD845";
+const SENDER_ID: &str = "sender-0";
+const RECEIVER_ID: &str = "receiver-0";
+
+const NS_CONNECTION: &str = "urn:x-cast:com.google.cast.tp.connection";
+const NS_HEARTBEAT: &str = "urn:x-cast:com.google.cast.tp.heartbeat";
+const NS_RECEIVER: &str = "urn:x-cast:com.google.cast.receiver";
+const NS_MEDIA: &str = "urn:x-cast:com.google.cast.media";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Generous because receivers only answer a load once they have started buffering
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(20);
+/// Receivers drop connections that miss a few of these
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+type Pending = Arc<Mutex<HashMap<u64, oneshot::Sender<Value>>>>;
+
+/// One CASTV2 `CastMessage`. Only string payloads are used by the namespaces we speak.
+#[derive(Debug, Clone, PartialEq)]
+struct CastMessage {
+    source: String,
+    destination: String,
+    namespace: String,
+    payload: String,
+}
+
+impl CastMessage {
+    fn new(destination: &str, namespace: &str, payload: &Value) -> Self {
+        Self {
+            source: SENDER_ID.to_string(),
+            destination: destination.to_string(),
+            namespace: namespace.to_string(),
+            payload: payload.to_string(),
+        }
+    }
+
+    /// Protobuf encoding, written by hand since it is a single flat message
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(self.payload.len() + 128);
+        // protocol_version = CASTV2_1_0
+        out.extend_from_slice(&[0x08, 0x00]);
+        encode_string(&mut out, 2, &self.source);
+        encode_string(&mut out, 3, &self.destination);
+        encode_string(&mut out, 4, &self.namespace);
+        // payload_type = STRING
+        out.extend_from_slice(&[0x28, 0x00]);
+        encode_string(&mut out, 6, &self.payload);
+        out
+    }
+
+    fn decode(mut bytes: &[u8]) -> Result<Self> {
+        let mut message = Self {
+            source: String::new(),
+            destination: String::new(),
+            namespace: String::new(),
+            payload: String::new(),
+        };
+        while !bytes.is_empty() {
+            let key = read_varint(&mut bytes)?;
+            match key & 0x7 {
+                0 => {
+                    read_varint(&mut bytes)?;
+                }
+                2 => {
+                    let length = read_varint(&mut bytes)? as usize;
+                    if length > bytes.len() {
+                        return Err(anyhow!("Truncated cast message"));
+                    }
+                    let (value, rest) = bytes.split_at(length);
+                    bytes = rest;
+                    let field = match key >> 3 {
+                        2 => &mut message.source,
+                        3 => &mut message.destination,
+                        4 => &mut message.namespace,
+                        6 => &mut message.payload,
+                        // Binary payloads
+                        _ => continue,
+                    };
+                    *field = String::from_utf8(value.to_vec())?;
+                }
+                wire_type => return Err(anyhow!("Unexpected protobuf wire type {}", wire_type)),
+            }
+        }
+        Ok(message)
+    }
+}
+
+fn encode_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn encode_string(out: &mut Vec<u8>, field: u64, value: &str) {
+    encode_varint(out, (field << 3) | 2);
+    encode_varint(out, value.len() as u64);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn read_varint(bytes: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes
+            .split_first()
+            .ok_or_else(|| anyhow!("Truncated cast message"))?;
+        *bytes = rest;
+        value |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("Malformed varint in cast message"))
+}
+
+/// Messages are framed by their length as a big-endian u32
+async fn read_message(reader: &mut (impl AsyncRead + Unpin)) -> Result<CastMessage> {
+    let length = reader.read_u32().await? as usize;
+    if length > MAX_MESSAGE_SIZE {
+        return Err(anyhow!("Cast message too large"));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).await?;
+    CastMessage::decode(&body)
+}
+
+async fn write_message(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &CastMessage,
+) -> Result<()> {
+    let body = message.encode();
+    let mut frame = Vec::with_capacity(body.len() + 4);
+    frame.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    frame.extend_from_slice(&body);
+    writer.write_all(&frame).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// A Google Cast device running the Default Media Receiver on our behalf
+pub struct ChromecastReceiver {
+    outgoing: mpsc::UnboundedSender<CastMessage>,
+    pending: Pending,
+    next_request: AtomicU64,
+    // Where media messages go once the receiver app is running
+    transport_id: String,
+    session_id: String,
+    media_session_id: Mutex<Option<u64>>,
+    // Receivers leave the duration out of most status updates
+    duration: Mutex<Option<Duration>>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ChromecastReceiver {
+    pub async fn connect(address: SocketAddr) -> Result<Self> {
+        let tcp = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address))
+            .await
+            .context("Timed out connecting to the Chromecast")??;
+        // Receivers present certificates signed by Google's device CA, not a web one
+        let connector = tokio_native_tls::native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(true)
+            .danger_accept_invalid_hostnames(true)
+            .build()?;
+        let stream = tokio_native_tls::TlsConnector::from(connector)
+            .connect(&address.ip().to_string(), tcp)
+            .await
+            .context("TLS handshake with the Chromecast failed")?;
+        Self::from_stream(stream).await
+    }
+
+    async fn from_stream<S>(stream: S) -> Result<Self>
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let (outgoing, mut queue) = mpsc::unbounded_channel::<CastMessage>();
+        let pending = Pending::default();
+        let mut tasks = Vec::new();
+
+        tasks.push(tokio::spawn(async move {
+            while let Some(message) = queue.recv().await {
+                if let Err(e) = write_message(&mut writer, &message).await {
+                    debug!("Failed to write to the Chromecast: {}", e);
+                    break;
+                }
+            }
+        }));
+
+        let replies = outgoing.clone();
+        let waiting = pending.clone();
+        tasks.push(tokio::spawn(async move {
+            loop {
+                let message = match read_message(&mut reader).await {
+                    Ok(message) => message,
+                    Err(e) => {
+                        debug!("Chromecast connection closed: {}", e);
+                        break;
+                    }
+                };
+                let Ok(payload) = serde_json::from_str::<Value>(&message.payload) else {
+                    continue;
+                };
+                if message.namespace == NS_HEARTBEAT && payload["type"] == "PING" {
+                    let _ = replies.send(CastMessage::new(
+                        &message.source,
+                        NS_HEARTBEAT,
+                        &json!({ "type": "PONG" }),
+                    ));
+                    continue;
+                }
+                // Unsolicited status updates carry request id 0
+                if let Some(id) = payload["requestId"].as_u64().filter(|id| *id != 0)
+                    && let Some(waiter) = waiting.lock().unwrap().remove(&id)
+                {
+                    let _ = waiter.send(payload);
+                }
+            }
+            // Whatever is still waiting will never get an answer
+            waiting.lock().unwrap().clear();
+        }));
+
+        let heartbeat = outgoing.clone();
+        tasks.push(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(HEARTBEAT_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let ping = CastMessage::new(RECEIVER_ID, NS_HEARTBEAT, &json!({ "type": "PING" }));
+                if heartbeat.send(ping).is_err() {
+                    break;
+                }
+            }
+        }));
+
+        let mut receiver = Self {
+            outgoing,
+            pending,
+            next_request: AtomicU64::new(1),
+            transport_id: String::new(),
+            session_id: String::new(),
+            media_session_id: Mutex::new(None),
+            duration: Mutex::new(None),
+            tasks,
+        };
+
+        receiver.send(RECEIVER_ID, NS_CONNECTION, &json!({ "type": "CONNECT" }))?;
+        let status = receiver
+            .request(
+                RECEIVER_ID,
+                NS_RECEIVER,
+                json!({ "type": "LAUNCH", "appId": DEFAULT_MEDIA_RECEIVER }),
+            )
+            .await?;
+        let app = status["status"]["applications"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|app| app["appId"] == DEFAULT_MEDIA_RECEIVER)
+            .ok_or_else(|| anyhow!("The Chromecast did not start its media receiver"))?;
+        receiver.transport_id = app["transportId"]
+            .as_str()
+            .ok_or_else(|| anyhow!("The Chromecast media receiver has no transport"))?
+            .to_string();
+        receiver.session_id = app["sessionId"].as_str().unwrap_or_default().to_string();
+        receiver.send(
+            &receiver.transport_id,
+            NS_CONNECTION,
+            &json!({ "type": "CONNECT" }),
+        )?;
+
+        Ok(receiver)
+    }
+
+    fn send(&self, destination: &str, namespace: &str, payload: &Value) -> Result<()> {
+        self.outgoing
+            .send(CastMessage::new(destination, namespace, payload))
+            .map_err(|_| anyhow!("Lost the connection to the Chromecast"))
+    }
+
+    /// Send `payload` and wait for the message answering it
+    async fn request(
+        &self,
+        destination: &str,
+        namespace: &str,
+        mut payload: Value,
+    ) -> Result<Value> {
+        let id = self.next_request.fetch_add(1, Ordering::Relaxed);
+        payload["requestId"] = json!(id);
+        let (sender, answer) = oneshot::channel();
+        self.pending.lock().unwrap().insert(id, sender);
+        self.send(destination, namespace, &payload)?;
+
+        let answer = tokio::time::timeout(REQUEST_TIMEOUT, answer).await;
+        self.pending.lock().unwrap().remove(&id);
+        let answer = answer
+            .map_err(|_| anyhow!("The Chromecast did not answer {}", payload["type"]))?
+            .map_err(|_| anyhow!("Lost the connection to the Chromecast"))?;
+
+        match answer["type"].as_str() {
+            Some(
+                "LAUNCH_ERROR"
+                | "LOAD_FAILED"
+                | "LOAD_CANCELLED"
+                | "INVALID_REQUEST"
+                | "INVALID_PLAYER_STATE",
+            ) => Err(anyhow!(
+                "The Chromecast refused {}: {}",
+                payload["type"],
+                answer
+                    .get("reason")
+                    .or(answer.get("type"))
+                    .unwrap_or(&Value::Null)
+            )),
+            _ => Ok(answer),
+        }
+    }
+
+    /// Send a command for the loaded media
+    async fn media_command(&self, mut payload: Value) -> Result<()> {
+        let session = self
+            .media_session_id
+            .lock()
+            .unwrap()
+            .ok_or_else(|| anyhow!("Nothing is playing on the Chromecast"))?;
+        payload["mediaSessionId"] = json!(session);
+        let answer = self.request(&self.transport_id, NS_MEDIA, payload).await?;
+        self.remember(&answer);
+        Ok(())
+    }
+
+    /// Read a MEDIA_STATUS message, keeping what later ones may leave out
+    fn remember(&self, answer: &Value) -> Option<CastStatus> {
+        let status = answer["status"].as_array()?.first()?;
+        if let Some(id) = status["mediaSessionId"].as_u64() {
+            *self.media_session_id.lock().unwrap() = Some(id);
+        }
+        if let Some(duration) = status["media"]["duration"]
+            .as_f64()
+            .filter(|duration| *duration > 0.0)
+        {
+            *self.duration.lock().unwrap() = Some(Duration::from_secs_f64(duration));
+        }
+
+        let state = match status["playerState"].as_str() {
+            Some("PLAYING") => PlayerState::Playing,
+            Some("PAUSED") => PlayerState::Paused,
+            Some("BUFFERING") => PlayerState::Loading,
+            _ if status["idleReason"] == "ERROR" => PlayerState::Error,
+            _ => PlayerState::Stopped,
+        };
+        Some(CastStatus {
+            state,
+            position: Duration::from_secs_f64(
+                status["currentTime"].as_f64().unwrap_or(0.0).max(0.0),
+            ),
+            duration: *self.duration.lock().unwrap(),
+        })
+    }
+}
+
+impl Drop for ChromecastReceiver {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl CastReceiver for ChromecastReceiver {
+    async fn load(&self, media: &CastMedia) -> Result<()> {
+        *self.duration.lock().unwrap() = None;
+        let answer = self
+            .request(
+                &self.transport_id,
+                NS_MEDIA,
+                json!({
+                    "type": "LOAD",
+                    "autoplay": true,
+                    "currentTime": media.start.as_secs_f64(),
+                    "media": {
+                        "contentId": media.url,
+                        "contentType": media.content_type,
+                        "streamType": "BUFFERED",
+                        "metadata": { "metadataType": 0, "title": media.title },
+                    },
+                }),
+            )
+            .await?;
+        self.remember(&answer)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("The Chromecast did not load the stream"))
+    }
+
+    async fn play(&self) -> Result<()> {
+        self.media_command(json!({ "type": "PLAY" })).await
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.media_command(json!({ "type": "PAUSE" })).await
+    }
+
+    async fn seek(&self, position: Duration) -> Result<()> {
+        self.media_command(json!({ "type": "SEEK", "currentTime": position.as_secs_f64() }))
+            .await
+    }
+
+    async fn set_volume(&self, volume: f64) -> Result<()> {
+        self.request(
+            RECEIVER_ID,
+            NS_RECEIVER,
+            json!({ "type": "SET_VOLUME", "volume": { "level": volume.clamp(0.0, 1.0) } }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn set_muted(&self, muted: bool) -> Result<()> {
+        self.request(
+            RECEIVER_ID,
+            NS_RECEIVER,
+            json!({ "type": "SET_VOLUME", "volume": { "muted": muted } }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        // Closing the receiver app returns the TV to its idle screen
+        self.request(
+            RECEIVER_ID,
+            NS_RECEIVER,
+            json!({ "type": "STOP", "sessionId": self.session_id }),
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn status(&self) -> Result<CastStatus> {
+        let answer = self
+            .request(
+                &self.transport_id,
+                NS_MEDIA,
+                json!({ "type": "GET_STATUS" }),
+            )
+            .await?;
+        Ok(self.remember(&answer).unwrap_or(CastStatus {
+            state: PlayerState::Stopped,
+            position: Duration::ZERO,
+            duration: *self.duration.lock().unwrap(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    /// Answers like a Chromecast running the Default Media Receiver, logging what it receives
+    async fn fake_receiver(stream: DuplexStream, log: mpsc::UnboundedSender<CastMessage>) {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let ping = CastMessage {
+            source: RECEIVER_ID.to_string(),
+            destination: SENDER_ID.to_string(),
+            namespace: NS_HEARTBEAT.to_string(),
+            payload: json!({ "type": "PING" }).to_string(),
+        };
+        write_message(&mut writer, &ping).await.unwrap();
+
+        while let Ok(message) = read_message(&mut reader).await {
+            let request: Value = serde_json::from_str(&message.payload).unwrap();
+            let id = request["requestId"].clone();
+            let answer = match request["type"].as_str().unwrap() {
+                "LAUNCH" => Some(json!({
+                    "type": "RECEIVER_STATUS",
+                    "requestId": id,
+                    "status": { "applications": [{
+                        "appId": DEFAULT_MEDIA_RECEIVER,
+                        "transportId": "web-1",
+                        "sessionId": "session-1",
+                    }] },
+                })),
+                "LOAD" => Some(json!({
+                    "type": "MEDIA_STATUS",
+                    "requestId": id,
+                    "status": [{
+                        "mediaSessionId": 7,
+                        "playerState": "BUFFERING",
+                        "currentTime": request["currentTime"],
+                        "media": { "duration": 120.0 },
+                    }],
+                })),
+                "PAUSE" | "GET_STATUS" => Some(json!({
+                    "type": "MEDIA_STATUS",
+                    "requestId": id,
+                    "status": [{ "mediaSessionId": 7, "playerState": "PAUSED", "currentTime": 12.5 }],
+                })),
+                _ => None,
+            };
+            if let Some(answer) = answer {
+                let reply = CastMessage {
+                    source: message.destination.clone(),
+                    destination: message.source.clone(),
+                    namespace: message.namespace.clone(),
+                    payload: answer.to_string(),
+                };
+                write_message(&mut writer, &reply).await.unwrap();
+            }
+            let _ = log.send(message);
+        }
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let message = CastMessage::new(RECEIVER_ID, NS_CONNECTION, &json!({ "type": "CONNECT" }));
+        let encoded = message.encode();
+        assert_eq!(&encoded[..4], &[0x08, 0x00, 0x12, 0x08]);
+        assert_eq!(&encoded[4..12], b"sender-0");
+        assert_eq!(CastMessage::decode(&encoded).unwrap(), message);
+
+        // Payloads over 127 bytes need a two byte length
+        let long = CastMessage::new(RECEIVER_ID, NS_MEDIA, &json!({ "title": "x".repeat(200) }));
+        assert_eq!(CastMessage::decode(&long.encode()).unwrap(), long);
+        assert!(CastMessage::decode(&long.encode()[..50]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_and_control() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (log, mut received) = mpsc::unbounded_channel();
+        tokio::spawn(fake_receiver(server, log));
+
+        let receiver = ChromecastReceiver::from_stream(client).await.unwrap();
+        receiver
+            .load(&CastMedia {
+                url: "http://server/video.mkv".to_string(),
+                title: "Movie".to_string(),
+                content_type: "video/x-matroska".to_string(),
+                start: Duration::from_secs(30),
+            })
+            .await
+            .unwrap();
+        receiver.pause().await.unwrap();
+        assert_eq!(
+            receiver.status().await.unwrap(),
+            CastStatus {
+                state: PlayerState::Paused,
+                position: Duration::from_millis(12500),
+                duration: Some(Duration::from_secs(120)),
+            }
+        );
+
+        let mut messages = Vec::new();
+        while let Ok(message) = received.try_recv() {
+            messages.push((
+                message.destination,
+                serde_json::from_str::<Value>(&message.payload).unwrap(),
+            ));
+        }
+        // The pong may go out before or after the first connect
+        let pong = messages
+            .iter()
+            .position(|(_, payload)| payload["type"] == "PONG")
+            .expect("ping was answered");
+        messages.remove(pong);
+        let kinds: Vec<_> = messages
+            .iter()
+            .map(|(destination, payload)| {
+                format!("{} {}", destination, payload["type"].as_str().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                "receiver-0 CONNECT",
+                "receiver-0 LAUNCH",
+                "web-1 CONNECT",
+                "web-1 LOAD",
+                "web-1 PAUSE",
+                "web-1 GET_STATUS",
+            ]
+        );
+        assert_eq!(
+            messages[3].1["media"]["contentId"],
+            "http://server/video.mkv"
+        );
+        assert_eq!(messages[3].1["currentTime"], 30.0);
+        assert_eq!(messages[4].1["mediaSessionId"], 7);
+    }
+}
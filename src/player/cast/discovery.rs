@@ -0,0 +1,461 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tracing::{debug, warn};
+
+use super::dlna::{xml_blocks, xml_value};
+use super::{CastDevice, CastTarget};
+
+const MDNS_ADDRESS: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)), 5353);
+const SSDP_ADDRESS: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)), 1900);
+const CAST_SERVICE: &str = "_googlecast._tcp.local";
+const MEDIA_RENDERER: &str = "urn:schemas-upnp-org:device:MediaRenderer:1";
+/// Used when a Chromecast answers without saying which port it listens on
+const CHROMECAST_PORT: u16 = 8009;
+const DESCRIPTION_TIMEOUT: Duration = Duration::from_secs(3);
+
+const DNS_A: u16 = 1;
+const DNS_PTR: u16 = 12;
+const DNS_TXT: u16 = 16;
+const DNS_SRV: u16 = 33;
+
+/// Look for Chromecasts and DLNA renderers on the local network for `timeout`
+pub async fn discover(timeout: Duration) -> Vec<CastDevice> {
+    let (chromecasts, renderers) =
+        tokio::join!(discover_chromecasts(timeout), discover_renderers(timeout));
+
+    let mut devices = Vec::new();
+    for (kind, found) in [("Chromecast", chromecasts), ("DLNA", renderers)] {
+        match found {
+            Ok(found) => devices.extend(found),
+            Err(e) => warn!("{} discovery failed: {}", kind, e),
+        }
+    }
+    devices.sort_by_key(|device| device.name.to_lowercase());
+    devices
+}
+
+/// One-shot mDNS query: answers come straight back to our port rather than to the group
+async fn discover_chromecasts(timeout: Duration) -> Result<Vec<CastDevice>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    socket
+        .send_to(&mdns_query(CAST_SERVICE), MDNS_ADDRESS)
+        .await?;
+
+    let mut devices: Vec<CastDevice> = Vec::new();
+    let mut buffer = vec![0; 9000];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await
+    {
+        let (length, from) = received?;
+        match parse_mdns_response(&buffer[..length], from.ip()) {
+            Ok(found) => {
+                for device in found {
+                    if !devices.contains(&device) {
+                        devices.push(device);
+                    }
+                }
+            }
+            Err(e) => debug!("Ignoring unreadable mDNS answer from {}: {}", from, e),
+        }
+    }
+    Ok(devices)
+}
+
+async fn discover_renderers(timeout: Duration) -> Result<Vec<CastDevice>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await?;
+    let search = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}\r\nMAN: \"ssdp:discover\"\r\nMX: {}\r\nST: {}\r\n\r\n",
+        SSDP_ADDRESS,
+        timeout.as_secs().clamp(1, 5),
+        MEDIA_RENDERER
+    );
+    socket.send_to(search.as_bytes(), SSDP_ADDRESS).await?;
+
+    let mut locations = Vec::new();
+    let mut buffer = vec![0; 4096];
+    let deadline = tokio::time::Instant::now() + timeout;
+    while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buffer)).await
+    {
+        let (length, _) = received?;
+        if let Some(location) = ssdp_location(&String::from_utf8_lossy(&buffer[..length]))
+            && !locations.contains(&location)
+        {
+            locations.push(location);
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(DESCRIPTION_TIMEOUT)
+        .build()?;
+    let described =
+        futures::future::join_all(locations.iter().map(|location| describe(&client, location)))
+            .await;
+    Ok(described
+        .into_iter()
+        .zip(&locations)
+        .filter_map(|(device, location)| {
+            device
+                .inspect_err(|e| debug!("Skipping renderer at {}: {}", location, e))
+                .ok()
+                .flatten()
+        })
+        .collect())
+}
+
+fn mdns_query(service: &str) -> Vec<u8> {
+    // No id or flags, a single question
+    let mut query = vec![0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in service.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&DNS_PTR.to_be_bytes());
+    // Class IN
+    query.extend_from_slice(&[0, 1]);
+    query
+}
+
+struct DnsReader<'a> {
+    packet: &'a [u8],
+    offset: usize,
+}
+
+impl DnsReader<'_> {
+    fn bytes(&mut self, length: usize) -> Result<&[u8]> {
+        let bytes = self
+            .packet
+            .get(self.offset..self.offset + length)
+            .ok_or_else(|| anyhow!("Truncated DNS packet"))?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// A possibly compressed name, as dotted labels
+    fn name(&mut self) -> Result<String> {
+        let mut labels = Vec::new();
+        let mut offset = self.offset;
+        let mut jumped = false;
+        // Bounds the number of pointers followed, so loops end
+        for _ in 0..128 {
+            let length = *self
+                .packet
+                .get(offset)
+                .ok_or_else(|| anyhow!("Truncated DNS name"))? as usize;
+            if length & 0xC0 == 0xC0 {
+                let low = *self
+                    .packet
+                    .get(offset + 1)
+                    .ok_or_else(|| anyhow!("Truncated DNS name"))?
+                    as usize;
+                if !jumped {
+                    self.offset = offset + 2;
+                    jumped = true;
+                }
+                offset = ((length & 0x3F) << 8) | low;
+            } else if length == 0 {
+                if !jumped {
+                    self.offset = offset + 1;
+                }
+                return Ok(labels.join("."));
+            } else {
+                let label = self
+                    .packet
+                    .get(offset + 1..offset + 1 + length)
+                    .ok_or_else(|| anyhow!("Truncated DNS name"))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + length;
+            }
+        }
+        Err(anyhow!("DNS name too long"))
+    }
+}
+
+/// Chromecasts announced in an mDNS answer sent from `from`
+fn parse_mdns_response(packet: &[u8], from: IpAddr) -> Result<Vec<CastDevice>> {
+    let mut reader = DnsReader { packet, offset: 0 };
+    let header = reader.bytes(12)?;
+    // Other queries on the network
+    if header[2] & 0x80 == 0 {
+        return Ok(Vec::new());
+    }
+    let count = |index: usize| u16::from_be_bytes([header[index], header[index + 1]]);
+    let questions = count(4);
+    let records = count(6) as usize + count(8) as usize + count(10) as usize;
+
+    for _ in 0..questions {
+        reader.name()?;
+        reader.bytes(4)?;
+    }
+
+    let mut instances = Vec::new();
+    let mut services = HashMap::new();
+    let mut names = HashMap::new();
+    let mut addresses = HashMap::new();
+    for _ in 0..records {
+        let owner = reader.name()?;
+        let kind = reader.u16()?;
+        // Class and TTL
+        reader.bytes(6)?;
+        let length = reader.u16()? as usize;
+        let end = reader.offset + length;
+        if end > packet.len() {
+            return Err(anyhow!("Truncated DNS record"));
+        }
+
+        match kind {
+            DNS_PTR if owner.eq_ignore_ascii_case(CAST_SERVICE) => {
+                let instance = reader.name()?;
+                if !instances.contains(&instance) {
+                    instances.push(instance);
+                }
+            }
+            DNS_SRV => {
+                // Priority and weight
+                reader.bytes(4)?;
+                let port = reader.u16()?;
+                services.insert(owner, (port, reader.name()?));
+            }
+            DNS_TXT => {
+                let mut text = &packet[reader.offset..end];
+                while let Some((&length, rest)) = text.split_first() {
+                    let entry = &rest[..(length as usize).min(rest.len())];
+                    text = &rest[entry.len()..];
+                    if let Some(name) = entry.strip_prefix(b"fn=") {
+                        names.insert(owner.clone(), String::from_utf8_lossy(name).into_owned());
+                    }
+                }
+            }
+            DNS_A if length == 4 => {
+                let octets = reader.bytes(4)?;
+                addresses.insert(
+                    owner,
+                    Ipv4Addr::new(octets[0], octets[1], octets[2], octets[3]),
+                );
+            }
+            _ => {}
+        }
+        reader.offset = end;
+    }
+
+    Ok(instances
+        .into_iter()
+        .map(|instance| {
+            let (port, host) = services
+                .get(&instance)
+                .cloned()
+                .unwrap_or((CHROMECAST_PORT, String::new()));
+            let address = addresses
+                .get(&host)
+                .map_or(from, |address| IpAddr::V4(*address));
+            let name = names
+                .get(&instance)
+                .cloned()
+                .unwrap_or_else(|| instance.split('.').next().unwrap_or(&instance).to_string());
+            CastDevice {
+                name,
+                target: CastTarget::Chromecast(SocketAddr::new(address, port)),
+            }
+        })
+        .collect())
+}
+
+/// Where an answer to our M-SEARCH says the device description lives
+fn ssdp_location(response: &str) -> Option<String> {
+    let mut lines = response.lines();
+    if !lines.next()?.starts_with("HTTP/1.1 200") {
+        return None;
+    }
+    lines.find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("location")
+            .then(|| value.trim().to_string())
+    })
+}
+
+async fn describe(client: &reqwest::Client, location: &str) -> Result<Option<CastDevice>> {
+    let description = client
+        .get(location)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parse_description(&description, location)
+}
+
+/// A renderer from its UPnP device description, or `None` if it can't be controlled
+fn parse_description(xml: &str, location: &str) -> Result<Option<CastDevice>> {
+    let base = xml_value(xml, "URLBase")
+        .filter(|base| !base.is_empty())
+        .unwrap_or_else(|| location.to_string());
+    let base = url::Url::parse(&base)?;
+
+    let mut av_transport = None;
+    let mut rendering_control = None;
+    for service in xml_blocks(xml, "service") {
+        let (Some(kind), Some(control_url)) = (
+            xml_value(service, "serviceType"),
+            xml_value(service, "controlURL"),
+        ) else {
+            continue;
+        };
+        let control_url = base.join(&control_url)?.to_string();
+        if kind.starts_with("urn:schemas-upnp-org:service:AVTransport:") {
+            av_transport.get_or_insert(control_url);
+        } else if kind.starts_with("urn:schemas-upnp-org:service:RenderingControl:") {
+            rendering_control.get_or_insert(control_url);
+        }
+    }
+
+    let Some(av_transport) = av_transport else {
+        return Ok(None);
+    };
+    let name = xml_value(xml, "friendlyName")
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| base.host_str().unwrap_or("DLNA renderer").to_string());
+    Ok(Some(CastDevice {
+        name,
+        target: CastTarget::Dlna {
+            av_transport,
+            rendering_control,
+        },
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::cast::dlna::tests::fake_renderer;
+
+    fn push_name(packet: &mut Vec<u8>, name: &str) {
+        for label in name.split('.') {
+            packet.push(label.len() as u8);
+            packet.extend_from_slice(label.as_bytes());
+        }
+        packet.push(0);
+    }
+
+    fn push_record(packet: &mut Vec<u8>, owner: &[u8], kind: u16, data: &[u8]) {
+        packet.extend_from_slice(owner);
+        packet.extend_from_slice(&kind.to_be_bytes());
+        packet.extend_from_slice(&[0, 1, 0, 0, 0, 120]);
+        packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        packet.extend_from_slice(data);
+    }
+
+    #[test]
+    fn test_parse_mdns_response() {
+        // An answer with the PTR record plus SRV, TXT and A records as additionals
+        let mut packet = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 3];
+        let mut service = Vec::new();
+        push_name(&mut service, CAST_SERVICE);
+        let mut instance = Vec::new();
+        push_name(&mut instance, "Chromecast-1234._googlecast._tcp.local");
+        push_record(&mut packet, &service, DNS_PTR, &instance);
+        // Points back at the instance name inside the PTR record
+        let instance_pointer = [0xC0, (12 + service.len() + 10) as u8];
+
+        let mut srv = vec![0, 0, 0, 0, 0x1F, 0x49];
+        push_name(&mut srv, "1234.local");
+        push_record(&mut packet, &instance_pointer, DNS_SRV, &srv);
+        let mut txt = vec![7];
+        txt.extend_from_slice(b"id=1234");
+        txt.push(14);
+        txt.extend_from_slice(b"fn=Living Room");
+        push_record(&mut packet, &instance_pointer, DNS_TXT, &txt);
+        let mut host = Vec::new();
+        push_name(&mut host, "1234.local");
+        push_record(&mut packet, &host, DNS_A, &[192, 168, 1, 20]);
+
+        let devices = parse_mdns_response(&packet, "10.0.0.1".parse().unwrap()).unwrap();
+        assert_eq!(
+            devices,
+            [CastDevice {
+                name: "Living Room".to_string(),
+                target: CastTarget::Chromecast("192.168.1.20:8009".parse().unwrap()),
+            }]
+        );
+
+        // Our own query, looped back
+        let query = mdns_query(CAST_SERVICE);
+        assert!(
+            parse_mdns_response(&query, "10.0.0.1".parse().unwrap())
+                .unwrap()
+                .is_empty()
+        );
+        assert!(parse_mdns_response(&packet[..40], "10.0.0.1".parse().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_ssdp_location() {
+        let response = "HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age=1800\r\nLocation: http://192.168.1.30:49152/description.xml\r\nST: urn:schemas-upnp-org:device:MediaRenderer:1\r\n\r\n";
+        assert_eq!(
+            ssdp_location(response).as_deref(),
+            Some("http://192.168.1.30:49152/description.xml")
+        );
+        assert_eq!(
+            ssdp_location("NOTIFY * HTTP/1.1\r\nLOCATION: x\r\n\r\n"),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_describe_renderer() {
+        let description = r#"<?xml version="1.0"?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:MediaRenderer:1</deviceType>
+    <friendlyName>Bedroom TV</friendlyName>
+    <serviceList>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:RenderingControl:1</serviceType>
+        <controlURL>/RenderingControl/control</controlURL>
+      </service>
+      <service>
+        <serviceType>urn:schemas-upnp-org:service:AVTransport:1</serviceType>
+        <controlURL>AVTransport/control</controlURL>
+      </service>
+    </serviceList>
+  </device>
+</root>"#;
+        let (base, _) = fake_renderer(HashMap::from([(
+            "/upnp/description.xml",
+            description.to_string(),
+        )]))
+        .await;
+
+        let device = describe(
+            &reqwest::Client::new(),
+            &format!("{}/upnp/description.xml", base),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            device,
+            Some(CastDevice {
+                name: "Bedroom TV".to_string(),
+                target: CastTarget::Dlna {
+                    av_transport: format!("{}/upnp/AVTransport/control", base),
+                    rendering_control: Some(format!("{}/RenderingControl/control", base)),
+                },
+            })
+        );
+
+        // Renderers without an AVTransport service can't be cast to
+        assert_eq!(
+            parse_description("<root><friendlyName>Speaker</friendlyName></root>", &base).unwrap(),
+            None
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use anyhow::{Context, Result, anyhow};
+use async_trait::async_trait;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::debug;
+
+use super::{CastMedia, CastReceiver, CastStatus};
+use crate::player::PlayerState;
+
+const AV_TRANSPORT: &str = "urn:schemas-upnp-org:service:AVTransport:1";
+const RENDERING_CONTROL: &str = "urn:schemas-upnp-org:service:RenderingControl:1";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A UPnP MediaRenderer, driven through SOAP calls to its AVTransport and
+/// RenderingControl services
+pub struct DlnaReceiver {
+    client: reqwest::Client,
+    av_transport: String,
+    rendering_control: Option<String>,
+    // Renderers report "NOT_IMPLEMENTED" for the duration while they are still opening a stream
+    duration: Mutex<Option<Duration>>,
+}
+
+impl DlnaReceiver {
+    pub fn new(av_transport: String, rendering_control: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            av_transport,
+            rendering_control,
+            duration: Mutex::new(None),
+        }
+    }
+
+    /// Call `action` on the service at `control_url`, returning the response envelope
+    async fn call(
+        &self,
+        control_url: &str,
+        service: &str,
+        action: &str,
+        arguments: &[(&str, &str)],
+    ) -> Result<String> {
+        let arguments: String = arguments
+            .iter()
+            .map(|(name, value)| format!("<{0}>{1}</{0}>", name, escape_xml(value)))
+            .collect();
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?><s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><s:Body><u:{action} xmlns:u="{service}">{arguments}</u:{action}></s:Body></s:Envelope>"#
+        );
+
+        let response = self
+            .client
+            .post(control_url)
+            .header("Content-Type", r#"text/xml; charset="utf-8""#)
+            .header("SOAPAction", format!(r#""{}#{}""#, service, action))
+            .body(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach the renderer for {}", action))?;
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let reason = xml_value(&text, "errorDescription")
+                .or_else(|| xml_value(&text, "faultstring"))
+                .unwrap_or_else(|| status.to_string());
+            return Err(anyhow!("The renderer refused {}: {}", action, reason));
+        }
+        Ok(text)
+    }
+
+    async fn transport(&self, action: &str, arguments: &[(&str, &str)]) -> Result<String> {
+        let mut all = vec![("InstanceID", "0")];
+        all.extend_from_slice(arguments);
+        self.call(&self.av_transport, AV_TRANSPORT, action, &all)
+            .await
+    }
+
+    async fn rendering(&self, action: &str, arguments: &[(&str, &str)]) -> Result<String> {
+        let control_url = self
+            .rendering_control
+            .as_deref()
+            .ok_or_else(|| anyhow!("The renderer has no volume control"))?;
+        let mut all = vec![("InstanceID", "0"), ("Channel", "Master")];
+        all.extend_from_slice(arguments);
+        self.call(control_url, RENDERING_CONTROL, action, &all)
+            .await
+    }
+}
+
+#[async_trait]
+impl CastReceiver for DlnaReceiver {
+    async fn load(&self, media: &CastMedia) -> Result<()> {
+        *self.duration.lock().unwrap() = None;
+        self.transport(
+            "SetAVTransportURI",
+            &[
+                ("CurrentURI", &media.url),
+                ("CurrentURIMetaData", &didl_metadata(media)),
+            ],
+        )
+        .await?;
+        self.transport("Play", &[("Speed", "1")]).await?;
+
+        if !media.start.is_zero() {
+            // Plenty of renderers can only seek once the stream is open
+            let target = format_time(media.start);
+            for _ in 0..10 {
+                match self
+                    .transport("Seek", &[("Unit", "REL_TIME"), ("Target", &target)])
+                    .await
+                {
+                    Ok(_) => break,
+                    Err(e) => debug!("Renderer not ready to seek yet: {}", e),
+                }
+                tokio::time::sleep(Duration::from_millis(500)).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn play(&self) -> Result<()> {
+        self.transport("Play", &[("Speed", "1")]).await.map(|_| ())
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.transport("Pause", &[]).await.map(|_| ())
+    }
+
+    async fn seek(&self, position: Duration) -> Result<()> {
+        self.transport(
+            "Seek",
+            &[("Unit", "REL_TIME"), ("Target", &format_time(position))],
+        )
+        .await
+        .map(|_| ())
+    }
+
+    async fn set_volume(&self, volume: f64) -> Result<()> {
+        let volume = ((volume.clamp(0.0, 1.0) * 100.0).round() as u32).to_string();
+        self.rendering("SetVolume", &[("DesiredVolume", &volume)])
+            .await
+            .map(|_| ())
+    }
+
+    async fn set_muted(&self, muted: bool) -> Result<()> {
+        self.rendering("SetMute", &[("DesiredMute", if muted { "1" } else { "0" })])
+            .await
+            .map(|_| ())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        self.transport("Stop", &[]).await.map(|_| ())
+    }
+
+    async fn status(&self) -> Result<CastStatus> {
+        let transport = self.transport("GetTransportInfo", &[]).await?;
+        let state = match xml_value(&transport, "CurrentTransportState").as_deref() {
+            Some("PLAYING") => PlayerState::Playing,
+            Some("PAUSED_PLAYBACK") => PlayerState::Paused,
+            Some("TRANSITIONING") => PlayerState::Loading,
+            _ if xml_value(&transport, "CurrentTransportStatus").as_deref()
+                == Some("ERROR_OCCURRED") =>
+            {
+                PlayerState::Error
+            }
+            _ => PlayerState::Stopped,
+        };
+
+        let position = self.transport("GetPositionInfo", &[]).await?;
+        if let Some(duration) = xml_value(&position, "TrackDuration")
+            .as_deref()
+            .and_then(parse_time)
+            .filter(|duration| !duration.is_zero())
+        {
+            *self.duration.lock().unwrap() = Some(duration);
+        }
+        Ok(CastStatus {
+            state,
+            position: xml_value(&position, "RelTime")
+                .as_deref()
+                .and_then(parse_time)
+                .unwrap_or_default(),
+            duration: *self.duration.lock().unwrap(),
+        })
+    }
+}
+
+/// DIDL-Lite description of `media`; many renderers refuse a URI without one
+fn didl_metadata(media: &CastMedia) -> String {
+    let class = if media.content_type.starts_with("audio/") {
+        "object.item.audioItem.musicTrack"
+    } else {
+        "object.item.videoItem"
+    };
+    format!(
+        r#"<DIDL-Lite xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/"><item id="0" parentID="-1" restricted="1"><dc:title>{}</dc:title><upnp:class>{}</upnp:class><res protocolInfo="http-get:*:{}:*">{}</res></item></DIDL-Lite>"#,
+        escape_xml(&media.title),
+        class,
+        escape_xml(&media.content_type),
+        escape_xml(&media.url),
+    )
+}
+
+/// "H:MM:SS" as UPnP writes times
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+/// "H:MM:SS" with optional fractional seconds; `None` for "NOT_IMPLEMENTED" and the like
+fn parse_time(text: &str) -> Option<Duration> {
+    let mut parts = text.trim().splitn(3, ':');
+    let hours: u64 = parts.next()?.parse().ok()?;
+    let minutes: u64 = parts.next()?.parse().ok()?;
+    let seconds: f64 = parts.next()?.parse().ok()?;
+    if !seconds.is_finite() || seconds < 0.0 {
+        return None;
+    }
+    Some(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+pub(super) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Start and end of the content of each `tag` element, whatever namespace prefix it has
+fn elements<'a>(xml: &'a str, tag: &'a str) -> impl Iterator<Item = (usize, usize)> + 'a {
+    let mut from = 0;
+    std::iter::from_fn(move || {
+        while let Some(offset) = xml[from..].find('<') {
+            let start = from + offset + 1;
+            let name_end = xml[start..]
+                .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .map_or(xml.len(), |end| start + end);
+            let name = &xml[start..name_end];
+            from = name_end;
+            if name.rsplit(':').next() != Some(tag) {
+                continue;
+            }
+            let open_end = name_end + xml[name_end..].find('>')?;
+            if xml[..open_end].ends_with('/') {
+                from = open_end;
+                return Some((open_end + 1, open_end + 1));
+            }
+            let content_start = open_end + 1;
+            let close = format!("</{}>", name);
+            let content_end = content_start + xml[content_start..].find(&close)?;
+            from = content_end + close.len();
+            return Some((content_start, content_end));
+        }
+        None
+    })
+}
+
+/// Text of the first `tag` element
+pub(super) fn xml_value(xml: &str, tag: &str) -> Option<String> {
+    elements(xml, tag)
+        .next()
+        .map(|(start, end)| unescape_xml(xml[start..end].trim()))
+}
+
+/// Raw content of every `tag` element
+pub(super) fn xml_blocks<'a>(xml: &'a str, tag: &'a str) -> Vec<&'a str> {
+    elements(xml, tag)
+        .map(|(start, end)| &xml[start..end])
+        .collect()
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// A renderer on localhost answering SOAP calls and serving `documents` by path.
+    /// Returns its base URL and the actions it was asked for, with their arguments.
+    pub(in crate::player::cast) async fn fake_renderer(
+        documents: HashMap<&'static str, String>,
+    ) -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let log = calls.clone();
+
+        tokio::spawn(async move {
+            let mut state = "NO_MEDIA_PRESENT";
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let mut stream = BufReader::new(stream);
+                let mut request_line = String::new();
+                stream.read_line(&mut request_line).await.unwrap();
+                let path = request_line.split(' ').nth(1).unwrap_or("/").to_string();
+                let mut length = 0;
+                let mut action = String::new();
+                loop {
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(':').unwrap();
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => length = value.trim().parse().unwrap(),
+                        "soapaction" => {
+                            action = value
+                                .trim()
+                                .trim_matches('"')
+                                .rsplit('#')
+                                .next()
+                                .unwrap()
+                                .to_string()
+                        }
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                stream.read_exact(&mut body).await.unwrap();
+
+                let response = if action.is_empty() {
+                    documents.get(path.as_str()).cloned().unwrap_or_default()
+                } else {
+                    log.lock()
+                        .unwrap()
+                        .push((action.clone(), String::from_utf8(body).unwrap()));
+                    let values = match action.as_str() {
+                        "Play" => {
+                            state = "PLAYING";
+                            String::new()
+                        }
+                        "Pause" => {
+                            state = "PAUSED_PLAYBACK";
+                            String::new()
+                        }
+                        "GetTransportInfo" => format!(
+                            "<CurrentTransportState>{}</CurrentTransportState><CurrentTransportStatus>OK</CurrentTransportStatus>",
+                            state
+                        ),
+                        "GetPositionInfo" => {
+                            "<TrackDuration>0:10:00</TrackDuration><RelTime>0:00:42</RelTime>"
+                                .to_string()
+                        }
+                        _ => String::new(),
+                    };
+                    format!(
+                        r#"<?xml version="1.0"?><s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:{0}Response xmlns:u="urn:schemas-upnp-org:service:AVTransport:1">{1}</u:{0}Response></s:Body></s:Envelope>"#,
+                        action, values
+                    )
+                };
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/xml\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.len(),
+                    response
+                );
+                stream.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        (base, calls)
+    }
+
+    #[test]
+    fn test_times() {
+        assert_eq!(format_time(Duration::from_secs(3723)), "1:02:03");
+        assert_eq!(
+            parse_time("0:01:23.500"),
+            Some(Duration::from_millis(83500))
+        );
+        assert_eq!(parse_time("NOT_IMPLEMENTED"), None);
+    }
+
+    #[test]
+    fn test_xml_helpers() {
+        let xml = "<root><u:Name a=\"1\">Tom &amp; Jerry</u:Name><Empty/><item><x>1</x></item><item><x>2</x></item></root>";
+        assert_eq!(xml_value(xml, "Name").as_deref(), Some("Tom & Jerry"));
+        assert_eq!(xml_value(xml, "Empty").as_deref(), Some(""));
+        assert_eq!(xml_blocks(xml, "item"), ["<x>1</x>", "<x>2</x>"]);
+        assert_eq!(xml_value(xml, "Missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_control_renderer() {
+        let (base, calls) = fake_renderer(HashMap::new()).await;
+        let receiver = DlnaReceiver::new(
+            format!("{}/AVTransport/control", base),
+            Some(format!("{}/RenderingControl/control", base)),
+        );
+
+        receiver
+            .load(&CastMedia {
+                url: "http://server/video?a=1&b=2".to_string(),
+                title: "Movie".to_string(),
+                content_type: "video/mp4".to_string(),
+                start: Duration::ZERO,
+            })
+            .await
+            .unwrap();
+        assert_eq!(receiver.status().await.unwrap().state, PlayerState::Playing);
+
+        receiver.pause().await.unwrap();
+        receiver.set_volume(0.3).await.unwrap();
+        assert_eq!(
+            receiver.status().await.unwrap(),
+            CastStatus {
+                state: PlayerState::Paused,
+                position: Duration::from_secs(42),
+                duration: Some(Duration::from_secs(600)),
+            }
+        );
+
+        let calls = calls.lock().unwrap();
+        let actions: Vec<_> = calls.iter().map(|(action, _)| action.as_str()).collect();
+        assert_eq!(
+            actions,
+            [
+                "SetAVTransportURI",
+                "Play",
+                "GetTransportInfo",
+                "GetPositionInfo",
+                "Pause",
+                "SetVolume",
+                "GetTransportInfo",
+                "GetPositionInfo",
+            ]
+        );
+        // The URI is escaped once on its own and the metadata twice
+        assert_eq!(
+            xml_value(&calls[0].1, "CurrentURI").as_deref(),
+            Some("http://server/video?a=1&b=2")
+        );
+        let metadata = xml_value(&calls[0].1, "CurrentURIMetaData").unwrap();
+        assert_eq!(xml_value(&metadata, "title").as_deref(), Some("Movie"));
+        assert_eq!(
+            xml_value(&calls[5].1, "DesiredVolume").as_deref(),
+            Some("30")
+        );
+    }
+}
@@ -0,0 +1,149 @@
+mod chromecast;
+mod discovery;
+mod dlna;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use super::PlayerState;
+pub use chromecast::ChromecastReceiver;
+pub use discovery::discover;
+pub use dlna::DlnaReceiver;
+
+/// A device on the local network that can play a stream by itself
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastDevice {
+    pub name: String,
+    pub target: CastTarget,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CastTarget {
+    /// Chromecast or another Google Cast receiver
+    Chromecast(SocketAddr),
+    /// UPnP MediaRenderer, controlled through its AVTransport and RenderingControl services
+    Dlna {
+        av_transport: String,
+        rendering_control: Option<String>,
+    },
+}
+
+/// What to play on a receiver
+#[derive(Debug, Clone)]
+pub struct CastMedia {
+    pub url: String,
+    pub title: String,
+    pub content_type: String,
+    pub start: Duration,
+}
+
+/// What a receiver reports it is doing
+#[derive(Debug, Clone, PartialEq)]
+pub struct CastStatus {
+    pub state: PlayerState,
+    pub position: Duration,
+    pub duration: Option<Duration>,
+}
+
+/// Remote control for a receiver playing the stream itself
+#[async_trait]
+pub trait CastReceiver: Send + Sync {
+    async fn load(&self, media: &CastMedia) -> Result<()>;
+    async fn play(&self) -> Result<()>;
+    async fn pause(&self) -> Result<()>;
+    async fn seek(&self, position: Duration) -> Result<()>;
+    /// 0.0 to 1.0
+    async fn set_volume(&self, volume: f64) -> Result<()>;
+    async fn set_muted(&self, muted: bool) -> Result<()>;
+    async fn stop(&self) -> Result<()>;
+    async fn status(&self) -> Result<CastStatus>;
+}
+
+pub async fn connect(device: &CastDevice) -> Result<Box<dyn CastReceiver>> {
+    Ok(match &device.target {
+        CastTarget::Chromecast(address) => Box::new(ChromecastReceiver::connect(*address).await?),
+        CastTarget::Dlna {
+            av_transport,
+            rendering_control,
+        } => Box::new(DlnaReceiver::new(
+            av_transport.clone(),
+            rendering_control.clone(),
+        )),
+    })
+}
+
+/// MIME type receivers expect for the stream at `url`. Transcodes are named by their
+/// protocol or file extension, not the `container` of the media they come from.
+pub fn content_type(url: &str, container: &str) -> &'static str {
+    let from_url = url::Url::parse(url).ok().and_then(|url| {
+        if url
+            .query_pairs()
+            .any(|(key, value)| key == "protocol" && value == "hls")
+        {
+            return container_type("hls");
+        }
+        let (_, extension) = url.path().rsplit_once('.')?;
+        container_type(extension)
+    });
+    from_url
+        .or_else(|| container_type(container))
+        .unwrap_or("video/mp4")
+}
+
+/// MIME type for a stream in `container`, if it's a known one
+fn container_type(container: &str) -> Option<&'static str> {
+    Some(match container.to_ascii_lowercase().as_str() {
+        "mp4" | "m4v" | "mov" => "video/mp4",
+        "mkv" | "matroska" => "video/x-matroska",
+        "webm" => "video/webm",
+        "ts" | "mpegts" => "video/mp2t",
+        "hls" | "m3u8" => "application/x-mpegURL",
+        "mpd" | "dash" => "application/dash+xml",
+        "avi" => "video/x-msvideo",
+        "mp3" => "audio/mpeg",
+        "flac" => "audio/flac",
+        "m4a" | "aac" => "audio/mp4",
+        "ogg" | "opus" => "audio/ogg",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_follows_the_url() {
+        // A Plex transcode of an MKV is HLS
+        assert_eq!(
+            content_type(
+                "http://plex:32400/video/:/transcode/universal/start.m3u8?protocol=hls",
+                "mkv"
+            ),
+            "application/x-mpegURL"
+        );
+        assert_eq!(
+            content_type(
+                "http://jellyfin:8096/videos/1/master.m3u8?MediaSourceId=1",
+                "mkv"
+            ),
+            "application/x-mpegURL"
+        );
+        assert_eq!(
+            content_type(
+                "http://plex:32400/library/parts/1/2/file.webm?X-Plex-Token=t",
+                "mkv"
+            ),
+            "video/webm"
+        );
+        // Without an extension the container decides
+        assert_eq!(
+            content_type("http://jellyfin:8096/Videos/1/stream?Static=true", "mkv"),
+            "video/x-matroska"
+        );
+        assert_eq!(content_type("http://host/movie.mp4", "mkv"), "video/mp4");
+        assert_eq!(content_type("http://host/stream", ""), "video/mp4");
+    }
+}
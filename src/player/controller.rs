@@ -1,17 +1,31 @@
 use anyhow::Result;
 use gtk4;
+use std::path::PathBuf;
 use std::time::Duration;
-use tokio::sync::{mpsc, oneshot};
+use tokio::sync::{broadcast, mpsc, oneshot};
 use tracing::{debug, error, info};
 
-use super::{Player, PlayerState};
+use super::factory::{Player, create_player};
 use crate::config::Config;
+use crate::core::player_traits::{
+    AudioOutputDevice, MediaPlayer, PlaybackStats, PlayerCapabilities, PlayerEvent, PlayerState,
+    ReplayGainMode,
+};
+use crate::models::{AudioEffects, Chapter, CropRect, VideoAdjustments};
 
 use crate::player::UpscalingMode;
 
 /// Commands that can be sent to the player controller
 #[derive(Debug)]
 pub enum PlayerCommand {
+    /// Subscribe to events pushed by the player backend
+    Subscribe {
+        respond_to: oneshot::Sender<broadcast::Receiver<PlayerEvent>>,
+    },
+    /// Get the optional features the backend supports
+    GetCapabilities {
+        respond_to: oneshot::Sender<PlayerCapabilities>,
+    },
     /// Create a video widget for rendering
     CreateVideoWidget {
         respond_to: oneshot::Sender<gtk4::Widget>,
@@ -77,11 +91,19 @@ pub enum PlayerCommand {
         track_index: i32,
         respond_to: oneshot::Sender<Result<()>>,
     },
+    /// Load an external subtitle file as an extra track
+    AddSubtitle {
+        url: String,
+        title: String,
+        language: Option<String>,
+        select: bool,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
     /// Get current audio track
     GetCurrentAudioTrack { respond_to: oneshot::Sender<i32> },
     /// Get current subtitle track
     GetCurrentSubtitleTrack { respond_to: oneshot::Sender<i32> },
-    /// Set upscaling mode (MPV only)
+    /// Set upscaling mode, if the backend supports it
     SetUpscalingMode {
         mode: UpscalingMode,
         respond_to: oneshot::Sender<Result<()>>,
@@ -93,6 +115,25 @@ pub enum PlayerCommand {
     },
     /// Get playback speed
     GetPlaybackSpeed { respond_to: oneshot::Sender<f64> },
+    /// Set subtitle delay in milliseconds
+    SetSubtitleDelay {
+        delay_ms: i64,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Set audio delay in milliseconds
+    SetAudioDelay {
+        delay_ms: i64,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Get embedded chapters
+    GetChapters {
+        respond_to: oneshot::Sender<Vec<Chapter>>,
+    },
+    /// Seek to the start of an embedded chapter
+    SeekToChapter {
+        index: usize,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
     /// Frame step forward
     FrameStepForward {
         respond_to: oneshot::Sender<Result<()>>,
@@ -115,6 +156,64 @@ pub enum PlayerCommand {
     CycleAudioTrack {
         respond_to: oneshot::Sender<Result<()>>,
     },
+    /// Skip video decoding and output
+    SetAudioOnly {
+        audio_only: bool,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Preload the media to play straight after the current one
+    QueueNext {
+        url: Option<String>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Set ReplayGain volume normalisation
+    SetReplayGain {
+        mode: ReplayGainMode,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Set aspect override, zoom, pan, crop and colour
+    SetVideoAdjustments {
+        adjustments: VideoAdjustments,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Look for black bars in the current frame
+    DetectBlackBars {
+        respond_to: oneshot::Sender<Result<Option<CropRect>>>,
+    },
+    /// Save the current frame to an image file
+    Screenshot {
+        path: PathBuf,
+        include_subtitles: bool,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Get decoding and buffering statistics
+    GetStats {
+        respond_to: oneshot::Sender<Result<PlaybackStats>>,
+    },
+    /// List the audio outputs
+    GetAudioDevices {
+        respond_to: oneshot::Sender<Result<Vec<AudioOutputDevice>>>,
+    },
+    /// Play on another audio output
+    SetAudioDevice {
+        name: String,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Repeat a section, or stop repeating it
+    SetAbLoop {
+        range: Option<(Duration, Duration)>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Replace the dynamic range preset and equalizer
+    SetAudioEffects {
+        effects: AudioEffects,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
+    /// Choose which codecs go to the output undecoded
+    SetAudioPassthrough {
+        codecs: Vec<String>,
+        respond_to: oneshot::Sender<Result<()>>,
+    },
     /// Shutdown the player controller
     Shutdown,
 }
@@ -128,21 +227,35 @@ pub struct PlayerController {
 impl PlayerController {
     /// Create a new player controller with the given config
     pub fn new(config: &Config) -> Result<(PlayerHandle, PlayerController)> {
-        let player = Player::new(config)?;
+        let player = create_player(config)?;
+        Ok(Self::with_player(player))
+    }
+
+    /// Create a controller around an already constructed backend
+    pub fn with_player(player: Player) -> (PlayerHandle, PlayerController) {
         let (sender, receiver) = mpsc::unbounded_channel();
 
         let controller = PlayerController { player, receiver };
         let handle = PlayerHandle { sender };
 
-        Ok((handle, controller))
+        (handle, controller)
     }
 
     /// Run the controller event loop
     pub async fn run(mut self) {
-        info!("🎮 PlayerController: Starting event loop");
+        info!(
+            "🎮 PlayerController: Starting event loop ({} backend)",
+            self.player.backend_name()
+        );
 
         while let Some(command) = self.receiver.recv().await {
             match command {
+                PlayerCommand::Subscribe { respond_to } => {
+                    let _ = respond_to.send(self.player.subscribe());
+                }
+                PlayerCommand::GetCapabilities { respond_to } => {
+                    let _ = respond_to.send(self.player.capabilities());
+                }
                 PlayerCommand::CreateVideoWidget { respond_to } => {
                     debug!("🎮 PlayerController: Creating video widget");
                     let widget = self.player.create_video_widget();
@@ -151,6 +264,13 @@ impl PlayerController {
                 PlayerCommand::LoadMedia { url, respond_to } => {
                     debug!("🎮 PlayerController: Loading media: {}", url);
                     let result = self.player.load_media(&url).await;
+                    if let Err(e) = &result {
+                        error!(
+                            "❌ PlayerController ({}): Failed to load media: {}",
+                            self.player.backend_name(),
+                            e
+                        );
+                    }
                     let _ = respond_to.send(result);
                 }
                 PlayerCommand::Play { respond_to } => {
@@ -227,6 +347,20 @@ impl PlayerController {
                     let result = self.player.set_subtitle_track(track_index).await;
                     let _ = respond_to.send(result);
                 }
+                PlayerCommand::AddSubtitle {
+                    url,
+                    title,
+                    language,
+                    select,
+                    respond_to,
+                } => {
+                    debug!("🎮 PlayerController: Adding external subtitle '{}'", title);
+                    let result = self
+                        .player
+                        .add_subtitle(&url, &title, language.as_deref(), select)
+                        .await;
+                    let _ = respond_to.send(result);
+                }
                 PlayerCommand::GetCurrentAudioTrack { respond_to } => {
                     let track = self.player.get_current_audio_track().await;
                     let _ = respond_to.send(track);
@@ -235,17 +369,89 @@ impl PlayerController {
                     let track = self.player.get_current_subtitle_track().await;
                     let _ = respond_to.send(track);
                 }
+                PlayerCommand::SetAudioOnly {
+                    audio_only,
+                    respond_to,
+                } => {
+                    debug!("🎮 PlayerController: Setting audio only to {}", audio_only);
+                    let result = self.player.set_audio_only(audio_only).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::QueueNext { url, respond_to } => {
+                    debug!("🎮 PlayerController: Queueing next media: {:?}", url);
+                    let result = self.player.queue_next(url.as_deref()).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::SetReplayGain { mode, respond_to } => {
+                    debug!("🎮 PlayerController: Setting ReplayGain to {:?}", mode);
+                    let result = self.player.set_replaygain(mode).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::SetVideoAdjustments {
+                    adjustments,
+                    respond_to,
+                } => {
+                    debug!(
+                        "🎮 PlayerController: Setting video adjustments to {:?}",
+                        adjustments
+                    );
+                    let result = self.player.set_video_adjustments(adjustments).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::DetectBlackBars { respond_to } => {
+                    debug!("🎮 PlayerController: Detecting black bars");
+                    let result = self.player.detect_black_bars().await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::Screenshot {
+                    path,
+                    include_subtitles,
+                    respond_to,
+                } => {
+                    debug!("🎮 PlayerController: Saving screenshot to {:?}", path);
+                    let result = self.player.screenshot(&path, include_subtitles).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::GetStats { respond_to } => {
+                    let stats = self.player.get_stats().await;
+                    let _ = respond_to.send(stats);
+                }
+                PlayerCommand::GetAudioDevices { respond_to } => {
+                    let devices = self.player.get_audio_devices().await;
+                    let _ = respond_to.send(devices);
+                }
+                PlayerCommand::SetAudioDevice { name, respond_to } => {
+                    debug!("🎮 PlayerController: Setting audio device to {:?}", name);
+                    let result = self.player.set_audio_device(&name).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::SetAbLoop { range, respond_to } => {
+                    debug!("🎮 PlayerController: Setting A-B loop to {:?}", range);
+                    let result = self.player.set_ab_loop(range).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::SetAudioEffects {
+                    effects,
+                    respond_to,
+                } => {
+                    debug!(
+                        "🎮 PlayerController: Setting audio effects to {:?}",
+                        effects
+                    );
+                    let result = self.player.set_audio_effects(effects).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::SetAudioPassthrough { codecs, respond_to } => {
+                    debug!(
+                        "🎮 PlayerController: Setting audio passthrough to {:?}",
+                        codecs
+                    );
+                    let result = self.player.set_audio_passthrough(&codecs).await;
+                    let _ = respond_to.send(result);
+                }
                 PlayerCommand::SetUpscalingMode { mode, respond_to } => {
                     debug!("🎮 PlayerController: Setting upscaling mode to {:?}", mode);
-                    let result = match &self.player {
-                        Player::Mpv(mpv) => mpv.set_upscaling_mode(mode).await,
-                        Player::GStreamer(_) => {
-                            // GStreamer doesn't support upscaling modes
-                            Err(anyhow::anyhow!(
-                                "Upscaling mode not supported for GStreamer backend"
-                            ))
-                        }
-                    };
+                    let result = self.player.set_upscaling_mode(mode).await;
                     let _ = respond_to.send(result);
                 }
                 PlayerCommand::SetPlaybackSpeed { speed, respond_to } => {
@@ -257,6 +463,34 @@ impl PlayerController {
                     let speed = self.player.get_playback_speed().await;
                     let _ = respond_to.send(speed);
                 }
+                PlayerCommand::SetSubtitleDelay {
+                    delay_ms,
+                    respond_to,
+                } => {
+                    debug!(
+                        "🎮 PlayerController: Setting subtitle delay to {}ms",
+                        delay_ms
+                    );
+                    let result = self.player.set_subtitle_delay(delay_ms).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::SetAudioDelay {
+                    delay_ms,
+                    respond_to,
+                } => {
+                    debug!("🎮 PlayerController: Setting audio delay to {}ms", delay_ms);
+                    let result = self.player.set_audio_delay(delay_ms).await;
+                    let _ = respond_to.send(result);
+                }
+                PlayerCommand::GetChapters { respond_to } => {
+                    let chapters = self.player.get_chapters().await;
+                    let _ = respond_to.send(chapters);
+                }
+                PlayerCommand::SeekToChapter { index, respond_to } => {
+                    debug!("🎮 PlayerController: Seeking to chapter {}", index);
+                    let result = self.player.seek_to_chapter(index).await;
+                    let _ = respond_to.send(result);
+                }
                 PlayerCommand::FrameStepForward { respond_to } => {
                     debug!("🎮 PlayerController: Frame stepping forward");
                     let result = self.player.frame_step_forward().await;
@@ -310,6 +544,35 @@ unsafe impl Send for PlayerHandle {}
 unsafe impl Sync for PlayerHandle {}
 
 impl PlayerHandle {
+    /// Wrap a raw command channel, so tests can stand in for the controller
+    #[cfg(test)]
+    pub(crate) fn from_sender(sender: mpsc::UnboundedSender<PlayerCommand>) -> Self {
+        Self { sender }
+    }
+
+    /// Subscribe to state changes, position updates, end of stream, track changes
+    /// and buffering progress
+    pub async fn subscribe(&self) -> Result<broadcast::Receiver<PlayerEvent>> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::Subscribe { respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))
+    }
+
+    /// Get the optional features the backend supports
+    pub async fn capabilities(&self) -> Result<PlayerCapabilities> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::GetCapabilities { respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))
+    }
+
     /// Create a video widget for rendering
     pub async fn create_video_widget(&self) -> Result<gtk4::Widget> {
         let (respond_to, response) = oneshot::channel();
@@ -487,6 +750,29 @@ impl PlayerHandle {
             .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
     }
 
+    /// Load an external subtitle file, optionally switching to it
+    pub async fn add_subtitle(
+        &self,
+        url: &str,
+        title: &str,
+        language: Option<&str>,
+        select: bool,
+    ) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::AddSubtitle {
+                url: url.to_string(),
+                title: title.to_string(),
+                language: language.map(str::to_string),
+                select,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
     /// Get current audio track
     pub async fn get_current_audio_track(&self) -> Result<i32> {
         let (respond_to, response) = oneshot::channel();
@@ -509,7 +795,158 @@ impl PlayerHandle {
             .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))
     }
 
-    /// Set upscaling mode (MPV only)
+    /// Skip video decoding and output, for music
+    pub async fn set_audio_only(&self, audio_only: bool) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetAudioOnly {
+                audio_only,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Preload media to play without a gap after the current one, or drop it with `None`
+    pub async fn queue_next(&self, url: Option<&str>) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::QueueNext {
+                url: url.map(|url| url.to_string()),
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Set ReplayGain volume normalisation, if the backend supports it
+    pub async fn set_replaygain(&self, mode: ReplayGainMode) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetReplayGain { mode, respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Set aspect override, zoom, pan, crop and colour, if the backend supports it
+    pub async fn set_video_adjustments(&self, adjustments: VideoAdjustments) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetVideoAdjustments {
+                adjustments,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Look for black bars in the current frame, if the backend supports it
+    pub async fn detect_black_bars(&self) -> Result<Option<CropRect>> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::DetectBlackBars { respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Save the current frame to `path`, if the backend supports it
+    pub async fn screenshot(&self, path: PathBuf, include_subtitles: bool) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::Screenshot {
+                path,
+                include_subtitles,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Get decoding and buffering statistics, if the backend supports it
+    pub async fn get_stats(&self) -> Result<PlaybackStats> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::GetStats { respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// List the audio outputs, if the backend supports choosing one
+    pub async fn get_audio_devices(&self) -> Result<Vec<AudioOutputDevice>> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::GetAudioDevices { respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Play on the named audio output, or the system default if empty
+    pub async fn set_audio_device(&self, name: &str) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetAudioDevice {
+                name: name.to_string(),
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Repeat the part between two positions, or stop with `None`
+    pub async fn set_ab_loop(&self, range: Option<(Duration, Duration)>) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetAbLoop { range, respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Apply a dynamic range preset and equalizer, if the backend supports it
+    pub async fn set_audio_effects(&self, effects: AudioEffects) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetAudioEffects {
+                effects,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Send audio in these codecs to the output undecoded
+    pub async fn set_audio_passthrough(&self, codecs: Vec<String>) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetAudioPassthrough { codecs, respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Set upscaling mode, if the backend supports it
     pub async fn set_upscaling_mode(&self, mode: UpscalingMode) -> Result<()> {
         let (respond_to, response) = oneshot::channel();
         self.sender
@@ -531,6 +968,34 @@ impl PlayerHandle {
             .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
     }
 
+    /// Set subtitle delay in milliseconds; positive values show subtitles later
+    pub async fn set_subtitle_delay(&self, delay_ms: i64) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetSubtitleDelay {
+                delay_ms,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
+    /// Set audio delay in milliseconds; positive values play audio later
+    pub async fn set_audio_delay(&self, delay_ms: i64) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SetAudioDelay {
+                delay_ms,
+                respond_to,
+            })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
     /// Get playback speed
     pub async fn get_playback_speed(&self) -> Result<f64> {
         let (respond_to, response) = oneshot::channel();
@@ -542,6 +1007,28 @@ impl PlayerHandle {
             .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))
     }
 
+    /// Get embedded chapters
+    pub async fn get_chapters(&self) -> Result<Vec<Chapter>> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::GetChapters { respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))
+    }
+
+    /// Seek to the start of an embedded chapter
+    pub async fn seek_to_chapter(&self, index: usize) -> Result<()> {
+        let (respond_to, response) = oneshot::channel();
+        self.sender
+            .send(PlayerCommand::SeekToChapter { index, respond_to })
+            .map_err(|_| anyhow::anyhow!("Player controller disconnected"))?;
+        response
+            .await
+            .map_err(|_| anyhow::anyhow!("Failed to receive response from player controller"))?
+    }
+
     /// Frame step forward
     pub async fn frame_step_forward(&self) -> Result<()> {
         let (respond_to, response) = oneshot::channel();
@@ -1,29 +1,84 @@
 use anyhow::{Context, Result};
+use async_trait::async_trait;
 use gdk4 as gdk;
 use gstreamer as gst;
 use gstreamer::glib;
 use gstreamer::prelude::*;
+use gstreamer_video as gst_video;
 use gtk4::{self, prelude::*};
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tokio::sync::RwLock;
+use tokio::sync::broadcast;
 use tracing::{debug, error, info, warn};
 
-#[derive(Debug, Clone)]
-pub enum PlayerState {
-    Idle,
-    Loading,
-    Playing,
-    Paused,
-    Stopped,
-    Error,
+use crate::core::player_traits::{
+    AudioOutputDevice, MediaPlayer, PlaybackStats, PlayerCapabilities, PlayerEvent,
+    PlayerEventSource, PlayerState, ReplayGainMode,
+};
+use crate::models::video_adjustments::detect_black_bars;
+use crate::models::{AudioEffects, Chapter, CropRect, VideoAdjustments};
+
+/// How often position updates are pushed to subscribers while media is loaded
+const POSITION_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// How long to wait for the next decoded frame when looking at one
+const SOURCE_FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Parts of the audio filter the settings call for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct AudioFilterParts {
+    tempo: bool,
+    replaygain: bool,
+    /// Compressor and equalizer
+    effects: bool,
+}
+
+impl AudioFilterParts {
+    fn new(mode: ReplayGainMode, effects: &AudioEffects, passthrough: bool) -> Self {
+        // Every part only takes raw audio, so compressed formats meant for the
+        // receiver would be decoded before they reached the sink
+        if passthrough {
+            return Self {
+                tempo: false,
+                replaygain: false,
+                effects: false,
+            };
+        }
+        Self {
+            tempo: true,
+            replaygain: mode != ReplayGainMode::Off,
+            effects: !effects.is_default(),
+        }
+    }
 }
 
 pub struct GStreamerPlayer {
     playbin: Arc<Mutex<Option<gst::Element>>>,
-    state: Arc<RwLock<PlayerState>>,
+    events: PlayerEventSource,
+    position_timer: Arc<Mutex<Option<glib::SourceId>>>,
     video_sink: Arc<Mutex<Option<gst::Element>>>,
     is_playbin3: Arc<Mutex<bool>>,
+    chapters: Arc<Mutex<Vec<Chapter>>>,
+    audio_only: Arc<Mutex<bool>>,
+    replaygain: Arc<Mutex<ReplayGainMode>>,
+    audio_effects: Arc<Mutex<AudioEffects>>,
+    /// URI handed to playbin from `about-to-finish` to continue without a gap
+    next_uri: Arc<Mutex<Option<String>>>,
+    /// Set once the next URI was handed over, until its stream starts
+    advancing: Arc<Mutex<bool>>,
+    video_adjustments: Arc<Mutex<VideoAdjustments>>,
+    /// Rate of the last seek; every seek has to repeat it or playback returns to 1x
+    playback_rate: Arc<Mutex<f64>>,
+    /// Section played as a segment that is sought back into when it is done
+    ab_loop: Arc<Mutex<Option<(Duration, Duration)>>>,
+    /// `AudioOutputDevice::name` to play on, empty for the system default
+    audio_device: Arc<Mutex<String>>,
+    /// The device behind `audio_device`, looked up when it changes rather than every load
+    audio_sink_device: Arc<Mutex<Option<gst::Device>>>,
+    /// Codecs the audio sink is allowed to receive undecoded
+    audio_passthrough: Arc<Mutex<Vec<String>>>,
+    /// Position to seek back to once a stream restarted for a new subtitle file prerolls
+    reload_position: Arc<Mutex<Option<Duration>>>,
 }
 
 impl GStreamerPlayer {
@@ -47,9 +102,23 @@ impl GStreamerPlayer {
 
         Ok(Self {
             playbin: Arc::new(Mutex::new(None)),
-            state: Arc::new(RwLock::new(PlayerState::Idle)),
+            events: PlayerEventSource::new(),
+            position_timer: Arc::new(Mutex::new(None)),
             video_sink: Arc::new(Mutex::new(None)),
             is_playbin3: Arc::new(Mutex::new(false)),
+            chapters: Arc::new(Mutex::new(Vec::new())),
+            audio_only: Arc::new(Mutex::new(false)),
+            replaygain: Arc::new(Mutex::new(ReplayGainMode::default())),
+            audio_effects: Arc::new(Mutex::new(AudioEffects::default())),
+            next_uri: Arc::new(Mutex::new(None)),
+            advancing: Arc::new(Mutex::new(false)),
+            video_adjustments: Arc::new(Mutex::new(VideoAdjustments::default())),
+            playback_rate: Arc::new(Mutex::new(1.0)),
+            ab_loop: Arc::new(Mutex::new(None)),
+            audio_device: Arc::new(Mutex::new(String::new())),
+            audio_sink_device: Arc::new(Mutex::new(None)),
+            audio_passthrough: Arc::new(Mutex::new(Vec::new())),
+            reload_position: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -97,42 +166,6 @@ impl GStreamerPlayer {
         );
     }
 
-    pub fn create_video_widget(&self) -> gtk4::Widget {
-        info!("GStreamerPlayer::create_video_widget() - Starting video widget creation");
-
-        // Create a GTK Picture widget for video display
-        debug!("GStreamerPlayer::create_video_widget() - Creating GTK Picture widget");
-        let picture = gtk4::Picture::new();
-        picture.set_can_shrink(true);
-        picture.set_vexpand(true);
-        picture.set_hexpand(true);
-        debug!("GStreamerPlayer::create_video_widget() - Picture widget created");
-
-        // Check if we should force fallback mode or use alternative sink
-        let force_fallback = std::env::var("REEL_FORCE_FALLBACK_SINK").is_ok();
-        let use_gl_sink = std::env::var("REEL_USE_GL_SINK").is_ok();
-
-        // Try to create optimized video sink with glsinkbin wrapper
-        let video_sink = self.create_optimized_video_sink(force_fallback, use_gl_sink);
-
-        // If we have a gtk4paintablesink, extract and set its paintable
-        if let Some(ref sink) = video_sink
-            && let Some(gtk_sink) = self.extract_gtk4_sink(sink)
-        {
-            let paintable = gtk_sink.property::<gdk::Paintable>("paintable");
-            picture.set_paintable(Some(&paintable));
-            debug!("GStreamerPlayer::create_video_widget() - Paintable set on Picture widget");
-        }
-
-        // Store the video sink
-        *self.video_sink.lock().unwrap() = video_sink;
-
-        // Return the widget
-        let widget = picture.upcast::<gtk4::Widget>();
-        info!("GStreamerPlayer::create_video_widget() - Video widget creation complete");
-        widget
-    }
-
     fn create_optimized_video_sink(
         &self,
         force_fallback: bool,
@@ -496,16 +529,651 @@ impl GStreamerPlayer {
         None
     }
 
-    pub async fn load_media(&self, url: &str, _video_sink: Option<&gst::Element>) -> Result<()> {
-        info!("GStreamerPlayer::load_media() - Loading media: {}", url);
-        debug!("GStreamerPlayer::load_media() - Full URL: {}", url);
+    /// Flatten a table of contents into chapters; editions only group them
+    fn collect_toc_chapters(entries: &[gst::TocEntry], chapters: &mut Vec<Chapter>) {
+        for entry in entries {
+            if entry.entry_type() == gst::TocEntryType::Chapter
+                && let Some((start, _)) = entry.start_stop_times()
+            {
+                let title = entry
+                    .tags()
+                    .and_then(|tags| tags.get::<gst::tags::Title>().map(|t| t.get().to_string()))
+                    .filter(|title| !title.is_empty())
+                    .unwrap_or_else(|| format!("Chapter {}", chapters.len() + 1));
+                chapters.push(Chapter {
+                    title,
+                    start_time: Duration::from_nanos(start.max(0) as u64),
+                    thumbnail_url: None,
+                });
+            }
+            Self::collect_toc_chapters(&entry.sub_entries(), chapters);
+        }
+    }
+
+    fn video_filter(&self) -> Option<gst::Bin> {
+        self.playbin
+            .lock()
+            .unwrap()
+            .as_ref()?
+            .property::<Option<gst::Element>>("video-filter")?
+            .downcast::<gst::Bin>()
+            .ok()
+    }
 
-        // Update state
+    /// Catch the next frame decoded, before the video filter changes it
+    async fn next_source_sample(&self) -> Result<gst::Sample> {
+        let pad = self
+            .video_filter()
+            .and_then(|filter| filter.by_name("reel-crop"))
+            .and_then(|crop| crop.static_pad("sink"))
+            .ok_or_else(|| anyhow::anyhow!("No video is playing"))?;
+
+        let (respond_to, response) = tokio::sync::oneshot::channel();
+        let respond_to = Mutex::new(Some(respond_to));
+        pad.add_probe(gst::PadProbeType::BUFFER, move |pad, probe_info| {
+            let caps = pad.current_caps();
+            let sample = probe_info
+                .buffer()
+                .zip(caps.as_ref())
+                .map(|(buffer, caps)| {
+                    gst::Sample::builder()
+                        .buffer(&buffer.to_owned())
+                        .caps(caps)
+                        .build()
+                });
+            if let Some(respond_to) = respond_to.lock().unwrap().take() {
+                let _ = respond_to.send(sample);
+            }
+            gst::PadProbeReturn::Remove
+        });
+
+        match glib::future_with_timeout(SOURCE_FRAME_TIMEOUT, response).await {
+            Ok(Ok(Some(sample))) => Ok(sample),
+            _ => Err(anyhow::anyhow!("No frame arrived, is the video playing?")),
+        }
+    }
+
+    fn find_element(
+        bin: &gst::Bin,
+        predicate: impl Fn(&gst::Element) -> bool,
+    ) -> Option<gst::Element> {
+        bin.iterate_recurse()
+            .into_iter()
+            .filter_map(Result::ok)
+            .find(|element| predicate(element))
+    }
+
+    fn element_klass(element: &gst::Element) -> Option<String> {
+        element
+            .factory()?
+            .metadata(gst::ELEMENT_METADATA_KLASS)
+            .map(|klass| klass.to_string())
+    }
+
+    /// rgvolume applies the gain tags; without it playbin plays tracks as they are
+    fn create_replaygain_filter(mode: ReplayGainMode) -> Option<gst::Element> {
+        if mode == ReplayGainMode::Off {
+            return None;
+        }
+        match gst::ElementFactory::make("rgvolume")
+            .name("reel-replaygain")
+            .property("album-mode", mode == ReplayGainMode::Album)
+            .build()
         {
-            let mut state = self.state.write().await;
-            *state = PlayerState::Loading;
-            debug!("GStreamerPlayer::load_media() - State set to Loading");
+            Ok(filter) => Some(filter),
+            Err(e) => {
+                warn!(
+                    "GStreamerPlayer - rgvolume not available, ReplayGain disabled: {}",
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// scaletempo and ReplayGain, then audiodynamic with a volume for its make up gain and
+    /// the equalizer, each only when `AudioFilterParts` asks for it. playbin only takes
+    /// an audio filter before it starts, so effects turned on while an item plays that
+    /// was loaded without them apply from the next load.
+    fn create_audio_filter(
+        mode: ReplayGainMode,
+        effects: &Arc<Mutex<AudioEffects>>,
+        passthrough: bool,
+    ) -> Option<gst::Element> {
+        let parts = AudioFilterParts::new(mode, &effects.lock().unwrap(), passthrough);
+        let make = |factory: &str, name: &str| {
+            gst::ElementFactory::make(factory)
+                .name(name)
+                .build()
+                .map_err(|e| warn!("GStreamerPlayer - {} not available: {}", factory, e))
+                .ok()
+        };
+        let mut elements: Vec<gst::Element> = Vec::new();
+        // scaletempo keeps the pitch when playing faster or slower
+        if parts.tempo {
+            elements.extend(make("scaletempo", "reel-scaletempo"));
+        }
+        if parts.replaygain {
+            elements.extend(Self::create_replaygain_filter(mode));
+        }
+        // Without these only ReplayGain is left to do
+        if parts.effects
+            && let (Some(convert), Some(dynamic), Some(makeup), Some(equalizer), Some(output)) = (
+                make("audioconvert", "reel-audio-convert"),
+                make("audiodynamic", "reel-dynamic"),
+                make("volume", "reel-makeup"),
+                make("equalizer-10bands", "reel-equalizer"),
+                make("audioconvert", "reel-audio-output"),
+            )
+        {
+            dynamic.set_property_from_str("mode", "compressor");
+            dynamic.set_property_from_str("characteristics", "soft-knee");
+            elements.extend([convert, dynamic, makeup, equalizer, output]);
+        }
+        match elements.len() {
+            0 => return None,
+            1 => return elements.pop(),
+            _ => {}
+        }
+
+        let bin = gst::Bin::with_name("reel-audio-filter");
+        bin.add_many(&elements).ok()?;
+        gst::Element::link_many(&elements).ok()?;
+        let sink_pad = elements.first()?.static_pad("sink")?;
+        let ghost_sink = gst::GhostPad::with_target(&sink_pad).ok()?;
+        ghost_sink.set_active(true).ok()?;
+        bin.add_pad(&ghost_sink).ok()?;
+        let src_pad = elements.last()?.static_pad("src")?;
+        let ghost_src = gst::GhostPad::with_target(&src_pad).ok()?;
+        ghost_src.set_active(true).ok()?;
+        bin.add_pad(&ghost_src).ok()?;
+
+        Self::apply_audio_effects(&bin, &effects.lock().unwrap());
+        Some(bin.upcast())
+    }
+
+    /// The audio filter if it has this name, or its element with this name
+    fn audio_filter_element(&self, name: &str) -> Option<gst::Element> {
+        let filter = self
+            .playbin
+            .lock()
+            .unwrap()
+            .as_ref()?
+            .property::<Option<gst::Element>>("audio-filter")?;
+        if filter.name() == name {
+            return Some(filter);
+        }
+        filter.downcast::<gst::Bin>().ok()?.by_name(name)
+    }
+
+    /// Seek at `rate`, within the A-B loop as a segment that reports its end instead of
+    /// playing on
+    fn seek_playbin(
+        playbin: &gst::Element,
+        position: Duration,
+        flags: gst::SeekFlags,
+        rate: f64,
+        ab_loop: Option<(Duration, Duration)>,
+    ) -> Result<(), glib::BoolError> {
+        let (flags, stop_type, stop) = match ab_loop {
+            Some((_, end)) if position < end => (
+                flags | gst::SeekFlags::SEGMENT,
+                gst::SeekType::Set,
+                Some(gst::ClockTime::from_nseconds(end.as_nanos() as u64)),
+            ),
+            _ => (flags, gst::SeekType::None, gst::ClockTime::NONE),
+        };
+        playbin.seek(
+            rate,
+            flags,
+            gst::SeekType::Set,
+            gst::ClockTime::from_nseconds(position.as_nanos() as u64),
+            stop_type,
+            stop,
+        )
+    }
+
+    fn apply_audio_effects(filter: &gst::Bin, effects: &AudioEffects) {
+        let compression = effects.preset.compression();
+        if let Some(dynamic) = filter.by_name("reel-dynamic") {
+            // audiodynamic's ratio is output over input above the threshold
+            let (threshold, ratio) =
+                compression.map_or((1.0, 1.0), |c| (c.threshold, 1.0 / c.ratio));
+            dynamic.set_property("threshold", threshold as f32);
+            dynamic.set_property("ratio", ratio as f32);
         }
+        if let Some(makeup) = filter.by_name("reel-makeup") {
+            makeup.set_property("volume", compression.map_or(1.0, |c| c.makeup));
+        }
+        if let Some(equalizer) = filter.by_name("reel-equalizer") {
+            for (band, gain) in effects.band_gains().iter().enumerate() {
+                equalizer.set_property(&format!("band{}", band), *gain);
+            }
+        }
+    }
+
+    /// Audio sinks of PipeWire, PulseAudio or ALSA, keyed by a name that
+    /// survives restarts where the sound server provides one
+    fn audio_sink_devices() -> Vec<(String, gst::Device)> {
+        let monitor = gst::DeviceMonitor::new();
+        monitor.add_filter(Some("Audio/Sink"), None);
+        if monitor.start().is_err() {
+            warn!("GStreamerPlayer - Failed to start the audio device monitor");
+            return Vec::new();
+        }
+        let devices = monitor
+            .devices()
+            .into_iter()
+            .map(|device| {
+                let name = device
+                    .properties()
+                    .and_then(|props| {
+                        ["node.name", "device.name", "alsa.name"]
+                            .iter()
+                            .find_map(|key| props.get::<String>(*key).ok())
+                    })
+                    .unwrap_or_else(|| device.display_name().to_string());
+                (name, device)
+            })
+            .collect();
+        monitor.stop();
+        devices
+    }
+
+    /// Caps of the compressed formats a receiver can take for these codecs
+    fn passthrough_caps(codecs: &[String]) -> Vec<&'static str> {
+        let mut caps: Vec<&'static str> = Vec::new();
+        for codec in codecs {
+            let name = match codec.as_str() {
+                "ac3" => "audio/x-ac3",
+                "eac3" => "audio/x-eac3",
+                // DTS-HD streams carry a DTS core and share its caps
+                "dts" | "dts-hd" => "audio/x-dts",
+                "truehd" => "audio/x-true-hd",
+                _ => continue,
+            };
+            if !caps.contains(&name) {
+                caps.push(name);
+            }
+        }
+        caps
+    }
+
+    /// The chosen output behind a capsfilter that only lets through raw audio and the
+    /// passthrough formats, so playbin decodes everything else. `None` keeps playbin's
+    /// own sink when nothing was changed.
+    fn create_audio_sink(
+        device_name: &str,
+        device: Option<&gst::Device>,
+        passthrough: &[String],
+    ) -> Option<gst::Element> {
+        if device_name.is_empty() && passthrough.is_empty() {
+            return None;
+        }
+
+        let sink = device.and_then(|device| device.create_element(None).ok());
+        if sink.is_none() && !device_name.is_empty() {
+            warn!(
+                "GStreamerPlayer - Audio device {} not found, using the default",
+                device_name
+            );
+        }
+        let sink = match sink {
+            Some(sink) => sink,
+            None => gst::ElementFactory::make("autoaudiosink")
+                .build()
+                .map_err(|e| warn!("GStreamerPlayer - autoaudiosink not available: {}", e))
+                .ok()?,
+        };
+
+        let mut caps = gst::Caps::new_empty_simple("audio/x-raw");
+        {
+            let caps = caps.get_mut()?;
+            for name in Self::passthrough_caps(passthrough) {
+                caps.append_structure(gst::Structure::new_empty(name));
+            }
+        }
+        let filter = gst::ElementFactory::make("capsfilter")
+            .property("caps", &caps)
+            .build()
+            .ok()?;
+
+        let bin = gst::Bin::with_name("reel-audio-sink");
+        bin.add_many([&filter, &sink]).ok()?;
+        filter.link(&sink).ok()?;
+        let sink_pad = filter.static_pad("sink")?;
+        let ghost_sink = gst::GhostPad::with_target(&sink_pad).ok()?;
+        ghost_sink.set_active(true).ok()?;
+        bin.add_pad(&ghost_sink).ok()?;
+        Some(bin.upcast())
+    }
+
+    /// videocrop, videobalance and capssetter between the decoders and the sink. playbin
+    /// can't scale its output, so zoom and pan are done by cropping as well.
+    fn create_video_filter(adjustments: &Arc<Mutex<VideoAdjustments>>) -> Option<gst::Element> {
+        let make = |factory: &str, name: &str| {
+            gst::ElementFactory::make(factory)
+                .name(name)
+                .build()
+                .map_err(|e| warn!("GStreamerPlayer - {} not available: {}", factory, e))
+                .ok()
+        };
+        let crop = make("videocrop", "reel-crop")?;
+        let convert = make("videoconvert", "reel-convert")?;
+        let balance = make("videobalance", "reel-balance")?;
+        // capssetter is only needed for the aspect override
+        let aspect = make("capssetter", "reel-aspect");
+
+        let bin = gst::Bin::with_name("reel-video-filter");
+        bin.add_many([&crop, &convert, &balance]).ok()?;
+        gst::Element::link_many([&crop, &convert, &balance]).ok()?;
+        let last = match aspect {
+            Some(aspect) => {
+                bin.add(&aspect).ok()?;
+                balance.link(&aspect).ok()?;
+                aspect
+            }
+            None => balance,
+        };
+
+        let sink_pad = crop.static_pad("sink")?;
+        let ghost_sink = gst::GhostPad::with_target(&sink_pad).ok()?;
+        ghost_sink.set_active(true).ok()?;
+        bin.add_pad(&ghost_sink).ok()?;
+        let src_pad = last.static_pad("src")?;
+        let ghost_src = gst::GhostPad::with_target(&src_pad).ok()?;
+        ghost_src.set_active(true).ok()?;
+        bin.add_pad(&ghost_src).ok()?;
+
+        // Crop and aspect depend on the frame size, so apply again whenever it is negotiated
+        let weak_bin = bin.downgrade();
+        let adjustments = adjustments.clone();
+        sink_pad.connect_notify(Some("caps"), move |_, _| {
+            if let Some(bin) = weak_bin.upgrade() {
+                Self::apply_video_adjustments(&bin, &adjustments.lock().unwrap());
+            }
+        });
+
+        Some(bin.upcast())
+    }
+
+    fn apply_video_adjustments(filter: &gst::Bin, adjustments: &VideoAdjustments) {
+        if let Some(balance) = filter.by_name("reel-balance") {
+            balance.set_property("brightness", adjustments.brightness as f64 / 100.0);
+            balance.set_property("contrast", 1.0 + adjustments.contrast as f64 / 100.0);
+            balance.set_property("saturation", 1.0 + adjustments.saturation as f64 / 100.0);
+        }
+
+        let Some(crop) = filter.by_name("reel-crop") else {
+            return;
+        };
+        let Some(info) = crop
+            .static_pad("sink")
+            .and_then(|pad| pad.current_caps())
+            .and_then(|caps| gst_video::VideoInfo::from_caps(&caps).ok())
+        else {
+            return;
+        };
+        let (width, height) = (info.width(), info.height());
+
+        let (left, right, top, bottom) = adjustments.crop_edges(width, height);
+        crop.set_property("left", left as i32);
+        crop.set_property("right", right as i32);
+        crop.set_property("top", top as i32);
+        crop.set_property("bottom", bottom as i32);
+
+        // The override is applied as the pixel aspect ratio giving that shape to the full frame
+        if let Some(aspect) = filter.by_name("reel-aspect") {
+            let caps = match adjustments.aspect_ratio.and_then(|ratio| {
+                gst::Fraction::approximate_f64(ratio * height as f64 / width as f64)
+            }) {
+                Some(par) => gst::Caps::builder("video/x-raw")
+                    .field("pixel-aspect-ratio", par)
+                    .build(),
+                None => gst::Caps::new_empty_simple("video/x-raw"),
+            };
+            aspect.set_property("caps", &caps);
+        }
+    }
+
+    /// Scan the luma plane of an 8-bit planar YUV frame
+    fn frame_black_bars(buffer: &gst::BufferRef, info: &gst_video::VideoInfo) -> Option<CropRect> {
+        let format_info = info.format_info();
+        if !format_info.is_yuv() || format_info.n_planes() < 2 || format_info.bits() != 8 {
+            debug!(
+                "GStreamerPlayer - Can't look for black bars in {:?} frames",
+                info.format()
+            );
+            return None;
+        }
+        let frame = gst_video::VideoFrameRef::from_buffer_ref_readable(buffer, info).ok()?;
+        detect_black_bars(
+            frame.plane_data(0).ok()?,
+            info.width() as usize,
+            info.height() as usize,
+            frame.plane_stride()[0] as usize,
+        )
+    }
+
+    fn handle_bus_message(
+        msg: &gst::Message,
+        events: &PlayerEventSource,
+        chapters: &Mutex<Vec<Chapter>>,
+        advancing: &Mutex<bool>,
+        ab_loop: &Mutex<Option<(Duration, Duration)>>,
+        playback_rate: &Mutex<f64>,
+        reload_position: &Mutex<Option<Duration>>,
+    ) {
+        use gst::MessageView;
+
+        match msg.view() {
+            MessageView::AsyncDone(_) => {
+                // A stream restarted for a new subtitle file is ready to seek again
+                if let Some(position) = reload_position.lock().unwrap().take()
+                    && let Some(pipeline) = msg.src().and_then(|s| s.downcast_ref::<gst::Element>())
+                    && let Err(e) = Self::seek_playbin(
+                        pipeline,
+                        position,
+                        gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+                        *playback_rate.lock().unwrap(),
+                        *ab_loop.lock().unwrap(),
+                    )
+                {
+                    warn!(
+                        "GStreamerPlayer - Failed to restore position after adding subtitle: {}",
+                        e
+                    );
+                }
+            }
+            MessageView::SegmentDone(_) => {
+                // The end of the A-B loop; a non-flushing seek continues without a gap
+                if let Some((start, end)) = *ab_loop.lock().unwrap()
+                    && let Some(pipeline) = msg.src().and_then(|s| s.downcast_ref::<gst::Element>())
+                    && let Err(e) = Self::seek_playbin(
+                        pipeline,
+                        start,
+                        gst::SeekFlags::ACCURATE,
+                        *playback_rate.lock().unwrap(),
+                        Some((start, end)),
+                    )
+                {
+                    warn!("GStreamerPlayer - Failed to repeat the A-B loop: {}", e);
+                }
+            }
+            MessageView::StreamStart(_) => {
+                // The URI set in about-to-finish is playing now
+                if std::mem::take(&mut *advancing.lock().unwrap()) {
+                    info!("GStreamerPlayer - Bus message: Next media started");
+                    chapters.lock().unwrap().clear();
+                    events.emit(PlayerEvent::NextMediaStarted);
+                    events.emit(PlayerEvent::TracksChanged);
+                }
+            }
+            MessageView::Eos(_) => {
+                info!("GStreamerPlayer - Bus message: End of stream");
+                events.set_state(PlayerState::Stopped);
+                events.emit(PlayerEvent::EndOfStream);
+            }
+            MessageView::Error(err) => {
+                error!(
+                    "GStreamerPlayer - Bus error from {:?}: {} ({:?})",
+                    err.src().map(|s| s.path_string()),
+                    err.error(),
+                    err.debug()
+                );
+                events.set_state(PlayerState::Error);
+                events.emit(PlayerEvent::Error(err.error().to_string()));
+            }
+            MessageView::StateChanged(state_changed) => {
+                // Only the pipeline's own transitions reflect the player state
+                if state_changed
+                    .src()
+                    .is_some_and(|s| s.downcast_ref::<gst::Pipeline>().is_some())
+                {
+                    debug!(
+                        "GStreamerPlayer - State changed from {:?} to {:?}",
+                        state_changed.old(),
+                        state_changed.current()
+                    );
+                    // Prerolling passes through Paused, so only report it once loaded
+                    match state_changed.current() {
+                        gst::State::Playing => events.set_state(PlayerState::Playing),
+                        gst::State::Paused if events.state() != PlayerState::Loading => {
+                            events.set_state(PlayerState::Paused)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            MessageView::Buffering(buffering) => {
+                let percent = buffering.percent();
+                debug!("GStreamerPlayer - Buffering: {}%", percent);
+                events.emit(PlayerEvent::Buffering(percent.clamp(0, 100) as u8));
+            }
+            MessageView::StreamsSelected(_) => {
+                events.emit(PlayerEvent::TracksChanged);
+            }
+            MessageView::Toc(toc) => {
+                let (toc, _updated) = toc.toc();
+                let mut parsed = Vec::new();
+                Self::collect_toc_chapters(&toc.entries(), &mut parsed);
+                parsed.sort_by_key(|chapter| chapter.start_time);
+                debug!(
+                    "GStreamerPlayer - Table of contents with {} chapters",
+                    parsed.len()
+                );
+
+                let mut current = chapters.lock().unwrap();
+                if *current != parsed {
+                    *current = parsed;
+                    drop(current);
+                    events.emit(PlayerEvent::ChaptersChanged);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Push the playback position to subscribers while the pipeline is running
+    fn start_position_updates(&self) {
+        if let Some(timer) = self.position_timer.lock().unwrap().take() {
+            timer.remove();
+        }
+
+        let playbin = self.playbin.clone();
+        let events = self.events.clone();
+        let timer = glib::timeout_add_local(POSITION_UPDATE_INTERVAL, move || {
+            if let Some(playbin) = playbin.lock().unwrap().as_ref()
+                && let Some(position) = playbin.query_position::<gst::ClockTime>()
+            {
+                let duration = playbin
+                    .query_duration::<gst::ClockTime>()
+                    .map(|d| Duration::from_nanos(d.nseconds()));
+                events.emit(PlayerEvent::PositionChanged {
+                    position: Duration::from_nanos(position.nseconds()),
+                    duration,
+                });
+            }
+            glib::ControlFlow::Continue
+        });
+        *self.position_timer.lock().unwrap() = Some(timer);
+    }
+}
+
+#[async_trait(?Send)]
+impl MediaPlayer for GStreamerPlayer {
+    fn backend_name(&self) -> &'static str {
+        "GStreamer"
+    }
+
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            frame_step_backward: false,
+            upscaling: false,
+            playback_speed: true,
+            subtitle_delay: true,
+            audio_delay: true,
+            external_subtitles: true,
+            gapless: true,
+            replaygain: true,
+            video_adjustments: true,
+            black_bar_detection: true,
+            screenshots: true,
+            audio_output_selection: true,
+            audio_passthrough: true,
+            audio_effects: true,
+            ab_loop: true,
+        }
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.events.subscribe()
+    }
+
+    fn create_video_widget(&self) -> gtk4::Widget {
+        info!("GStreamerPlayer::create_video_widget() - Starting video widget creation");
+
+        // Create a GTK Picture widget for video display. The sink's paintable is not
+        // tied to a window, so the picture can be moved into the picture-in-picture
+        // window and back while playing.
+        debug!("GStreamerPlayer::create_video_widget() - Creating GTK Picture widget");
+        let picture = gtk4::Picture::new();
+        picture.set_can_shrink(true);
+        picture.set_vexpand(true);
+        picture.set_hexpand(true);
+        debug!("GStreamerPlayer::create_video_widget() - Picture widget created");
+
+        // Check if we should force fallback mode or use alternative sink
+        let force_fallback = std::env::var("REEL_FORCE_FALLBACK_SINK").is_ok();
+        let use_gl_sink = std::env::var("REEL_USE_GL_SINK").is_ok();
+
+        // Try to create optimized video sink with glsinkbin wrapper
+        let video_sink = self.create_optimized_video_sink(force_fallback, use_gl_sink);
+
+        // If we have a gtk4paintablesink, extract and set its paintable
+        if let Some(ref sink) = video_sink
+            && let Some(gtk_sink) = self.extract_gtk4_sink(sink)
+        {
+            let paintable = gtk_sink.property::<gdk::Paintable>("paintable");
+            picture.set_paintable(Some(&paintable));
+            debug!("GStreamerPlayer::create_video_widget() - Paintable set on Picture widget");
+        }
+
+        // Store the video sink
+        *self.video_sink.lock().unwrap() = video_sink;
+
+        // Return the widget
+        let widget = picture.upcast::<gtk4::Widget>();
+        info!("GStreamerPlayer::create_video_widget() - Video widget creation complete");
+        widget
+    }
+
+    async fn load_media(&self, url: &str) -> Result<()> {
+        info!("GStreamerPlayer::load_media() - Loading media: {}", url);
+        debug!("GStreamerPlayer::load_media() - Full URL: {}", url);
+
+        self.events.set_state(PlayerState::Loading);
+        debug!("GStreamerPlayer::load_media() - State set to Loading");
 
         // Clear existing playbin if any
         if let Some(old_playbin) = self.playbin.lock().unwrap().as_ref() {
@@ -515,6 +1183,13 @@ impl GStreamerPlayer {
                 .context("Failed to set old playbin to null state")?;
         }
 
+        let audio_only = *self.audio_only.lock().unwrap();
+        let flags = if audio_only {
+            "soft-volume+audio"
+        } else {
+            "soft-colorbalance+deinterlace+soft-volume+audio+video+text"
+        };
+
         // Try to create playbin3 first (better subtitle support)
         let (playbin, is_playbin3) = if gst::ElementFactory::find("playbin3").is_some() {
             info!("GStreamerPlayer::load_media() - Creating playbin3 element");
@@ -524,11 +1199,8 @@ impl GStreamerPlayer {
                 .build()
                 .context("Failed to create playbin3 element")?;
 
-            // Enable all features including text overlay
-            pb.set_property_from_str(
-                "flags",
-                "soft-colorbalance+deinterlace+soft-volume+audio+video+text",
-            );
+            // Enable all features including text overlay, or only audio for music
+            pb.set_property_from_str("flags", flags);
 
             // playbin3 has QoS always enabled, no property needed
 
@@ -555,11 +1227,8 @@ impl GStreamerPlayer {
                 .build()
                 .context("Failed to create playbin element")?;
 
-            // Enable all features including text overlay
-            pb.set_property_from_str(
-                "flags",
-                "soft-colorbalance+deinterlace+soft-volume+audio+video+text",
-            );
+            // Enable all features including text overlay, or only audio for music
+            pb.set_property_from_str("flags", flags);
 
             // Enable QoS for better performance
             if pb.has_property("enable-qos") {
@@ -589,8 +1258,31 @@ impl GStreamerPlayer {
         // Store whether we're using playbin3
         *self.is_playbin3.lock().unwrap() = is_playbin3;
 
+        let passthrough = self.audio_passthrough.lock().unwrap().clone();
+        if let Some(filter) = Self::create_audio_filter(
+            *self.replaygain.lock().unwrap(),
+            &self.audio_effects,
+            !passthrough.is_empty(),
+        ) {
+            playbin.set_property("audio-filter", &filter);
+        }
+        if let Some(sink) = Self::create_audio_sink(
+            &self.audio_device.lock().unwrap(),
+            self.audio_sink_device.lock().unwrap().as_ref(),
+            &passthrough,
+        ) {
+            playbin.set_property("audio-sink", &sink);
+        }
+
+        // Always in place, playbin only takes a video filter before it starts
+        if !audio_only && let Some(filter) = Self::create_video_filter(&self.video_adjustments) {
+            playbin.set_property("video-filter", &filter);
+        }
+
         // Use our stored video sink if available
-        if let Some(sink) = self.video_sink.lock().unwrap().as_ref() {
+        if audio_only {
+            debug!("GStreamerPlayer::load_media() - Audio only, no video sink needed");
+        } else if let Some(sink) = self.video_sink.lock().unwrap().as_ref() {
             debug!("GStreamerPlayer::load_media() - Setting video sink on playbin");
             playbin.set_property("video-sink", sink);
             info!("GStreamerPlayer::load_media() - Video sink configured");
@@ -635,18 +1327,61 @@ impl GStreamerPlayer {
         let bus = playbin.bus().context("Failed to get playbin bus")?;
         debug!("GStreamerPlayer::load_media() - Got playbin bus");
 
-        let state_clone = self.state.clone();
+        self.chapters.lock().unwrap().clear();
+        *self.next_uri.lock().unwrap() = None;
+        *self.advancing.lock().unwrap() = false;
+        // A new playbin starts at 1x without a loop
+        *self.playback_rate.lock().unwrap() = 1.0;
+        *self.ab_loop.lock().unwrap() = None;
+        *self.reload_position.lock().unwrap() = None;
+        let events = self.events.clone();
+        let chapters = self.chapters.clone();
+        let advancing = self.advancing.clone();
+        let ab_loop = self.ab_loop.clone();
+        let playback_rate = self.playback_rate.clone();
+        let reload_position = self.reload_position.clone();
         let _ = bus
             .add_watch(move |_, msg| {
-                let state = state_clone.clone();
-                let msg = msg.clone();
-                glib::spawn_future_local(async move {
-                    Self::handle_bus_message(&msg, state).await;
-                });
+                Self::handle_bus_message(
+                    msg,
+                    &events,
+                    &chapters,
+                    &advancing,
+                    &ab_loop,
+                    &playback_rate,
+                    &reload_position,
+                );
                 glib::ControlFlow::Continue
             })
             .context("Failed to add bus watch")?;
 
+        // Emitted from the streaming thread while there is still time to set the next URI
+        let next_uri = self.next_uri.clone();
+        let advancing = self.advancing.clone();
+        playbin.connect("about-to-finish", false, move |args| {
+            if let Some(uri) = next_uri.lock().unwrap().take()
+                && let Ok(playbin) = args[0].get::<gst::Element>()
+            {
+                debug!("GStreamerPlayer - About to finish, continuing with {}", uri);
+                playbin.set_property("uri", uri.as_str());
+                *advancing.lock().unwrap() = true;
+            }
+            None
+        });
+
+        // playbin3 announces track changes on the bus, playbin only through signals
+        if !is_playbin3 {
+            for signal in ["audio-changed", "text-changed"] {
+                let events = self.events.clone();
+                playbin.connect(signal, false, move |_| {
+                    events.emit(PlayerEvent::TracksChanged);
+                    None
+                });
+            }
+        }
+
+        self.start_position_updates();
+
         // Set to ready state first
         debug!("GStreamerPlayer::load_media() - Setting playbin to Ready state");
         playbin
@@ -658,7 +1393,7 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn play(&self) -> Result<()> {
+    async fn play(&self) -> Result<()> {
         info!("GStreamerPlayer::play() - Starting playback");
 
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
@@ -794,8 +1529,7 @@ impl GStreamerPlayer {
                 }
             }
 
-            let mut state = self.state.write().await;
-            *state = PlayerState::Playing;
+            self.events.set_state(PlayerState::Playing);
             info!("GStreamerPlayer::play() - Player state set to Playing");
         } else {
             error!("GStreamerPlayer::play() - No playbin available!");
@@ -805,7 +1539,7 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn pause(&self) -> Result<()> {
+    async fn pause(&self) -> Result<()> {
         debug!("Pausing playback");
 
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
@@ -813,43 +1547,45 @@ impl GStreamerPlayer {
                 .set_state(gst::State::Paused)
                 .context("Failed to set playbin to paused state")?;
 
-            let mut state = self.state.write().await;
-            *state = PlayerState::Paused;
+            self.events.set_state(PlayerState::Paused);
         }
         Ok(())
     }
 
-    pub async fn stop(&self) -> Result<()> {
+    async fn stop(&self) -> Result<()> {
         debug!("Stopping playback");
 
+        if let Some(timer) = self.position_timer.lock().unwrap().take() {
+            timer.remove();
+        }
+
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             playbin
                 .set_state(gst::State::Null)
                 .context("Failed to set playbin to null state")?;
 
-            let mut state = self.state.write().await;
-            *state = PlayerState::Stopped;
+            self.events.set_state(PlayerState::Stopped);
         }
         Ok(())
     }
 
-    pub async fn seek(&self, position: Duration) -> Result<()> {
+    async fn seek(&self, position: Duration) -> Result<()> {
         debug!("Seeking to {:?}", position);
 
-        let position_ns = position.as_nanos() as i64;
-
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
-            playbin
-                .seek_simple(
-                    gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
-                    gst::ClockTime::from_nseconds(position_ns as u64),
-                )
-                .context("Failed to seek")?;
+            Self::seek_playbin(
+                playbin,
+                position,
+                gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+                *self.playback_rate.lock().unwrap(),
+                *self.ab_loop.lock().unwrap(),
+            )
+            .context("Failed to seek")?;
         }
         Ok(())
     }
 
-    pub async fn get_position(&self) -> Option<Duration> {
+    async fn get_position(&self) -> Option<Duration> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             playbin
                 .query_position::<gst::ClockTime>()
@@ -859,7 +1595,7 @@ impl GStreamerPlayer {
         }
     }
 
-    pub async fn get_duration(&self) -> Option<Duration> {
+    async fn get_duration(&self) -> Option<Duration> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             playbin
                 .query_duration::<gst::ClockTime>()
@@ -869,14 +1605,14 @@ impl GStreamerPlayer {
         }
     }
 
-    pub async fn set_volume(&self, volume: f64) -> Result<()> {
+    async fn set_volume(&self, volume: f64) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             playbin.set_property("volume", volume);
         }
         Ok(())
     }
 
-    pub async fn get_video_dimensions(&self) -> Option<(i32, i32)> {
+    async fn get_video_dimensions(&self) -> Option<(i32, i32)> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             // Get video sink's pad
             if let Some(video_sink) = playbin.property::<Option<gst::Element>>("video-sink")
@@ -930,47 +1666,13 @@ impl GStreamerPlayer {
         }
     }
 
-    async fn handle_bus_message(msg: &gst::Message, state: Arc<RwLock<PlayerState>>) {
-        use gst::MessageView;
-
-        match msg.view() {
-            MessageView::Eos(_) => {
-                info!("GStreamerPlayer - Bus message: End of stream");
-                let mut state = state.write().await;
-                *state = PlayerState::Stopped;
-            }
-            MessageView::Error(err) => {
-                error!(
-                    "GStreamerPlayer - Bus error from {:?}: {} ({:?})",
-                    err.src().map(|s| s.path_string()),
-                    err.error(),
-                    err.debug()
-                );
-                let mut state = state.write().await;
-                *state = PlayerState::Error;
-            }
-            MessageView::StateChanged(state_changed) => {
-                if state_changed
-                    .src()
-                    .map(|s| s == state_changed.src().unwrap())
-                    .unwrap_or(false)
-                {
-                    debug!(
-                        "GStreamerPlayer - State changed from {:?} to {:?}",
-                        state_changed.old(),
-                        state_changed.current()
-                    );
-                }
-            }
-            MessageView::Buffering(buffering) => {
-                let percent = buffering.percent();
-                debug!("GStreamerPlayer - Buffering: {}%", percent);
-            }
-            _ => {}
-        }
+    async fn set_audio_only(&self, audio_only: bool) -> Result<()> {
+        // The pipeline is built per load, so this applies from the next one
+        *self.audio_only.lock().unwrap() = audio_only;
+        Ok(())
     }
 
-    pub async fn get_state(&self) -> PlayerState {
+    async fn get_state(&self) -> PlayerState {
         // Query GStreamer for the actual state instead of relying on cached state
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             let (_, current, _) = playbin.state(gst::ClockTime::ZERO);
@@ -984,10 +1686,10 @@ impl GStreamerPlayer {
         }
 
         // Fall back to cached state if playbin is not available
-        self.state.read().await.clone()
+        self.events.state()
     }
 
-    pub async fn get_audio_tracks(&self) -> Vec<(i32, String)> {
+    async fn get_audio_tracks(&self) -> Vec<(i32, String)> {
         let mut tracks = Vec::new();
 
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
@@ -1074,7 +1776,7 @@ impl GStreamerPlayer {
         tracks
     }
 
-    pub async fn get_subtitle_tracks(&self) -> Vec<(i32, String)> {
+    async fn get_subtitle_tracks(&self) -> Vec<(i32, String)> {
         let mut tracks = Vec::new();
 
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
@@ -1166,7 +1868,7 @@ impl GStreamerPlayer {
         tracks
     }
 
-    pub async fn set_audio_track(&self, track_index: i32) -> Result<()> {
+    async fn set_audio_track(&self, track_index: i32) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             let is_playbin3 = *self.is_playbin3.lock().unwrap();
 
@@ -1185,7 +1887,53 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn set_subtitle_track(&self, track_index: i32) -> Result<()> {
+    /// Load a sidecar subtitle file. playbin only takes a single subtitle URI, so
+    /// unselected subtitles are skipped once one is loaded, and a selected one
+    /// replaces it. Replacing restarts the stream; the position is restored from the
+    /// bus once it has prerolled instead of waiting for it here.
+    async fn add_subtitle(
+        &self,
+        url: &str,
+        title: &str,
+        _language: Option<&str>,
+        select: bool,
+    ) -> Result<()> {
+        let Some(playbin) = self.playbin.lock().unwrap().clone() else {
+            return Err(anyhow::anyhow!("No media loaded"));
+        };
+
+        if !select && playbin.property::<Option<String>>("suburi").is_some() {
+            debug!(
+                "Skipping external subtitle '{}', one is already loaded",
+                title
+            );
+            return Ok(());
+        }
+
+        let (_, current_state, _) = playbin.state(gst::ClockTime::ZERO);
+        if current_state <= gst::State::Ready {
+            // Not prerolled yet, the subtitle is picked up when playback starts
+            playbin.set_property("suburi", url);
+            info!("Set external subtitle '{}'", title);
+            return Ok(());
+        }
+
+        // suburi is only read when the stream starts, so restart it in place
+        *self.reload_position.lock().unwrap() = playbin
+            .query_position::<gst::ClockTime>()
+            .map(|position| Duration::from_nanos(position.nseconds()));
+        playbin
+            .set_state(gst::State::Ready)
+            .context("Failed to reset playbin for subtitle")?;
+        playbin.set_property("suburi", url);
+        playbin
+            .set_state(current_state)
+            .context("Failed to restore playbin state after adding subtitle")?;
+        info!("Reloading stream with external subtitle '{}'", title);
+        Ok(())
+    }
+
+    async fn set_subtitle_track(&self, track_index: i32) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             info!("Setting subtitle track to index: {}", track_index);
 
@@ -1225,7 +1973,7 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn get_current_audio_track(&self) -> i32 {
+    async fn get_current_audio_track(&self) -> i32 {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             if playbin.has_property("current-audio-stream") {
                 // playbin3 property
@@ -1241,7 +1989,7 @@ impl GStreamerPlayer {
         }
     }
 
-    pub async fn get_current_subtitle_track(&self) -> i32 {
+    async fn get_current_subtitle_track(&self) -> i32 {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             // Check if we have any subtitle tracks available
             let n_text = if playbin.has_property("n-text-streams") {
@@ -1271,33 +2019,341 @@ impl GStreamerPlayer {
         }
     }
 
-    pub async fn set_playback_speed(&self, speed: f64) -> Result<()> {
+    async fn set_playback_speed(&self, speed: f64) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             // GStreamer uses a seek with rate to change playback speed
             let position = playbin.query_position::<gst::ClockTime>();
             if let Some(pos) = position {
-                playbin
-                    .seek(
-                        speed,
-                        gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
-                        gst::SeekType::Set,
-                        pos,
-                        gst::SeekType::None,
-                        gst::ClockTime::NONE,
-                    )
-                    .map_err(|e| anyhow::anyhow!("Failed to set playback speed: {:?}", e))?;
+                Self::seek_playbin(
+                    playbin,
+                    Duration::from_nanos(pos.nseconds()),
+                    gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                    speed,
+                    *self.ab_loop.lock().unwrap(),
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to set playback speed: {:?}", e))?;
+                *self.playback_rate.lock().unwrap() = speed;
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_ab_loop(&self, range: Option<(Duration, Duration)>) -> Result<()> {
+        if let Some((start, end)) = range
+            && end <= start
+        {
+            return Err(anyhow::anyhow!("The loop ends before it starts"));
+        }
+        *self.ab_loop.lock().unwrap() = range;
+
+        if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
+            // Start the first repeat right away, or let playback run on past the end
+            let position = match range {
+                Some((start, _)) => Some(start),
+                None => playbin
+                    .query_position::<gst::ClockTime>()
+                    .map(|pos| Duration::from_nanos(pos.nseconds())),
+            };
+            if let Some(position) = position {
+                Self::seek_playbin(
+                    playbin,
+                    position,
+                    gst::SeekFlags::FLUSH | gst::SeekFlags::ACCURATE,
+                    *self.playback_rate.lock().unwrap(),
+                    range,
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to set A-B loop: {:?}", e))?;
             }
         }
         Ok(())
     }
 
-    pub async fn get_playback_speed(&self) -> f64 {
-        // GStreamer doesn't have a simple way to get current playback rate
-        // We'd need to track it separately or query the segment
-        1.0 // Default to normal speed for now
+    /// Shift subtitle timing; positive values show subtitles later
+    async fn set_subtitle_delay(&self, delay_ms: i64) -> Result<()> {
+        if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
+            if !playbin.has_property("text-offset") {
+                return Err(anyhow::anyhow!(
+                    "Subtitle delay is not supported by this GStreamer version"
+                ));
+            }
+            playbin.set_property("text-offset", delay_ms * 1_000_000);
+        }
+        Ok(())
+    }
+
+    /// Shift audio timing; positive values play audio later
+    async fn set_audio_delay(&self, delay_ms: i64) -> Result<()> {
+        if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
+            playbin.set_property("av-offset", delay_ms * 1_000_000);
+        }
+        Ok(())
+    }
+
+    async fn get_playback_speed(&self) -> f64 {
+        *self.playback_rate.lock().unwrap()
+    }
+
+    async fn get_chapters(&self) -> Vec<Chapter> {
+        self.chapters.lock().unwrap().clone()
+    }
+
+    async fn queue_next(&self, url: Option<&str>) -> Result<()> {
+        *self.next_uri.lock().unwrap() = url.map(|url| url.to_string());
+        Ok(())
+    }
+
+    async fn set_replaygain(&self, mode: ReplayGainMode) -> Result<()> {
+        *self.replaygain.lock().unwrap() = mode;
+
+        // Switching between track and album works in place; turning it on or off
+        // takes effect from the next load
+        if mode != ReplayGainMode::Off
+            && let Some(filter) = self.audio_filter_element("reel-replaygain")
+        {
+            filter.set_property("album-mode", mode == ReplayGainMode::Album);
+        }
+        Ok(())
+    }
+
+    async fn set_audio_effects(&self, effects: AudioEffects) -> Result<()> {
+        *self.audio_effects.lock().unwrap() = effects;
+
+        // Only when the filter was built with the effects, otherwise they apply from the next load
+        if let Some(filter) = self
+            .audio_filter_element("reel-audio-filter")
+            .and_then(|filter| filter.downcast::<gst::Bin>().ok())
+        {
+            Self::apply_audio_effects(&filter, &effects);
+        }
+        Ok(())
+    }
+
+    async fn get_audio_devices(&self) -> Result<Vec<AudioOutputDevice>> {
+        let devices = Self::audio_sink_devices();
+        // Picks up the chosen device if it was plugged in after it was chosen
+        let audio_device = self.audio_device.lock().unwrap().clone();
+        if !audio_device.is_empty() {
+            *self.audio_sink_device.lock().unwrap() = devices
+                .iter()
+                .find(|(name, _)| *name == audio_device)
+                .map(|(_, device)| device.clone());
+        }
+        Ok(devices
+            .into_iter()
+            .map(|(name, device)| AudioOutputDevice {
+                name,
+                description: device.display_name().to_string(),
+            })
+            .collect())
+    }
+
+    // The audio sink is picked when playbin is created, so the device and passthrough
+    // take effect from the next load
+    async fn set_audio_device(&self, name: &str) -> Result<()> {
+        let mut audio_device = self.audio_device.lock().unwrap();
+        if *audio_device == name {
+            return Ok(());
+        }
+        *audio_device = name.to_string();
+        *self.audio_sink_device.lock().unwrap() = if name.is_empty() {
+            None
+        } else {
+            Self::audio_sink_devices()
+                .into_iter()
+                .find(|(device_name, _)| device_name == name)
+                .map(|(_, device)| device)
+        };
+        Ok(())
+    }
+
+    async fn set_audio_passthrough(&self, codecs: &[String]) -> Result<()> {
+        *self.audio_passthrough.lock().unwrap() = codecs.to_vec();
+        Ok(())
+    }
+
+    async fn set_video_adjustments(&self, adjustments: VideoAdjustments) -> Result<()> {
+        *self.video_adjustments.lock().unwrap() = adjustments;
+
+        // Otherwise applied when the filter is created on the next load
+        if let Some(filter) = self.video_filter() {
+            Self::apply_video_adjustments(&filter, &adjustments);
+        }
+        Ok(())
+    }
+
+    async fn detect_black_bars(&self) -> Result<Option<CropRect>> {
+        let sample = self
+            .next_source_sample()
+            .await
+            .context("No frame to look for black bars in")?;
+        let crop = sample
+            .buffer()
+            .zip(sample.caps())
+            .and_then(|(buffer, caps)| {
+                let info = gst_video::VideoInfo::from_caps(caps).ok()?;
+                Self::frame_black_bars(buffer, &info)
+            });
+        Ok(crop)
+    }
+
+    async fn screenshot(&self, path: &Path, include_subtitles: bool) -> Result<()> {
+        let sample = if include_subtitles {
+            // The last frame handed to the sink, with subtitles drawn on it
+            let playbin = self
+                .playbin
+                .lock()
+                .unwrap()
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("No video is playing"))?;
+            playbin
+                .property::<Option<gst::Sample>>("sample")
+                .ok_or_else(|| anyhow::anyhow!("No frame to take a screenshot of"))?
+        } else {
+            // The decoded frame before the video filter crops or adjusts it
+            self.next_source_sample()
+                .await
+                .context("No frame to take a screenshot of")?
+        };
+
+        // Encoding runs in its own pipeline, so the main loop keeps going meanwhile
+        let png_caps = gst::Caps::new_empty_simple("image/png");
+        let png = gst_video::convert_sample_future(
+            &sample,
+            &png_caps,
+            Some(gst::ClockTime::from_seconds(5)),
+        )
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to encode screenshot: {}", e))?;
+
+        let buffer = png
+            .buffer()
+            .ok_or_else(|| anyhow::anyhow!("Encoded screenshot is empty"))?;
+        let map = buffer
+            .map_readable()
+            .map_err(|e| anyhow::anyhow!("Failed to read encoded screenshot: {}", e))?;
+        // The controller runs on the GTK thread, outside the tokio runtime
+        std::fs::write(path, map.as_slice())
+            .with_context(|| format!("Failed to write screenshot to {}", path.display()))
+    }
+
+    async fn get_stats(&self) -> Result<PlaybackStats> {
+        let playbin = self
+            .playbin
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("No media loaded"))?;
+        let mut stats = PlaybackStats::default();
+
+        // Property and signal names differ between playbin versions
+        let playbin3 = playbin.has_property("n-video-streams");
+        let current = |playbin3_name: &str, playbin_name: &str| {
+            let name = if playbin3 {
+                playbin3_name
+            } else {
+                playbin_name
+            };
+            if playbin.has_property(name) {
+                playbin.property::<i32>(name).max(0)
+            } else {
+                0
+            }
+        };
+        let current_video = current("current-video-stream", "current-video");
+        let current_audio = current("current-audio-stream", "current-audio");
+        let (video_tags, audio_tags, video_pad) = if playbin3 {
+            (
+                playbin.emit_by_name::<Option<gst::TagList>>(
+                    "get-video-stream-tags",
+                    &[&current_video],
+                ),
+                playbin.emit_by_name::<Option<gst::TagList>>(
+                    "get-audio-stream-tags",
+                    &[&current_audio],
+                ),
+                playbin.emit_by_name::<Option<gst::Pad>>("get-video-stream-pad", &[&current_video]),
+            )
+        } else {
+            (
+                playbin.emit_by_name::<Option<gst::TagList>>("get-video-tags", &[&current_video]),
+                playbin.emit_by_name::<Option<gst::TagList>>("get-audio-tags", &[&current_audio]),
+                playbin.emit_by_name::<Option<gst::Pad>>("get-video-pad", &[&current_video]),
+            )
+        };
+        stats.video_codec = video_tags
+            .as_ref()
+            .and_then(|tags| tags.get::<gst::tags::VideoCodec>())
+            .map(|codec| codec.get().to_string());
+        stats.audio_codec = audio_tags
+            .as_ref()
+            .and_then(|tags| tags.get::<gst::tags::AudioCodec>())
+            .map(|codec| codec.get().to_string());
+        stats.container = video_tags
+            .iter()
+            .chain(audio_tags.iter())
+            .find_map(|tags| tags.get::<gst::tags::ContainerFormat>())
+            .map(|format| format.get().to_string());
+
+        if let Some(caps) = video_pad.and_then(|pad| pad.current_caps())
+            && let Ok(info) = gst_video::VideoInfo::from_caps(&caps)
+        {
+            stats.resolution = Some((info.width(), info.height()));
+            let fps = info.fps();
+            if fps.numer() > 0 && fps.denom() > 0 {
+                stats.frame_rate = Some(fps.numer() as f64 / fps.denom() as f64);
+            }
+        }
+
+        if let Some(decoder) = playbin
+            .downcast_ref::<gst::Bin>()
+            .and_then(|bin| {
+                Self::find_element(bin, |element| {
+                    Self::element_klass(element)
+                        .is_some_and(|klass| klass.contains("Decoder") && klass.contains("Video"))
+                })
+            })
+            .and_then(|decoder| decoder.factory())
+        {
+            let hardware = decoder
+                .metadata(gst::ELEMENT_METADATA_KLASS)
+                .is_some_and(|klass| klass.contains("Hardware"));
+            stats.decoder = Some(decoder.name().to_string());
+            stats.hwdec = hardware.then(|| decoder.name().to_string());
+        }
+
+        // Every base sink counts what it rendered and dropped as late
+        let video_sink = playbin.property::<Option<gst::Element>>("video-sink");
+        let sink_stats = video_sink
+            .and_then(|sink| match sink.downcast_ref::<gst::Bin>() {
+                Some(bin) => Self::find_element(bin, |element| element.has_property("stats")),
+                None => sink.has_property("stats").then_some(sink),
+            })
+            .map(|sink| sink.property::<gst::Structure>("stats"));
+        stats.dropped_frames = sink_stats
+            .as_ref()
+            .and_then(|stats| stats.get::<u64>("dropped").ok());
+        // Sinks only report frames they dropped, so delayed_frames stays unknown
+
+        let mut query = gst::query::Buffering::new(gst::Format::Time);
+        if playbin.query(&mut query) {
+            let (_, percent) = query.percent();
+            stats.buffer_percent = Some(percent.clamp(0, 100) as u8);
+            let (_, average_in, _, _) = query.stats();
+            stats.network_bytes_per_second = (average_in > 0).then_some(average_in as u64);
+            let (_, stop, _) = query.range();
+            if let (gst::GenericFormattedValue::Time(Some(stop)), Some(position)) =
+                (stop, playbin.query_position::<gst::ClockTime>())
+            {
+                stats.cache_duration = Some(Duration::from_nanos(
+                    stop.saturating_sub(position).nseconds(),
+                ));
+            }
+        }
+
+        Ok(stats)
     }
 
-    pub async fn frame_step_forward(&self) -> Result<()> {
+    async fn frame_step_forward(&self) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             // GStreamer frame stepping requires pausing first and then seeking
             // Frame stepping with Step events is complex and not well-supported
@@ -1313,15 +2369,7 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn frame_step_backward(&self) -> Result<()> {
-        // GStreamer doesn't natively support backward frame stepping
-        // We'd need to implement this with seeking
-        Err(anyhow::anyhow!(
-            "Backward frame stepping not supported in GStreamer"
-        ))
-    }
-
-    pub async fn toggle_mute(&self) -> Result<()> {
+    async fn toggle_mute(&self) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             let current_mute = playbin.property::<bool>("mute");
             playbin.set_property("mute", !current_mute);
@@ -1329,7 +2377,7 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn is_muted(&self) -> bool {
+    async fn is_muted(&self) -> bool {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             playbin.property::<bool>("mute")
         } else {
@@ -1337,7 +2385,7 @@ impl GStreamerPlayer {
         }
     }
 
-    pub async fn cycle_subtitle_track(&self) -> Result<()> {
+    async fn cycle_subtitle_track(&self) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             let n_text = playbin.property::<i32>("n-text");
             if n_text > 0 {
@@ -1349,7 +2397,7 @@ impl GStreamerPlayer {
         Ok(())
     }
 
-    pub async fn cycle_audio_track(&self) -> Result<()> {
+    async fn cycle_audio_track(&self) -> Result<()> {
         if let Some(playbin) = self.playbin.lock().unwrap().as_ref() {
             let n_audio = playbin.property::<i32>("n-audio");
             if n_audio > 0 {
@@ -1361,3 +2409,55 @@ impl GStreamerPlayer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::AudioPreset;
+
+    #[test]
+    fn test_audio_filter_parts() {
+        let night = AudioEffects {
+            preset: AudioPreset::Night,
+            ..Default::default()
+        };
+
+        let parts = AudioFilterParts::new(ReplayGainMode::Off, &AudioEffects::default(), false);
+        assert!(parts.tempo);
+        assert!(!parts.replaygain);
+        assert!(!parts.effects);
+
+        let parts = AudioFilterParts::new(ReplayGainMode::Album, &night, false);
+        assert!(parts.replaygain);
+        assert!(parts.effects);
+
+        let mut equalizer = AudioEffects::default();
+        equalizer.equalizer[0] = 4.0;
+        assert!(AudioFilterParts::new(ReplayGainMode::Off, &equalizer, false).effects);
+    }
+
+    #[test]
+    fn test_no_audio_filter_while_passing_through() {
+        let night = AudioEffects {
+            preset: AudioPreset::Night,
+            ..Default::default()
+        };
+        assert_eq!(
+            AudioFilterParts::new(ReplayGainMode::Track, &night, true),
+            AudioFilterParts {
+                tempo: false,
+                replaygain: false,
+                effects: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_passthrough_caps() {
+        let codecs = ["dts", "dts-hd", "ac3", "unknown"].map(String::from);
+        assert_eq!(
+            GStreamerPlayer::passthrough_caps(&codecs),
+            vec!["audio/x-dts", "audio/x-ac3"]
+        );
+    }
+}
@@ -1,12 +1,26 @@
+pub mod capabilities;
+pub mod cast;
 pub mod controller;
 pub mod factory;
 pub mod gstreamer_player;
+pub mod mpris;
 pub mod mpv_player;
-pub use controller::{PlayerController, PlayerHandle};
-pub use factory::Player;
+pub mod null_player;
+pub mod remote_control;
+pub mod trickplay;
+pub mod watch_party;
 #[allow(unused_imports)]
-pub use factory::PlayerState;
+pub use crate::core::player_traits::{
+    AudioOutputDevice, MediaPlayer, PlaybackStats, PlayerCapabilities, PlayerEvent, PlayerState,
+    ReplayGainMode,
+};
+pub use capabilities::DeviceCapabilities;
+pub use controller::{PlayerController, PlayerHandle};
 pub use gstreamer_player::GStreamerPlayer;
+pub use mpris::{MprisAction, MprisMetadata, MprisServer};
 pub use mpv_player::MpvPlayer;
 #[allow(unused_imports)]
 pub use mpv_player::UpscalingMode;
+pub use null_player::NullPlayer;
+pub use remote_control::{PlayMode, RemoteCommand, RemoteControlHandle, RemotePlayback};
+pub use watch_party::{DriftCorrection, PartyEvent, PartyMessage, WatchParty};
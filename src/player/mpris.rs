@@ -0,0 +1,794 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{connection, fdo, interface};
+
+use super::{PlayerHandle, PlayerState};
+use crate::models::MediaItem;
+
+/// Well-known name clients like playerctl and GNOME Shell look for. Each process
+/// registers it with an `.instance<pid>` suffix so several can run side by side.
+pub const BUS_NAME: &str = "org.mpris.MediaPlayer2.reel";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+const DESKTOP_ENTRY: &str = "dev.arsfeld.Reel";
+/// Same limits the player page applies to its speed shortcuts
+const MINIMUM_RATE: f64 = 0.25;
+const MAXIMUM_RATE: f64 = 4.0;
+/// Position drift between updates beyond which a jump is announced with `Seeked`
+const SEEK_DETECTION_THRESHOLD: Duration = Duration::from_secs(2);
+/// Clients extrapolate the position themselves, so steady playback is only resynced this often
+const POSITION_UPDATE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Requests from MPRIS clients that need the UI rather than the player
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MprisAction {
+    Raise,
+    Next,
+    Previous,
+}
+
+/// What is currently playing, as shown by desktop media controls
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MprisMetadata {
+    pub media_id: Option<String>,
+    pub title: String,
+    pub show_title: Option<String>,
+    pub artwork_url: Option<String>,
+    pub length: Option<Duration>,
+}
+
+impl MprisMetadata {
+    pub fn from_media_item(item: &MediaItem) -> Self {
+        let (show_title, artwork_url) = match item {
+            MediaItem::Movie(movie) => (None, movie.poster_url.clone()),
+            MediaItem::Episode(episode) => (
+                episode.show_title.clone(),
+                episode
+                    .show_poster_url
+                    .clone()
+                    .or_else(|| episode.thumbnail_url.clone()),
+            ),
+            _ => (None, None),
+        };
+
+        Self {
+            media_id: Some(item.id().to_string()),
+            title: item.title().to_string(),
+            show_title,
+            artwork_url,
+            length: item.duration(),
+        }
+    }
+
+    /// D-Bus object path identifying the track; media IDs contain characters paths don't allow
+    fn track_id(&self) -> OwnedObjectPath {
+        let path = match &self.media_id {
+            Some(id) => {
+                let sanitized: String = id
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect();
+                format!("/dev/arsfeld/Reel/Track/{}", sanitized)
+            }
+            None => NO_TRACK.to_string(),
+        };
+        ObjectPath::try_from(path)
+            .map(OwnedObjectPath::from)
+            .unwrap_or_else(|_| {
+                OwnedObjectPath::from(ObjectPath::from_static_str_unchecked(NO_TRACK))
+            })
+    }
+
+    fn to_dbus(&self) -> HashMap<String, OwnedValue> {
+        let mut map = HashMap::new();
+        let mut insert = |key: &str, value: Value<'_>| {
+            if let Ok(value) = value.try_to_owned() {
+                map.insert(key.to_string(), value);
+            }
+        };
+
+        insert("mpris:trackid", Value::from(self.track_id()));
+        if self.media_id.is_none() {
+            return map;
+        }
+
+        insert("xesam:title", Value::from(self.title.as_str()));
+        if let Some(show_title) = &self.show_title {
+            insert("xesam:album", Value::from(show_title.as_str()));
+            insert("xesam:artist", Value::from(vec![show_title.as_str()]));
+        }
+        if let Some(artwork_url) = &self.artwork_url {
+            insert("mpris:artUrl", Value::from(artwork_url.as_str()));
+        }
+        if let Some(length) = self.length {
+            insert("mpris:length", Value::from(length.as_micros() as i64));
+        }
+        map
+    }
+}
+
+/// Bus name of this process
+pub fn bus_name() -> String {
+    format!("{}.instance{}", BUS_NAME, std::process::id())
+}
+
+/// Whether a position report should go to `MprisServer::update_position`: it jumped
+/// away from where playback at `rate` would be, or the last one is a while ago
+pub fn position_update_due(
+    last: Option<(Duration, Instant)>,
+    position: Duration,
+    rate: f64,
+    now: Instant,
+) -> bool {
+    let Some((last_position, at)) = last else {
+        return true;
+    };
+    let elapsed = now.saturating_duration_since(at);
+    let expected = last_position + elapsed.mul_f64(rate.max(0.0));
+    elapsed >= POSITION_UPDATE_INTERVAL || position.abs_diff(expected) > SEEK_DETECTION_THRESHOLD
+}
+
+fn artwork_cache_path(url: &str) -> Option<PathBuf> {
+    Some(
+        dirs::cache_dir()?
+            .join("reel")
+            .join("mpris")
+            .join(format!("{:x}.jpg", md5::compute(url))),
+    )
+}
+
+/// A `file://` URI for artwork, downloading it on first use. Server artwork URLs
+/// carry the access token and the bus is readable by every client, so they are
+/// never handed out as they are.
+pub async fn local_artwork(url: &str) -> Option<String> {
+    if url.starts_with("file://") {
+        return Some(url.to_string());
+    }
+    let path = artwork_cache_path(url)?;
+    if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        let bytes = match reqwest::get(url).await.and_then(|r| r.error_for_status()) {
+            Ok(response) => response.bytes().await.ok()?,
+            Err(e) => {
+                debug!("Failed to download MPRIS artwork: {}", e);
+                return None;
+            }
+        };
+        tokio::fs::create_dir_all(path.parent()?).await.ok()?;
+        tokio::fs::write(&path, &bytes).await.ok()?;
+    }
+    url::Url::from_file_path(&path).ok().map(String::from)
+}
+
+fn playback_status(state: &PlayerState) -> &'static str {
+    match state {
+        PlayerState::Playing => "Playing",
+        PlayerState::Paused => "Paused",
+        _ => "Stopped",
+    }
+}
+
+fn to_fdo(error: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(error.to_string())
+}
+
+struct RootInterface {
+    actions: mpsc::UnboundedSender<MprisAction>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl RootInterface {
+    fn raise(&self) {
+        let _ = self.actions.send(MprisAction::Raise);
+    }
+
+    fn quit(&self) {}
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_raise(&self) -> bool {
+        true
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn identity(&self) -> &str {
+        "Reel"
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn desktop_entry(&self) -> &str {
+        DESKTOP_ENTRY
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct PlayerInterface {
+    player: PlayerHandle,
+    actions: mpsc::UnboundedSender<MprisAction>,
+    state: PlayerState,
+    metadata: MprisMetadata,
+    volume: f64,
+    rate: f64,
+    can_go_next: bool,
+    can_go_previous: bool,
+    /// Last known position and when it was reported, for spotting jumps
+    position: Duration,
+    position_updated_at: Instant,
+}
+
+impl PlayerInterface {
+    /// Where playback should be now, extrapolated from the last report
+    fn expected_position(&self) -> Duration {
+        if self.state == PlayerState::Playing {
+            self.position + self.position_updated_at.elapsed().mul_f64(self.rate)
+        } else {
+            self.position
+        }
+    }
+
+    fn record_position(&mut self, position: Duration) {
+        self.position = position;
+        self.position_updated_at = Instant::now();
+    }
+
+    async fn seek_to(
+        &mut self,
+        position: Duration,
+        emitter: &SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.player.seek(position).await.map_err(to_fdo)?;
+        self.record_position(position);
+        Self::seeked(emitter, position.as_micros() as i64).await?;
+        Ok(())
+    }
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl PlayerInterface {
+    fn next(&self) {
+        let _ = self.actions.send(MprisAction::Next);
+    }
+
+    fn previous(&self) {
+        let _ = self.actions.send(MprisAction::Previous);
+    }
+
+    async fn pause(&mut self) -> fdo::Result<()> {
+        self.player.pause().await.map_err(to_fdo)
+    }
+
+    async fn play_pause(&mut self) -> fdo::Result<()> {
+        match self.player.get_state().await.map_err(to_fdo)? {
+            PlayerState::Playing => self.player.pause().await.map_err(to_fdo),
+            _ => self.player.play().await.map_err(to_fdo),
+        }
+    }
+
+    async fn stop(&mut self) -> fdo::Result<()> {
+        self.player.stop().await.map_err(to_fdo)
+    }
+
+    async fn play(&mut self) -> fdo::Result<()> {
+        self.player.play().await.map_err(to_fdo)
+    }
+
+    /// Relative seek by `offset` microseconds
+    async fn seek(
+        &mut self,
+        offset: i64,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let current = self
+            .player
+            .get_position()
+            .await
+            .map_err(to_fdo)?
+            .unwrap_or_else(|| self.expected_position());
+        let target = current.as_micros() as i64 + offset;
+
+        // Seeking past the end behaves like Next, as the spec asks
+        if let Some(length) = self.metadata.length
+            && target > length.as_micros() as i64
+        {
+            let _ = self.actions.send(MprisAction::Next);
+            return Ok(());
+        }
+
+        self.seek_to(Duration::from_micros(target.max(0) as u64), &emitter)
+            .await
+    }
+
+    /// Absolute seek, ignored if the client's track is no longer playing
+    async fn set_position(
+        &mut self,
+        track_id: ObjectPath<'_>,
+        position: i64,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if track_id.as_str() != self.metadata.track_id().as_str() || position < 0 {
+            return Ok(());
+        }
+        if let Some(length) = self.metadata.length
+            && position > length.as_micros() as i64
+        {
+            return Ok(());
+        }
+
+        self.seek_to(Duration::from_micros(position as u64), &emitter)
+            .await
+    }
+
+    fn open_uri(&self, _uri: &str) -> fdo::Result<()> {
+        Err(fdo::Error::NotSupported(
+            "Opening URIs is not supported".into(),
+        ))
+    }
+
+    #[zbus(signal)]
+    async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> &str {
+        playback_status(&self.state)
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    #[zbus(property)]
+    async fn set_rate(&mut self, rate: f64) {
+        let rate = rate.clamp(MINIMUM_RATE, MAXIMUM_RATE);
+        match self.player.set_playback_speed(rate).await {
+            Ok(()) => self.rate = rate,
+            Err(e) => warn!("MPRIS: failed to set rate: {}", e),
+        }
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        self.metadata.to_dbus()
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        self.volume
+    }
+
+    #[zbus(property)]
+    async fn set_volume(&mut self, volume: f64) {
+        let volume = volume.clamp(0.0, 1.0);
+        match self.player.set_volume(volume).await {
+            Ok(()) => self.volume = volume,
+            Err(e) => warn!("MPRIS: failed to set volume: {}", e),
+        }
+    }
+
+    /// Clients poll this, so it isn't announced; jumps are signalled with `Seeked`
+    #[zbus(property(emits_changed_signal = "false"))]
+    async fn position(&self) -> i64 {
+        let position = match self.player.get_position().await {
+            Ok(Some(position)) => position,
+            _ => self.expected_position(),
+        };
+        position.as_micros() as i64
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn minimum_rate(&self) -> f64 {
+        MINIMUM_RATE
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn maximum_rate(&self) -> f64 {
+        MAXIMUM_RATE
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.can_go_next
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.can_go_previous
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.metadata.media_id.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.metadata.media_id.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        self.metadata.media_id.is_some()
+    }
+
+    #[zbus(property(emits_changed_signal = "const"))]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+/// Exposes playback on the session bus as an MPRIS2 media player, so desktop
+/// media controls, media keys and tools like playerctl can drive it
+#[derive(Clone)]
+pub struct MprisServer {
+    connection: zbus::Connection,
+}
+
+impl MprisServer {
+    /// Register on the session bus. Actions the player can't handle itself are
+    /// delivered on the returned receiver.
+    pub async fn start(
+        player: PlayerHandle,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<MprisAction>)> {
+        let builder = connection::Builder::session().context("Failed to reach session bus")?;
+        Self::start_on(builder, player).await
+    }
+
+    /// Register on the bus of an existing connection builder
+    pub async fn start_on(
+        builder: connection::Builder<'_>,
+        player: PlayerHandle,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<MprisAction>)> {
+        let (actions, receiver) = mpsc::unbounded_channel();
+
+        let root = RootInterface {
+            actions: actions.clone(),
+        };
+        let player = PlayerInterface {
+            player,
+            actions,
+            state: PlayerState::Idle,
+            metadata: MprisMetadata::default(),
+            volume: 1.0,
+            rate: 1.0,
+            can_go_next: false,
+            can_go_previous: false,
+            position: Duration::ZERO,
+            position_updated_at: Instant::now(),
+        };
+
+        let bus_name = bus_name();
+        let connection = builder
+            .name(bus_name.as_str())?
+            .serve_at(OBJECT_PATH, root)?
+            .serve_at(OBJECT_PATH, player)?
+            .build()
+            .await
+            .context("Failed to register MPRIS service")?;
+
+        info!("MPRIS service registered as {}", bus_name);
+        Ok((Self { connection }, receiver))
+    }
+
+    async fn player_interface(&self) -> Result<zbus::object_server::InterfaceRef<PlayerInterface>> {
+        Ok(self
+            .connection
+            .object_server()
+            .interface::<_, PlayerInterface>(OBJECT_PATH)
+            .await?)
+    }
+
+    pub async fn set_playback_state(&self, state: PlayerState) -> Result<()> {
+        let iface_ref = self.player_interface().await?;
+        let mut iface = iface_ref.get_mut().await;
+        if iface.state == state {
+            return Ok(());
+        }
+
+        // Restart extrapolation from the current point under the new state
+        let position = iface.expected_position();
+        iface.record_position(position);
+        iface.state = state;
+        iface
+            .playback_status_changed(iface_ref.signal_emitter())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_metadata(&self, metadata: MprisMetadata) -> Result<()> {
+        let iface_ref = self.player_interface().await?;
+        let mut iface = iface_ref.get_mut().await;
+        if iface.metadata == metadata {
+            return Ok(());
+        }
+
+        iface.metadata = metadata;
+        iface.record_position(Duration::ZERO);
+        let emitter = iface_ref.signal_emitter();
+        iface.metadata_changed(emitter).await?;
+        iface.can_play_changed(emitter).await?;
+        iface.can_pause_changed(emitter).await?;
+        iface.can_seek_changed(emitter).await?;
+        Ok(())
+    }
+
+    /// Whether the playlist has items before and after the current one
+    pub async fn set_navigation(&self, can_go_next: bool, can_go_previous: bool) -> Result<()> {
+        let iface_ref = self.player_interface().await?;
+        let mut iface = iface_ref.get_mut().await;
+        let emitter = iface_ref.signal_emitter();
+        if iface.can_go_next != can_go_next {
+            iface.can_go_next = can_go_next;
+            iface.can_go_next_changed(emitter).await?;
+        }
+        if iface.can_go_previous != can_go_previous {
+            iface.can_go_previous = can_go_previous;
+            iface.can_go_previous_changed(emitter).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn set_volume(&self, volume: f64) -> Result<()> {
+        let iface_ref = self.player_interface().await?;
+        let mut iface = iface_ref.get_mut().await;
+        if (iface.volume - volume).abs() < f64::EPSILON {
+            return Ok(());
+        }
+
+        iface.volume = volume;
+        iface.volume_changed(iface_ref.signal_emitter()).await?;
+        Ok(())
+    }
+
+    /// Record the playback position, announcing it with `Seeked` when it jumped
+    /// rather than advancing normally
+    pub async fn update_position(&self, position: Duration) -> Result<()> {
+        let iface_ref = self.player_interface().await?;
+        let mut iface = iface_ref.get_mut().await;
+
+        let expected = iface.expected_position();
+        let drift = position.abs_diff(expected);
+        iface.record_position(position);
+
+        if drift > SEEK_DETECTION_THRESHOLD {
+            debug!("MPRIS: position jumped to {:?}", position);
+            PlayerInterface::seeked(iface_ref.signal_emitter(), position.as_micros() as i64)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::controller::PlayerCommand;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+
+    /// A private bus so tests don't depend on, or show up in, the user's session
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+        }
+    }
+
+    /// Answers player commands like a controller would, reporting the ones it acted on
+    fn fake_player() -> (PlayerHandle, mpsc::UnboundedReceiver<String>) {
+        let (sender, mut commands) = mpsc::unbounded_channel();
+        let (log, log_receiver) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let mut state = PlayerState::Paused;
+            while let Some(command) = commands.recv().await {
+                match command {
+                    PlayerCommand::Play { respond_to } => {
+                        state = PlayerState::Playing;
+                        let _ = log.send("play".to_string());
+                        let _ = respond_to.send(Ok(()));
+                    }
+                    PlayerCommand::Pause { respond_to } => {
+                        state = PlayerState::Paused;
+                        let _ = log.send("pause".to_string());
+                        let _ = respond_to.send(Ok(()));
+                    }
+                    PlayerCommand::GetState { respond_to } => {
+                        let _ = respond_to.send(state.clone());
+                    }
+                    PlayerCommand::GetPosition { respond_to } => {
+                        let _ = respond_to.send(Some(Duration::from_secs(10)));
+                    }
+                    PlayerCommand::Seek {
+                        position,
+                        respond_to,
+                    } => {
+                        let _ = log.send(format!("seek {}", position.as_secs()));
+                        let _ = respond_to.send(Ok(()));
+                    }
+                    _ => {}
+                }
+            }
+        });
+        (PlayerHandle::from_sender(sender), log_receiver)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon"]
+    async fn test_mpris_over_private_bus() {
+        let bus = PrivateBus::start().expect("failed to start dbus-daemon");
+
+        let (player, mut log) = fake_player();
+        let builder = connection::Builder::address(bus.address.as_str()).unwrap();
+        let (server, mut actions) = MprisServer::start_on(builder, player).await.unwrap();
+
+        server
+            .set_metadata(MprisMetadata {
+                media_id: Some("plex:42".to_string()),
+                title: "Pilot".to_string(),
+                show_title: Some("Some Show".to_string()),
+                artwork_url: None,
+                length: Some(Duration::from_secs(60)),
+            })
+            .await
+            .unwrap();
+        server.set_navigation(true, false).await.unwrap();
+        server
+            .set_playback_state(PlayerState::Playing)
+            .await
+            .unwrap();
+
+        let client = connection::Builder::address(bus.address.as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let proxy = zbus::Proxy::new(
+            &client,
+            bus_name(),
+            OBJECT_PATH,
+            "org.mpris.MediaPlayer2.Player",
+        )
+        .await
+        .unwrap();
+
+        let status: String = proxy.get_property("PlaybackStatus").await.unwrap();
+        assert_eq!(status, "Playing");
+        assert!(proxy.get_property::<bool>("CanGoNext").await.unwrap());
+
+        let metadata: HashMap<String, OwnedValue> = proxy.get_property("Metadata").await.unwrap();
+        let title: String = metadata["xesam:title"]
+            .try_clone()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(title, "Pilot");
+        let length: i64 = metadata["mpris:length"]
+            .try_clone()
+            .unwrap()
+            .try_into()
+            .unwrap();
+        assert_eq!(length, 60_000_000);
+
+        proxy.call_method("PlayPause", &()).await.unwrap();
+        assert_eq!(log.recv().await.unwrap(), "play");
+
+        proxy.call_method("Seek", &(5_000_000i64)).await.unwrap();
+        assert_eq!(log.recv().await.unwrap(), "seek 15");
+
+        proxy.call_method("Next", &()).await.unwrap();
+        assert_eq!(actions.recv().await.unwrap(), MprisAction::Next);
+    }
+
+    #[test]
+    fn test_bus_name_is_per_process() {
+        let name = bus_name();
+        assert!(name.starts_with("org.mpris.MediaPlayer2.reel.instance"));
+        assert!(zbus::names::WellKnownName::try_from(name.as_str()).is_ok());
+    }
+
+    #[test]
+    fn test_position_update_due() {
+        let start = Instant::now();
+        let last = Some((Duration::from_secs(60), start));
+        let at = |secs: f64| start + Duration::from_secs_f64(secs);
+
+        assert!(position_update_due(None, Duration::ZERO, 1.0, start));
+        // Steady playback, at normal and double speed
+        assert!(!position_update_due(
+            last,
+            Duration::from_secs(61),
+            1.0,
+            at(1.0)
+        ));
+        assert!(!position_update_due(
+            last,
+            Duration::from_secs(62),
+            2.0,
+            at(1.0)
+        ));
+        // Resynced now and then even without a jump
+        assert!(position_update_due(
+            last,
+            Duration::from_secs(65),
+            1.0,
+            at(5.0)
+        ));
+        // Seeks either way
+        assert!(position_update_due(
+            last,
+            Duration::from_secs(90),
+            1.0,
+            at(1.0)
+        ));
+        assert!(position_update_due(
+            last,
+            Duration::from_secs(30),
+            1.0,
+            at(1.0)
+        ));
+    }
+
+    #[test]
+    fn test_artwork_is_never_shared_with_its_token() {
+        let path = artwork_cache_path("https://plex:32400/photo?X-Plex-Token=secret").unwrap();
+        assert!(!path.to_string_lossy().contains("secret"));
+        assert_eq!(path.extension().unwrap(), "jpg");
+    }
+
+    #[test]
+    fn test_track_id_is_valid_object_path() {
+        let metadata = MprisMetadata {
+            media_id: Some("source-1:item/42".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata.track_id().as_str(),
+            "/dev/arsfeld/Reel/Track/source_1_item_42"
+        );
+        assert_eq!(MprisMetadata::default().track_id().as_str(), NO_TRACK);
+    }
+}
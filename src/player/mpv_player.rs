@@ -1,5 +1,6 @@
 use crate::config::Config;
 use anyhow::Result;
+use async_trait::async_trait;
 use gtk4::GLArea;
 use gtk4::{self, glib, prelude::*};
 use libmpv2::Mpv;
@@ -7,14 +8,30 @@ use libmpv2_sys::*;
 use std::cell::Cell;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString, c_void};
+use std::path::Path;
 use std::ptr;
-use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
+use tokio::sync::broadcast;
 use tracing::{debug, error, info, warn};
 
+use crate::core::player_traits::{
+    AudioOutputDevice, MediaPlayer, PlaybackStats, PlayerCapabilities, PlayerEvent,
+    PlayerEventSource, PlayerState, ReplayGainMode,
+};
+use crate::models::{AudioEffects, Chapter, CropRect, VideoAdjustments};
+
+/// How often mpv properties are checked for changes to push to subscribers
+const EVENT_WATCH_INTERVAL: Duration = Duration::from_millis(250);
+/// How long cropdetect looks at frames before its result is read
+const CROP_DETECT_DURATION: Duration = Duration::from_secs(1);
+/// Label of the temporary cropdetect filter
+const CROP_DETECT_LABEL: &str = "reelcrop";
+/// Minimum position change before another position update is pushed
+const POSITION_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
 // MPV render update flags
 
 // Wrapper for mpv_render_context pointer to make it Send/Sync
@@ -31,15 +48,6 @@ struct OpenGLFunctions {
 unsafe impl Send for OpenGLFunctions {}
 unsafe impl Sync for OpenGLFunctions {}
 
-#[derive(Debug, Clone)]
-pub enum PlayerState {
-    Idle,
-    Loading,
-    Playing,
-    Paused,
-    Stopped,
-}
-
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum UpscalingMode {
     None,
@@ -59,11 +67,23 @@ impl UpscalingMode {
     }
 }
 
+/// Last values seen by the event watch, used to detect changes
+#[derive(Default)]
+struct WatchedProperties {
+    paused: Option<bool>,
+    eof_reached: bool,
+    track_count: Option<i64>,
+    chapter_count: Option<i64>,
+    buffering_percent: Option<u8>,
+    position: Option<Duration>,
+}
+
 struct MpvPlayerInner {
     mpv: Arc<Mutex<Option<Mpv>>>,
     mpv_gl: Arc<Mutex<Option<MpvRenderContextPtr>>>,
     gl_functions: Arc<Mutex<Option<OpenGLFunctions>>>,
-    state: Arc<RwLock<PlayerState>>,
+    events: PlayerEventSource,
+    event_timer: Arc<Mutex<Option<glib::SourceId>>>,
     update_callback_registered: Arc<Mutex<bool>>,
     pending_media_url: Arc<Mutex<Option<String>>>,
     last_render_time: Arc<Mutex<Instant>>,
@@ -78,6 +98,23 @@ struct MpvPlayerInner {
     seek_timer: Arc<Mutex<Option<glib::SourceId>>>,
     last_seek_target: Arc<Mutex<Option<f64>>>,
     upscaling_mode: Arc<Mutex<UpscalingMode>>,
+    audio_only: Arc<Mutex<bool>>,
+    replaygain: Arc<Mutex<ReplayGainMode>>,
+    /// Value of `audio-device`, "auto" for the system default
+    audio_device: Arc<Mutex<String>>,
+    /// Value of `audio-spdif`, the codecs passed through undecoded
+    audio_spdif: Arc<Mutex<String>>,
+    /// Value of `af`, the compressor and equalizer filter chain
+    audio_filter: Arc<Mutex<String>>,
+    /// URL appended to the playlist to follow the current file without a gap
+    queued_next_url: Arc<Mutex<Option<String>>>,
+    /// File handed to loadfile that isn't open yet; sub-add fails until it is
+    opening_url: Arc<Mutex<Option<String>>>,
+    /// `sub-add` arguments of subtitles asked for while the file was opening
+    pending_subtitles: Arc<Mutex<Vec<[String; 4]>>>,
+    /// Video track selected while the GLArea was unrealized, e.g. while it is moved
+    /// between the player page and the picture-in-picture window
+    detached_vid: Arc<Mutex<Option<String>>>,
 }
 
 #[derive(Clone)]
@@ -104,7 +141,8 @@ impl MpvPlayer {
                 mpv: Arc::new(Mutex::new(None)),
                 mpv_gl: Arc::new(Mutex::new(None)),
                 gl_functions: Arc::new(Mutex::new(None)),
-                state: Arc::new(RwLock::new(PlayerState::Idle)),
+                events: PlayerEventSource::new(),
+                event_timer: Arc::new(Mutex::new(None)),
                 update_callback_registered: Arc::new(Mutex::new(false)),
                 pending_media_url: Arc::new(Mutex::new(None)),
                 last_render_time: Arc::new(Mutex::new(Instant::now())),
@@ -119,6 +157,15 @@ impl MpvPlayer {
                 seek_timer: Arc::new(Mutex::new(None)),
                 last_seek_target: Arc::new(Mutex::new(None)),
                 upscaling_mode: Arc::new(Mutex::new(UpscalingMode::None)),
+                audio_only: Arc::new(Mutex::new(false)),
+                replaygain: Arc::new(Mutex::new(ReplayGainMode::default())),
+                audio_device: Arc::new(Mutex::new("auto".to_string())),
+                audio_spdif: Arc::new(Mutex::new(String::new())),
+                audio_filter: Arc::new(Mutex::new(String::new())),
+                queued_next_url: Arc::new(Mutex::new(None)),
+                opening_url: Arc::new(Mutex::new(None)),
+                pending_subtitles: Arc::new(Mutex::new(Vec::new())),
+                detached_vid: Arc::new(Mutex::new(None)),
             }),
         })
     }
@@ -385,7 +432,163 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub fn create_video_widget(&self) -> gtk4::Widget {
+    fn apply_upscaling_settings(&self, mpv: &Mpv, mode: UpscalingMode) -> Result<()> {
+        // Clear any existing shaders first
+        mpv.set_property("glsl-shaders", "").unwrap_or(());
+
+        // Get the shader directory path
+        let shader_dir = std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
+            .map(|p| p.join("../share/reel/shaders"))
+            .or_else(|| {
+                // Fallback to development path
+                std::env::current_dir()
+                    .ok()
+                    .map(|p| p.join("assets/shaders"))
+            })
+            .unwrap_or_else(|| std::path::PathBuf::from("assets/shaders"));
+
+        match mode {
+            UpscalingMode::None => {
+                // Use basic bilinear scaling
+                mpv.set_property("scale", "bilinear").unwrap_or(());
+                mpv.set_property("cscale", "bilinear").unwrap_or(());
+                mpv.set_property("dscale", "bilinear").unwrap_or(());
+                mpv.set_property("sigmoid-upscaling", false).unwrap_or(());
+                mpv.set_property("deband", false).unwrap_or(());
+                debug!("Upscaling disabled - using bilinear");
+            }
+            UpscalingMode::HighQuality => {
+                // Use FSRCNNX shader for high quality upscaling
+                let shader_path = shader_dir.join("FSRCNNX_x2_8-0-4-1.glsl");
+                if shader_path.exists() {
+                    mpv.set_property("glsl-shaders", shader_path.to_str().unwrap_or(""))
+                        .unwrap_or(());
+                    debug!("High quality upscaling enabled with FSRCNNX shader");
+                } else {
+                    // Fallback to built-in high quality scalers
+                    mpv.set_property("scale", "ewa_lanczossharp").unwrap_or(());
+                    mpv.set_property("cscale", "ewa_lanczossharp").unwrap_or(());
+                    mpv.set_property("dscale", "mitchell").unwrap_or(());
+                    mpv.set_property("sigmoid-upscaling", true).unwrap_or(());
+                    mpv.set_property("deband", true).unwrap_or(());
+                    mpv.set_property("deband-iterations", 2).unwrap_or(());
+                    mpv.set_property("deband-threshold", 48).unwrap_or(());
+                    mpv.set_property("deband-range", 16).unwrap_or(());
+                    mpv.set_property("deband-grain", 24).unwrap_or(());
+                    debug!(
+                        "High quality upscaling enabled with built-in scalers (shader not found)"
+                    );
+                }
+            }
+            UpscalingMode::FSR => {
+                // AMD FSR shader
+                let shader_path = shader_dir.join("FSR.glsl");
+                if shader_path.exists() {
+                    mpv.set_property("glsl-shaders", shader_path.to_str().unwrap_or(""))
+                        .unwrap_or(());
+                    debug!("FSR upscaling enabled with shader");
+                } else {
+                    // Fallback to spline36
+                    mpv.set_property("scale", "spline36").unwrap_or(());
+                    mpv.set_property("cscale", "spline36").unwrap_or(());
+                    mpv.set_property("dscale", "mitchell").unwrap_or(());
+                    mpv.set_property("sigmoid-upscaling", true).unwrap_or(());
+                    mpv.set_property("deband", true).unwrap_or(());
+                    debug!("FSR upscaling mode set with fallback (shader not found)");
+                }
+            }
+            UpscalingMode::Anime => {
+                // Anime4K shaders - combine Clamp Highlights and Upscale
+                let clamp_path = shader_dir.join("Anime4K_Clamp_Highlights.glsl");
+                let upscale_path = shader_dir.join("Anime4K_Upscale_CNN_x2_M.glsl");
+
+                if clamp_path.exists() && upscale_path.exists() {
+                    // Use both shaders in sequence for best results
+                    let shader_list = format!(
+                        "{}:{}",
+                        clamp_path.to_str().unwrap_or(""),
+                        upscale_path.to_str().unwrap_or("")
+                    );
+                    mpv.set_property("glsl-shaders", shader_list.as_str())
+                        .unwrap_or(());
+                    debug!("Anime upscaling enabled with Anime4K shaders");
+                } else if upscale_path.exists() {
+                    // Use only upscale if clamp is missing
+                    mpv.set_property("glsl-shaders", upscale_path.to_str().unwrap_or(""))
+                        .unwrap_or(());
+                    debug!("Anime upscaling enabled with Anime4K upscale only");
+                } else {
+                    // Fallback to optimized built-in settings for anime
+                    mpv.set_property("scale", "ewa_lanczossharp").unwrap_or(());
+                    mpv.set_property("cscale", "ewa_lanczossoft").unwrap_or(());
+                    mpv.set_property("dscale", "mitchell").unwrap_or(());
+                    mpv.set_property("sigmoid-upscaling", false).unwrap_or(());
+                    mpv.set_property("deband", true).unwrap_or(());
+                    mpv.set_property("deband-iterations", 4).unwrap_or(());
+                    mpv.set_property("deband-threshold", 64).unwrap_or(());
+                    mpv.set_property("deband-range", 16).unwrap_or(());
+                    mpv.set_property("deband-grain", 48).unwrap_or(());
+                    debug!("Anime upscaling mode set with fallback (shaders not found)");
+                }
+            }
+        }
+
+        info!("Upscaling mode changed to: {}", mode.to_string());
+        Ok(())
+    }
+
+    /// Watch mpv properties and push changes to subscribers
+    fn start_event_watch(&self) {
+        if let Some(timer) = self.inner.event_timer.lock().unwrap().take() {
+            timer.remove();
+        }
+
+        let inner: Weak<MpvPlayerInner> = Arc::downgrade(&self.inner);
+        let mut watched = WatchedProperties::default();
+        let timer = glib::timeout_add_local(EVENT_WATCH_INTERVAL, move || {
+            let Some(inner) = inner.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            inner.emit_property_changes(&mut watched);
+            glib::ControlFlow::Continue
+        });
+        *self.inner.event_timer.lock().unwrap() = Some(timer);
+    }
+}
+
+#[async_trait(?Send)]
+impl MediaPlayer for MpvPlayer {
+    fn backend_name(&self) -> &'static str {
+        "MPV"
+    }
+
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            frame_step_backward: true,
+            upscaling: true,
+            playback_speed: true,
+            subtitle_delay: true,
+            audio_delay: true,
+            external_subtitles: true,
+            gapless: true,
+            replaygain: true,
+            video_adjustments: true,
+            black_bar_detection: true,
+            screenshots: true,
+            audio_output_selection: true,
+            audio_passthrough: true,
+            audio_effects: true,
+            ab_loop: true,
+        }
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.inner.events.subscribe()
+    }
+
+    fn create_video_widget(&self) -> gtk4::Widget {
         debug!("Creating GLArea for MPV rendering");
 
         let gl_area = GLArea::new();
@@ -398,6 +601,8 @@ impl MpvPlayer {
         // Don't request a specific version - let GTK choose what's available
         // MPV should work with whatever GL context GTK provides
 
+        self.start_event_watch();
+
         // Clone inner for use in closures
         let inner = self.inner.clone();
 
@@ -432,6 +637,15 @@ impl MpvPlayer {
             // Initialize render context
             if let Err(e) = player_self.init_gl_render_context(gl_area) {
                 error!("Failed to initialize GL render context: {}", e);
+                return;
+            }
+
+            // Bring the video back after the widget was moved to another window
+            if let Some(vid) = inner_realize.detached_vid.lock().unwrap().take()
+                && let Some(ref mpv) = *inner_realize.mpv.lock().unwrap()
+            {
+                debug!("Restoring video track {} after reparenting", vid);
+                mpv.set_property("vid", vid.as_str()).unwrap_or(());
             }
         });
 
@@ -599,10 +813,23 @@ impl MpvPlayer {
             // Clean up render context
             if let Some(MpvRenderContextPtr(mpv_gl)) = inner_unrealize.mpv_gl.lock().unwrap().take()
             {
+                // Freeing the render context forcefully disables video, so remember the
+                // track to restore it once the widget is realized in its new parent
+                if let Some(ref mpv) = *inner_unrealize.mpv.lock().unwrap()
+                    && let Ok(vid) = mpv.get_property::<String>("vid")
+                    && vid != "no"
+                {
+                    *inner_unrealize.detached_vid.lock().unwrap() = Some(vid);
+                }
+
                 unsafe {
                     mpv_render_context_free(mpv_gl);
                 }
             }
+
+            // The next GL context has its own framebuffer and needs a fresh update callback
+            *inner_unrealize.cached_fbo.lock().unwrap() = -1;
+            *inner_unrealize.update_callback_registered.lock().unwrap() = false;
         });
 
         // Note: We don't store the GLArea to keep the player Send+Sync
@@ -640,17 +867,15 @@ impl MpvPlayer {
         gl_area.upcast::<gtk4::Widget>()
     }
 
-    pub async fn load_media(&self, url: &str, _video_sink: Option<()>) -> Result<()> {
+    async fn load_media(&self, url: &str) -> Result<()> {
         info!("Loading media: {}", url);
 
-        // Update state
-        {
-            let mut state = self.inner.state.write().await;
-            *state = PlayerState::Loading;
-        }
+        self.inner.events.set_state(PlayerState::Loading);
+
+        let audio_only = *self.inner.audio_only.lock().unwrap();
 
-        // Check if render context is initialized
-        if self.inner.mpv_gl.lock().unwrap().is_none() {
+        // Check if render context is initialized; audio never needs one
+        if !audio_only && self.inner.mpv_gl.lock().unwrap().is_none() {
             warn!(
                 "MpvPlayer::load_media() - Render context not initialized yet, deferring media load"
             );
@@ -665,6 +890,16 @@ impl MpvPlayer {
             *self.inner.mpv.lock().unwrap() = Some(mpv);
         }
 
+        // Without a video widget nothing else starts watching for events
+        if self.inner.event_timer.lock().unwrap().is_none() {
+            self.start_event_watch();
+        }
+
+        // Anything queued belonged to the previous file
+        *self.inner.queued_next_url.lock().unwrap() = None;
+        self.inner.pending_subtitles.lock().unwrap().clear();
+        *self.inner.opening_url.lock().unwrap() = Some(url.to_string());
+
         // Load the media file
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             mpv.command("loadfile", &[url, "replace"])
@@ -677,40 +912,37 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn play(&self) -> Result<()> {
+    async fn play(&self) -> Result<()> {
         debug!("Starting playback");
 
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             mpv.set_property("pause", false)
                 .map_err(|e| anyhow::anyhow!("Failed to set pause=false: {:?}", e))?;
 
-            let mut state = self.inner.state.write().await;
-            *state = PlayerState::Playing;
+            self.inner.events.set_state(PlayerState::Playing);
             debug!("Playback started");
         } else {
             // If MPV not initialized yet, just update state - it will auto-play when loaded
             warn!("MpvPlayer::play() - MPV not initialized yet, will auto-play when ready");
-            let mut state = self.inner.state.write().await;
-            *state = PlayerState::Playing;
+            self.inner.events.set_state(PlayerState::Playing);
         }
 
         Ok(())
     }
 
-    pub async fn pause(&self) -> Result<()> {
+    async fn pause(&self) -> Result<()> {
         debug!("MpvPlayer::pause() - Pausing playback");
 
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             mpv.set_property("pause", true)
                 .map_err(|e| anyhow::anyhow!("Failed to set pause=true: {:?}", e))?;
 
-            let mut state = self.inner.state.write().await;
-            *state = PlayerState::Paused;
+            self.inner.events.set_state(PlayerState::Paused);
         }
         Ok(())
     }
 
-    pub async fn stop(&self) -> Result<()> {
+    async fn stop(&self) -> Result<()> {
         debug!("MpvPlayer::stop() - Stopping playback with immediate audio cut");
 
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
@@ -739,8 +971,7 @@ impl MpvPlayer {
             mpv.command("set", &["idle", "yes"])
                 .map_err(|e| anyhow::anyhow!("Failed to set idle mode: {:?}", e))?;
 
-            let mut state = self.inner.state.write().await;
-            *state = PlayerState::Stopped;
+            self.inner.events.set_state(PlayerState::Stopped);
 
             info!("MpvPlayer::stop() - Playback stopped with immediate audio termination");
         } else {
@@ -749,7 +980,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn seek(&self, position: Duration) -> Result<()> {
+    async fn seek(&self, position: Duration) -> Result<()> {
         debug!("MpvPlayer::seek() - Seeking to {:?}", position);
 
         let position_secs = position.as_secs_f64();
@@ -805,7 +1036,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn get_position(&self) -> Option<Duration> {
+    async fn get_position(&self) -> Option<Duration> {
         // If we have a pending seek, return that as the effective position
         {
             let last_target = self.inner.last_seek_target.lock().unwrap();
@@ -831,7 +1062,7 @@ impl MpvPlayer {
         None
     }
 
-    pub async fn get_duration(&self) -> Option<Duration> {
+    async fn get_duration(&self) -> Option<Duration> {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
             && let Ok(dur) = mpv.get_property::<f64>("duration")
         {
@@ -841,7 +1072,7 @@ impl MpvPlayer {
         None
     }
 
-    pub async fn set_volume(&self, volume: f64) -> Result<()> {
+    async fn set_volume(&self, volume: f64) -> Result<()> {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             // MPV expects volume in 0-100 range
             let mpv_volume = (volume * 100.0).clamp(0.0, 100.0);
@@ -851,7 +1082,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn get_video_dimensions(&self) -> Option<(i32, i32)> {
+    async fn get_video_dimensions(&self) -> Option<(i32, i32)> {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
             && let (Ok(width), Ok(height)) = (
                 mpv.get_property::<i64>("width"),
@@ -863,7 +1094,17 @@ impl MpvPlayer {
         None
     }
 
-    pub async fn get_state(&self) -> PlayerState {
+    async fn set_audio_only(&self, audio_only: bool) -> Result<()> {
+        *self.inner.audio_only.lock().unwrap() = audio_only;
+
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("vid", if audio_only { "no" } else { "auto" })
+                .map_err(|e| anyhow::anyhow!("Failed to set vid: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn get_state(&self) -> PlayerState {
         // Query MPV for the actual state instead of relying on cached state
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             // Check if MPV is actually paused or playing
@@ -885,10 +1126,10 @@ impl MpvPlayer {
         }
 
         // Fall back to cached state if MPV is not available
-        self.inner.state.read().await.clone()
+        self.inner.events.state()
     }
 
-    pub async fn get_audio_tracks(&self) -> Vec<(i32, String)> {
+    async fn get_audio_tracks(&self) -> Vec<(i32, String)> {
         let mut tracks = Vec::new();
 
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
@@ -931,7 +1172,7 @@ impl MpvPlayer {
         tracks
     }
 
-    pub async fn get_subtitle_tracks(&self) -> Vec<(i32, String)> {
+    async fn get_subtitle_tracks(&self) -> Vec<(i32, String)> {
         let mut tracks = Vec::new();
 
         // Add "None" option
@@ -977,7 +1218,7 @@ impl MpvPlayer {
         tracks
     }
 
-    pub async fn set_audio_track(&self, track_index: i32) -> Result<()> {
+    async fn set_audio_track(&self, track_index: i32) -> Result<()> {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             mpv.set_property("aid", track_index as i64)
                 .map_err(|e| anyhow::anyhow!("Failed to set audio track: {:?}", e))?;
@@ -986,7 +1227,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn set_subtitle_track(&self, track_index: i32) -> Result<()> {
+    async fn set_subtitle_track(&self, track_index: i32) -> Result<()> {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
             if track_index < 0 {
                 // Disable subtitles
@@ -1003,7 +1244,40 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn get_current_audio_track(&self) -> i32 {
+    /// Load a sidecar subtitle file as an additional subtitle track
+    async fn add_subtitle(
+        &self,
+        url: &str,
+        title: &str,
+        language: Option<&str>,
+        select: bool,
+    ) -> Result<()> {
+        // "auto" adds the track without switching to it
+        let flag = if select { "select" } else { "auto" };
+        let args = [url, flag, title, language.unwrap_or("")];
+
+        let guard = self.inner.mpv.lock().unwrap();
+        let Some(mpv) = guard.as_ref() else {
+            return Err(anyhow::anyhow!("MPV not initialized"));
+        };
+        // loadfile returns before the file is open and sub-add fails until it is, so
+        // the property watcher adds it once the file has opened
+        if self.inner.opening_url.lock().unwrap().is_some() {
+            debug!("Adding external subtitle '{}' once the file is open", title);
+            self.inner
+                .pending_subtitles
+                .lock()
+                .unwrap()
+                .push(args.map(str::to_string));
+            return Ok(());
+        }
+        mpv.command("sub-add", &args)
+            .map_err(|e| anyhow::anyhow!("Failed to add subtitle from {}: {:?}", url, e))?;
+        debug!("Added external subtitle '{}' from {}", title, url);
+        Ok(())
+    }
+
+    async fn get_current_audio_track(&self) -> i32 {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
             && let Ok(aid) = mpv.get_property::<i64>("aid")
         {
@@ -1012,7 +1286,7 @@ impl MpvPlayer {
         -1
     }
 
-    pub async fn get_current_subtitle_track(&self) -> i32 {
+    async fn get_current_subtitle_track(&self) -> i32 {
         if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
             && let Ok(sid) = mpv.get_property::<i64>("sid")
         {
@@ -1021,7 +1295,7 @@ impl MpvPlayer {
         -1
     }
 
-    pub async fn set_upscaling_mode(&self, mode: UpscalingMode) -> Result<()> {
+    async fn set_upscaling_mode(&self, mode: UpscalingMode) -> Result<()> {
         let mut current_mode = self.inner.upscaling_mode.lock().unwrap();
         *current_mode = mode;
         drop(current_mode);
@@ -1032,123 +1306,305 @@ impl MpvPlayer {
         Ok(())
     }
 
-    fn apply_upscaling_settings(&self, mpv: &Mpv, mode: UpscalingMode) -> Result<()> {
-        // Clear any existing shaders first
-        mpv.set_property("glsl-shaders", "").unwrap_or(());
+    async fn set_playback_speed(&self, speed: f64) -> Result<()> {
+        let inner = self.inner.clone();
+        if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
+            mpv.set_property("speed", speed)
+                .map_err(|e| anyhow::anyhow!("Failed to set playback speed: {:?}", e))?;
+        }
+        Ok(())
+    }
 
-        // Get the shader directory path
-        let shader_dir = std::env::current_exe()
-            .ok()
-            .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-            .map(|p| p.join("../share/reel/shaders"))
-            .or_else(|| {
-                // Fallback to development path
-                std::env::current_dir()
-                    .ok()
-                    .map(|p| p.join("assets/shaders"))
-            })
-            .unwrap_or_else(|| std::path::PathBuf::from("assets/shaders"));
+    /// Shift subtitle timing; positive values show subtitles later
+    async fn set_subtitle_delay(&self, delay_ms: i64) -> Result<()> {
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("sub-delay", delay_ms as f64 / 1000.0)
+                .map_err(|e| anyhow::anyhow!("Failed to set subtitle delay: {:?}", e))?;
+        }
+        Ok(())
+    }
 
-        match mode {
-            UpscalingMode::None => {
-                // Use basic bilinear scaling
-                mpv.set_property("scale", "bilinear").unwrap_or(());
-                mpv.set_property("cscale", "bilinear").unwrap_or(());
-                mpv.set_property("dscale", "bilinear").unwrap_or(());
-                mpv.set_property("sigmoid-upscaling", false).unwrap_or(());
-                mpv.set_property("deband", false).unwrap_or(());
-                debug!("Upscaling disabled - using bilinear");
-            }
-            UpscalingMode::HighQuality => {
-                // Use FSRCNNX shader for high quality upscaling
-                let shader_path = shader_dir.join("FSRCNNX_x2_8-0-4-1.glsl");
-                if shader_path.exists() {
-                    mpv.set_property("glsl-shaders", shader_path.to_str().unwrap_or(""))
-                        .unwrap_or(());
-                    debug!("High quality upscaling enabled with FSRCNNX shader");
-                } else {
-                    // Fallback to built-in high quality scalers
-                    mpv.set_property("scale", "ewa_lanczossharp").unwrap_or(());
-                    mpv.set_property("cscale", "ewa_lanczossharp").unwrap_or(());
-                    mpv.set_property("dscale", "mitchell").unwrap_or(());
-                    mpv.set_property("sigmoid-upscaling", true).unwrap_or(());
-                    mpv.set_property("deband", true).unwrap_or(());
-                    mpv.set_property("deband-iterations", 2).unwrap_or(());
-                    mpv.set_property("deband-threshold", 48).unwrap_or(());
-                    mpv.set_property("deband-range", 16).unwrap_or(());
-                    mpv.set_property("deband-grain", 24).unwrap_or(());
-                    debug!(
-                        "High quality upscaling enabled with built-in scalers (shader not found)"
-                    );
-                }
-            }
-            UpscalingMode::FSR => {
-                // AMD FSR shader
-                let shader_path = shader_dir.join("FSR.glsl");
-                if shader_path.exists() {
-                    mpv.set_property("glsl-shaders", shader_path.to_str().unwrap_or(""))
-                        .unwrap_or(());
-                    debug!("FSR upscaling enabled with shader");
-                } else {
-                    // Fallback to spline36
-                    mpv.set_property("scale", "spline36").unwrap_or(());
-                    mpv.set_property("cscale", "spline36").unwrap_or(());
-                    mpv.set_property("dscale", "mitchell").unwrap_or(());
-                    mpv.set_property("sigmoid-upscaling", true).unwrap_or(());
-                    mpv.set_property("deband", true).unwrap_or(());
-                    debug!("FSR upscaling mode set with fallback (shader not found)");
+    /// Shift audio timing; positive values play audio later
+    async fn set_audio_delay(&self, delay_ms: i64) -> Result<()> {
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("audio-delay", delay_ms as f64 / 1000.0)
+                .map_err(|e| anyhow::anyhow!("Failed to set audio delay: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn queue_next(&self, url: Option<&str>) -> Result<()> {
+        let guard = self.inner.mpv.lock().unwrap();
+        let Some(mpv) = guard.as_ref() else {
+            return Err(anyhow::anyhow!("MPV not initialized"));
+        };
+
+        // Drop everything but the current file, including whatever was queued before
+        mpv.command("playlist-clear", &[])
+            .map_err(|e| anyhow::anyhow!("Failed to clear playlist: {:?}", e))?;
+        if let Some(url) = url {
+            mpv.command("loadfile", &[url, "append"])
+                .map_err(|e| anyhow::anyhow!("Failed to queue next media: {:?}", e))?;
+        }
+        *self.inner.queued_next_url.lock().unwrap() = url.map(|url| url.to_string());
+        Ok(())
+    }
+
+    async fn set_replaygain(&self, mode: ReplayGainMode) -> Result<()> {
+        *self.inner.replaygain.lock().unwrap() = mode;
+
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("replaygain", MpvPlayerInner::replaygain_value(mode))
+                .map_err(|e| anyhow::anyhow!("Failed to set replaygain: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn get_audio_devices(&self) -> Result<Vec<AudioOutputDevice>> {
+        let mut devices = Vec::new();
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
+            && let Ok(count) = mpv.get_property::<i64>("audio-device-list/count")
+        {
+            for i in 0..count {
+                let Ok(name) = mpv.get_property::<String>(&format!("audio-device-list/{}/name", i))
+                else {
+                    continue;
+                };
+                // The default is offered separately
+                if name == "auto" {
+                    continue;
                 }
+                let description = mpv
+                    .get_property::<String>(&format!("audio-device-list/{}/description", i))
+                    .unwrap_or_else(|_| name.clone());
+                devices.push(AudioOutputDevice { name, description });
             }
-            UpscalingMode::Anime => {
-                // Anime4K shaders - combine Clamp Highlights and Upscale
-                let clamp_path = shader_dir.join("Anime4K_Clamp_Highlights.glsl");
-                let upscale_path = shader_dir.join("Anime4K_Upscale_CNN_x2_M.glsl");
+        }
+        Ok(devices)
+    }
 
-                if clamp_path.exists() && upscale_path.exists() {
-                    // Use both shaders in sequence for best results
-                    let shader_list = format!(
-                        "{}:{}",
-                        clamp_path.to_str().unwrap_or(""),
-                        upscale_path.to_str().unwrap_or("")
-                    );
-                    mpv.set_property("glsl-shaders", shader_list.as_str())
-                        .unwrap_or(());
-                    debug!("Anime upscaling enabled with Anime4K shaders");
-                } else if upscale_path.exists() {
-                    // Use only upscale if clamp is missing
-                    mpv.set_property("glsl-shaders", upscale_path.to_str().unwrap_or(""))
-                        .unwrap_or(());
-                    debug!("Anime upscaling enabled with Anime4K upscale only");
-                } else {
-                    // Fallback to optimized built-in settings for anime
-                    mpv.set_property("scale", "ewa_lanczossharp").unwrap_or(());
-                    mpv.set_property("cscale", "ewa_lanczossoft").unwrap_or(());
-                    mpv.set_property("dscale", "mitchell").unwrap_or(());
-                    mpv.set_property("sigmoid-upscaling", false).unwrap_or(());
-                    mpv.set_property("deband", true).unwrap_or(());
-                    mpv.set_property("deband-iterations", 4).unwrap_or(());
-                    mpv.set_property("deband-threshold", 64).unwrap_or(());
-                    mpv.set_property("deband-range", 16).unwrap_or(());
-                    mpv.set_property("deband-grain", 48).unwrap_or(());
-                    debug!("Anime upscaling mode set with fallback (shaders not found)");
+    async fn set_audio_device(&self, name: &str) -> Result<()> {
+        let device = if name.is_empty() { "auto" } else { name };
+        *self.inner.audio_device.lock().unwrap() = device.to_string();
+
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("audio-device", device)
+                .map_err(|e| anyhow::anyhow!("Failed to set audio-device: {:?}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn set_ab_loop(&self, range: Option<(Duration, Duration)>) -> Result<()> {
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            match range {
+                Some((start, end)) => {
+                    mpv.set_property("ab-loop-a", start.as_secs_f64())
+                        .and_then(|_| mpv.set_property("ab-loop-b", end.as_secs_f64()))
+                        .map_err(|e| anyhow::anyhow!("Failed to set A-B loop: {:?}", e))?;
+                    // Start the first repeat right away, like every later one
+                    mpv.command(
+                        "seek",
+                        &[&start.as_secs_f64().to_string(), "absolute+exact"],
+                    )
+                    .map_err(|e| anyhow::anyhow!("Failed to seek to loop start: {:?}", e))?;
+                }
+                None => {
+                    mpv.set_property("ab-loop-a", "no")
+                        .and_then(|_| mpv.set_property("ab-loop-b", "no"))
+                        .map_err(|e| anyhow::anyhow!("Failed to clear A-B loop: {:?}", e))?;
                 }
             }
         }
+        Ok(())
+    }
 
-        info!("Upscaling mode changed to: {}", mode.to_string());
+    async fn set_audio_effects(&self, effects: AudioEffects) -> Result<()> {
+        let filter = effects.to_mpv_filter();
+        *self.inner.audio_filter.lock().unwrap() = filter.clone();
+
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("af", filter.as_str())
+                .map_err(|e| anyhow::anyhow!("Failed to set af: {:?}", e))?;
+        }
         Ok(())
     }
 
-    pub async fn set_playback_speed(&self, speed: f64) -> Result<()> {
-        let inner = self.inner.clone();
-        if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
-            mpv.set_property("speed", speed)
-                .map_err(|e| anyhow::anyhow!("Failed to set playback speed: {:?}", e))?;
+    async fn set_audio_passthrough(&self, codecs: &[String]) -> Result<()> {
+        let spdif = codecs.join(",");
+        *self.inner.audio_spdif.lock().unwrap() = spdif.clone();
+
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("audio-spdif", spdif.as_str())
+                .map_err(|e| anyhow::anyhow!("Failed to set audio-spdif: {:?}", e))?;
         }
         Ok(())
     }
 
-    pub async fn get_playback_speed(&self) -> f64 {
+    async fn set_video_adjustments(&self, adjustments: VideoAdjustments) -> Result<()> {
+        let mpv = self.inner.mpv.lock().unwrap();
+        let mpv = mpv
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("MPV not initialized"))?;
+
+        // -1 keeps the aspect the stream is flagged with
+        let aspect = adjustments
+            .aspect_ratio
+            .map_or_else(|| "-1".to_string(), |ratio| format!("{:.4}", ratio));
+        // video-zoom is a power of two
+        let zoom = adjustments.zoom.max(0.1).log2();
+        let crop = adjustments
+            .crop
+            .map(|crop| crop.to_mpv_string())
+            .unwrap_or_default();
+
+        mpv.set_property("video-aspect-override", aspect.as_str())
+            .map_err(|e| anyhow::anyhow!("Failed to set aspect override: {:?}", e))?;
+        mpv.set_property("video-zoom", zoom)
+            .map_err(|e| anyhow::anyhow!("Failed to set zoom: {:?}", e))?;
+        mpv.set_property("video-pan-x", adjustments.pan_x)
+            .map_err(|e| anyhow::anyhow!("Failed to set pan: {:?}", e))?;
+        mpv.set_property("video-pan-y", adjustments.pan_y)
+            .map_err(|e| anyhow::anyhow!("Failed to set pan: {:?}", e))?;
+        mpv.set_property("video-crop", crop.as_str())
+            .map_err(|e| anyhow::anyhow!("Failed to set crop: {:?}", e))?;
+        for (property, value) in [
+            ("brightness", adjustments.brightness),
+            ("contrast", adjustments.contrast),
+            ("saturation", adjustments.saturation),
+        ] {
+            mpv.set_property(property, value.clamp(-100, 100) as i64)
+                .map_err(|e| anyhow::anyhow!("Failed to set {}: {:?}", property, e))?;
+        }
+        Ok(())
+    }
+
+    async fn detect_black_bars(&self) -> Result<Option<CropRect>> {
+        let filter = format!("@{}:lavfi=[cropdetect=round=2]", CROP_DETECT_LABEL);
+        match *self.inner.mpv.lock().unwrap() {
+            Some(ref mpv) => mpv
+                .command("vf", &["add", &filter])
+                .map_err(|e| anyhow::anyhow!("Failed to add cropdetect filter: {:?}", e))?,
+            None => return Err(anyhow::anyhow!("MPV not initialized")),
+        }
+
+        // cropdetect only sees frames while they are decoded
+        glib::timeout_future(CROP_DETECT_DURATION).await;
+
+        let mpv = self.inner.mpv.lock().unwrap();
+        let mpv = mpv
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("MPV not initialized"))?;
+        let detected = |key: &str| {
+            mpv.get_property::<String>(&format!(
+                "vf-metadata/{}/by-key/lavfi.cropdetect.{}",
+                CROP_DETECT_LABEL, key
+            ))
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+        };
+        let crop = match (detected("x"), detected("y"), detected("w"), detected("h")) {
+            (Some(x), Some(y), Some(width), Some(height)) => Some(CropRect {
+                x,
+                y,
+                width,
+                height,
+            }),
+            _ => None,
+        };
+        let source_size = (
+            mpv.get_property::<i64>("video-params/w").unwrap_or(0) as u32,
+            mpv.get_property::<i64>("video-params/h").unwrap_or(0) as u32,
+        );
+
+        mpv.command("vf", &["remove", &format!("@{}", CROP_DETECT_LABEL)])
+            .unwrap_or(());
+
+        let Some(crop) = crop else {
+            warn!("cropdetect reported nothing, is the video playing?");
+            return Ok(None);
+        };
+        debug!("cropdetect found {:?} in a {:?} frame", crop, source_size);
+        if (crop.x, crop.y, crop.width, crop.height) == (0, 0, source_size.0, source_size.1)
+            || crop.width == 0
+            || crop.height == 0
+        {
+            Ok(None)
+        } else {
+            Ok(Some(crop))
+        }
+    }
+
+    async fn screenshot(&self, path: &Path, include_subtitles: bool) -> Result<()> {
+        let mpv = self.inner.mpv.lock().unwrap();
+        let mpv = mpv
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("MPV not initialized"))?;
+        let path = path
+            .to_str()
+            .ok_or_else(|| anyhow::anyhow!("Screenshot path is not valid UTF-8"))?;
+
+        // Both flags take the frame at source resolution, unlike "window"
+        let flags = if include_subtitles {
+            "subtitles"
+        } else {
+            "video"
+        };
+        mpv.command("screenshot-to-file", &[path, flags])
+            .map_err(|e| anyhow::anyhow!("Failed to take screenshot: {:?}", e))
+    }
+
+    async fn get_stats(&self) -> Result<PlaybackStats> {
+        let mpv = self.inner.mpv.lock().unwrap();
+        let mpv = mpv
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("MPV not initialized"))?;
+        let string = |name: &str| {
+            mpv.get_property::<String>(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+        };
+        let count = |name: &str| {
+            mpv.get_property::<i64>(name)
+                .ok()
+                .map(|value| value.max(0) as u64)
+        };
+
+        let resolution = match (
+            mpv.get_property::<i64>("video-params/w"),
+            mpv.get_property::<i64>("video-params/h"),
+        ) {
+            (Ok(width), Ok(height)) => Some((width as u32, height as u32)),
+            _ => None,
+        };
+        // Frames dropped by the decoder to catch up, and by the output
+        let dropped_frames = match (count("decoder-frame-drop-count"), count("frame-drop-count")) {
+            (None, None) => None,
+            (decoder, output) => Some(decoder.unwrap_or(0) + output.unwrap_or(0)),
+        };
+
+        Ok(PlaybackStats {
+            container: string("file-format"),
+            video_codec: string("video-format"),
+            audio_codec: string("audio-codec-name"),
+            resolution,
+            frame_rate: mpv
+                .get_property::<f64>("container-fps")
+                .ok()
+                .filter(|fps| *fps > 0.0),
+            decoder: string("video-codec"),
+            hwdec: string("hwdec-current").filter(|hwdec| hwdec != "no"),
+            dropped_frames,
+            delayed_frames: count("vo-delayed-frame-count"),
+            cache_duration: mpv
+                .get_property::<f64>("demuxer-cache-duration")
+                .ok()
+                .map(|secs| Duration::from_secs_f64(secs.max(0.0))),
+            buffer_percent: count("cache-buffering-state").map(|percent| percent.min(100) as u8),
+            network_bytes_per_second: count("cache-speed"),
+        })
+    }
+
+    async fn get_playback_speed(&self) -> f64 {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             mpv.get_property::<f64>("speed").unwrap_or(1.0)
@@ -1157,7 +1613,49 @@ impl MpvPlayer {
         }
     }
 
-    pub async fn frame_step_forward(&self) -> Result<()> {
+    async fn get_chapters(&self) -> Vec<Chapter> {
+        let mut chapters = Vec::new();
+
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap()
+            && let Ok(count) = mpv.get_property::<i64>("chapter-list/count")
+        {
+            for i in 0..count {
+                // Every chapter is listed so indices keep matching mpv's `chapter`
+                // property; one without a time starts where the one before it does
+                let start_time = mpv
+                    .get_property::<f64>(&format!("chapter-list/{}/time", i))
+                    .map(|time| Duration::from_secs_f64(time.max(0.0)))
+                    .unwrap_or_else(|_| {
+                        chapters
+                            .last()
+                            .map_or(Duration::ZERO, |previous: &Chapter| previous.start_time)
+                    });
+                let title = mpv
+                    .get_property::<String>(&format!("chapter-list/{}/title", i))
+                    .ok()
+                    .filter(|title| !title.is_empty())
+                    .unwrap_or_else(|| format!("Chapter {}", i + 1));
+
+                chapters.push(Chapter {
+                    title,
+                    start_time,
+                    thumbnail_url: None,
+                });
+            }
+        }
+
+        chapters
+    }
+
+    async fn seek_to_chapter(&self, index: usize) -> Result<()> {
+        if let Some(ref mpv) = *self.inner.mpv.lock().unwrap() {
+            mpv.set_property("chapter", index as i64)
+                .map_err(|e| anyhow::anyhow!("Failed to seek to chapter {}: {:?}", index, e))?;
+        }
+        Ok(())
+    }
+
+    async fn frame_step_forward(&self) -> Result<()> {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             mpv.command("frame-step", &[])
@@ -1166,7 +1664,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn frame_step_backward(&self) -> Result<()> {
+    async fn frame_step_backward(&self) -> Result<()> {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             mpv.command("frame-back-step", &[])
@@ -1175,7 +1673,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn toggle_mute(&self) -> Result<()> {
+    async fn toggle_mute(&self) -> Result<()> {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             let muted = mpv.get_property::<bool>("mute").unwrap_or(false);
@@ -1185,7 +1683,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn is_muted(&self) -> bool {
+    async fn is_muted(&self) -> bool {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             mpv.get_property::<bool>("mute").unwrap_or(false)
@@ -1194,7 +1692,7 @@ impl MpvPlayer {
         }
     }
 
-    pub async fn cycle_subtitle_track(&self) -> Result<()> {
+    async fn cycle_subtitle_track(&self) -> Result<()> {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             mpv.command("cycle", &["sub"])
@@ -1203,7 +1701,7 @@ impl MpvPlayer {
         Ok(())
     }
 
-    pub async fn cycle_audio_track(&self) -> Result<()> {
+    async fn cycle_audio_track(&self) -> Result<()> {
         let inner = self.inner.clone();
         if let Some(ref mpv) = *inner.mpv.lock().unwrap() {
             mpv.command("cycle", &["audio"])
@@ -1214,6 +1712,128 @@ impl MpvPlayer {
 }
 
 impl MpvPlayerInner {
+    fn replaygain_value(mode: ReplayGainMode) -> &'static str {
+        match mode {
+            ReplayGainMode::Off => "no",
+            ReplayGainMode::Track => "track",
+            ReplayGainMode::Album => "album",
+        }
+    }
+
+    fn emit_property_changes(&self, watched: &mut WatchedProperties) {
+        let guard = self.mpv.lock().unwrap();
+        let Some(mpv) = guard.as_ref() else {
+            return;
+        };
+
+        // Nothing is loaded; start fresh for the next file
+        if mpv.get_property::<bool>("idle-active").unwrap_or(true) {
+            *watched = WatchedProperties::default();
+            return;
+        }
+
+        // The file handed to loadfile has opened, so subtitles asked for meanwhile can go in
+        let opened = {
+            let mut opening = self.opening_url.lock().unwrap();
+            let opened = opening.is_some()
+                && mpv.get_property::<String>("path").ok().as_deref() == opening.as_deref()
+                && mpv.get_property::<String>("file-format").is_ok();
+            if opened {
+                *opening = None;
+            }
+            opened
+        };
+        if opened {
+            let pending = std::mem::take(&mut *self.pending_subtitles.lock().unwrap());
+            for args in pending {
+                if let Err(e) = mpv.command("sub-add", &args.each_ref().map(String::as_str)) {
+                    warn!("Failed to add subtitle from {}: {:?}", args[0], e);
+                }
+            }
+        }
+
+        if let Ok(paused) = mpv.get_property::<bool>("pause")
+            && watched.paused != Some(paused)
+        {
+            watched.paused = Some(paused);
+            self.events.set_state(if paused {
+                PlayerState::Paused
+            } else {
+                PlayerState::Playing
+            });
+        }
+
+        // The queued file took over; mpv reports its path exactly as it was appended
+        let queued_started = {
+            let mut queued = self.queued_next_url.lock().unwrap();
+            let started = queued.is_some()
+                && mpv.get_property::<String>("path").ok().as_deref() == queued.as_deref();
+            if started {
+                *queued = None;
+            }
+            started
+        };
+        if queued_started {
+            watched.eof_reached = false;
+            watched.position = None;
+            self.events.emit(PlayerEvent::NextMediaStarted);
+        }
+
+        // keep-open leaves the last frame up, so the end shows as eof-reached
+        let eof_reached = mpv.get_property::<bool>("eof-reached").unwrap_or(false);
+        if eof_reached && !watched.eof_reached {
+            self.events.emit(PlayerEvent::EndOfStream);
+        }
+        watched.eof_reached = eof_reached;
+
+        if let Ok(track_count) = mpv.get_property::<i64>("track-list/count")
+            && watched.track_count != Some(track_count)
+        {
+            watched.track_count = Some(track_count);
+            self.events.emit(PlayerEvent::TracksChanged);
+        }
+
+        if let Ok(chapter_count) = mpv.get_property::<i64>("chapter-list/count")
+            && watched.chapter_count != Some(chapter_count)
+        {
+            watched.chapter_count = Some(chapter_count);
+            self.events.emit(PlayerEvent::ChaptersChanged);
+        }
+
+        if mpv
+            .get_property::<bool>("paused-for-cache")
+            .unwrap_or(false)
+        {
+            let percent = mpv
+                .get_property::<i64>("cache-buffering-state")
+                .unwrap_or(0)
+                .clamp(0, 100) as u8;
+            if watched.buffering_percent != Some(percent) {
+                watched.buffering_percent = Some(percent);
+                self.events.emit(PlayerEvent::Buffering(percent));
+            }
+        } else if watched.buffering_percent.take().is_some() {
+            self.events.emit(PlayerEvent::Buffering(100));
+        }
+
+        if let Ok(position) = mpv.get_property::<f64>("time-pos") {
+            let position = Duration::from_secs_f64(position.max(0.0));
+            // Report steady progress about once per interval, and seeks right away
+            let should_report = watched
+                .position
+                .is_none_or(|last| position < last || position - last >= POSITION_UPDATE_INTERVAL);
+            if should_report {
+                watched.position = Some(position);
+                let duration = mpv
+                    .get_property::<f64>("duration")
+                    .ok()
+                    .map(|d| Duration::from_secs_f64(d.max(0.0)));
+                self.events
+                    .emit(PlayerEvent::PositionChanged { position, duration });
+            }
+        }
+    }
+
     fn init_mpv(&self) -> Result<Mpv> {
         info!("Creating MPV instance");
 
@@ -1278,6 +1898,27 @@ impl MpvPlayerInner {
         // Set basic options
         mpv.set_property("keep-open", "yes")
             .map_err(|e| anyhow::anyhow!("Failed to set keep-open: {:?}", e))?;
+        // Queued files start without a gap, opened ahead of time
+        mpv.set_property("gapless-audio", "yes").unwrap_or(());
+        mpv.set_property("prefetch-playlist", true).unwrap_or(());
+        mpv.set_property(
+            "replaygain",
+            Self::replaygain_value(*self.replaygain.lock().unwrap()),
+        )
+        .unwrap_or(());
+        mpv.set_property("audio-device", self.audio_device.lock().unwrap().as_str())
+            .unwrap_or(());
+        mpv.set_property("audio-spdif", self.audio_spdif.lock().unwrap().as_str())
+            .unwrap_or(());
+        mpv.set_property("af", self.audio_filter.lock().unwrap().as_str())
+            .unwrap_or(());
+        // Speed changes go through scaletempo2, keeping voices at their pitch
+        mpv.set_property("audio-pitch-correction", true)
+            .unwrap_or(());
+        if *self.audio_only.lock().unwrap() {
+            mpv.set_property("vid", "no")
+                .map_err(|e| anyhow::anyhow!("Failed to set vid: {:?}", e))?;
+        }
         mpv.set_property("hwdec", "auto-safe") // auto-safe is more stable than auto
             .map_err(|e| anyhow::anyhow!("Failed to set hwdec: {:?}", e))?;
         mpv.set_property("input-default-bindings", false)
@@ -1364,6 +2005,10 @@ impl Drop for MpvPlayerInner {
             seek_timer.remove();
         }
 
+        if let Some(event_timer) = self.event_timer.lock().unwrap().take() {
+            event_timer.remove();
+        }
+
         // Clean up render context
         if let Some(MpvRenderContextPtr(mpv_gl)) = self.mpv_gl.lock().unwrap().take() {
             unsafe {
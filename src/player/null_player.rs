@@ -0,0 +1,1048 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use gtk4::{self, prelude::*};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{debug, info};
+
+use crate::core::player_traits::{
+    MediaPlayer, PlaybackStats, PlayerCapabilities, PlayerEvent, PlayerEventSource, PlayerState,
+    ReplayGainMode,
+};
+use crate::models::{Chapter, VideoAdjustments};
+
+/// How often the simulated timeline is advanced and checked for the end or a failure
+const TICK_INTERVAL: Duration = Duration::from_millis(20);
+/// Minimum position change before another position update is pushed
+const POSITION_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+/// Length of media whose URL doesn't say otherwise
+const DEFAULT_DURATION: Duration = Duration::from_secs(30 * 60);
+/// One frame at 24 fps, used for frame stepping
+const FRAME_DURATION: Duration = Duration::from_nanos(41_666_667);
+const VIDEO_DIMENSIONS: (i32, i32) = (1920, 1080);
+
+/// Time source for the simulated timeline. The manual clock only moves when
+/// told to, so tests can play through an hour of media instantly.
+#[derive(Clone)]
+pub enum NullClock {
+    System(Instant),
+    Manual(Arc<Mutex<Duration>>),
+}
+
+impl NullClock {
+    pub fn system() -> Self {
+        Self::System(Instant::now())
+    }
+
+    pub fn manual() -> Self {
+        Self::Manual(Arc::new(Mutex::new(Duration::ZERO)))
+    }
+
+    /// Move a manual clock forward; does nothing for the system clock
+    pub fn advance(&self, by: Duration) {
+        if let Self::Manual(now) = self {
+            *now.lock().unwrap() += by;
+        }
+    }
+
+    fn now(&self) -> Duration {
+        match self {
+            Self::System(start) => start.elapsed(),
+            Self::Manual(now) => *now.lock().unwrap(),
+        }
+    }
+}
+
+/// What the loaded URL asked for. Query parameters on any URL can shape the media:
+/// `duration` (seconds), `audio` and `subtitles` (track counts), `chapters` (evenly
+/// spaced), `fail=load` to make loading fail and `fail_at` (seconds) to fail
+/// partway through playback.
+#[derive(Debug, Clone, PartialEq)]
+struct SimulatedMedia {
+    duration: Duration,
+    audio_tracks: usize,
+    subtitle_tracks: usize,
+    chapters: usize,
+    fail_on_load: bool,
+    fail_at: Option<Duration>,
+}
+
+impl SimulatedMedia {
+    fn from_url(url: &str) -> Self {
+        let mut media = Self {
+            duration: DEFAULT_DURATION,
+            audio_tracks: 1,
+            subtitle_tracks: 1,
+            chapters: 0,
+            fail_on_load: false,
+            fail_at: None,
+        };
+
+        let Ok(url) = url::Url::parse(url) else {
+            return media;
+        };
+        for (key, value) in url.query_pairs() {
+            match key.as_ref() {
+                "duration" => {
+                    if let Ok(secs) = value.parse::<f64>() {
+                        media.duration = Duration::from_secs_f64(secs.max(0.0));
+                    }
+                }
+                "audio" => media.audio_tracks = value.parse().unwrap_or(media.audio_tracks),
+                "subtitles" => {
+                    media.subtitle_tracks = value.parse().unwrap_or(media.subtitle_tracks)
+                }
+                "chapters" => media.chapters = value.parse().unwrap_or(media.chapters),
+                "fail" => media.fail_on_load = value == "load",
+                "fail_at" => {
+                    media.fail_at = value
+                        .parse::<f64>()
+                        .ok()
+                        .map(|secs| Duration::from_secs_f64(secs.max(0.0)))
+                }
+                _ => {}
+            }
+        }
+        media
+    }
+
+    fn chapters(&self) -> Vec<Chapter> {
+        (0..self.chapters)
+            .map(|i| Chapter {
+                title: format!("Chapter {}", i + 1),
+                start_time: self.duration * i as u32 / self.chapters as u32,
+                thumbnail_url: None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Default)]
+struct Timeline {
+    media: Option<SimulatedMedia>,
+    /// Position when the clock was last anchored
+    position: Duration,
+    /// Clock time playback last (re)started, while playing
+    anchor: Option<Duration>,
+    speed: f64,
+    volume: f64,
+    muted: bool,
+    audio_tracks: Vec<String>,
+    subtitle_tracks: Vec<String>,
+    current_audio_track: i32,
+    current_subtitle_track: i32,
+    subtitle_delay_ms: i64,
+    audio_delay_ms: i64,
+    last_reported_position: Option<Duration>,
+    /// Media queued to take over at the end without a gap
+    next_media: Option<SimulatedMedia>,
+    audio_only: bool,
+    /// Section jumped back to the start of when its end is reached
+    ab_loop: Option<(Duration, Duration)>,
+}
+
+impl Timeline {
+    fn current_position(&self, now: Duration) -> Duration {
+        let Some(media) = &self.media else {
+            return Duration::ZERO;
+        };
+        let elapsed = self
+            .anchor
+            .map(|anchor| now.saturating_sub(anchor).mul_f64(self.speed))
+            .unwrap_or_default();
+        (self.position + elapsed).min(media.duration)
+    }
+
+    /// Swap in new media at the start, with its default tracks
+    fn load(&mut self, media: SimulatedMedia) {
+        self.audio_tracks = (1..=media.audio_tracks)
+            .map(|i| format!("Audio {}", i))
+            .collect();
+        self.subtitle_tracks = (1..=media.subtitle_tracks)
+            .map(|i| format!("Subtitle {}", i))
+            .collect();
+        self.current_audio_track = if media.audio_tracks > 0 { 1 } else { -1 };
+        self.current_subtitle_track = -1;
+        self.media = Some(media);
+        self.position = Duration::ZERO;
+        self.last_reported_position = Some(Duration::ZERO);
+    }
+
+    /// Freeze the timeline at its current position
+    fn settle(&mut self, now: Duration) {
+        self.position = self.current_position(now);
+        if self.anchor.is_some() {
+            self.anchor = Some(now);
+        }
+    }
+}
+
+struct NullPlayerInner {
+    timeline: Mutex<Timeline>,
+    events: PlayerEventSource,
+    clock: NullClock,
+}
+
+impl NullPlayerInner {
+    /// Advance the simulated timeline, reporting progress, the end and injected failures
+    fn tick(&self) {
+        let now = self.clock.now();
+        let mut timeline = self.timeline.lock().unwrap();
+        if timeline.anchor.is_none() {
+            return;
+        }
+        let Some(media) = timeline.media.clone() else {
+            return;
+        };
+
+        let position = timeline.current_position(now);
+        if let Some(fail_at) = media.fail_at
+            && position >= fail_at
+        {
+            timeline.position = fail_at;
+            timeline.anchor = None;
+            drop(timeline);
+            self.fail("Simulated playback error");
+            return;
+        }
+
+        if let Some((start, end)) = timeline.ab_loop
+            && position >= end
+        {
+            timeline.position = start;
+            timeline.anchor = Some(now);
+            timeline.last_reported_position = Some(start);
+            drop(timeline);
+            self.report_position(start, Some(media.duration));
+            return;
+        }
+
+        if position >= media.duration
+            && let Some(next) = timeline.next_media.take()
+        {
+            let (duration, has_chapters) = (next.duration, next.chapters > 0);
+            timeline.load(next);
+            timeline.anchor = Some(now);
+            drop(timeline);
+            self.events.emit(PlayerEvent::NextMediaStarted);
+            self.events.emit(PlayerEvent::TracksChanged);
+            if has_chapters {
+                self.events.emit(PlayerEvent::ChaptersChanged);
+            }
+            self.report_position(Duration::ZERO, Some(duration));
+            return;
+        }
+
+        if position >= media.duration {
+            timeline.position = media.duration;
+            timeline.anchor = None;
+            timeline.last_reported_position = Some(media.duration);
+            drop(timeline);
+            self.report_position(media.duration, Some(media.duration));
+            self.events.set_state(PlayerState::Stopped);
+            self.events.emit(PlayerEvent::EndOfStream);
+            return;
+        }
+
+        let should_report = timeline
+            .last_reported_position
+            .is_none_or(|last| position < last || position - last >= POSITION_UPDATE_INTERVAL);
+        if should_report {
+            timeline.last_reported_position = Some(position);
+            drop(timeline);
+            self.report_position(position, Some(media.duration));
+        }
+    }
+
+    fn report_position(&self, position: Duration, duration: Option<Duration>) {
+        self.events
+            .emit(PlayerEvent::PositionChanged { position, duration });
+    }
+
+    fn fail(&self, message: &str) {
+        debug!("NullPlayer: {}", message);
+        self.events.set_state(PlayerState::Error);
+        self.events.emit(PlayerEvent::Error(message.to_string()));
+    }
+}
+
+/// A player backend that simulates a media timeline in software, without any
+/// display, audio output or network access. Used for headless testing.
+#[derive(Clone)]
+pub struct NullPlayer {
+    inner: Arc<NullPlayerInner>,
+}
+
+impl NullPlayer {
+    pub fn new() -> Self {
+        Self::with_clock(NullClock::system())
+    }
+
+    pub fn with_clock(clock: NullClock) -> Self {
+        info!("Initializing null player backend");
+
+        let inner = Arc::new(NullPlayerInner {
+            timeline: Mutex::new(Timeline {
+                speed: 1.0,
+                volume: 1.0,
+                current_subtitle_track: -1,
+                ..Default::default()
+            }),
+            events: PlayerEventSource::new(),
+            clock,
+        });
+
+        // Plain thread rather than a runtime timer so this works under glib and tokio alike
+        let weak: Weak<NullPlayerInner> = Arc::downgrade(&inner);
+        std::thread::spawn(move || {
+            while let Some(inner) = weak.upgrade() {
+                inner.tick();
+                drop(inner);
+                std::thread::sleep(TICK_INTERVAL);
+            }
+        });
+
+        Self { inner }
+    }
+
+    /// Fail playback as if the stream broke
+    pub fn inject_error(&self, message: &str) {
+        let now = self.inner.clock.now();
+        {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            timeline.settle(now);
+            timeline.anchor = None;
+        }
+        self.inner.fail(message);
+    }
+
+    fn loaded(&self) -> Result<()> {
+        if self.inner.timeline.lock().unwrap().media.is_some() {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("No media loaded"))
+        }
+    }
+}
+
+impl Default for NullPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait(?Send)]
+impl MediaPlayer for NullPlayer {
+    fn backend_name(&self) -> &'static str {
+        "Null"
+    }
+
+    fn capabilities(&self) -> PlayerCapabilities {
+        PlayerCapabilities {
+            frame_step_backward: true,
+            upscaling: false,
+            playback_speed: true,
+            subtitle_delay: true,
+            audio_delay: true,
+            external_subtitles: true,
+            gapless: true,
+            replaygain: true,
+            video_adjustments: true,
+            black_bar_detection: false,
+            screenshots: false,
+            audio_output_selection: false,
+            audio_passthrough: false,
+            audio_effects: false,
+            ab_loop: true,
+        }
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<PlayerEvent> {
+        self.inner.events.subscribe()
+    }
+
+    fn create_video_widget(&self) -> gtk4::Widget {
+        gtk4::Picture::new().upcast()
+    }
+
+    async fn load_media(&self, url: &str) -> Result<()> {
+        info!("NullPlayer: Loading media: {}", url);
+        let media = SimulatedMedia::from_url(url);
+        self.inner.events.set_state(PlayerState::Loading);
+
+        if media.fail_on_load {
+            self.inner.fail("Simulated load error");
+            return Err(anyhow::anyhow!("Simulated load error"));
+        }
+
+        let duration = media.duration;
+        let has_chapters = media.chapters > 0;
+        {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            timeline.load(media);
+            timeline.anchor = None;
+            timeline.next_media = None;
+        }
+
+        self.inner.events.emit(PlayerEvent::TracksChanged);
+        if has_chapters {
+            self.inner.events.emit(PlayerEvent::ChaptersChanged);
+        }
+        self.inner.report_position(Duration::ZERO, Some(duration));
+        Ok(())
+    }
+
+    async fn play(&self) -> Result<()> {
+        self.loaded()?;
+        let now = self.inner.clock.now();
+        {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            if timeline.anchor.is_none() {
+                timeline.anchor = Some(now);
+            }
+        }
+        self.inner.events.set_state(PlayerState::Playing);
+        Ok(())
+    }
+
+    async fn pause(&self) -> Result<()> {
+        self.loaded()?;
+        let now = self.inner.clock.now();
+        {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            timeline.settle(now);
+            timeline.anchor = None;
+        }
+        self.inner.events.set_state(PlayerState::Paused);
+        Ok(())
+    }
+
+    async fn stop(&self) -> Result<()> {
+        {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            timeline.media = None;
+            timeline.next_media = None;
+            timeline.position = Duration::ZERO;
+            timeline.anchor = None;
+            timeline.last_reported_position = None;
+        }
+        self.inner.events.set_state(PlayerState::Stopped);
+        Ok(())
+    }
+
+    async fn seek(&self, position: Duration) -> Result<()> {
+        self.loaded()?;
+        let now = self.inner.clock.now();
+        let (position, duration) = {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            let duration = timeline.media.as_ref().map(|m| m.duration);
+            timeline.position = position.min(duration.unwrap_or(position));
+            if timeline.anchor.is_some() {
+                timeline.anchor = Some(now);
+            }
+            timeline.last_reported_position = Some(timeline.position);
+            (timeline.position, duration)
+        };
+        self.inner.report_position(position, duration);
+        Ok(())
+    }
+
+    async fn get_position(&self) -> Option<Duration> {
+        let now = self.inner.clock.now();
+        let timeline = self.inner.timeline.lock().unwrap();
+        timeline
+            .media
+            .as_ref()
+            .map(|_| timeline.current_position(now))
+    }
+
+    async fn get_duration(&self) -> Option<Duration> {
+        let timeline = self.inner.timeline.lock().unwrap();
+        timeline.media.as_ref().map(|m| m.duration)
+    }
+
+    async fn get_state(&self) -> PlayerState {
+        self.inner.events.state()
+    }
+
+    async fn get_video_dimensions(&self) -> Option<(i32, i32)> {
+        let timeline = self.inner.timeline.lock().unwrap();
+        timeline
+            .media
+            .as_ref()
+            .filter(|_| !timeline.audio_only)
+            .map(|_| VIDEO_DIMENSIONS)
+    }
+
+    async fn set_audio_only(&self, audio_only: bool) -> Result<()> {
+        self.inner.timeline.lock().unwrap().audio_only = audio_only;
+        Ok(())
+    }
+
+    async fn set_volume(&self, volume: f64) -> Result<()> {
+        self.inner.timeline.lock().unwrap().volume = volume.clamp(0.0, 1.0);
+        Ok(())
+    }
+
+    async fn toggle_mute(&self) -> Result<()> {
+        let mut timeline = self.inner.timeline.lock().unwrap();
+        timeline.muted = !timeline.muted;
+        Ok(())
+    }
+
+    async fn is_muted(&self) -> bool {
+        self.inner.timeline.lock().unwrap().muted
+    }
+
+    async fn get_audio_tracks(&self) -> Vec<(i32, String)> {
+        let timeline = self.inner.timeline.lock().unwrap();
+        (1..).zip(timeline.audio_tracks.iter().cloned()).collect()
+    }
+
+    async fn get_subtitle_tracks(&self) -> Vec<(i32, String)> {
+        let timeline = self.inner.timeline.lock().unwrap();
+        std::iter::once((-1, "None".to_string()))
+            .chain((1..).zip(timeline.subtitle_tracks.iter().cloned()))
+            .collect()
+    }
+
+    async fn set_audio_track(&self, track_index: i32) -> Result<()> {
+        let mut timeline = self.inner.timeline.lock().unwrap();
+        if track_index < 1 || track_index as usize > timeline.audio_tracks.len() {
+            return Err(anyhow::anyhow!("No audio track {}", track_index));
+        }
+        timeline.current_audio_track = track_index;
+        Ok(())
+    }
+
+    async fn set_subtitle_track(&self, track_index: i32) -> Result<()> {
+        let mut timeline = self.inner.timeline.lock().unwrap();
+        if track_index > timeline.subtitle_tracks.len() as i32 {
+            return Err(anyhow::anyhow!("No subtitle track {}", track_index));
+        }
+        timeline.current_subtitle_track = track_index.max(-1);
+        Ok(())
+    }
+
+    async fn get_current_audio_track(&self) -> i32 {
+        self.inner.timeline.lock().unwrap().current_audio_track
+    }
+
+    async fn get_current_subtitle_track(&self) -> i32 {
+        self.inner.timeline.lock().unwrap().current_subtitle_track
+    }
+
+    async fn cycle_audio_track(&self) -> Result<()> {
+        let mut timeline = self.inner.timeline.lock().unwrap();
+        let count = timeline.audio_tracks.len() as i32;
+        if count > 0 {
+            timeline.current_audio_track = timeline.current_audio_track % count + 1;
+        }
+        Ok(())
+    }
+
+    async fn cycle_subtitle_track(&self) -> Result<()> {
+        let mut timeline = self.inner.timeline.lock().unwrap();
+        let count = timeline.subtitle_tracks.len() as i32;
+        // Cycles through every track and then off again, like mpv
+        timeline.current_subtitle_track = match timeline.current_subtitle_track {
+            current if current >= count => -1,
+            current => current.max(0) + 1,
+        };
+        Ok(())
+    }
+
+    async fn add_subtitle(
+        &self,
+        _url: &str,
+        title: &str,
+        _language: Option<&str>,
+        select: bool,
+    ) -> Result<()> {
+        self.loaded()?;
+        {
+            let mut timeline = self.inner.timeline.lock().unwrap();
+            timeline.subtitle_tracks.push(title.to_string());
+            if select {
+                timeline.current_subtitle_track = timeline.subtitle_tracks.len() as i32;
+            }
+        }
+        self.inner.events.emit(PlayerEvent::TracksChanged);
+        Ok(())
+    }
+
+    async fn set_subtitle_delay(&self, delay_ms: i64) -> Result<()> {
+        self.inner.timeline.lock().unwrap().subtitle_delay_ms = delay_ms;
+        Ok(())
+    }
+
+    async fn set_audio_delay(&self, delay_ms: i64) -> Result<()> {
+        self.inner.timeline.lock().unwrap().audio_delay_ms = delay_ms;
+        Ok(())
+    }
+
+    async fn set_playback_speed(&self, speed: f64) -> Result<()> {
+        if speed <= 0.0 {
+            return Err(anyhow::anyhow!("Invalid playback speed {}", speed));
+        }
+        let now = self.inner.clock.now();
+        let mut timeline = self.inner.timeline.lock().unwrap();
+        timeline.settle(now);
+        timeline.speed = speed;
+        Ok(())
+    }
+
+    async fn get_playback_speed(&self) -> f64 {
+        self.inner.timeline.lock().unwrap().speed
+    }
+
+    async fn get_stats(&self) -> Result<PlaybackStats> {
+        let timeline = self.inner.timeline.lock().unwrap();
+        if timeline.media.is_none() {
+            return Ok(PlaybackStats::default());
+        }
+        // Simulated frames are never late and nothing is downloaded
+        let video = !timeline.audio_only;
+        Ok(PlaybackStats {
+            container: Some("simulated".to_string()),
+            video_codec: video.then(|| "null".to_string()),
+            audio_codec: Some("null".to_string()),
+            resolution: video.then_some((VIDEO_DIMENSIONS.0 as u32, VIDEO_DIMENSIONS.1 as u32)),
+            frame_rate: video.then(|| 1.0 / FRAME_DURATION.as_secs_f64()),
+            decoder: video.then(|| "null".to_string()),
+            hwdec: None,
+            dropped_frames: Some(0),
+            delayed_frames: Some(0),
+            cache_duration: None,
+            buffer_percent: None,
+            network_bytes_per_second: None,
+        })
+    }
+
+    async fn get_chapters(&self) -> Vec<Chapter> {
+        let timeline = self.inner.timeline.lock().unwrap();
+        timeline
+            .media
+            .as_ref()
+            .map(SimulatedMedia::chapters)
+            .unwrap_or_default()
+    }
+
+    async fn queue_next(&self, url: Option<&str>) -> Result<()> {
+        // Media that fails to load is only noticed when it would start, so it is skipped
+        let next = url
+            .map(SimulatedMedia::from_url)
+            .filter(|media| !media.fail_on_load);
+        self.inner.timeline.lock().unwrap().next_media = next;
+        Ok(())
+    }
+
+    async fn set_replaygain(&self, mode: ReplayGainMode) -> Result<()> {
+        // Simulated media has no loudness to even out
+        debug!("NullPlayer: ReplayGain set to {}", mode.as_str());
+        Ok(())
+    }
+
+    async fn set_ab_loop(&self, range: Option<(Duration, Duration)>) -> Result<()> {
+        if let Some((start, end)) = range
+            && end <= start
+        {
+            return Err(anyhow::anyhow!("The loop ends before it starts"));
+        }
+        self.inner.timeline.lock().unwrap().ab_loop = range;
+        Ok(())
+    }
+
+    async fn set_video_adjustments(&self, adjustments: VideoAdjustments) -> Result<()> {
+        // There is no picture to adjust
+        debug!("NullPlayer: Video adjustments set to {:?}", adjustments);
+        Ok(())
+    }
+
+    async fn frame_step_forward(&self) -> Result<()> {
+        let position = self.get_position().await.unwrap_or_default();
+        self.seek(position + FRAME_DURATION).await
+    }
+
+    async fn frame_step_backward(&self) -> Result<()> {
+        let position = self.get_position().await.unwrap_or_default();
+        self.seek(position.saturating_sub(FRAME_DURATION)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::{PlayerController, PlayerHandle};
+
+    /// Long enough for the tick thread to notice a clock change
+    const EVENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Run a test against a controller fronting a null player on a manual clock
+    async fn with_player<F, Fut>(test: F)
+    where
+        F: FnOnce(PlayerHandle, NullPlayer, NullClock) -> Fut,
+        Fut: std::future::Future<Output = ()>,
+    {
+        let clock = NullClock::manual();
+        let player = NullPlayer::with_clock(clock.clone());
+        let (handle, controller) = PlayerController::with_player(Box::new(player.clone()));
+
+        let local = tokio::task::LocalSet::new();
+        local
+            .run_until(async move {
+                tokio::task::spawn_local(controller.run());
+                test(handle.clone(), player, clock).await;
+                handle.shutdown().ok();
+            })
+            .await;
+    }
+
+    async fn wait_for(
+        events: &mut broadcast::Receiver<PlayerEvent>,
+        matches: impl Fn(&PlayerEvent) -> bool,
+    ) -> PlayerEvent {
+        tokio::time::timeout(EVENT_TIMEOUT, async {
+            loop {
+                let event = events.recv().await.expect("player event channel closed");
+                if matches(&event) {
+                    return event;
+                }
+            }
+        })
+        .await
+        .expect("timed out waiting for player event")
+    }
+
+    #[test]
+    fn test_simulated_media_from_url() {
+        let media =
+            SimulatedMedia::from_url("null://episode?duration=90&audio=2&subtitles=0&fail_at=30");
+        assert_eq!(media.duration, Duration::from_secs(90));
+        assert_eq!(media.audio_tracks, 2);
+        assert_eq!(media.subtitle_tracks, 0);
+        assert_eq!(media.fail_at, Some(Duration::from_secs(30)));
+        assert!(!media.fail_on_load);
+
+        let media = SimulatedMedia::from_url("https://server/video.mkv");
+        assert_eq!(media.duration, DEFAULT_DURATION);
+    }
+
+    #[tokio::test]
+    async fn test_plays_to_end_of_stream() {
+        with_player(|handle, _player, clock| async move {
+            let mut events = handle.subscribe().await.unwrap();
+            handle.load_media("null://movie?duration=10").await.unwrap();
+            handle.play().await.unwrap();
+            assert_eq!(handle.get_state().await.unwrap(), PlayerState::Playing);
+
+            clock.advance(Duration::from_secs(4));
+            let event = wait_for(&mut events, |e| {
+                matches!(e, PlayerEvent::PositionChanged { position, .. } if *position >= Duration::from_secs(4))
+            })
+            .await;
+            assert_eq!(
+                event,
+                PlayerEvent::PositionChanged {
+                    position: Duration::from_secs(4),
+                    duration: Some(Duration::from_secs(10)),
+                }
+            );
+
+            clock.advance(Duration::from_secs(10));
+            wait_for(&mut events, |e| *e == PlayerEvent::EndOfStream).await;
+            assert_eq!(handle.get_state().await.unwrap(), PlayerState::Stopped);
+            assert_eq!(
+                handle.get_position().await.unwrap(),
+                Some(Duration::from_secs(10))
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_pause_seek_and_speed() {
+        with_player(|handle, _player, clock| async move {
+            handle
+                .load_media("null://movie?duration=600")
+                .await
+                .unwrap();
+            handle.play().await.unwrap();
+
+            clock.advance(Duration::from_secs(5));
+            handle.pause().await.unwrap();
+            clock.advance(Duration::from_secs(60));
+            assert_eq!(
+                handle.get_position().await.unwrap(),
+                Some(Duration::from_secs(5))
+            );
+
+            handle.seek(Duration::from_secs(100)).await.unwrap();
+            handle.set_playback_speed(2.0).await.unwrap();
+            handle.play().await.unwrap();
+            clock.advance(Duration::from_secs(10));
+            assert_eq!(
+                handle.get_position().await.unwrap(),
+                Some(Duration::from_secs(120))
+            );
+            assert_eq!(handle.get_playback_speed().await.unwrap(), 2.0);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_ab_loop() {
+        with_player(|handle, _player, clock| async move {
+            let mut events = handle.subscribe().await.unwrap();
+            handle
+                .load_media("null://movie?duration=600")
+                .await
+                .unwrap();
+            handle.play().await.unwrap();
+            handle
+                .set_ab_loop(Some((Duration::from_secs(10), Duration::from_secs(20))))
+                .await
+                .unwrap();
+
+            clock.advance(Duration::from_secs(25));
+            wait_for(&mut events, |e| {
+                *e == PlayerEvent::PositionChanged {
+                    position: Duration::from_secs(10),
+                    duration: Some(Duration::from_secs(600)),
+                }
+            })
+            .await;
+
+            // Cleared, playback runs on past the end of the loop
+            handle.set_ab_loop(None).await.unwrap();
+            clock.advance(Duration::from_secs(15));
+            assert_eq!(
+                handle.get_position().await.unwrap(),
+                Some(Duration::from_secs(25))
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_tracks() {
+        with_player(|handle, _player, _clock| async move {
+            let mut events = handle.subscribe().await.unwrap();
+            handle
+                .load_media("null://movie?audio=2&subtitles=1")
+                .await
+                .unwrap();
+            wait_for(&mut events, |e| *e == PlayerEvent::TracksChanged).await;
+
+            assert_eq!(handle.get_audio_tracks().await.unwrap().len(), 2);
+            handle.cycle_audio_track().await.unwrap();
+            assert_eq!(handle.get_current_audio_track().await.unwrap(), 2);
+
+            handle
+                .add_subtitle("https://server/subs.srt", "English", Some("en"), true)
+                .await
+                .unwrap();
+            wait_for(&mut events, |e| *e == PlayerEvent::TracksChanged).await;
+            let subtitles = handle.get_subtitle_tracks().await.unwrap();
+            assert_eq!(subtitles.last(), Some(&(2, "English".to_string())));
+            assert_eq!(handle.get_current_subtitle_track().await.unwrap(), 2);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        with_player(|handle, _player, _clock| async move {
+            // Nothing to report before something is loaded
+            assert_eq!(handle.get_stats().await.unwrap(), PlaybackStats::default());
+
+            handle.load_media("null://movie").await.unwrap();
+            let stats = handle.get_stats().await.unwrap();
+            assert_eq!(
+                stats.resolution,
+                Some((VIDEO_DIMENSIONS.0 as u32, VIDEO_DIMENSIONS.1 as u32))
+            );
+            assert_eq!(stats.frame_rate, Some(1.0 / FRAME_DURATION.as_secs_f64()));
+            assert_eq!(stats.dropped_frames, Some(0));
+            assert_eq!(stats.network_bytes_per_second, None);
+
+            // Music has no picture to describe
+            handle.set_audio_only(true).await.unwrap();
+            let stats = handle.get_stats().await.unwrap();
+            assert_eq!(stats.video_codec, None);
+            assert_eq!(stats.resolution, None);
+            assert_eq!(stats.audio_codec.as_deref(), Some("null"));
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_chapters() {
+        with_player(|handle, _player, _clock| async move {
+            let mut events = handle.subscribe().await.unwrap();
+            handle
+                .load_media("null://movie?duration=100&chapters=4")
+                .await
+                .unwrap();
+            wait_for(&mut events, |e| *e == PlayerEvent::ChaptersChanged).await;
+
+            let chapters = handle.get_chapters().await.unwrap();
+            assert_eq!(chapters.len(), 4);
+            assert_eq!(chapters[2].title, "Chapter 3");
+            assert_eq!(chapters[2].start_time, Duration::from_secs(50));
+            assert_eq!(
+                Chapter::index_at(&chapters, Duration::from_secs(74)),
+                Some(2)
+            );
+
+            handle.seek_to_chapter(3).await.unwrap();
+            assert_eq!(
+                handle.get_position().await.unwrap(),
+                Some(Duration::from_secs(75))
+            );
+            assert!(handle.seek_to_chapter(4).await.is_err());
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_gapless_queue_next() {
+        with_player(|handle, _player, clock| async move {
+            let mut events = handle.subscribe().await.unwrap();
+            handle.set_audio_only(true).await.unwrap();
+            handle
+                .load_media("null://track-1?duration=10")
+                .await
+                .unwrap();
+            handle
+                .queue_next(Some("null://track-2?duration=20"))
+                .await
+                .unwrap();
+            handle.play().await.unwrap();
+            assert_eq!(handle.get_video_dimensions().await.unwrap(), None);
+
+            // The queued track takes over without stopping
+            clock.advance(Duration::from_secs(10));
+            wait_for(&mut events, |e| *e == PlayerEvent::NextMediaStarted).await;
+            assert_eq!(handle.get_state().await.unwrap(), PlayerState::Playing);
+            assert_eq!(
+                handle.get_duration().await.unwrap(),
+                Some(Duration::from_secs(20))
+            );
+
+            // Nothing queued after it, so the second track ends normally
+            clock.advance(Duration::from_secs(20));
+            wait_for(&mut events, |e| *e == PlayerEvent::EndOfStream).await;
+            assert_eq!(handle.get_state().await.unwrap(), PlayerState::Stopped);
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_gapless_queue_next_dropped() {
+        with_player(|handle, _player, clock| async move {
+            let mut events = handle.subscribe().await.unwrap();
+
+            // Taking the queued track back lets the current one end normally
+            handle
+                .load_media("null://track-1?duration=10")
+                .await
+                .unwrap();
+            handle
+                .queue_next(Some("null://track-2?duration=20"))
+                .await
+                .unwrap();
+            handle.queue_next(None).await.unwrap();
+            handle.play().await.unwrap();
+            clock.advance(Duration::from_secs(10));
+            let event = wait_for(&mut events, |e| {
+                matches!(e, PlayerEvent::NextMediaStarted | PlayerEvent::EndOfStream)
+            })
+            .await;
+            assert_eq!(event, PlayerEvent::EndOfStream);
+
+            // So does loading something else, and a queued track that would fail to load
+            handle
+                .load_media("null://track-3?duration=10")
+                .await
+                .unwrap();
+            handle
+                .queue_next(Some("null://track-4?duration=20"))
+                .await
+                .unwrap();
+            handle
+                .load_media("null://track-5?duration=10")
+                .await
+                .unwrap();
+            handle
+                .queue_next(Some("null://track-6?fail=load"))
+                .await
+                .unwrap();
+            handle.play().await.unwrap();
+            clock.advance(Duration::from_secs(10));
+            let event = wait_for(&mut events, |e| {
+                matches!(e, PlayerEvent::NextMediaStarted | PlayerEvent::EndOfStream)
+            })
+            .await;
+            assert_eq!(event, PlayerEvent::EndOfStream);
+            assert_eq!(
+                handle.get_duration().await.unwrap(),
+                Some(Duration::from_secs(10))
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_audio_only_and_replaygain() {
+        with_player(|handle, _player, _clock| async move {
+            handle.load_media("null://track").await.unwrap();
+            assert_eq!(
+                handle.get_video_dimensions().await.unwrap(),
+                Some(VIDEO_DIMENSIONS)
+            );
+
+            handle.set_audio_only(true).await.unwrap();
+            handle.set_replaygain(ReplayGainMode::Album).await.unwrap();
+            assert_eq!(handle.get_video_dimensions().await.unwrap(), None);
+
+            handle.set_audio_only(false).await.unwrap();
+            assert_eq!(
+                handle.get_video_dimensions().await.unwrap(),
+                Some(VIDEO_DIMENSIONS)
+            );
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_injected_errors() {
+        with_player(|handle, player, clock| async move {
+            assert!(handle.load_media("null://broken?fail=load").await.is_err());
+            assert_eq!(handle.get_state().await.unwrap(), PlayerState::Error);
+
+            let mut events = handle.subscribe().await.unwrap();
+
+            handle
+                .load_media("null://flaky?duration=60&fail_at=20")
+                .await
+                .unwrap();
+            handle.play().await.unwrap();
+            clock.advance(Duration::from_secs(25));
+            wait_for(&mut events, |e| matches!(e, PlayerEvent::Error(_))).await;
+            assert_eq!(
+                handle.get_position().await.unwrap(),
+                Some(Duration::from_secs(20))
+            );
+
+            handle.load_media("null://movie").await.unwrap();
+            handle.play().await.unwrap();
+            player.inject_error("Connection reset");
+            let event = wait_for(&mut events, |e| matches!(e, PlayerEvent::Error(_))).await;
+            assert_eq!(event, PlayerEvent::Error("Connection reset".to_string()));
+            assert_eq!(handle.get_state().await.unwrap(), PlayerState::Error);
+        })
+        .await;
+    }
+}
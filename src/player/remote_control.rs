@@ -0,0 +1,103 @@
+use once_cell::sync::Lazy;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+use super::PlayerState;
+use crate::models::MediaItemId;
+
+/// Where remotely requested items go relative to what is playing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Replace what is playing
+    Now,
+    /// Queue right after the current item
+    Next,
+    /// Queue at the end
+    Last,
+}
+
+/// What a Jellyfin or Plex app asked this player to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteCommand {
+    Play {
+        items: Vec<MediaItemId>,
+        start_index: usize,
+        start_position: Option<Duration>,
+        mode: PlayMode,
+    },
+    PlayPause,
+    Pause,
+    Resume,
+    Stop,
+    Seek(Duration),
+    /// Seconds forward, or backward when negative
+    SeekRelative(i64),
+    Next,
+    Previous,
+    /// 0.0 to 1.0
+    SetVolume(f64),
+    VolumeUp,
+    VolumeDown,
+    /// Toggled when no state is given
+    SetMuted(Option<bool>),
+    ToggleFullscreen,
+    ShowMessage(String),
+}
+
+/// What the player is doing, as reported to the apps controlling it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemotePlayback {
+    pub media_id: Option<MediaItemId>,
+    pub state: PlayerState,
+    pub position: Duration,
+    pub duration: Option<Duration>,
+    pub volume: f64,
+}
+
+impl Default for RemotePlayback {
+    fn default() -> Self {
+        Self {
+            media_id: None,
+            state: PlayerState::Stopped,
+            position: Duration::ZERO,
+            duration: None,
+            volume: 1.0,
+        }
+    }
+}
+
+static PLAYBACK: Lazy<watch::Sender<RemotePlayback>> =
+    Lazy::new(|| watch::channel(RemotePlayback::default()).0);
+
+/// Update what controlling apps see; unchanged state wakes nobody
+pub fn report_playback(playback: RemotePlayback) {
+    PLAYBACK.send_if_modified(|current| {
+        if *current == playback {
+            false
+        } else {
+            *current = playback;
+            true
+        }
+    });
+}
+
+pub fn watch_playback() -> watch::Receiver<RemotePlayback> {
+    PLAYBACK.subscribe()
+}
+
+/// Keeps a backend accepting remote commands; dropping it stops that
+#[derive(Debug)]
+pub struct RemoteControlHandle(JoinHandle<()>);
+
+impl RemoteControlHandle {
+    pub fn new(task: JoinHandle<()>) -> Self {
+        Self(task)
+    }
+}
+
+impl Drop for RemoteControlHandle {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
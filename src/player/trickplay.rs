@@ -0,0 +1,99 @@
+use anyhow::{Context, Result, anyhow};
+use gstreamer as gst;
+use gstreamer::prelude::*;
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::models::{Trickplay, TrickplayFrame};
+
+/// Width of generated preview thumbnails
+const THUMBNAIL_WIDTH: i32 = 320;
+/// Upper bound on frames so very long files don't take forever to index
+const MAX_FRAMES: u64 = 600;
+/// How long to wait for the pipeline to preroll after a seek
+const PREROLL_TIMEOUT_SECS: u64 = 10;
+
+/// Generate seek preview thumbnails for a local file by seeking a headless playbin
+/// through it and snapshotting frames as JPEG. This blocks, so run it off the main thread.
+pub fn generate_trickplay(uri: &str, interval: Duration) -> Result<Trickplay> {
+    info!("Generating preview thumbnails for {}", uri);
+
+    gst::init().context("Failed to initialize GStreamer")?;
+
+    let playbin = gst::ElementFactory::make("playbin")
+        .property("uri", uri)
+        .build()
+        .context("Failed to create playbin")?;
+    let video_sink = gst::ElementFactory::make("fakesink")
+        .build()
+        .context("Failed to create video fakesink")?;
+    let audio_sink = gst::ElementFactory::make("fakesink")
+        .build()
+        .context("Failed to create audio fakesink")?;
+    playbin.set_property("video-sink", &video_sink);
+    playbin.set_property("audio-sink", &audio_sink);
+
+    let result = grab_frames(&playbin, interval);
+    playbin.set_state(gst::State::Null).ok();
+    result
+}
+
+fn grab_frames(playbin: &gst::Element, interval: Duration) -> Result<Trickplay> {
+    let bus = playbin.bus().context("Playbin has no bus")?;
+
+    playbin
+        .set_state(gst::State::Paused)
+        .context("Failed to preroll thumbnail pipeline")?;
+    wait_for_preroll(&bus)?;
+
+    let duration = playbin
+        .query_duration::<gst::ClockTime>()
+        .context("Failed to query duration")?;
+    let duration = Duration::from_nanos(duration.nseconds());
+    let interval = interval.max(duration / MAX_FRAMES as u32);
+
+    let caps = gst::Caps::builder("image/jpeg")
+        .field("width", THUMBNAIL_WIDTH)
+        .field("pixel-aspect-ratio", gst::Fraction::new(1, 1))
+        .build();
+
+    let mut frames = Vec::new();
+    let mut timestamp = Duration::ZERO;
+    while timestamp < duration {
+        playbin.seek_simple(
+            gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT,
+            gst::ClockTime::from_nseconds(timestamp.as_nanos() as u64),
+        )?;
+        wait_for_preroll(&bus)?;
+
+        let sample = playbin.emit_by_name::<Option<gst::Sample>>("convert-sample", &[&caps]);
+        if let Some(buffer) = sample.as_ref().and_then(|s| s.buffer()) {
+            let map = buffer.map_readable()?;
+            frames.push(TrickplayFrame {
+                timestamp,
+                image: map.as_slice().to_vec(),
+            });
+        }
+
+        timestamp += interval;
+    }
+
+    debug!("Generated {} preview thumbnails", frames.len());
+    Ok(Trickplay { frames })
+}
+
+fn wait_for_preroll(bus: &gst::Bus) -> Result<()> {
+    use gst::MessageView;
+
+    let message = bus
+        .timed_pop_filtered(
+            gst::ClockTime::from_seconds(PREROLL_TIMEOUT_SECS),
+            &[gst::MessageType::AsyncDone, gst::MessageType::Error],
+        )
+        .ok_or_else(|| anyhow!("Timed out waiting for thumbnail pipeline"))?;
+
+    match message.view() {
+        MessageView::Error(err) => Err(anyhow!("Thumbnail pipeline error: {}", err.error())),
+        _ => Ok(()),
+    }
+}
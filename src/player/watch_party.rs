@@ -0,0 +1,655 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::OwnedReadHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, info, warn};
+
+/// Bumped whenever messages change in a way older versions can't read
+const PROTOCOL_VERSION: u32 = 2;
+/// Port tried first so firewall rules can be written for it; any free port is used otherwise
+pub const DEFAULT_PORT: u16 = 47630;
+/// How long joining waits for the host to say what is playing
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+/// Identifies messages sent by the host itself when relaying
+const HOST_ID: u64 = 0;
+/// Longest line read from a connection; real messages are a few hundred bytes
+const MAX_MESSAGE_LEN: usize = 16 * 1024;
+/// How long the host keeps a connection that greeted with the wrong code, to slow
+/// down guessing
+const REJECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Drift beyond which a participant seeks instead of catching up gradually
+const SEEK_THRESHOLD: f64 = 3.0;
+/// Drift at which catching up starts, and the one at which it stops again
+const NUDGE_START: f64 = 0.25;
+const NUDGE_STOP: f64 = 0.05;
+/// How much faster or slower than normal a participant plays while catching up
+const NUDGE_AMOUNT: f64 = 0.05;
+
+/// One line of the watch party protocol, sent as JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PartyMessage {
+    /// First thing a participant sends after connecting, with the secret part of the
+    /// join code; the host drops connections that don't know it
+    Hello {
+        version: u32,
+        name: String,
+        secret: u32,
+    },
+    Load {
+        media_id: String,
+    },
+    Play {
+        position_ms: u64,
+    },
+    Pause {
+        position_ms: u64,
+    },
+    Seek {
+        position_ms: u64,
+    },
+    /// Sent by the host every second, for late joiners and drift correction
+    State {
+        media_id: String,
+        position_ms: u64,
+        playing: bool,
+    },
+}
+
+impl PartyMessage {
+    fn encode(&self) -> Result<String> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        Ok(line)
+    }
+
+    fn decode(line: &str) -> Result<Self> {
+        serde_json::from_str(line).with_context(|| format!("Invalid watch party message: {}", line))
+    }
+}
+
+/// What happens in the party, as the player page sees it
+#[derive(Debug, Clone, PartialEq)]
+pub enum PartyEvent {
+    /// Playback change made by another participant
+    Message(PartyMessage),
+    ParticipantJoined(String),
+    ParticipantLeft(String),
+    /// The connection to the host was lost or the host ended the party
+    Ended(String),
+}
+
+/// How a participant should move to the host's position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DriftCorrection {
+    /// Play at this multiple of the chosen speed, 1.0 once in sync
+    Rate(f64),
+    Seek,
+}
+
+/// Correction for being `drift` seconds behind the host, negative when ahead.
+/// `gradual` allows catching up by speed, which doesn't work while paused or after
+/// an explicit seek. `nudging` tells whether the last correction was a rate change, so
+/// catching up continues until well in sync instead of flapping around the threshold.
+pub fn drift_correction(drift: f64, gradual: bool, nudging: bool) -> DriftCorrection {
+    let distance = drift.abs();
+    if distance > SEEK_THRESHOLD || (!gradual && distance > NUDGE_START) {
+        DriftCorrection::Seek
+    } else if gradual && (distance > NUDGE_START || (nudging && distance > NUDGE_STOP)) {
+        DriftCorrection::Rate(1.0 + NUDGE_AMOUNT * drift.signum())
+    } else {
+        DriftCorrection::Rate(1.0)
+    }
+}
+
+/// Code others join with: the host's IPv4 address, port and the party's secret as
+/// twenty hex digits, e.g. `C0A8-0105-BA0E-1234-ABCD` for 192.168.1.5:47630. The
+/// address is the one on the local network, so parties only reach machines on it.
+pub fn join_code(address: SocketAddrV4, secret: u32) -> String {
+    let [a, b, c, d] = address.ip().octets();
+    let [e, f] = address.port().to_be_bytes();
+    let [g, h, i, j] = secret.to_be_bytes();
+    format!(
+        "{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}-{:02X}{:02X}",
+        a, b, c, d, e, f, g, h, i, j
+    )
+}
+
+/// Address to connect to and secret to greet with for a join code;
+/// `host:port/SECRET` is accepted as well, with the secret in hex
+pub fn parse_join_code(code: &str) -> Result<(String, u32)> {
+    let code = code.trim();
+    let invalid = || anyhow::anyhow!("“{}” is not a watch party code", code);
+    if let Some((address, secret)) = code.split_once('/') {
+        let secret = u32::from_str_radix(secret, 16).map_err(|_| invalid())?;
+        return Ok((address.to_string(), secret));
+    }
+
+    let digits: String = code.chars().filter(|c| !matches!(c, '-' | ' ')).collect();
+    if digits.len() != 20 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let mut bytes = [0u8; 10];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16)?;
+    }
+    let ip = Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]);
+    let port = u16::from_be_bytes([bytes[4], bytes[5]]);
+    let secret = u32::from_be_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]);
+    Ok((SocketAddrV4::new(ip, port).to_string(), secret))
+}
+
+/// Address other machines on the network reach this one at; no packets are sent
+fn local_address() -> Ipv4Addr {
+    std::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .and_then(|socket| {
+            socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9))?;
+            socket.local_addr()
+        })
+        .ok()
+        .and_then(|address| match address {
+            SocketAddr::V4(address) if !address.ip().is_unspecified() => Some(*address.ip()),
+            _ => None,
+        })
+        .unwrap_or(Ipv4Addr::LOCALHOST)
+}
+
+/// A watch party this instance hosts or takes part in; leaving it is dropping it
+pub struct WatchParty {
+    outgoing: mpsc::UnboundedSender<PartyMessage>,
+    /// Join code while hosting
+    code: Option<String>,
+    port: u16,
+    task: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for WatchParty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchParty")
+            .field("code", &self.code)
+            .field("port", &self.port)
+            .finish()
+    }
+}
+
+impl Drop for WatchParty {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl WatchParty {
+    /// Start accepting participants on all interfaces, from those who know the join code
+    pub async fn host() -> Result<(Self, mpsc::UnboundedReceiver<PartyEvent>)> {
+        let listener = match TcpListener::bind((Ipv4Addr::UNSPECIFIED, DEFAULT_PORT)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                debug!("Port {} unavailable ({}), using any", DEFAULT_PORT, e);
+                TcpListener::bind((Ipv4Addr::UNSPECIFIED, 0))
+                    .await
+                    .context("Failed to open the watch party port")?
+            }
+        };
+        let port = listener.local_addr()?.port();
+        let [a, b, c, d, ..] = *uuid::Uuid::new_v4().as_bytes();
+        let secret = u32::from_be_bytes([a, b, c, d]);
+        let code = join_code(SocketAddrV4::new(local_address(), port), secret);
+        info!("Hosting watch party on port {}", port);
+
+        let (outgoing, outgoing_receiver) = mpsc::unbounded_channel();
+        let (events, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_host(listener, secret, outgoing_receiver, events));
+        Ok((
+            Self {
+                outgoing,
+                code: Some(code),
+                port,
+                task,
+            },
+            receiver,
+        ))
+    }
+
+    /// Join the party behind `code`, returning what the host is playing as well
+    pub async fn join(
+        code: &str,
+        name: &str,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<PartyEvent>, String)> {
+        let (address, secret) = parse_join_code(code)?;
+        let stream = tokio::time::timeout(JOIN_TIMEOUT, TcpStream::connect(&address))
+            .await
+            .map_err(|_| anyhow::anyhow!("The watch party host did not answer"))?
+            .with_context(|| format!("Failed to connect to the watch party at {}", address))?;
+        let port = stream.peer_addr()?.port();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = MessageReader::new(reader);
+
+        let hello = PartyMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: name.to_string(),
+            secret,
+        };
+        writer.write_all(hello.encode()?.as_bytes()).await?;
+
+        // The host answers with its current state, which says what to play
+        let media_id = tokio::time::timeout(JOIN_TIMEOUT, async {
+            loop {
+                match reader.next().await? {
+                    Some(PartyMessage::State { media_id, .. }) => return Ok(media_id),
+                    Some(_) => continue,
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "The host closed the connection; check the code, or the host may run another version of Reel"
+                        ));
+                    }
+                }
+            }
+        })
+        .await
+        .map_err(|_| anyhow::anyhow!("The watch party host did not say what is playing"))??;
+        info!("Joined watch party at {} playing {}", address, media_id);
+
+        let (outgoing, outgoing_receiver) = mpsc::unbounded_channel();
+        let (events, receiver) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_guest(reader, writer, outgoing_receiver, events));
+        Ok((
+            Self {
+                outgoing,
+                code: None,
+                port,
+                task,
+            },
+            receiver,
+            media_id,
+        ))
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.code.is_some()
+    }
+
+    pub fn code(&self) -> Option<&str> {
+        self.code.as_deref()
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Share a playback change with everyone else in the party
+    pub fn send(&self, message: PartyMessage) {
+        if self.outgoing.send(message).is_err() {
+            debug!("Watch party connection already closed");
+        }
+    }
+
+    /// For sending from async code; messages sent after the party ends are dropped
+    pub fn outgoing(&self) -> mpsc::UnboundedSender<PartyMessage> {
+        self.outgoing.clone()
+    }
+}
+
+/// Reads one message per line, refusing lines longer than `MAX_MESSAGE_LEN`. A partly
+/// read line is kept between calls, so reading can be cancelled by `select!`.
+struct MessageReader {
+    reader: BufReader<OwnedReadHalf>,
+    line: String,
+}
+
+impl MessageReader {
+    fn new(reader: OwnedReadHalf) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            line: String::new(),
+        }
+    }
+
+    /// The next message, or `None` once the other side closed the connection
+    async fn next(&mut self) -> Result<Option<PartyMessage>> {
+        loop {
+            let remaining = MAX_MESSAGE_LEN.saturating_sub(self.line.len());
+            let read = (&mut self.reader)
+                .take(remaining as u64)
+                .read_line(&mut self.line)
+                .await?;
+            if self.line.ends_with('\n') {
+                let line = std::mem::take(&mut self.line);
+                return PartyMessage::decode(line.trim_end()).map(Some);
+            }
+            if read == 0 {
+                return if remaining == 0 {
+                    Err(anyhow::anyhow!(
+                        "Watch party message longer than {} bytes",
+                        MAX_MESSAGE_LEN
+                    ))
+                } else {
+                    Ok(None)
+                };
+            }
+        }
+    }
+}
+
+/// What connections report back to the host
+enum GuestUpdate {
+    Joined(String),
+    Left(String),
+    Message(PartyMessage),
+}
+
+async fn run_host(
+    listener: TcpListener,
+    secret: u32,
+    mut outgoing: mpsc::UnboundedReceiver<PartyMessage>,
+    events: mpsc::UnboundedSender<PartyEvent>,
+) {
+    // Messages go out to every participant except the one they came from
+    let (relay, _) = broadcast::channel::<(u64, Arc<str>)>(64);
+    let (updates_sender, mut updates) = mpsc::unbounded_channel();
+    // Dropped, and with it every connection, when the party ends
+    let mut connections = JoinSet::new();
+    // Handed to participants as they join
+    let (latest_state, _) = watch::channel::<Option<Arc<str>>>(None);
+    let mut next_id = HOST_ID + 1;
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, address)) => {
+                    debug!("Watch party connection from {}", address);
+                    let id = next_id;
+                    next_id += 1;
+                    let relay = relay.subscribe();
+                    let updates = updates_sender.clone();
+                    let latest_state = latest_state.subscribe();
+                    connections.spawn(async move {
+                        if let Err(e) =
+                            serve_guest(id, stream, secret, relay, &updates, latest_state).await
+                        {
+                            debug!("Watch party connection from {} closed: {}", address, e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept watch party connection: {}", e),
+            },
+            message = outgoing.recv() => {
+                let Some(message) = message else { break };
+                match message.encode() {
+                    Ok(line) => {
+                        let line: Arc<str> = line.into();
+                        if matches!(message, PartyMessage::State { .. }) {
+                            latest_state.send_replace(Some(line.clone()));
+                        }
+                        let _ = relay.send((HOST_ID, line));
+                    }
+                    Err(e) => warn!("Failed to encode watch party message: {}", e),
+                }
+            }
+            Some((id, update)) = updates.recv() => {
+                let event = match update {
+                    GuestUpdate::Joined(name) => PartyEvent::ParticipantJoined(name),
+                    GuestUpdate::Left(name) => PartyEvent::ParticipantLeft(name),
+                    GuestUpdate::Message(message) => {
+                        if let Ok(line) = message.encode() {
+                            let _ = relay.send((id, line.into()));
+                        }
+                        PartyEvent::Message(message)
+                    }
+                };
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+            Some(_) = connections.join_next() => {}
+        }
+    }
+    debug!("Watch party ended");
+}
+
+async fn serve_guest(
+    id: u64,
+    stream: TcpStream,
+    secret: u32,
+    mut relay: broadcast::Receiver<(u64, Arc<str>)>,
+    updates: &mpsc::UnboundedSender<(u64, GuestUpdate)>,
+    latest_state: watch::Receiver<Option<Arc<str>>>,
+) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = MessageReader::new(reader);
+
+    let hello = tokio::time::timeout(JOIN_TIMEOUT, reader.next())
+        .await
+        .map_err(|_| anyhow::anyhow!("no greeting in time"))??;
+    let name = match hello {
+        Some(PartyMessage::Hello { version, .. }) if version != PROTOCOL_VERSION => {
+            return Err(anyhow::anyhow!("unsupported protocol version {}", version));
+        }
+        Some(PartyMessage::Hello {
+            name,
+            secret: greeted,
+            ..
+        }) => {
+            if greeted != secret {
+                tokio::time::sleep(REJECT_DELAY).await;
+                return Err(anyhow::anyhow!("wrong join code"));
+            }
+            name
+        }
+        _ => return Err(anyhow::anyhow!("no greeting")),
+    };
+    let _ = updates.send((id, GuestUpdate::Joined(name.clone())));
+
+    let state = latest_state.borrow().clone();
+    if let Some(state) = state {
+        writer.write_all(state.as_bytes()).await?;
+    }
+
+    let result = async {
+        loop {
+            tokio::select! {
+                message = reader.next() => match message? {
+                    // Only the host speaks for the party as a whole
+                    Some(PartyMessage::Hello { .. } | PartyMessage::State { .. }) => {}
+                    Some(message) => {
+                        let _ = updates.send((id, GuestUpdate::Message(message)));
+                    }
+                    None => return Ok(()),
+                },
+                relayed = relay.recv() => match relayed {
+                    Ok((origin, line)) if origin != id => writer.write_all(line.as_bytes()).await?,
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        debug!("Participant {} missed {} watch party messages", id, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                },
+            }
+        }
+    }
+    .await;
+
+    let _ = updates.send((id, GuestUpdate::Left(name)));
+    result
+}
+
+async fn run_guest(
+    mut reader: MessageReader,
+    mut writer: tokio::net::tcp::OwnedWriteHalf,
+    mut outgoing: mpsc::UnboundedReceiver<PartyMessage>,
+    events: mpsc::UnboundedSender<PartyEvent>,
+) {
+    let reason = loop {
+        tokio::select! {
+            message = reader.next() => match message {
+                Ok(Some(message)) => {
+                    if events.send(PartyEvent::Message(message)).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break "The host ended the watch party".to_string(),
+                Err(e) => {
+                    warn!("Lost the watch party connection: {}", e);
+                    break "Lost the connection to the watch party".to_string();
+                }
+            },
+            message = outgoing.recv() => {
+                let Some(message) = message else { return };
+                let written = match message.encode() {
+                    Ok(line) => writer.write_all(line.as_bytes()).await,
+                    Err(e) => {
+                        warn!("Failed to encode watch party message: {}", e);
+                        continue;
+                    }
+                };
+                if let Err(e) = written {
+                    warn!("Lost the watch party connection: {}", e);
+                    break "Lost the connection to the watch party".to_string();
+                }
+            }
+        }
+    };
+    let _ = events.send(PartyEvent::Ended(reason));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Where a guest on this machine reaches `host`, with its secret
+    fn loopback_code(host: &WatchParty) -> String {
+        let (_, secret) = parse_join_code(host.code().unwrap()).unwrap();
+        format!("127.0.0.1:{}/{:X}", host.port(), secret)
+    }
+
+    #[test]
+    fn test_join_code_round_trip() {
+        let address = SocketAddrV4::new(Ipv4Addr::new(192, 168, 1, 5), 47630);
+        let code = join_code(address, 0x1234ABCD);
+        assert_eq!(code, "C0A8-0105-BA0E-1234-ABCD");
+        assert_eq!(
+            parse_join_code(&code).unwrap(),
+            ("192.168.1.5:47630".to_string(), 0x1234ABCD)
+        );
+        assert_eq!(
+            parse_join_code(" c0a80105ba0e1234abcd ").unwrap(),
+            ("192.168.1.5:47630".to_string(), 0x1234ABCD)
+        );
+        assert_eq!(
+            parse_join_code("localhost:4000/ff").unwrap(),
+            ("localhost:4000".to_string(), 0xFF)
+        );
+        assert!(parse_join_code("C0A8-0105-BA0E").is_err());
+        assert!(parse_join_code("localhost:4000").is_err());
+        assert!(parse_join_code("localhost:4000/xyz").is_err());
+    }
+
+    #[test]
+    fn test_drift_correction() {
+        assert_eq!(
+            drift_correction(0.1, true, false),
+            DriftCorrection::Rate(1.0)
+        );
+        assert_eq!(
+            drift_correction(0.5, true, false),
+            DriftCorrection::Rate(1.05)
+        );
+        assert_eq!(
+            drift_correction(-0.5, true, false),
+            DriftCorrection::Rate(0.95)
+        );
+        // Keeps catching up until well in sync
+        assert_eq!(
+            drift_correction(0.1, true, true),
+            DriftCorrection::Rate(1.05)
+        );
+        assert_eq!(
+            drift_correction(0.01, true, true),
+            DriftCorrection::Rate(1.0)
+        );
+        assert_eq!(drift_correction(5.0, true, false), DriftCorrection::Seek);
+        assert_eq!(drift_correction(0.5, false, false), DriftCorrection::Seek);
+    }
+
+    #[tokio::test]
+    async fn test_party_on_localhost() {
+        let (host, mut host_events) = WatchParty::host().await.unwrap();
+        host.send(PartyMessage::State {
+            media_id: "movie-1".to_string(),
+            position_ms: 1000,
+            playing: true,
+        });
+
+        let (guest, mut guest_events, media_id) = WatchParty::join(&loopback_code(&host), "Guest")
+            .await
+            .unwrap();
+        assert_eq!(media_id, "movie-1");
+        assert_eq!(
+            host_events.recv().await,
+            Some(PartyEvent::ParticipantJoined("Guest".to_string()))
+        );
+
+        guest.send(PartyMessage::Pause { position_ms: 2000 });
+        assert_eq!(
+            host_events.recv().await,
+            Some(PartyEvent::Message(PartyMessage::Pause {
+                position_ms: 2000
+            }))
+        );
+
+        host.send(PartyMessage::Seek { position_ms: 3000 });
+        assert_eq!(
+            guest_events.recv().await,
+            Some(PartyEvent::Message(PartyMessage::Seek {
+                position_ms: 3000
+            }))
+        );
+
+        drop(host);
+        assert!(matches!(
+            guest_events.recv().await,
+            Some(PartyEvent::Ended(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_secret_is_turned_away() {
+        let (host, mut host_events) = WatchParty::host().await.unwrap();
+        host.send(PartyMessage::State {
+            media_id: "movie-1".to_string(),
+            position_ms: 0,
+            playing: true,
+        });
+
+        let (_, secret) = parse_join_code(host.code().unwrap()).unwrap();
+        let code = format!("127.0.0.1:{}/{:X}", host.port(), secret.wrapping_add(1));
+        assert!(WatchParty::join(&code, "Stranger").await.is_err());
+        assert!(host_events.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_overlong_message_closes_the_connection() {
+        let (host, mut host_events) = WatchParty::host().await.unwrap();
+        let mut stream = TcpStream::connect(("127.0.0.1", host.port()))
+            .await
+            .unwrap();
+        stream
+            .write_all(&vec![b'x'; MAX_MESSAGE_LEN + 1])
+            .await
+            .unwrap();
+
+        // The host hangs up instead of buffering the line
+        let mut buffer = [0u8; 16];
+        let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer))
+            .await
+            .expect("the host kept the connection open");
+        assert!(matches!(read, Ok(0) | Err(_)));
+        assert!(host_events.try_recv().is_err());
+    }
+}
@@ -3,10 +3,14 @@ use async_trait::async_trait;
 
 use crate::db::connection::DatabaseConnection;
 use crate::models::{
-    Episode, Library, LibraryId, MediaItem, MediaItemId, MediaType, ShowId, SourceId, StreamInfo,
+    Chapter, ChapterMarker, ChapterType, Episode, Library, LibraryId, MarkerSkipMode, MediaItem,
+    MediaItemId, MediaType, PlayQueue, ShowId, SourceId, StreamInfo, TranscodeStatus, Trickplay,
+    VideoAdjustments,
 };
+use crate::player::DeviceCapabilities;
 use crate::services::commands::Command;
 use crate::services::core::media::MediaService;
+use std::collections::HashMap;
 
 /// Get all libraries
 pub struct GetLibrariesCommand {
@@ -161,6 +165,169 @@ impl Command<Option<(i64, i64)>> for GetPlaybackProgressCommand {
     }
 }
 
+/// Get the saved subtitle and audio delays for a media item, in milliseconds
+pub struct GetSyncOffsetsCommand {
+    pub db: DatabaseConnection,
+    pub media_id: MediaItemId,
+}
+
+#[async_trait]
+impl Command<(i64, i64)> for GetSyncOffsetsCommand {
+    async fn execute(&self) -> Result<(i64, i64)> {
+        use crate::services::core::playback::PlaybackService;
+
+        PlaybackService::get_sync_offsets(&self.db, &self.media_id).await
+    }
+}
+
+/// Save the subtitle and audio delays for a media item
+pub struct SaveSyncOffsetsCommand {
+    pub db: DatabaseConnection,
+    pub media_id: MediaItemId,
+    pub subtitle_delay_ms: i64,
+    pub audio_delay_ms: i64,
+}
+
+#[async_trait]
+impl Command<()> for SaveSyncOffsetsCommand {
+    async fn execute(&self) -> Result<()> {
+        use crate::services::core::playback::PlaybackService;
+
+        PlaybackService::save_sync_offsets(
+            &self.db,
+            &self.media_id,
+            self.subtitle_delay_ms,
+            self.audio_delay_ms,
+        )
+        .await
+    }
+}
+
+/// Get the picture settings saved for a media item
+pub struct GetVideoAdjustmentsCommand {
+    pub db: DatabaseConnection,
+    pub media_id: MediaItemId,
+}
+
+#[async_trait]
+impl Command<VideoAdjustments> for GetVideoAdjustmentsCommand {
+    async fn execute(&self) -> Result<VideoAdjustments> {
+        use crate::services::core::playback::PlaybackService;
+
+        PlaybackService::get_video_adjustments(&self.db, &self.media_id).await
+    }
+}
+
+/// Save the picture settings for a media item
+pub struct SaveVideoAdjustmentsCommand {
+    pub db: DatabaseConnection,
+    pub media_id: MediaItemId,
+    pub adjustments: VideoAdjustments,
+}
+
+#[async_trait]
+impl Command<()> for SaveVideoAdjustmentsCommand {
+    async fn execute(&self) -> Result<()> {
+        use crate::services::core::playback::PlaybackService;
+
+        PlaybackService::save_video_adjustments(&self.db, &self.media_id, &self.adjustments).await
+    }
+}
+
+/// Get the marker skip modes remembered for a show
+pub struct GetShowSkipModesCommand {
+    pub db: DatabaseConnection,
+    pub show_id: ShowId,
+}
+
+#[async_trait]
+impl Command<HashMap<ChapterType, MarkerSkipMode>> for GetShowSkipModesCommand {
+    async fn execute(&self) -> Result<HashMap<ChapterType, MarkerSkipMode>> {
+        use crate::services::core::playback::PlaybackService;
+
+        PlaybackService::get_show_skip_modes(&self.db, &self.show_id).await
+    }
+}
+
+/// Remember how a marker type should be skipped for a show
+pub struct SaveShowSkipModeCommand {
+    pub db: DatabaseConnection,
+    pub show_id: ShowId,
+    pub marker_type: ChapterType,
+    pub mode: MarkerSkipMode,
+}
+
+#[async_trait]
+impl Command<()> for SaveShowSkipModeCommand {
+    async fn execute(&self) -> Result<()> {
+        use crate::services::core::playback::PlaybackService;
+
+        PlaybackService::save_show_skip_mode(&self.db, &self.show_id, self.marker_type, self.mode)
+            .await
+    }
+}
+
+/// Load the saved play queue
+pub struct GetPlayQueueCommand {
+    pub db: DatabaseConnection,
+}
+
+#[async_trait]
+impl Command<PlayQueue> for GetPlayQueueCommand {
+    async fn execute(&self) -> Result<PlayQueue> {
+        use crate::services::core::PlaylistService;
+
+        PlaylistService::load_queue(&self.db).await
+    }
+}
+
+/// Save the play queue after it changed
+pub struct SavePlayQueueCommand {
+    pub db: DatabaseConnection,
+    pub queue: PlayQueue,
+}
+
+#[async_trait]
+impl Command<()> for SavePlayQueueCommand {
+    async fn execute(&self) -> Result<()> {
+        use crate::services::core::PlaylistService;
+
+        PlaylistService::save_queue(&self.db, &self.queue).await
+    }
+}
+
+/// Add a media item to the saved play queue
+pub struct QueueMediaCommand {
+    pub db: DatabaseConnection,
+    pub media_id: MediaItemId,
+    /// Insert after the current item instead of at the end
+    pub play_next: bool,
+}
+
+#[async_trait]
+impl Command<PlayQueue> for QueueMediaCommand {
+    async fn execute(&self) -> Result<PlayQueue> {
+        use crate::services::core::PlaylistService;
+
+        PlaylistService::queue_media(&self.db, &self.media_id, self.play_next).await
+    }
+}
+
+/// Build a music queue from an album or track, along with the album cover
+pub struct BuildMusicQueueCommand {
+    pub db: DatabaseConnection,
+    pub media_id: MediaItemId,
+}
+
+#[async_trait]
+impl Command<(PlayQueue, Option<String>)> for BuildMusicQueueCommand {
+    async fn execute(&self) -> Result<(PlayQueue, Option<String>)> {
+        use crate::services::core::PlaylistService;
+
+        PlaylistService::build_music_queue(&self.db, &self.media_id).await
+    }
+}
+
 /// Update playback progress
 pub struct UpdatePlaybackProgressCommand {
     pub db: DatabaseConnection,
@@ -184,6 +351,34 @@ impl Command<()> for UpdatePlaybackProgressCommand {
     }
 }
 
+/// Tell the item's server about playback happening somewhere else, like on a cast receiver
+pub struct ReportPlaybackProgressCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+    pub position: std::time::Duration,
+    pub duration: std::time::Duration,
+}
+
+#[async_trait]
+impl Command<()> for ReportPlaybackProgressCommand {
+    async fn execute(&self) -> Result<()> {
+        use crate::db::repository::{MediaRepositoryImpl, Repository};
+
+        let media_item = MediaRepositoryImpl::new(self.db.clone())
+            .find_by_id(self.media_item_id.as_str())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Media item not found"))?;
+        crate::services::core::BackendService::update_playback_progress(
+            &self.db,
+            &media_item.source_id,
+            &self.media_item_id,
+            self.position,
+            self.duration,
+        )
+        .await
+    }
+}
+
 /// Save a library
 pub struct SaveLibraryCommand {
     pub db: DatabaseConnection,
@@ -286,3 +481,159 @@ impl Command<StreamInfo> for GetStreamUrlCommand {
         crate::services::core::BackendService::get_stream_url(&self.db, &self.media_item_id).await
     }
 }
+
+/// Get a stream of a media item for a cast receiver, in a transcode session separate
+/// from the local player's. A `max_bitrate` other than 0 caps it at a picked quality.
+pub struct GetCastStreamCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+    pub max_bitrate: u64,
+}
+
+#[async_trait]
+impl Command<StreamInfo> for GetCastStreamCommand {
+    async fn execute(&self) -> Result<StreamInfo> {
+        let capabilities = DeviceCapabilities::cast_receiver().with_max_bitrate(self.max_bitrate);
+        crate::services::core::BackendService::get_stream_url_for(
+            &self.db,
+            &self.media_item_id,
+            &capabilities,
+        )
+        .await
+    }
+}
+
+/// Get a fresh stream URL for a media item after its stream dropped, going
+/// through whichever of its server's connections works now
+pub struct ReconnectStreamCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+}
+
+#[async_trait]
+impl Command<StreamInfo> for ReconnectStreamCommand {
+    async fn execute(&self) -> Result<StreamInfo> {
+        use crate::db::repository::{MediaRepositoryImpl, Repository};
+        use crate::services::core::ConnectionService;
+
+        let media_item = MediaRepositoryImpl::new(self.db.clone())
+            .find_by_id(self.media_item_id.as_str())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Media item not found"))?;
+        let source_id = SourceId::new(media_item.source_id);
+
+        // The cached connection is the one that just dropped, so test them all again
+        ConnectionService::cache().remove(&source_id).await;
+        if ConnectionService::select_best_connection(&self.db, &source_id)
+            .await?
+            .is_none()
+        {
+            anyhow::bail!("No connection to the server is available");
+        }
+
+        crate::services::core::BackendService::get_stream_url(&self.db, &self.media_item_id).await
+    }
+}
+
+/// Stop the server-side transcode session backing a stream
+pub struct StopTranscodeSessionCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+    pub session_id: String,
+}
+
+#[async_trait]
+impl Command<()> for StopTranscodeSessionCommand {
+    async fn execute(&self) -> Result<()> {
+        crate::services::core::BackendService::stop_transcode_session(
+            &self.db,
+            &self.media_item_id,
+            &self.session_id,
+        )
+        .await
+    }
+}
+
+/// Keep the server-side transcode session backing a stream alive
+pub struct PingTranscodeSessionCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+    pub session_id: String,
+}
+
+#[async_trait]
+impl Command<()> for PingTranscodeSessionCommand {
+    async fn execute(&self) -> Result<()> {
+        crate::services::core::BackendService::ping_transcode_session(
+            &self.db,
+            &self.media_item_id,
+            &self.session_id,
+        )
+        .await
+    }
+}
+
+/// Get seek-bar preview thumbnails for a media item
+pub struct GetTrickplayCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+    pub stream_url: String,
+}
+
+#[async_trait]
+impl Command<Option<Trickplay>> for GetTrickplayCommand {
+    async fn execute(&self) -> Result<Option<Trickplay>> {
+        crate::services::core::TrickplayService::get_trickplay(
+            &self.db,
+            &self.media_item_id,
+            &self.stream_url,
+        )
+        .await
+    }
+}
+
+/// Get what the server is doing in the transcode session behind a stream
+pub struct GetTranscodeStatusCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+    pub session_id: String,
+}
+
+#[async_trait]
+impl Command<Option<TranscodeStatus>> for GetTranscodeStatusCommand {
+    async fn execute(&self) -> Result<Option<TranscodeStatus>> {
+        crate::services::core::BackendService::get_transcode_status(
+            &self.db,
+            &self.media_item_id,
+            &self.session_id,
+        )
+        .await
+    }
+}
+
+/// Get the intro, credits, recap and preview markers for a media item
+pub struct GetMediaMarkersCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+}
+
+#[async_trait]
+impl Command<Vec<ChapterMarker>> for GetMediaMarkersCommand {
+    async fn execute(&self) -> Result<Vec<ChapterMarker>> {
+        crate::services::core::BackendService::get_media_markers(&self.db, &self.media_item_id)
+            .await
+    }
+}
+
+/// Get the server's chapters for a media item
+pub struct GetChaptersCommand {
+    pub db: DatabaseConnection,
+    pub media_item_id: MediaItemId,
+}
+
+#[async_trait]
+impl Command<Vec<Chapter>> for GetChaptersCommand {
+    async fn execute(&self) -> Result<Vec<Chapter>> {
+        crate::services::core::BackendService::get_chapters(&self.db, &self.media_item_id).await
+    }
+}
@@ -6,9 +6,11 @@ use crate::db::repository::{
     source_repository::{SourceRepository, SourceRepositoryImpl},
 };
 use crate::models::{
-    AuthProvider, ConnectionInfo, Credentials, Episode, HomeSection, MediaItem, MediaItemId, Movie,
-    Show, Source, SourceId, SourceType, StreamInfo,
+    AuthProvider, Chapter, ChapterMarker, ConnectionInfo, Credentials, Episode, HomeSection,
+    MediaItem, MediaItemId, Movie, Show, Source, SourceId, SourceType, StreamInfo, TranscodeStatus,
+    Trickplay,
 };
+use crate::player::DeviceCapabilities;
 use crate::services::core::auth::AuthService;
 use anyhow::{Context, Result};
 use sea_orm::{ActiveModelTrait, Set};
@@ -25,6 +27,84 @@ impl BackendService {
         db: &DatabaseConnection,
         media_item_id: &MediaItemId,
     ) -> Result<StreamInfo> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.get_stream_url(media_item_id).await
+    }
+
+    /// Get a stream of a media item negotiated for another player's `capabilities`
+    pub async fn get_stream_url_for(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+        capabilities: &DeviceCapabilities,
+    ) -> Result<StreamInfo> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend
+            .get_stream_url_for(media_item_id, capabilities)
+            .await
+    }
+
+    /// Stop the server-side transcode session for a media item's stream
+    pub async fn stop_transcode_session(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+        session_id: &str,
+    ) -> Result<()> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.stop_transcode_session(session_id).await
+    }
+
+    /// Keep the server-side transcode session for a media item's stream alive
+    pub async fn ping_transcode_session(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+        session_id: &str,
+    ) -> Result<()> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.ping_transcode_session(session_id).await
+    }
+
+    /// What the server is doing in the transcode session behind a media item's stream
+    pub async fn get_transcode_status(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+        session_id: &str,
+    ) -> Result<Option<TranscodeStatus>> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.get_transcode_status(session_id).await
+    }
+
+    /// Fetch server-generated seek preview thumbnails for a media item
+    pub async fn get_trickplay(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+    ) -> Result<Option<Trickplay>> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.get_trickplay(media_item_id).await
+    }
+
+    /// Fetch the skippable segment markers for a media item
+    pub async fn get_media_markers(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+    ) -> Result<Vec<ChapterMarker>> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.fetch_media_markers(media_item_id).await
+    }
+
+    /// Get server-side chapters (with thumbnails when available) for a media item
+    pub async fn get_chapters(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+    ) -> Result<Vec<Chapter>> {
+        let backend = Self::create_backend_for_media_item(db, media_item_id).await?;
+        backend.fetch_chapters(media_item_id).await
+    }
+
+    /// Create a backend instance for the source a media item belongs to
+    async fn create_backend_for_media_item(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+    ) -> Result<Box<dyn MediaBackend>> {
         // Load media item to find its source
         let media_repo = MediaRepositoryImpl::new(db.clone());
         let media_item = media_repo
@@ -39,9 +119,7 @@ impl BackendService {
             .await?
             .ok_or_else(|| anyhow::anyhow!("Source not found"))?;
 
-        // Create backend and get stream URL
-        let backend = Self::create_backend_for_source(db, &source_entity).await?;
-        backend.get_stream_url(media_item_id).await
+        Self::create_backend_for_source(db, &source_entity).await
     }
 
     /// Create a backend instance for a source - stateless factory
@@ -226,6 +304,55 @@ impl BackendService {
         }
     }
 
+    /// Let the apps of every configured server control playback here. Plex apps
+    /// name the server with each command, so one Plex session serves all Plex sources.
+    pub async fn start_remote_control(
+        db: &DatabaseConnection,
+        commands: tokio::sync::mpsc::UnboundedSender<crate::player::RemoteCommand>,
+    ) -> Result<Vec<crate::player::RemoteControlHandle>> {
+        let source_repo = SourceRepositoryImpl::new(db.clone());
+        let sources = source_repo.find_all().await?;
+        let configured: Vec<Source> = sources.iter().map(Self::entity_to_source).collect();
+
+        let mut handles = Vec::new();
+        let mut plex_started = false;
+        for source_entity in sources.iter() {
+            let is_plex = matches!(source_entity.source_type.as_str(), "plex" | "PlexServer");
+            if is_plex && plex_started {
+                continue;
+            }
+
+            let backend = match Self::create_backend_for_source(db, source_entity).await {
+                Ok(backend) => backend,
+                Err(e) => {
+                    tracing::warn!(
+                        "Remote control unavailable for source {}: {}",
+                        source_entity.id,
+                        e
+                    );
+                    continue;
+                }
+            };
+            match backend
+                .start_remote_control(commands.clone(), &configured)
+                .await
+            {
+                Ok(Some(handle)) => {
+                    plex_started |= is_plex;
+                    handles.push(handle);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!(
+                    "Failed to start remote control for source {}: {}",
+                    source_entity.id,
+                    e
+                ),
+            }
+        }
+
+        Ok(handles)
+    }
+
     /// Update playback progress on the backend server
     pub async fn update_playback_progress(
         db: &DatabaseConnection,
@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Longest title kept in capture file names
+const MAX_TITLE_LEN: usize = 100;
+
+/// Stateless service for saving screenshots and clips of what is playing
+pub struct CaptureService;
+
+impl CaptureService {
+    /// Free path in `directory` for a capture of `title` at `position`, creating the directory
+    pub async fn capture_path(
+        directory: &Path,
+        title: &str,
+        position: Duration,
+        extension: &str,
+    ) -> Result<PathBuf> {
+        tokio::fs::create_dir_all(directory)
+            .await
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+
+        let stem = Self::file_stem(title, position);
+        let mut path = directory.join(format!("{}.{}", stem, extension));
+        let mut copy = 2;
+        while tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            path = directory.join(format!("{} ({}).{}", stem, copy, extension));
+            copy += 1;
+        }
+        Ok(path)
+    }
+
+    /// Copy the part of a stream between `start` and `end` to `path` without re-encoding.
+    /// Needs ffmpeg; the clip starts at the keyframe at or before `start`.
+    pub async fn export_clip(
+        stream_url: &str,
+        start: Duration,
+        end: Duration,
+        path: &Path,
+    ) -> Result<()> {
+        if end <= start {
+            return Err(anyhow::anyhow!("The clip ends before it starts"));
+        }
+
+        let input = match url::Url::parse(stream_url) {
+            Ok(url) if url.scheme() == "file" => url
+                .to_file_path()
+                .map_err(|_| anyhow::anyhow!("Invalid file URL: {}", stream_url))?
+                .into_os_string(),
+            _ => stream_url.into(),
+        };
+
+        info!(
+            "Exporting clip {:?}..{:?} to {}",
+            start,
+            end,
+            path.display()
+        );
+        let output = tokio::process::Command::new("ffmpeg")
+            .args(["-nostdin", "-hide_banner", "-loglevel", "error", "-y"])
+            .arg("-ss")
+            .arg(format!("{:.3}", start.as_secs_f64()))
+            .arg("-i")
+            .arg(input)
+            .arg("-t")
+            .arg(format!("{:.3}", (end - start).as_secs_f64()))
+            // Keep every stream, subtitles included, as they are
+            .args(["-map", "0", "-c", "copy"])
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => {
+                    anyhow::anyhow!("Exporting clips needs ffmpeg, which is not installed")
+                }
+                _ => anyhow::anyhow!("Failed to run ffmpeg: {}", e),
+            })?;
+
+        if !output.status.success() {
+            // Don't leave a partial clip behind
+            let _ = tokio::fs::remove_file(path).await;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            debug!("ffmpeg failed: {}", stderr);
+            return Err(anyhow::anyhow!(
+                "ffmpeg failed: {}",
+                stderr.lines().last().unwrap_or("unknown error")
+            ));
+        }
+        Ok(())
+    }
+
+    /// "Title 01-02-03" with characters file systems reject replaced
+    fn file_stem(title: &str, position: Duration) -> String {
+        let mut name: String = title
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => ' ',
+                c => c,
+            })
+            .take(MAX_TITLE_LEN)
+            .collect();
+        // Hidden files and trailing dots or spaces upset some file managers
+        name = name
+            .trim_matches(|c: char| c == '.' || c.is_whitespace())
+            .to_string();
+        if name.is_empty() {
+            name = "Reel".to_string();
+        }
+
+        let seconds = position.as_secs();
+        format!(
+            "{} {:02}-{:02}-{:02}",
+            name,
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_stem() {
+        assert_eq!(
+            CaptureService::file_stem("The Office - S02E01", Duration::from_secs(3725)),
+            "The Office - S02E01 01-02-05"
+        );
+        assert_eq!(
+            CaptureService::file_stem("AC/DC: Live?", Duration::from_millis(59_900)),
+            "AC_DC_ Live_ 00-00-59"
+        );
+        assert_eq!(
+            CaptureService::file_stem("...", Duration::ZERO),
+            "Reel 00-00-00"
+        );
+    }
+}
@@ -295,6 +295,9 @@ impl MediaService {
                             view_count: movie.view_count as i32,
                             last_watched_at: movie.last_watched_at.map(|dt| dt.naive_utc()),
                             updated_at: chrono::Utc::now().naive_utc(),
+                            subtitle_delay_ms: 0,
+                            audio_delay_ms: 0,
+                            video_adjustments: None,
                         };
                         playback_repo.insert(progress).await?;
                     }
@@ -332,6 +335,9 @@ impl MediaService {
                             view_count: episode.view_count as i32,
                             last_watched_at: episode.last_watched_at.map(|dt| dt.naive_utc()),
                             updated_at: chrono::Utc::now().naive_utc(),
+                            subtitle_delay_ms: 0,
+                            audio_delay_ms: 0,
+                            video_adjustments: None,
                         };
                         playback_repo.insert(progress).await?;
                     }
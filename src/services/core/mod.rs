@@ -2,18 +2,22 @@
 /// These are pure functions that operate on data without maintaining state
 pub mod auth;
 pub mod backend;
+pub mod capture;
 pub mod connection;
 pub mod connection_cache;
 pub mod media;
 pub mod playback;
 pub mod playlist;
 pub mod sync;
+pub mod trickplay;
 
 pub use auth::AuthService;
 pub use backend::BackendService;
+pub use capture::CaptureService;
 pub use connection::ConnectionService;
 pub use connection_cache::{ConnectionCache, ConnectionState, ConnectionType};
 pub use media::MediaService;
 pub use playback::PlaybackService;
 pub use playlist::PlaylistService;
 pub use sync::{SyncProgress, SyncResult, SyncService, SyncStatus};
+pub use trickplay::TrickplayService;
@@ -1,11 +1,15 @@
 use anyhow::{Context, Result};
+use std::collections::HashMap;
 use std::time::Duration;
 use tracing::{debug, info};
 
 use crate::db::connection::DatabaseConnection;
 use crate::db::entities::PlaybackProgressModel;
-use crate::db::repository::{PlaybackRepository, PlaybackRepositoryImpl, Repository};
-use crate::models::MediaItemId;
+use crate::db::repository::{
+    PlaybackRepository, PlaybackRepositoryImpl, Repository, ShowPreferencesRepository,
+    ShowPreferencesRepositoryImpl,
+};
+use crate::models::{ChapterType, MarkerSkipMode, MediaItemId, ShowId, VideoAdjustments};
 
 /// Pure functions for playback operations
 pub struct PlaybackService;
@@ -52,6 +56,9 @@ impl PlaybackService {
             view_count: if is_watched { 1 } else { 0 },
             last_watched_at: Some(chrono::Utc::now().naive_utc()),
             updated_at: chrono::Utc::now().naive_utc(),
+            subtitle_delay_ms: 0,
+            audio_delay_ms: 0,
+            video_adjustments: None,
         };
 
         if let Some(mut existing) = repo
@@ -77,6 +84,128 @@ impl PlaybackService {
         Ok(())
     }
 
+    /// Get the subtitle and audio delays saved for a media item, in milliseconds
+    pub async fn get_sync_offsets(
+        db: &DatabaseConnection,
+        item_id: &MediaItemId,
+    ) -> Result<(i64, i64)> {
+        let repo = PlaybackRepositoryImpl::new(db.clone());
+        // Progress is saved without a user in the single-user setup
+        let progress = repo
+            .find_by_media_id(&item_id.to_string())
+            .await
+            .context("Failed to get playback offsets")?;
+        Ok(progress.map_or((0, 0), |p| (p.subtitle_delay_ms, p.audio_delay_ms)))
+    }
+
+    /// Save the subtitle and audio delays for a media item so they are re-applied next time
+    pub async fn save_sync_offsets(
+        db: &DatabaseConnection,
+        item_id: &MediaItemId,
+        subtitle_delay_ms: i64,
+        audio_delay_ms: i64,
+    ) -> Result<()> {
+        let repo = PlaybackRepositoryImpl::new(db.clone());
+        repo.upsert_sync_offsets(
+            &item_id.to_string(),
+            None,
+            subtitle_delay_ms,
+            audio_delay_ms,
+        )
+        .await?;
+        debug!(
+            "Saved sync offsets for item {}: subtitles {}ms, audio {}ms",
+            item_id, subtitle_delay_ms, audio_delay_ms
+        );
+        Ok(())
+    }
+
+    /// Get the picture settings saved for a media item
+    pub async fn get_video_adjustments(
+        db: &DatabaseConnection,
+        item_id: &MediaItemId,
+    ) -> Result<VideoAdjustments> {
+        let repo = PlaybackRepositoryImpl::new(db.clone());
+        let progress = repo
+            .find_by_media_id(&item_id.to_string())
+            .await
+            .context("Failed to get video adjustments")?;
+        Ok(progress
+            .and_then(|p| p.video_adjustments)
+            .and_then(|json| serde_json::from_value(json).ok())
+            .unwrap_or_default())
+    }
+
+    /// Save the picture settings for a media item so they are re-applied next time
+    pub async fn save_video_adjustments(
+        db: &DatabaseConnection,
+        item_id: &MediaItemId,
+        adjustments: &VideoAdjustments,
+    ) -> Result<()> {
+        let repo = PlaybackRepositoryImpl::new(db.clone());
+        // Nothing is stored for untouched items
+        let json = if adjustments.is_default() {
+            None
+        } else {
+            Some(serde_json::to_value(adjustments)?)
+        };
+        repo.upsert_video_adjustments(&item_id.to_string(), None, json)
+            .await?;
+        debug!(
+            "Saved video adjustments for item {}: {:?}",
+            item_id, adjustments
+        );
+        Ok(())
+    }
+
+    /// Get the marker skip modes a show overrides, keyed by marker type
+    pub async fn get_show_skip_modes(
+        db: &DatabaseConnection,
+        show_id: &ShowId,
+    ) -> Result<HashMap<ChapterType, MarkerSkipMode>> {
+        let repo = ShowPreferencesRepositoryImpl::new(db.clone());
+        let preferences = repo
+            .find_by_id(show_id.as_str())
+            .await
+            .context("Failed to get show preferences")?;
+
+        let Some(preferences) = preferences else {
+            return Ok(HashMap::new());
+        };
+        Ok([
+            ChapterType::Intro,
+            ChapterType::Credits,
+            ChapterType::Recap,
+            ChapterType::Preview,
+        ]
+        .into_iter()
+        .filter_map(|marker_type| {
+            preferences
+                .skip_mode(marker_type)
+                .map(|mode| (marker_type, mode))
+        })
+        .collect())
+    }
+
+    /// Remember how a marker type should be skipped for every episode of a show
+    pub async fn save_show_skip_mode(
+        db: &DatabaseConnection,
+        show_id: &ShowId,
+        marker_type: ChapterType,
+        mode: MarkerSkipMode,
+    ) -> Result<()> {
+        let repo = ShowPreferencesRepositoryImpl::new(db.clone());
+        repo.set_skip_mode(show_id.as_str(), marker_type, mode)
+            .await?;
+        debug!(
+            "Saved {} skip mode for show {}: {}",
+            marker_type.as_str(),
+            show_id,
+            mode.as_str()
+        );
+        Ok(())
+    }
+
     /// Mark item as watched
     pub async fn mark_watched(
         db: &DatabaseConnection,
@@ -1,6 +1,11 @@
 use crate::db::connection::DatabaseConnection;
-use crate::db::repository::{MediaRepository, MediaRepositoryImpl, Repository};
-use crate::models::{EpisodeInfo, MediaItemId, PlaylistContext, ShowId};
+use crate::db::entities::{MediaItemModel, PlayQueueItemModel, PlayQueueModel};
+use crate::db::repository::{
+    MediaRepository, MediaRepositoryImpl, PlayQueueRepository, PlayQueueRepositoryImpl, Repository,
+};
+use crate::models::{
+    EpisodeInfo, MediaItemId, PlayQueue, PlaylistContext, QueueItem, RepeatMode, ShowId,
+};
 use anyhow::Result;
 use std::sync::Arc;
 use tracing::{debug, error, info};
@@ -92,7 +97,7 @@ impl PlaylistService {
     ) -> Result<Option<MediaItemId>> {
         match context {
             PlaylistContext::SingleItem => Ok(None),
-            PlaylistContext::TvShow { .. } => {
+            PlaylistContext::TvShow { .. } | PlaylistContext::Queue(_) => {
                 // The context already knows the next item
                 Ok(context.get_next_item())
             }
@@ -107,13 +112,224 @@ impl PlaylistService {
     ) -> Result<Option<MediaItemId>> {
         match context {
             PlaylistContext::SingleItem => Ok(None),
-            PlaylistContext::TvShow { .. } => {
+            PlaylistContext::TvShow { .. } | PlaylistContext::Queue(_) => {
                 // The context already knows the previous item
                 Ok(context.get_previous_item())
             }
         }
     }
 
+    /// Load the play queue saved by the last session
+    pub async fn load_queue(db: &DatabaseConnection) -> Result<PlayQueue> {
+        let repo = PlayQueueRepositoryImpl::new(db.clone());
+        let (state, items) = repo.load_queue().await?;
+
+        let items: Vec<QueueItem> = items
+            .into_iter()
+            .map(|item| QueueItem {
+                id: MediaItemId::new(&item.media_item_id),
+                title: item.title,
+                subtitle: item.subtitle,
+                media_type: item.media_type,
+                duration_ms: item.duration_ms,
+                original_position: item.original_position.map(|p| p as usize),
+            })
+            .collect();
+
+        let mut queue = PlayQueue {
+            items,
+            ..Default::default()
+        };
+        if let Some(state) = state {
+            queue.current_index = state
+                .current_index
+                .map(|index| index as usize)
+                .filter(|&index| index < queue.items.len());
+            queue.shuffle = state.shuffle;
+            queue.repeat = RepeatMode::parse(&state.repeat_mode).unwrap_or_default();
+        }
+
+        debug!("Loaded play queue with {} items", queue.items.len());
+        Ok(queue)
+    }
+
+    /// Persist the play queue so it survives restarts
+    pub async fn save_queue(db: &DatabaseConnection, queue: &PlayQueue) -> Result<()> {
+        let repo = PlayQueueRepositoryImpl::new(db.clone());
+
+        let state = PlayQueueModel {
+            id: 0, // The repository always writes the single state row
+            current_index: queue.current_index.map(|index| index as i32),
+            shuffle: queue.shuffle,
+            repeat_mode: queue.repeat.as_str().to_string(),
+            updated_at: chrono::Utc::now().naive_utc(),
+        };
+        let items = queue
+            .items
+            .iter()
+            .enumerate()
+            .map(|(position, item)| PlayQueueItemModel {
+                position: position as i32,
+                media_item_id: item.id.to_string(),
+                title: item.title.clone(),
+                subtitle: item.subtitle.clone(),
+                media_type: item.media_type.clone(),
+                duration_ms: item.duration_ms,
+                original_position: item.original_position.map(|p| p as i32),
+            })
+            .collect();
+
+        repo.replace_queue(state, items).await
+    }
+
+    /// Build a queue entry for a media item from the database
+    pub async fn build_queue_item(
+        db: &DatabaseConnection,
+        media_id: &MediaItemId,
+    ) -> Result<QueueItem> {
+        let repo = MediaRepositoryImpl::new(db.clone());
+        let item = repo
+            .find_by_id(media_id.as_ref())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Media item not found: {}", media_id))?;
+
+        Ok(Self::queue_item_from_model(item))
+    }
+
+    fn queue_item_from_model(item: MediaItemModel) -> QueueItem {
+        QueueItem {
+            id: MediaItemId::new(&item.id),
+            subtitle: Self::queue_item_subtitle(&item),
+            title: item.title,
+            media_type: item.media_type,
+            duration_ms: item.duration_ms,
+            original_position: None,
+        }
+    }
+
+    fn metadata_str(item: &MediaItemModel, key: &str) -> Option<String> {
+        item.metadata
+            .as_ref()
+            .and_then(|m| m.get(key))
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    }
+
+    /// Secondary line shown for an item in the queue
+    fn queue_item_subtitle(item: &MediaItemModel) -> Option<String> {
+        let metadata_str = |key: &str| Self::metadata_str(item, key);
+
+        match item.media_type.as_str() {
+            "episode" => {
+                let number = format!(
+                    "S{}E{}",
+                    item.season_number.unwrap_or(0),
+                    item.episode_number.unwrap_or(0)
+                );
+                Some(match metadata_str("show_title") {
+                    Some(show) => format!("{} · {}", show, number),
+                    None => number,
+                })
+            }
+            "track" => metadata_str("artist"),
+            _ => item.year.map(|year| year.to_string()),
+        }
+    }
+
+    /// Add a media item to the saved queue, either right after the current item or at the end
+    pub async fn queue_media(
+        db: &DatabaseConnection,
+        media_id: &MediaItemId,
+        play_next: bool,
+    ) -> Result<PlayQueue> {
+        let item = Self::build_queue_item(db, media_id).await?;
+        let mut queue = Self::load_queue(db).await?;
+
+        if play_next {
+            queue.play_next(item);
+        } else {
+            queue.enqueue(item);
+        }
+        Self::save_queue(db, &queue).await?;
+
+        info!(
+            "Queued {} ({}), queue now has {} items",
+            media_id,
+            if play_next { "next" } else { "last" },
+            queue.items.len()
+        );
+        Ok(queue)
+    }
+
+    /// Build a music queue from an album, or from the album of a track starting at that
+    /// track. Also returns the album cover.
+    pub async fn build_music_queue(
+        db: &DatabaseConnection,
+        media_id: &MediaItemId,
+    ) -> Result<(PlayQueue, Option<String>)> {
+        let repo = MediaRepositoryImpl::new(db.clone());
+        let item = repo
+            .find_by_id(media_id.as_ref())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Media item not found: {}", media_id))?;
+
+        // Tracks only know their album by title
+        let album = match item.media_type.as_str() {
+            "album" => Some(item.title.clone()),
+            "track" => Self::metadata_str(&item, "album"),
+            other => return Err(anyhow::anyhow!("Cannot play {} as music", other)),
+        };
+
+        let mut tracks: Vec<MediaItemModel> = match &album {
+            Some(album) => repo
+                .find_by_library_and_type(&item.library_id, "track")
+                .await?
+                .into_iter()
+                .filter(|track| Self::metadata_str(track, "album").as_ref() == Some(album))
+                .collect(),
+            None => Vec::new(),
+        };
+        tracks.sort_by_key(|track| {
+            let number = track
+                .metadata
+                .as_ref()
+                .and_then(|m| m.get("track_number"))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(u64::MAX);
+            (number, track.title.clone())
+        });
+        if tracks.is_empty() && item.media_type == "track" {
+            tracks.push(item.clone());
+        }
+        if tracks.is_empty() {
+            return Err(anyhow::anyhow!("No tracks found for {}", item.title));
+        }
+
+        let current = tracks
+            .iter()
+            .position(|track| track.id == item.id)
+            .unwrap_or(0);
+        let cover_url = item
+            .poster_url
+            .clone()
+            .or_else(|| tracks[current].poster_url.clone());
+        let queue = PlayQueue {
+            items: tracks
+                .into_iter()
+                .map(Self::queue_item_from_model)
+                .collect(),
+            current_index: Some(current),
+            ..Default::default()
+        };
+
+        info!(
+            "Built music queue for {} with {} tracks",
+            album.as_deref().unwrap_or(&item.title),
+            queue.items.len()
+        );
+        Ok((queue, cover_url))
+    }
+
     /// Get next unwatched episode for continue watching
     pub async fn get_next_unwatched_episode(
         db: &DatabaseConnection,
@@ -199,17 +199,16 @@ impl SyncService {
                 shows.into_iter().map(MediaItem::Show).collect()
             }
             crate::models::LibraryType::Music => {
-                // For music, we might need both albums and tracks
+                // Albums and tracks each come in one listing, not a request per album
+                let library_id = crate::models::LibraryId::new(library.id.clone());
                 let mut music_items = Vec::new();
-                if let Ok(albums) = backend
-                    .get_music_albums(&crate::models::LibraryId::new(library.id.clone()))
-                    .await
-                {
+                if let Ok(albums) = backend.get_music_albums(&library_id).await {
                     music_items.extend(albums.into_iter().map(MediaItem::MusicAlbum));
                 }
-                // TODO: get_music_tracks takes album_id, not library_id
-                // We need to iterate through albums first, then get tracks for each
-                // For now, skip tracks
+                match backend.get_library_tracks(&library_id).await {
+                    Ok(tracks) => music_items.extend(tracks.into_iter().map(MediaItem::MusicTrack)),
+                    Err(e) => warn!("Failed to get tracks for {}: {}", library.title, e),
+                }
                 music_items
             }
             crate::models::LibraryType::Photos | crate::models::LibraryType::Mixed => {
@@ -589,3 +588,194 @@ pub struct SyncProgress {
     pub total_items: usize,
     pub percentage: u32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backends::traits::{SearchResults, WatchStatus};
+    use crate::db::connection::Database;
+    use crate::db::entities::SourceModel;
+    use crate::db::repository::{
+        LibraryRepositoryImpl, MediaRepository, MediaRepositoryImpl, SourceRepositoryImpl,
+    };
+    use crate::models::{
+        BackendId, Credentials, Episode, LibraryId, LibraryType, MediaItemId, Movie, MusicAlbum,
+        MusicTrack, Season, Show, ShowId, StreamInfo, User,
+    };
+    use crate::services::core::playlist::PlaylistService;
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use std::time::Duration;
+
+    /// A server with one album of music, answering nothing else
+    #[derive(Debug)]
+    struct MusicBackend;
+
+    fn track(id: &str, title: &str, number: u32) -> MusicTrack {
+        MusicTrack {
+            id: id.to_string(),
+            title: title.to_string(),
+            artist: "Band".to_string(),
+            album: "Debut".to_string(),
+            track_number: Some(number),
+            duration: Duration::from_secs(180),
+            cover_url: None,
+        }
+    }
+
+    #[async_trait]
+    impl MediaBackend for MusicBackend {
+        async fn initialize(&self) -> Result<Option<User>> {
+            Ok(None)
+        }
+        async fn is_initialized(&self) -> bool {
+            true
+        }
+        fn as_any(&self) -> &dyn std::any::Any {
+            self
+        }
+        async fn authenticate(&self, _credentials: Credentials) -> Result<User> {
+            unimplemented!()
+        }
+        async fn get_libraries(&self) -> Result<Vec<Library>> {
+            Ok(Vec::new())
+        }
+        async fn get_movies(&self, _library_id: &LibraryId) -> Result<Vec<Movie>> {
+            Ok(Vec::new())
+        }
+        async fn get_shows(&self, _library_id: &LibraryId) -> Result<Vec<Show>> {
+            Ok(Vec::new())
+        }
+        async fn get_seasons(&self, _show_id: &ShowId) -> Result<Vec<Season>> {
+            Ok(Vec::new())
+        }
+        async fn get_episodes(&self, _show_id: &ShowId, _season: u32) -> Result<Vec<Episode>> {
+            Ok(Vec::new())
+        }
+        async fn get_music_albums(&self, _library_id: &LibraryId) -> Result<Vec<MusicAlbum>> {
+            Ok(vec![MusicAlbum {
+                id: "album".to_string(),
+                title: "Debut".to_string(),
+                artist: "Band".to_string(),
+                year: Some(2001),
+                track_count: 2,
+                duration: Duration::from_secs(360),
+                cover_url: None,
+                genres: Vec::new(),
+            }])
+        }
+        async fn get_library_tracks(&self, _library_id: &LibraryId) -> Result<Vec<MusicTrack>> {
+            // Listed out of order, as a server sorting by title would
+            Ok(vec![
+                track("second", "B Side", 2),
+                track("first", "Opener", 1),
+            ])
+        }
+        async fn get_stream_url(&self, _media_id: &MediaItemId) -> Result<StreamInfo> {
+            unimplemented!()
+        }
+        async fn update_progress(
+            &self,
+            _media_id: &MediaItemId,
+            _position: Duration,
+            _duration: Duration,
+        ) -> Result<()> {
+            unimplemented!()
+        }
+        async fn mark_watched(&self, _media_id: &MediaItemId) -> Result<()> {
+            unimplemented!()
+        }
+        async fn mark_unwatched(&self, _media_id: &MediaItemId) -> Result<()> {
+            unimplemented!()
+        }
+        async fn get_watch_status(&self, _media_id: &MediaItemId) -> Result<WatchStatus> {
+            unimplemented!()
+        }
+        async fn search(&self, _query: &str) -> Result<SearchResults> {
+            unimplemented!()
+        }
+        async fn get_backend_id(&self) -> BackendId {
+            BackendId::new("music")
+        }
+        async fn get_last_sync_time(&self) -> Option<DateTime<Utc>> {
+            None
+        }
+        async fn supports_offline(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_music_library_saves_tracks() {
+        let db = Database::in_memory().await.unwrap().get_connection();
+        let now = Utc::now().naive_utc();
+        SourceRepositoryImpl::new(db.clone())
+            .insert(SourceModel {
+                id: "source".to_string(),
+                name: "Server".to_string(),
+                source_type: "plex".to_string(),
+                auth_provider_id: None,
+                connection_url: None,
+                connections: None,
+                machine_id: None,
+                is_owned: true,
+                is_online: true,
+                last_sync: None,
+                last_connection_test: None,
+                connection_failure_count: 0,
+                connection_quality: None,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+        LibraryRepositoryImpl::new(db.clone())
+            .insert(LibraryModel {
+                id: "music".to_string(),
+                source_id: "source".to_string(),
+                title: "Music".to_string(),
+                library_type: "music".to_string(),
+                icon: None,
+                item_count: 0,
+                created_at: now,
+                updated_at: now,
+            })
+            .await
+            .unwrap();
+        let library = Library {
+            id: "music".to_string(),
+            title: "Music".to_string(),
+            library_type: LibraryType::Music,
+            icon: None,
+            item_count: 0,
+        };
+
+        let synced =
+            SyncService::sync_library(&db, &MusicBackend, &SourceId::new("source"), &library)
+                .await
+                .unwrap();
+        assert_eq!(synced, 3);
+
+        let repo = MediaRepositoryImpl::new(db.clone());
+        let mut tracks = repo
+            .find_by_library_and_type("music", "track")
+            .await
+            .unwrap();
+        tracks.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(tracks.len(), 2);
+        let metadata = tracks[0].metadata.as_ref().unwrap();
+        assert_eq!(tracks[0].id, "first");
+        assert_eq!(metadata["album"], "Debut");
+        assert_eq!(metadata["artist"], "Band");
+        assert_eq!(metadata["track_number"], 1);
+        assert_eq!(tracks[0].duration_ms, Some(180_000));
+        assert_eq!(tracks[1].metadata.as_ref().unwrap()["track_number"], 2);
+
+        // The album plays its tracks in order
+        let (queue, _) = PlaylistService::build_music_queue(&db, &MediaItemId::new("album"))
+            .await
+            .unwrap();
+        let ids: Vec<&str> = queue.items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["first", "second"]);
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::db::connection::DatabaseConnection;
+use crate::models::{MediaItemId, Trickplay};
+use crate::services::core::BackendService;
+
+/// Spacing of thumbnails generated for local files
+const LOCAL_THUMBNAIL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Stateless service for seek-bar preview thumbnails
+/// Every source is normalized to BIF and cached on disk next to the image cache
+pub struct TrickplayService;
+
+impl TrickplayService {
+    /// Get preview thumbnails for a media item, from the disk cache when possible
+    pub async fn get_trickplay(
+        db: &DatabaseConnection,
+        media_item_id: &MediaItemId,
+        stream_url: &str,
+    ) -> Result<Option<Trickplay>> {
+        let cache_path = Self::cache_path(media_item_id);
+
+        if let Ok(data) = tokio::fs::read(&cache_path).await {
+            match Trickplay::from_bif(&data) {
+                Ok(trickplay) => {
+                    debug!("Loaded preview thumbnails from {}", cache_path.display());
+                    return Ok(Some(trickplay));
+                }
+                Err(e) => warn!(
+                    "Discarding corrupt preview cache {}: {}",
+                    cache_path.display(),
+                    e
+                ),
+            }
+        }
+
+        let trickplay = if stream_url.starts_with("file://") {
+            let uri = stream_url.to_string();
+            Some(
+                tokio::task::spawn_blocking(move || {
+                    crate::player::trickplay::generate_trickplay(&uri, LOCAL_THUMBNAIL_INTERVAL)
+                })
+                .await??,
+            )
+        } else {
+            BackendService::get_trickplay(db, media_item_id).await?
+        };
+
+        let Some(trickplay) = trickplay.filter(|t| !t.is_empty()) else {
+            return Ok(None);
+        };
+
+        if let Some(parent) = cache_path.parent()
+            && let Err(e) = tokio::fs::create_dir_all(parent).await
+        {
+            warn!("Failed to create preview cache directory: {}", e);
+        } else if let Err(e) = tokio::fs::write(&cache_path, trickplay.to_bif()).await {
+            warn!("Failed to cache preview thumbnails: {}", e);
+        }
+
+        Ok(Some(trickplay))
+    }
+
+    fn cache_path(media_item_id: &MediaItemId) -> PathBuf {
+        let id_hash = format!("{:x}", md5::compute(media_item_id.as_str()));
+        dirs::cache_dir()
+            .unwrap_or_else(|| PathBuf::from("/tmp"))
+            .join("reel")
+            .join("trickplay")
+            .join(format!("{}.bif", id_hash))
+    }
+}